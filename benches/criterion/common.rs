@@ -0,0 +1,391 @@
+//! Criterion benchmark groups for keygen, prove (the `Randomize` step of the client), and verify
+//! (the `Randomize` step of the server) of the Base, Expand, and Shuffle schemes, shared by the
+//! criterion benchmark targets (which only differ in their parameters).
+
+use ark_bls12_381::Bls12_381;
+use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+use ark_groth16::Groth16;
+use astro_float::BigFloat;
+use criterion::{black_box, Criterion};
+use num_bigint::BigUint;
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use vldp::circuits::{CircuitBase, CircuitExpand, CircuitShuffle};
+use vldp::client::{ClientBase, ClientExpand, ClientShuffle};
+use vldp::config::{BasicConfig, BasicConfigGadget};
+use vldp::messages::shuffle::PrfEvalPointSet;
+use vldp::messages::{derive_prf_eval_points, InputValueSignatureInput};
+use vldp::prelude::*;
+use vldp::server::{ServerBase, ServerExpand, ServerShuffle};
+
+pub type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+pub type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+
+const GAMMA: f64 = 0.5;
+
+/// Time bounds of the current step, time of the input value, and its signature.
+type SignedInput<const TIME_BYTES: usize> = (
+    ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+    [u8; TIME_BYTES],
+    ClientSignatureSchemeSignature<Conf>,
+);
+
+/// Input value (1) at time 1 and its signature by the emulated trusted environment, along with the
+/// time bounds `[0, 1]` of the current step.
+fn signed_input<const INPUT_BYTES: usize, const TIME_BYTES: usize>(
+    client_signature_scheme: &ClientSignatureSchemeParameters<Conf>,
+    client_sig_sk: &ClientSignatureSchemeSecretKey<Conf>,
+    rng: &mut ChaChaRng,
+) -> SignedInput<TIME_BYTES> {
+    let mut input_value = [0; INPUT_BYTES];
+    input_value[0] = 1;
+    let mut input_value_time = [0; TIME_BYTES];
+    input_value_time[0] = 1;
+    let signature = <Conf as Config>::ClientSignatureScheme::sign(
+        client_signature_scheme,
+        client_sig_sk,
+        &InputValueSignatureInput {
+            input_value,
+            input_value_time,
+        }
+        .to_bytes(),
+        rng,
+    )
+    .unwrap();
+    (
+        ([0; TIME_BYTES], input_value_time),
+        input_value_time,
+        signature,
+    )
+}
+
+/// Benchmark keygen, prove, and verify of the Base scheme in the benchmark group `name`.
+pub fn bench_base<
+    const INPUT_BYTES: usize,
+    const TIME_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+    const K: u64,
+>(
+    c: &mut Criterion,
+    name: &str,
+) {
+    let rng = &mut ChaChaRng::from_seed([0; 32]);
+    let zkp_rng = &mut ChaChaRng::from_seed([1; 32]);
+    let parameters =
+        ParametersBase::<Conf, GAMMA_BYTES>::setup(BigFloat::from_f64(GAMMA, GAMMA_BYTES * 8), rng)
+            .unwrap();
+    let keygen = |zkp_rng: &mut ChaChaRng| {
+        CircuitBase::<
+            Conf,
+            ConfG,
+            INPUT_BYTES,
+            TIME_BYTES,
+            GAMMA_BYTES,
+            RANDOMNESS_BYTES,
+            K,
+            false,
+        >::keygen(parameters.clone(), zkp_rng)
+        .unwrap()
+    };
+    let mut group = c.benchmark_group(name);
+    group.bench_function("keygen", |b| b.iter(|| black_box(keygen(zkp_rng))));
+
+    let (proving_key, verifying_key) = keygen(zkp_rng);
+    let server =
+        ServerBase::<Conf, INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, false>::new(
+            parameters.clone(),
+            verifying_key,
+            rng,
+        )
+        .unwrap();
+    let (client_sig_pk, client_sig_sk) =
+        <Conf as Config>::ClientSignatureScheme::keygen(&parameters.client_signature_scheme, rng)
+            .unwrap();
+    let mut client =
+        ClientBase::<Conf, INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, false>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+    let client_message = client
+        .generate_randomness_create([0; TIME_BYTES], rng)
+        .unwrap();
+    let server_message = server
+        .generate_randomness_create(&client_message, rng)
+        .unwrap();
+    client.generate_randomness_verify(&server_message).unwrap();
+    let (time_bounds, input_value_time, input_value_signature) =
+        signed_input::<INPUT_BYTES, TIME_BYTES>(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            rng,
+        );
+
+    let prove = |zkp_rng: &mut ChaChaRng| {
+        client
+            .verifiable_randomization_create::<ConfG>(
+                time_bounds,
+                input_value_time,
+                [BigUint::from(1_u8)],
+                input_value_signature.clone(),
+                zkp_rng,
+                false,
+            )
+            .unwrap()
+    };
+    group.bench_function("prove", |b| b.iter(|| black_box(prove(zkp_rng))));
+
+    let message = prove(zkp_rng);
+    group.bench_function("verify", |b| {
+        b.iter(|| {
+            assert!(
+                server
+                    .verifiable_randomization_verify::<ConfG>(&message, time_bounds, zkp_rng, false)
+                    .unwrap()
+                    .0
+            )
+        })
+    });
+    group.finish();
+}
+
+/// Benchmark keygen, prove, and verify of the Expand scheme in the benchmark group `name`.
+pub fn bench_expand<
+    const MT_DEPTH: usize,
+    const INPUT_BYTES: usize,
+    const TIME_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+    const K: u64,
+>(
+    c: &mut Criterion,
+    name: &str,
+) {
+    let rng = &mut ChaChaRng::from_seed([0; 32]);
+    let zkp_rng = &mut ChaChaRng::from_seed([1; 32]);
+    let parameters = ParametersExpand::<Conf, GAMMA_BYTES>::setup(
+        BigFloat::from_f64(GAMMA, GAMMA_BYTES * 8),
+        rng,
+    )
+    .unwrap();
+    let keygen = |zkp_rng: &mut ChaChaRng| {
+        CircuitExpand::<
+            Conf,
+            ConfG,
+            MT_DEPTH,
+            INPUT_BYTES,
+            TIME_BYTES,
+            GAMMA_BYTES,
+            RANDOMNESS_BYTES,
+            K,
+            false,
+        >::keygen(parameters.clone(), zkp_rng)
+        .unwrap()
+    };
+    let mut group = c.benchmark_group(name);
+    group.bench_function("keygen", |b| b.iter(|| black_box(keygen(zkp_rng))));
+
+    let (proving_key, verifying_key) = keygen(zkp_rng);
+    let server = ServerExpand::<
+        Conf,
+        MT_DEPTH,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        false,
+    >::new(parameters.clone(), verifying_key, rng)
+    .unwrap();
+    let (client_sig_pk, client_sig_sk) =
+        <Conf as Config>::ClientSignatureScheme::keygen(&parameters.client_signature_scheme, rng)
+            .unwrap();
+    let mut client = ClientExpand::<
+        Conf,
+        MT_DEPTH,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        false,
+    >::new(
+        parameters.clone(),
+        server.get_signature_public_key(),
+        client_sig_pk,
+        proving_key,
+    )
+    .unwrap();
+    let client_message = client.generate_randomness_create(rng).unwrap();
+    let server_message = server
+        .generate_randomness_create(&client_message, rng)
+        .unwrap();
+    client.generate_randomness_verify(&server_message).unwrap();
+    let (time_bounds, input_value_time, input_value_signature) =
+        signed_input::<INPUT_BYTES, TIME_BYTES>(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            rng,
+        );
+    let prf_eval_points = derive_prf_eval_points::<Conf>(
+        rng.gen(),
+        ((RANDOMNESS_BYTES - 1) / Conf::PRF_OUTPUT_BYTES) + 1,
+    )
+    .unwrap();
+
+    let mut prove = |zkp_rng: &mut ChaChaRng| {
+        client
+            .verifiable_randomization_create::<ConfG>(
+                time_bounds,
+                input_value_time,
+                BigUint::from(1_u8),
+                input_value_signature.clone(),
+                &prf_eval_points,
+                0,
+                zkp_rng,
+                false,
+            )
+            .unwrap()
+    };
+    group.bench_function("prove", |b| b.iter(|| black_box(prove(zkp_rng))));
+
+    let message = prove(zkp_rng);
+    group.bench_function("verify", |b| {
+        b.iter(|| {
+            assert!(
+                server
+                    .verifiable_randomization_verify::<ConfG>(
+                        &message,
+                        time_bounds,
+                        &prf_eval_points,
+                        0,
+                        zkp_rng,
+                        false,
+                    )
+                    .unwrap()
+                    .0
+            )
+        })
+    });
+    group.finish();
+}
+
+/// Benchmark keygen, prove, and verify of the Shuffle scheme in the benchmark group `name`.
+pub fn bench_shuffle<
+    const INPUT_BYTES: usize,
+    const TIME_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+    const K: u64,
+>(
+    c: &mut Criterion,
+    name: &str,
+) {
+    let rng = &mut ChaChaRng::from_seed([0; 32]);
+    let zkp_rng = &mut ChaChaRng::from_seed([1; 32]);
+    let parameters = ParametersShuffle::<Conf, GAMMA_BYTES>::setup(
+        BigFloat::from_f64(GAMMA, GAMMA_BYTES * 8),
+        rng,
+    )
+    .unwrap();
+    let keygen = |zkp_rng: &mut ChaChaRng| {
+        CircuitShuffle::<
+            Conf,
+            ConfG,
+            INPUT_BYTES,
+            TIME_BYTES,
+            GAMMA_BYTES,
+            RANDOMNESS_BYTES,
+            K,
+            false,
+        >::keygen(parameters.clone(), zkp_rng)
+        .unwrap()
+    };
+    let mut group = c.benchmark_group(name);
+    group.bench_function("keygen", |b| b.iter(|| black_box(keygen(zkp_rng))));
+
+    let (proving_key, verifying_key) = keygen(zkp_rng);
+    let server = ServerShuffle::<
+        Conf,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        false,
+    >::new(parameters.clone(), verifying_key, rng)
+    .unwrap();
+    let (client_sig_pk, client_sig_sk) =
+        <Conf as Config>::ClientSignatureScheme::keygen(&parameters.client_signature_scheme, rng)
+            .unwrap();
+    let mut client = ClientShuffle::<
+        Conf,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        false,
+    >::new(
+        parameters.clone(),
+        server.get_signature_public_key(),
+        client_sig_pk,
+        proving_key,
+    )
+    .unwrap();
+    let client_message = client.generate_randomness_create(rng).unwrap();
+    let server_message = server
+        .generate_randomness_create(&client_message, rng)
+        .unwrap();
+    client.generate_randomness_verify(&server_message).unwrap();
+    let (time_bounds, input_value_time, input_value_signature) =
+        signed_input::<INPUT_BYTES, TIME_BYTES>(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            rng,
+        );
+    let prf_eval_points = PrfEvalPointSet::<Conf>::derive(
+        0,
+        rng.gen(),
+        ((RANDOMNESS_BYTES - 1) / Conf::PRF_OUTPUT_BYTES) + 1,
+    )
+    .unwrap();
+
+    let prove = |zkp_rng: &mut ChaChaRng| {
+        client
+            .verifiable_randomization_create::<ConfG>(
+                time_bounds,
+                input_value_time,
+                BigUint::from(1_u8),
+                input_value_signature.clone(),
+                &prf_eval_points,
+                zkp_rng,
+                false,
+            )
+            .unwrap()
+    };
+    group.bench_function("prove", |b| b.iter(|| black_box(prove(zkp_rng))));
+
+    let message = prove(zkp_rng);
+    group.bench_function("verify", |b| {
+        b.iter(|| {
+            assert!(
+                server
+                    .verifiable_randomization_verify::<ConfG>(
+                        &message,
+                        time_bounds,
+                        &prf_eval_points,
+                        zkp_rng,
+                        false,
+                    )
+                    .unwrap()
+                    .0
+            )
+        })
+    });
+    group.finish();
+}
@@ -0,0 +1,33 @@
+//! Criterion benchmarks of keygen, prove, and verify for the Base, Expand, and Shuffle schemes at
+//! a small fixed parameter set. Criterion reports the mean and confidence interval of every step
+//! and compares them with the previous run (e.g., on another commit).
+//!
+//! Run with `cargo bench --features criterion --bench criterion_schemes`.
+
+mod common;
+
+use common::{bench_base, bench_expand, bench_shuffle};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+const MT_DEPTH: usize = 4;
+const INPUT_BYTES: usize = 2;
+const TIME_BYTES: usize = 1;
+const GAMMA_BYTES: usize = 2;
+const RANDOMNESS_BYTES: usize = 4;
+const K: u64 = 8;
+
+fn schemes(c: &mut Criterion) {
+    bench_base::<INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K>(c, "base");
+    bench_expand::<MT_DEPTH, INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K>(
+        c, "expand",
+    );
+    bench_shuffle::<INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K>(c, "shuffle");
+}
+
+// every iteration takes (at least) milliseconds, so the minimal sample size suffices
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = schemes
+}
+criterion_main!(benches);
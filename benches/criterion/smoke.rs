@@ -0,0 +1,37 @@
+//! Quick smoke variant of the criterion benchmarks (see `schemes.rs`) with tiny parameters and
+//! short measurement times, e.g., to check in CI that the benchmarks still run.
+//!
+//! Run with `cargo bench --features criterion --bench criterion_smoke`.
+
+mod common;
+
+use common::{bench_base, bench_expand, bench_shuffle};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::time::Duration;
+
+const MT_DEPTH: usize = 2;
+const INPUT_BYTES: usize = 1;
+const TIME_BYTES: usize = 1;
+const GAMMA_BYTES: usize = 1;
+const RANDOMNESS_BYTES: usize = 2;
+const K: u64 = 2;
+
+fn schemes(c: &mut Criterion) {
+    bench_base::<INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K>(c, "base_smoke");
+    bench_expand::<MT_DEPTH, INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K>(
+        c,
+        "expand_smoke",
+    );
+    bench_shuffle::<INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K>(c, "shuffle_smoke");
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .sample_size(10)
+        .warm_up_time(Duration::from_millis(100))
+        .measurement_time(Duration::from_secs(1))
+        .without_plots();
+    targets = schemes
+}
+criterion_main!(benches);
@@ -0,0 +1,168 @@
+//! Benchmark the cost of creating a client per submission in the Base scheme, comparing a clone of
+//! the proving key per client, a proving key shared among all clients, and a single client that is
+//! reset between submissions. Only creating (or resetting) the clients is measured; every client
+//! then submits once outside of the measurement, which checks that it still works.
+
+use astro_float::BigFloat;
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use vldp::circuits::CircuitBase;
+use vldp::prelude::*;
+use vldp::testing::{
+    submit_base, Conf, ConfG, SmallClientBase as Client, SmallServerBase as Server,
+};
+
+const N_CLIENTS: usize = 8;
+
+/// Allocator that keeps track of the peak number of allocated bytes.
+struct PeakAllocator {
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+unsafe impl GlobalAlloc for PeakAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let current = self.current.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        self.peak.fetch_max(current, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.current.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAllocator = PeakAllocator {
+    current: AtomicUsize::new(0),
+    peak: AtomicUsize::new(0),
+};
+
+/// Run `strategy` and return its result, its duration and the peak memory allocated on top of
+/// what was allocated before.
+fn measure<T>(strategy: impl FnOnce() -> T) -> (T, Duration, usize) {
+    let baseline = ALLOCATOR.current.load(Ordering::Relaxed);
+    ALLOCATOR.peak.store(baseline, Ordering::Relaxed);
+    let start = Instant::now();
+    let result = strategy();
+    let duration = start.elapsed();
+    (
+        result,
+        duration,
+        ALLOCATOR.peak.load(Ordering::Relaxed) - baseline,
+    )
+}
+
+fn main() {
+    let rng = &mut ChaChaRng::from_seed([0; 32]);
+    let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+    let (proving_key, verifying_key) =
+        CircuitBase::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(parameters.clone(), rng).unwrap();
+    let server = Server::new(parameters.clone(), verifying_key, rng).unwrap();
+    let server_sig_pk = server.get_signature_public_key();
+    let client_keys = (0..N_CLIENTS)
+        .map(|_| {
+            <Conf as Config>::ClientSignatureScheme::keygen(
+                &parameters.client_signature_scheme,
+                rng,
+            )
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    // clone the proving key per client, keeping all clients alive until the end
+    let (mut clients, cloned_time, cloned_memory) = measure(|| {
+        client_keys
+            .iter()
+            .map(|(client_sig_pk, _)| {
+                Client::new(
+                    parameters.clone(),
+                    server_sig_pk,
+                    *client_sig_pk,
+                    proving_key.clone(),
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>()
+    });
+    for (client, (_, client_sig_sk)) in clients.iter_mut().zip(client_keys.iter()) {
+        assert!(submit_base(
+            client,
+            client_sig_sk,
+            &server,
+            &parameters,
+            rng
+        ));
+    }
+    drop(clients);
+
+    // share the proving key among all clients, keeping all clients alive until the end
+    let shared_proving_key = Arc::new(proving_key);
+    let (mut clients, shared_time, shared_memory) = measure(|| {
+        client_keys
+            .iter()
+            .map(|(client_sig_pk, _)| {
+                Client::new_with_shared_proving_key(
+                    parameters.clone(),
+                    server_sig_pk,
+                    *client_sig_pk,
+                    shared_proving_key.clone(),
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>()
+    });
+    for (client, (_, client_sig_sk)) in clients.iter_mut().zip(client_keys.iter()) {
+        assert!(submit_base(
+            client,
+            client_sig_sk,
+            &server,
+            &parameters,
+            rng
+        ));
+    }
+    drop(clients);
+
+    // reuse a single client, measuring its creation and every reset
+    let (mut client, mut reset_time, mut reset_memory) = measure(|| {
+        Client::new_with_shared_proving_key(
+            parameters.clone(),
+            server_sig_pk,
+            client_keys[0].0,
+            shared_proving_key.clone(),
+        )
+        .unwrap()
+    });
+    for (client_sig_pk, client_sig_sk) in client_keys.iter() {
+        let ((), time, memory) = measure(|| client.reset(*client_sig_pk));
+        reset_time += time;
+        reset_memory = reset_memory.max(memory);
+        assert!(submit_base(
+            &mut client,
+            client_sig_sk,
+            &server,
+            &parameters,
+            rng
+        ));
+    }
+
+    println!("{} clients:", N_CLIENTS);
+    for (name, time, memory) in [
+        ("clone per client", cloned_time, cloned_memory),
+        ("shared key", shared_time, shared_memory),
+        ("single client with reset", reset_time, reset_memory),
+    ] {
+        println!(
+            "{:<25} {:>10.3?} total, speedup {:.2}x, peak memory {} KiB",
+            name,
+            time,
+            cloned_time.as_secs_f64() / time.as_secs_f64(),
+            memory / 1024
+        );
+    }
+}
@@ -0,0 +1,47 @@
+//! Benchmark the conversion of gamma to bytes for the Expand scheme, comparing a fresh conversion
+//! with the conversion that is stored during setup.
+
+use ark_bls12_381::Bls12_381;
+use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+use ark_groth16::Groth16;
+use astro_float::BigFloat;
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use std::hint::black_box;
+use std::time::Instant;
+use vldp::config::BasicConfig;
+use vldp::prelude::*;
+
+const N_MEASURE: u32 = 10_000;
+
+fn main() {
+    const GAMMA_BYTES: usize = 8;
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+
+    let gamma = BigFloat::from_f64(0.5, GAMMA_BYTES * 8);
+    let parameters = ParametersExpand::<Conf, GAMMA_BYTES>::setup(
+        gamma.clone(),
+        &mut ChaChaRng::from_seed([0; 32]),
+    )
+    .unwrap();
+
+    // fresh conversion, as performed before on every allocation of the parameters in a circuit
+    let start = Instant::now();
+    for _ in 0..N_MEASURE {
+        black_box(
+            ParametersExpand::<Conf, GAMMA_BYTES>::compute_gamma_as_bytes(black_box(&gamma))
+                .unwrap(),
+        );
+    }
+    let fresh = start.elapsed() / N_MEASURE;
+
+    // stored conversion
+    let start = Instant::now();
+    for _ in 0..N_MEASURE {
+        black_box(black_box(&parameters).gamma_as_bytes().unwrap());
+    }
+    let cached = start.elapsed() / N_MEASURE;
+
+    println!("fresh conversion:  {:?} per call", fresh);
+    println!("cached conversion: {:?} per call", cached);
+}
@@ -0,0 +1,132 @@
+//! Benchmark the per-proof time of a client in the Base scheme with and without a (warm) prover
+//! context, which caches the circuit-invariant precomputation of the proof system across proofs.
+
+use ark_bls12_381::Bls12_381;
+use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+use ark_groth16::Groth16;
+use astro_float::BigFloat;
+use num_bigint::BigUint;
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use vldp::circuits::CircuitBase;
+use vldp::client::ClientBase;
+use vldp::config::{BasicConfig, BasicConfigGadget};
+use vldp::messages::InputValueSignatureInput;
+use vldp::prelude::*;
+use vldp::server::ServerBase;
+
+const N_PROOFS: u32 = 8;
+
+const INPUT_BYTES: usize = 2;
+const TIME_BYTES: usize = 1;
+const GAMMA_BYTES: usize = 2;
+const RANDOMNESS_BYTES: usize = 4;
+const K: u64 = 4;
+
+type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+type Circuit =
+    CircuitBase<Conf, ConfG, INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, false>;
+type Client = ClientBase<Conf, INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, false>;
+type Server = ServerBase<Conf, INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, false>;
+
+/// Run the protocol for `N_PROOFS` submissions of the given client and return the total time
+/// spent in the `Randomize` step (i.e., mostly proving).
+fn time_proofs(
+    client: &mut Client,
+    client_sig_sk: &ClientSignatureSchemeSecretKey<Conf>,
+    server: &Server,
+    parameters: &ParametersBase<Conf, GAMMA_BYTES>,
+    rng: &mut ChaChaRng,
+) -> Duration {
+    let mut total = Duration::ZERO;
+    for _ in 0..N_PROOFS {
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let start = Instant::now();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                ([0], [1]),
+                [1],
+                [BigUint::from(3_u8)],
+                input_value_signature,
+                rng,
+                false,
+            )
+            .unwrap();
+        total += start.elapsed();
+        assert!(
+            server
+                .verifiable_randomization_verify::<ConfG>(&submission, ([0], [1]), rng, false)
+                .unwrap()
+                .0
+        );
+    }
+    total
+}
+
+fn main() {
+    let rng = &mut ChaChaRng::from_seed([0; 32]);
+    let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+    let (proving_key, verifying_key) = Circuit::keygen(parameters.clone(), rng).unwrap();
+    let server = Server::new(parameters.clone(), verifying_key, rng).unwrap();
+    let (client_sig_pk, client_sig_sk) =
+        <Conf as Config>::ClientSignatureScheme::keygen(&parameters.client_signature_scheme, rng)
+            .unwrap();
+    let proving_key = Arc::new(proving_key);
+    let new_client = || {
+        Client::new_with_shared_proving_key(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key.clone(),
+        )
+        .unwrap()
+    };
+
+    let cold_time = time_proofs(&mut new_client(), &client_sig_sk, &server, &parameters, rng);
+
+    let start = Instant::now();
+    let prover_context = Arc::new(Circuit::prover_context(parameters.clone()).unwrap());
+    let context_time = start.elapsed();
+    let warm_time = time_proofs(
+        &mut new_client().with_prover_context(prover_context),
+        &client_sig_sk,
+        &server,
+        &parameters,
+        rng,
+    );
+
+    println!(
+        "{} proofs (prover context computed once in {:.3?}):",
+        N_PROOFS, context_time
+    );
+    for (name, time) in [
+        ("without prover context", cold_time),
+        ("with warm prover context", warm_time),
+    ] {
+        println!(
+            "{:<25} {:>10.3?} per proof, speedup {:.2}x",
+            name,
+            time / N_PROOFS,
+            cold_time.as_secs_f64() / time.as_secs_f64()
+        );
+    }
+}
@@ -17,19 +17,24 @@ use vldp::circuits::CircuitBase;
 use vldp::client::ClientBase;
 use vldp::config::{BasicConfig, BasicConfigGadget, Config, ConfigGadget};
 use vldp::error::GenericError;
+use vldp::messages::InputValueSignatureInput;
 use vldp::prelude::{
-    ClientCommitmentSchemeOutput, ClientSignatureSchemePublicKey, ClientSignatureSchemeSecretKey,
-    ConstraintField, CryptoRng, Error, ParametersBase, Proof, ProofSystemRng, ProvingKey,
-    ServerSignatureSchemeSignature, SignatureScheme, ZKPRng,
+    validate_scheme_params, ClientCommitmentSchemeOutput, ClientSignatureSchemePublicKey,
+    ClientSignatureSchemeSecretKey, ConstraintField, CryptoRng, ParametersBase, Proof,
+    ProofSystemRng, ProvingKey, ServerSignatureSchemeSignature, SignatureScheme, ZKPRng,
 };
 use vldp::server::ServerBase;
+use vldp::time::TimeWindow;
+
+/// Errors of the VLDP library and of parsing the dataset
+type Error = Box<dyn std::error::Error>;
 
 const USERS: u16 = 182;
 
 #[derive(Debug)]
 struct GeoDataRecord {
     user: u16,
-    day: u8,
+    day: u32,
     postcode: String,
 }
 
@@ -107,8 +112,7 @@ fn setup<
     ),
     Error,
 > {
-    assert!(IS_REAL_INPUT || INPUT_BYTES + GAMMA_BYTES == RANDOMNESS_BYTES);
-    assert!(!IS_REAL_INPUT || 2 * INPUT_BYTES + GAMMA_BYTES == RANDOMNESS_BYTES);
+    validate_scheme_params::<INPUT_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>()?;
     assert!(K >= 2 && (K.ilog2() + 1) as usize <= INPUT_BYTES * 8);
     assert!(INPUT_BYTES * 8 <= ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize);
     assert!(GAMMA_BYTES * 8 <= ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize);
@@ -219,23 +223,17 @@ where
             proving_key.clone(),
         )?;
         // generate randomness
-        let mut lower_bound_time = [0; TIME_BYTES];
-        lower_bound_time[0] = record.day;
+        let (lower_bound_time, input_value_time) =
+            TimeWindow::from_counter::<TIME_BYTES>(record.day.into())?;
         let gen_rand_client_message =
             client.generate_randomness_create(lower_bound_time.clone(), &mut rng)?;
         let gen_rand_server_message =
             server.generate_randomness_create(&gen_rand_client_message, &mut rng)?;
-        let result = client.generate_randomness_verify(&gen_rand_server_message)?;
-        assert!(
-            result,
-            "Verification of generate randomness protocol failed."
-        );
+        client.generate_randomness_verify(&gen_rand_server_message)?;
 
         // input data from trusted environment
         let input_value = BigUint::from(*postcode_bin_map.get(&record.postcode).unwrap());
 
-        let mut input_value_time = [0; TIME_BYTES];
-        input_value_time[0] = record.day + 1;
         let time_bounds = (lower_bound_time, input_value_time.clone());
 
         let mut input_value_bytes = [0; INPUT_BYTES];
@@ -243,8 +241,11 @@ where
             input_value_bytes[idx] = *byte;
         }
 
-        let mut message_bytes = input_value_bytes.to_vec();
-        message_bytes.extend_from_slice(&input_value_time);
+        let message_bytes = InputValueSignatureInput {
+            input_value: input_value_bytes,
+            input_value_time,
+        }
+        .to_bytes();
 
         let input_value_signature = Conf::ClientSignatureScheme::sign(
             &parameters.client_signature_scheme,
@@ -257,7 +258,7 @@ where
         let ver_rand_client_message = client.verifiable_randomization_create::<ConfG>(
             time_bounds,
             input_value_time,
-            input_value.clone(),
+            [input_value.clone()],
             input_value_signature,
             &mut zkp_rng,
             true,
@@ -272,7 +273,7 @@ where
             result.0,
             "Verification of verifiable randomization protocol failed."
         );
-        ldp_values.push(result.1);
+        ldp_values.push(result.1[0]);
 
         if record.user == USERS - 1 {
             println!("Day {}:", record.day);
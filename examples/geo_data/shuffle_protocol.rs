@@ -17,20 +17,26 @@ use vldp::circuits::CircuitShuffle;
 use vldp::client::ClientShuffle;
 use vldp::config::{BasicConfig, BasicConfigGadget, Config, ConfigGadget};
 use vldp::error::GenericError;
+use vldp::messages::shuffle::PrfEvalPointSet;
+use vldp::messages::InputValueSignatureInput;
 use vldp::prelude::{
-    ClientCommitmentSchemeOutput, ClientSignatureSchemePublicKey, ClientSignatureSchemeSecretKey,
-    ConstraintField, CryptoRng, Error, PRFSchemeInput, ParametersShuffle, Proof, ProofSystemRng,
-    ProvingKey, ServerSignatureSchemePublicKey, ServerSignatureSchemeSignature, SignatureScheme,
-    ZKPRng,
+    validate_scheme_params, ClientCommitmentSchemeOutput, ClientSignatureSchemePublicKey,
+    ClientSignatureSchemeSecretKey, ConstraintField, CryptoRng, PRFSchemeInput, ParametersShuffle,
+    Proof, ProofSystemRng, ProvingKey, ServerSignatureSchemePublicKey,
+    ServerSignatureSchemeSignature, SignatureScheme, ZKPRng,
 };
 use vldp::server::ServerShuffle;
+use vldp::time::TimeWindow;
+
+/// Errors of the VLDP library and of parsing the dataset
+type Error = Box<dyn std::error::Error>;
 
 const USERS: u16 = 182;
 
 #[derive(Debug)]
 struct GeoDataRecord {
     user: u16,
-    day: u8,
+    day: u32,
     postcode: String,
 }
 
@@ -116,8 +122,7 @@ fn setup<
     ),
     Error,
 > {
-    assert!(IS_REAL_INPUT || INPUT_BYTES + GAMMA_BYTES == RANDOMNESS_BYTES);
-    assert!(!IS_REAL_INPUT || 2 * INPUT_BYTES + GAMMA_BYTES == RANDOMNESS_BYTES);
+    validate_scheme_params::<INPUT_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>()?;
     assert!(K >= 2 && (K.ilog2() + 1) as usize <= INPUT_BYTES * 8);
     assert!(INPUT_BYTES * 8 <= ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize);
     assert!(GAMMA_BYTES * 8 <= ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize);
@@ -203,9 +208,13 @@ where
             IS_REAL_INPUT,
         >(gamma.clone(), &mut rng, &mut zkp_rng)?;
     let server_sig_pk = server.get_signature_public_key();
-    let prf_eval_points = (0..((RANDOMNESS_BYTES - 1) / 32) + 1)
-        .map(|_| rng.gen::<PRFSchemeInput<Conf>>())
-        .collect::<Vec<_>>();
+    // a single epoch of eval points for all records
+    let prf_eval_points = PrfEvalPointSet::<Conf>::new(
+        0,
+        (0..((RANDOMNESS_BYTES - 1) / Conf::PRF_OUTPUT_BYTES) + 1)
+            .map(|_| rng.gen::<PRFSchemeInput<Conf>>())
+            .collect(),
+    );
 
     let mut ldp_values = vec![];
 
@@ -233,19 +242,13 @@ where
         let gen_rand_client_message = client.generate_randomness_create(&mut rng)?;
         let gen_rand_server_message =
             server.generate_randomness_create(&gen_rand_client_message, &mut rng)?;
-        let result = client.generate_randomness_verify(&gen_rand_server_message)?;
-        assert!(
-            result,
-            "Verification of generate randomness protocol failed."
-        );
+        client.generate_randomness_verify(&gen_rand_server_message)?;
 
         // input data from trusted environment
         let input_value = BigUint::from(*postcode_bin_map.get(&record.postcode).unwrap());
 
-        let mut input_value_time = [0; TIME_BYTES];
-        input_value_time[0] = record.day + 1;
-        let mut lower_bound_time = [0; TIME_BYTES];
-        lower_bound_time[0] = record.day;
+        let (lower_bound_time, input_value_time) =
+            TimeWindow::from_counter::<TIME_BYTES>(record.day.into())?;
         let time_bounds = (lower_bound_time, input_value_time.clone());
 
         let mut input_value_bytes = [0; INPUT_BYTES];
@@ -253,8 +256,11 @@ where
             input_value_bytes[idx] = *byte;
         }
 
-        let mut message_bytes = input_value_bytes.to_vec();
-        message_bytes.extend_from_slice(&input_value_time);
+        let message_bytes = InputValueSignatureInput {
+            input_value: input_value_bytes,
+            input_value_time,
+        }
+        .to_bytes();
 
         let input_value_signature = Conf::ClientSignatureScheme::sign(
             &parameters.client_signature_scheme,
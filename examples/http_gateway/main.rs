@@ -0,0 +1,529 @@
+/// Minimal REST+JSON gateway in front of a server of the Base VLDP scheme, such that clients only
+/// need to speak HTTP. Messages are exchanged as base64-encoded (compressed) arkworks bytes:
+/// - `GET /proving_key` returns the proving key of the circuit, which clients need to prove
+/// - `POST /genrand` with `{"message": ...}` returns the server message of the `GenRand` step
+/// - `POST /randomize` with `{"message": ...}` verifies a submission and returns its outcome and
+///   LDP value
+///
+/// Run with `cargo run --release --example http_gateway [address] [proving key] [verifying key]`.
+/// The keys are loaded from the given key files (e.g., the output of a trusted setup, see
+/// `vldp::config::save_proving_key`), or generated from entropy if no key files are given.
+use ark_bls12_381::Bls12_381;
+use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+use ark_groth16::Groth16;
+use ark_serialize::CanonicalSerialize;
+use astro_float::BigFloat;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::time::Duration;
+use vldp::circuits::CircuitBase;
+use vldp::config::{
+    load_proving_key, load_verifying_key, BasicConfig, BasicConfigGadget, KeyParameters,
+};
+use vldp::error::GenericError;
+use vldp::prelude::{ParametersBase, ProvingKey, VerifyingKey};
+use vldp::server::ServerBase;
+
+/// Errors of the VLDP library and of handling the HTTP requests
+type Error = Box<dyn std::error::Error>;
+
+const INPUT_BYTES: usize = 2;
+const TIME_BYTES: usize = 1;
+const GAMMA_BYTES: usize = 2;
+const RANDOMNESS_BYTES: usize = 4;
+const K: u64 = 4;
+
+/// Seed of the public parameters (the parameters of the commitment and signature schemes), which
+/// clients derive from it as well. The keys of the circuit are never derived from a known seed, as
+/// anyone who knows the randomness of the key generation could forge proofs.
+const PARAMETERS_SEED: [u8; 32] = [0; 32];
+
+/// Maximum length of the request line and of each header line in bytes
+const MAX_LINE_BYTES: usize = 8 * 1024;
+/// Maximum length of a request body in bytes, far above the size of any (base64-encoded) message
+const MAX_BODY_BYTES: usize = 64 * 1024;
+/// Time after which a connection that stalls while sending its request is dropped, such that a
+/// single slow client cannot block the gateway, which serves one connection at a time
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+type Server = ServerBase<Conf, INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, false>;
+
+/// Request body of both endpoints
+#[derive(Serialize, Deserialize)]
+struct MessageRequest {
+    message: String,
+}
+
+/// Response body of `GET /proving_key`
+#[derive(Serialize, Deserialize)]
+struct ProvingKeyResponse {
+    proving_key: String,
+}
+
+/// Response body of `POST /genrand`
+#[derive(Serialize, Deserialize)]
+struct GenRandResponse {
+    message: String,
+}
+
+/// Outcome of verifying a submission
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Outcome {
+    Accepted,
+    Rejected,
+    Error,
+}
+
+/// Response body of `POST /randomize`
+#[derive(Serialize, Deserialize)]
+struct RandomizeResponse {
+    outcome: Outcome,
+    ldp_value: Option<u64>,
+    error: Option<String>,
+}
+
+/// Response body of all failed requests
+#[derive(Serialize, Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// Gateway wrapping a server, which accepts submissions for a fixed time window
+struct Gateway {
+    server: Server,
+    /// Base64-encoded (compressed) proving key, as handed out to the clients
+    proving_key: String,
+    time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+    rng: Mutex<ChaChaRng>,
+}
+
+impl Gateway {
+    fn new(
+        server: Server,
+        proving_key: &ProvingKey<Conf>,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+    ) -> Result<Self, Error> {
+        let mut proving_key_bytes = vec![];
+        proving_key.serialize_compressed(&mut proving_key_bytes)?;
+        Ok(Self {
+            server,
+            proving_key: BASE64_STANDARD.encode(proving_key_bytes),
+            time_bounds,
+            rng: Mutex::new(ChaChaRng::from_entropy()),
+        })
+    }
+
+    /// Handle a single request, returning the HTTP status and JSON body of the response.
+    fn handle(&self, method: &str, path: &str, body: &[u8]) -> (u16, String) {
+        let result = match (method, path) {
+            ("GET", "/proving_key") => Ok((
+                200,
+                to_json(&ProvingKeyResponse {
+                    proving_key: self.proving_key.clone(),
+                }),
+            )),
+            ("POST", "/genrand") => self.genrand(body),
+            ("POST", "/randomize") => self.randomize(body),
+            _ => {
+                return (
+                    404,
+                    to_json(&ErrorResponse {
+                        error: format!("No endpoint {} {}", method, path),
+                    }),
+                )
+            }
+        };
+        result.unwrap_or_else(|error| {
+            (
+                400,
+                to_json(&ErrorResponse {
+                    error: error.to_string(),
+                }),
+            )
+        })
+    }
+
+    fn genrand(&self, body: &[u8]) -> Result<(u16, String), Error> {
+        let client_message = decode_message(body)?;
+        let mut rng = self.rng.lock().unwrap();
+        let server_message = self
+            .server
+            .generate_randomness_create(&client_message, &mut *rng)?;
+        Ok((
+            200,
+            to_json(&GenRandResponse {
+                message: BASE64_STANDARD.encode(server_message),
+            }),
+        ))
+    }
+
+    fn randomize(&self, body: &[u8]) -> Result<(u16, String), Error> {
+        let client_message = decode_message(body)?;
+        let mut rng = self.rng.lock().unwrap();
+        let response = match self.server.verifiable_randomization_verify::<ConfG>(
+            &client_message,
+            self.time_bounds,
+            &mut *rng,
+            false,
+        ) {
+            Ok((true, [ldp_value])) => RandomizeResponse {
+                outcome: Outcome::Accepted,
+                ldp_value: Some(ldp_value),
+                error: None,
+            },
+            Ok((false, _)) => RandomizeResponse {
+                outcome: Outcome::Rejected,
+                ldp_value: None,
+                error: None,
+            },
+            Err(error) => RandomizeResponse {
+                outcome: Outcome::Error,
+                ldp_value: None,
+                error: Some(error.to_string()),
+            },
+        };
+        Ok((200, to_json(&response)))
+    }
+
+    /// Serve HTTP/1.1 requests on `listener`, one connection at a time.
+    fn serve(&self, listener: TcpListener) -> Result<(), Error> {
+        for stream in listener.incoming() {
+            if let Err(error) = self.serve_connection(stream?) {
+                eprintln!("Failed to serve connection: {}", error);
+            }
+        }
+        Ok(())
+    }
+
+    fn serve_connection(&self, stream: TcpStream) -> Result<(), Error> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let mut reader = BufReader::new(stream);
+        let (status, body) = match read_request(&mut reader) {
+            Ok(request) => self.handle(&request.method, &request.path, &request.body),
+            Err(response) => response,
+        };
+        let reason = match status {
+            200 => "OK",
+            404 => "Not Found",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            _ => "Bad Request",
+        };
+        write!(
+            reader.get_mut(),
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            reason,
+            body.len(),
+            body
+        )?;
+        Ok(())
+    }
+}
+
+/// HTTP request read from a connection
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+/// Read a request from `reader`, returning the HTTP status and JSON body of the error response if
+/// it is malformed or exceeds [`MAX_LINE_BYTES`] or [`MAX_BODY_BYTES`].
+fn read_request<R: BufRead>(reader: &mut R) -> Result<Request, (u16, String)> {
+    // request line and headers
+    let request_line = read_line(reader)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+    let mut content_length = 0;
+    loop {
+        let header = read_line(reader)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().map_err(|error| {
+                    error_response(400, format!("Invalid content length: {}", error))
+                })?;
+            }
+        }
+    }
+
+    // body
+    if content_length > MAX_BODY_BYTES {
+        Err(error_response(
+            413,
+            format!(
+                "Body of {} bytes exceeds {} bytes",
+                content_length, MAX_BODY_BYTES
+            ),
+        ))?
+    }
+    let mut body = vec![0; content_length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|error| error_response(400, error.to_string()))?;
+
+    Ok(Request { method, path, body })
+}
+
+/// Read a single line of at most [`MAX_LINE_BYTES`] bytes from `reader`.
+fn read_line<R: BufRead>(reader: &mut R) -> Result<String, (u16, String)> {
+    let mut line = String::new();
+    reader
+        .by_ref()
+        .take(MAX_LINE_BYTES as u64 + 1)
+        .read_line(&mut line)
+        .map_err(|error| error_response(400, error.to_string()))?;
+    if line.len() > MAX_LINE_BYTES {
+        Err(error_response(
+            431,
+            format!("Line exceeds {} bytes", MAX_LINE_BYTES),
+        ))?
+    }
+    Ok(line)
+}
+
+fn decode_message(body: &[u8]) -> Result<Vec<u8>, Error> {
+    let request: MessageRequest = serde_json::from_slice(body)?;
+    BASE64_STANDARD
+        .decode(request.message)
+        .map_err(|error| GenericError::ParseError(error.to_string()).into())
+}
+
+fn error_response(status: u16, error: String) -> (u16, String) {
+    (status, to_json(&ErrorResponse { error }))
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    // all response types only contain strings, integers and unit variants
+    serde_json::to_string(value).unwrap()
+}
+
+/// Load the proving and verifying key from the files at `key_files`, or generate them from
+/// entropy if no key files are given.
+fn setup_keys(
+    parameters: &ParametersBase<Conf, GAMMA_BYTES>,
+    key_files: Option<(String, String)>,
+) -> Result<(ProvingKey<Conf>, VerifyingKey<Conf>), Error> {
+    match key_files {
+        Some((proving_key_path, verifying_key_path)) => {
+            let key_parameters = KeyParameters::base::<
+                INPUT_BYTES,
+                TIME_BYTES,
+                GAMMA_BYTES,
+                RANDOMNESS_BYTES,
+                K,
+                false,
+                1,
+            >();
+            Ok((
+                load_proving_key::<Conf>(proving_key_path, &key_parameters)?,
+                load_verifying_key::<Conf>(verifying_key_path, &key_parameters)?,
+            ))
+        }
+        None => Ok(CircuitBase::<
+            Conf,
+            ConfG,
+            INPUT_BYTES,
+            TIME_BYTES,
+            GAMMA_BYTES,
+            RANDOMNESS_BYTES,
+            K,
+            false,
+        >::keygen(parameters.clone(), &mut ChaChaRng::from_entropy())?),
+    }
+}
+
+fn main() -> Result<(), Error> {
+    let mut args = std::env::args().skip(1);
+    let address = args.next().unwrap_or_else(|| "127.0.0.1:8080".to_string());
+    let key_files = match (args.next(), args.next()) {
+        (Some(proving_key_path), Some(verifying_key_path)) => {
+            Some((proving_key_path, verifying_key_path))
+        }
+        (None, None) => None,
+        _ => Err("Expected the paths of both the proving and the verifying key")?,
+    };
+
+    let parameters = ParametersBase::<Conf, GAMMA_BYTES>::setup(
+        BigFloat::from_f64(0.5, 64),
+        &mut ChaChaRng::from_seed(PARAMETERS_SEED),
+    )?;
+    let (proving_key, verifying_key) = setup_keys(&parameters, key_files)?;
+    let server = Server::new(parameters, verifying_key, &mut ChaChaRng::from_entropy())?;
+
+    let gateway = Gateway::new(server, &proving_key, ([0], [u8::MAX]))?;
+    let listener = TcpListener::bind(&address)?;
+    println!("Listening on {}", address);
+    gateway.serve(listener)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_serialize::CanonicalDeserialize;
+    use num_bigint::BigUint;
+    use vldp::client::ClientBase;
+    use vldp::config::{save_proving_key, save_verifying_key};
+    use vldp::messages::InputValueSignatureInput;
+    use vldp::prelude::{Config, SignatureScheme};
+
+    type Client =
+        ClientBase<Conf, INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, false>;
+
+    fn post(gateway: &Gateway, path: &str, message: &[u8]) -> (u16, String) {
+        let request = to_json(&MessageRequest {
+            message: BASE64_STANDARD.encode(message),
+        });
+        gateway.handle("POST", path, request.as_bytes())
+    }
+
+    #[test]
+    fn submission_is_verified_through_gateway() {
+        let rng = &mut ChaChaRng::from_seed([0; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) = CircuitBase::<
+            Conf,
+            ConfG,
+            INPUT_BYTES,
+            TIME_BYTES,
+            GAMMA_BYTES,
+            RANDOMNESS_BYTES,
+            K,
+            false,
+        >::keygen(parameters.clone(), rng)
+        .unwrap();
+
+        // the gateway loads the keys from key files
+        let directory = std::env::temp_dir();
+        let key_files = (
+            directory.join(format!("vldp-gateway-{}-proving.key", std::process::id())),
+            directory.join(format!("vldp-gateway-{}-verifying.key", std::process::id())),
+        );
+        let key_parameters = KeyParameters::base::<
+            INPUT_BYTES,
+            TIME_BYTES,
+            GAMMA_BYTES,
+            RANDOMNESS_BYTES,
+            K,
+            false,
+            1,
+        >();
+        save_proving_key::<Conf>(&key_files.0, &key_parameters, &proving_key).unwrap();
+        save_verifying_key::<Conf>(&key_files.1, &key_parameters, &verifying_key).unwrap();
+        let (proving_key, verifying_key) = setup_keys(
+            &parameters,
+            Some((
+                key_files.0.to_string_lossy().into_owned(),
+                key_files.1.to_string_lossy().into_owned(),
+            )),
+        )
+        .unwrap();
+        std::fs::remove_file(&key_files.0).unwrap();
+        std::fs::remove_file(&key_files.1).unwrap();
+
+        let server = Server::new(parameters.clone(), verifying_key, rng).unwrap();
+        let gateway = Gateway::new(server, &proving_key, ([0], [1])).unwrap();
+
+        // the client obtains the proving key from the gateway
+        let (status, body) = gateway.handle("GET", "/proving_key", b"");
+        assert_eq!(status, 200, "{}", body);
+        let response: ProvingKeyResponse = serde_json::from_str(&body).unwrap();
+        let proving_key = ProvingKey::<Conf>::deserialize_compressed(
+            BASE64_STANDARD.decode(response.proving_key).unwrap().as_slice(),
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = Client::new(
+            parameters.clone(),
+            gateway.server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+
+        // GenRand
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let truncated = &client_message[..client_message.len() - 1];
+        assert_eq!(post(&gateway, "/genrand", truncated).0, 400);
+        let (status, body) = post(&gateway, "/genrand", &client_message);
+        assert_eq!(status, 200, "{}", body);
+        let response: GenRandResponse = serde_json::from_str(&body).unwrap();
+        let server_message = BASE64_STANDARD.decode(response.message).unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+
+        // Randomize
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                ([0], [1]),
+                [1],
+                [BigUint::from(3_u8)],
+                input_value_signature,
+                rng,
+                false,
+            )
+            .unwrap();
+        let (status, body) = post(&gateway, "/randomize", &submission);
+        assert_eq!(status, 200, "{}", body);
+        let response: RandomizeResponse = serde_json::from_str(&body).unwrap();
+        assert_eq!(response.outcome, Outcome::Accepted);
+        assert!(response.ldp_value.unwrap() <= K);
+
+        // malformed requests
+        let (status, _) = post(&gateway, "/randomize", &[0; 4]);
+        assert_eq!(status, 200);
+        assert_eq!(gateway.handle("POST", "/randomize", b"{}").0, 400);
+        assert_eq!(gateway.handle("GET", "/genrand", b"").0, 404);
+    }
+
+    #[test]
+    fn oversized_requests_are_rejected() {
+        let read = |request: &[u8]| read_request(&mut &request[..]).map(|request| request.body);
+
+        let request = b"POST /randomize HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}";
+        assert_eq!(read(request).unwrap(), b"{}");
+
+        let request = format!(
+            "POST /randomize HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_BYTES + 1
+        );
+        assert_eq!(read(request.as_bytes()).unwrap_err().0, 413);
+
+        let request = format!(
+            "POST /randomize HTTP/1.1\r\nX-Padding: {}\r\n\r\n",
+            "a".repeat(MAX_LINE_BYTES)
+        );
+        assert_eq!(read(request.as_bytes()).unwrap_err().0, 431);
+
+        let request = b"POST /randomize HTTP/1.1\r\nContent-Length: -1\r\n\r\n";
+        assert_eq!(read(request).unwrap_err().0, 400);
+    }
+}
@@ -17,12 +17,18 @@ use vldp::circuits::CircuitBase;
 use vldp::client::ClientBase;
 use vldp::config::{BasicConfig, BasicConfigGadget, Config, ConfigGadget};
 use vldp::error::GenericError;
+use vldp::messages::InputValueSignatureInput;
 use vldp::prelude::{
-    ClientCommitmentSchemeOutput, ClientSignatureSchemePublicKey, ClientSignatureSchemeSecretKey,
-    ConstraintField, CryptoRng, Error, ParametersBase, Proof, ProofSystemRng, ProvingKey,
-    ServerSignatureSchemeSignature, SignatureScheme, ZKPRng,
+    float_conversion_precision, validate_scheme_params, ClientCommitmentSchemeOutput,
+    ClientSignatureSchemePublicKey, ClientSignatureSchemeSecretKey, ConstraintField, CryptoRng,
+    ParametersBase, Proof, ProofSystemRng, ProvingKey, ServerSignatureSchemeSignature,
+    SignatureScheme, ZKPRng,
 };
-use vldp::server::ServerBase;
+use vldp::server::{Aggregator, ServerBase};
+use vldp::time::TimeWindow;
+
+/// Errors of the VLDP library and of parsing the dataset
+type Error = Box<dyn std::error::Error>;
 
 const HOUSEHOLDS: u16 = 5566;
 const MAX_VALUE: f64 = 0.3527045043460217;
@@ -30,7 +36,7 @@ const MAX_VALUE: f64 = 0.3527045043460217;
 #[derive(Debug)]
 struct SmartMeterRecord {
     household: u16,
-    day: u8,
+    day: u32,
     average_energy: BigFloat,
 }
 
@@ -101,8 +107,7 @@ fn setup<
     ),
     Error,
 > {
-    assert!(IS_REAL_INPUT || INPUT_BYTES + GAMMA_BYTES == RANDOMNESS_BYTES);
-    assert!(!IS_REAL_INPUT || 2 * INPUT_BYTES + GAMMA_BYTES == RANDOMNESS_BYTES);
+    validate_scheme_params::<INPUT_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>()?;
     assert!(K >= 2 && (K.ilog2() + 1) as usize <= INPUT_BYTES * 8);
     assert!(INPUT_BYTES * 8 <= ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize);
     assert!(GAMMA_BYTES * 8 <= ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize);
@@ -190,7 +195,7 @@ where
         >(gamma.clone(), &mut rng, &mut zkp_rng)?;
     let server_sig_pk = server.get_signature_public_key();
 
-    let mut ldp_values = vec![];
+    let mut aggregator = Aggregator::new();
 
     for record in records.iter() {
         if record.household % 100 == 0 {
@@ -213,23 +218,19 @@ where
             proving_key.clone(),
         )?;
         // generate randomness
-        let mut lower_bound_time = [0; TIME_BYTES];
-        lower_bound_time[0] = record.day;
+        let (lower_bound_time, input_value_time) =
+            TimeWindow::from_counter::<TIME_BYTES>(record.day.into())?;
         let gen_rand_client_message =
             client.generate_randomness_create(lower_bound_time.clone(), &mut rng)?;
         let gen_rand_server_message =
             server.generate_randomness_create(&gen_rand_client_message, &mut rng)?;
-        let result = client.generate_randomness_verify(&gen_rand_server_message)?;
-        assert!(
-            result,
-            "Verification of generate randomness protocol failed."
-        );
+        client.generate_randomness_verify(&gen_rand_server_message)?;
 
         // input data from trusted environment
         let input_value = if record.average_energy.is_zero() {
             BigUint::zero()
         } else {
-            let precision = INPUT_BYTES * 8 * 2;
+            let precision = float_conversion_precision("INPUT_BYTES", INPUT_BYTES)?;
             let input_as_string = record
                 .average_energy
                 .mul_full_prec(
@@ -241,8 +242,9 @@ where
                 .convert_to_radix(
                     Radix::Dec,
                     RoundingMode::None,
-                    &mut Consts::new().expect("Constants cache initialization should not fail."),
-                )?
+                    &mut Consts::new().map_err(GenericError::from)?,
+                )
+                .map_err(GenericError::from)?
                 .1
                 .iter()
                 .map(|digit| digit.to_string())
@@ -254,8 +256,6 @@ where
             }
         };
 
-        let mut input_value_time = [0; TIME_BYTES];
-        input_value_time[0] = record.day + 1;
         let time_bounds = (lower_bound_time, input_value_time.clone());
 
         let mut input_value_bytes = [0; INPUT_BYTES];
@@ -263,8 +263,11 @@ where
             input_value_bytes[idx] = *byte;
         }
 
-        let mut message_bytes = input_value_bytes.to_vec();
-        message_bytes.extend_from_slice(&input_value_time);
+        let message_bytes = InputValueSignatureInput {
+            input_value: input_value_bytes,
+            input_value_time,
+        }
+        .to_bytes();
 
         let input_value_signature = Conf::ClientSignatureScheme::sign(
             &parameters.client_signature_scheme,
@@ -277,7 +280,7 @@ where
         let ver_rand_client_message = client.verifiable_randomization_create::<ConfG>(
             time_bounds,
             input_value_time,
-            input_value.clone(),
+            [input_value.clone()],
             input_value_signature,
             &mut zkp_rng,
             true,
@@ -292,36 +295,18 @@ where
             result.0,
             "Verification of verifiable randomization protocol failed."
         );
-        ldp_values.push(result.1);
+        aggregator.add_timed(&time_bounds.0, result.1[0]);
+    }
 
-        if record.household == HOUSEHOLDS - 1 {
-            let max_value = BigFloat::from_f64(MAX_VALUE, 100);
-            let sample_sum = ldp_values.iter().sum::<u64>() as f64;
-            let estimate = BigFloat::from_f64(sample_sum / K as f64, 100)
-                .sub(
-                    &gamma.mul(
-                        &BigFloat::from_f64(HOUSEHOLDS as f64 / 2.0, 100),
-                        100,
-                        RoundingMode::None,
-                    ),
-                    100,
-                    RoundingMode::None,
-                )
-                .div(
-                    &(BigFloat::from_u8(1, 100).sub(&gamma, 100, RoundingMode::None)),
-                    100,
-                    RoundingMode::None,
-                )
-                .div(
-                    &BigFloat::from_f64(HOUSEHOLDS as f64, 100),
-                    100,
-                    RoundingMode::None,
-                );
-            let estimate = estimate.mul(&max_value, 100, RoundingMode::None);
-            println!("Day {}:", record.day);
-            println!("Estimate: {estimate}");
-            ldp_values.clear();
-        }
+    let max_value = BigFloat::from_f64(MAX_VALUE, 100);
+    let gamma_bytes = parameters.gamma_as_bytes()?;
+    for window in aggregator.windows() {
+        let estimate = aggregator
+            .estimate_for_window::<K>(&window, &gamma_bytes)
+            .expect("every window has a value")
+            .mul(&max_value, 100, RoundingMode::None);
+        println!("Day {}:", BigUint::from_bytes_le(&window));
+        println!("Estimate: {estimate}");
     }
     Ok(())
 }
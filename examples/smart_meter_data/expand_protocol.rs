@@ -11,18 +11,25 @@ use csv::StringRecord;
 use num_bigint::BigUint;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
+use std::cell::Cell;
 use std::process;
 use std::str::FromStr;
 use vldp::circuits::CircuitExpand;
-use vldp::client::ClientExpand;
+use vldp::client::{ClientExpand, ClientExpandStream, RandomizationRecord};
 use vldp::config::{BasicConfig, BasicConfigGadget, Config, ConfigGadget};
-use vldp::error::GenericError;
+use vldp::error::{GenericError, VldpError};
+use vldp::messages::InputValueSignatureInput;
 use vldp::prelude::{
-    ClientMerkleTreeRoot, ClientSignatureSchemePublicKey, ClientSignatureSchemeSecretKey,
-    ConstraintField, CryptoRng, Error, PRFSchemeInput, ParametersExpand, Proof, ProofSystemRng,
-    ProvingKey, ServerSignatureSchemeSignature, SignatureScheme, ZKPRng,
+    float_conversion_precision, validate_scheme_params, ClientMerkleTreeRoot,
+    ClientSignatureSchemePublicKey, ClientSignatureSchemeSecretKey, ConstraintField, CryptoRng,
+    PRFSchemeInput, ParametersExpand, Proof, ProofSystemRng, ProvingKey,
+    ServerSignatureSchemeSignature, SignatureScheme, ZKPRng,
 };
-use vldp::server::ServerExpand;
+use vldp::server::{Aggregator, ServerExpand};
+use vldp::time::TimeWindow;
+
+/// Errors of the VLDP library and of parsing the dataset
+type Error = Box<dyn std::error::Error>;
 
 const HOUSEHOLDS: u16 = 5566;
 const MAX_VALUE: f64 = 0.3527045043460217;
@@ -30,7 +37,7 @@ const MAX_VALUE: f64 = 0.3527045043460217;
 #[derive(Debug)]
 struct SmartMeterRecord {
     household: u16,
-    day: u8,
+    day: u32,
     average_energy: BigFloat,
 }
 
@@ -62,19 +69,47 @@ impl TryFrom<StringRecord> for SmartMeterRecord {
     }
 }
 
-fn load_data() -> Result<Vec<SmartMeterRecord>, Error> {
-    let mut reader = csv::Reader::from_path("resources/shuffle-model-parameters/energy_data.csv")?;
-    let mut records = reader
-        .records()
-        .flat_map(|record| record.map(|record| record.try_into()))
-        .collect::<Result<Vec<SmartMeterRecord>, _>>()?;
+/// Lazily read the records of the dataset, such that it never has to be held in memory at once.
+fn records() -> Result<impl Iterator<Item = Result<SmartMeterRecord, Error>>, Error> {
+    let reader = csv::Reader::from_path("resources/shuffle-model-parameters/energy_data.csv")?;
     let max_value = BigFloat::from_f64(MAX_VALUE, 100);
-    for record in records.iter_mut() {
+    Ok(reader.into_records().map(move |record| {
+        let mut record = SmartMeterRecord::try_from(record?)?;
         record.average_energy = record
             .average_energy
             .div(&max_value, 100, RoundingMode::None);
+        Ok(record)
+    }))
+}
+
+/// Input value of a record as an `INPUT_BYTES` integer, as obtained from the trusted environment.
+fn input_value<const INPUT_BYTES: usize>(average_energy: &BigFloat) -> Result<BigUint, VldpError> {
+    if average_energy.is_zero() {
+        return Ok(BigUint::zero());
+    }
+    let precision = float_conversion_precision("INPUT_BYTES", INPUT_BYTES)?;
+    let input_as_string = average_energy
+        .mul_full_prec(
+            &BigFloat::from_u8(2, precision)
+                .powi(INPUT_BYTES * 8, precision, RoundingMode::Down)
+                .sub_full_prec(&BigFloat::from_u8(1, precision)),
+        )
+        .int()
+        .convert_to_radix(
+            Radix::Dec,
+            RoundingMode::None,
+            &mut Consts::new().map_err(GenericError::from)?,
+        )
+        .map_err(GenericError::from)?
+        .1
+        .iter()
+        .map(|digit| digit.to_string())
+        .collect::<String>();
+    if input_as_string.is_empty() {
+        Ok(BigUint::zero())
+    } else {
+        Ok(BigUint::from_str(&input_as_string).expect("This parse should not fail."))
     }
-    Ok(records)
 }
 
 fn setup<
@@ -111,8 +146,7 @@ fn setup<
     ),
     Error,
 > {
-    assert!(IS_REAL_INPUT || INPUT_BYTES + GAMMA_BYTES == RANDOMNESS_BYTES);
-    assert!(!IS_REAL_INPUT || 2 * INPUT_BYTES + GAMMA_BYTES == RANDOMNESS_BYTES);
+    validate_scheme_params::<INPUT_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>()?;
     assert!(K >= 2 && (K.ilog2() + 1) as usize <= INPUT_BYTES * 8);
     assert!(INPUT_BYTES * 8 <= ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize);
     assert!(GAMMA_BYTES * 8 <= ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize);
@@ -184,7 +218,6 @@ where
     ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
     Proof<Conf>: CanonicalDeserialize,
 {
-    let records = load_data()?;
     // setup
     let mut rng = ChaChaRng::from_entropy();
     let mut zkp_rng = Conf::ZKPRng::new();
@@ -202,146 +235,132 @@ where
             IS_REAL_INPUT,
         >(gamma.clone(), &mut rng, &mut zkp_rng)?;
     let server_sig_pk = server.get_signature_public_key();
-    let prf_eval_points = (0..((RANDOMNESS_BYTES - 1) / 32) + 1)
+    let prf_eval_points = (0..((RANDOMNESS_BYTES - 1) / Conf::PRF_OUTPUT_BYTES) + 1)
         .map(|_| rng.gen::<PRFSchemeInput<Conf>>())
         .collect::<Vec<_>>();
 
-    let mut ldp_values = vec![];
+    // the trusted environment and the server use their own rngs
+    let mut trusted_environment_rng = ChaChaRng::from_entropy();
+    let mut server_rng = ChaChaRng::from_entropy();
+    let mut server_zkp_rng = Conf::ZKPRng::new();
 
-    for record in records.iter() {
-        if record.household % 100 == 0 {
-            println!("{}", record.household)
-        }
-        let client_public_key = client_public_keys[record.household as usize].clone();
-        let client_secret_key = client_secret_keys[record.household as usize].clone();
-        let mut client = ClientExpand::<
-            _,
-            MT_DEPTH,
-            INPUT_BYTES,
-            TIME_BYTES,
-            GAMMA_BYTES,
-            RANDOMNESS_BYTES,
-            K,
-            IS_REAL_INPUT,
-        >::new(
-            parameters.clone(),
-            server_sig_pk.clone(),
-            client_public_key,
-            proving_key.clone(),
-        )?;
-        // generate randomness
-        let gen_rand_client_message = client.generate_randomness_create(&mut rng)?;
-        let gen_rand_server_message =
-            server.generate_randomness_create(&gen_rand_client_message, &mut rng)?;
-        let result = client.generate_randomness_verify(&gen_rand_server_message)?;
-        assert!(
-            result,
-            "Verification of generate randomness protocol failed."
-        );
+    // time bounds and index of the record that was randomized last, which the server needs to
+    // verify its message
+    let current_record = Cell::new(None);
+    let records = records()?.map(
+        |record| -> Result<RandomizationRecord<Conf, TIME_BYTES>, VldpError> {
+            let record = record.map_err(|e| GenericError::ParseError(e.to_string()))?;
+            if record.household % 100 == 0 {
+                println!("{}", record.household)
+            }
 
-        // input data from trusted environment
-        let input_value = if record.average_energy.is_zero() {
-            BigUint::zero()
-        } else {
-            let precision = INPUT_BYTES * 8 * 2;
-            let input_as_string = record
-                .average_energy
-                .mul_full_prec(
-                    &BigFloat::from_u8(2, precision)
-                        .powi(INPUT_BYTES * 8, precision, RoundingMode::Down)
-                        .sub_full_prec(&BigFloat::from_u8(1, precision)),
-                )
-                .int()
-                .convert_to_radix(
-                    Radix::Dec,
-                    RoundingMode::None,
-                    &mut Consts::new().expect("Constants cache initialization should not fail."),
-                )?
-                .1
-                .iter()
-                .map(|digit| digit.to_string())
-                .collect::<String>();
-            if input_as_string.is_empty() {
-                BigUint::zero()
-            } else {
-                BigUint::from_str(&input_as_string).expect("This parse should not fail.")
+            // input data from trusted environment
+            let input_value = input_value::<INPUT_BYTES>(&record.average_energy)?;
+            let (lower_bound_time, input_value_time) =
+                TimeWindow::from_counter::<TIME_BYTES>(record.day.into())?;
+            let time_bounds = (lower_bound_time, input_value_time);
+
+            let mut input_value_bytes = [0; INPUT_BYTES];
+            for (idx, byte) in input_value.to_bytes_le().iter().enumerate() {
+                input_value_bytes[idx] = *byte;
             }
-        };
 
-        let mut input_value_time = [0; TIME_BYTES];
-        input_value_time[0] = record.day + 1;
-        let mut lower_bound_time = [0; TIME_BYTES];
-        lower_bound_time[0] = record.day;
-        let time_bounds = (lower_bound_time, input_value_time.clone());
+            let message_bytes = InputValueSignatureInput {
+                input_value: input_value_bytes,
+                input_value_time,
+            }
+            .to_bytes();
 
-        let mut input_value_bytes = [0; INPUT_BYTES];
-        for (idx, byte) in input_value.to_bytes_le().iter().enumerate() {
-            input_value_bytes[idx] = *byte;
-        }
+            let input_value_signature = Conf::ClientSignatureScheme::sign(
+                &parameters.client_signature_scheme,
+                &client_secret_keys[record.household as usize],
+                &message_bytes,
+                &mut trusted_environment_rng,
+            )?;
 
-        let mut message_bytes = input_value_bytes.to_vec();
-        message_bytes.extend_from_slice(&input_value_time);
+            current_record.set(Some((time_bounds, record.day as usize)));
+            Ok(RandomizationRecord {
+                client_sig_pk: client_public_keys[record.household as usize].clone(),
+                time_bounds,
+                input_value_time,
+                input_value,
+                input_value_signature,
+                index: record.day as usize,
+            })
+        },
+    );
 
-        let input_value_signature = Conf::ClientSignatureScheme::sign(
-            &parameters.client_signature_scheme,
-            &client_secret_key,
-            &message_bytes,
-            &mut rng,
-        )?;
+    // a single client is reused for all households
+    let client = ClientExpand::<
+        _,
+        MT_DEPTH,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >::new(
+        parameters.clone(),
+        server_sig_pk,
+        client_public_keys[0].clone(),
+        proving_key,
+    )?;
+    let messages = ClientExpandStream::<
+        _,
+        ConfG,
+        _,
+        _,
+        _,
+        MT_DEPTH,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >::new(
+        client,
+        records,
+        |gen_rand_client_message: &[u8]| {
+            server.generate_randomness_create(gen_rand_client_message, &mut server_rng)
+        },
+        prf_eval_points.clone(),
+        &mut rng,
+        &mut zkp_rng,
+    )
+    .with_skip_proof(true);
 
-        // verifiable randomizations
-        let ver_rand_client_message = client.verifiable_randomization_create::<ConfG>(
-            time_bounds,
-            input_value_time,
-            input_value.clone(),
-            input_value_signature,
-            &prf_eval_points,
-            record.day as usize,
-            &mut zkp_rng,
-            true,
-        )?;
+    let mut aggregator = Aggregator::new();
+    for ver_rand_client_message in messages {
+        let ver_rand_client_message = ver_rand_client_message?;
+        let (time_bounds, index) = current_record
+            .take()
+            .expect("every message belongs to a record");
         let result = server.verifiable_randomization_verify::<ConfG>(
             &ver_rand_client_message,
             time_bounds,
             &prf_eval_points,
-            record.day as usize,
-            &mut zkp_rng,
+            index,
+            &mut server_zkp_rng,
             true,
         )?;
         assert!(
             result.0,
             "Verification of verifiable randomization protocol failed."
         );
-        ldp_values.push(result.1);
+        aggregator.add_timed(&time_bounds.0, result.1);
+    }
 
-        if record.household == HOUSEHOLDS - 1 {
-            let max_value = BigFloat::from_f64(MAX_VALUE, 100);
-            let sample_sum = ldp_values.iter().sum::<u64>() as f64;
-            let estimate = BigFloat::from_f64(sample_sum / K as f64, 100)
-                .sub(
-                    &gamma.mul(
-                        &BigFloat::from_f64(HOUSEHOLDS as f64 / 2.0, 100),
-                        100,
-                        RoundingMode::None,
-                    ),
-                    100,
-                    RoundingMode::None,
-                )
-                .div(
-                    &(BigFloat::from_u8(1, 100).sub(&gamma, 100, RoundingMode::None)),
-                    100,
-                    RoundingMode::None,
-                )
-                .div(
-                    &BigFloat::from_f64(HOUSEHOLDS as f64, 100),
-                    100,
-                    RoundingMode::None,
-                );
-            let estimate = estimate.mul(&max_value, 100, RoundingMode::None);
-            println!("Day {}:", record.day);
-            println!("Estimate: {estimate}");
-            ldp_values.clear();
-        }
+    let max_value = BigFloat::from_f64(MAX_VALUE, 100);
+    let gamma_bytes = parameters.gamma_as_bytes()?;
+    for window in aggregator.windows() {
+        let estimate = aggregator
+            .estimate_for_window::<K>(&window, &gamma_bytes)
+            .expect("every window has a value")
+            .mul(&max_value, 100, RoundingMode::None);
+        println!("Day {}:", BigUint::from_bytes_le(&window));
+        println!("Estimate: {estimate}");
     }
     Ok(())
 }
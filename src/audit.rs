@@ -0,0 +1,260 @@
+//! Offline tools for auditing a deployment of the Expand VLDP scheme, based on seeds that clients
+//! voluntarily reveal for this purpose. These are never needed for running the protocol itself.
+
+use crate::client::leaf_client_randomness;
+use crate::prelude::*;
+
+/// Seeds that a client of the Expand scheme reveals for auditing: its own PRF seed (from which the
+/// leaves of its Merkle tree are derived) and the server seed it obtained in the `GenRand` step.
+#[derive(Clone)]
+pub struct RevealedSeeds<Conf: Config> {
+    pub client_seed: PRFSchemeSeed<Conf>,
+    pub server_seed: PRFSchemeSeed<Conf>,
+}
+
+/// Reconstruct the randomness that an Expand client used in its `index`-th `Randomize` step, given
+/// its revealed seeds and the public `prf_eval_points` of that step.
+pub fn reconstruct_randomness<Conf: Config, const RANDOMNESS_BYTES: usize>(
+    seeds: &RevealedSeeds<Conf>,
+    prf_eval_points: &[PRFSchemeInput<Conf>],
+    index: usize,
+) -> Result<[u8; RANDOMNESS_BYTES], Error> {
+    // client randomness of the leaf at `index`
+    let mut randomness =
+        leaf_client_randomness::<Conf, RANDOMNESS_BYTES>(&seeds.client_seed, index)?;
+
+    // combine with the server randomness
+    for (chunk, prf_eval_point) in randomness
+        .chunks_mut(Conf::PRF_OUTPUT_BYTES)
+        .zip(prf_eval_points)
+    {
+        chunk
+            .iter_mut()
+            .zip(Conf::PRFScheme::evaluate(&seeds.server_seed, prf_eval_point)?.as_ref())
+            .for_each(|(client_byte, server_byte)| *client_byte ^= *server_byte);
+    }
+    Ok(randomness)
+}
+
+/// Result of comparing the randomness of two clients.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndependenceReport {
+    /// Both clients used the same client seed.
+    pub shared_client_seed: bool,
+    /// Both clients obtained the same server seed.
+    pub shared_server_seed: bool,
+    /// Indices of the `Randomize` steps in which both clients used identical randomness.
+    pub identical_indices: Vec<usize>,
+    /// Correlation between the bits of both clients' randomness, in `[-1, 1]`. This is close to
+    /// zero for independent randomness.
+    pub correlation: f64,
+    /// Number of bits the correlation was computed over.
+    pub compared_bits: u64,
+}
+
+impl IndependenceReport {
+    /// Whether no shared seed material, no identical randomness, and no correlation beyond four
+    /// standard deviations of that of independent uniform bits (i.e., `4 / sqrt(compared_bits)`)
+    /// was found.
+    pub fn is_independent(&self) -> bool {
+        !self.shared_client_seed
+            && !self.shared_server_seed
+            && self.identical_indices.is_empty()
+            && self.correlation.abs() <= 4.0 / (self.compared_bits as f64).sqrt()
+    }
+}
+
+/// Check whether two Expand clients used independent randomness in their first `num_steps`
+/// `Randomize` steps. Entry `j` of `prf_eval_points` contains the public PRF evaluation points of
+/// step `j`.
+pub fn audit_randomness_independence<Conf: Config, const RANDOMNESS_BYTES: usize>(
+    first: &RevealedSeeds<Conf>,
+    second: &RevealedSeeds<Conf>,
+    prf_eval_points: &[Vec<PRFSchemeInput<Conf>>],
+    num_steps: usize,
+) -> Result<IndependenceReport, Error> {
+    let mut identical_indices = vec![];
+    let mut matching_bits = 0;
+    let mut compared_bits = 0;
+    for (index, eval_points) in prf_eval_points.iter().enumerate().take(num_steps) {
+        let first_randomness =
+            reconstruct_randomness::<Conf, RANDOMNESS_BYTES>(first, eval_points, index)?;
+        let second_randomness =
+            reconstruct_randomness::<Conf, RANDOMNESS_BYTES>(second, eval_points, index)?;
+        if first_randomness == second_randomness {
+            identical_indices.push(index);
+        }
+        matching_bits += first_randomness
+            .iter()
+            .zip(second_randomness)
+            .map(|(first_byte, second_byte)| (!(first_byte ^ second_byte)).count_ones() as u64)
+            .sum::<u64>();
+        compared_bits += RANDOMNESS_BYTES as u64 * 8;
+    }
+    Ok(IndependenceReport {
+        shared_client_seed: first.client_seed == second.client_seed,
+        shared_server_seed: first.server_seed == second.server_seed,
+        identical_indices,
+        correlation: 2.0 * matching_bits as f64 / compared_bits as f64 - 1.0,
+        compared_bits,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::circuits::CircuitExpand;
+    use crate::client::ClientExpand;
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::messages::expand::VerifiableRandomizationMessageExpand;
+    use crate::messages::{derive_prf_eval_points, InputValueSignatureInput};
+    use crate::primitives::ldp::apply_ldp;
+    use crate::server::ServerExpand;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_serialize::CanonicalDeserialize;
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+
+    #[test]
+    fn reconstructed_randomness_matches_the_client() {
+        let rng = &mut ChaChaRng::from_seed([17; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([18; 32]);
+        let parameters =
+            ParametersExpand::<Conf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitExpand::<Conf, ConfG, 3, 2, 1, 2, 4, 4, false>::keygen(
+                parameters.clone(),
+                zkp_rng,
+            )
+            .unwrap();
+        let server = ServerExpand::<Conf, 3, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            verifying_key,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientExpand::<Conf, 3, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let prf_eval_points = (0..4)
+            .map(|_| derive_prf_eval_points::<Conf>(rng.gen(), 1))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        // the randomness of every leaf of the client's tree leads to the LDP value it submitted
+        let seeds = client.reveal_seeds().unwrap();
+        let gamma_bytes = parameters.gamma_as_bytes().unwrap();
+        for (index, eval_points) in prf_eval_points.iter().enumerate() {
+            // prove one step past the first leaf, whose commitment randomness is drawn later
+            let skip_proof = index != 1;
+            let submission = client
+                .verifiable_randomization_create::<ConfG>(
+                    ([0], [1]),
+                    [1],
+                    BigUint::from(3_u8),
+                    input_value_signature.clone(),
+                    eval_points,
+                    index,
+                    zkp_rng,
+                    skip_proof,
+                )
+                .unwrap();
+            let message = VerifiableRandomizationMessageExpand::<Conf, 2>::deserialize_compressed(
+                submission.as_slice(),
+            )
+            .unwrap();
+            let randomness = reconstruct_randomness::<Conf, 4>(&seeds, eval_points, index).unwrap();
+            assert_eq!(
+                apply_ldp::<2, 2, 4, false>(&BigUint::from(3_u8), &randomness, &gamma_bytes),
+                message.ldp_value
+            );
+            if !skip_proof {
+                assert!(
+                    server
+                        .verifiable_randomization_verify::<ConfG>(
+                            &submission,
+                            ([0], [1]),
+                            eval_points,
+                            index,
+                            zkp_rng,
+                            false
+                        )
+                        .unwrap()
+                        .0
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn identical_seeds_are_flagged_as_correlated() {
+        let rng = &mut ChaChaRng::from_seed([16; 32]);
+        let prf_eval_points = (0..8)
+            .map(|_| derive_prf_eval_points::<Conf>(rng.gen(), 2))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let mut seeds = || RevealedSeeds::<Conf> {
+            client_seed: rng.gen(),
+            server_seed: rng.gen(),
+        };
+        let first = seeds();
+        let second = seeds();
+
+        let report =
+            audit_randomness_independence::<Conf, 64>(&first, &second, &prf_eval_points, 8)
+                .unwrap();
+        assert!(report.is_independent(), "{:?}", report);
+        assert_eq!(report.compared_bits, 8 * 64 * 8);
+
+        let report =
+            audit_randomness_independence::<Conf, 64>(&first, &first, &prf_eval_points, 8).unwrap();
+        assert!(!report.is_independent());
+        assert!(report.shared_client_seed && report.shared_server_seed);
+        assert_eq!(report.identical_indices, (0..8).collect::<Vec<_>>());
+        assert_eq!(report.correlation, 1.0);
+
+        // a reused client seed is flagged, even though the randomness differs
+        let reused = RevealedSeeds::<Conf> {
+            client_seed: first.client_seed,
+            server_seed: second.server_seed,
+        };
+        let report =
+            audit_randomness_independence::<Conf, 64>(&first, &reused, &prf_eval_points, 8)
+                .unwrap();
+        assert!(!report.is_independent());
+        assert!(report.identical_indices.is_empty());
+    }
+}
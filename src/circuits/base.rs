@@ -1,17 +1,31 @@
 //! Definitions of the R1CS ZKP circuits for the Base VLDP scheme.
 
+use super::{
+    constraint_stats, real_input_rounding_var, signed_input_value_message_var, CircuitStats,
+    Relation, Relations,
+};
 use crate::client::ClientBaseStorage;
 use crate::prelude::{constraints::*, *};
+use crate::primitives::ldp::{
+    boundary_gap, ldp_randomness_bytes, randomized_ldp_value, randomness_limit,
+};
 use ark_ff::PrimeField;
 use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::ToConstraintFieldGadget;
-use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
-use ark_std::{One, Zero};
-use num_bigint::BigUint;
-use std::cmp::{min, Ordering};
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError,
+};
+use ark_serialize::CanonicalDeserialize;
+use ark_std::One;
+use std::cmp::Ordering;
 use std::marker::PhantomData;
 
 /// Struct for R1CS constraint generation for the Base scheme.
+///
+/// The circuit randomizes a vector of `D` input values (a single one by default) under one proof:
+/// value `i` uses the `i`-th chunk of `ldp_randomness_bytes` bytes of the randomness, while the
+/// signature (over the concatenation of all values) and the commitment are checked once. For
+/// `D = 1`, this is exactly the circuit for a single input value.
 #[derive(Clone)]
 pub struct CircuitBase<
     Conf: Config,
@@ -22,6 +36,7 @@ pub struct CircuitBase<
     const RANDOMNESS_BYTES: usize,
     const K: u64,
     const IS_REAL_INPUT: bool,
+    const D: usize = 1,
 > {
     #[doc(hidden)]
     _config_gadget: PhantomData<ConfG>,
@@ -29,20 +44,40 @@ pub struct CircuitBase<
     // parameters
     params: ParametersBase<Conf, GAMMA_BYTES>,
     // public inputs
-    ldp_value: Option<u64>,
+    ldp_value: Option<[u64; D]>,
     time_bounds: Option<([u8; TIME_BYTES], [u8; TIME_BYTES])>,
     client_sig_pk: Option<ClientSignatureSchemePublicKey<Conf>>,
     client_randomness_commitment: Option<ClientCommitmentSchemeOutput<Conf>>,
     server_randomness: Option<[u8; RANDOMNESS_BYTES]>,
 
     // private witnesses
-    true_value: Option<[u8; INPUT_BYTES]>,
+    true_value: Option<[[u8; INPUT_BYTES]; D]>,
     time: Option<[u8; TIME_BYTES]>,
     true_value_signature: Option<ClientSignatureSchemeSignature<Conf>>,
     client_randomness: Option<[u8; RANDOMNESS_BYTES]>,
     client_randomness_commitment_randomness: Option<ClientCommitmentSchemeRandomness<Conf>>,
 }
 
+/// Values from which the client proves a single `Randomize` step with the Base circuit (see
+/// `CircuitBase::first_failing_relation`). The client randomness and its commitment are taken from
+/// the client storage.
+#[derive(Clone)]
+pub struct WitnessBase<
+    Conf: Config,
+    const INPUT_BYTES: usize,
+    const TIME_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+    const D: usize = 1,
+> {
+    pub ldp_value: [u64; D],
+    pub time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+    pub true_value: [[u8; INPUT_BYTES]; D],
+    pub time: [u8; TIME_BYTES],
+    pub true_value_signature: ClientSignatureSchemeSignature<Conf>,
+    pub client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
+    pub server_randomness: [u8; RANDOMNESS_BYTES],
+}
+
 impl<
         Conf: Config,
         ConfG: ConfigGadget<Conf>,
@@ -52,6 +87,7 @@ impl<
         const RANDOMNESS_BYTES: usize,
         const K: u64,
         const IS_REAL_INPUT: bool,
+        const D: usize,
     >
     CircuitBase<
         Conf,
@@ -62,13 +98,12 @@ impl<
         RANDOMNESS_BYTES,
         K,
         IS_REAL_INPUT,
+        D,
     >
 {
-    pub fn keygen(
-        params: ParametersBase<Conf, GAMMA_BYTES>,
-        zkp_rng: &mut ZKPRng<Conf>,
-    ) -> Result<(ProvingKey<Conf>, VerifyingKey<Conf>), Error> {
-        let circuit = Self {
+    /// Circuit without any inputs, as needed for key generation and other precomputation.
+    fn without_witness(params: ParametersBase<Conf, GAMMA_BYTES>) -> Self {
+        Self {
             _config_gadget: PhantomData,
             params,
             ldp_value: None,
@@ -81,16 +116,38 @@ impl<
             true_value_signature: None,
             client_randomness: None,
             client_randomness_commitment_randomness: None,
-        };
-        Conf::ZKPScheme::keygen(circuit, zkp_rng)
+        }
+    }
+
+    pub fn keygen(
+        params: ParametersBase<Conf, GAMMA_BYTES>,
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<(ProvingKey<Conf>, VerifyingKey<Conf>), Error> {
+        Conf::ZKPScheme::keygen(Self::without_witness(params), zkp_rng)
+    }
+
+    /// Precompute the prover context of the circuit for the given parameters, which
+    /// `prove_with_context` reuses for every proof (see `ProofSystem::prover_context`).
+    pub fn prover_context(
+        params: ParametersBase<Conf, GAMMA_BYTES>,
+    ) -> Result<ProverContext<Conf>, Error> {
+        Conf::ZKPScheme::prover_context(Self::without_witness(params))
+    }
+
+    /// Count the constraints and variables of the circuit for the given parameters, without
+    /// generating any keys.
+    pub fn constraint_stats(
+        params: ParametersBase<Conf, GAMMA_BYTES>,
+    ) -> Result<CircuitStats, Error> {
+        constraint_stats(Self::without_witness(params))
     }
 
     pub fn prove(
         proving_key: &ProvingKey<Conf>,
         params: ParametersBase<Conf, GAMMA_BYTES>,
-        ldp_value: u64,
+        ldp_value: [u64; D],
         time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
-        true_value: [u8; INPUT_BYTES],
+        true_value: [[u8; INPUT_BYTES]; D],
         time: [u8; TIME_BYTES],
         true_value_signature: ClientSignatureSchemeSignature<Conf>,
         client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
@@ -98,34 +155,108 @@ impl<
         client_storage: ClientBaseStorage<Conf, RANDOMNESS_BYTES>,
         zkp_rng: &mut ZKPRng<Conf>,
     ) -> Result<Proof<Conf>, Error> {
-        let circuit = Self {
+        let witness = WitnessBase {
+            ldp_value,
+            time_bounds,
+            true_value,
+            time,
+            true_value_signature,
+            client_sig_pk,
+            server_randomness,
+        };
+        let circuit = Self::with_witness(params, witness, client_storage);
+        Conf::ZKPScheme::prove(proving_key, circuit, zkp_rng)
+    }
+
+    /// Create a proof like `prove`, but with the given proof randomness (see
+    /// `ProofSystem::prove_with_randomness`), such that the proof can be reproduced exactly.
+    pub fn prove_with_randomness(
+        proving_key: &ProvingKey<Conf>,
+        params: ParametersBase<Conf, GAMMA_BYTES>,
+        witness: WitnessBase<Conf, INPUT_BYTES, TIME_BYTES, RANDOMNESS_BYTES, D>,
+        client_storage: ClientBaseStorage<Conf, RANDOMNESS_BYTES>,
+        proof_randomness: &ProofRandomness<Conf>,
+    ) -> Result<Proof<Conf>, Error> {
+        let circuit = Self::with_witness(params, witness, client_storage);
+        Conf::ZKPScheme::prove_with_randomness(proving_key, circuit, proof_randomness)
+    }
+
+    /// Create a proof like `prove_with_randomness`, but reuse the `prover_context` of the circuit.
+    pub fn prove_with_context(
+        proving_key: &ProvingKey<Conf>,
+        prover_context: &ProverContext<Conf>,
+        params: ParametersBase<Conf, GAMMA_BYTES>,
+        witness: WitnessBase<Conf, INPUT_BYTES, TIME_BYTES, RANDOMNESS_BYTES, D>,
+        client_storage: ClientBaseStorage<Conf, RANDOMNESS_BYTES>,
+        proof_randomness: &ProofRandomness<Conf>,
+    ) -> Result<Proof<Conf>, Error> {
+        let circuit = Self::with_witness(params, witness, client_storage);
+        Conf::ZKPScheme::prove_with_context(proving_key, prover_context, circuit, proof_randomness)
+    }
+
+    /// Reference verifier for diagnostics: instead of creating a proof for the given inputs,
+    /// synthesize the circuit natively and return the first relation that does not hold (in the
+    /// order of the constraints of the circuit), if any. A proof for these inputs is accepted if
+    /// and only if this returns `None`.
+    ///
+    /// As the proof itself cannot reveal which relation failed, this needs the witness of the
+    /// client, e.g., when debugging a rejected submission in a test deployment.
+    pub fn first_failing_relation(
+        params: ParametersBase<Conf, GAMMA_BYTES>,
+        witness: WitnessBase<Conf, INPUT_BYTES, TIME_BYTES, RANDOMNESS_BYTES, D>,
+        client_storage: ClientBaseStorage<Conf, RANDOMNESS_BYTES>,
+    ) -> Result<Option<Relation>, Error> {
+        let circuit = Self::with_witness(params, witness, client_storage);
+        let cs = ConstraintSystem::<ConstraintField<Conf>>::new_ref();
+        for (relation, holds) in circuit.relations(cs.clone())? {
+            if !holds.value()? {
+                return Ok(Some(relation));
+            }
+        }
+        // the only constraints that are enforced directly are those that compute the LDP value
+        if cs.is_satisfied()? {
+            Ok(None)
+        } else {
+            Ok(Some(Relation::LdpValue))
+        }
+    }
+
+    pub(crate) fn with_witness(
+        params: ParametersBase<Conf, GAMMA_BYTES>,
+        witness: WitnessBase<Conf, INPUT_BYTES, TIME_BYTES, RANDOMNESS_BYTES, D>,
+        client_storage: ClientBaseStorage<Conf, RANDOMNESS_BYTES>,
+    ) -> Self {
+        Self {
             _config_gadget: PhantomData,
             params,
-            ldp_value: Some(ldp_value),
-            time_bounds: Some(time_bounds),
-            client_sig_pk: Some(client_sig_pk),
+            ldp_value: Some(witness.ldp_value),
+            time_bounds: Some(witness.time_bounds),
+            client_sig_pk: Some(witness.client_sig_pk),
             client_randomness_commitment: client_storage.client_randomness_commitment,
-            server_randomness: Some(server_randomness),
-            true_value: Some(true_value),
-            time: Some(time),
-            true_value_signature: Some(true_value_signature),
+            server_randomness: Some(witness.server_randomness),
+            true_value: Some(witness.true_value),
+            time: Some(witness.time),
+            true_value_signature: Some(witness.true_value_signature),
             client_randomness: client_storage.client_randomness,
             client_randomness_commitment_randomness: client_storage
                 .client_randomness_commitment_randomness,
-        };
-        Conf::ZKPScheme::prove(proving_key, circuit, zkp_rng)
+        }
     }
 
-    pub fn verify(
-        verifying_key: &VerifyingKey<Conf>,
-        proof: &Proof<Conf>,
-        ldp_value: u64,
+    /// Public inputs of the Base circuit, in the order in which they are allocated: the client's
+    /// `gamma` (only if gamma is public, see `ParametersBase::with_public_gamma`), all `D` values
+    /// of `ldp_value` (each as 8 little-endian bytes), both `time_bounds`, `client_sig_pk`,
+    /// `client_randomness_commitment`, and `server_randomness`. Byte arrays are packed into field
+    /// elements as done by their `ToConstraintField` implementation, except for `gamma`, which is
+    /// a single field element.
+    pub fn public_inputs(
+        gamma: Option<&[u8; GAMMA_BYTES]>,
+        ldp_value: [u64; D],
         time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
         client_sig_pk: &ClientSignatureSchemePublicKey<Conf>,
         client_randomness_commitment: ClientCommitmentSchemeOutput<Conf>,
         server_randomness: [u8; RANDOMNESS_BYTES],
-        zkp_rng: &mut ZKPRng<Conf>,
-    ) -> Result<bool, Error>
+    ) -> Result<Vec<ConstraintField<Conf>>, Error>
     where
         ClientSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
         ClientCommitmentSchemeOutput<Conf>: ToConstraintField<ConstraintField<Conf>>,
@@ -133,12 +264,18 @@ impl<
         // convert inputs into correct format for proof verification
         let mut public_inputs = Vec::new();
 
-        public_inputs.extend_from_slice(
-            &ldp_value
-                .to_le_bytes()
-                .to_field_elements()
-                .ok_or(GenericError::ConversionError)?,
-        );
+        if let Some(gamma) = gamma {
+            public_inputs.push(ConstraintField::<Conf>::from_le_bytes_mod_order(gamma));
+        }
+
+        for ldp_value in ldp_value {
+            public_inputs.extend_from_slice(
+                &ldp_value
+                    .to_le_bytes()
+                    .to_field_elements()
+                    .ok_or(GenericError::ConversionError)?,
+            );
+        }
         public_inputs.extend_from_slice(
             &time_bounds
                 .0
@@ -167,35 +304,67 @@ impl<
                 .ok_or(GenericError::ConversionError)?,
         );
 
+        Ok(public_inputs)
+    }
+
+    pub fn verify(
+        verifying_key: &VerifyingKey<Conf>,
+        proof: &Proof<Conf>,
+        gamma: Option<&[u8; GAMMA_BYTES]>,
+        ldp_value: [u64; D],
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        client_sig_pk: &ClientSignatureSchemePublicKey<Conf>,
+        client_randomness_commitment: ClientCommitmentSchemeOutput<Conf>,
+        server_randomness: [u8; RANDOMNESS_BYTES],
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<bool, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        ClientCommitmentSchemeOutput<Conf>: ToConstraintField<ConstraintField<Conf>>,
+    {
+        let public_inputs = Self::public_inputs(
+            gamma,
+            ldp_value,
+            time_bounds,
+            client_sig_pk,
+            client_randomness_commitment,
+            server_randomness,
+        )?;
         Conf::ZKPScheme::verify(verifying_key, &public_inputs, proof, zkp_rng)
     }
-}
 
-impl<
-        Conf: Config,
-        ConfG: ConfigGadget<Conf>,
-        const INPUT_BYTES: usize,
-        const TIME_BYTES: usize,
-        const GAMMA_BYTES: usize,
-        const RANDOMNESS_BYTES: usize,
-        const K: u64,
-        const IS_REAL_INPUT: bool,
-    > ConstraintSynthesizer<ConstraintField<Conf>>
-    for CircuitBase<
-        Conf,
-        ConfG,
-        INPUT_BYTES,
-        TIME_BYTES,
-        GAMMA_BYTES,
-        RANDOMNESS_BYTES,
-        K,
-        IS_REAL_INPUT,
-    >
-{
-    fn generate_constraints(
+    /// Verify a proof like `verify`, but with the proof and the public inputs given as bytes, e.g.,
+    /// as kept by a verifier service that stores submissions and verifies them later. Both use the
+    /// compressed arkworks serialization (`CanonicalSerialize`):
+    /// - `proof_bytes`: the `Proof<Conf>` (for Groth16: the points A, B, and C, each compressed)
+    /// - `public_input_bytes`: the vector returned by `public_inputs`, i.e., the number of field
+    ///   elements as a little-endian `u64`, followed by every field element as a little-endian
+    ///   integer of `ConstraintField::<Conf>::MODULUS_BIT_SIZE` bits rounded up to full 8-byte
+    ///   limbs (32 bytes for the scalar field of BLS12-381)
+    ///
+    /// Buffers with bytes left over after the serialization are rejected with a `ParseError`, so
+    /// every stored submission has exactly one encoding.
+    pub fn verify_raw(
+        verifying_key: &VerifyingKey<Conf>,
+        proof_bytes: &[u8],
+        public_input_bytes: &[u8],
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<bool, Error>
+    where
+        Proof<Conf>: CanonicalDeserialize,
+    {
+        let proof = deserialize_exact::<Proof<Conf>>(proof_bytes, "proof")?;
+        let public_inputs =
+            deserialize_exact::<Vec<ConstraintField<Conf>>>(public_input_bytes, "public inputs")?;
+        Conf::ZKPScheme::verify(verifying_key, &public_inputs, &proof, zkp_rng)
+    }
+
+    /// Allocate all variables and constraints of the circuit, except for the final check that all
+    /// `=?=` relations hold. These are returned instead, labeled by the relation they belong to.
+    fn relations(
         self,
         cs: ConstraintSystemRef<ConstraintField<Conf>>,
-    ) -> ark_relations::r1cs::Result<()> {
+    ) -> Result<Relations<ConstraintField<Conf>>, SynthesisError> {
         // --- SANITY CHECKS ---
         if !cs.is_in_setup_mode()
             && (self.ldp_value.is_none()
@@ -208,15 +377,20 @@ impl<
         }
 
         // --- ALLOCATE VARIABLES ---
-        // allocate constants
+        // allocate constants (and gamma as public input, if it is public)
         let params = ParametersBaseVar::<_, ConfG>::new_constant(cs.clone(), &self.params)?;
+        let signed_input = self.params.signed_input;
 
         // allocate public inputs
-        let ldp_value = FpVar::new_input(cs.clone(), || {
-            self.ldp_value
-                .map(|x| ConstraintField::<Conf>::from(x))
-                .ok_or(SynthesisError::AssignmentMissing)
-        })?;
+        let ldp_value = (0..D)
+            .map(|index| {
+                FpVar::new_input(cs.clone(), || {
+                    self.ldp_value
+                        .map(|x| ConstraintField::<Conf>::from(x[index]))
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
         let time_lower_bound = FpVar::new_input(cs.clone(), || {
             self.time_bounds
                 .as_ref()
@@ -244,11 +418,15 @@ impl<
         )?;
 
         // allocate witnesses
-        let true_value = FpVar::new_witness(cs.clone(), || {
-            self.true_value
-                .map(|x| ConstraintField::<Conf>::from_le_bytes_mod_order(&x))
-                .ok_or(SynthesisError::AssignmentMissing)
-        })?;
+        let true_value = (0..D)
+            .map(|index| {
+                FpVar::new_witness(cs.clone(), || {
+                    self.true_value
+                        .map(|x| ConstraintField::<Conf>::from_le_bytes_mod_order(&x[index]))
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
         let time = FpVar::new_witness(cs.clone(), || {
             self.time
                 .map(|x| ConstraintField::<Conf>::from_le_bytes_mod_order(&x))
@@ -276,137 +454,114 @@ impl<
             .map(|(client_byte, server_byte)| client_byte.xor(server_byte))
             .collect::<Result<Vec<_>, _>>()?;
 
-        // 2: ldp_value = LDP.Apply(true_value, randomness)
+        // 2: ldp_value[i] = LDP.Apply(true_value[i], randomness[i]) for every dimension i
         let k = FpVar::new_constant(cs.clone(), ConstraintField::<Conf>::from(K))?;
+        // exclusive upper bound of the random values (see `bin_boundaries`)
         let max_bound = FpVar::new_constant(
             cs.clone(),
-            ConstraintField::<Conf>::from_le_bytes_mod_order(&[u8::MAX; INPUT_BYTES]),
+            ConstraintField::<Conf>::from_le_bytes_mod_order(
+                &randomness_limit::<INPUT_BYTES>().to_bytes_le(),
+            ),
         )?;
         let boundary_gap = ConstraintField::<Conf>::from_le_bytes_mod_order(
-            &if IS_REAL_INPUT {
-                BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / (K + 1)
-            } else {
-                BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / K
-            }
-            .to_bytes_le(),
+            &boundary_gap::<INPUT_BYTES, K, IS_REAL_INPUT>().to_bytes_le(),
         );
-        let computed_ldp_value = FpVar::new_witness(cs.clone(), || {
-            Ok(ConstraintField::<Conf>::from({
-                let mut randomness = self.server_randomness.unwrap();
-                randomness
-                    .iter_mut()
-                    .zip(self.client_randomness.unwrap())
-                    .for_each(|(client_byte, server_byte)| *client_byte ^= server_byte);
-                let boundary_gap = if IS_REAL_INPUT {
-                    BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / (K + 1)
-                } else {
-                    BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / K
-                };
-                let computed_ldp_value =
-                    BigUint::from_bytes_le(&randomness[GAMMA_BYTES..GAMMA_BYTES + INPUT_BYTES])
-                        / boundary_gap;
-                let computed_ldp_value = if computed_ldp_value.is_zero() {
-                    0
-                } else {
-                    computed_ldp_value.to_u64_digits()[0]
-                };
-                if IS_REAL_INPUT {
-                    min(computed_ldp_value, K)
-                } else {
-                    min(computed_ldp_value, K - 1) + 1
+        // for signed input, the (signed) true value is in two's complement, and LDP is applied to
+        // it shifted by `signed_input_offset`, i.e., with its most significant bit flipped
+        let ldp_input_value = true_value
+            .iter()
+            .map(|true_value| {
+                if !signed_input {
+                    return Ok(true_value.clone());
                 }
-            }))
-        })?;
+                let mut bytes = true_value.to_bytes()?[0..INPUT_BYTES].to_vec();
+                bytes[INPUT_BYTES - 1] = bytes[INPUT_BYTES - 1].xor(&UInt8::constant(0x80))?;
+                Ok(bytes.to_constraint_field()?[0].clone())
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+        let mut ldp_value_checks = Vec::with_capacity(2 * D);
+        for (index, (ldp_value, true_value)) in ldp_value.iter().zip(&ldp_input_value).enumerate() {
+            let offset = index * ldp_randomness_bytes::<INPUT_BYTES, GAMMA_BYTES, IS_REAL_INPUT>();
+            let randomness = &randomness[offset..];
 
-        let randomness_fp =
-            &randomness[GAMMA_BYTES..GAMMA_BYTES + INPUT_BYTES].to_constraint_field()?[0];
-        let lower_bound = if IS_REAL_INPUT {
-            computed_ldp_value.clone() * boundary_gap
-        } else {
-            (computed_ldp_value.clone() - ConstraintField::<Conf>::one()) * boundary_gap
-        };
-        let computed_upper_bound = if IS_REAL_INPUT {
-            (computed_ldp_value.clone() + ConstraintField::<Conf>::one()) * boundary_gap
-        } else {
-            computed_ldp_value.clone() * boundary_gap
-        };
+            let computed_ldp_value = FpVar::new_witness(cs.clone(), || {
+                Ok(ConstraintField::<Conf>::from({
+                    let mut randomness = self.server_randomness.unwrap();
+                    randomness
+                        .iter_mut()
+                        .zip(self.client_randomness.unwrap())
+                        .for_each(|(client_byte, server_byte)| *client_byte ^= server_byte);
+                    randomized_ldp_value::<INPUT_BYTES, K, IS_REAL_INPUT>(
+                        &randomness[offset + GAMMA_BYTES..offset + GAMMA_BYTES + INPUT_BYTES],
+                    )
+                }))
+            })?;
 
-        // adjust the upper bound in case ldp_value == k;
-        let ldp_equal_to_k = k.is_eq(&computed_ldp_value)?;
-        let upper_bound = FpVar::new_witness(cs.clone(), || {
-            if ldp_equal_to_k.value().unwrap() {
-                max_bound.value()
+            let randomness_fp =
+                &randomness[GAMMA_BYTES..GAMMA_BYTES + INPUT_BYTES].to_constraint_field()?[0];
+            let lower_bound = if IS_REAL_INPUT {
+                computed_ldp_value.clone() * boundary_gap
             } else {
-                computed_upper_bound.value()
-            }
-        })?;
-        upper_bound.conditional_enforce_equal(&max_bound, &ldp_equal_to_k)?;
-        upper_bound.conditional_enforce_equal(&computed_upper_bound, &ldp_equal_to_k.not())?;
-        // randomness >= lower_bound
-        let lower_bound_check =
-            randomness_fp.is_cmp_unchecked(&lower_bound, Ordering::Greater, true)?;
-        // randomness < upper_bound
-        let upper_bound_check =
-            randomness_fp.is_cmp_unchecked(&upper_bound, Ordering::Less, false)?;
-
-        let ldp_bit = params.gamma.compute_ldp_bit(&randomness[0..GAMMA_BYTES])?;
-
-        // cast true_value if is_real_input
-        let true_value_computed = if IS_REAL_INPUT {
-            let true_value_times_k = &true_value * k;
-            let multiplicand = FpVar::new_witness(cs.clone(), || {
-                Ok(ConstraintField::<Conf>::from_le_bytes_mod_order(
-                    &(BigUint::from_bytes_le(&self.true_value.unwrap()) * K
-                        / BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]))
-                    .to_bytes_le(),
-                ))
-            })?;
-            let remainder = FpVar::new_witness(cs.clone(), || {
-                Ok(true_value_times_k.value().unwrap()
-                    - multiplicand.value().unwrap()
-                        * ConstraintField::<Conf>::from_le_bytes_mod_order(&[u8::MAX; INPUT_BYTES]))
-            })?;
-            let true_value_randomness = Boolean::le_bits_to_fp_var(
-                &randomness[GAMMA_BYTES + INPUT_BYTES..GAMMA_BYTES + 2 * INPUT_BYTES]
-                    .to_bits_le()?,
-            )?;
-
-            // true_value_randomness <= remainder
-            let true_value_random_bit =
-                remainder.is_cmp_unchecked(&true_value_randomness, Ordering::Greater, true)?;
-            let true_value_computed = FpVar::new_witness(cs.clone(), || {
-                Ok(multiplicand.value().unwrap()
-                    + if true_value_random_bit.value().unwrap() {
-                        ConstraintField::<Conf>::one()
-                    } else {
-                        ConstraintField::<Conf>::zero()
-                    })
+                (computed_ldp_value.clone() - ConstraintField::<Conf>::one()) * boundary_gap
+            };
+            let computed_upper_bound = if IS_REAL_INPUT {
+                (computed_ldp_value.clone() + ConstraintField::<Conf>::one()) * boundary_gap
+            } else {
+                computed_ldp_value.clone() * boundary_gap
+            };
+
+            // adjust the upper bound in case ldp_value == k;
+            let ldp_equal_to_k = k.is_eq(&computed_ldp_value)?;
+            let upper_bound = FpVar::new_witness(cs.clone(), || {
+                if ldp_equal_to_k.value().unwrap() {
+                    max_bound.value()
+                } else {
+                    computed_upper_bound.value()
+                }
             })?;
+            upper_bound.conditional_enforce_equal(&max_bound, &ldp_equal_to_k)?;
+            upper_bound.conditional_enforce_equal(&computed_upper_bound, &ldp_equal_to_k.not())?;
+            // randomness >= lower_bound
+            ldp_value_checks.push(randomness_fp.is_cmp_unchecked(
+                &lower_bound,
+                Ordering::Greater,
+                true,
+            )?);
+            // randomness < upper_bound
+            ldp_value_checks.push(randomness_fp.is_cmp_unchecked(
+                &upper_bound,
+                Ordering::Less,
+                false,
+            )?);
 
-            true_value_computed.conditional_enforce_equal(
-                &(&multiplicand + ConstraintField::<Conf>::one()),
-                &true_value_random_bit,
-            )?;
-            true_value_computed
-                .conditional_enforce_equal(&multiplicand, &true_value_random_bit.not())?;
-            remainder.enforce_equal(
-                &(true_value_times_k
-                    - multiplicand.clone()
-                        * ConstraintField::<Conf>::from_le_bytes_mod_order(
-                            &[u8::MAX; INPUT_BYTES],
-                        )),
-            )?;
-            true_value_computed
-        } else {
-            true_value.clone()
-        };
-        ldp_value.conditional_enforce_equal(&true_value_computed, &ldp_bit.not())?;
-        ldp_value.conditional_enforce_equal(&computed_ldp_value, &ldp_bit)?;
+            let ldp_bit = params.gamma.compute_ldp_bit(&randomness[0..GAMMA_BYTES])?;
+
+            // cast true_value if is_real_input
+            let true_value_computed = if IS_REAL_INPUT {
+                real_input_rounding_var::<_, INPUT_BYTES, K>(
+                    cs.clone(),
+                    true_value,
+                    &randomness[GAMMA_BYTES + INPUT_BYTES..GAMMA_BYTES + 2 * INPUT_BYTES],
+                )?
+            } else {
+                true_value.clone()
+            };
+            ldp_value.conditional_enforce_equal(&true_value_computed, &ldp_bit.not())?;
+            ldp_value.conditional_enforce_equal(&computed_ldp_value, &ldp_bit)?;
+        }
 
         // 3: true_value_signature =?= ClientSig.Sign(client_sig_pk, true_value)
+        // (the signed input value is the concatenation of all `D` values)
         // NOTE: correctness of this constraint is checked at the end
-        let mut message_bytes = true_value.to_bytes()?[0..INPUT_BYTES].to_vec();
-        message_bytes.extend_from_slice(&time.to_bytes()?[0..TIME_BYTES]);
+        let mut true_value_bytes = Vec::with_capacity(D * INPUT_BYTES);
+        for true_value in &true_value {
+            true_value_bytes.extend_from_slice(&true_value.to_bytes()?[0..INPUT_BYTES]);
+        }
+        let message_bytes = signed_input_value_message_var::<Conf, ConfG>(
+            params.client_input_hash_scheme.as_ref(),
+            &true_value_bytes,
+            &time.to_bytes()?[0..TIME_BYTES],
+        )?;
 
         let true_value_signature_correct = ConfG::ClientSignatureVerifyGadget::verify(
             &params.client_signature_scheme,
@@ -433,15 +588,62 @@ impl<
         let time_upper_bound_check =
             time.is_cmp_unchecked(&time_upper_bound, Ordering::Less, true)?;
 
+        let mut relations = vec![
+            (Relation::InputValueSignature, true_value_signature_correct),
+            (
+                Relation::RandomnessCommitment,
+                client_randomness_commitment_correct,
+            ),
+        ];
+        relations.extend(
+            ldp_value_checks
+                .into_iter()
+                .map(|check| (Relation::LdpValue, check)),
+        );
+        relations.extend([
+            (Relation::TimeBound, time_lower_bound_check),
+            (Relation::TimeBound, time_upper_bound_check),
+        ]);
+        Ok(relations)
+    }
+}
+
+impl<
+        Conf: Config,
+        ConfG: ConfigGadget<Conf>,
+        const INPUT_BYTES: usize,
+        const TIME_BYTES: usize,
+        const GAMMA_BYTES: usize,
+        const RANDOMNESS_BYTES: usize,
+        const K: u64,
+        const IS_REAL_INPUT: bool,
+        const D: usize,
+    > ConstraintSynthesizer<ConstraintField<Conf>>
+    for CircuitBase<
+        Conf,
+        ConfG,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+        D,
+    >
+{
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintField<Conf>>,
+    ) -> ark_relations::r1cs::Result<()> {
+        let relations = self.relations(cs.clone())?;
+
         // Check correctness of `=?=` constraints (i.e. 2, 3, 4, and 5)
-        Boolean::kary_and(&[
-            true_value_signature_correct,
-            client_randomness_commitment_correct,
-            lower_bound_check,
-            upper_bound_check,
-            time_lower_bound_check,
-            time_upper_bound_check,
-        ])?
+        Boolean::kary_and(
+            &relations
+                .into_iter()
+                .map(|(_, holds)| holds)
+                .collect::<Vec<_>>(),
+        )?
         .enforce_equal(&Boolean::TRUE)?;
 
         #[cfg(feature = "print-trace")]
@@ -454,3 +656,198 @@ impl<
         Ok(())
     }
 }
+
+/// Deserialize `bytes` (compressed), and fail with a `ParseError` if they are not consumed exactly.
+fn deserialize_exact<T: CanonicalDeserialize>(bytes: &[u8], what: &str) -> Result<T, Error> {
+    let mut reader = bytes;
+    let value = T::deserialize_compressed(&mut reader)?;
+    if !reader.is_empty() {
+        Err(GenericError::ParseError(format!(
+            "the serialized {} have {} trailing bytes",
+            what,
+            reader.len()
+        )))?
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CircuitBase, WitnessBase};
+    use crate::client::ClientBaseStorage;
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::messages::InputValueSignatureInput;
+    use crate::prelude::*;
+    use crate::primitives::ldp::apply_ldp;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_serialize::CanonicalSerialize;
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+    type Circuit = CircuitBase<Conf, ConfG, 2, 1, 2, 4, 4, false>;
+
+    /// Build the circuit for input value 3 at `time` (within the time bounds `([0], [1])` only for
+    /// time 1) from scratch, i.e., without running the protocol. The input value signature is
+    /// over `signed_value` instead of the input value.
+    fn hand_built_circuit(time: u8, signed_value: [u8; 2], rng: &mut ChaChaRng) -> Circuit {
+        let parameters =
+            ParametersBase::<Conf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let true_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: signed_value,
+                input_value_time: [time],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+
+        let client_randomness: [u8; 4] = rng.gen();
+        let server_randomness: [u8; 4] = rng.gen();
+        let commitment_randomness = ClientCommitmentSchemeRandomness::<Conf>::rand(rng);
+        let client_randomness_commitment = <Conf as Config>::ClientCommitmentScheme::commit(
+            &parameters.client_commitment_scheme,
+            &client_randomness,
+            &commitment_randomness,
+        )
+        .unwrap();
+        let mut randomness = client_randomness;
+        randomness
+            .iter_mut()
+            .zip(server_randomness)
+            .for_each(|(client_byte, server_byte)| *client_byte ^= server_byte);
+        let ldp_value = apply_ldp::<2, 2, 4, false>(
+            &BigUint::from(3_u8),
+            &randomness,
+            &parameters.gamma_as_bytes().unwrap(),
+        );
+
+        Circuit::with_witness(
+            parameters,
+            WitnessBase {
+                ldp_value: [ldp_value],
+                time_bounds: ([0], [1]),
+                true_value: [[3, 0]],
+                time: [time],
+                true_value_signature,
+                client_sig_pk,
+                server_randomness,
+            },
+            ClientBaseStorage {
+                client_randomness: Some(client_randomness),
+                client_randomness_commitment_randomness: Some(commitment_randomness),
+                client_randomness_commitment: Some(client_randomness_commitment),
+                ..ClientBaseStorage::new()
+            },
+        )
+    }
+
+    fn is_satisfied(circuit: Circuit) -> bool {
+        let cs = ConstraintSystem::<ConstraintField<Conf>>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn constraint_stats_match_synthesis_with_witness() {
+        let rng = &mut ChaChaRng::from_seed([108; 32]);
+        let circuit = hand_built_circuit(1, [3, 0], rng);
+        let stats = Circuit::constraint_stats(circuit.params.clone()).unwrap();
+        assert!(stats.num_constraints > 0);
+
+        let cs = ConstraintSystem::<ConstraintField<Conf>>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        assert_eq!(stats.num_constraints, cs.num_constraints());
+        assert_eq!(stats.num_witness_variables, cs.num_witness_variables());
+        assert_eq!(stats.num_instance_variables, cs.num_instance_variables());
+    }
+
+    #[test]
+    fn hand_built_witness_satisfies_constraints() {
+        let rng = &mut ChaChaRng::from_seed([94; 32]);
+        let circuit = hand_built_circuit(1, [3, 0], rng);
+        assert!(is_satisfied(circuit.clone()));
+
+        // wrong LDP value
+        let mut wrong_ldp_value = circuit;
+        wrong_ldp_value.ldp_value = wrong_ldp_value.ldp_value.map(|[value]| [value % 4 + 1]);
+        assert!(!is_satisfied(wrong_ldp_value));
+
+        // correctly signed, but outside the time bounds
+        assert!(!is_satisfied(hand_built_circuit(2, [3, 0], rng)));
+
+        // signature over a different input value
+        assert!(!is_satisfied(hand_built_circuit(1, [4, 0], rng)));
+    }
+
+    #[test]
+    fn stored_proof_and_public_inputs_verify_from_bytes() {
+        let rng = &mut ChaChaRng::from_seed([131; 32]);
+        let circuit = hand_built_circuit(1, [3, 0], rng);
+        let (proving_key, verifying_key) = Circuit::keygen(circuit.params.clone(), rng).unwrap();
+        let proof = <Conf as Config>::ZKPScheme::prove(&proving_key, circuit.clone(), rng).unwrap();
+        let public_inputs = |ldp_value: [u64; 1]| {
+            Circuit::public_inputs(
+                None,
+                ldp_value,
+                circuit.time_bounds.unwrap(),
+                &circuit.client_sig_pk.unwrap(),
+                circuit.client_randomness_commitment.unwrap(),
+                circuit.server_randomness.unwrap(),
+            )
+            .unwrap()
+        };
+
+        // e.g., written by the server when the submission arrives
+        let mut proof_bytes = vec![];
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+        let mut public_input_bytes = vec![];
+        let ldp_value = circuit.ldp_value.unwrap();
+        public_inputs(ldp_value)
+            .serialize_compressed(&mut public_input_bytes)
+            .unwrap();
+        // the documented layout: the length, then 32 bytes per field element
+        assert_eq!(
+            public_input_bytes.len(),
+            8 + 32 * public_inputs(ldp_value).len()
+        );
+        assert_eq!(
+            public_input_bytes[0..8],
+            (public_inputs(ldp_value).len() as u64).to_le_bytes()
+        );
+        assert!(
+            Circuit::verify_raw(&verifying_key, &proof_bytes, &public_input_bytes, rng).unwrap()
+        );
+
+        // the proof does not verify for other public inputs
+        let mut other_input_bytes = vec![];
+        public_inputs([ldp_value[0] % 4 + 1])
+            .serialize_compressed(&mut other_input_bytes)
+            .unwrap();
+        assert!(
+            !Circuit::verify_raw(&verifying_key, &proof_bytes, &other_input_bytes, rng).unwrap()
+        );
+
+        // trailing bytes are rejected
+        public_input_bytes.push(0);
+        assert!(matches!(
+            Circuit::verify_raw(&verifying_key, &proof_bytes, &public_input_bytes, rng),
+            Err(VldpError::Generic(GenericError::ParseError(_)))
+        ));
+    }
+}
@@ -0,0 +1,547 @@
+//! Definitions of the R1CS ZKP circuit for the Base VLDP scheme with the discrete Laplace
+//! mechanism (see `crate::primitives::laplace`) instead of randomized response.
+
+use super::{signed_input_value_message_var, Relation, Relations};
+use crate::client::ClientBaseStorage;
+use crate::prelude::{constraints::*, *};
+use crate::primitives::laplace::laplace_randomness_bytes;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::ToConstraintFieldGadget;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+/// Struct for R1CS constraint generation for the Base scheme with the discrete Laplace mechanism:
+/// `ldp_value = true_value + TRIALS + noise`, where the noise is derived from the randomness as
+/// in `apply_laplace` with the success probability `alpha` (see `laplace_alpha_as_bytes`), which
+/// is a constant of the circuit. The signature, commitment, and time constraints are those of
+/// `CircuitBase`.
+///
+/// The circuit randomizes a single input value. It uses the commitment, signature, and input
+/// hash parameters of `params`; gamma and signed input are not supported.
+#[derive(Clone)]
+pub struct CircuitBaseLaplace<
+    Conf: Config,
+    ConfG: ConfigGadget<Conf>,
+    const INPUT_BYTES: usize,
+    const TIME_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+    const TRIALS: usize,
+> {
+    #[doc(hidden)]
+    _config_gadget: PhantomData<ConfG>,
+
+    // parameters
+    params: ParametersBase<Conf, GAMMA_BYTES>,
+    alpha: [u8; GAMMA_BYTES],
+    // public inputs
+    ldp_value: Option<u64>,
+    time_bounds: Option<([u8; TIME_BYTES], [u8; TIME_BYTES])>,
+    client_sig_pk: Option<ClientSignatureSchemePublicKey<Conf>>,
+    client_randomness_commitment: Option<ClientCommitmentSchemeOutput<Conf>>,
+    server_randomness: Option<[u8; RANDOMNESS_BYTES]>,
+
+    // private witnesses
+    true_value: Option<[u8; INPUT_BYTES]>,
+    time: Option<[u8; TIME_BYTES]>,
+    true_value_signature: Option<ClientSignatureSchemeSignature<Conf>>,
+    client_randomness: Option<[u8; RANDOMNESS_BYTES]>,
+    client_randomness_commitment_randomness: Option<ClientCommitmentSchemeRandomness<Conf>>,
+}
+
+/// Values from which the client proves a single `Randomize` step with the Base circuit with the
+/// discrete Laplace mechanism (see `CircuitBaseLaplace::prove`). The client randomness and its
+/// commitment are taken from the client storage.
+#[derive(Clone)]
+pub struct WitnessBaseLaplace<
+    Conf: Config,
+    const INPUT_BYTES: usize,
+    const TIME_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+> {
+    pub ldp_value: u64,
+    pub time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+    pub true_value: [u8; INPUT_BYTES],
+    pub time: [u8; TIME_BYTES],
+    pub true_value_signature: ClientSignatureSchemeSignature<Conf>,
+    pub client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
+    pub server_randomness: [u8; RANDOMNESS_BYTES],
+}
+
+/// Public inputs of the Base circuit with the discrete Laplace mechanism that follow `ldp_value`
+/// (see `CircuitBaseLaplace::verify`).
+pub struct PublicInputsBaseLaplace<
+    'a,
+    Conf: Config,
+    const TIME_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+> {
+    pub time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+    pub client_sig_pk: &'a ClientSignatureSchemePublicKey<Conf>,
+    pub client_randomness_commitment: ClientCommitmentSchemeOutput<Conf>,
+    pub server_randomness: [u8; RANDOMNESS_BYTES],
+}
+
+impl<
+        Conf: Config,
+        ConfG: ConfigGadget<Conf>,
+        const INPUT_BYTES: usize,
+        const TIME_BYTES: usize,
+        const GAMMA_BYTES: usize,
+        const RANDOMNESS_BYTES: usize,
+        const TRIALS: usize,
+    >
+    CircuitBaseLaplace<Conf, ConfG, INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, TRIALS>
+{
+    /// Circuit without any inputs, as needed for key generation.
+    fn without_witness(
+        params: ParametersBase<Conf, GAMMA_BYTES>,
+        alpha: [u8; GAMMA_BYTES],
+    ) -> Self {
+        Self {
+            _config_gadget: PhantomData,
+            params,
+            alpha,
+            ldp_value: None,
+            time_bounds: None,
+            client_sig_pk: None,
+            client_randomness_commitment: None,
+            server_randomness: None,
+            true_value: None,
+            time: None,
+            true_value_signature: None,
+            client_randomness: None,
+            client_randomness_commitment_randomness: None,
+        }
+    }
+
+    pub fn keygen(
+        params: ParametersBase<Conf, GAMMA_BYTES>,
+        alpha: [u8; GAMMA_BYTES],
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<(ProvingKey<Conf>, VerifyingKey<Conf>), Error> {
+        Conf::ZKPScheme::keygen(Self::without_witness(params, alpha), zkp_rng)
+    }
+
+    pub fn prove(
+        proving_key: &ProvingKey<Conf>,
+        params: ParametersBase<Conf, GAMMA_BYTES>,
+        alpha: [u8; GAMMA_BYTES],
+        witness: WitnessBaseLaplace<Conf, INPUT_BYTES, TIME_BYTES, RANDOMNESS_BYTES>,
+        client_storage: ClientBaseStorage<Conf, RANDOMNESS_BYTES>,
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<Proof<Conf>, Error> {
+        let circuit = Self::with_witness(params, alpha, witness, client_storage);
+        Conf::ZKPScheme::prove(proving_key, circuit, zkp_rng)
+    }
+
+    fn with_witness(
+        params: ParametersBase<Conf, GAMMA_BYTES>,
+        alpha: [u8; GAMMA_BYTES],
+        witness: WitnessBaseLaplace<Conf, INPUT_BYTES, TIME_BYTES, RANDOMNESS_BYTES>,
+        client_storage: ClientBaseStorage<Conf, RANDOMNESS_BYTES>,
+    ) -> Self {
+        Self {
+            _config_gadget: PhantomData,
+            params,
+            alpha,
+            ldp_value: Some(witness.ldp_value),
+            time_bounds: Some(witness.time_bounds),
+            client_sig_pk: Some(witness.client_sig_pk),
+            client_randomness_commitment: client_storage.client_randomness_commitment,
+            server_randomness: Some(witness.server_randomness),
+            true_value: Some(witness.true_value),
+            time: Some(witness.time),
+            true_value_signature: Some(witness.true_value_signature),
+            client_randomness: client_storage.client_randomness,
+            client_randomness_commitment_randomness: client_storage
+                .client_randomness_commitment_randomness,
+        }
+    }
+
+    /// Public inputs of the circuit, in the order in which they are allocated: `ldp_value` (as 8
+    /// little-endian bytes), followed by both `time_bounds`, `client_sig_pk`,
+    /// `client_randomness_commitment`, and `server_randomness` of `inputs`, as for `CircuitBase`
+    /// without public gamma.
+    pub fn public_inputs(
+        ldp_value: u64,
+        inputs: &PublicInputsBaseLaplace<Conf, TIME_BYTES, RANDOMNESS_BYTES>,
+    ) -> Result<Vec<ConstraintField<Conf>>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        ClientCommitmentSchemeOutput<Conf>: ToConstraintField<ConstraintField<Conf>>,
+    {
+        let mut public_inputs = Vec::new();
+        public_inputs.extend_from_slice(
+            &ldp_value
+                .to_le_bytes()
+                .to_field_elements()
+                .ok_or(GenericError::ConversionError)?,
+        );
+        public_inputs.extend_from_slice(
+            &inputs
+                .time_bounds
+                .0
+                .to_field_elements()
+                .ok_or(GenericError::ConversionError)?,
+        );
+        public_inputs.extend_from_slice(
+            &inputs
+                .time_bounds
+                .1
+                .to_field_elements()
+                .ok_or(GenericError::ConversionError)?,
+        );
+        public_inputs.extend_from_slice(
+            &inputs
+                .client_sig_pk
+                .to_field_elements()
+                .ok_or(GenericError::ConversionError)?,
+        );
+        public_inputs.extend_from_slice(
+            &inputs
+                .client_randomness_commitment
+                .to_field_elements()
+                .ok_or(GenericError::ConversionError)?,
+        );
+        public_inputs.extend_from_slice(
+            &inputs
+                .server_randomness
+                .to_field_elements()
+                .ok_or(GenericError::ConversionError)?,
+        );
+        Ok(public_inputs)
+    }
+
+    pub fn verify(
+        verifying_key: &VerifyingKey<Conf>,
+        proof: &Proof<Conf>,
+        ldp_value: u64,
+        inputs: &PublicInputsBaseLaplace<Conf, TIME_BYTES, RANDOMNESS_BYTES>,
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<bool, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        ClientCommitmentSchemeOutput<Conf>: ToConstraintField<ConstraintField<Conf>>,
+    {
+        let public_inputs = Self::public_inputs(ldp_value, inputs)?;
+        Conf::ZKPScheme::verify(verifying_key, &public_inputs, proof, zkp_rng)
+    }
+
+    fn relations(
+        self,
+        cs: ConstraintSystemRef<ConstraintField<Conf>>,
+    ) -> Result<Relations<ConstraintField<Conf>>, SynthesisError> {
+        assert!(laplace_randomness_bytes::<GAMMA_BYTES, TRIALS>() <= RANDOMNESS_BYTES);
+
+        // --- SANITY CHECKS ---
+        if !cs.is_in_setup_mode()
+            && (self.ldp_value.is_none()
+                || self.server_randomness.is_none()
+                || self.true_value.is_none()
+                || self.true_value_signature.is_none()
+                || self.client_randomness.is_none())
+        {
+            Err(SynthesisError::AssignmentMissing)?
+        }
+
+        // --- ALLOCATE VARIABLES ---
+        // allocate constants
+        let client_commitment_scheme =
+            ClientCommitmentSchemeParametersVar::<_, ConfG>::new_constant(
+                cs.clone(),
+                &self.params.client_commitment_scheme,
+            )?;
+        let client_signature_scheme = ClientSignatureSchemeParametersVar::<_, ConfG>::new_constant(
+            cs.clone(),
+            &self.params.client_signature_scheme,
+        )?;
+        let client_input_hash_scheme = self
+            .params
+            .client_input_hash_scheme
+            .as_ref()
+            .map(|parameters| {
+                ClientInputHashParametersVar::<_, ConfG>::new_constant(cs.clone(), parameters)
+            })
+            .transpose()?;
+        let alpha = FpVar::new_constant(
+            cs.clone(),
+            ConstraintField::<Conf>::from_le_bytes_mod_order(&self.alpha),
+        )?;
+
+        // allocate public inputs
+        let ldp_value = FpVar::new_input(cs.clone(), || {
+            self.ldp_value
+                .map(ConstraintField::<Conf>::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let time_lower_bound = FpVar::new_input(cs.clone(), || {
+            self.time_bounds
+                .as_ref()
+                .map(|(lb, _)| ConstraintField::<Conf>::from_le_bytes_mod_order(lb))
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let time_upper_bound = FpVar::new_input(cs.clone(), || {
+            self.time_bounds
+                .as_ref()
+                .map(|(_, ub)| ConstraintField::<Conf>::from_le_bytes_mod_order(ub))
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let client_sig_pk =
+            ClientSignatureSchemePublicKeyVar::<_, ConfG>::new_input(cs.clone(), || {
+                self.client_sig_pk.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+        let client_randomness_commitment =
+            ClientCommitmentSchemeOutputVar::<_, ConfG>::new_input(cs.clone(), || {
+                self.client_randomness_commitment
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?;
+        let server_randomness = UInt8::new_input_vec(
+            cs.clone(),
+            &self.server_randomness.unwrap_or([0; RANDOMNESS_BYTES]),
+        )?;
+
+        // allocate witnesses
+        let true_value =
+            UInt8::new_witness_vec(cs.clone(), &self.true_value.unwrap_or([0; INPUT_BYTES]))?;
+        let time = FpVar::new_witness(cs.clone(), || {
+            self.time
+                .map(|x| ConstraintField::<Conf>::from_le_bytes_mod_order(&x))
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let true_value_signature =
+            ClientSignatureSchemeSignatureVar::<_, ConfG>::new_witness(cs.clone(), || {
+                Ok(self.true_value_signature.unwrap_or_default())
+            })?;
+        let client_randomness = UInt8::new_witness_vec(
+            cs.clone(),
+            &self.client_randomness.unwrap_or([0; RANDOMNESS_BYTES]),
+        )?;
+        let client_randomness_commitment_randomness =
+            ClientCommitmentSchemeRandomnessVar::<_, ConfG>::new_witness(cs.clone(), || {
+                self.client_randomness_commitment_randomness
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?;
+
+        // --- CONSTRAINTS ---
+        // 1: randomness = client_randomness XOR server_randomness
+        let randomness = client_randomness
+            .iter()
+            .zip(server_randomness.iter())
+            .map(|(client_byte, server_byte)| client_byte.xor(server_byte))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // 2: ldp_value =?= true_value + TRIALS + noise, where the noise is the difference of two
+        // geometric samples, each counting the leading successful trials (trial <= alpha)
+        // NOTE: correctness of this constraint is checked at the end
+        let mut geometric_samples = Vec::with_capacity(2);
+        for trials in randomness[0..laplace_randomness_bytes::<GAMMA_BYTES, TRIALS>()]
+            .chunks(TRIALS * GAMMA_BYTES)
+        {
+            let mut alive = Boolean::TRUE;
+            let mut count = FpVar::zero();
+            for trial in trials.chunks(GAMMA_BYTES) {
+                let success = trial.to_constraint_field()?[0].is_cmp_unchecked(
+                    &alpha,
+                    Ordering::Less,
+                    true,
+                )?;
+                alive = alive.and(&success)?;
+                count += FpVar::from(alive.clone());
+            }
+            geometric_samples.push(count);
+        }
+        let true_value_fp = true_value.to_constraint_field()?[0].clone();
+        let computed_ldp_value =
+            true_value_fp + ConstraintField::<Conf>::from(TRIALS as u64) + &geometric_samples[0]
+                - &geometric_samples[1];
+        let ldp_value_correct = ldp_value.is_eq(&computed_ldp_value)?;
+
+        // 3: true_value_signature =?= ClientSig.Sign(client_sig_pk, true_value)
+        // NOTE: correctness of this constraint is checked at the end
+        let message_bytes = signed_input_value_message_var::<Conf, ConfG>(
+            client_input_hash_scheme.as_ref(),
+            &true_value,
+            &time.to_bytes()?[0..TIME_BYTES],
+        )?;
+        let true_value_signature_correct = ConfG::ClientSignatureVerifyGadget::verify(
+            &client_signature_scheme,
+            &client_sig_pk,
+            &message_bytes,
+            &true_value_signature,
+        )?;
+
+        // 4: client_randomness_commitment =?= Comm(client_seed, client_seed_commitment_randomness)
+        // NOTE: correctness of this constraint is checked at the end
+        let client_randomness_commitment_computed = ConfG::ClientCommitmentVerifyGadget::commit(
+            &client_commitment_scheme,
+            &client_randomness,
+            &client_randomness_commitment_randomness,
+        )?;
+        let client_randomness_commitment_correct =
+            client_randomness_commitment.is_eq(&client_randomness_commitment_computed)?;
+
+        // 5: time_lower_bound < time <= time_upper_bound
+        let time_lower_bound_check =
+            time_lower_bound.is_cmp_unchecked(&time, Ordering::Less, false)?;
+        let time_upper_bound_check =
+            time.is_cmp_unchecked(&time_upper_bound, Ordering::Less, true)?;
+
+        Ok(vec![
+            (Relation::InputValueSignature, true_value_signature_correct),
+            (
+                Relation::RandomnessCommitment,
+                client_randomness_commitment_correct,
+            ),
+            (Relation::LdpValue, ldp_value_correct),
+            (Relation::TimeBound, time_lower_bound_check),
+            (Relation::TimeBound, time_upper_bound_check),
+        ])
+    }
+}
+
+impl<
+        Conf: Config,
+        ConfG: ConfigGadget<Conf>,
+        const INPUT_BYTES: usize,
+        const TIME_BYTES: usize,
+        const GAMMA_BYTES: usize,
+        const RANDOMNESS_BYTES: usize,
+        const TRIALS: usize,
+    > ConstraintSynthesizer<ConstraintField<Conf>>
+    for CircuitBaseLaplace<
+        Conf,
+        ConfG,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        TRIALS,
+    >
+{
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintField<Conf>>,
+    ) -> ark_relations::r1cs::Result<()> {
+        let relations = self.relations(cs)?;
+
+        // Check correctness of `=?=` constraints (i.e. 2, 3, 4, and 5)
+        Boolean::kary_and(
+            &relations
+                .into_iter()
+                .map(|(_, holds)| holds)
+                .collect::<Vec<_>>(),
+        )?
+        .enforce_equal(&Boolean::TRUE)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CircuitBaseLaplace, WitnessBaseLaplace};
+    use crate::client::ClientBaseStorage;
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::messages::InputValueSignatureInput;
+    use crate::prelude::*;
+    use crate::primitives::laplace::{apply_laplace, laplace_alpha_as_bytes};
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+    type Circuit = CircuitBaseLaplace<Conf, ConfG, 2, 1, 2, 16, 4>;
+
+    /// Build the circuit for input value 3 at `time` (within the time bounds `([0], [1])` only for
+    /// time 1), without running the protocol.
+    fn hand_built_circuit(time: u8, rng: &mut ChaChaRng) -> Circuit {
+        let parameters =
+            ParametersBase::<Conf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let alpha = laplace_alpha_as_bytes::<2>(&BigFloat::from_f64(0.5, 64)).unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let true_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [time],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+
+        let client_randomness: [u8; 16] = rng.gen();
+        let server_randomness: [u8; 16] = rng.gen();
+        let commitment_randomness = ClientCommitmentSchemeRandomness::<Conf>::rand(rng);
+        let client_randomness_commitment = <Conf as Config>::ClientCommitmentScheme::commit(
+            &parameters.client_commitment_scheme,
+            &client_randomness,
+            &commitment_randomness,
+        )
+        .unwrap();
+        let mut randomness = client_randomness;
+        randomness
+            .iter_mut()
+            .zip(server_randomness)
+            .for_each(|(client_byte, server_byte)| *client_byte ^= server_byte);
+        let ldp_value = apply_laplace::<2, 4>(&BigUint::from(3_u8), &randomness, &alpha).unwrap();
+
+        Circuit::with_witness(
+            parameters,
+            alpha,
+            WitnessBaseLaplace {
+                ldp_value,
+                time_bounds: ([0], [1]),
+                true_value: [3, 0],
+                time: [time],
+                true_value_signature,
+                client_sig_pk,
+                server_randomness,
+            },
+            ClientBaseStorage {
+                client_randomness: Some(client_randomness),
+                client_randomness_commitment_randomness: Some(commitment_randomness),
+                client_randomness_commitment: Some(client_randomness_commitment),
+                ..ClientBaseStorage::new()
+            },
+        )
+    }
+
+    fn is_satisfied(circuit: Circuit) -> bool {
+        let cs = ConstraintSystem::<ConstraintField<Conf>>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn laplace_ldp_value_is_enforced() {
+        let rng = &mut ChaChaRng::from_seed([120; 32]);
+        let circuit = hand_built_circuit(1, rng);
+        assert!(is_satisfied(circuit.clone()));
+
+        // any other LDP value (i.e., other noise) is rejected
+        for offset in [1, 2] {
+            let mut wrong_ldp_value = circuit.clone();
+            wrong_ldp_value.ldp_value = wrong_ldp_value.ldp_value.map(|value| value + offset);
+            assert!(!is_satisfied(wrong_ldp_value));
+        }
+
+        // correctly signed, but outside the time bounds
+        assert!(!is_satisfied(hand_built_circuit(2, rng)));
+    }
+}
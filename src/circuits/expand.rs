@@ -1,14 +1,20 @@
 //! Definitions of the R1CS ZKP circuits for the Expand VLDP scheme.
 
+use super::{
+    constraint_stats, real_input_rounding_var, signed_input_value_message_var, CircuitStats,
+    PublicInputDescriptor,
+};
 use crate::client::ClientExpandStorage;
+use crate::messages::expand::VerifiableBundleExpand;
 use crate::prelude::{constraints::*, *};
+use crate::primitives::ldp::{boundary_gap, randomized_ldp_value, randomness_limit};
 use ark_ff::PrimeField;
 use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::ToConstraintFieldGadget;
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
-use ark_std::{One, Zero};
-use num_bigint::BigUint;
-use std::cmp::{min, Ordering};
+use ark_serialize::CanonicalDeserialize;
+use ark_std::One;
+use std::cmp::Ordering;
 use std::marker::PhantomData;
 
 /// Struct for R1CS constraint generation for the Expand scheme.
@@ -29,8 +35,11 @@ pub struct CircuitExpand<
 
     // parameters
     params: ParametersExpand<Conf, GAMMA_BYTES>,
+    // whether `ldp_value` is a witness that only opens the public `ldp_value_commitment`
+    blinded: bool,
     // public inputs
     ldp_value: Option<u64>,
+    ldp_value_commitment: Option<ClientCommitmentSchemeOutput<Conf>>,
     time_bounds: Option<([u8; TIME_BYTES], [u8; TIME_BYTES])>,
     client_sig_pk: Option<ClientSignatureSchemePublicKey<Conf>>,
     client_merkle_tree_root: Option<ClientMerkleTreeRoot<Conf>>,
@@ -44,6 +53,42 @@ pub struct CircuitExpand<
     client_randomness: Option<[u8; RANDOMNESS_BYTES]>,
     client_randomness_commitment_randomness: Option<ClientCommitmentSchemeRandomness<Conf>>,
     merkle_path: Option<ClientMerkleTreePath<Conf>>,
+    ldp_value_commitment_randomness: Option<ClientCommitmentSchemeRandomness<Conf>>,
+}
+
+/// Values from which the client proves a single `Randomize` step with the Expand circuit (see
+/// `CircuitExpand::prove`). The Merkle root, index, and path are taken from the client storage.
+#[derive(Clone)]
+pub struct WitnessExpand<
+    Conf: Config,
+    const INPUT_BYTES: usize,
+    const TIME_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+> {
+    pub ldp_value: u64,
+    pub time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+    pub true_value: [u8; INPUT_BYTES],
+    pub time: [u8; TIME_BYTES],
+    pub true_value_signature: ClientSignatureSchemeSignature<Conf>,
+    pub client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
+    pub server_randomness: [u8; RANDOMNESS_BYTES],
+    pub client_randomness: [u8; RANDOMNESS_BYTES],
+    pub client_randomness_commitment_randomness: ClientCommitmentSchemeRandomness<Conf>,
+}
+
+/// Public inputs of the Expand circuit that follow the (possibly blinded) `ldp_value`, as the
+/// server takes them from a client message (see `CircuitExpand::verify`).
+pub struct PublicInputsExpand<
+    'a,
+    Conf: Config,
+    const TIME_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+> {
+    pub time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+    pub client_sig_pk: &'a ClientSignatureSchemePublicKey<Conf>,
+    pub client_merkle_tree_root: &'a ClientMerkleTreeRoot<Conf>,
+    pub index: usize,
+    pub server_randomness: [u8; RANDOMNESS_BYTES],
 }
 
 impl<
@@ -73,10 +118,56 @@ impl<
         params: ParametersExpand<Conf, GAMMA_BYTES>,
         zkp_rng: &mut ZKPRng<Conf>,
     ) -> Result<(ProvingKey<Conf>, VerifyingKey<Conf>), Error> {
-        let circuit = Self {
+        Conf::ZKPScheme::keygen(Self::blank(params, false), zkp_rng)
+    }
+
+    /// Generate keys for the blinded mode, in which the proof only exposes a commitment to
+    /// `ldp_value` (see `prove_blinded`).
+    pub fn keygen_blinded(
+        params: ParametersExpand<Conf, GAMMA_BYTES>,
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<(ProvingKey<Conf>, VerifyingKey<Conf>), Error> {
+        Conf::ZKPScheme::keygen(Self::blank(params, true), zkp_rng)
+    }
+
+    /// Precompute the prover context of the circuit for the given parameters, which clients reuse
+    /// for every proof (see `ProofSystem::prover_context` and `ClientExpand::with_prover_context`).
+    pub fn prover_context(
+        params: ParametersExpand<Conf, GAMMA_BYTES>,
+    ) -> Result<ProverContext<Conf>, Error> {
+        Conf::ZKPScheme::prover_context(Self::blank(params, false))
+    }
+
+    /// Precompute the prover context of the circuit in the blinded mode (see `keygen_blinded`).
+    pub fn prover_context_blinded(
+        params: ParametersExpand<Conf, GAMMA_BYTES>,
+    ) -> Result<ProverContext<Conf>, Error> {
+        Conf::ZKPScheme::prover_context(Self::blank(params, true))
+    }
+
+    /// Count the constraints and variables of the circuit for the given parameters, without
+    /// generating any keys.
+    pub fn constraint_stats(
+        params: ParametersExpand<Conf, GAMMA_BYTES>,
+    ) -> Result<CircuitStats, Error> {
+        constraint_stats(Self::blank(params, false))
+    }
+
+    /// Count the constraints and variables of the circuit in the blinded mode (see
+    /// `keygen_blinded`).
+    pub fn constraint_stats_blinded(
+        params: ParametersExpand<Conf, GAMMA_BYTES>,
+    ) -> Result<CircuitStats, Error> {
+        constraint_stats(Self::blank(params, true))
+    }
+
+    fn blank(params: ParametersExpand<Conf, GAMMA_BYTES>, blinded: bool) -> Self {
+        Self {
             _config_gadget: PhantomData,
             params,
+            blinded,
             ldp_value: None,
+            ldp_value_commitment: None,
             time_bounds: None,
             client_sig_pk: None,
             client_merkle_tree_root: None,
@@ -88,8 +179,8 @@ impl<
             client_randomness: None,
             client_randomness_commitment_randomness: None,
             merkle_path: None,
-        };
-        Conf::ZKPScheme::keygen(circuit, zkp_rng)
+            ldp_value_commitment_randomness: None,
+        }
     }
 
     pub fn prove(
@@ -107,90 +198,402 @@ impl<
         client_storage: ClientExpandStorage<Conf>,
         zkp_rng: &mut ZKPRng<Conf>,
     ) -> Result<Proof<Conf>, Error> {
-        let circuit = Self {
+        Self::prove_with_witness(
+            proving_key,
+            params,
+            WitnessExpand {
+                ldp_value,
+                time_bounds,
+                true_value,
+                time,
+                true_value_signature,
+                client_sig_pk,
+                server_randomness,
+                client_randomness,
+                client_randomness_commitment_randomness,
+            },
+            client_storage,
+            zkp_rng,
+        )
+    }
+
+    /// Generate a proof like `prove`, with the values of the client bundled in a `witness`.
+    pub fn prove_with_witness(
+        proving_key: &ProvingKey<Conf>,
+        params: ParametersExpand<Conf, GAMMA_BYTES>,
+        witness: WitnessExpand<Conf, INPUT_BYTES, TIME_BYTES, RANDOMNESS_BYTES>,
+        client_storage: ClientExpandStorage<Conf>,
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<Proof<Conf>, Error> {
+        let circuit = Self::assigned(params, witness, client_storage)?;
+        Conf::ZKPScheme::prove(proving_key, circuit, zkp_rng)
+    }
+
+    /// Generate a proof in the blinded mode (for keys from `keygen_blinded`), in which
+    /// `ldp_value` is only exposed as its commitment (as 8 little-endian bytes) with the given
+    /// `ldp_value_commitment_randomness`.
+    pub fn prove_blinded(
+        proving_key: &ProvingKey<Conf>,
+        params: ParametersExpand<Conf, GAMMA_BYTES>,
+        witness: WitnessExpand<Conf, INPUT_BYTES, TIME_BYTES, RANDOMNESS_BYTES>,
+        client_storage: ClientExpandStorage<Conf>,
+        ldp_value_commitment_randomness: ClientCommitmentSchemeRandomness<Conf>,
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<Proof<Conf>, Error> {
+        let circuit = Self::assigned_blinded(
+            params,
+            witness,
+            client_storage,
+            ldp_value_commitment_randomness,
+        )?;
+        Conf::ZKPScheme::prove(proving_key, circuit, zkp_rng)
+    }
+
+    /// Circuit in the blinded mode, with all values assigned (see `prove_blinded`).
+    pub(crate) fn assigned_blinded(
+        params: ParametersExpand<Conf, GAMMA_BYTES>,
+        witness: WitnessExpand<Conf, INPUT_BYTES, TIME_BYTES, RANDOMNESS_BYTES>,
+        client_storage: ClientExpandStorage<Conf>,
+        ldp_value_commitment_randomness: ClientCommitmentSchemeRandomness<Conf>,
+    ) -> Result<Self, Error> {
+        let ldp_value_commitment = Conf::ClientCommitmentScheme::commit(
+            &params.client_commitment_scheme,
+            &witness.ldp_value.to_le_bytes(),
+            &ldp_value_commitment_randomness,
+        )?;
+        Ok(Self {
+            blinded: true,
+            ldp_value_commitment: Some(ldp_value_commitment),
+            ldp_value_commitment_randomness: Some(ldp_value_commitment_randomness),
+            ..Self::assigned(params, witness, client_storage)?
+        })
+    }
+
+    pub(crate) fn assigned(
+        params: ParametersExpand<Conf, GAMMA_BYTES>,
+        witness: WitnessExpand<Conf, INPUT_BYTES, TIME_BYTES, RANDOMNESS_BYTES>,
+        client_storage: ClientExpandStorage<Conf>,
+    ) -> Result<Self, Error> {
+        // the circuit binds the public index to the leaf position of the path, so catch a mismatch
+        // here instead of producing a proof that does not verify
+        let merkle_path = client_storage
+            .merkle_tree
+            .as_ref()
+            .ok_or(SynthesisError::AssignmentMissing)?
+            .generate_proof(client_storage.index)?;
+        check_merkle_path_index::<Conf>(&merkle_path, client_storage.index)?;
+
+        Ok(Self {
             _config_gadget: PhantomData,
             params,
-            ldp_value: Some(ldp_value),
-            time_bounds: Some(time_bounds),
-            client_sig_pk: Some(client_sig_pk),
+            blinded: false,
+            ldp_value: Some(witness.ldp_value),
+            ldp_value_commitment: None,
+            time_bounds: Some(witness.time_bounds),
+            client_sig_pk: Some(witness.client_sig_pk),
             client_merkle_tree_root: client_storage.merkle_tree.as_ref().map(|mt| mt.root()),
             index: Some(client_storage.index as u64),
-            server_randomness: Some(server_randomness),
-            true_value: Some(true_value),
-            time: Some(time),
-            true_value_signature: Some(true_value_signature),
-            client_randomness: Some(client_randomness),
-            client_randomness_commitment_randomness: Some(client_randomness_commitment_randomness),
-            merkle_path: Some(
-                client_storage
-                    .merkle_tree
-                    .ok_or(SynthesisError::AssignmentMissing)?
-                    .generate_proof(client_storage.index)?,
+            server_randomness: Some(witness.server_randomness),
+            true_value: Some(witness.true_value),
+            time: Some(witness.time),
+            true_value_signature: Some(witness.true_value_signature),
+            client_randomness: Some(witness.client_randomness),
+            client_randomness_commitment_randomness: Some(
+                witness.client_randomness_commitment_randomness,
             ),
-        };
-        Conf::ZKPScheme::prove(proving_key, circuit, zkp_rng)
+            merkle_path: Some(merkle_path),
+            ldp_value_commitment_randomness: None,
+        })
     }
 
-    pub fn verify(
-        verifying_key: &VerifyingKey<Conf>,
-        proof: &Proof<Conf>,
+    /// Public inputs of the Expand circuit, in the order in which they are allocated:
+    /// `ldp_value` (as 8 little-endian bytes), both `time_bounds`, `client_sig_pk`,
+    /// `client_merkle_tree_root`, `index` (as little-endian bytes), and `server_randomness`. Byte
+    /// arrays are packed into field elements as done by their `ToConstraintField` implementation.
+    pub fn public_inputs(
         ldp_value: u64,
         time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
         client_sig_pk: &ClientSignatureSchemePublicKey<Conf>,
         client_merkle_tree_root: &ClientMerkleTreeRoot<Conf>,
         index: usize,
         server_randomness: [u8; RANDOMNESS_BYTES],
-        zkp_rng: &mut ZKPRng<Conf>,
-    ) -> Result<bool, Error>
+    ) -> Result<Vec<ConstraintField<Conf>>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>>,
+    {
+        Self::public_inputs_with(
+            ldp_value,
+            &PublicInputsExpand {
+                time_bounds,
+                client_sig_pk,
+                client_merkle_tree_root,
+                index,
+                server_randomness,
+            },
+        )
+    }
+
+    /// Public inputs of the Expand circuit like `public_inputs`, with all inputs but `ldp_value`
+    /// bundled in `inputs`.
+    pub fn public_inputs_with(
+        ldp_value: u64,
+        inputs: &PublicInputsExpand<Conf, TIME_BYTES, RANDOMNESS_BYTES>,
+    ) -> Result<Vec<ConstraintField<Conf>>, Error>
     where
         ClientSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
         ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>>,
     {
         // convert inputs into correct format for proof verification
-        let mut public_inputs = Vec::new();
+        let mut public_inputs = ldp_value
+            .to_le_bytes()
+            .to_field_elements()
+            .ok_or(GenericError::ConversionError)?;
+        public_inputs.extend(Self::unblinded_public_inputs(inputs)?);
+        Ok(public_inputs)
+    }
 
+    /// Public inputs of the Expand circuit in the blinded mode, which are those of
+    /// `public_inputs` with `ldp_value_commitment` in place of `ldp_value`.
+    pub fn blinded_public_inputs(
+        ldp_value_commitment: &ClientCommitmentSchemeOutput<Conf>,
+        inputs: &PublicInputsExpand<Conf, TIME_BYTES, RANDOMNESS_BYTES>,
+    ) -> Result<Vec<ConstraintField<Conf>>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        ClientCommitmentSchemeOutput<Conf>: ToConstraintField<ConstraintField<Conf>>,
+    {
+        let mut public_inputs = ldp_value_commitment
+            .to_field_elements()
+            .ok_or(GenericError::ConversionError)?;
+        public_inputs.extend(Self::unblinded_public_inputs(inputs)?);
+        Ok(public_inputs)
+    }
+
+    /// Public inputs that follow the (possibly blinded) `ldp_value`.
+    fn unblinded_public_inputs(
+        inputs: &PublicInputsExpand<Conf, TIME_BYTES, RANDOMNESS_BYTES>,
+    ) -> Result<Vec<ConstraintField<Conf>>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>>,
+    {
+        let mut public_inputs = Vec::new();
         public_inputs.extend_from_slice(
-            &ldp_value
-                .to_le_bytes()
-                .to_field_elements()
-                .ok_or(GenericError::ConversionError)?,
-        );
-        public_inputs.extend_from_slice(
-            &time_bounds
+            &inputs
+                .time_bounds
                 .0
                 .to_field_elements()
                 .ok_or(GenericError::ConversionError)?,
         );
         public_inputs.extend_from_slice(
-            &time_bounds
+            &inputs
+                .time_bounds
                 .1
                 .to_field_elements()
                 .ok_or(GenericError::ConversionError)?,
         );
         public_inputs.extend_from_slice(
-            &client_sig_pk
+            &inputs
+                .client_sig_pk
                 .to_field_elements()
                 .ok_or(GenericError::ConversionError)?,
         );
         public_inputs.extend_from_slice(
-            &client_merkle_tree_root
+            &inputs
+                .client_merkle_tree_root
                 .to_field_elements()
                 .ok_or(GenericError::ConversionError)?,
         );
         public_inputs.extend_from_slice(
-            &index
+            &inputs
+                .index
                 .to_le_bytes()
                 .to_field_elements()
                 .ok_or(GenericError::ConversionError)?,
         );
         public_inputs.extend_from_slice(
-            &server_randomness
+            &inputs
+                .server_randomness
                 .to_field_elements()
                 .ok_or(GenericError::ConversionError)?,
         );
 
+        Ok(public_inputs)
+    }
+
+    /// Machine-readable description of the public inputs of the Expand circuit, in the order in
+    /// which `public_inputs` assembles them.
+    pub fn public_input_schema() -> Result<Vec<PublicInputDescriptor>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>> + Default,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>> + Default,
+    {
+        let mut schema = vec![PublicInputDescriptor::le_bytes::<ConstraintField<Conf>>(
+            "ldp_value",
+            8,
+        )];
+        schema.extend(Self::unblinded_public_input_schema()?);
+        Ok(schema)
+    }
+
+    /// Machine-readable description of the public inputs of the Expand circuit in the blinded
+    /// mode, in the order in which `blinded_public_inputs` assembles them.
+    pub fn blinded_public_input_schema() -> Result<Vec<PublicInputDescriptor>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>> + Default,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>> + Default,
+        ClientCommitmentSchemeOutput<Conf>: ToConstraintField<ConstraintField<Conf>> + Default,
+    {
+        let mut schema = vec![PublicInputDescriptor::native::<ConstraintField<Conf>, _>(
+            "ldp_value_commitment",
+            &ClientCommitmentSchemeOutput::<Conf>::default(),
+        )?];
+        schema.extend(Self::unblinded_public_input_schema()?);
+        Ok(schema)
+    }
+
+    /// Description of the public inputs that follow the (possibly blinded) `ldp_value`.
+    fn unblinded_public_input_schema() -> Result<Vec<PublicInputDescriptor>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>> + Default,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>> + Default,
+    {
+        Ok(vec![
+            PublicInputDescriptor::le_bytes::<ConstraintField<Conf>>(
+                "time_lower_bound",
+                TIME_BYTES,
+            ),
+            PublicInputDescriptor::le_bytes::<ConstraintField<Conf>>(
+                "time_upper_bound",
+                TIME_BYTES,
+            ),
+            // the number of field elements of these does not depend on their value
+            PublicInputDescriptor::native::<ConstraintField<Conf>, _>(
+                "client_sig_pk",
+                &ClientSignatureSchemePublicKey::<Conf>::default(),
+            )?,
+            PublicInputDescriptor::native::<ConstraintField<Conf>, _>(
+                "client_merkle_tree_root",
+                &ClientMerkleTreeRoot::<Conf>::default(),
+            )?,
+            PublicInputDescriptor::le_bytes::<ConstraintField<Conf>>(
+                "index",
+                std::mem::size_of::<usize>(),
+            ),
+            PublicInputDescriptor::le_bytes::<ConstraintField<Conf>>(
+                "server_randomness",
+                RANDOMNESS_BYTES,
+            ),
+        ])
+    }
+
+    pub fn verify(
+        verifying_key: &VerifyingKey<Conf>,
+        proof: &Proof<Conf>,
+        ldp_value: u64,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        client_sig_pk: &ClientSignatureSchemePublicKey<Conf>,
+        client_merkle_tree_root: &ClientMerkleTreeRoot<Conf>,
+        index: usize,
+        server_randomness: [u8; RANDOMNESS_BYTES],
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<bool, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>>,
+    {
+        Self::verify_with_inputs(
+            verifying_key,
+            proof,
+            ldp_value,
+            &PublicInputsExpand {
+                time_bounds,
+                client_sig_pk,
+                client_merkle_tree_root,
+                index,
+                server_randomness,
+            },
+            zkp_rng,
+        )
+    }
+
+    /// Verify a proof like `verify`, with all public inputs but `ldp_value` bundled in `inputs`.
+    pub fn verify_with_inputs(
+        verifying_key: &VerifyingKey<Conf>,
+        proof: &Proof<Conf>,
+        ldp_value: u64,
+        inputs: &PublicInputsExpand<Conf, TIME_BYTES, RANDOMNESS_BYTES>,
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<bool, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>>,
+    {
+        let public_inputs = Self::public_inputs_with(ldp_value, inputs)?;
         Conf::ZKPScheme::verify(verifying_key, &public_inputs, proof, zkp_rng)
     }
+
+    /// Verify a proof of the blinded mode, which only exposes `ldp_value_commitment`.
+    pub fn verify_blinded(
+        verifying_key: &VerifyingKey<Conf>,
+        proof: &Proof<Conf>,
+        ldp_value_commitment: &ClientCommitmentSchemeOutput<Conf>,
+        inputs: &PublicInputsExpand<Conf, TIME_BYTES, RANDOMNESS_BYTES>,
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<bool, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        ClientCommitmentSchemeOutput<Conf>: ToConstraintField<ConstraintField<Conf>>,
+    {
+        let public_inputs = Self::blinded_public_inputs(ldp_value_commitment, inputs)?;
+        Conf::ZKPScheme::verify(verifying_key, &public_inputs, proof, zkp_rng)
+    }
+
+    /// Verify a bundled submission, which contains the proof and all of its public inputs.
+    pub fn verify_bundle(
+        verifying_key: &VerifyingKey<Conf>,
+        bundle: &VerifiableBundleExpand<Conf, TIME_BYTES, RANDOMNESS_BYTES>,
+    ) -> Result<bool, Error>
+    where
+        Proof<Conf>: CanonicalDeserialize,
+        ClientSignatureSchemePublicKey<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>>,
+    {
+        Self::verify_with_inputs(
+            verifying_key,
+            &bundle.proof,
+            bundle.ldp_value,
+            &PublicInputsExpand {
+                time_bounds: (bundle.time_lower_bound, bundle.time_upper_bound),
+                client_sig_pk: &bundle.client_sig_pk,
+                client_merkle_tree_root: &bundle.client_merkle_tree_root,
+                index: bundle.index,
+                server_randomness: bundle.server_randomness,
+            },
+            &mut ZKPRng::<Conf>::new(),
+        )
+    }
+}
+
+/// Check that `merkle_path` leads to the leaf at `index`, as enforced by the Expand circuit.
+pub(crate) fn check_merkle_path_index<Conf: Config>(
+    merkle_path: &ClientMerkleTreePath<Conf>,
+    index: usize,
+) -> Result<(), Error> {
+    // the circuit only sees as many bits of the leaf position as the path is deep
+    let depth = merkle_path.auth_path.len() + 1;
+    if merkle_path.leaf_index == index && index.checked_shr(depth as u32).unwrap_or(0) == 0 {
+        Ok(())
+    } else {
+        Err(ClientError::MerklePathIndexMismatch {
+            index,
+            leaf_position: merkle_path.leaf_index,
+        })?
+    }
 }
 
 impl<
@@ -236,12 +639,32 @@ impl<
         // allocate constants
         let params = ParametersExpandVar::<_, ConfG>::new_constant(cs.clone(), &self.params)?;
 
-        // allocate public inputs
-        let ldp_value = FpVar::new_input(cs.clone(), || {
-            self.ldp_value
-                .map(|x| ConstraintField::<Conf>::from(x))
-                .ok_or(SynthesisError::AssignmentMissing)
-        })?;
+        // allocate public inputs (in the blinded mode, `ldp_value` is a witness and only its
+        // commitment is public)
+        let ldp_value_commitment = if self.blinded {
+            Some(ClientCommitmentSchemeOutputVar::<_, ConfG>::new_input(
+                cs.clone(),
+                || {
+                    self.ldp_value_commitment
+                        .ok_or(SynthesisError::AssignmentMissing)
+                },
+            )?)
+        } else {
+            None
+        };
+        let ldp_value = FpVar::new_variable(
+            cs.clone(),
+            || {
+                self.ldp_value
+                    .map(|x| ConstraintField::<Conf>::from(x))
+                    .ok_or(SynthesisError::AssignmentMissing)
+            },
+            if self.blinded {
+                AllocationMode::Witness
+            } else {
+                AllocationMode::Input
+            },
+        )?;
         let time_lower_bound = FpVar::new_input(cs.clone(), || {
             self.time_bounds
                 .as_ref()
@@ -320,17 +743,15 @@ impl<
 
         // 2: ldp_value = LDP.Apply(true_value, randomness)
         let k = FpVar::new_constant(cs.clone(), ConstraintField::<Conf>::from(K))?;
+        // exclusive upper bound of the random values (see `bin_boundaries`)
         let max_bound = FpVar::new_constant(
             cs.clone(),
-            ConstraintField::<Conf>::from_le_bytes_mod_order(&[u8::MAX; INPUT_BYTES]),
+            ConstraintField::<Conf>::from_le_bytes_mod_order(
+                &randomness_limit::<INPUT_BYTES>().to_bytes_le(),
+            ),
         )?;
         let boundary_gap = ConstraintField::<Conf>::from_le_bytes_mod_order(
-            &if IS_REAL_INPUT {
-                BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / (K + 1)
-            } else {
-                BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / K
-            }
-            .to_bytes_le(),
+            &boundary_gap::<INPUT_BYTES, K, IS_REAL_INPUT>().to_bytes_le(),
         );
         let computed_ldp_value = FpVar::new_witness(cs.clone(), || {
             Ok(ConstraintField::<Conf>::from({
@@ -339,24 +760,9 @@ impl<
                     .iter_mut()
                     .zip(self.client_randomness.unwrap())
                     .for_each(|(client_byte, server_byte)| *client_byte ^= server_byte);
-                let boundary_gap = if IS_REAL_INPUT {
-                    BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / (K + 1)
-                } else {
-                    BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / K
-                };
-                let computed_ldp_value =
-                    BigUint::from_bytes_le(&randomness[GAMMA_BYTES..GAMMA_BYTES + INPUT_BYTES])
-                        / boundary_gap;
-                let computed_ldp_value = if computed_ldp_value.is_zero() {
-                    0
-                } else {
-                    computed_ldp_value.to_u64_digits()[0]
-                };
-                if IS_REAL_INPUT {
-                    min(computed_ldp_value, K)
-                } else {
-                    min(computed_ldp_value, K - 1) + 1
-                }
+                randomized_ldp_value::<INPUT_BYTES, K, IS_REAL_INPUT>(
+                    &randomness[GAMMA_BYTES..GAMMA_BYTES + INPUT_BYTES],
+                )
             }))
         })?;
 
@@ -395,50 +801,11 @@ impl<
 
         // cast true_value if is_real_input
         let true_value_computed = if IS_REAL_INPUT {
-            let true_value_times_k = &true_value * k;
-            let multiplicand = FpVar::new_witness(cs.clone(), || {
-                Ok(ConstraintField::<Conf>::from_le_bytes_mod_order(
-                    &(BigUint::from_bytes_le(&self.true_value.unwrap()) * K
-                        / BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]))
-                    .to_bytes_le(),
-                ))
-            })?;
-            let remainder = FpVar::new_witness(cs.clone(), || {
-                Ok(true_value_times_k.value().unwrap()
-                    - multiplicand.value().unwrap()
-                        * ConstraintField::<Conf>::from_le_bytes_mod_order(&[u8::MAX; INPUT_BYTES]))
-            })?;
-            let true_value_randomness = Boolean::le_bits_to_fp_var(
-                &randomness[GAMMA_BYTES + INPUT_BYTES..GAMMA_BYTES + 2 * INPUT_BYTES]
-                    .to_bits_le()?,
-            )?;
-
-            // true_value_randomness <= remainder
-            let true_value_random_bit =
-                remainder.is_cmp_unchecked(&true_value_randomness, Ordering::Greater, true)?;
-            let true_value_computed = FpVar::new_witness(cs.clone(), || {
-                Ok(multiplicand.value().unwrap()
-                    + if true_value_random_bit.value().unwrap() {
-                        ConstraintField::<Conf>::one()
-                    } else {
-                        ConstraintField::<Conf>::zero()
-                    })
-            })?;
-
-            true_value_computed.conditional_enforce_equal(
-                &(&multiplicand + ConstraintField::<Conf>::one()),
-                &true_value_random_bit,
-            )?;
-            true_value_computed
-                .conditional_enforce_equal(&multiplicand, &true_value_random_bit.not())?;
-            remainder.enforce_equal(
-                &(true_value_times_k
-                    - multiplicand.clone()
-                        * ConstraintField::<Conf>::from_le_bytes_mod_order(
-                            &[u8::MAX; INPUT_BYTES],
-                        )),
-            )?;
-            true_value_computed
+            real_input_rounding_var::<_, INPUT_BYTES, K>(
+                cs.clone(),
+                &true_value,
+                &randomness[GAMMA_BYTES + INPUT_BYTES..GAMMA_BYTES + 2 * INPUT_BYTES],
+            )?
         } else {
             true_value.clone()
         };
@@ -446,8 +813,11 @@ impl<
         ldp_value.conditional_enforce_equal(&computed_ldp_value, &ldp_bit)?;
 
         // 3: true_value_signature =?= ClientSig.Sign(client_sig_pk, true_value)
-        let mut message_bytes = true_value.to_bytes()?[0..INPUT_BYTES].to_vec();
-        message_bytes.extend_from_slice(&time.to_bytes()?[0..TIME_BYTES]);
+        let message_bytes = signed_input_value_message_var::<Conf, ConfG>(
+            params.client_input_hash_scheme.as_ref(),
+            &true_value.to_bytes()?[0..INPUT_BYTES],
+            &time.to_bytes()?[0..TIME_BYTES],
+        )?;
 
         let true_value_signature_correct = ConfG::ClientSignatureVerifyGadget::verify(
             &params.client_signature_scheme,
@@ -484,7 +854,33 @@ impl<
         let time_upper_bound_check =
             time.is_cmp_unchecked(&time_upper_bound, Ordering::Less, true)?;
 
-        // Check correctness of `=?=` constraints (i.e. 2, 3, 5, and 6)
+        // 7 (blinded mode only): ldp_value_commitment =?= Comm(ldp_value, ldp_value_commitment_randomness)
+        let ldp_value_commitment_correct = match ldp_value_commitment {
+            Some(ldp_value_commitment) => {
+                let ldp_value_commitment_randomness =
+                    ClientCommitmentSchemeRandomnessVar::<_, ConfG>::new_witness(
+                        cs.clone(),
+                        || {
+                            self.ldp_value_commitment_randomness
+                                .ok_or(SynthesisError::AssignmentMissing)
+                        },
+                    )?;
+                // commit to ldp_value as a u64, like the public input in the unblinded mode
+                let ldp_value_bytes = ldp_value.to_bytes()?;
+                for byte in &ldp_value_bytes[8..] {
+                    byte.enforce_equal(&UInt8::constant(0))?;
+                }
+                ConfG::ClientCommitmentVerifyGadget::commit(
+                    &params.client_commitment_scheme,
+                    &ldp_value_bytes[0..8],
+                    &ldp_value_commitment_randomness,
+                )?
+                .is_eq(&ldp_value_commitment)?
+            }
+            None => Boolean::TRUE,
+        };
+
+        // Check correctness of `=?=` constraints (i.e. 2, 3, 5, 6, and 7)
         Boolean::kary_and(&[
             true_value_signature_correct,
             is_member,
@@ -492,6 +888,7 @@ impl<
             upper_bound_check,
             time_lower_bound_check,
             time_upper_bound_check,
+            ldp_value_commitment_correct,
         ])?
         .enforce_equal(&Boolean::TRUE)?;
 
@@ -505,3 +902,520 @@ impl<
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{check_merkle_path_index, CircuitExpand, PublicInputsExpand, WitnessExpand};
+    use crate::client::{ClientExpand, ClientExpandStorage, RandomizationInputExpand};
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::messages::expand::{
+        GenerateRandomnessMessageClientExpand, LdpValueRevealMessageExpand, VerifiableBundleExpand,
+        VerifiableRandomizationMessageExpand,
+    };
+    use crate::messages::{derive_prf_eval_points, InputValueSignatureInput};
+    use crate::prelude::*;
+    use crate::primitives::ldp::apply_ldp;
+    use crate::server::ServerExpand;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+    type Circuit = CircuitExpand<Conf, ConfG, 2, 2, 1, 2, 4, 4, false>;
+
+    /// Build the circuit for input value 3 at `time` (within the time bounds `([0], [1])` only for
+    /// time 1) from scratch, i.e., without running the protocol. The input value signature is
+    /// over `signed_value` instead of the input value, and the client randomness is committed to
+    /// in the first of the two leaves of the Merkle tree.
+    fn hand_built_circuit(time: u8, signed_value: [u8; 2], rng: &mut ChaChaRng) -> Circuit {
+        let parameters =
+            ParametersExpand::<Conf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let true_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: signed_value,
+                input_value_time: [time],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+
+        let client_randomness: [u8; 4] = rng.gen();
+        let server_randomness: [u8; 4] = rng.gen();
+        let commitment_randomness = ClientCommitmentSchemeRandomness::<Conf>::rand(rng);
+        let leaves = [
+            <Conf as Config>::ClientCommitmentScheme::commit(
+                &parameters.client_commitment_scheme,
+                &client_randomness,
+                &commitment_randomness,
+            )
+            .unwrap(),
+            <Conf as Config>::ClientCommitmentScheme::commit(
+                &parameters.client_commitment_scheme,
+                &rng.gen::<[u8; 4]>(),
+                &ClientCommitmentSchemeRandomness::<Conf>::rand(rng),
+            )
+            .unwrap(),
+        ];
+        let merkle_tree = ClientMerkleTree::<Conf>::new(
+            &parameters.client_merkle_tree_scheme.leaf_crh_params,
+            &parameters.client_merkle_tree_scheme.two_to_one_crh_params,
+            leaves,
+        )
+        .unwrap();
+        let mut randomness = client_randomness;
+        randomness
+            .iter_mut()
+            .zip(server_randomness)
+            .for_each(|(client_byte, server_byte)| *client_byte ^= server_byte);
+        let ldp_value = apply_ldp::<2, 2, 4, false>(
+            &BigUint::from(3_u8),
+            &randomness,
+            &parameters.gamma_as_bytes().unwrap(),
+        );
+
+        Circuit::assigned(
+            parameters,
+            WitnessExpand {
+                ldp_value,
+                time_bounds: ([0], [1]),
+                true_value: [3, 0],
+                time: [time],
+                true_value_signature,
+                client_sig_pk,
+                server_randomness,
+                client_randomness,
+                client_randomness_commitment_randomness: commitment_randomness,
+            },
+            ClientExpandStorage {
+                index: 0,
+                merkle_tree: Some(merkle_tree),
+                ..ClientExpandStorage::new()
+            },
+        )
+        .unwrap()
+    }
+
+    fn is_satisfied(circuit: Circuit) -> bool {
+        let cs = ConstraintSystem::<ConstraintField<Conf>>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    /// Run the protocol for a single submission and let the server bundle it. Returns the
+    /// serialized verifying key and bundle.
+    fn bundled_submission() -> (Vec<u8>, Vec<u8>) {
+        let rng = &mut ChaChaRng::from_seed([17; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([18; 32]);
+        let parameters = ParametersExpand::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) = Circuit::keygen(parameters.clone(), zkp_rng).unwrap();
+        let server = ServerExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            verifying_key.clone(),
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let prf_eval_points = derive_prf_eval_points::<Conf>([2; 32], 1).unwrap();
+        let time_bounds = ([0], [1]);
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                time_bounds,
+                [1],
+                BigUint::from(3_u8),
+                input_value_signature,
+                &prf_eval_points,
+                0,
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+
+        let bundle = server
+            .verifiable_bundle(&submission, time_bounds, &prf_eval_points, 0)
+            .unwrap()
+            .unwrap();
+        let mut verifying_key_bytes = vec![];
+        verifying_key
+            .serialize_compressed(&mut verifying_key_bytes)
+            .unwrap();
+        let mut bundle_bytes = vec![];
+        bundle.serialize_compressed(&mut bundle_bytes).unwrap();
+        (verifying_key_bytes, bundle_bytes)
+    }
+
+    #[test]
+    fn bundle_verifies_with_only_verifying_key() {
+        let (verifying_key, bundle) = bundled_submission();
+        let verifying_key =
+            VerifyingKey::<Conf>::deserialize_compressed(verifying_key.as_slice()).unwrap();
+        let mut bundle =
+            VerifiableBundleExpand::<Conf, 1, 4>::deserialize_compressed(bundle.as_slice())
+                .unwrap();
+        assert!(Circuit::verify_bundle(&verifying_key, &bundle).unwrap());
+
+        // a bundle with a different LDP value does not verify
+        bundle.ldp_value = bundle.ldp_value % 4 + 1;
+        assert!(!Circuit::verify_bundle(&verifying_key, &bundle).unwrap());
+    }
+
+    #[test]
+    fn blinded_submission_is_opened_by_reveal() {
+        let rng = &mut ChaChaRng::from_seed([29; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([30; 32]);
+        let parameters = ParametersExpand::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            Circuit::keygen_blinded(parameters.clone(), zkp_rng).unwrap();
+        // blinded submissions are verified with the blinded key only
+        let server = ServerExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            Default::default(),
+            rng,
+        )
+        .unwrap()
+        .with_blinded_verifying_key(verifying_key);
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let prf_eval_points = derive_prf_eval_points::<Conf>([4; 32], 1).unwrap();
+        let time_bounds = ([0], [1]);
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let submission = client
+            .blinded_randomization_create::<ConfG, _>(
+                RandomizationInputExpand {
+                    time_bounds,
+                    input_value_time: [1],
+                    input_value: BigUint::from(3_u8),
+                    input_value_signature,
+                    prf_eval_points: &prf_eval_points,
+                    index: 0,
+                },
+                rng,
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+
+        // commit phase
+        let ldp_value_commitment = server
+            .blinded_randomization_verify::<ConfG>(
+                &submission,
+                time_bounds,
+                &prf_eval_points,
+                0,
+                zkp_rng,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        // the proof is bound to the index
+        assert!(server
+            .blinded_randomization_verify::<ConfG>(
+                &submission,
+                time_bounds,
+                &prf_eval_points,
+                1,
+                zkp_rng,
+                false,
+            )
+            .unwrap()
+            .is_none());
+
+        // reveal phase
+        let reveal = client.ldp_value_reveal_create(0).unwrap();
+        let (valid, ldp_value) = server
+            .ldp_value_reveal_verify(&ldp_value_commitment, &reveal)
+            .unwrap();
+        assert!(valid);
+        assert!((1..=4).contains(&ldp_value));
+        assert!(client.ldp_value_reveal_create(0).is_err());
+
+        // revealing a different LDP value does not open the commitment
+        let mut reveal =
+            LdpValueRevealMessageExpand::deserialize_compressed(reveal.as_slice()).unwrap();
+        reveal.ldp_value = ldp_value % 4 + 1;
+        let mut reveal_bytes = vec![];
+        reveal.serialize_compressed(&mut reveal_bytes).unwrap();
+        assert_eq!(
+            server
+                .ldp_value_reveal_verify(&ldp_value_commitment, &reveal_bytes)
+                .unwrap(),
+            (false, u64::MAX)
+        );
+    }
+
+    #[test]
+    fn verified_context_matches_proven_values() {
+        let rng = &mut ChaChaRng::from_seed([64; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([65; 32]);
+        let parameters = ParametersExpand::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) = Circuit::keygen(parameters.clone(), zkp_rng).unwrap();
+        let server = ServerExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            verifying_key,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let prf_eval_points = derive_prf_eval_points::<Conf>([6; 32], 1).unwrap();
+        let time_bounds = ([0], [1]);
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                time_bounds,
+                [1],
+                BigUint::from(3_u8),
+                input_value_signature,
+                &prf_eval_points,
+                0,
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+
+        let context = server
+            .verifiable_randomization_verify_context::<ConfG>(
+                &submission,
+                time_bounds,
+                &prf_eval_points,
+                0,
+                zkp_rng,
+                false,
+            )
+            .unwrap()
+            .unwrap();
+        let client_message = GenerateRandomnessMessageClientExpand::<Conf>::deserialize_compressed(
+            client_message.as_slice(),
+        )
+        .unwrap();
+        let submission = VerifiableRandomizationMessageExpand::<Conf, 2>::deserialize_compressed(
+            submission.as_slice(),
+        )
+        .unwrap();
+        assert!(context.root == client_message.client_merkle_tree_root);
+        assert_eq!(context.index, 0);
+        assert_eq!(context.ldp_value, submission.ldp_value);
+        assert_eq!(context.time_bounds, time_bounds);
+    }
+
+    #[test]
+    fn hand_built_witness_satisfies_constraints() {
+        let rng = &mut ChaChaRng::from_seed([95; 32]);
+        let circuit = hand_built_circuit(1, [3, 0], rng);
+        assert!(is_satisfied(circuit.clone()));
+
+        // wrong LDP value
+        let mut wrong_ldp_value = circuit;
+        wrong_ldp_value.ldp_value = wrong_ldp_value.ldp_value.map(|value| value % 4 + 1);
+        assert!(!is_satisfied(wrong_ldp_value));
+
+        // correctly signed, but outside the time bounds
+        assert!(!is_satisfied(hand_built_circuit(2, [3, 0], rng)));
+
+        // signature over a different input value
+        assert!(!is_satisfied(hand_built_circuit(1, [4, 0], rng)));
+    }
+
+    #[test]
+    fn mismatched_merkle_path_index_is_reported() {
+        let rng = &mut ChaChaRng::from_seed([24; 32]);
+        let parameters =
+            ParametersExpand::<Conf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let leaves = (0..2)
+            .map(|_| {
+                <Conf as Config>::ClientCommitmentScheme::commit(
+                    &parameters.client_commitment_scheme,
+                    &[0; 4],
+                    &ClientCommitmentSchemeRandomness::<Conf>::rand(rng),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let merkle_tree = ClientMerkleTree::<Conf>::new(
+            &parameters.client_merkle_tree_scheme.leaf_crh_params,
+            &parameters.client_merkle_tree_scheme.two_to_one_crh_params,
+            leaves,
+        )
+        .unwrap();
+        let merkle_path = merkle_tree.generate_proof(1).unwrap();
+        check_merkle_path_index::<Conf>(&merkle_path, 1).unwrap();
+
+        let error = check_merkle_path_index::<Conf>(&merkle_path, 0).unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Client(ClientError::MerklePathIndexMismatch {
+                index: 0,
+                leaf_position: 1
+            })
+        ));
+        assert_eq!(
+            error.to_string(),
+            "The Merkle path leads to leaf 1, but the proof is for index 0."
+        );
+
+        // an index beyond the leaves of the tree cannot be encoded in the path
+        let mut merkle_path = merkle_path;
+        merkle_path.leaf_index = 5;
+        assert!(check_merkle_path_index::<Conf>(&merkle_path, 5).is_err());
+    }
+
+    #[test]
+    fn public_input_schema_matches_public_inputs() {
+        type Circuit = CircuitExpand<Conf, ConfG, 2, 2, 1, 2, 40, 4, false>;
+        let rng = &mut ChaChaRng::from_seed([23; 32]);
+        let parameters =
+            ParametersExpand::<Conf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (client_sig_pk, _) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let public_inputs = Circuit::public_inputs(
+            3,
+            ([0], [1]),
+            &client_sig_pk,
+            &Default::default(),
+            5,
+            [7; 40],
+        )
+        .unwrap();
+
+        let schema = Circuit::public_input_schema().unwrap();
+        assert_eq!(
+            schema
+                .iter()
+                .map(|descriptor| descriptor.field_elements)
+                .sum::<usize>(),
+            public_inputs.len()
+        );
+        // 40 bytes of server randomness do not fit into a single field element
+        assert_eq!(
+            schema.last().unwrap().to_string(),
+            "server_randomness: 40 LE bytes as 2 field element(s)"
+        );
+
+        // the blinded layout has the commitment in place of ldp_value
+        let ldp_value_commitment = <Conf as Config>::ClientCommitmentScheme::commit(
+            &parameters.client_commitment_scheme,
+            &3_u64.to_le_bytes(),
+            &Default::default(),
+        )
+        .unwrap();
+        let blinded_public_inputs = Circuit::blinded_public_inputs(
+            &ldp_value_commitment,
+            &PublicInputsExpand {
+                time_bounds: ([0], [1]),
+                client_sig_pk: &client_sig_pk,
+                client_merkle_tree_root: &Default::default(),
+                index: 5,
+                server_randomness: [7; 40],
+            },
+        )
+        .unwrap();
+        let blinded_schema = Circuit::blinded_public_input_schema().unwrap();
+        assert_eq!(
+            blinded_schema
+                .iter()
+                .map(|descriptor| descriptor.field_elements)
+                .sum::<usize>(),
+            blinded_public_inputs.len()
+        );
+        assert_eq!(blinded_schema[0].name, "ldp_value_commitment");
+        assert_eq!(blinded_schema[1..], schema[1..]);
+    }
+}
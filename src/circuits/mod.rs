@@ -3,8 +3,790 @@
 pub mod base;
 pub use base::*;
 
+pub mod base_laplace;
+pub use base_laplace::*;
+
 pub mod expand;
 pub use expand::*;
 
 pub mod shuffle;
 pub use shuffle::*;
+
+use crate::messages::INPUT_VALUE_SIGNATURE_DOMAIN_TAG;
+use crate::prelude::{constraints::*, *};
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::uint8::UInt8;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError, SynthesisMode,
+};
+use num_bigint::BigUint;
+use std::cmp::Ordering;
+
+/// Assemble the input value signature message inside the circuit. This mirrors
+/// [`crate::messages::InputValueSignatureInput::to_bytes`].
+pub(crate) fn input_value_signature_input_var<F: PrimeField>(
+    input_value: &[UInt8<F>],
+    input_value_time: &[UInt8<F>],
+) -> Vec<UInt8<F>> {
+    let mut message_bytes = UInt8::constant_vec(&INPUT_VALUE_SIGNATURE_DOMAIN_TAG);
+    message_bytes.extend(UInt8::constant_vec(
+        &(input_value.len() as u16).to_le_bytes(),
+    ));
+    message_bytes.extend_from_slice(input_value);
+    message_bytes.extend(UInt8::constant_vec(
+        &(input_value_time.len() as u16).to_le_bytes(),
+    ));
+    message_bytes.extend_from_slice(input_value_time);
+    message_bytes
+}
+
+/// Assemble the bytes whose signature is verified inside the circuit, i.e., the input value
+/// signature message or its hash if `client_input_hash_scheme` is set. This mirrors
+/// [`crate::messages::InputValueSignatureInput::signed_bytes`].
+pub(crate) fn signed_input_value_message_var<Conf: Config, ConfG: ConfigGadget<Conf>>(
+    client_input_hash_scheme: Option<&ClientInputHashParametersVar<Conf, ConfG>>,
+    input_value: &[UInt8<ConstraintField<Conf>>],
+    input_value_time: &[UInt8<ConstraintField<Conf>>],
+) -> Result<Vec<UInt8<ConstraintField<Conf>>>, SynthesisError> {
+    let message_bytes = input_value_signature_input_var(input_value, input_value_time);
+    match client_input_hash_scheme {
+        None => Ok(message_bytes),
+        Some(parameters) => {
+            // hash in chained blocks, like `signed_bytes`
+            let block_bytes = Conf::CLIENT_INPUT_HASH_BLOCK_BYTES;
+            let (block, mut rest) = message_bytes.split_at(message_bytes.len().min(block_bytes));
+            let mut digest =
+                ConfG::ClientInputHashGadget::evaluate(parameters, block)?.to_bytes()?;
+            while !rest.is_empty() {
+                if digest.len() >= block_bytes {
+                    return Err(SynthesisError::Unsatisfiable);
+                }
+                let (block, remaining) = rest.split_at(rest.len().min(block_bytes - digest.len()));
+                digest.extend_from_slice(block);
+                digest = ConfG::ClientInputHashGadget::evaluate(parameters, &digest)?.to_bytes()?;
+                rest = remaining;
+            }
+            Ok(digest)
+        }
+    }
+}
+
+/// Scale the real input value to `[0, K]` with randomized rounding inside the circuit, i.e.,
+/// `floor(true_value * K / max)` plus one if the `random_input` bytes are at most the remainder.
+/// This mirrors the real-input branch of [`crate::primitives::ldp::apply_ldp`], including the
+/// rounding up for `random_input == remainder`.
+pub(crate) fn real_input_rounding_var<F: PrimeField, const INPUT_BYTES: usize, const K: u64>(
+    cs: ConstraintSystemRef<F>,
+    true_value: &FpVar<F>,
+    random_input: &[UInt8<F>],
+) -> Result<FpVar<F>, SynthesisError> {
+    let max_value = F::from_le_bytes_mod_order(&[u8::MAX; INPUT_BYTES]);
+    let true_value_times_k = true_value * F::from(K);
+    let multiplicand = FpVar::new_witness(cs.clone(), || {
+        Ok(F::from_le_bytes_mod_order(
+            &(Into::<BigUint>::into(true_value.value()?.into_bigint()) * K
+                / BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]))
+            .to_bytes_le(),
+        ))
+    })?;
+    let remainder = FpVar::new_witness(cs.clone(), || {
+        Ok(true_value_times_k.value()? - multiplicand.value()? * max_value)
+    })?;
+    let true_value_randomness = Boolean::le_bits_to_fp_var(&random_input.to_bits_le()?)?;
+
+    // true_value_randomness <= remainder
+    let true_value_random_bit =
+        remainder.is_cmp_unchecked(&true_value_randomness, Ordering::Greater, true)?;
+    let true_value_computed = FpVar::new_witness(cs, || {
+        Ok(multiplicand.value()?
+            + if true_value_random_bit.value()? {
+                F::one()
+            } else {
+                F::zero()
+            })
+    })?;
+
+    true_value_computed
+        .conditional_enforce_equal(&(&multiplicand + F::one()), &true_value_random_bit)?;
+    true_value_computed.conditional_enforce_equal(&multiplicand, &true_value_random_bit.not())?;
+    remainder.enforce_equal(&(true_value_times_k - multiplicand * max_value))?;
+    Ok(true_value_computed)
+}
+
+/// Proving and verifying key of a circuit.
+pub type Keys<Conf> = (ProvingKey<Conf>, VerifyingKey<Conf>);
+
+/// Keys of the Base, Expand, and Shuffle circuits (in this order), as generated by `keygen_all`.
+pub type AllKeys<Conf> = (Keys<Conf>, Keys<Conf>, Keys<Conf>);
+
+/// Generate the keys of all three schemes (Base, Expand, and Shuffle) in one go, e.g., for a
+/// deployment that offers every scheme with the same byte widths.
+///
+/// Every circuit is set up with its own rng out of `zkp_rngs` (in the order Base, Expand,
+/// Shuffle). With the `parallel` feature, the circuits are set up concurrently, which mostly saves
+/// the time of the Base and Shuffle setup next to the (much slower) Expand setup; without it, they
+/// are set up one after the other. As every circuit only uses its own rng, the keys are the same
+/// either way. Note that the circuits differ in size, such that the QAP reduction (i.e., the
+/// evaluation domain and the Lagrange coefficients derived from it) cannot be reused across them,
+/// and the `ProofSystem` interface does not expose it anyway.
+pub fn keygen_all<
+    Conf: Config,
+    ConfG: ConfigGadget<Conf>,
+    const MT_DEPTH: usize,
+    const INPUT_BYTES: usize,
+    const TIME_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+    const K: u64,
+    const IS_REAL_INPUT: bool,
+>(
+    base_params: ParametersBase<Conf, GAMMA_BYTES>,
+    expand_params: ParametersExpand<Conf, GAMMA_BYTES>,
+    shuffle_params: ParametersShuffle<Conf, GAMMA_BYTES>,
+    zkp_rngs: [&mut ZKPRng<Conf>; 3],
+) -> Result<AllKeys<Conf>, Error>
+where
+    ParametersBase<Conf, GAMMA_BYTES>: Send,
+    ParametersExpand<Conf, GAMMA_BYTES>: Send,
+    ParametersShuffle<Conf, GAMMA_BYTES>: Send,
+    ZKPRng<Conf>: Send,
+    Keys<Conf>: Send,
+{
+    let [base_rng, expand_rng, shuffle_rng] = zkp_rngs;
+    // `Error` cannot be sent between threads, so errors are passed on as their message
+    let (base_keys, expand_keys, shuffle_keys) = join(
+        move || {
+            CircuitBase::<
+                Conf,
+                ConfG,
+                INPUT_BYTES,
+                TIME_BYTES,
+                GAMMA_BYTES,
+                RANDOMNESS_BYTES,
+                K,
+                IS_REAL_INPUT,
+            >::keygen(base_params, base_rng)
+            .map_err(|error| error.to_string())
+        },
+        move || {
+            CircuitExpand::<
+                Conf,
+                ConfG,
+                MT_DEPTH,
+                INPUT_BYTES,
+                TIME_BYTES,
+                GAMMA_BYTES,
+                RANDOMNESS_BYTES,
+                K,
+                IS_REAL_INPUT,
+            >::keygen(expand_params, expand_rng)
+            .map_err(|error| error.to_string())
+        },
+        move || {
+            CircuitShuffle::<
+                Conf,
+                ConfG,
+                INPUT_BYTES,
+                TIME_BYTES,
+                GAMMA_BYTES,
+                RANDOMNESS_BYTES,
+                K,
+                IS_REAL_INPUT,
+            >::keygen(shuffle_params, shuffle_rng)
+            .map_err(|error| error.to_string())
+        },
+    );
+    Ok((base_keys?, expand_keys?, shuffle_keys?))
+}
+
+/// Run the three closures concurrently.
+#[cfg(feature = "parallel")]
+fn join<A: Send, B: Send, C: Send>(
+    a: impl FnOnce() -> A + Send,
+    b: impl FnOnce() -> B + Send,
+    c: impl FnOnce() -> C + Send,
+) -> (A, B, C) {
+    let (b, (a, c)) = rayon::join(b, || rayon::join(a, c));
+    (a, b, c)
+}
+
+/// Run the three closures one after another.
+#[cfg(not(feature = "parallel"))]
+fn join<A, B, C>(a: impl FnOnce() -> A, b: impl FnOnce() -> B, c: impl FnOnce() -> C) -> (A, B, C) {
+    (a(), b(), c())
+}
+
+/// Encoding of a public input of a circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PublicInputEncoding {
+    /// Little-endian bytes, packed into field elements in chunks of as many bytes as fit into a
+    /// field element.
+    LeBytes,
+    /// The field elements of the value's `ToConstraintField` implementation (e.g., the
+    /// coordinates of a curve point).
+    Native,
+}
+
+/// Description of a single public input of a circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicInputDescriptor {
+    pub name: &'static str,
+    pub encoding: PublicInputEncoding,
+    /// Number of bytes, for inputs encoded as `LeBytes`
+    pub byte_width: Option<usize>,
+    pub field_elements: usize,
+}
+
+impl PublicInputDescriptor {
+    /// Describe a public input of `byte_width` little-endian bytes.
+    pub(crate) fn le_bytes<F: PrimeField>(name: &'static str, byte_width: usize) -> Self {
+        let bytes_per_element = ((F::MODULUS_BIT_SIZE - 1) / 8) as usize;
+        Self {
+            name,
+            encoding: PublicInputEncoding::LeBytes,
+            byte_width: Some(byte_width),
+            field_elements: byte_width.div_ceil(bytes_per_element),
+        }
+    }
+
+    /// Describe a public input that is encoded as the field elements of `value`.
+    pub(crate) fn native<F: PrimeField, T: ToConstraintField<F>>(
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            name,
+            encoding: PublicInputEncoding::Native,
+            byte_width: None,
+            field_elements: value
+                .to_field_elements()
+                .ok_or(GenericError::ConversionError)?
+                .len(),
+        })
+    }
+}
+
+impl std::fmt::Display for PublicInputDescriptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.byte_width {
+            Some(byte_width) => write!(
+                f,
+                "{}: {} LE bytes as {} field element(s)",
+                self.name, byte_width, self.field_elements
+            ),
+            None => write!(f, "{}: {} field element(s)", self.name, self.field_elements),
+        }
+    }
+}
+
+/// A relation that a circuit proves, as reported by a reference synthesis of the circuit (e.g.,
+/// [`CircuitBase::first_failing_relation`]) for diagnosing why a proof does not verify.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relation {
+    /// The client's signature on the input value and its time
+    InputValueSignature,
+    /// The commitment to the client randomness
+    RandomnessCommitment,
+    /// The LDP value as computed from the input value and the randomness
+    LdpValue,
+    /// The time of the input value lying within the time bounds
+    TimeBound,
+}
+
+/// The `=?=` relations of a circuit, each with the boolean that holds if the relation does.
+pub(crate) type Relations<F> = Vec<(Relation, Boolean<F>)>;
+
+impl std::fmt::Display for Relation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Relation::InputValueSignature => write!(f, "input value signature"),
+            Relation::RandomnessCommitment => write!(f, "randomness commitment"),
+            Relation::LdpValue => write!(f, "LDP value"),
+            Relation::TimeBound => write!(f, "time bound"),
+        }
+    }
+}
+
+/// Size of a circuit, as counted by a synthesis in setup mode (see, e.g.,
+/// [`CircuitBase::constraint_stats`]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitStats {
+    pub num_constraints: usize,
+    pub num_witness_variables: usize,
+    /// Number of instance variables, including the constant one
+    pub num_instance_variables: usize,
+    pub num_linear_combinations: usize,
+}
+
+impl std::fmt::Display for CircuitStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} constraints, {} witness variables, {} instance variables, {} linear combinations",
+            self.num_constraints,
+            self.num_witness_variables,
+            self.num_instance_variables,
+            self.num_linear_combinations
+        )
+    }
+}
+
+/// Synthesize `circuit` in setup mode, i.e., without any assignment, and count its size.
+pub(crate) fn constraint_stats<F: PrimeField, C: ConstraintSynthesizer<F>>(
+    circuit: C,
+) -> Result<CircuitStats, Error> {
+    let cs = ConstraintSystem::<F>::new_ref();
+    cs.set_mode(SynthesisMode::Setup);
+    circuit.generate_constraints(cs.clone())?;
+    let cs = cs.borrow().ok_or(SynthesisError::MissingCS)?;
+    Ok(CircuitStats {
+        num_constraints: cs.num_constraints,
+        num_witness_variables: cs.num_witness_variables,
+        num_instance_variables: cs.num_instance_variables,
+        num_linear_combinations: cs.num_linear_combinations,
+    })
+}
+
+/// Where the randomness of a proof created by a client comes from.
+pub(crate) enum ProofRandomnessSource<'a, Conf: Config> {
+    /// No proof is created, for a faster test run (see `skip_proof` of the clients).
+    Skip,
+    /// Fresh randomness from the given rng, as for `ProofSystem::prove`.
+    Sampled(&'a mut ZKPRng<Conf>),
+    /// Randomness that was recorded for an earlier proof, which is reproduced exactly.
+    Recorded(&'a ProofRandomness<Conf>),
+}
+
+impl<'a, Conf: Config> ProofRandomnessSource<'a, Conf> {
+    pub(crate) fn new(zkp_rng: &'a mut ZKPRng<Conf>, skip_proof: bool) -> Self {
+        if skip_proof {
+            Self::Skip
+        } else {
+            Self::Sampled(zkp_rng)
+        }
+    }
+
+    /// Create the proof for `circuit` with this randomness, reusing the `prover_context` if given.
+    /// Sampled randomness is only materialized for a non-empty context, such that the plain path
+    /// does not depend on `ProofSystem::prove_with_randomness`.
+    pub(crate) fn prove<C: ConstraintSynthesizer<ConstraintField<Conf>>>(
+        self,
+        proving_key: &ProvingKey<Conf>,
+        prover_context: Option<&ProverContext<Conf>>,
+        circuit: C,
+    ) -> Result<Proof<Conf>, Error> {
+        match (self, prover_context.filter(|context| !context.is_empty())) {
+            (Self::Skip, _) => Ok(Proof::<Conf>::default()),
+            (Self::Sampled(zkp_rng), None) => Conf::ZKPScheme::prove(proving_key, circuit, zkp_rng),
+            (Self::Sampled(zkp_rng), Some(prover_context)) => Conf::ZKPScheme::prove_with_context(
+                proving_key,
+                prover_context,
+                circuit,
+                &Conf::ZKPScheme::sample_proof_randomness(zkp_rng),
+            ),
+            (Self::Recorded(proof_randomness), None) => {
+                Conf::ZKPScheme::prove_with_randomness(proving_key, circuit, proof_randomness)
+            }
+            (Self::Recorded(proof_randomness), Some(prover_context)) => {
+                Conf::ZKPScheme::prove_with_context(
+                    proving_key,
+                    prover_context,
+                    circuit,
+                    proof_randomness,
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        keygen_all, real_input_rounding_var, signed_input_value_message_var, CircuitBase,
+        CircuitExpand, CircuitShuffle,
+    };
+    use crate::client::{ClientBase, ClientExpand, ClientShuffle};
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::messages::shuffle::PrfEvalPointSet;
+    use crate::messages::{derive_prf_eval_points, InputValueSignatureInput};
+    use crate::prelude::{constraints::*, *};
+    use crate::primitives::ldp::apply_ldp;
+    use crate::server::{ServerBase, ServerExpand, ServerShuffle};
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_serialize::CanonicalSerialize;
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+
+    /// Verify the input value signature in-circuit and return the number of constraints this took.
+    fn signature_constraints<const INPUT_BYTES: usize>(
+        parameters: &ParametersBase<Conf, 8>,
+        rng: &mut ChaChaRng,
+    ) -> usize {
+        let message = InputValueSignatureInput::<INPUT_BYTES, 1> {
+            input_value: [7; INPUT_BYTES],
+            input_value_time: [1],
+        };
+        let (pk, sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &sk,
+            &message
+                .signed_bytes::<Conf>(parameters.client_input_hash_scheme.as_ref())
+                .unwrap(),
+            rng,
+        )
+        .unwrap();
+
+        let cs = ConstraintSystem::<ConstraintField<Conf>>::new_ref();
+        let parameters_var =
+            ParametersBaseVar::<Conf, ConfG>::new_constant(cs.clone(), parameters).unwrap();
+        let pk_var =
+            ClientSignatureSchemePublicKeyVar::<Conf, ConfG>::new_input(cs.clone(), || Ok(pk))
+                .unwrap();
+        let signature_var =
+            ClientSignatureSchemeSignatureVar::<Conf, ConfG>::new_witness(cs.clone(), || {
+                Ok(signature)
+            })
+            .unwrap();
+        let input_value = UInt8::new_witness_vec(cs.clone(), &message.input_value).unwrap();
+        let input_value_time =
+            UInt8::new_witness_vec(cs.clone(), &message.input_value_time).unwrap();
+        let constraints_before = cs.num_constraints();
+
+        let message_var = signed_input_value_message_var::<Conf, ConfG>(
+            parameters_var.client_input_hash_scheme.as_ref(),
+            &input_value,
+            &input_value_time,
+        )
+        .unwrap();
+        <ConfG as ConfigGadget<Conf>>::ClientSignatureVerifyGadget::verify(
+            &parameters_var.client_signature_scheme,
+            &pk_var,
+            &message_var,
+            &signature_var,
+        )
+        .unwrap()
+        .enforce_equal(&Boolean::TRUE)
+        .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        cs.num_constraints() - constraints_before
+    }
+
+    /// Compare the real-input rounding of `apply_ldp` with the circuit for every input value and
+    /// random input bytes at, just below, and just above the remainder.
+    fn check_real_input_rounding_boundary<const K: u64>() {
+        for input_value in 0..=u8::MAX {
+            let remainder = (input_value as u64 * K % u8::MAX as u64) as u8;
+            let random_inputs = [
+                remainder.checked_sub(1),
+                Some(remainder),
+                remainder.checked_add(1),
+            ];
+            for random_input in random_inputs.into_iter().flatten() {
+                // gamma byte above the (zero) gamma, such that the LDP bit is not set
+                let native = apply_ldp::<1, 1, K, true>(
+                    &BigUint::from(input_value),
+                    &[u8::MAX, 0, random_input],
+                    &[0],
+                );
+
+                let cs = ConstraintSystem::<ConstraintField<Conf>>::new_ref();
+                let true_value = FpVar::new_witness(cs.clone(), || {
+                    Ok(ConstraintField::<Conf>::from(input_value))
+                })
+                .unwrap();
+                let random_input_var = UInt8::new_witness_vec(cs.clone(), &[random_input]).unwrap();
+                let circuit =
+                    real_input_rounding_var::<_, 1, K>(cs.clone(), &true_value, &random_input_var)
+                        .unwrap();
+                assert!(cs.is_satisfied().unwrap());
+                assert_eq!(
+                    circuit.value().unwrap(),
+                    ConstraintField::<Conf>::from(native),
+                    "input value {}, random input {}, remainder {}",
+                    input_value,
+                    random_input,
+                    remainder
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn real_input_rounding_agrees_at_remainder_boundary() {
+        check_real_input_rounding_boundary::<4>();
+        check_real_input_rounding_boundary::<7>();
+        check_real_input_rounding_boundary::<100>();
+    }
+
+    #[test]
+    fn hashed_input_signature_reduces_constraints() {
+        let rng = &mut ChaChaRng::from_seed([19; 32]);
+        let parameters =
+            ParametersBase::<Conf, 8>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let raw_constraints = signature_constraints::<200>(&parameters, rng);
+        let parameters = parameters.with_hashed_input(rng).unwrap();
+        let hashed_constraints = signature_constraints::<200>(&parameters, rng);
+        assert!(
+            hashed_constraints < raw_constraints,
+            "hashed: {}, raw: {}",
+            hashed_constraints,
+            raw_constraints
+        );
+    }
+
+    #[test]
+    fn hashed_input_longer_than_one_block_is_verified() {
+        let rng = &mut ChaChaRng::from_seed([20; 32]);
+        let parameters = ParametersBase::<Conf, 8>::setup(BigFloat::from_f64(0.5, 64), rng)
+            .unwrap()
+            .with_hashed_input(rng)
+            .unwrap();
+        // the encoded message of 12 + INPUT_BYTES + 1 bytes fills one block of 256 bytes exactly
+        // for INPUT_BYTES = 243, and needs a second (chained) block from INPUT_BYTES = 244 on
+        signature_constraints::<243>(&parameters, rng);
+        signature_constraints::<244>(&parameters, rng);
+        signature_constraints::<600>(&parameters, rng);
+
+        // the digest of a long message depends on all of its blocks
+        let digest = |last_byte| {
+            let mut input_value = [7; 600];
+            input_value[599] = last_byte;
+            InputValueSignatureInput::<600, 1> {
+                input_value,
+                input_value_time: [1],
+            }
+            .signed_bytes::<Conf>(parameters.client_input_hash_scheme.as_ref())
+            .unwrap()
+        };
+        assert_ne!(digest(7), digest(8));
+    }
+
+    #[test]
+    fn keys_from_keygen_all_verify_every_scheme() {
+        let rng = &mut ChaChaRng::from_seed([37; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([38; 32]);
+        let gamma = BigFloat::from_f64(0.5, 64);
+        let shared = SharedPrimitives::<Conf>::setup(rng).unwrap();
+        let base_parameters =
+            ParametersBase::<Conf, 2>::from_shared(gamma.clone(), &shared).unwrap();
+        let expand_parameters =
+            ParametersExpand::<Conf, 2>::from_shared(gamma.clone(), &shared, rng).unwrap();
+        let shuffle_parameters = ParametersShuffle::<Conf, 2>::from_shared(gamma, &shared).unwrap();
+        let (base_keys, expand_keys, shuffle_keys) =
+            keygen_all::<Conf, ConfG, 2, 2, 1, 2, 4, 4, false>(
+                base_parameters.clone(),
+                expand_parameters.clone(),
+                shuffle_parameters.clone(),
+                [
+                    &mut ChaChaRng::from_seed([39; 32]),
+                    &mut ChaChaRng::from_seed([40; 32]),
+                    &mut ChaChaRng::from_seed([41; 32]),
+                ],
+            )
+            .unwrap();
+
+        let (client_sig_pk, client_sig_sk) =
+            <Conf as Config>::ClientSignatureScheme::keygen(&shared.client_signature_scheme, rng)
+                .unwrap();
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &shared.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let prf_eval_points = derive_prf_eval_points::<Conf>([3; 32], 1).unwrap();
+        let time_bounds = ([0], [1]);
+
+        // Base
+        let server = ServerBase::<Conf, 2, 1, 2, 4, 4, false>::new(
+            base_parameters.clone(),
+            base_keys.1,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientBase::<Conf, 2, 1, 2, 4, 4, false>::new(
+            base_parameters,
+            server.get_signature_public_key(),
+            client_sig_pk,
+            base_keys.0,
+        )
+        .unwrap();
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                time_bounds,
+                [1],
+                [BigUint::from(3_u8)],
+                input_value_signature.clone(),
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+        assert!(
+            server
+                .verifiable_randomization_verify::<ConfG>(&submission, time_bounds, zkp_rng, false)
+                .unwrap()
+                .0
+        );
+
+        // Expand
+        let server = ServerExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+            expand_parameters.clone(),
+            expand_keys.1,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+            expand_parameters,
+            server.get_signature_public_key(),
+            client_sig_pk,
+            expand_keys.0,
+        )
+        .unwrap();
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                time_bounds,
+                [1],
+                BigUint::from(3_u8),
+                input_value_signature.clone(),
+                &prf_eval_points,
+                0,
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+        assert!(
+            server
+                .verifiable_randomization_verify::<ConfG>(
+                    &submission,
+                    time_bounds,
+                    &prf_eval_points,
+                    0,
+                    zkp_rng,
+                    false
+                )
+                .unwrap()
+                .0
+        );
+
+        // Shuffle
+        let server = ServerShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            shuffle_parameters.clone(),
+            shuffle_keys.1,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            shuffle_parameters,
+            server.get_signature_public_key(),
+            client_sig_pk,
+            shuffle_keys.0,
+        )
+        .unwrap();
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let prf_eval_points = PrfEvalPointSet::new(0, prf_eval_points);
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                time_bounds,
+                [1],
+                BigUint::from(3_u8),
+                input_value_signature,
+                &prf_eval_points,
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+        assert!(
+            server
+                .verifiable_randomization_verify::<ConfG>(
+                    &submission,
+                    time_bounds,
+                    &prf_eval_points,
+                    zkp_rng,
+                    false
+                )
+                .unwrap()
+                .0
+        );
+    }
+
+    fn to_bytes(verifying_key: &VerifyingKey<Conf>) -> Vec<u8> {
+        let mut bytes = vec![];
+        verifying_key.serialize_compressed(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn joint_keygen_equals_separate_keygen() {
+        let rng = &mut ChaChaRng::from_seed([69; 32]);
+        let gamma = BigFloat::from_f64(0.5, 64);
+        let base_parameters = ParametersBase::<Conf, 2>::setup(gamma.clone(), rng).unwrap();
+        let expand_parameters = ParametersExpand::<Conf, 2>::setup(gamma.clone(), rng).unwrap();
+        let shuffle_parameters = ParametersShuffle::<Conf, 2>::setup(gamma, rng).unwrap();
+        let zkp_rng = |seed| ChaChaRng::from_seed([seed; 32]);
+
+        let (base_keys, expand_keys, shuffle_keys) =
+            keygen_all::<Conf, ConfG, 2, 2, 1, 2, 4, 4, false>(
+                base_parameters.clone(),
+                expand_parameters.clone(),
+                shuffle_parameters.clone(),
+                [&mut zkp_rng(70), &mut zkp_rng(71), &mut zkp_rng(72)],
+            )
+            .unwrap();
+
+        let (_, base_verifying_key) = CircuitBase::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(
+            base_parameters,
+            &mut zkp_rng(70),
+        )
+        .unwrap();
+        let (_, expand_verifying_key) =
+            CircuitExpand::<Conf, ConfG, 2, 2, 1, 2, 4, 4, false>::keygen(
+                expand_parameters,
+                &mut zkp_rng(71),
+            )
+            .unwrap();
+        let (_, shuffle_verifying_key) =
+            CircuitShuffle::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(
+                shuffle_parameters,
+                &mut zkp_rng(72),
+            )
+            .unwrap();
+        assert_eq!(to_bytes(&base_keys.1), to_bytes(&base_verifying_key));
+        assert_eq!(to_bytes(&expand_keys.1), to_bytes(&expand_verifying_key));
+        assert_eq!(to_bytes(&shuffle_keys.1), to_bytes(&shuffle_verifying_key));
+    }
+}
@@ -1,14 +1,18 @@
 //! Definitions of the R1CS ZKP circuits for the Shuffle VLDP scheme.
 
+use super::{
+    constraint_stats, real_input_rounding_var, signed_input_value_message_var, CircuitStats,
+};
 use crate::client::ClientShuffleStorage;
+use crate::messages::shuffle::PrfEvalPointSet;
 use crate::prelude::{constraints::*, *};
+use crate::primitives::ldp::{boundary_gap, randomized_ldp_value, randomness_limit};
 use ark_ff::PrimeField;
 use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::ToConstraintFieldGadget;
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
-use ark_std::{One, Zero};
-use num_bigint::BigUint;
-use std::cmp::{min, Ordering};
+use ark_std::One;
+use std::cmp::Ordering;
 use std::marker::PhantomData;
 
 /// Struct for R1CS constraint generation for the Shuffle scheme.
@@ -33,6 +37,7 @@ pub struct CircuitShuffle<
     ldp_value: Option<u64>,
     time_bounds: Option<([u8; TIME_BYTES], [u8; TIME_BYTES])>,
     server_sig_pk: Option<ServerSignatureSchemePublicKey<Conf>>,
+    epoch: Option<u64>,
     prf_eval_points: Option<Vec<PRFSchemeInput<Conf>>>,
 
     // private witnesses
@@ -44,6 +49,20 @@ pub struct CircuitShuffle<
     client_seed_commitment_randomness: Option<ClientCommitmentSchemeRandomness<Conf>>,
     server_seed: Option<PRFSchemeSeed<Conf>>,
     server_signature: Option<ServerSignatureSchemeSignature<Conf>>,
+    server_nonce: Option<[u8; 16]>,
+}
+
+/// Values from which the client proves a single `Randomize` step with the Shuffle circuit (see
+/// `CircuitShuffle::prove`). The seeds and the server signature are taken from the client
+/// storage.
+#[derive(Clone)]
+pub struct WitnessShuffle<Conf: Config, const INPUT_BYTES: usize, const TIME_BYTES: usize> {
+    pub ldp_value: u64,
+    pub time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+    pub true_value: [u8; INPUT_BYTES],
+    pub time: [u8; TIME_BYTES],
+    pub true_value_signature: ClientSignatureSchemeSignature<Conf>,
+    pub client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
 }
 
 impl<
@@ -67,16 +86,15 @@ impl<
         IS_REAL_INPUT,
     >
 {
-    pub fn keygen(
-        params: ParametersShuffle<Conf, GAMMA_BYTES>,
-        zkp_rng: &mut ZKPRng<Conf>,
-    ) -> Result<(ProvingKey<Conf>, VerifyingKey<Conf>), Error> {
-        let circuit = Self {
+    /// Circuit without any inputs, as needed for key generation and other precomputation.
+    fn without_witness(params: ParametersShuffle<Conf, GAMMA_BYTES>) -> Self {
+        Self {
             _config_gadget: PhantomData,
             params,
             ldp_value: None,
             time_bounds: None,
             server_sig_pk: None,
+            epoch: None,
             prf_eval_points: None,
             true_value: None,
             time: None,
@@ -86,8 +104,31 @@ impl<
             client_seed_commitment_randomness: None,
             server_seed: None,
             server_signature: None,
-        };
-        Conf::ZKPScheme::keygen(circuit, zkp_rng)
+            server_nonce: None,
+        }
+    }
+
+    pub fn keygen(
+        params: ParametersShuffle<Conf, GAMMA_BYTES>,
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<(ProvingKey<Conf>, VerifyingKey<Conf>), Error> {
+        Conf::ZKPScheme::keygen(Self::without_witness(params), zkp_rng)
+    }
+
+    /// Precompute the prover context of the circuit for the given parameters, which clients reuse
+    /// for every proof (see `ProofSystem::prover_context` and `ClientShuffle::with_prover_context`).
+    pub fn prover_context(
+        params: ParametersShuffle<Conf, GAMMA_BYTES>,
+    ) -> Result<ProverContext<Conf>, Error> {
+        Conf::ZKPScheme::prover_context(Self::without_witness(params))
+    }
+
+    /// Count the constraints and variables of the circuit for the given parameters, without
+    /// generating any keys.
+    pub fn constraint_stats(
+        params: ParametersShuffle<Conf, GAMMA_BYTES>,
+    ) -> Result<CircuitStats, Error> {
+        constraint_stats(Self::without_witness(params))
     }
 
     pub fn prove(
@@ -95,7 +136,7 @@ impl<
         params: ParametersShuffle<Conf, GAMMA_BYTES>,
         ldp_value: u64,
         server_sig_pk: ServerSignatureSchemePublicKey<Conf>,
-        prf_eval_points: &[PRFSchemeInput<Conf>],
+        prf_eval_points: &PrfEvalPointSet<Conf>,
         time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
         true_value: [u8; INPUT_BYTES],
         time: [u8; TIME_BYTES],
@@ -104,34 +145,62 @@ impl<
         client_storage: ClientShuffleStorage<Conf>,
         zkp_rng: &mut ZKPRng<Conf>,
     ) -> Result<Proof<Conf>, Error> {
-        let circuit = Self {
+        let witness = WitnessShuffle {
+            ldp_value,
+            time_bounds,
+            true_value,
+            time,
+            true_value_signature,
+            client_sig_pk,
+        };
+        let circuit = Self::assigned(
+            params,
+            server_sig_pk,
+            prf_eval_points,
+            witness,
+            client_storage,
+        );
+        Conf::ZKPScheme::prove(proving_key, circuit, zkp_rng)
+    }
+
+    /// Circuit with all values assigned (see `prove`).
+    pub(crate) fn assigned(
+        params: ParametersShuffle<Conf, GAMMA_BYTES>,
+        server_sig_pk: ServerSignatureSchemePublicKey<Conf>,
+        prf_eval_points: &PrfEvalPointSet<Conf>,
+        witness: WitnessShuffle<Conf, INPUT_BYTES, TIME_BYTES>,
+        client_storage: ClientShuffleStorage<Conf>,
+    ) -> Self {
+        Self {
             _config_gadget: PhantomData,
             params,
-            ldp_value: Some(ldp_value),
-            time_bounds: Some(time_bounds),
+            ldp_value: Some(witness.ldp_value),
+            time_bounds: Some(witness.time_bounds),
             server_sig_pk: Some(server_sig_pk),
-            prf_eval_points: Some(prf_eval_points.to_vec()),
-            true_value: Some(true_value),
-            time: Some(time),
-            true_value_signature: Some(true_value_signature),
-            client_sig_pk: Some(client_sig_pk),
+            epoch: Some(prf_eval_points.epoch),
+            prf_eval_points: Some(prf_eval_points.points.clone()),
+            true_value: Some(witness.true_value),
+            time: Some(witness.time),
+            true_value_signature: Some(witness.true_value_signature),
+            client_sig_pk: Some(witness.client_sig_pk),
             client_seed: client_storage.client_seed,
             client_seed_commitment_randomness: client_storage.client_seed_commitment_randomness,
             server_seed: client_storage.server_seed,
             server_signature: client_storage.server_signature,
-        };
-        Conf::ZKPScheme::prove(proving_key, circuit, zkp_rng)
+            server_nonce: client_storage.server_nonce,
+        }
     }
 
-    pub fn verify(
-        verifying_key: &VerifyingKey<Conf>,
-        proof: &Proof<Conf>,
+    /// Public inputs of the Shuffle circuit, in the order in which they are allocated:
+    /// `ldp_value` (as 8 little-endian bytes), both `time_bounds`, `server_sig_pk`, the epoch of
+    /// `prf_eval_points` (as 8 little-endian bytes), and all of its points. Byte arrays are packed
+    /// into field elements as done by their `ToConstraintField` implementation.
+    pub fn public_inputs(
         ldp_value: u64,
         time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
         server_sig_pk: &ServerSignatureSchemePublicKey<Conf>,
-        prf_eval_points: &[PRFSchemeInput<Conf>],
-        zkp_rng: &mut ZKPRng<Conf>,
-    ) -> Result<bool, Error>
+        prf_eval_points: &PrfEvalPointSet<Conf>,
+    ) -> Result<Vec<ConstraintField<Conf>>, Error>
     where
         ServerSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
     {
@@ -161,7 +230,14 @@ impl<
                 .to_field_elements()
                 .ok_or(GenericError::ConversionError)?,
         );
-        for prf_eval_point in prf_eval_points {
+        public_inputs.extend_from_slice(
+            &prf_eval_points
+                .epoch
+                .to_le_bytes()
+                .to_field_elements()
+                .ok_or(GenericError::ConversionError)?,
+        );
+        for prf_eval_point in prf_eval_points.points.iter() {
             public_inputs.extend_from_slice(
                 &prf_eval_point
                     .to_field_elements()
@@ -169,6 +245,23 @@ impl<
             );
         }
 
+        Ok(public_inputs)
+    }
+
+    pub fn verify(
+        verifying_key: &VerifyingKey<Conf>,
+        proof: &Proof<Conf>,
+        ldp_value: u64,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        server_sig_pk: &ServerSignatureSchemePublicKey<Conf>,
+        prf_eval_points: &PrfEvalPointSet<Conf>,
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<bool, Error>
+    where
+        ServerSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+    {
+        let public_inputs =
+            Self::public_inputs(ldp_value, time_bounds, server_sig_pk, prf_eval_points)?;
         Conf::ZKPScheme::verify(verifying_key, &public_inputs, proof, zkp_rng)
     }
 }
@@ -201,12 +294,14 @@ impl<
         // --- SANITY CHECKS ---
         if !cs.is_in_setup_mode()
             && (self.ldp_value.is_none()
+                || self.epoch.is_none()
                 || self.prf_eval_points.is_none()
                 || self.true_value.is_none()
                 || self.true_value_signature.is_none()
                 || self.client_seed.is_none()
                 || self.server_seed.is_none()
-                || self.server_signature.is_none())
+                || self.server_signature.is_none()
+                || self.server_nonce.is_none())
         {
             Err(SynthesisError::AssignmentMissing)?
         }
@@ -218,7 +313,7 @@ impl<
         // allocate public inputs
         let ldp_value = FpVar::new_input(cs.clone(), || {
             self.ldp_value
-                .map(|x| ConstraintField::<Conf>::from(x))
+                .map(ConstraintField::<Conf>::from)
                 .ok_or(SynthesisError::AssignmentMissing)
         })?;
         let time_lower_bound = FpVar::new_input(cs.clone(), || {
@@ -237,7 +332,14 @@ impl<
             ServerSignatureSchemePublicKeyVar::<_, ConfG>::new_input(cs.clone(), || {
                 self.server_sig_pk.ok_or(SynthesisError::AssignmentMissing)
             })?;
-        let prf_eval_points = (0..((RANDOMNESS_BYTES - 1) / 32) + 1)
+        // the epoch is not used by any constraint, but as a public input it is still bound to the
+        // proof, such that the proof does not verify for the eval points of any other epoch
+        let _epoch = FpVar::new_input(cs.clone(), || {
+            self.epoch
+                .map(ConstraintField::<Conf>::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let prf_eval_points = (0..((RANDOMNESS_BYTES - 1) / Conf::PRF_OUTPUT_BYTES) + 1)
             .map(|index| {
                 UInt8::new_input_vec(
                     cs.clone(),
@@ -286,6 +388,8 @@ impl<
             ServerSignatureSchemeSignatureVar::<_, ConfG>::new_witness(cs.clone(), || {
                 Ok(self.server_signature.unwrap_or_default())
             })?;
+        let server_nonce =
+            UInt8::new_witness_vec(cs.clone(), &self.server_nonce.unwrap_or_default())?;
 
         // --- CONSTRAINTS ---
         // 1: seed = client_seed XOR server_seed
@@ -308,41 +412,24 @@ impl<
 
         // 3: ldp_value = LDP.Apply(true_value, randomness)
         let k = FpVar::new_constant(cs.clone(), ConstraintField::<Conf>::from(K))?;
+        // exclusive upper bound of the random values (see `bin_boundaries`)
         let max_bound = FpVar::new_constant(
             cs.clone(),
-            ConstraintField::<Conf>::from_le_bytes_mod_order(&[u8::MAX; INPUT_BYTES]),
+            ConstraintField::<Conf>::from_le_bytes_mod_order(
+                &randomness_limit::<INPUT_BYTES>().to_bytes_le(),
+            ),
         )?;
         let boundary_gap = ConstraintField::<Conf>::from_le_bytes_mod_order(
-            &if IS_REAL_INPUT {
-                BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / (K + 1)
-            } else {
-                BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / K
-            }
-            .to_bytes_le(),
+            &boundary_gap::<INPUT_BYTES, K, IS_REAL_INPUT>().to_bytes_le(),
         );
         let computed_ldp_value = FpVar::new_witness(cs.clone(), || {
             Ok(ConstraintField::<Conf>::from({
-                let boundary_gap = if IS_REAL_INPUT {
-                    BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / (K + 1)
-                } else {
-                    BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / K
-                };
-                let computed_ldp_value = BigUint::from_bytes_le(
+                randomized_ldp_value::<INPUT_BYTES, K, IS_REAL_INPUT>(
                     &randomness
                         .iter()
                         .map(|x| x.value().unwrap())
                         .collect::<Vec<_>>()[GAMMA_BYTES..GAMMA_BYTES + INPUT_BYTES],
-                ) / boundary_gap;
-                let computed_ldp_value = if computed_ldp_value.is_zero() {
-                    0
-                } else {
-                    computed_ldp_value.to_u64_digits()[0]
-                };
-                if IS_REAL_INPUT {
-                    min(computed_ldp_value, K)
-                } else {
-                    min(computed_ldp_value, K - 1) + 1
-                }
+                )
             }))
         })?;
 
@@ -381,50 +468,11 @@ impl<
 
         // cast true_value if is_real_input
         let true_value_computed = if IS_REAL_INPUT {
-            let true_value_times_k = &true_value * k;
-            let multiplicand = FpVar::new_witness(cs.clone(), || {
-                Ok(ConstraintField::<Conf>::from_le_bytes_mod_order(
-                    &(BigUint::from_bytes_le(&self.true_value.unwrap()) * K
-                        / BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]))
-                    .to_bytes_le(),
-                ))
-            })?;
-            let remainder = FpVar::new_witness(cs.clone(), || {
-                Ok(true_value_times_k.value().unwrap()
-                    - multiplicand.value().unwrap()
-                        * ConstraintField::<Conf>::from_le_bytes_mod_order(&[u8::MAX; INPUT_BYTES]))
-            })?;
-            let true_value_randomness = Boolean::le_bits_to_fp_var(
-                &randomness[GAMMA_BYTES + INPUT_BYTES..GAMMA_BYTES + 2 * INPUT_BYTES]
-                    .to_bits_le()?,
-            )?;
-
-            // true_value_randomness <= remainder
-            let true_value_random_bit =
-                remainder.is_cmp_unchecked(&true_value_randomness, Ordering::Greater, true)?;
-            let true_value_computed = FpVar::new_witness(cs.clone(), || {
-                Ok(multiplicand.value().unwrap()
-                    + if true_value_random_bit.value().unwrap() {
-                        ConstraintField::<Conf>::one()
-                    } else {
-                        ConstraintField::<Conf>::zero()
-                    })
-            })?;
-
-            true_value_computed.conditional_enforce_equal(
-                &(&multiplicand + ConstraintField::<Conf>::one()),
-                &true_value_random_bit,
-            )?;
-            true_value_computed
-                .conditional_enforce_equal(&multiplicand, &true_value_random_bit.not())?;
-            remainder.enforce_equal(
-                &(true_value_times_k
-                    - multiplicand.clone()
-                        * ConstraintField::<Conf>::from_le_bytes_mod_order(
-                            &[u8::MAX; INPUT_BYTES],
-                        )),
-            )?;
-            true_value_computed
+            real_input_rounding_var::<_, INPUT_BYTES, K>(
+                cs.clone(),
+                &true_value,
+                &randomness[GAMMA_BYTES + INPUT_BYTES..GAMMA_BYTES + 2 * INPUT_BYTES],
+            )?
         } else {
             true_value.clone()
         };
@@ -433,8 +481,11 @@ impl<
 
         // 4: true_value_signature =?= ClientSig.Sign(client_sig_pk, true_value)
         // NOTE: correctness of this constraint is checked at the end
-        let mut message_bytes = true_value.to_bytes()?[0..INPUT_BYTES].to_vec();
-        message_bytes.extend_from_slice(&time.to_bytes()?[0..TIME_BYTES]);
+        let message_bytes = signed_input_value_message_var::<Conf, ConfG>(
+            params.client_input_hash_scheme.as_ref(),
+            &true_value.to_bytes()?[0..INPUT_BYTES],
+            &time.to_bytes()?[0..TIME_BYTES],
+        )?;
 
         let true_value_signature_correct = ConfG::ClientSignatureVerifyGadget::verify(
             &params.client_signature_scheme,
@@ -451,11 +502,12 @@ impl<
             &client_seed_commitment_randomness,
         )?;
 
-        // 6: server_signature =?= ServerSig.Sign(server_sig_pk, client_seed_commitment || client_sig_pk || server_seed)
+        // 6: server_signature =?= ServerSig.Sign(server_sig_pk, client_seed_commitment || client_sig_pk || server_seed || server_nonce)
         // NOTE: correctness of this constraint is checked at the end
         let mut signature_input_bytes = client_seed_commitment.to_bytes()?;
         signature_input_bytes.extend_from_slice(&client_sig_pk.to_bytes()?);
         signature_input_bytes.extend_from_slice(&server_seed);
+        signature_input_bytes.extend_from_slice(&server_nonce);
         let server_signature_correct = ConfG::ServerSignatureVerifyGadget::verify(
             &params.server_signature_scheme,
             &server_sig_pk,
@@ -492,3 +544,256 @@ impl<
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::CircuitShuffle;
+    use crate::client::ClientShuffle;
+    use crate::config::{verify_raw, BasicConfig, BasicConfigGadget};
+    use crate::messages::shuffle::{
+        GenerateRandomnessSignatureInputShuffle, PrfEvalPointSet,
+        VerifiableRandomizationMessageShuffle,
+    };
+    use crate::messages::{derive_prf_eval_points, InputValueSignatureInput};
+    use crate::prelude::*;
+    use crate::primitives::ldp::apply_ldp;
+    use crate::server::ServerShuffle;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_ff::{BigInteger, PrimeField};
+    use ark_groth16::Groth16;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+    type Circuit = CircuitShuffle<Conf, ConfG, 2, 1, 2, 4, 4, false>;
+
+    /// Build the circuit for input value 3 at `time` (within the time bounds `([0], [1])` only for
+    /// time 1) from scratch, i.e., without running the protocol. The input value signature is
+    /// over `signed_value` instead of the input value.
+    fn hand_built_circuit(time: u8, signed_value: [u8; 2], rng: &mut ChaChaRng) -> Circuit {
+        let parameters =
+            ParametersShuffle::<Conf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (server_sig_pk, server_sig_sk) = <Conf as Config>::ServerSignatureScheme::keygen(
+            &parameters.server_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let true_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: signed_value,
+                input_value_time: [time],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+
+        // seeds and the server signature as in the `Generate Randomness` step
+        let client_seed: PRFSchemeSeed<Conf> = rng.gen();
+        let server_seed: PRFSchemeSeed<Conf> = rng.gen();
+        let server_nonce: [u8; 16] = rng.gen();
+        let commitment_randomness = ClientCommitmentSchemeRandomness::<Conf>::rand(rng);
+        let client_seed_commitment = <Conf as Config>::ClientCommitmentScheme::commit(
+            &parameters.client_commitment_scheme,
+            &client_seed,
+            &commitment_randomness,
+        )
+        .unwrap();
+        let mut signature_input_bytes = vec![];
+        GenerateRandomnessSignatureInputShuffle::<Conf> {
+            client_seed_commitment,
+            client_signature_public_key: client_sig_pk,
+            server_seed,
+            server_nonce,
+        }
+        .serialize_uncompressed(&mut signature_input_bytes)
+        .unwrap();
+        let server_signature = <Conf as Config>::ServerSignatureScheme::sign(
+            &parameters.server_signature_scheme,
+            &server_sig_sk,
+            &signature_input_bytes,
+            rng,
+        )
+        .unwrap();
+
+        let prf_eval_points = derive_prf_eval_points::<Conf>(rng.gen(), 1).unwrap();
+        let mut seed = client_seed;
+        seed.iter_mut()
+            .zip(server_seed)
+            .for_each(|(client_byte, server_byte)| *client_byte ^= server_byte);
+        let randomness = <Conf as Config>::PRFScheme::evaluate(&seed, &prf_eval_points[0]).unwrap();
+        let ldp_value = apply_ldp::<2, 2, 4, false>(
+            &BigUint::from(3_u8),
+            &randomness[0..4],
+            &parameters.gamma_as_bytes().unwrap(),
+        );
+
+        Circuit {
+            _config_gadget: Default::default(),
+            params: parameters,
+            ldp_value: Some(ldp_value),
+            time_bounds: Some(([0], [1])),
+            server_sig_pk: Some(server_sig_pk),
+            epoch: Some(0),
+            prf_eval_points: Some(prf_eval_points),
+            true_value: Some([3, 0]),
+            time: Some([time]),
+            true_value_signature: Some(true_value_signature),
+            client_sig_pk: Some(client_sig_pk),
+            client_seed: Some(client_seed),
+            client_seed_commitment_randomness: Some(commitment_randomness),
+            server_seed: Some(server_seed),
+            server_signature: Some(server_signature),
+            server_nonce: Some(server_nonce),
+        }
+    }
+
+    fn is_satisfied(circuit: Circuit) -> bool {
+        let cs = ConstraintSystem::<ConstraintField<Conf>>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn hand_built_witness_satisfies_constraints() {
+        let rng = &mut ChaChaRng::from_seed([96; 32]);
+        let circuit = hand_built_circuit(1, [3, 0], rng);
+        assert!(is_satisfied(circuit.clone()));
+
+        // wrong LDP value
+        let mut wrong_ldp_value = circuit.clone();
+        wrong_ldp_value.ldp_value = wrong_ldp_value.ldp_value.map(|value| value % 4 + 1);
+        assert!(!is_satisfied(wrong_ldp_value));
+
+        // server seed that the server did not sign
+        let mut unsigned_server_seed = circuit.clone();
+        unsigned_server_seed.server_seed = Some(rng.gen());
+        assert!(!is_satisfied(unsigned_server_seed));
+
+        // server nonce of another exchange
+        let mut unsigned_server_nonce = circuit;
+        unsigned_server_nonce.server_nonce = Some(rng.gen());
+        assert!(!is_satisfied(unsigned_server_nonce));
+
+        // correctly signed, but outside the time bounds
+        assert!(!is_satisfied(hand_built_circuit(2, [3, 0], rng)));
+
+        // signature over a different input value
+        assert!(!is_satisfied(hand_built_circuit(1, [4, 0], rng)));
+    }
+
+    #[test]
+    fn verify_raw_agrees_with_typed_verify() {
+        let rng = &mut ChaChaRng::from_seed([4; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([5; 32]);
+        let parameters = ParametersShuffle::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) = Circuit::keygen(parameters.clone(), zkp_rng).unwrap();
+        let server = ServerShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            verifying_key.clone(),
+            rng,
+        )
+        .unwrap();
+        let server_sig_pk = server.get_signature_public_key();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server_sig_pk,
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+
+        // create a submission
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let prf_eval_points = PrfEvalPointSet::<Conf>::derive(0, [1; 32], 1).unwrap();
+        let time_bounds = ([0], [1]);
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                time_bounds,
+                [1],
+                BigUint::from(3_u8),
+                input_value_signature,
+                &prf_eval_points,
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+        let submission = VerifiableRandomizationMessageShuffle::<Conf, 2>::deserialize_compressed(
+            submission.as_slice(),
+        )
+        .unwrap();
+
+        let mut verifying_key_bytes = vec![];
+        verifying_key
+            .serialize_compressed(&mut verifying_key_bytes)
+            .unwrap();
+        let mut proof_bytes = vec![];
+        submission
+            .proof
+            .serialize_compressed(&mut proof_bytes)
+            .unwrap();
+
+        // both the correct and an incorrect LDP value
+        for ldp_value in [submission.ldp_value, submission.ldp_value % 4 + 1] {
+            let typed = Circuit::verify(
+                &verifying_key,
+                &submission.proof,
+                ldp_value,
+                time_bounds,
+                &server_sig_pk,
+                &prf_eval_points,
+                zkp_rng,
+            )
+            .unwrap();
+            let public_inputs_le =
+                Circuit::public_inputs(ldp_value, time_bounds, &server_sig_pk, &prf_eval_points)
+                    .unwrap()
+                    .iter()
+                    .map(|input| input.into_bigint().to_bytes_le())
+                    .collect::<Vec<_>>();
+            let raw =
+                verify_raw::<Conf>(&verifying_key_bytes, &public_inputs_le, &proof_bytes).unwrap();
+            assert_eq!(typed, raw);
+            assert_eq!(typed, ldp_value == submission.ldp_value);
+        }
+
+        // public inputs that are not canonical field elements are rejected
+        assert!(
+            verify_raw::<Conf>(&verifying_key_bytes, &[vec![u8::MAX; 32]], &proof_bytes).is_err()
+        );
+    }
+}
@@ -1,21 +1,29 @@
 //! All functionalities for a client in the Base scheme
 
-use crate::circuits::CircuitBase;
+use super::{derive_commitment_randomness, CommitmentRandomnessPool};
+use crate::circuits::{CircuitBase, ProofRandomnessSource, Relation, WitnessBase};
+use crate::config::KeyFingerprint;
 use crate::messages::base::*;
+use crate::messages::is_weak_seed;
 use crate::prelude::*;
+use crate::primitives::ldp::{apply_ldp, ldp_randomness_bytes};
+use crate::primitives::prf::expand_prf_seed;
+use crate::time::check_time_in_bounds;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use ark_std::Zero;
 use num_bigint::BigUint;
-use std::cmp::min;
+use std::sync::Arc;
 
 /// Storage of values between steps for a client in the Base scheme
 #[derive(Clone)]
 pub struct ClientBaseStorage<Conf: Config, const RANDOMNESS_SIZE: usize> {
     pub client_randomness: Option<[u8; RANDOMNESS_SIZE]>,
     pub client_randomness_commitment_randomness: Option<ClientCommitmentSchemeRandomness<Conf>>,
+    pub client_randomness_commitment_seed: Option<[u8; 32]>,
     pub client_randomness_commitment: Option<ClientCommitmentSchemeOutput<Conf>>,
     pub server_seed: Option<PRFSchemeSeed<Conf>>,
     pub server_signature: Option<ServerSignatureSchemeSignature<Conf>>,
+    pub server_signature_expiry: Option<u64>,
+    pub server_nonce: Option<[u8; 16]>,
 }
 
 impl<Conf: Config, const RANDOMNESS_SIZE: usize> ClientBaseStorage<Conf, RANDOMNESS_SIZE> {
@@ -24,14 +32,49 @@ impl<Conf: Config, const RANDOMNESS_SIZE: usize> ClientBaseStorage<Conf, RANDOMN
         Self {
             client_randomness: None,
             client_randomness_commitment_randomness: None,
+            client_randomness_commitment_seed: None,
             client_randomness_commitment: None,
             server_seed: None,
             server_signature: None,
+            server_signature_expiry: None,
+            server_nonce: None,
         }
     }
+
+    /// Whether the stored values form a state the client can reach, i.e., the values of each step
+    /// are either all present or all absent, and no step has values without the steps before it.
+    fn is_consistent(&self) -> bool {
+        let client_values = [
+            self.client_randomness.is_some(),
+            self.client_randomness_commitment_randomness.is_some(),
+            self.client_randomness_commitment_seed.is_some(),
+            self.client_randomness_commitment.is_some(),
+        ];
+        let server_values = [
+            self.server_seed.is_some(),
+            self.server_signature.is_some(),
+            self.server_nonce.is_some(),
+        ];
+        let has_client_values = client_values.iter().all(|&value| value);
+        let has_server_values = server_values.iter().all(|&value| value);
+        (has_client_values || client_values.iter().all(|&value| !value))
+            && (has_server_values || server_values.iter().all(|&value| !value))
+            && (has_client_values || !has_server_values)
+            && (has_server_values || self.server_signature_expiry.is_none())
+    }
 }
 
+/// Server randomness, LDP values, and little-endian bytes of the input values of a `Randomize`
+/// step of a Base client with `D` input values
+type Randomized<const INPUT_BYTES: usize, const RANDOMNESS_BYTES: usize, const D: usize> =
+    ([u8; RANDOMNESS_BYTES], [u64; D], [[u8; INPUT_BYTES]; D]);
+
 /// Base scheme client
+///
+/// Every submission consists of `D` input values (a single one by default), which are randomized
+/// under one proof (see `CircuitBase`). Hence, `RANDOMNESS_BYTES` has to cover `D` chunks of
+/// `ldp_randomness_bytes` bytes, and the input value signature is over the concatenation of all
+/// values, i.e., an `InputValueSignatureInput` with `D * INPUT_BYTES` input bytes.
 pub struct ClientBase<
     Conf: Config,
     const INPUT_BYTES: usize,
@@ -40,12 +83,15 @@ pub struct ClientBase<
     const RANDOMNESS_BYTES: usize,
     const K: u64,
     const IS_REAL_INPUT: bool,
+    const D: usize = 1,
 > {
     parameters: ParametersBase<Conf, GAMMA_BYTES>,
     server_sig_pk: ServerSignatureSchemePublicKey<Conf>,
     client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
-    proving_key: ProvingKey<Conf>,
+    proving_key: Arc<ProvingKey<Conf>>,
     storage: ClientBaseStorage<Conf, RANDOMNESS_BYTES>,
+    commitment_randomness_pool: Option<CommitmentRandomnessPool<Conf>>,
+    prover_context: Option<Arc<ProverContext<Conf>>>,
 }
 
 impl<
@@ -56,7 +102,9 @@ impl<
         const RANDOMNESS_BYTES: usize,
         const K: u64,
         const IS_REAL_INPUT: bool,
-    > ClientBase<Conf, INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>
+        const D: usize,
+    >
+    ClientBase<Conf, INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT, D>
 {
     /// Create a new client with the given system parameters, signature public keys (server and client) and proof generation key.
     pub fn new(
@@ -65,15 +113,188 @@ impl<
         client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
         proving_key: ProvingKey<Conf>,
     ) -> Result<Self, Error> {
+        Self::new_with_shared_proving_key(
+            parameters,
+            server_sig_pk,
+            client_sig_pk,
+            Arc::new(proving_key),
+        )
+    }
+
+    /// Create a new client like `new`, but with a proof generation key that is shared with other
+    /// clients instead of owned by this client.
+    pub fn new_with_shared_proving_key(
+        parameters: ParametersBase<Conf, GAMMA_BYTES>,
+        server_sig_pk: ServerSignatureSchemePublicKey<Conf>,
+        client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
+        proving_key: Arc<ProvingKey<Conf>>,
+    ) -> Result<Self, Error> {
+        validate_vector_scheme_params::<
+            INPUT_BYTES,
+            GAMMA_BYTES,
+            RANDOMNESS_BYTES,
+            K,
+            IS_REAL_INPUT,
+            D,
+        >()?;
         Ok(Self {
             parameters,
             server_sig_pk,
             client_sig_pk,
             proving_key,
             storage: ClientBaseStorage::new(),
+            commitment_randomness_pool: None,
+            prover_context: None,
         })
     }
 
+    /// Reuse this client (including its proof generation key) for another client, which has the
+    /// given signature public key. This clears all values stored during previous steps.
+    pub fn reset(&mut self, client_sig_pk: ClientSignatureSchemePublicKey<Conf>) {
+        self.client_sig_pk = client_sig_pk;
+        self.storage = ClientBaseStorage::new();
+    }
+
+    /// Serialize all values stored during previous steps, such that a client that crashes between
+    /// the `Generate Randomness` and `Randomize` steps can continue with `import_storage`. The
+    /// commitment randomness is represented by the seed it was derived from.
+    ///
+    /// Note: the exported bytes contain the client randomness and must be kept secret.
+    pub fn export_storage(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        self.storage
+            .client_randomness
+            .serialize_compressed(&mut bytes)?;
+        self.storage
+            .client_randomness_commitment_seed
+            .serialize_compressed(&mut bytes)?;
+        self.storage
+            .client_randomness_commitment
+            .serialize_compressed(&mut bytes)?;
+        self.storage.server_seed.serialize_compressed(&mut bytes)?;
+        self.storage
+            .server_signature
+            .serialize_compressed(&mut bytes)?;
+        self.storage
+            .server_signature_expiry
+            .serialize_compressed(&mut bytes)?;
+        self.storage.server_nonce.serialize_compressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Replace the values stored during previous steps by the ones exported with `export_storage`.
+    /// Fails with `ClientError::InconsistentStorage` if the imported values do not form a state the
+    /// client can reach or disagree with each other (e.g., a commitment that does not open to the
+    /// client randomness), in which case the current storage is kept.
+    pub fn import_storage(&mut self, bytes: &[u8]) -> Result<(), Error>
+    where
+        ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
+        let mut reader = bytes;
+        let client_randomness = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let client_randomness_commitment_seed: Option<[u8; 32]> =
+            CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let client_randomness_commitment =
+            CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let server_seed = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let server_signature = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let server_signature_expiry = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let server_nonce = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        if !reader.is_empty() {
+            Err(ClientError::InconsistentStorage)?
+        }
+
+        let storage = ClientBaseStorage::<Conf, RANDOMNESS_BYTES> {
+            client_randomness,
+            client_randomness_commitment_randomness: client_randomness_commitment_seed
+                .map(derive_commitment_randomness::<Conf>),
+            client_randomness_commitment_seed,
+            client_randomness_commitment,
+            server_seed,
+            server_signature,
+            server_signature_expiry,
+            server_nonce,
+        };
+        if !storage.is_consistent() {
+            Err(ClientError::InconsistentStorage)?
+        }
+
+        // the commitment has to open to the client randomness, and the server signature has to be
+        // valid for the commitment
+        if let (Some(client_randomness), Some(commitment_randomness), Some(commitment)) = (
+            &storage.client_randomness,
+            &storage.client_randomness_commitment_randomness,
+            &storage.client_randomness_commitment,
+        ) {
+            if Conf::ClientCommitmentScheme::commit(
+                &self.parameters.client_commitment_scheme,
+                client_randomness,
+                commitment_randomness,
+            )? != *commitment
+            {
+                Err(ClientError::InconsistentStorage)?
+            }
+            if let (Some(server_seed), Some(server_signature), Some(server_nonce)) = (
+                &storage.server_seed,
+                &storage.server_signature,
+                &storage.server_nonce,
+            ) {
+                let signature_input = GenerateRandomnessSignatureInputBase::<Conf> {
+                    client_randomness_commitment: commitment.clone(),
+                    client_signature_public_key: self.client_sig_pk.clone(),
+                    server_seed: *server_seed,
+                    expiry: storage.server_signature_expiry,
+                    server_nonce: *server_nonce,
+                };
+                if !self.verify_server_signature(&signature_input, server_signature)? {
+                    Err(ClientError::InconsistentStorage)?
+                }
+            }
+        }
+        self.storage = storage;
+        Ok(())
+    }
+
+    /// Draw the commitment randomness of the `Generate Randomness` step from the given pool
+    /// instead of sampling it on demand. Falls back to sampling if the pool has run empty.
+    pub fn with_commitment_randomness_pool(mut self, pool: CommitmentRandomnessPool<Conf>) -> Self {
+        self.commitment_randomness_pool = Some(pool);
+        self
+    }
+
+    /// Create proofs with the given prover context (see `CircuitBase::prover_context`), which can
+    /// be shared with other clients like the proving key, instead of redoing the circuit-invariant
+    /// precomputation for every proof. The context must be computed for the same parameters.
+    pub fn with_prover_context(mut self, prover_context: Arc<ProverContext<Conf>>) -> Self {
+        self.prover_context = Some(prover_context);
+        self
+    }
+
+    /// Key fingerprint of the proving key, which a server can check with
+    /// `ServerBase::with_proving_key_fingerprint`.
+    pub fn proving_key_fingerprint(&self) -> Result<KeyFingerprint, Error> {
+        Conf::ZKPScheme::proving_key_fingerprint(&self.proving_key)
+    }
+
+    /// Check that the proving key belongs to the same key pair as the verifying key of the server
+    /// with the given key fingerprint (see `ServerBase::verifying_key_fingerprint`). Fails with
+    /// `GenericError::KeyFingerprintMismatch` otherwise, as the server would reject every proof.
+    pub fn with_verifying_key_fingerprint(
+        self,
+        verifying_key_fingerprint: KeyFingerprint,
+    ) -> Result<Self, Error> {
+        let proving_key_fingerprint = self.proving_key_fingerprint()?;
+        if proving_key_fingerprint != verifying_key_fingerprint {
+            Err(GenericError::KeyFingerprintMismatch {
+                expected: verifying_key_fingerprint,
+                actual: proving_key_fingerprint,
+            })?
+        }
+        Ok(self)
+    }
+
     /// Given the current time step, perform the first part of the `Generate Randomness` step of the client.
     pub fn generate_randomness_create<R: Rng + CryptoRng>(
         &mut self,
@@ -87,21 +308,41 @@ impl<
         // generate a seed
         let mut client_seed = PRFSchemeSeed::<Conf>::default();
         rng.fill_bytes(&mut client_seed);
+        self.generate_randomness_create_with_seed(time, client_seed, rng)
+    }
 
+    /// Perform the first part of the `Generate Randomness` step like `generate_randomness_create`,
+    /// but with a client seed from an external source (e.g., a hardware token or a KDF) instead of
+    /// one drawn from `rng`. The client randomness only depends on the seed; `rng` is still used
+    /// for the commitment randomness (unless it is drawn from a pool). The seed must be secret
+    /// and must not be reused, as the client randomness would repeat otherwise.
+    pub fn generate_randomness_create_with_seed<R: Rng + CryptoRng>(
+        &mut self,
+        time: [u8; TIME_BYTES],
+        client_seed: PRFSchemeSeed<Conf>,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error>
+    where
+        ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    {
         // generate client randomness from seed
         let mut client_randomness = [0; RANDOMNESS_BYTES];
-        for (index, chunk) in client_randomness.chunks_mut(32).enumerate() {
-            let mut eval_point = [0; 32];
-            for (new_byte, old_byte) in index.to_le_bytes().into_iter().zip(eval_point.iter_mut()) {
-                *old_byte = new_byte;
-            }
-            chunk.copy_from_slice(
-                &Conf::PRFScheme::evaluate(&client_seed, &eval_point)?[0..chunk.len()],
-            );
-        }
+        expand_prf_seed::<Conf>(&client_seed, &mut client_randomness)?;
 
         // commit to client randomness
-        let commitment_randomness = ClientCommitmentSchemeRandomness::<Conf>::rand(rng);
+        let (commitment_seed, commitment_randomness) = self
+            .commitment_randomness_pool
+            .as_ref()
+            .and_then(|pool| pool.pop())
+            .unwrap_or_else(|| {
+                let mut commitment_seed = [0; 32];
+                rng.fill_bytes(&mut commitment_seed);
+                (
+                    commitment_seed,
+                    derive_commitment_randomness::<Conf>(commitment_seed),
+                )
+            });
         let client_randomness_commitment = Conf::ClientCommitmentScheme::commit(
             &self.parameters.client_commitment_scheme,
             &client_randomness,
@@ -111,6 +352,7 @@ impl<
         // storage
         self.storage.client_randomness = Some(client_randomness);
         self.storage.client_randomness_commitment_randomness = Some(commitment_randomness);
+        self.storage.client_randomness_commitment_seed = Some(commitment_seed);
         self.storage.client_randomness_commitment = Some(client_randomness_commitment.clone());
 
         // return message
@@ -125,7 +367,10 @@ impl<
     }
 
     /// Perform the second part of the `Generate Randomness` step of the client.
-    pub fn generate_randomness_verify(&mut self, server_message: &[u8]) -> Result<bool, Error>
+    ///
+    /// A server message whose signature does not verify is rejected with
+    /// `ProtocolError::ServerSignatureInvalid` (tagged `Side::Peer`).
+    pub fn generate_randomness_verify(&mut self, server_message: &[u8]) -> Result<(), Error>
     where
         ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
         ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
@@ -134,6 +379,9 @@ impl<
         // deserialize server message
         let server_message =
             GenerateRandomnessMessageServerBase::<Conf>::deserialize_compressed(server_message)?;
+        if is_weak_seed(&server_message.server_seed) {
+            Err(ClientError::WeakServerSeed)?
+        }
 
         // reconstruct signature input
         let signature_input = GenerateRandomnessSignatureInputBase::<Conf> {
@@ -144,31 +392,45 @@ impl<
                 .ok_or(ClientError::UnobtainedValue)?,
             client_signature_public_key: self.client_sig_pk.clone(),
             server_seed: server_message.server_seed.clone(),
+            expiry: server_message.expiry,
+            server_nonce: server_message.server_nonce,
         };
-        let mut signature_input_bytes = Vec::new();
-        signature_input.serialize_uncompressed(&mut signature_input_bytes)?;
 
         // verify signature
-        if Conf::ServerSignatureScheme::verify(
+        if !self.verify_server_signature(&signature_input, &server_message.server_signature)? {
+            Err(ProtocolError::ServerSignatureInvalid.peer())?
+        }
+
+        // storage
+        self.storage.server_seed = Some(server_message.server_seed);
+        self.storage.server_signature = Some(server_message.server_signature);
+        self.storage.server_signature_expiry = server_message.expiry;
+        self.storage.server_nonce = Some(server_message.server_nonce);
+        Ok(())
+    }
+
+    /// Verify the server signature over the given signature input.
+    fn verify_server_signature(
+        &self,
+        signature_input: &GenerateRandomnessSignatureInputBase<Conf>,
+        server_signature: &ServerSignatureSchemeSignature<Conf>,
+    ) -> Result<bool, Error>
+    where
+        ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    {
+        let mut signature_input_bytes = Vec::new();
+        signature_input.serialize_uncompressed(&mut signature_input_bytes)?;
+        Conf::ServerSignatureScheme::verify(
             &self.parameters.server_signature_scheme,
             &self.server_sig_pk,
             &signature_input_bytes,
-            &server_message.server_signature,
-        )? {
-            // storage
-            self.storage.server_seed = Some(server_message.server_seed);
-            self.storage.server_signature = Some(server_message.server_signature);
-
-            // return success
-            Ok(true)
-        } else {
-            // signature verification failed
-            Ok(false)
-        }
+            server_signature,
+        )
     }
 
-    /// Given the time bounds of the current step, the true input value, the time it was created,
-    /// and its signature perform the `Randomize` step of the client.
+    /// Given the time bounds of the current step, the true input value (all `D` values), the time
+    /// it was created, and its signature perform the `Randomize` step of the client.
     ///
     /// The `skip_proof` flag can be set to `true` to do a faster test run of this function that
     /// only executes the randomization (without proof generation).
@@ -177,7 +439,7 @@ impl<
         &self,
         time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
         input_value_time: [u8; TIME_BYTES],
-        input_value: BigUint,
+        input_value: [BigUint; D],
         input_value_signature: ClientSignatureSchemeSignature<Conf>,
         zkp_rng: &mut ZKPRng<Conf>,
         skip_proof: bool,
@@ -188,92 +450,108 @@ impl<
         ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
         ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
     {
-        // compute server randomness
-        let server_seed = self
-            .storage
-            .server_seed
-            .ok_or(ClientError::UnobtainedValue)?;
-        let mut server_randomness = [0; RANDOMNESS_BYTES];
-        for (index, chunk) in server_randomness.chunks_mut(32).enumerate() {
-            let mut eval_point = [0; 32];
-            for (new_byte, old_byte) in index.to_le_bytes().into_iter().zip(eval_point.iter_mut()) {
-                *old_byte = new_byte;
-            }
-            chunk.copy_from_slice(
-                &Conf::PRFScheme::evaluate(&server_seed, &eval_point)?[0..chunk.len()],
-            );
-        }
-        // compute full randomness from client and server part
-        let mut randomness = server_randomness.clone();
-        randomness
-            .iter_mut()
-            .zip(
-                self.storage
-                    .client_randomness
-                    .ok_or(ClientError::UnobtainedValue)?,
-            )
-            .for_each(|(client_byte, server_byte)| *client_byte ^= server_byte);
+        self.randomization_message::<ConfG>(
+            time_bounds,
+            input_value_time,
+            input_value,
+            input_value_signature,
+            ProofRandomnessSource::new(zkp_rng, skip_proof),
+        )
+    }
 
-        // apply LDP
-        let ldp_bit = {
-            (BigUint::from_bytes_le(&randomness[0..GAMMA_BYTES])
-                <= BigUint::from_bytes_le(&self.parameters.gamma_as_bytes()?)) as u8
-        };
+    /// Perform the `Randomize` step like `verifiable_randomization_create`, and additionally return
+    /// the randomness of the proof. With it, `verifiable_randomization_replay` reproduces the exact
+    /// message from the same inputs (and client storage), e.g., to demonstrate to an auditor how a
+    /// proof was generated.
+    ///
+    /// Note: the proof randomness reveals the witness of the proof (including the input value) and
+    /// must be stored as securely as the input value itself.
+    pub fn verifiable_randomization_create_recorded<ConfG: ConfigGadget<Conf>>(
+        &self,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        input_value_time: [u8; TIME_BYTES],
+        input_value: [BigUint; D],
+        input_value_signature: ClientSignatureSchemeSignature<Conf>,
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<(Vec<u8>, ProofRandomness<Conf>), Error>
+    where
+        ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    {
+        let proof_randomness = Conf::ZKPScheme::sample_proof_randomness(zkp_rng);
+        let message = self.randomization_message::<ConfG>(
+            time_bounds,
+            input_value_time,
+            input_value,
+            input_value_signature,
+            ProofRandomnessSource::Recorded(&proof_randomness),
+        )?;
+        Ok((message, proof_randomness))
+    }
 
-        let ldp_value = if ldp_bit == 0 {
-            if IS_REAL_INPUT {
-                let input_value_times_k = &input_value * K;
-                let multiplicand =
-                    &input_value_times_k / BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]);
-                let remainder = &input_value_times_k
-                    - &multiplicand * BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]);
-                let random_input_bytes =
-                    &randomness[GAMMA_BYTES + INPUT_BYTES..GAMMA_BYTES + 2 * INPUT_BYTES];
-                let random_input_bit =
-                    (BigUint::from_bytes_le(&random_input_bytes) <= remainder) as u64;
-                if multiplicand.is_zero() {
-                    random_input_bit
-                } else {
-                    multiplicand.to_u64_digits()[0] + random_input_bit
-                }
-            } else {
-                if (&input_value).is_zero() {
-                    0
-                } else {
-                    (&input_value).to_u64_digits()[0]
-                }
-            }
-        } else {
-            // ldp_bit == 1
-            let boundary_gap = if IS_REAL_INPUT {
-                BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / (K + 1)
-            } else {
-                BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / K
-            };
-            let computed_ldp_value =
-                BigUint::from_bytes_le(&randomness[GAMMA_BYTES..GAMMA_BYTES + INPUT_BYTES])
-                    / boundary_gap;
-            let computed_ldp_value = if computed_ldp_value.is_zero() {
-                0
-            } else {
-                computed_ldp_value.to_u64_digits()[0]
-            };
-            if IS_REAL_INPUT {
-                min(computed_ldp_value, K)
-            } else {
-                min(computed_ldp_value, K - 1) + 1
-            }
-        };
+    /// Reproduce the message of `verifiable_randomization_create_recorded` from the same inputs
+    /// and the recorded proof randomness.
+    pub fn verifiable_randomization_replay<ConfG: ConfigGadget<Conf>>(
+        &self,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        input_value_time: [u8; TIME_BYTES],
+        input_value: [BigUint; D],
+        input_value_signature: ClientSignatureSchemeSignature<Conf>,
+        proof_randomness: &ProofRandomness<Conf>,
+    ) -> Result<Vec<u8>, Error>
+    where
+        ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    {
+        self.randomization_message::<ConfG>(
+            time_bounds,
+            input_value_time,
+            input_value,
+            input_value_signature,
+            ProofRandomnessSource::Recorded(proof_randomness),
+        )
+    }
 
-        let mut input_value_bytes = [0; INPUT_BYTES];
-        for (idx, byte) in input_value.to_bytes_le().iter().enumerate() {
-            input_value_bytes[idx] = *byte;
-        }
+    /// Create the message of the `Randomize` step, with a proof from the given `source` of proof
+    /// randomness.
+    fn randomization_message<ConfG: ConfigGadget<Conf>>(
+        &self,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        input_value_time: [u8; TIME_BYTES],
+        input_value: [BigUint; D],
+        input_value_signature: ClientSignatureSchemeSignature<Conf>,
+        source: ProofRandomnessSource<'_, Conf>,
+    ) -> Result<Vec<u8>, Error>
+    where
+        ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    {
+        check_time_in_bounds(&input_value_time, &time_bounds)?;
+        let server_seed = self
+            .storage
+            .server_seed
+            .ok_or(ProtocolError::GenerateRandomnessIncomplete.local())?;
+        let gamma_bytes = self.parameters.gamma_as_bytes()?;
+        let (server_randomness, ldp_value, input_value_bytes) =
+            self.randomize(&input_value, &gamma_bytes)?;
 
         // create proof
-        let proof = if skip_proof {
-            Proof::<Conf>::default()
-        } else {
+        let witness = WitnessBase {
+            ldp_value,
+            time_bounds,
+            true_value: input_value_bytes,
+            time: input_value_time,
+            true_value_signature: input_value_signature,
+            client_sig_pk: self.client_sig_pk.clone(),
+            server_randomness,
+        };
+        let circuit =
             CircuitBase::<
                 _,
                 ConfG,
@@ -283,24 +561,18 @@ impl<
                 RANDOMNESS_BYTES,
                 K,
                 IS_REAL_INPUT,
-            >::prove(
-                &self.proving_key,
-                self.parameters.clone(),
-                ldp_value,
-                time_bounds,
-                input_value_bytes,
-                input_value_time,
-                input_value_signature,
-                self.client_sig_pk.clone(),
-                server_randomness,
-                self.storage.clone(),
-                zkp_rng,
-            )?
-        };
+                D,
+            >::with_witness(self.parameters.clone(), witness, self.storage.clone());
+        let proof = source.prove(&self.proving_key, self.prover_context.as_deref(), circuit)?;
 
         // return message
         let mut serialized_message = vec![];
-        VerifiableRandomizationMessageBase::<Conf, INPUT_BYTES> {
+        VerifiableRandomizationMessageBase::<Conf, INPUT_BYTES, D> {
+            gamma_bytes: GAMMA_BYTES as u16,
+            gamma: self
+                .parameters
+                .gamma_is_public
+                .then(|| gamma_bytes.to_vec()),
             client_sig_pk: self.client_sig_pk.clone(),
             client_randomness_commitment: self
                 .storage
@@ -312,11 +584,1046 @@ impl<
                 .storage
                 .server_signature
                 .clone()
-                .ok_or(ClientError::UnobtainedValue)?,
+                .ok_or(ProtocolError::GenerateRandomnessIncomplete.local())?,
+            expiry: self.storage.server_signature_expiry,
+            server_nonce: self
+                .storage
+                .server_nonce
+                .ok_or(ProtocolError::GenerateRandomnessIncomplete.local())?,
             proof,
             ldp_value,
         }
         .serialize_compressed(&mut serialized_message)?;
         Ok(serialized_message)
     }
+
+    /// Perform the `Randomize` step like `verifiable_randomization_create` (with the same inputs),
+    /// but instead of creating a proof, run the reference verifier of the circuit to find the
+    /// first relation that does not hold, if any (see `CircuitBase::first_failing_relation`).
+    /// This is meant for diagnosing why a submission is rejected.
+    pub fn verifiable_randomization_debug<ConfG: ConfigGadget<Conf>>(
+        &self,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        input_value_time: [u8; TIME_BYTES],
+        input_value: [BigUint; D],
+        input_value_signature: ClientSignatureSchemeSignature<Conf>,
+    ) -> Result<Option<Relation>, Error> {
+        let (server_randomness, ldp_value, input_value_bytes) =
+            self.randomize(&input_value, &self.parameters.gamma_as_bytes()?)?;
+        CircuitBase::<
+            _,
+            ConfG,
+            INPUT_BYTES,
+            TIME_BYTES,
+            GAMMA_BYTES,
+            RANDOMNESS_BYTES,
+            K,
+            IS_REAL_INPUT,
+            D,
+        >::first_failing_relation(
+            self.parameters.clone(),
+            WitnessBase {
+                ldp_value,
+                time_bounds,
+                true_value: input_value_bytes,
+                time: input_value_time,
+                true_value_signature: input_value_signature,
+                client_sig_pk: self.client_sig_pk.clone(),
+                server_randomness,
+            },
+            self.storage.clone(),
+        )
+    }
+
+    /// Derive the server randomness from the stored server seed and apply LDP to every value of
+    /// `input_value` with its chunk of the combined client and server randomness. Returns the
+    /// server randomness, the LDP values, and the input values as bytes.
+    fn randomize(
+        &self,
+        input_value: &[BigUint; D],
+        gamma_bytes: &[u8; GAMMA_BYTES],
+    ) -> Result<Randomized<INPUT_BYTES, RANDOMNESS_BYTES, D>, Error> {
+        // `new` checks that the `D` chunks fit into `RANDOMNESS_BYTES`
+        let chunk_bytes = ldp_randomness_bytes::<INPUT_BYTES, GAMMA_BYTES, IS_REAL_INPUT>();
+
+        // compute server randomness
+        let server_seed = self
+            .storage
+            .server_seed
+            .ok_or(ProtocolError::GenerateRandomnessIncomplete.local())?;
+        let mut server_randomness = [0; RANDOMNESS_BYTES];
+        expand_prf_seed::<Conf>(&server_seed, &mut server_randomness)?;
+        // compute full randomness from client and server part
+        let mut randomness = server_randomness.clone();
+        randomness
+            .iter_mut()
+            .zip(
+                self.storage
+                    .client_randomness
+                    .ok_or(ClientError::UnobtainedValue)?,
+            )
+            .for_each(|(client_byte, server_byte)| *client_byte ^= server_byte);
+
+        // apply LDP
+        let mut ldp_value = [0; D];
+        let mut input_value_bytes = [[0; INPUT_BYTES]; D];
+        for (index, input_value) in input_value.iter().enumerate() {
+            ldp_value[index] = apply_ldp::<INPUT_BYTES, GAMMA_BYTES, K, IS_REAL_INPUT>(
+                input_value,
+                &randomness[index * chunk_bytes..],
+                gamma_bytes,
+            );
+            for (idx, byte) in input_value.to_bytes_le().iter().enumerate() {
+                input_value_bytes[index][idx] = *byte;
+            }
+            if self.parameters.signed_input {
+                // the circuit takes the signed input value in two's complement, as it is signed
+                input_value_bytes[index][INPUT_BYTES - 1] ^= 0x80;
+            }
+        }
+        Ok((server_randomness, ldp_value, input_value_bytes))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ClientBase;
+    use crate::circuits::{CircuitBase, Relation};
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::messages::base::VerifiableRandomizationMessageBase;
+    use crate::messages::InputValueSignatureInput;
+    use crate::prelude::*;
+    use crate::primitives::ldp::{encode_signed_input, signed_input_bytes};
+    use crate::primitives::parameters::FingerprintParameters;
+    use crate::server::ServerBase;
+    use crate::testing::submit_base;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_serialize::CanonicalDeserialize;
+    use astro_float::BigFloat;
+    use num_bigint::{BigInt, BigUint};
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+    use std::sync::Arc;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    /// Commits to up to 65 bytes of client randomness.
+    type WideConf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 65>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+    type Client = ClientBase<Conf, 2, 1, 2, 4, 4, false>;
+    type Server = ServerBase<Conf, 2, 1, 2, 4, 4, false>;
+
+    #[test]
+    fn shared_and_reset_clients_match_cloned_clients() {
+        let rng = &mut ChaChaRng::from_seed([20; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitBase::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(parameters.clone(), rng)
+                .unwrap();
+        let server = Server::new(parameters.clone(), verifying_key, rng).unwrap();
+        let server_sig_pk = server.get_signature_public_key();
+        let client_keys = (0..2)
+            .map(|_| {
+                <Conf as Config>::ClientSignatureScheme::keygen(
+                    &parameters.client_signature_scheme,
+                    rng,
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // clone the proving key per client
+        let cloned = client_keys
+            .iter()
+            .map(|(client_sig_pk, client_sig_sk)| {
+                let mut client = Client::new(
+                    parameters.clone(),
+                    server_sig_pk,
+                    *client_sig_pk,
+                    proving_key.clone(),
+                )
+                .unwrap();
+                submit_base(&mut client, client_sig_sk, &server, &parameters, rng)
+            })
+            .collect::<Vec<_>>();
+
+        // share the proving key among all clients
+        let shared_proving_key = Arc::new(proving_key);
+        let shared = client_keys
+            .iter()
+            .map(|(client_sig_pk, client_sig_sk)| {
+                let mut client = Client::new_with_shared_proving_key(
+                    parameters.clone(),
+                    server_sig_pk,
+                    *client_sig_pk,
+                    shared_proving_key.clone(),
+                )
+                .unwrap();
+                submit_base(&mut client, client_sig_sk, &server, &parameters, rng)
+            })
+            .collect::<Vec<_>>();
+
+        // reuse a single client
+        let mut client = Client::new_with_shared_proving_key(
+            parameters.clone(),
+            server_sig_pk,
+            client_keys[0].0,
+            shared_proving_key,
+        )
+        .unwrap();
+        let reset = client_keys
+            .iter()
+            .map(|(client_sig_pk, client_sig_sk)| {
+                client.reset(*client_sig_pk);
+                submit_base(&mut client, client_sig_sk, &server, &parameters, rng)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(cloned, vec![true, true]);
+        assert_eq!(shared, cloned);
+        assert_eq!(reset, cloned);
+    }
+
+    #[test]
+    fn changed_gamma_only_requires_new_circuit_keys() {
+        let rng = &mut ChaChaRng::from_seed([106; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+
+        // the primitives are kept, only gamma changes
+        let upgraded = parameters
+            .clone()
+            .with_gamma(BigFloat::from_f64(0.25, 64))
+            .unwrap();
+        assert_ne!(
+            upgraded.gamma_as_bytes().unwrap(),
+            parameters.gamma_as_bytes().unwrap()
+        );
+        let primitive_bytes = |parameters: &ParametersBase<Conf, 2>| {
+            let mut bytes = vec![];
+            parameters
+                .client_commitment_scheme
+                .write_fingerprint_bytes(&mut bytes)
+                .unwrap();
+            parameters
+                .server_signature_scheme
+                .write_fingerprint_bytes(&mut bytes)
+                .unwrap();
+            parameters
+                .client_signature_scheme
+                .write_fingerprint_bytes(&mut bytes)
+                .unwrap();
+            bytes
+        };
+        assert_eq!(primitive_bytes(&upgraded), primitive_bytes(&parameters));
+
+        // after a new keygen, the client keys provisioned before the change still work
+        let (proving_key, verifying_key) =
+            CircuitBase::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(upgraded.clone(), rng)
+                .unwrap();
+        let server = Server::new(upgraded.clone(), verifying_key, rng).unwrap();
+        let mut client = Client::new(
+            upgraded.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        assert!(submit_base(
+            &mut client,
+            &client_sig_sk,
+            &server,
+            &upgraded,
+            rng
+        ));
+    }
+
+    #[test]
+    fn key_fingerprint_mismatch_is_reported_before_any_submission() {
+        let rng = &mut ChaChaRng::from_seed([128; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitBase::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(parameters.clone(), rng)
+                .unwrap();
+        // a key pair of a second setup for the same circuit, e.g., from a stale deployment
+        let (stale_proving_key, _) =
+            CircuitBase::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(parameters.clone(), rng)
+                .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let server = Server::new(parameters.clone(), verifying_key, rng).unwrap();
+        let server_fingerprint = server.verifying_key_fingerprint().unwrap();
+
+        // matching keys pass the check on both sides
+        let mut client = Client::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap()
+        .with_verifying_key_fingerprint(server_fingerprint)
+        .unwrap();
+        let server = server
+            .with_proving_key_fingerprint(client.proving_key_fingerprint().unwrap())
+            .unwrap();
+        assert!(submit_base(
+            &mut client,
+            &client_sig_sk,
+            &server,
+            &parameters,
+            rng
+        ));
+
+        // mismatching keys fail with a dedicated error instead of rejected proofs
+        let stale_client = Client::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            stale_proving_key,
+        )
+        .unwrap();
+        let stale_fingerprint = stale_client.proving_key_fingerprint().unwrap();
+        assert_ne!(stale_fingerprint, server_fingerprint);
+        assert!(matches!(
+            stale_client.with_verifying_key_fingerprint(server_fingerprint),
+            Err(VldpError::Generic(GenericError::KeyFingerprintMismatch { expected, actual }))
+                if expected == server_fingerprint && actual == stale_fingerprint
+        ));
+        assert!(matches!(
+            server.with_proving_key_fingerprint(stale_fingerprint),
+            Err(VldpError::Generic(
+                GenericError::KeyFingerprintMismatch { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn protocol_errors_are_tagged_with_the_violating_side() {
+        let rng = &mut ChaChaRng::from_seed([88; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        // no proofs are created, so neither key is used
+        let server = Server::new(parameters.clone(), Default::default(), rng).unwrap();
+        let other_server = Server::new(parameters.clone(), Default::default(), rng).unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let proving_key = ark_groth16::ProvingKey {
+            vk: Default::default(),
+            beta_g1: Default::default(),
+            delta_g1: Default::default(),
+            a_query: vec![],
+            b_g1_query: vec![],
+            b_g2_query: vec![],
+            h_query: vec![],
+            l_query: vec![],
+        };
+        let mut client = Client::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+
+        // the server message is signed with another key than the one the client expects
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let server_message = other_server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        let error = client
+            .generate_randomness_verify(&server_message)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Protocol(Side::Peer, ProtocolError::ServerSignatureInvalid)
+        ));
+
+        // hence, the client has no server seed to randomize with
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let error = client
+            .verifiable_randomization_create::<ConfG>(
+                ([0], [1]),
+                [1],
+                [BigUint::from(3_u8)],
+                input_value_signature,
+                rng,
+                true,
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Protocol(Side::Local, ProtocolError::GenerateRandomnessIncomplete)
+        ));
+    }
+
+    #[test]
+    fn debug_verify_reports_first_failing_relation() {
+        let rng = &mut ChaChaRng::from_seed([23; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        // no proofs are created, so neither key is used
+        let server = Server::new(parameters.clone(), Default::default(), rng).unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let proving_key = ark_groth16::ProvingKey {
+            vk: Default::default(),
+            beta_g1: Default::default(),
+            delta_g1: Default::default(),
+            a_query: vec![],
+            b_g1_query: vec![],
+            b_g2_query: vec![],
+            h_query: vec![],
+            l_query: vec![],
+        };
+        let mut client = Client::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let debug = |time_bounds, input_value: u8| {
+            client
+                .verifiable_randomization_debug::<ConfG>(
+                    time_bounds,
+                    [1],
+                    [BigUint::from(input_value)],
+                    input_value_signature.clone(),
+                )
+                .unwrap()
+        };
+
+        assert_eq!(debug(([0], [1]), 3), None);
+        // the input value was created at time 1, which is not after the lower bound
+        let relation = debug(([1], [2]), 3);
+        assert_eq!(relation, Some(Relation::TimeBound));
+        assert_eq!(relation.unwrap().to_string(), "time bound");
+        assert_eq!(debug(([0], [1]), 2), Some(Relation::InputValueSignature));
+    }
+
+    #[test]
+    fn recorded_proof_randomness_reproduces_the_proof() {
+        let rng = &mut ChaChaRng::from_seed([42; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([43; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitBase::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(parameters.clone(), zkp_rng)
+                .unwrap();
+        let server = Server::new(parameters.clone(), verifying_key, rng).unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = Client::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+
+        let (submission, proof_randomness) = client
+            .verifiable_randomization_create_recorded::<ConfG>(
+                ([0], [1]),
+                [1],
+                [BigUint::from(3_u8)],
+                input_value_signature.clone(),
+                zkp_rng,
+            )
+            .unwrap();
+        assert!(
+            server
+                .verifiable_randomization_verify::<ConfG>(&submission, ([0], [1]), zkp_rng, false)
+                .unwrap()
+                .0
+        );
+        let replay = |proof_randomness| {
+            client
+                .verifiable_randomization_replay::<ConfG>(
+                    ([0], [1]),
+                    [1],
+                    [BigUint::from(3_u8)],
+                    input_value_signature.clone(),
+                    proof_randomness,
+                )
+                .unwrap()
+        };
+        assert_eq!(replay(&proof_randomness), submission);
+        let other_randomness = <Conf as Config>::ZKPScheme::sample_proof_randomness(
+            &mut ChaChaRng::from_seed([44; 32]),
+        );
+        assert_ne!(replay(&other_randomness), submission);
+    }
+
+    #[test]
+    fn proofs_with_shared_prover_context_verify() {
+        let rng = &mut ChaChaRng::from_seed([52; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([53; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitBase::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(parameters.clone(), zkp_rng)
+                .unwrap();
+        let prover_context = Arc::new(
+            CircuitBase::<Conf, ConfG, 2, 1, 2, 4, 4, false>::prover_context(parameters.clone())
+                .unwrap(),
+        );
+        let server = Server::new(parameters.clone(), verifying_key, rng).unwrap();
+        let proving_key = Arc::new(proving_key);
+
+        // the context stays warm across submissions of different clients
+        for _ in 0..2 {
+            let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+                &parameters.client_signature_scheme,
+                rng,
+            )
+            .unwrap();
+            let mut client = Client::new_with_shared_proving_key(
+                parameters.clone(),
+                server.get_signature_public_key(),
+                client_sig_pk,
+                proving_key.clone(),
+            )
+            .unwrap()
+            .with_prover_context(prover_context.clone());
+            assert!(submit_base(
+                &mut client,
+                &client_sig_sk,
+                &server,
+                &parameters,
+                rng
+            ));
+        }
+    }
+
+    #[test]
+    fn input_time_outside_of_time_bounds_is_rejected() {
+        let rng = &mut ChaChaRng::from_seed([55; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([56; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let server = Server::new(parameters.clone(), Default::default(), rng).unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        // the check fails before proving, so the proving key is never used
+        let proving_key = ark_groth16::ProvingKey {
+            vk: Default::default(),
+            beta_g1: Default::default(),
+            delta_g1: Default::default(),
+            a_query: vec![],
+            b_g1_query: vec![],
+            b_g2_query: vec![],
+            h_query: vec![],
+            l_query: vec![],
+        };
+        let mut client = Client::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+
+        // the lower bound is exclusive
+        for input_value_time in [[1], [3]] {
+            let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+                &parameters.client_signature_scheme,
+                &client_sig_sk,
+                &InputValueSignatureInput {
+                    input_value: [3, 0],
+                    input_value_time,
+                }
+                .to_bytes(),
+                rng,
+            )
+            .unwrap();
+            let error = client
+                .verifiable_randomization_create::<ConfG>(
+                    ([1], [2]),
+                    input_value_time,
+                    [BigUint::from(3_u8)],
+                    input_value_signature,
+                    zkp_rng,
+                    false,
+                )
+                .unwrap_err();
+            assert!(matches!(
+                error,
+                VldpError::Client(ClientError::TimeOutOfBounds { .. })
+            ));
+            assert!(error.to_string().contains("time bounds (1, 2]"));
+        }
+    }
+
+    #[test]
+    fn imported_storage_continues_the_submission() {
+        let rng = &mut ChaChaRng::from_seed([61; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitBase::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(parameters.clone(), rng)
+                .unwrap();
+        let server = Server::new(parameters.clone(), verifying_key, rng).unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let proving_key = Arc::new(proving_key);
+        let mut client = Client::new_with_shared_proving_key(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key.clone(),
+        )
+        .unwrap();
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let exported = client.export_storage().unwrap();
+
+        // a server seed without the commitment it was signed for is rejected
+        client.storage.client_randomness_commitment = None;
+        let mut restarted_client = Client::new_with_shared_proving_key(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        let error = restarted_client
+            .import_storage(&client.export_storage().unwrap())
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Client(ClientError::InconsistentStorage)
+        ));
+
+        // values that disagree with each other are rejected: a commitment seed the commitment was
+        // not made with, and a server seed the server did not sign
+        client.import_storage(&exported).unwrap();
+        client.storage.client_randomness_commitment_seed = Some([0; 32]);
+        let error = restarted_client
+            .import_storage(&client.export_storage().unwrap())
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Client(ClientError::InconsistentStorage)
+        ));
+        client.import_storage(&exported).unwrap();
+        client.storage.server_seed.as_mut().unwrap()[0] ^= 1;
+        let error = restarted_client
+            .import_storage(&client.export_storage().unwrap())
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Client(ClientError::InconsistentStorage)
+        ));
+
+        // the restarted client continues with the `Randomize` step
+        restarted_client.import_storage(&exported).unwrap();
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let submission = restarted_client
+            .verifiable_randomization_create::<ConfG>(
+                ([0], [1]),
+                [1],
+                [BigUint::from(3_u8)],
+                input_value_signature,
+                &mut ChaChaRng::from_seed([62; 32]),
+                false,
+            )
+            .unwrap();
+        assert!(
+            server
+                .verifiable_randomization_verify::<ConfG>(
+                    &submission,
+                    ([0], [1]),
+                    &mut ChaChaRng::from_seed([63; 32]),
+                    false,
+                )
+                .unwrap()
+                .0
+        );
+    }
+
+    #[test]
+    fn vector_submission_randomizes_every_value() {
+        let rng = &mut ChaChaRng::from_seed([73; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        // two input values with 4 bytes of randomness each
+        let (proving_key, verifying_key) =
+            CircuitBase::<Conf, ConfG, 2, 1, 2, 8, 4, false, 2>::keygen(parameters.clone(), rng)
+                .unwrap();
+        let server = ServerBase::<Conf, 2, 1, 2, 8, 4, false, 2>::new(
+            parameters.clone(),
+            verifying_key,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientBase::<Conf, 2, 1, 2, 8, 4, false, 2>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key.clone(),
+        )
+        .unwrap();
+
+        // 8 bytes of randomness do not cover three input values
+        assert!(matches!(
+            ClientBase::<Conf, 2, 1, 2, 8, 4, false, 3>::new(
+                parameters.clone(),
+                server.get_signature_public_key(),
+                client_sig_pk,
+                proving_key,
+            ),
+            Err(VldpError::Generic(GenericError::InvalidSchemeParameters(_)))
+        ));
+        assert!(matches!(
+            ServerBase::<Conf, 2, 1, 2, 8, 4, false, 3>::new(
+                parameters.clone(),
+                Default::default(),
+                rng
+            ),
+            Err(VldpError::Generic(GenericError::InvalidSchemeParameters(_)))
+        ));
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+
+        // the signature is over the concatenation of both input values
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0, 1, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                ([0], [1]),
+                [1],
+                [BigUint::from(3_u8), BigUint::from(1_u8)],
+                input_value_signature,
+                &mut ChaChaRng::from_seed([74; 32]),
+                false,
+            )
+            .unwrap();
+        let (accepted, ldp_value) = server
+            .verifiable_randomization_verify::<ConfG>(
+                &submission,
+                ([0], [1]),
+                &mut ChaChaRng::from_seed([75; 32]),
+                false,
+            )
+            .unwrap();
+        assert!(accepted);
+        assert!(ldp_value.iter().all(|value| (1..=4).contains(value)));
+
+        // the proof does not hold for another value of the second dimension
+        let mut message = VerifiableRandomizationMessageBase::<Conf, 2, 2>::deserialize_compressed(
+            submission.as_slice(),
+        )
+        .unwrap();
+        message.ldp_value[1] = message.ldp_value[1] % 4 + 1;
+        assert!(
+            !server
+                .verifiable_randomization_verify_message::<ConfG>(
+                    &message,
+                    ([0], [1]),
+                    &mut ChaChaRng::from_seed([76; 32]),
+                    false,
+                )
+                .unwrap()
+                .0
+        );
+    }
+
+    /// Run a full submission with real proofs for the given amount of randomness and return
+    /// whether the server accepted it. The server randomness is a public input of the proof, so
+    /// the proof only verifies if the server reconstructs exactly the bytes the client used.
+    fn accepted_with_randomness_bytes<const RANDOMNESS_BYTES: usize>(seed: u8) -> bool {
+        let rng = &mut ChaChaRng::from_seed([seed; 32]);
+        let parameters =
+            ParametersBase::<WideConf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitBase::<WideConf, ConfG, 2, 1, 2, RANDOMNESS_BYTES, 4, false>::keygen(
+                parameters.clone(),
+                rng,
+            )
+            .unwrap();
+        let server = ServerBase::<WideConf, 2, 1, 2, RANDOMNESS_BYTES, 4, false>::new(
+            parameters.clone(),
+            verifying_key,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <WideConf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientBase::<WideConf, 2, 1, 2, RANDOMNESS_BYTES, 4, false>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let input_value_signature = <WideConf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                ([0], [1]),
+                [1],
+                [BigUint::from(3_u8)],
+                input_value_signature,
+                rng,
+                false,
+            )
+            .unwrap();
+        server
+            .verifiable_randomization_verify::<ConfG>(&submission, ([0], [1]), rng, false)
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn client_and_server_expand_partial_prf_chunks_alike() {
+        // multiples of the PRF output size (32 bytes) and sizes with a partial final chunk
+        assert!(accepted_with_randomness_bytes::<31>(82));
+        assert!(accepted_with_randomness_bytes::<32>(83));
+        assert!(accepted_with_randomness_bytes::<33>(84));
+        assert!(accepted_with_randomness_bytes::<64>(85));
+        assert!(accepted_with_randomness_bytes::<65>(86));
+    }
+
+    #[test]
+    fn supplied_client_seed_determines_the_client_randomness() {
+        let rng = &mut ChaChaRng::from_seed([98; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (server_sig_pk, _) = <Conf as Config>::ServerSignatureScheme::keygen(
+            &parameters.server_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, _) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        // no proof is created, so the proving key is not used
+        let proving_key = ark_groth16::ProvingKey {
+            vk: Default::default(),
+            beta_g1: Default::default(),
+            delta_g1: Default::default(),
+            a_query: vec![],
+            b_g1_query: vec![],
+            b_g2_query: vec![],
+            h_query: vec![],
+            l_query: vec![],
+        };
+        let create_with_seed = |client_seed, commitment_rng_seed| {
+            let mut client = Client::new(
+                parameters.clone(),
+                server_sig_pk,
+                client_sig_pk,
+                proving_key.clone(),
+            )
+            .unwrap();
+            let message = client
+                .generate_randomness_create_with_seed(
+                    [1],
+                    client_seed,
+                    &mut ChaChaRng::from_seed(commitment_rng_seed),
+                )
+                .unwrap();
+            (message, client.storage.client_randomness.unwrap())
+        };
+
+        // the same seed (and commitment randomness) results in the same commitment
+        let (message, client_randomness) = create_with_seed([7; 32], [99; 32]);
+        assert_eq!(
+            create_with_seed([7; 32], [99; 32]),
+            (message.clone(), client_randomness)
+        );
+
+        // the client randomness only depends on the seed, the commitment also on its randomness
+        let (other_message, other_client_randomness) = create_with_seed([7; 32], [100; 32]);
+        assert_ne!(other_message, message);
+        assert_eq!(other_client_randomness, client_randomness);
+        assert_ne!(create_with_seed([8; 32], [99; 32]).1, client_randomness);
+    }
+
+    #[test]
+    fn signed_input_round_trips_into_the_correct_bin() {
+        let rng = &mut ChaChaRng::from_seed([101; 32]);
+        // the input value is (almost) never replaced by a random value
+        let parameters = ParametersBase::setup(BigFloat::from_f64(1e-4, 64), rng)
+            .unwrap()
+            .with_signed_input();
+        let (proving_key, verifying_key) =
+            CircuitBase::<Conf, ConfG, 1, 1, 2, 4, 4, true>::keygen(parameters.clone(), rng)
+                .unwrap();
+        let server =
+            ServerBase::<Conf, 1, 1, 2, 4, 4, true>::new(parameters.clone(), verifying_key, rng)
+                .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientBase::<Conf, 1, 1, 2, 4, 4, true>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+
+        // the client signs -1 in two's complement and submits it shifted by the offset
+        let input_value = BigInt::from(-1);
+        assert_eq!(signed_input_bytes::<1>(&input_value).unwrap(), [0xff]);
+        assert_eq!(
+            encode_signed_input::<1>(&input_value).unwrap(),
+            BigUint::from(127_u8)
+        );
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: signed_input_bytes::<1>(&input_value).unwrap(),
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                ([0], [1]),
+                [1],
+                [encode_signed_input::<1>(&input_value).unwrap()],
+                input_value_signature,
+                rng,
+                false,
+            )
+            .unwrap();
+        let (is_valid, ldp_value) = server
+            .verifiable_randomization_verify::<ConfG>(&submission, ([0], [1]), rng, false)
+            .unwrap();
+        assert!(is_valid);
+        // 127 lies between the bins 1 and 2, whose ranges are [-128, -1] and [-64, 63]
+        assert!(ldp_value[0] == 1 || ldp_value[0] == 2);
+        assert!(server.decoded_range(ldp_value[0]).contains(&input_value));
+
+        // values outside of the two's complement range of the input bytes are rejected
+        assert!(encode_signed_input::<1>(&BigInt::from(127)).is_ok());
+        assert!(encode_signed_input::<1>(&BigInt::from(128)).is_err());
+        assert!(encode_signed_input::<1>(&BigInt::from(-128)).is_ok());
+        assert!(encode_signed_input::<1>(&BigInt::from(-129)).is_err());
+    }
 }
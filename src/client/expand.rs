@@ -1,14 +1,18 @@
 //! All functionalities for a client in the Expand scheme
 
-use crate::circuits::CircuitExpand;
+use crate::audit::RevealedSeeds;
+use crate::circuits::{CircuitExpand, ProofRandomnessSource, WitnessExpand};
 use crate::messages::expand::*;
+use crate::messages::is_weak_seed;
 use crate::prelude::*;
+use crate::primitives::ldp::apply_ldp;
+use crate::time::check_time_in_bounds;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use ark_std::Zero;
 use num_bigint::BigUint;
 use rand::{RngCore, SeedableRng};
 use rand_chacha::ChaChaRng;
-use std::cmp::min;
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
 /// Storage of values between steps for a client in the Expand scheme
 #[derive(Clone)]
@@ -18,6 +22,106 @@ pub struct ClientExpandStorage<Conf: Config> {
     pub merkle_tree: Option<ClientMerkleTree<Conf>>,
     pub server_seed: Option<PRFSchemeSeed<Conf>>,
     pub server_signature: Option<ServerSignatureSchemeSignature<Conf>>,
+    pub server_signature_expiry: Option<u64>,
+    pub server_nonce: Option<[u8; 16]>,
+    /// Openings of the LDP values of all blinded `Randomize` steps that have not been revealed
+    /// yet, by index
+    pub blinded_ldp_values: BTreeMap<usize, LdpValueRevealMessageExpand>,
+    /// Number of leaves appended to the merkle tree with `ClientExpand::append_leaf` (0 for a tree
+    /// created by the `Generate Randomness` step)
+    pub appended_leaves: usize,
+}
+
+/// Input of a single `Randomize` step of a client in the Expand scheme: the time bounds of the
+/// current step, the true input value, the time it was created, and its signature, along with the
+/// list of public `prf_eval_points` (s in the paper) and the current `index` (j in the paper).
+pub struct RandomizationInputExpand<'a, Conf: Config, const TIME_BYTES: usize> {
+    pub time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+    pub input_value_time: [u8; TIME_BYTES],
+    pub input_value: BigUint,
+    pub input_value_signature: ClientSignatureSchemeSignature<Conf>,
+    pub prf_eval_points: &'a [PRFSchemeInput<Conf>],
+    pub index: usize,
+}
+
+/// Client randomness of the `index`-th leaf of the merkle tree of an Expand client (i.e., of its
+/// `index`-th `Randomize` step), given its `client_seed`: chunk `inner_index` (of
+/// `PRF_OUTPUT_BYTES` bytes) is the PRF evaluated at the eval point
+/// `index * num_evals + inner_index`, where `num_evals` is the number of chunks.
+pub fn leaf_client_randomness<Conf: Config, const RANDOMNESS_BYTES: usize>(
+    client_seed: &PRFSchemeSeed<Conf>,
+    index: usize,
+) -> Result<[u8; RANDOMNESS_BYTES], Error> {
+    let mut client_randomness = [0; RANDOMNESS_BYTES];
+    let num_evals = ((RANDOMNESS_BYTES - 1) / Conf::PRF_OUTPUT_BYTES) + 1;
+    for (inner_index, chunk) in client_randomness
+        .chunks_mut(Conf::PRF_OUTPUT_BYTES)
+        .enumerate()
+    {
+        let eval_index = index * num_evals + inner_index;
+        let mut eval_point = [0; 32];
+        for (new_byte, old_byte) in eval_index
+            .to_le_bytes()
+            .into_iter()
+            .zip(eval_point.iter_mut())
+        {
+            *old_byte = new_byte;
+        }
+        chunk.copy_from_slice(
+            &Conf::PRFScheme::evaluate(client_seed, &eval_point)?.as_ref()[0..chunk.len()],
+        );
+    }
+    Ok(client_randomness)
+}
+
+/// Derivation of the secret values of all leaves of the merkle tree of an Expand client from the
+/// seed of its generator (see `ClientExpand::generate_randomness_create_with_seed`). The generator
+/// first yields the client seed, and then the commitment randomness of every leaf in order. The
+/// iterator yields the client randomness (see `leaf_client_randomness`) and the commitment
+/// randomness of every leaf, starting with the leaf at index 0.
+pub struct LeafDerivation<Conf: Config, const RANDOMNESS_BYTES: usize> {
+    generator: ChaChaRng,
+    client_seed: PRFSchemeSeed<Conf>,
+    index: usize,
+}
+
+impl<Conf: Config, const RANDOMNESS_BYTES: usize> LeafDerivation<Conf, RANDOMNESS_BYTES> {
+    pub fn new(generator_seed: [u8; 32]) -> Self {
+        let mut generator = ChaChaRng::from_seed(generator_seed);
+        let mut client_seed = PRFSchemeSeed::<Conf>::default();
+        generator.fill_bytes(&mut client_seed);
+        Self {
+            generator,
+            client_seed,
+            index: 0,
+        }
+    }
+
+    /// The client seed, from which the client randomness of all leaves is derived
+    pub fn client_seed(&self) -> &PRFSchemeSeed<Conf> {
+        &self.client_seed
+    }
+}
+
+impl<Conf: Config, const RANDOMNESS_BYTES: usize> Iterator
+    for LeafDerivation<Conf, RANDOMNESS_BYTES>
+{
+    type Item = Result<
+        (
+            [u8; RANDOMNESS_BYTES],
+            ClientCommitmentSchemeRandomness<Conf>,
+        ),
+        Error,
+    >;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let commitment_randomness =
+            ClientCommitmentSchemeRandomness::<Conf>::rand(&mut self.generator);
+        let client_randomness =
+            leaf_client_randomness::<Conf, RANDOMNESS_BYTES>(&self.client_seed, self.index);
+        self.index += 1;
+        Some(client_randomness.map(|client_randomness| (client_randomness, commitment_randomness)))
+    }
 }
 
 impl<Conf: Config> ClientExpandStorage<Conf> {
@@ -29,7 +133,33 @@ impl<Conf: Config> ClientExpandStorage<Conf> {
             merkle_tree: None,
             server_seed: None,
             server_signature: None,
+            server_signature_expiry: None,
+            server_nonce: None,
+            blinded_ldp_values: BTreeMap::new(),
+            appended_leaves: 0,
+        }
+    }
+
+    /// Whether the stored values form a state the client can reach, i.e., the server values are
+    /// either all present or all absent, and no step has values without the steps before it.
+    fn is_consistent(&self) -> bool {
+        let server_values = [
+            self.server_seed.is_some(),
+            self.server_signature.is_some(),
+            self.server_nonce.is_some(),
+        ];
+        let has_server_values = server_values.iter().all(|&value| value);
+        if self.appended_leaves > 0 && self.generator_seed.is_none() {
+            return false;
+        }
+        if !has_server_values {
+            // appending a leaf discards the server values, but not the openings of earlier steps
+            return server_values.iter().all(|&value| !value)
+                && self.server_signature_expiry.is_none()
+                && (self.appended_leaves > 0
+                    || (self.index == 0 && self.blinded_ldp_values.is_empty()));
         }
+        self.generator_seed.is_some()
     }
 }
 
@@ -47,8 +177,9 @@ pub struct ClientExpand<
     parameters: ParametersExpand<Conf, GAMMA_BYTES>,
     server_sig_pk: ServerSignatureSchemePublicKey<Conf>,
     client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
-    proving_key: ProvingKey<Conf>,
+    proving_key: Arc<ProvingKey<Conf>>,
     storage: ClientExpandStorage<Conf>,
+    prover_context: Option<Arc<ProverContext<Conf>>>,
 }
 
 impl<
@@ -79,12 +210,160 @@ impl<
         client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
         proving_key: ProvingKey<Conf>,
     ) -> Result<Self, Error> {
+        Self::new_with_shared_proving_key(
+            parameters,
+            server_sig_pk,
+            client_sig_pk,
+            Arc::new(proving_key),
+        )
+    }
+
+    /// Create a new client like `new`, but with a proof generation key that is shared with other
+    /// clients instead of owned by this client.
+    pub fn new_with_shared_proving_key(
+        parameters: ParametersExpand<Conf, GAMMA_BYTES>,
+        server_sig_pk: ServerSignatureSchemePublicKey<Conf>,
+        client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
+        proving_key: Arc<ProvingKey<Conf>>,
+    ) -> Result<Self, Error> {
+        validate_scheme_params::<INPUT_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>()?;
         Ok(Self {
             parameters,
             server_sig_pk,
             client_sig_pk,
             proving_key,
             storage: ClientExpandStorage::new(),
+            prover_context: None,
+        })
+    }
+
+    /// Reuse this client (including its proof generation key) for another client, which has the
+    /// given signature public key. This clears all values stored during previous steps.
+    pub fn reset(&mut self, client_sig_pk: ClientSignatureSchemePublicKey<Conf>) {
+        self.client_sig_pk = client_sig_pk;
+        self.storage = ClientExpandStorage::new();
+    }
+
+    /// Serialize all values stored during previous steps, such that a client that crashes between
+    /// steps can continue with `import_storage`. The merkle tree is represented by the seed of its
+    /// generator and the number of appended leaves (see `append_leaf`), from which all its leaves
+    /// are derived.
+    ///
+    /// Note: the exported bytes contain the generator seed and must be kept secret.
+    pub fn export_storage(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        self.storage
+            .generator_seed
+            .serialize_compressed(&mut bytes)?;
+        self.storage.index.serialize_compressed(&mut bytes)?;
+        self.storage.server_seed.serialize_compressed(&mut bytes)?;
+        self.storage
+            .server_signature
+            .serialize_compressed(&mut bytes)?;
+        self.storage
+            .server_signature_expiry
+            .serialize_compressed(&mut bytes)?;
+        self.storage.server_nonce.serialize_compressed(&mut bytes)?;
+        self.storage
+            .blinded_ldp_values
+            .serialize_compressed(&mut bytes)?;
+        self.storage
+            .appended_leaves
+            .serialize_compressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Replace the values stored during previous steps by the ones exported with `export_storage`,
+    /// recreating the merkle tree from the seed of its generator. Fails with
+    /// `ClientError::InconsistentStorage` if the imported values do not form a state the client can
+    /// reach or disagree with each other (e.g., a server signature over another merkle tree), in
+    /// which case the current storage is kept.
+    pub fn import_storage(&mut self, bytes: &[u8]) -> Result<(), Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
+        let mut reader = bytes;
+        let generator_seed: Option<[u8; 32]> =
+            CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let index = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let server_seed = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let server_signature = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let server_signature_expiry = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let server_nonce = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let blinded_ldp_values = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let appended_leaves = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        if !reader.is_empty() {
+            Err(ClientError::InconsistentStorage)?
+        }
+
+        let mut storage = ClientExpandStorage::<Conf> {
+            generator_seed,
+            index,
+            merkle_tree: None,
+            server_seed,
+            server_signature,
+            server_signature_expiry,
+            server_nonce,
+            blinded_ldp_values,
+            appended_leaves,
+        };
+        if !storage.is_consistent() || appended_leaves > 1 << (MT_DEPTH - 1) {
+            Err(ClientError::InconsistentStorage)?
+        }
+        storage.merkle_tree = generator_seed
+            .map(|generator_seed| match appended_leaves {
+                0 => self.merkle_tree_from_generator_seed(generator_seed),
+                _ => self.appended_merkle_tree(generator_seed, appended_leaves),
+            })
+            .transpose()?;
+
+        // the server signature has to be valid for the root of the recreated merkle tree
+        if let (Some(merkle_tree), Some(server_seed), Some(server_signature), Some(server_nonce)) = (
+            &storage.merkle_tree,
+            &storage.server_seed,
+            &storage.server_signature,
+            &storage.server_nonce,
+        ) {
+            let signature_input = GenerateRandomnessSignatureInputExpand::<Conf> {
+                client_merkle_tree_root: merkle_tree.root(),
+                client_signature_public_key: self.client_sig_pk.clone(),
+                server_seed: *server_seed,
+                expiry: storage.server_signature_expiry,
+                server_nonce: *server_nonce,
+            };
+            if !self.verify_server_signature(&signature_input, server_signature)? {
+                Err(ClientError::InconsistentStorage)?
+            }
+        }
+        self.storage = storage;
+        Ok(())
+    }
+
+    /// Create proofs with the given prover context (see `CircuitExpand::prover_context` and
+    /// `CircuitExpand::prover_context_blinded`), which can be shared with other clients like the
+    /// proving key, instead of redoing the circuit-invariant precomputation for every proof. The
+    /// context must be computed for the same parameters and mode as the proving key.
+    pub fn with_prover_context(mut self, prover_context: Arc<ProverContext<Conf>>) -> Self {
+        self.prover_context = Some(prover_context);
+        self
+    }
+
+    /// Reveal the client seed and the server seed of the current `Generate Randomness` step for
+    /// an audit (see `crate::audit`). This allows to reconstruct the randomness of all `Randomize`
+    /// steps of this step, so it must only be done once all of them have been submitted.
+    pub fn reveal_seeds(&self) -> Result<RevealedSeeds<Conf>, Error> {
+        let generator_seed = self
+            .storage
+            .generator_seed
+            .ok_or(ClientError::UnobtainedValue)?;
+        Ok(RevealedSeeds {
+            client_seed: *LeafDerivation::<Conf, RANDOMNESS_BYTES>::new(generator_seed)
+                .client_seed(),
+            server_seed: self
+                .storage
+                .server_seed
+                .ok_or(ProtocolError::GenerateRandomnessIncomplete.local())?,
         })
     }
 
@@ -97,51 +376,27 @@ impl<
         ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
     {
         // make a new rng and store its seed, so we do not have to store the entire merkle tree in memory
-        let mut generator = ChaChaRng::from_rng(rng)?;
-        let generator_seed = generator.get_seed();
+        let generator_seed = ChaChaRng::from_rng(rng)?.get_seed();
+        self.generate_randomness_create_with_seed(generator_seed)
+    }
 
-        // create the merkle tree
-        let mut client_seed = PRFSchemeSeed::<Conf>::default();
-        generator.fill_bytes(&mut client_seed);
-        let leaves = (0..2_usize.pow((MT_DEPTH - 1) as u32))
-            .map(|index| {
-                let mut client_randomness = [0; RANDOMNESS_BYTES];
-                let num_evals = ((RANDOMNESS_BYTES - 1) / 32) + 1;
-                for (inner_index, chunk) in client_randomness.chunks_mut(32).enumerate() {
-                    let eval_index = index * num_evals + inner_index;
-                    let mut eval_point = [0; 32];
-                    for (new_byte, old_byte) in eval_index
-                        .to_le_bytes()
-                        .into_iter()
-                        .zip(eval_point.iter_mut())
-                    {
-                        *old_byte = new_byte;
-                    }
-                    chunk.copy_from_slice(
-                        &Conf::PRFScheme::evaluate(&client_seed, &eval_point)?[0..chunk.len()],
-                    );
-                }
-                let client_randomness_commitment_randomness =
-                    ClientCommitmentSchemeRandomness::<Conf>::rand(&mut generator);
-                Conf::ClientCommitmentScheme::commit(
-                    &self.parameters.client_commitment_scheme,
-                    &client_randomness,
-                    &client_randomness_commitment_randomness,
-                )
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-        let merkle_tree = ClientMerkleTree::<Conf>::new(
-            &self.parameters.client_merkle_tree_scheme.leaf_crh_params,
-            &self
-                .parameters
-                .client_merkle_tree_scheme
-                .two_to_one_crh_params,
-            leaves,
-        )?;
+    /// Perform the first part of the `Generate Randomness` step like `generate_randomness_create`,
+    /// but with the seed of the generator from an external source (e.g., a hardware token or a
+    /// KDF). All client seeds and commitment randomness of the Merkle tree are derived from this
+    /// seed, so the step is deterministic. The seed must be secret and must not be reused.
+    pub fn generate_randomness_create_with_seed(
+        &mut self,
+        generator_seed: [u8; 32],
+    ) -> Result<Vec<u8>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    {
+        let merkle_tree = self.merkle_tree_from_generator_seed(generator_seed)?;
 
         // storage
         self.storage.generator_seed = Some(generator_seed);
         self.storage.merkle_tree = Some(merkle_tree.clone());
+        self.storage.appended_leaves = 0;
 
         // return message
         let mut serialized_message = vec![];
@@ -153,8 +408,168 @@ impl<
         Ok(serialized_message)
     }
 
+    /// Perform the first part of the `Generate Randomness` step like `generate_randomness_create`,
+    /// but for the merkle tree of the leaves appended so far (see `append_leaf`) instead of a new
+    /// tree.
+    pub fn generate_randomness_create_appended(&self) -> Result<Vec<u8>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    {
+        if self.storage.appended_leaves == 0 {
+            Err(ClientError::UnobtainedValue)?
+        }
+        let mut serialized_message = vec![];
+        GenerateRandomnessMessageClientExpand::<Conf> {
+            client_merkle_tree_root: self
+                .current_merkle_root()
+                .ok_or(ClientError::UnobtainedValue)?,
+            client_signature_public_key: self.client_sig_pk.clone(),
+        }
+        .serialize_compressed(&mut serialized_message)?;
+        Ok(serialized_message)
+    }
+
+    /// Append the commitment to the client randomness of another `Randomize` step as the next leaf
+    /// of the merkle tree, for a client whose `Randomize` steps are not known in advance. The leaf
+    /// is derived from the seed of the generator like the leaves created by
+    /// `generate_randomness_create`, where the seed is drawn from `rng` when the first leaf is
+    /// appended to a blank tree. The tree is then updated in place instead of being rebuilt. Sets
+    /// `index` to the position of the appended leaf, such that the next `Randomize` step proves
+    /// for it, and returns the updated root and the path to the appended leaf.
+    ///
+    /// The server signature of a previous `Generate Randomness` step does not cover the updated
+    /// root, so it is discarded: before the next `Randomize` step, the server has to sign the new
+    /// root by running the step again with `generate_randomness_create_appended`.
+    ///
+    /// Fails with `ClientError::MerkleTreeFull` if no leaf of the tree is free, which includes a
+    /// tree created by `generate_randomness_create`.
+    pub fn append_leaf<R: Rng + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+    ) -> Result<(ClientMerkleTreeRoot<Conf>, ClientMerkleTreePath<Conf>), Error> {
+        let leaves = 1 << (MT_DEPTH - 1);
+        let position = self.storage.appended_leaves;
+        if position >= leaves || (position == 0 && self.storage.merkle_tree.is_some()) {
+            Err(ClientError::MerkleTreeFull { leaves })?
+        }
+        let generator_seed = match self.storage.generator_seed {
+            Some(generator_seed) if position > 0 => generator_seed,
+            _ => ChaChaRng::from_rng(rng)?.get_seed(),
+        };
+        let mut merkle_tree = match self.storage.merkle_tree.take() {
+            Some(merkle_tree) => merkle_tree,
+            None => self.blank_merkle_tree()?,
+        };
+        let (client_randomness, client_randomness_commitment_randomness) =
+            LeafDerivation::<Conf, RANDOMNESS_BYTES>::new(generator_seed)
+                .nth(position)
+                .expect("every leaf can be derived")?;
+        let leaf = Conf::ClientCommitmentScheme::commit(
+            &self.parameters.client_commitment_scheme,
+            &client_randomness,
+            &client_randomness_commitment_randomness,
+        )?;
+        merkle_tree.update(position, &leaf)?;
+        let root = merkle_tree.root();
+        let merkle_path = merkle_tree.generate_proof(position)?;
+
+        // storage
+        self.storage.generator_seed = Some(generator_seed);
+        self.storage.merkle_tree = Some(merkle_tree);
+        self.storage.appended_leaves += 1;
+        self.storage.index = position;
+        self.storage.server_seed = None;
+        self.storage.server_signature = None;
+        self.storage.server_signature_expiry = None;
+        self.storage.server_nonce = None;
+        Ok((root, merkle_path))
+    }
+
+    /// Root of the merkle tree created in the `Generate Randomness` step, if that step has been
+    /// performed.
+    pub fn current_merkle_root(&self) -> Option<ClientMerkleTreeRoot<Conf>> {
+        self.storage.merkle_tree.as_ref().map(|mt| mt.root())
+    }
+
+    /// Create the merkle tree over the commitments to the client randomness of all `Randomize`
+    /// steps from the seed of the generator.
+    fn merkle_tree_from_generator_seed(
+        &self,
+        generator_seed: [u8; 32],
+    ) -> Result<ClientMerkleTree<Conf>, Error> {
+        let leaves = self
+            .commitments_from_generator_seed(generator_seed, 2_usize.pow((MT_DEPTH - 1) as u32))?;
+        let merkle_tree = ClientMerkleTree::<Conf>::new(
+            &self.parameters.client_merkle_tree_scheme.leaf_crh_params,
+            &self
+                .parameters
+                .client_merkle_tree_scheme
+                .two_to_one_crh_params,
+            leaves,
+        )?;
+        Ok(merkle_tree)
+    }
+
+    /// Recreate the merkle tree of the first `appended_leaves` leaves appended with `append_leaf`
+    /// from the seed of the generator.
+    fn appended_merkle_tree(
+        &self,
+        generator_seed: [u8; 32],
+        appended_leaves: usize,
+    ) -> Result<ClientMerkleTree<Conf>, Error> {
+        let mut merkle_tree = self.blank_merkle_tree()?;
+        for (position, leaf) in self
+            .commitments_from_generator_seed(generator_seed, appended_leaves)?
+            .iter()
+            .enumerate()
+        {
+            merkle_tree.update(position, leaf)?;
+        }
+        Ok(merkle_tree)
+    }
+
+    /// Create a merkle tree of depth `MT_DEPTH` without any leaves.
+    fn blank_merkle_tree(&self) -> Result<ClientMerkleTree<Conf>, Error> {
+        Ok(ClientMerkleTree::<Conf>::blank(
+            &self.parameters.client_merkle_tree_scheme.leaf_crh_params,
+            &self
+                .parameters
+                .client_merkle_tree_scheme
+                .two_to_one_crh_params,
+            MT_DEPTH,
+        )?)
+    }
+
+    /// Create the commitments to the client randomness of the first `leaves` `Randomize` steps
+    /// (i.e., the leaves of the merkle tree) from the seed of the generator.
+    fn commitments_from_generator_seed(
+        &self,
+        generator_seed: [u8; 32],
+        leaves: usize,
+    ) -> Result<Vec<ClientCommitmentSchemeOutput<Conf>>, Error> {
+        let leaves = LeafDerivation::<Conf, RANDOMNESS_BYTES>::new(generator_seed)
+            .take(leaves)
+            .map(|leaf| {
+                let (client_randomness, client_randomness_commitment_randomness) = leaf?;
+                Conf::ClientCommitmentScheme::commit(
+                    &self.parameters.client_commitment_scheme,
+                    &client_randomness,
+                    &client_randomness_commitment_randomness,
+                )
+                .map_err(Error::from)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(leaves)
+    }
+
     /// Perform the second part of the `Generate Randomness` step of the client.
-    pub fn generate_randomness_verify(&mut self, server_message: &[u8]) -> Result<bool, Error>
+    ///
+    /// A server message whose signature does not verify is rejected with
+    /// `ProtocolError::ServerSignatureInvalid` (tagged `Side::Peer`). The signed input is
+    /// reconstructed with the root of the client's own merkle tree rather than taken from the
+    /// server message, so a signature over any other root (e.g., one the server received
+    /// corrupted) is rejected the same way.
+    pub fn generate_randomness_verify(&mut self, server_message: &[u8]) -> Result<(), Error>
     where
         ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
         ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
@@ -162,6 +577,9 @@ impl<
         // deserialize server message
         let server_message =
             GenerateRandomnessMessageServerExpand::<Conf>::deserialize_compressed(server_message)?;
+        if is_weak_seed(&server_message.server_seed) {
+            Err(ClientError::WeakServerSeed)?
+        }
 
         // reconstruct signature input
         let signature_input = GenerateRandomnessSignatureInputExpand::<Conf> {
@@ -173,27 +591,40 @@ impl<
                 .ok_or(ClientError::UnobtainedValue)?,
             client_signature_public_key: self.client_sig_pk.clone(),
             server_seed: server_message.server_seed.clone(),
+            expiry: server_message.expiry,
+            server_nonce: server_message.server_nonce,
         };
-        let mut signature_input_bytes = Vec::new();
-        signature_input.serialize_uncompressed(&mut signature_input_bytes)?;
 
         // verify signature
-        if Conf::ServerSignatureScheme::verify(
+        if !self.verify_server_signature(&signature_input, &server_message.server_signature)? {
+            Err(ProtocolError::ServerSignatureInvalid.peer())?
+        }
+
+        // storage
+        self.storage.server_seed = Some(server_message.server_seed);
+        self.storage.server_signature = Some(server_message.server_signature);
+        self.storage.server_signature_expiry = server_message.expiry;
+        self.storage.server_nonce = Some(server_message.server_nonce);
+        Ok(())
+    }
+
+    /// Verify the server signature over the given signature input.
+    fn verify_server_signature(
+        &self,
+        signature_input: &GenerateRandomnessSignatureInputExpand<Conf>,
+        server_signature: &ServerSignatureSchemeSignature<Conf>,
+    ) -> Result<bool, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    {
+        let mut signature_input_bytes = Vec::new();
+        signature_input.serialize_uncompressed(&mut signature_input_bytes)?;
+        Conf::ServerSignatureScheme::verify(
             &self.parameters.server_signature_scheme,
             &self.server_sig_pk,
             &signature_input_bytes,
-            &server_message.server_signature,
-        )? {
-            // storage
-            self.storage.server_seed = Some(server_message.server_seed);
-            self.storage.server_signature = Some(server_message.server_signature);
-
-            // return success
-            Ok(true)
-        } else {
-            // signature verification failed
-            Ok(false)
-        }
+            server_signature,
+        )
     }
 
     /// Given the time bounds of the current step, the true input value, the time it was created,
@@ -219,44 +650,246 @@ impl<
         ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
         ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
     {
-        // reconstruct the generator that was used to create this entry of the merkle tree
-        let mut generator = ChaChaRng::from_seed(
-            self.storage
-                .generator_seed
+        let input = RandomizationInputExpand {
+            time_bounds,
+            input_value_time,
+            input_value,
+            input_value_signature,
+            prf_eval_points,
+            index,
+        };
+        let mut serialized_message = vec![];
+        self.randomization_message::<ConfG>(
+            input,
+            ProofRandomnessSource::new(zkp_rng, skip_proof),
+        )?
+        .serialize_compressed(&mut serialized_message)?;
+        Ok(serialized_message)
+    }
+
+    /// Perform the `Randomize` step like `verifiable_randomization_create` (with its arguments
+    /// bundled in `input`), and additionally return the randomness of the proof. With it,
+    /// `verifiable_randomization_replay` reproduces the exact message from the same inputs and the
+    /// client storage from before this step (see `export_storage`), e.g., to demonstrate to an
+    /// auditor how a proof was generated.
+    ///
+    /// Note: the proof randomness reveals the witness of the proof (including the input value) and
+    /// must be stored as securely as the input value itself.
+    pub fn verifiable_randomization_create_recorded<ConfG: ConfigGadget<Conf>>(
+        &mut self,
+        input: RandomizationInputExpand<Conf, TIME_BYTES>,
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<(Vec<u8>, ProofRandomness<Conf>), Error>
+    where
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    {
+        let proof_randomness = Conf::ZKPScheme::sample_proof_randomness(zkp_rng);
+        let message = self.verifiable_randomization_replay::<ConfG>(input, &proof_randomness)?;
+        Ok((message, proof_randomness))
+    }
+
+    /// Reproduce the message of `verifiable_randomization_create_recorded` from the same inputs
+    /// and the recorded proof randomness. Like any `Randomize` step, this advances the client
+    /// storage to the next index.
+    pub fn verifiable_randomization_replay<ConfG: ConfigGadget<Conf>>(
+        &mut self,
+        input: RandomizationInputExpand<Conf, TIME_BYTES>,
+        proof_randomness: &ProofRandomness<Conf>,
+    ) -> Result<Vec<u8>, Error>
+    where
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    {
+        let mut serialized_message = vec![];
+        self.randomization_message::<ConfG>(
+            input,
+            ProofRandomnessSource::Recorded(proof_randomness),
+        )?
+        .serialize_compressed(&mut serialized_message)?;
+        Ok(serialized_message)
+    }
+
+    /// Perform the `Randomize` step like `verifiable_randomization_create` (with its arguments
+    /// bundled in `input`), but create a compact message for a server that keeps the state of the
+    /// `GenRand` session (see `ServerExpand::generate_randomness_create_session`), which omits all
+    /// fields the server already knows from that session.
+    pub fn compact_randomization_create<ConfG: ConfigGadget<Conf>>(
+        &mut self,
+        input: RandomizationInputExpand<Conf, TIME_BYTES>,
+        zkp_rng: &mut ZKPRng<Conf>,
+        skip_proof: bool,
+    ) -> Result<Vec<u8>, Error>
+    where
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    {
+        let mut serialized_message = vec![];
+        self.randomization_message::<ConfG>(
+            input,
+            ProofRandomnessSource::new(zkp_rng, skip_proof),
+        )?
+        .compact()
+        .serialize_compressed(&mut serialized_message)?;
+        Ok(serialized_message)
+    }
+
+    fn randomization_message<ConfG: ConfigGadget<Conf>>(
+        &mut self,
+        input: RandomizationInputExpand<Conf, TIME_BYTES>,
+        source: ProofRandomnessSource<'_, Conf>,
+    ) -> Result<VerifiableRandomizationMessageExpand<Conf, INPUT_BYTES>, Error>
+    where
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    {
+        let (ldp_value, proof, server_seed) = self.randomize::<ConfG>(input, None, source)?;
+
+        Ok(VerifiableRandomizationMessageExpand {
+            gamma_bytes: GAMMA_BYTES as u16,
+            client_sig_pk: self.client_sig_pk.clone(),
+            client_merkle_tree_root: self
+                .storage
+                .merkle_tree
+                .as_ref()
+                .map(|mt| mt.root())
                 .ok_or(ClientError::UnobtainedValue)?,
-        );
+            server_seed,
+            server_signature: self
+                .storage
+                .server_signature
+                .clone()
+                .ok_or(ProtocolError::GenerateRandomnessIncomplete.local())?,
+            expiry: self.storage.server_signature_expiry,
+            server_nonce: self
+                .storage
+                .server_nonce
+                .ok_or(ProtocolError::GenerateRandomnessIncomplete.local())?,
+            proof,
+            ldp_value,
+        })
+    }
 
-        // compute the client seed, client randomness and commitment randomness again
-        let mut client_seed = PRFSchemeSeed::<Conf>::default();
-        generator.fill_bytes(&mut client_seed);
-        let mut client_randomness = [0; RANDOMNESS_BYTES];
-        let num_evals = ((RANDOMNESS_BYTES - 1) / 32) + 1;
-        for (inner_index, chunk) in client_randomness.chunks_mut(32).enumerate() {
-            let eval_index = index * num_evals + inner_index;
-            let mut eval_point = [0; 32];
-            for (new_byte, old_byte) in eval_index
-                .to_le_bytes()
-                .into_iter()
-                .zip(eval_point.iter_mut())
-            {
-                *old_byte = new_byte;
-            }
-            chunk.copy_from_slice(
-                &Conf::PRFScheme::evaluate(&client_seed, &eval_point)?[0..chunk.len()],
-            );
+    /// Perform the `Randomize` step like `verifiable_randomization_create` (with its arguments
+    /// bundled in `input`), but in the blinded mode: the message only contains a commitment to the
+    /// LDP value, which is revealed later with `ldp_value_reveal_create`. This requires a proving
+    /// key from `CircuitExpand::keygen_blinded`.
+    pub fn blinded_randomization_create<ConfG: ConfigGadget<Conf>, R: Rng + CryptoRng>(
+        &mut self,
+        input: RandomizationInputExpand<Conf, TIME_BYTES>,
+        rng: &mut R,
+        zkp_rng: &mut ZKPRng<Conf>,
+        skip_proof: bool,
+    ) -> Result<Vec<u8>, Error>
+    where
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+        ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+    {
+        let mut reveal = LdpValueRevealMessageExpand {
+            ldp_value: 0,
+            ldp_value_commitment_seed: rng.gen(),
+        };
+        let ldp_value_commitment_randomness = reveal.commitment_randomness::<Conf>();
+        let index = input.index;
+        let (ldp_value, proof, server_seed) = self.randomize::<ConfG>(
+            input,
+            Some(&ldp_value_commitment_randomness),
+            ProofRandomnessSource::new(zkp_rng, skip_proof),
+        )?;
+        reveal.ldp_value = ldp_value;
+        let ldp_value_commitment =
+            reveal.commitment::<Conf>(&self.parameters.client_commitment_scheme)?;
+        self.storage.blinded_ldp_values.insert(index, reveal);
+
+        // return message
+        let mut serialized_message = vec![];
+        BlindedRandomizationMessageExpand::<Conf, INPUT_BYTES> {
+            gamma_bytes: GAMMA_BYTES as u16,
+            client_sig_pk: self.client_sig_pk.clone(),
+            client_merkle_tree_root: self
+                .storage
+                .merkle_tree
+                .as_ref()
+                .map(|mt| mt.root())
+                .ok_or(ClientError::UnobtainedValue)?,
+            server_seed,
+            server_signature: self
+                .storage
+                .server_signature
+                .clone()
+                .ok_or(ProtocolError::GenerateRandomnessIncomplete.local())?,
+            expiry: self.storage.server_signature_expiry,
+            server_nonce: self
+                .storage
+                .server_nonce
+                .ok_or(ProtocolError::GenerateRandomnessIncomplete.local())?,
+            proof,
+            ldp_value_commitment,
         }
-        let client_randomness_commitment_randomness =
-            ClientCommitmentSchemeRandomness::<Conf>::rand(&mut generator);
+        .serialize_compressed(&mut serialized_message)?;
+        Ok(serialized_message)
+    }
+
+    /// Reveal the LDP value of the blinded `Randomize` step at `index`, i.e., open the commitment
+    /// of its message. The opening is removed from storage afterwards.
+    pub fn ldp_value_reveal_create(&mut self, index: usize) -> Result<Vec<u8>, Error> {
+        let reveal = self
+            .storage
+            .blinded_ldp_values
+            .remove(&index)
+            .ok_or(ClientError::UnobtainedValue)?;
+        let mut serialized_message = vec![];
+        reveal.serialize_compressed(&mut serialized_message)?;
+        Ok(serialized_message)
+    }
+
+    /// Randomize the input value and prove this, as shared by the unblinded and blinded
+    /// `Randomize` step. Returns the LDP value, the proof, and the server seed.
+    fn randomize<ConfG: ConfigGadget<Conf>>(
+        &mut self,
+        input: RandomizationInputExpand<Conf, TIME_BYTES>,
+        ldp_value_commitment_randomness: Option<&ClientCommitmentSchemeRandomness<Conf>>,
+        source: ProofRandomnessSource<'_, Conf>,
+    ) -> Result<(u64, Proof<Conf>, PRFSchemeSeed<Conf>), Error> {
+        let RandomizationInputExpand {
+            time_bounds,
+            input_value_time,
+            input_value,
+            input_value_signature,
+            prf_eval_points,
+            index,
+        } = input;
+
+        check_time_in_bounds(&input_value_time, &time_bounds)?;
+
+        // compute the client randomness and commitment randomness of this leaf again
+        let generator_seed = self
+            .storage
+            .generator_seed
+            .ok_or(ClientError::UnobtainedValue)?;
+        let (client_randomness, client_randomness_commitment_randomness) =
+            LeafDerivation::<Conf, RANDOMNESS_BYTES>::new(generator_seed)
+                .nth(index)
+                .expect("every leaf can be derived")?;
 
         // compute server randomness
         let server_seed = self
             .storage
             .server_seed
-            .ok_or(ClientError::UnobtainedValue)?;
+            .ok_or(ProtocolError::GenerateRandomnessIncomplete.local())?;
         let mut server_randomness = [0; RANDOMNESS_BYTES];
-        for (chunk, prf_eval_point) in server_randomness.chunks_mut(32).zip(prf_eval_points) {
+        for (chunk, prf_eval_point) in server_randomness
+            .chunks_mut(Conf::PRF_OUTPUT_BYTES)
+            .zip(prf_eval_points)
+        {
             chunk.copy_from_slice(
-                &Conf::PRFScheme::evaluate(&server_seed, &prf_eval_point)?[0..chunk.len()],
+                &Conf::PRFScheme::evaluate(&server_seed, &prf_eval_point)?.as_ref()[0..chunk.len()],
             );
         }
         // compute full randomness from client and server part
@@ -267,55 +900,11 @@ impl<
             .for_each(|(client_byte, server_byte)| *client_byte ^= server_byte);
 
         // apply LDP
-        let ldp_bit = {
-            (BigUint::from_bytes_le(&randomness[0..GAMMA_BYTES])
-                <= BigUint::from_bytes_le(&self.parameters.gamma_as_bytes()?)) as u8
-        };
-
-        let ldp_value = if ldp_bit == 0 {
-            if IS_REAL_INPUT {
-                let input_value_times_k = &input_value * K;
-                let multiplicand =
-                    &input_value_times_k / BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]);
-                let remainder = &input_value_times_k
-                    - &multiplicand * BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]);
-                let random_input_bytes =
-                    &randomness[GAMMA_BYTES + INPUT_BYTES..GAMMA_BYTES + 2 * INPUT_BYTES];
-                let random_input_bit =
-                    (BigUint::from_bytes_le(&random_input_bytes) <= remainder) as u64;
-                if multiplicand.is_zero() {
-                    random_input_bit
-                } else {
-                    multiplicand.to_u64_digits()[0] + random_input_bit
-                }
-            } else {
-                if (&input_value).is_zero() {
-                    0
-                } else {
-                    (&input_value).to_u64_digits()[0]
-                }
-            }
-        } else {
-            // ldp_bit == 1
-            let boundary_gap = if IS_REAL_INPUT {
-                BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / (K + 1)
-            } else {
-                BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / K
-            };
-            let computed_ldp_value =
-                BigUint::from_bytes_le(&randomness[GAMMA_BYTES..GAMMA_BYTES + INPUT_BYTES])
-                    / boundary_gap;
-            let computed_ldp_value = if computed_ldp_value.is_zero() {
-                0
-            } else {
-                computed_ldp_value.to_u64_digits()[0]
-            };
-            if IS_REAL_INPUT {
-                min(computed_ldp_value, K)
-            } else {
-                min(computed_ldp_value, K - 1) + 1
-            }
-        };
+        let ldp_value = apply_ldp::<INPUT_BYTES, GAMMA_BYTES, K, IS_REAL_INPUT>(
+            &input_value,
+            &randomness,
+            &self.parameters.gamma_as_bytes()?,
+        );
 
         let mut input_value_bytes = [0; INPUT_BYTES];
         for (idx, byte) in input_value.to_bytes_le().iter().enumerate() {
@@ -323,60 +912,579 @@ impl<
         }
 
         // create proof
-        let proof = if skip_proof {
+        let proof = if let ProofRandomnessSource::Skip = source {
             Proof::<Conf>::default()
         } else {
-            CircuitExpand::<
-                _,
-                ConfG,
-                MT_DEPTH,
-                INPUT_BYTES,
-                TIME_BYTES,
-                GAMMA_BYTES,
-                RANDOMNESS_BYTES,
-                K,
-                IS_REAL_INPUT,
-            >::prove(
-                &self.proving_key,
-                self.parameters.clone(),
+            let witness = WitnessExpand {
                 ldp_value,
                 time_bounds,
-                input_value_bytes,
-                input_value_time,
-                input_value_signature,
-                self.client_sig_pk.clone(),
+                true_value: input_value_bytes,
+                time: input_value_time,
+                true_value_signature: input_value_signature,
+                client_sig_pk: self.client_sig_pk.clone(),
                 server_randomness,
                 client_randomness,
                 client_randomness_commitment_randomness,
-                self.storage.clone(),
-                zkp_rng,
-            )?
+            };
+            let circuit = match ldp_value_commitment_randomness {
+                None => CircuitExpand::<
+                    _,
+                    ConfG,
+                    MT_DEPTH,
+                    INPUT_BYTES,
+                    TIME_BYTES,
+                    GAMMA_BYTES,
+                    RANDOMNESS_BYTES,
+                    K,
+                    IS_REAL_INPUT,
+                >::assigned(
+                    self.parameters.clone(), witness, self.storage.clone()
+                )?,
+                Some(ldp_value_commitment_randomness) => CircuitExpand::<
+                    _,
+                    ConfG,
+                    MT_DEPTH,
+                    INPUT_BYTES,
+                    TIME_BYTES,
+                    GAMMA_BYTES,
+                    RANDOMNESS_BYTES,
+                    K,
+                    IS_REAL_INPUT,
+                >::assigned_blinded(
+                    self.parameters.clone(),
+                    witness,
+                    self.storage.clone(),
+                    ldp_value_commitment_randomness.clone(),
+                )?,
+            };
+            source.prove(&self.proving_key, self.prover_context.as_deref(), circuit)?
         };
 
-        // store the seed after the generator has been used
-        self.storage.generator_seed = Some(generator.get_seed());
         self.storage.index += 1;
 
-        // return message
-        let mut serialized_message = vec![];
-        VerifiableRandomizationMessageExpand::<Conf, INPUT_BYTES> {
-            client_sig_pk: self.client_sig_pk.clone(),
-            client_merkle_tree_root: self
-                .storage
-                .merkle_tree
-                .as_ref()
-                .map(|mt| mt.root())
-                .ok_or(ClientError::UnobtainedValue)?,
-            server_seed,
-            server_signature: self
-                .storage
-                .server_signature
-                .clone()
-                .ok_or(ClientError::UnobtainedValue)?,
-            proof,
-            ldp_value,
+        Ok((ldp_value, proof, server_seed))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ClientExpand, RandomizationInputExpand};
+    use crate::circuits::CircuitExpand;
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::messages::expand::*;
+    use crate::messages::{derive_prf_eval_points, InputValueSignatureInput};
+    use crate::prelude::*;
+    use crate::server::{reconstruct_merkle_tree, ServerExpand};
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_serialize::CanonicalSerialize;
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+    use std::sync::Arc;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+
+    #[test]
+    fn imported_storage_is_checked_against_the_merkle_tree() {
+        let rng = &mut ChaChaRng::from_seed([65; 32]);
+        let parameters = ParametersExpand::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        // no proofs are created, so neither key is used
+        let server = ServerExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            Default::default(),
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, _) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let new_client = || {
+            ClientExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+                parameters.clone(),
+                server.get_signature_public_key(),
+                client_sig_pk,
+                ark_groth16::ProvingKey {
+                    vk: Default::default(),
+                    beta_g1: Default::default(),
+                    delta_g1: Default::default(),
+                    a_query: vec![],
+                    b_g1_query: vec![],
+                    b_g2_query: vec![],
+                    h_query: vec![],
+                    l_query: vec![],
+                },
+            )
+            .unwrap()
+        };
+        let mut client = new_client();
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let mut restarted_client = new_client();
+        restarted_client
+            .import_storage(&client.export_storage().unwrap())
+            .unwrap();
+        assert_eq!(
+            restarted_client.storage.merkle_tree.unwrap().root(),
+            client.storage.merkle_tree.as_ref().unwrap().root()
+        );
+
+        // a client with appended leaves continues appending after them
+        let mut appending_client = new_client();
+        let _ = appending_client.append_leaf(rng).unwrap();
+        let mut restarted_client = new_client();
+        restarted_client
+            .import_storage(&appending_client.export_storage().unwrap())
+            .unwrap();
+        assert_eq!(restarted_client.storage.appended_leaves, 1);
+        assert_eq!(
+            restarted_client.current_merkle_root(),
+            appending_client.current_merkle_root()
+        );
+        let (root, merkle_path) = restarted_client.append_leaf(rng).unwrap();
+        assert_eq!(merkle_path.leaf_index, 1);
+        assert_eq!(
+            Some(root),
+            appending_client.append_leaf(rng).ok().map(|(root, _)| root)
+        );
+
+        // a generator seed of another merkle tree than the one the server signed is rejected
+        client.storage.generator_seed.as_mut().unwrap()[0] ^= 1;
+        let mut restarted_client = new_client();
+        let error = restarted_client
+            .import_storage(&client.export_storage().unwrap())
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Client(ClientError::InconsistentStorage)
+        ));
+        assert!(restarted_client.storage.generator_seed.is_none());
+    }
+
+    #[test]
+    fn recorded_proof_randomness_reproduces_the_proof() {
+        let rng = &mut ChaChaRng::from_seed([80; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([81; 32]);
+        let parameters =
+            ParametersExpand::<Conf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitExpand::<Conf, ConfG, 2, 2, 1, 2, 4, 4, false>::keygen(
+                parameters.clone(),
+                zkp_rng,
+            )
+            .unwrap();
+        let server = ServerExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            verifying_key,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let new_client = || {
+            ClientExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+                parameters.clone(),
+                server.get_signature_public_key(),
+                client_sig_pk,
+                proving_key.clone(),
+            )
+            .unwrap()
+        };
+        let mut client = new_client();
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let prf_eval_points = derive_prf_eval_points::<Conf>([3; 32], 1).unwrap();
+        let time_bounds = ([0], [1]);
+        let input = || RandomizationInputExpand {
+            time_bounds,
+            input_value_time: [1],
+            input_value: BigUint::from(3_u8),
+            input_value_signature: input_value_signature.clone(),
+            prf_eval_points: &prf_eval_points,
+            index: 0,
+        };
+
+        // the replay starts from the storage before the `Randomize` step
+        let storage = client.export_storage().unwrap();
+        let (submission, proof_randomness) = client
+            .verifiable_randomization_create_recorded::<ConfG>(input(), zkp_rng)
+            .unwrap();
+        assert!(
+            server
+                .verifiable_randomization_verify::<ConfG>(
+                    &submission,
+                    time_bounds,
+                    &prf_eval_points,
+                    0,
+                    zkp_rng,
+                    false
+                )
+                .unwrap()
+                .0
+        );
+        let replay = |proof_randomness| {
+            let mut client = new_client();
+            client.import_storage(&storage).unwrap();
+            client
+                .verifiable_randomization_replay::<ConfG>(input(), proof_randomness)
+                .unwrap()
+        };
+        assert_eq!(replay(&proof_randomness), submission);
+        let other_randomness = <Conf as Config>::ZKPScheme::sample_proof_randomness(
+            &mut ChaChaRng::from_seed([82; 32]),
+        );
+        assert_ne!(replay(&other_randomness), submission);
+
+        // a client with a prover context creates the same proof
+        let prover_context = Arc::new(
+            CircuitExpand::<Conf, ConfG, 2, 2, 1, 2, 4, 4, false>::prover_context(
+                parameters.clone(),
+            )
+            .unwrap(),
+        );
+        let mut client = new_client().with_prover_context(prover_context);
+        client.import_storage(&storage).unwrap();
+        assert_eq!(
+            client
+                .verifiable_randomization_replay::<ConfG>(input(), &proof_randomness)
+                .unwrap(),
+            submission
+        );
+    }
+
+    #[test]
+    fn reconstructed_merkle_tree_has_claimed_root() {
+        let rng = &mut ChaChaRng::from_seed([78; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([79; 32]);
+        let parameters =
+            ParametersExpand::<Conf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, _) = CircuitExpand::<Conf, ConfG, 3, 2, 1, 2, 4, 4, false>::keygen(
+            parameters.clone(),
+            zkp_rng,
+        )
+        .unwrap();
+        let (server_sig_pk, _) = <Conf as Config>::ServerSignatureScheme::keygen(
+            &parameters.server_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, _) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientExpand::<Conf, 3, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server_sig_pk,
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        assert!(client.current_merkle_root().is_none());
+        client.generate_randomness_create(rng).unwrap();
+
+        let mut leaves = client
+            .commitments_from_generator_seed(client.storage.generator_seed.unwrap(), 4)
+            .unwrap();
+        let merkle_tree =
+            reconstruct_merkle_tree::<Conf>(&parameters.client_merkle_tree_scheme, &leaves, 3)
+                .unwrap();
+        assert_eq!(Some(merkle_tree.root()), client.current_merkle_root());
+
+        // the leaves must fill a tree of the given depth exactly
+        assert!(
+            reconstruct_merkle_tree::<Conf>(&parameters.client_merkle_tree_scheme, &leaves, 2)
+                .is_err()
+        );
+
+        // swapped leaves lead to another root
+        leaves.swap(0, 1);
+        let merkle_tree =
+            reconstruct_merkle_tree::<Conf>(&parameters.client_merkle_tree_scheme, &leaves, 3)
+                .unwrap();
+        assert_ne!(Some(merkle_tree.root()), client.current_merkle_root());
+    }
+
+    #[test]
+    fn incrementally_appended_leaves_match_a_fresh_tree() {
+        type Client = ClientExpand<Conf, 4, 2, 1, 2, 4, 4, false>;
+        let rng = &mut ChaChaRng::from_seed([105; 32]);
+        let parameters =
+            ParametersExpand::<Conf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let merkle_tree_parameters = &parameters.client_merkle_tree_scheme;
+        let (server_sig_pk, _) = <Conf as Config>::ServerSignatureScheme::keygen(
+            &parameters.server_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, _) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        // no proofs are created, so the proving key is not used
+        let new_client = || {
+            Client::new(
+                parameters.clone(),
+                server_sig_pk,
+                client_sig_pk,
+                ark_groth16::ProvingKey {
+                    vk: Default::default(),
+                    beta_g1: Default::default(),
+                    delta_g1: Default::default(),
+                    a_query: vec![],
+                    b_g1_query: vec![],
+                    b_g2_query: vec![],
+                    h_query: vec![],
+                    l_query: vec![],
+                },
+            )
+            .unwrap()
+        };
+
+        // every appended leaf is the one the next Randomize step proves for
+        let mut client = new_client();
+        for position in 0..8 {
+            let (root, merkle_path) = client.append_leaf(rng).unwrap();
+            assert_eq!(client.storage.index, position);
+            assert_eq!(merkle_path.leaf_index, position);
+            let leaves = client
+                .commitments_from_generator_seed(client.storage.generator_seed.unwrap(), 8)
+                .unwrap();
+            assert!(merkle_path
+                .verify(
+                    &merkle_tree_parameters.leaf_crh_params,
+                    &merkle_tree_parameters.two_to_one_crh_params,
+                    &root,
+                    leaves[position],
+                )
+                .unwrap());
         }
-        .serialize_compressed(&mut serialized_message)?;
-        Ok(serialized_message)
+
+        // the leaves are the ones of a tree created by `Generate Randomness` from the same seed
+        let generator_seed = client.storage.generator_seed.unwrap();
+        let leaves = client
+            .commitments_from_generator_seed(generator_seed, 8)
+            .unwrap();
+        let merkle_tree =
+            reconstruct_merkle_tree::<Conf>(merkle_tree_parameters, &leaves, 4).unwrap();
+        assert_eq!(client.current_merkle_root(), Some(merkle_tree.root()));
+        let mut generated_client = new_client();
+        generated_client
+            .generate_randomness_create_with_seed(generator_seed)
+            .unwrap();
+        assert_eq!(
+            generated_client.current_merkle_root(),
+            client.current_merkle_root()
+        );
+
+        // a full tree takes no further leaves, and neither does a tree of `Generate Randomness`
+        assert!(matches!(
+            client.append_leaf(rng),
+            Err(VldpError::Client(ClientError::MerkleTreeFull { leaves: 8 }))
+        ));
+        assert_eq!(client.storage.index, 7);
+        assert!(matches!(
+            generated_client.append_leaf(rng),
+            Err(VldpError::Client(ClientError::MerkleTreeFull { leaves: 8 }))
+        ));
+    }
+
+    #[test]
+    fn randomize_proves_for_an_appended_leaf() {
+        let rng = &mut ChaChaRng::from_seed([106; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([107; 32]);
+        let parameters =
+            ParametersExpand::<Conf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitExpand::<Conf, ConfG, 2, 2, 1, 2, 4, 4, false>::keygen(
+                parameters.clone(),
+                zkp_rng,
+            )
+            .unwrap();
+        let server = ServerExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            verifying_key,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let prf_eval_points = derive_prf_eval_points::<Conf>([4; 32], 1).unwrap();
+        let time_bounds = ([0], [1]);
+
+        for index in 0..2 {
+            let _ = client.append_leaf(rng).unwrap();
+            // the server has to sign the root of the tree with the appended leaf
+            let client_message = client.generate_randomness_create_appended().unwrap();
+            let server_message = server
+                .generate_randomness_create(&client_message, rng)
+                .unwrap();
+            client.generate_randomness_verify(&server_message).unwrap();
+
+            let submission = client
+                .verifiable_randomization_create::<ConfG>(
+                    time_bounds,
+                    [1],
+                    BigUint::from(3_u8),
+                    input_value_signature.clone(),
+                    &prf_eval_points,
+                    index,
+                    zkp_rng,
+                    false,
+                )
+                .unwrap();
+            assert!(
+                server
+                    .verifiable_randomization_verify::<ConfG>(
+                        &submission,
+                        time_bounds,
+                        &prf_eval_points,
+                        index,
+                        zkp_rng,
+                        false
+                    )
+                    .unwrap()
+                    .0
+            );
+        }
+    }
+
+    #[test]
+    fn server_signature_over_another_root_is_rejected() {
+        type Client = ClientExpand<Conf, 3, 2, 1, 2, 4, 4, false>;
+        let rng = &mut ChaChaRng::from_seed([118; 32]);
+        let parameters =
+            ParametersExpand::<Conf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (server_sig_pk, server_sig_sk) = <Conf as Config>::ServerSignatureScheme::keygen(
+            &parameters.server_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut clients = (0..2)
+            .map(|_| {
+                let (client_sig_pk, _) = <Conf as Config>::ClientSignatureScheme::keygen(
+                    &parameters.client_signature_scheme,
+                    rng,
+                )
+                .unwrap();
+                // the proving key is not needed for the `GenRand` step
+                let proving_key = ark_groth16::ProvingKey {
+                    vk: Default::default(),
+                    beta_g1: Default::default(),
+                    delta_g1: Default::default(),
+                    a_query: vec![],
+                    b_g1_query: vec![],
+                    b_g2_query: vec![],
+                    h_query: vec![],
+                    l_query: vec![],
+                };
+                let mut client = Client::new(
+                    parameters.clone(),
+                    server_sig_pk,
+                    client_sig_pk,
+                    proving_key,
+                )
+                .unwrap();
+                client.generate_randomness_create(rng).unwrap();
+                client
+            })
+            .collect::<Vec<_>>();
+
+        // server message signed over the given root for the first client
+        let mut server_message = |root: ClientMerkleTreeRoot<Conf>| {
+            let server_seed = rng.gen::<PRFSchemeSeed<Conf>>();
+            let server_nonce = rng.gen::<[u8; 16]>();
+            let mut signature_input_bytes = Vec::new();
+            GenerateRandomnessSignatureInputExpand::<Conf> {
+                client_merkle_tree_root: root,
+                client_signature_public_key: clients[0].client_sig_pk,
+                server_seed,
+                expiry: None,
+                server_nonce,
+            }
+            .serialize_uncompressed(&mut signature_input_bytes)
+            .unwrap();
+            let server_signature = <Conf as Config>::ServerSignatureScheme::sign(
+                &parameters.server_signature_scheme,
+                &server_sig_sk,
+                &signature_input_bytes,
+                rng,
+            )
+            .unwrap();
+            let mut bytes = vec![];
+            GenerateRandomnessMessageServerExpand::<Conf> {
+                server_seed,
+                server_signature,
+                expiry: None,
+                server_nonce,
+            }
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+            bytes
+        };
+
+        let other_root = clients[1].current_merkle_root().unwrap();
+        let own_root = clients[0].current_merkle_root().unwrap();
+        let wrong_root_message = server_message(other_root);
+        let own_root_message = server_message(own_root);
+        let error = clients[0]
+            .generate_randomness_verify(&wrong_root_message)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Protocol(Side::Peer, ProtocolError::ServerSignatureInvalid)
+        ));
+        assert!(clients[0].storage.server_seed.is_none());
+        clients[0]
+            .generate_randomness_verify(&own_root_message)
+            .unwrap();
     }
 }
@@ -8,3 +8,173 @@ pub use expand::*;
 
 pub mod shuffle;
 pub use shuffle::*;
+
+pub mod pool;
+pub use pool::*;
+
+pub mod stream;
+pub use stream::*;
+
+use crate::prelude::*;
+use num_bigint::BigUint;
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use std::collections::HashMap;
+
+/// Commitment randomness of the `Generate Randomness` step that is derived from `seed`. The
+/// clients store the seed rather than the randomness itself, as the randomness cannot be
+/// deserialized for every commitment scheme (see `ClientBase::import_storage`).
+pub(crate) fn derive_commitment_randomness<Conf: Config>(
+    seed: [u8; 32],
+) -> ClientCommitmentSchemeRandomness<Conf> {
+    ClientCommitmentSchemeRandomness::<Conf>::rand(&mut ChaChaRng::from_seed(seed))
+}
+
+/// Check that no two clients of a population were provisioned with the same signature public key,
+/// which would make their submissions indistinguishable to the server. Otherwise, fails with
+/// `ClientError::DuplicatePublicKeys`, listing the indices (into `keys`) of each set of clients
+/// that share a key.
+pub fn validate_distinct_keys<Conf: Config>(
+    keys: &[ClientSignatureSchemePublicKey<Conf>],
+) -> Result<(), Error> {
+    let mut indices_by_key = HashMap::<_, Vec<usize>>::new();
+    for (index, key) in keys.iter().enumerate() {
+        indices_by_key.entry(key).or_default().push(index);
+    }
+    let mut collisions = indices_by_key
+        .into_values()
+        .filter(|indices| indices.len() > 1)
+        .collect::<Vec<_>>();
+    if collisions.is_empty() {
+        Ok(())
+    } else {
+        collisions.sort();
+        Err(ClientError::DuplicatePublicKeys(collisions))?
+    }
+}
+
+/// Encode the time `value` (e.g., a day index) as `TIME_BYTES` little-endian bytes, as expected for
+/// the input value time and the `time_bounds` of the `Randomize` step. Fails with
+/// `ClientError::TimeTooLarge` if `value` does not fit into `TIME_BYTES` bytes, rather than
+/// silently truncating it.
+pub fn encode_time<const TIME_BYTES: usize>(value: u64) -> Result<[u8; TIME_BYTES], Error> {
+    let value_bytes = value.to_le_bytes();
+    let used_bytes = TIME_BYTES.min(value_bytes.len());
+    if value_bytes[used_bytes..].iter().any(|byte| *byte != 0) {
+        Err(ClientError::TimeTooLarge {
+            value,
+            time_bytes: TIME_BYTES,
+        })?
+    }
+    let mut time = [0; TIME_BYTES];
+    time[..used_bytes].copy_from_slice(&value_bytes[..used_bytes]);
+    Ok(time)
+}
+
+/// Encode the input `value` as `INPUT_BYTES` little-endian bytes, as signed by the trusted
+/// environment. Fails with `ClientError::InputTooLarge` if `value` does not fit into
+/// `INPUT_BYTES` bytes.
+pub fn encode_input_value<const INPUT_BYTES: usize>(
+    value: &BigUint,
+) -> Result<[u8; INPUT_BYTES], Error> {
+    let value_bytes = value.to_bytes_le();
+    if value_bytes.len() > INPUT_BYTES {
+        Err(ClientError::InputTooLarge {
+            value: value.clone(),
+            input_bytes: INPUT_BYTES,
+        })?
+    }
+    let mut input_value = [0; INPUT_BYTES];
+    input_value[..value_bytes.len()].copy_from_slice(&value_bytes);
+    Ok(input_value)
+}
+
+/// Decode a time encoded with `encode_time`. Fails with `GenericError::ConversionError` if the
+/// time does not fit into a `u64` (only possible for `TIME_BYTES > 8`).
+pub fn decode_time<const TIME_BYTES: usize>(time: &[u8; TIME_BYTES]) -> Result<u64, Error> {
+    let used_bytes = TIME_BYTES.min(8);
+    if time[used_bytes..].iter().any(|byte| *byte != 0) {
+        Err(GenericError::ConversionError)?
+    }
+    let mut value_bytes = [0; 8];
+    value_bytes[..used_bytes].copy_from_slice(&time[..used_bytes]);
+    Ok(u64::from_le_bytes(value_bytes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode_time, encode_input_value, encode_time, validate_distinct_keys};
+    use crate::config::BasicConfig;
+    use crate::prelude::*;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+
+    #[test]
+    fn duplicate_keys_are_reported() {
+        let rng = &mut ChaChaRng::from_seed([51; 32]);
+        let parameters = <Conf as Config>::ClientSignatureScheme::setup(rng).unwrap();
+        let mut keys = (0..5)
+            .map(|_| {
+                <Conf as Config>::ClientSignatureScheme::keygen(&parameters, rng)
+                    .unwrap()
+                    .0
+            })
+            .collect::<Vec<_>>();
+        validate_distinct_keys::<Conf>(&keys).unwrap();
+
+        keys[3] = keys[1];
+        let error = validate_distinct_keys::<Conf>(&keys).unwrap_err();
+        match &error {
+            VldpError::Client(ClientError::DuplicatePublicKeys(collisions)) => {
+                assert_eq!(collisions, &vec![vec![1, 3]])
+            }
+            _ => panic!("unexpected error: {}", error),
+        }
+    }
+
+    #[test]
+    fn time_is_encoded_across_all_bytes() {
+        // a day index beyond a single byte
+        let time = encode_time::<4>(1000).unwrap();
+        assert_eq!(time, [0xe8, 0x03, 0, 0]);
+        assert_eq!(u32::from_le_bytes(time), 1000);
+        assert_eq!(decode_time(&time).unwrap(), 1000);
+        assert_eq!(
+            decode_time(&encode_time::<10>(u64::MAX).unwrap()).unwrap(),
+            u64::MAX
+        );
+
+        // values that do not fit are rejected instead of wrapped
+        assert!(matches!(
+            encode_time::<1>(256).unwrap_err(),
+            VldpError::Client(ClientError::TimeTooLarge {
+                value: 256,
+                time_bytes: 1,
+            })
+        ));
+        assert!(encode_time::<4>(1 << 32).is_err());
+        assert!(decode_time(&[0, 0, 0, 0, 0, 0, 0, 0, 1]).is_err());
+    }
+
+    #[test]
+    fn input_value_that_does_not_fit_is_rejected() {
+        assert_eq!(
+            encode_input_value::<4>(&BigUint::from(1000_u32)).unwrap(),
+            [0xe8, 0x03, 0, 0]
+        );
+        assert_eq!(
+            encode_input_value::<2>(&BigUint::from(u16::MAX)).unwrap(),
+            [0xff, 0xff]
+        );
+        assert!(matches!(
+            encode_input_value::<2>(&BigUint::from(1_u32 << 16)).unwrap_err(),
+            VldpError::Client(ClientError::InputTooLarge { input_bytes: 2, .. })
+        ));
+    }
+}
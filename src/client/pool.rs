@@ -0,0 +1,276 @@
+//! Pool of pre-sampled commitment randomness for clients in the Base and Shuffle schemes.
+//!
+//! Clients of the Expand scheme do not sample commitment randomness in the `GenRand` step: all of
+//! it is derived from the seed of their generator (see
+//! `ClientExpand::generate_randomness_create_with_seed`), such that it can be reconstructed for
+//! every `Randomize` step. A pool is therefore not applicable to them.
+
+use super::derive_commitment_randomness;
+use crate::prelude::*;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+/// Pool of pre-sampled commitment randomness, such that latency-sensitive clients do not have to
+/// sample it during the `GenRand` step. Every value is derived from a seed (see
+/// `derive_commitment_randomness`), which is handed out along with it.
+///
+/// Clones share the same underlying pool, so a clone can be refilled from a background thread
+/// (see `refill_in_background`) while the client pops values from it. Every value is handed out
+/// at most once.
+pub struct CommitmentRandomnessPool<Conf: Config> {
+    values: Arc<Mutex<VecDeque<SeededRandomness<Conf>>>>,
+    /// Notified whenever a value is popped, to wake up a background refill.
+    popped: Arc<Condvar>,
+}
+
+/// Commitment randomness along with the seed it was derived from
+pub type SeededRandomness<Conf> = ([u8; 32], ClientCommitmentSchemeRandomness<Conf>);
+
+/// Handle of a background refill of a `CommitmentRandomnessPool`, which stops the refill when it
+/// is dropped.
+pub struct RefillHandle {
+    stopped: Arc<AtomicBool>,
+    wake: Box<dyn Fn() + Send>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for RefillHandle {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        (self.wake)();
+        if let Some(thread) = self.thread.take() {
+            // the refill thread does not panic, apart from a panicking rng
+            let _ = thread.join();
+        }
+    }
+}
+
+// We need to manually implement clone, as `Conf` itself does not need to be cloned.
+impl<Conf: Config> Clone for CommitmentRandomnessPool<Conf> {
+    fn clone(&self) -> Self {
+        Self {
+            values: self.values.clone(),
+            popped: self.popped.clone(),
+        }
+    }
+}
+
+impl<Conf: Config> Default for CommitmentRandomnessPool<Conf> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Conf: Config> CommitmentRandomnessPool<Conf> {
+    /// Construct an empty pool
+    pub fn new() -> Self {
+        Self {
+            values: Arc::new(Mutex::new(VecDeque::new())),
+            popped: Arc::new(Condvar::new()),
+        }
+    }
+
+    /// Sample `n` new values and add them to the pool.
+    pub fn fill<R: Rng + CryptoRng>(&self, n: usize, rng: &mut R) {
+        // sample outside the lock, so popping is never blocked by sampling
+        let values = Self::sample(n, rng);
+        self.lock().extend(values);
+    }
+
+    /// Keep the pool filled with (at least) `target` values from a background thread, which
+    /// samples with `rng` whenever values have been popped. The refill runs until the returned
+    /// handle is dropped.
+    pub fn refill_in_background<R: Rng + CryptoRng + Send + 'static>(
+        &self,
+        target: usize,
+        mut rng: R,
+    ) -> RefillHandle
+    where
+        Conf: 'static,
+        ClientCommitmentSchemeRandomness<Conf>: Send,
+    {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let pool = self.clone();
+        let thread_stopped = stopped.clone();
+        let thread = std::thread::spawn(move || loop {
+            let missing = {
+                let mut values = pool.lock();
+                while values.len() >= target && !thread_stopped.load(Ordering::SeqCst) {
+                    values = pool
+                        .popped
+                        .wait(values)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                }
+                if thread_stopped.load(Ordering::SeqCst) {
+                    return;
+                }
+                target - values.len()
+            };
+            pool.fill(missing, &mut rng);
+        });
+        let pool = self.clone();
+        RefillHandle {
+            stopped,
+            wake: Box::new(move || {
+                // notify while holding the lock, such that the thread is either waiting or
+                // checks the stop flag before it waits again
+                let _values = pool.lock();
+                pool.popped.notify_all();
+            }),
+            thread: Some(thread),
+        }
+    }
+
+    /// Remove a value (along with its seed) from the pool, if there is any left.
+    pub fn pop(&self) -> Option<SeededRandomness<Conf>> {
+        let value = self.lock().pop_front();
+        self.popped.notify_all();
+        value
+    }
+
+    /// Number of values that are still available in the pool.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether the pool has run out of values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn sample<R: Rng + CryptoRng>(n: usize, rng: &mut R) -> Vec<SeededRandomness<Conf>> {
+        (0..n)
+            .map(|_| {
+                let mut seed = [0; 32];
+                rng.fill_bytes(&mut seed);
+                (seed, derive_commitment_randomness::<Conf>(seed))
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<SeededRandomness<Conf>>> {
+        // a panic while holding the lock cannot leave the queue in an inconsistent state
+        self.values
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CommitmentRandomnessPool;
+    use crate::client::ClientShuffle;
+    use crate::config::BasicConfig;
+    use crate::prelude::*;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use astro_float::BigFloat;
+    use rand::{RngCore, SeedableRng};
+    use rand_chacha::ChaChaRng;
+    use std::collections::HashSet;
+    use std::time::{Duration, Instant};
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    type Client = ClientShuffle<Conf, 8, 1, 8, 16, 8, false>;
+
+    fn client(rng: &mut ChaChaRng) -> Client {
+        let parameters = ParametersShuffle::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (server_sig_pk, _) = <Conf as Config>::ServerSignatureScheme::keygen(
+            &parameters.server_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, _) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        // the proving key is not needed for the `GenRand` step
+        let proving_key = ark_groth16::ProvingKey {
+            vk: Default::default(),
+            beta_g1: Default::default(),
+            delta_g1: Default::default(),
+            a_query: vec![],
+            b_g1_query: vec![],
+            b_g2_query: vec![],
+            h_query: vec![],
+            l_query: vec![],
+        };
+        Client::new(parameters, server_sig_pk, client_sig_pk, proving_key).unwrap()
+    }
+
+    #[test]
+    fn pool_supplies_distinct_values() {
+        let rng = &mut ChaChaRng::from_seed([1; 32]);
+        let pool = CommitmentRandomnessPool::<Conf>::new();
+        pool.fill(16, rng);
+        pool.clone().fill(16, rng);
+        assert_eq!(pool.len(), 32);
+
+        let values = (0..32)
+            .map(|_| pool.pop().unwrap().0)
+            .collect::<HashSet<_>>();
+        assert_eq!(values.len(), 32);
+        assert!(pool.is_empty());
+        assert!(pool.pop().is_none());
+    }
+
+    #[test]
+    fn background_refill_keeps_the_pool_filled() {
+        let pool = CommitmentRandomnessPool::<Conf>::new();
+        let wait_for_len = |len| {
+            let start = Instant::now();
+            while pool.len() != len {
+                assert!(
+                    start.elapsed() < Duration::from_secs(10),
+                    "pool was not refilled"
+                );
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        };
+        let refill = pool.refill_in_background(8, ChaChaRng::from_seed([4; 32]));
+        wait_for_len(8);
+
+        let mut values = HashSet::new();
+        for _ in 0..3 {
+            for _ in 0..5 {
+                assert!(values.insert(pool.pop().unwrap().0));
+            }
+            wait_for_len(8);
+        }
+
+        // after the refill is stopped, the pool runs empty
+        drop(refill);
+        for _ in 0..8 {
+            assert!(values.insert(pool.pop().unwrap().0));
+        }
+        assert!(pool.is_empty());
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn pooled_commitment_equals_on_demand_commitment() {
+        let mut on_demand_client = client(&mut ChaChaRng::from_seed([2; 32]));
+        let on_demand_message = on_demand_client
+            .generate_randomness_create(&mut ChaChaRng::from_seed([3; 32]))
+            .unwrap();
+
+        // the on-demand path samples the commitment seed right after the 32-byte client seed
+        let mut pool_rng = ChaChaRng::from_seed([3; 32]);
+        pool_rng.fill_bytes(&mut [0; 32]);
+        let pool = CommitmentRandomnessPool::<Conf>::new();
+        pool.fill(1, &mut pool_rng);
+
+        let mut pooled_client = client(&mut ChaChaRng::from_seed([2; 32]))
+            .with_commitment_randomness_pool(pool.clone());
+        let pooled_message = pooled_client
+            .generate_randomness_create(&mut ChaChaRng::from_seed([3; 32]))
+            .unwrap();
+        assert_eq!(on_demand_message, pooled_message);
+        assert!(pool.is_empty());
+    }
+}
@@ -1,21 +1,28 @@
 //! All functionalities for a client in the Expand scheme
 
-use crate::circuits::CircuitShuffle;
+use super::{derive_commitment_randomness, CommitmentRandomnessPool};
+use crate::circuits::{CircuitShuffle, ProofRandomnessSource, WitnessShuffle};
+use crate::messages::is_weak_seed;
 use crate::messages::shuffle::*;
 use crate::prelude::*;
+use crate::primitives::ldp::apply_ldp;
+use crate::server::check_gamma_bytes;
+use crate::time::check_time_in_bounds;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use ark_std::Zero;
 use num_bigint::BigUint;
-use std::cmp::min;
+use std::collections::HashSet;
+use std::sync::Arc;
 
 /// Storage of values between steps for a client in the Shuffle scheme
 #[derive(Clone)]
 pub struct ClientShuffleStorage<Conf: Config> {
     pub client_seed: Option<PRFSchemeSeed<Conf>>,
     pub client_seed_commitment_randomness: Option<ClientCommitmentSchemeRandomness<Conf>>,
+    pub client_seed_commitment_seed: Option<[u8; 32]>,
     pub client_seed_commitment: Option<ClientCommitmentSchemeOutput<Conf>>,
     pub server_seed: Option<PRFSchemeSeed<Conf>>,
     pub server_signature: Option<ServerSignatureSchemeSignature<Conf>>,
+    pub server_nonce: Option<[u8; 16]>,
 }
 
 impl<Conf: Config> ClientShuffleStorage<Conf> {
@@ -24,11 +31,34 @@ impl<Conf: Config> ClientShuffleStorage<Conf> {
         Self {
             client_seed: None,
             client_seed_commitment_randomness: None,
+            client_seed_commitment_seed: None,
             client_seed_commitment: None,
             server_seed: None,
             server_signature: None,
+            server_nonce: None,
         }
     }
+
+    /// Whether the stored values form a state the client can reach, i.e., the values of each step
+    /// are either all present or all absent, and no step has values without the steps before it.
+    fn is_consistent(&self) -> bool {
+        let client_values = [
+            self.client_seed.is_some(),
+            self.client_seed_commitment_randomness.is_some(),
+            self.client_seed_commitment_seed.is_some(),
+            self.client_seed_commitment.is_some(),
+        ];
+        let server_values = [
+            self.server_seed.is_some(),
+            self.server_signature.is_some(),
+            self.server_nonce.is_some(),
+        ];
+        let has_client_values = client_values.iter().all(|&value| value);
+        let has_server_values = server_values.iter().all(|&value| value);
+        (has_client_values || client_values.iter().all(|&value| !value))
+            && (has_server_values || server_values.iter().all(|&value| !value))
+            && (has_client_values || !has_server_values)
+    }
 }
 
 /// Shuffle scheme client
@@ -44,8 +74,11 @@ pub struct ClientShuffle<
     parameters: ParametersShuffle<Conf, GAMMA_BYTES>,
     server_sig_pk: ServerSignatureSchemePublicKey<Conf>,
     client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
-    proving_key: ProvingKey<Conf>,
+    proving_key: Arc<ProvingKey<Conf>>,
+    verifying_key: Option<VerifyingKey<Conf>>,
     storage: ClientShuffleStorage<Conf>,
+    commitment_randomness_pool: Option<CommitmentRandomnessPool<Conf>>,
+    prover_context: Option<Arc<ProverContext<Conf>>>,
 }
 
 impl<
@@ -66,15 +99,158 @@ impl<
         client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
         proving_key: ProvingKey<Conf>,
     ) -> Result<Self, Error> {
+        Self::new_with_shared_proving_key(
+            parameters,
+            server_sig_pk,
+            client_sig_pk,
+            Arc::new(proving_key),
+        )
+    }
+
+    /// Create a new client like `new`, but with a proof generation key that is shared with other
+    /// clients instead of owned by this client.
+    pub fn new_with_shared_proving_key(
+        parameters: ParametersShuffle<Conf, GAMMA_BYTES>,
+        server_sig_pk: ServerSignatureSchemePublicKey<Conf>,
+        client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
+        proving_key: Arc<ProvingKey<Conf>>,
+    ) -> Result<Self, Error> {
+        validate_scheme_params::<INPUT_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>()?;
         Ok(Self {
             parameters,
             server_sig_pk,
             client_sig_pk,
             proving_key,
+            verifying_key: None,
             storage: ClientShuffleStorage::new(),
+            commitment_randomness_pool: None,
+            prover_context: None,
         })
     }
 
+    /// Reuse this client (including its proof generation key) for another client, which has the
+    /// given signature public key. This clears all values stored during previous steps.
+    pub fn reset(&mut self, client_sig_pk: ClientSignatureSchemePublicKey<Conf>) {
+        self.client_sig_pk = client_sig_pk;
+        self.storage = ClientShuffleStorage::new();
+    }
+
+    /// Serialize all values stored during previous steps, such that a client that crashes between
+    /// the `Generate Randomness` and `Randomize` steps can continue with `import_storage`. The
+    /// commitment randomness is represented by the seed it was derived from.
+    ///
+    /// Note: the exported bytes contain the client seed and must be kept secret.
+    pub fn export_storage(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        self.storage.client_seed.serialize_compressed(&mut bytes)?;
+        self.storage
+            .client_seed_commitment_seed
+            .serialize_compressed(&mut bytes)?;
+        self.storage
+            .client_seed_commitment
+            .serialize_compressed(&mut bytes)?;
+        self.storage.server_seed.serialize_compressed(&mut bytes)?;
+        self.storage
+            .server_signature
+            .serialize_compressed(&mut bytes)?;
+        self.storage.server_nonce.serialize_compressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Replace the values stored during previous steps by the ones exported with `export_storage`.
+    /// Fails with `ClientError::InconsistentStorage` if the imported values do not form a state the
+    /// client can reach or disagree with each other (e.g., a commitment that does not open to the
+    /// client seed), in which case the current storage is kept.
+    pub fn import_storage(&mut self, bytes: &[u8]) -> Result<(), Error>
+    where
+        ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
+        let mut reader = bytes;
+        let client_seed = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let client_seed_commitment_seed: Option<[u8; 32]> =
+            CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let client_seed_commitment = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let server_seed = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let server_signature = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        let server_nonce = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+        if !reader.is_empty() {
+            Err(ClientError::InconsistentStorage)?
+        }
+
+        let storage = ClientShuffleStorage::<Conf> {
+            client_seed,
+            client_seed_commitment_randomness: client_seed_commitment_seed
+                .map(derive_commitment_randomness::<Conf>),
+            client_seed_commitment_seed,
+            client_seed_commitment,
+            server_seed,
+            server_signature,
+            server_nonce,
+        };
+        if !storage.is_consistent() {
+            Err(ClientError::InconsistentStorage)?
+        }
+
+        // the commitment has to open to the client seed, and the server signature has to be valid
+        // for the commitment
+        if let (Some(client_seed), Some(commitment_randomness), Some(commitment)) = (
+            &storage.client_seed,
+            &storage.client_seed_commitment_randomness,
+            &storage.client_seed_commitment,
+        ) {
+            if Conf::ClientCommitmentScheme::commit(
+                &self.parameters.client_commitment_scheme,
+                client_seed,
+                commitment_randomness,
+            )? != *commitment
+            {
+                Err(ClientError::InconsistentStorage)?
+            }
+            if let (Some(server_seed), Some(server_signature), Some(server_nonce)) = (
+                &storage.server_seed,
+                &storage.server_signature,
+                &storage.server_nonce,
+            ) {
+                let signature_input = GenerateRandomnessSignatureInputShuffle::<Conf> {
+                    client_seed_commitment: commitment.clone(),
+                    client_signature_public_key: self.client_sig_pk.clone(),
+                    server_seed: *server_seed,
+                    server_nonce: *server_nonce,
+                };
+                if !self.verify_server_signature(&signature_input, server_signature)? {
+                    Err(ClientError::InconsistentStorage)?
+                }
+            }
+        }
+        self.storage = storage;
+        Ok(())
+    }
+
+    /// Draw the commitment randomness of the `Generate Randomness` step from the given pool
+    /// instead of sampling it on demand. Falls back to sampling if the pool has run empty.
+    pub fn with_commitment_randomness_pool(mut self, pool: CommitmentRandomnessPool<Conf>) -> Self {
+        self.commitment_randomness_pool = Some(pool);
+        self
+    }
+
+    /// Create proofs with the given prover context (see `CircuitShuffle::prover_context`), which
+    /// can be shared with other clients like the proving key, instead of redoing the
+    /// circuit-invariant precomputation for every proof. The context must be computed for the same
+    /// parameters.
+    pub fn with_prover_context(mut self, prover_context: Arc<ProverContext<Conf>>) -> Self {
+        self.prover_context = Some(prover_context);
+        self
+    }
+
+    /// Give the client the proof verification key as well, such that it can check its own
+    /// messages with `verify_own_proof` before sending them.
+    pub fn with_verifying_key(mut self, verifying_key: VerifyingKey<Conf>) -> Self {
+        self.verifying_key = Some(verifying_key);
+        self
+    }
+
     /// Perform the first part of the `Generate Randomness` step of the client.
     pub fn generate_randomness_create<R: Rng + CryptoRng>(
         &mut self,
@@ -86,7 +262,34 @@ impl<
     {
         let mut client_seed = PRFSchemeSeed::<Conf>::default();
         rng.fill_bytes(&mut client_seed);
-        let commitment_randomness = ClientCommitmentSchemeRandomness::<Conf>::rand(rng);
+        self.generate_randomness_create_with_seed(client_seed, rng)
+    }
+
+    /// Perform the first part of the `Generate Randomness` step like `generate_randomness_create`,
+    /// but with a client seed from an external source (e.g., a hardware token or a KDF) instead of
+    /// one drawn from `rng`, which is still used for the commitment randomness (unless it is drawn
+    /// from a pool). The seed must be secret and must not be reused.
+    pub fn generate_randomness_create_with_seed<R: Rng + CryptoRng>(
+        &mut self,
+        client_seed: PRFSchemeSeed<Conf>,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error>
+    where
+        ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    {
+        let (commitment_seed, commitment_randomness) = self
+            .commitment_randomness_pool
+            .as_ref()
+            .and_then(|pool| pool.pop())
+            .unwrap_or_else(|| {
+                let mut commitment_seed = [0; 32];
+                rng.fill_bytes(&mut commitment_seed);
+                (
+                    commitment_seed,
+                    derive_commitment_randomness::<Conf>(commitment_seed),
+                )
+            });
         let client_seed_commitment = Conf::ClientCommitmentScheme::commit(
             &self.parameters.client_commitment_scheme,
             &client_seed,
@@ -96,6 +299,7 @@ impl<
         // storage
         self.storage.client_seed = Some(client_seed);
         self.storage.client_seed_commitment_randomness = Some(commitment_randomness);
+        self.storage.client_seed_commitment_seed = Some(commitment_seed);
         self.storage.client_seed_commitment = Some(client_seed_commitment.clone());
 
         // return message
@@ -109,7 +313,10 @@ impl<
     }
 
     /// Perform the second part of the `Generate Randomness` step of the client.
-    pub fn generate_randomness_verify(&mut self, server_message: &[u8]) -> Result<bool, Error>
+    ///
+    /// A server message whose signature does not verify is rejected with
+    /// `ProtocolError::ServerSignatureInvalid` (tagged `Side::Peer`).
+    pub fn generate_randomness_verify(&mut self, server_message: &[u8]) -> Result<(), Error>
     where
         ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
         ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
@@ -118,6 +325,9 @@ impl<
         // deserialize server message
         let server_message =
             GenerateRandomnessMessageServerShuffle::<Conf>::deserialize_compressed(server_message)?;
+        if is_weak_seed(&server_message.server_seed) {
+            Err(ClientError::WeakServerSeed)?
+        }
 
         // reconstruct signature input
         let signature_input = GenerateRandomnessSignatureInputShuffle::<Conf> {
@@ -128,32 +338,45 @@ impl<
                 .ok_or(ClientError::UnobtainedValue)?,
             client_signature_public_key: self.client_sig_pk.clone(),
             server_seed: server_message.server_seed.clone(),
+            server_nonce: server_message.server_nonce,
         };
-        let mut signature_input_bytes = Vec::new();
-        signature_input.serialize_uncompressed(&mut signature_input_bytes)?;
 
         // verify signature
-        if Conf::ServerSignatureScheme::verify(
+        if !self.verify_server_signature(&signature_input, &server_message.server_signature)? {
+            Err(ProtocolError::ServerSignatureInvalid.peer())?
+        }
+
+        // storage
+        self.storage.server_seed = Some(server_message.server_seed);
+        self.storage.server_signature = Some(server_message.server_signature);
+        self.storage.server_nonce = Some(server_message.server_nonce);
+        Ok(())
+    }
+
+    /// Verify the server signature over the given signature input.
+    fn verify_server_signature(
+        &self,
+        signature_input: &GenerateRandomnessSignatureInputShuffle<Conf>,
+        server_signature: &ServerSignatureSchemeSignature<Conf>,
+    ) -> Result<bool, Error>
+    where
+        ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    {
+        let mut signature_input_bytes = Vec::new();
+        signature_input.serialize_uncompressed(&mut signature_input_bytes)?;
+        Conf::ServerSignatureScheme::verify(
             &self.parameters.server_signature_scheme,
             &self.server_sig_pk,
             &signature_input_bytes,
-            &server_message.server_signature,
-        )? {
-            // storage
-            self.storage.server_seed = Some(server_message.server_seed);
-            self.storage.server_signature = Some(server_message.server_signature);
-
-            // return success
-            Ok(true)
-        } else {
-            // signature verification failed
-            Ok(false)
-        }
+            server_signature,
+        )
     }
 
     /// Given the time bounds of the current step, the true input value, the time it was created,
-    /// and its signature, along with the list of public `prf_eval_points` (s in the paper) and
-    /// current `index` (j in the paper) perform the `Randomize` step of the client.
+    /// and its signature, along with the public `prf_eval_points` (s in the paper) of the current
+    /// epoch perform the `Randomize` step of the client. The proof is bound to the epoch of
+    /// `prf_eval_points`, and only verifies for the same set (see `PrfEvalPointSet`).
     ///
     /// The `skip_proof` flag can be set to `true` to do a faster test run of this function that
     /// only executes the randomization (without proof generation).
@@ -164,13 +387,94 @@ impl<
         input_value_time: [u8; TIME_BYTES],
         input_value: BigUint,
         input_value_signature: ClientSignatureSchemeSignature<Conf>,
-        prf_eval_points: &[PRFSchemeInput<Conf>],
+        prf_eval_points: &PrfEvalPointSet<Conf>,
         zkp_rng: &mut ZKPRng<Conf>,
         skip_proof: bool,
     ) -> Result<Vec<u8>, Error>
     where
         Proof<Conf>: CanonicalDeserialize,
     {
+        self.randomization_message::<ConfG>(
+            time_bounds,
+            input_value_time,
+            input_value,
+            input_value_signature,
+            prf_eval_points,
+            ProofRandomnessSource::new(zkp_rng, skip_proof),
+        )
+    }
+
+    /// Perform the `Randomize` step like `verifiable_randomization_create`, and additionally return
+    /// the randomness of the proof. With it, `verifiable_randomization_replay` reproduces the exact
+    /// message from the same inputs (and client storage), e.g., to demonstrate to an auditor how a
+    /// proof was generated.
+    ///
+    /// Note: the proof randomness reveals the witness of the proof (including the input value) and
+    /// must be stored as securely as the input value itself.
+    pub fn verifiable_randomization_create_recorded<ConfG: ConfigGadget<Conf>>(
+        &self,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        input_value_time: [u8; TIME_BYTES],
+        input_value: BigUint,
+        input_value_signature: ClientSignatureSchemeSignature<Conf>,
+        prf_eval_points: &PrfEvalPointSet<Conf>,
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<(Vec<u8>, ProofRandomness<Conf>), Error>
+    where
+        Proof<Conf>: CanonicalDeserialize,
+    {
+        let proof_randomness = Conf::ZKPScheme::sample_proof_randomness(zkp_rng);
+        let message = self.randomization_message::<ConfG>(
+            time_bounds,
+            input_value_time,
+            input_value,
+            input_value_signature,
+            prf_eval_points,
+            ProofRandomnessSource::Recorded(&proof_randomness),
+        )?;
+        Ok((message, proof_randomness))
+    }
+
+    /// Reproduce the message of `verifiable_randomization_create_recorded` from the same inputs
+    /// and the recorded proof randomness.
+    pub fn verifiable_randomization_replay<ConfG: ConfigGadget<Conf>>(
+        &self,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        input_value_time: [u8; TIME_BYTES],
+        input_value: BigUint,
+        input_value_signature: ClientSignatureSchemeSignature<Conf>,
+        prf_eval_points: &PrfEvalPointSet<Conf>,
+        proof_randomness: &ProofRandomness<Conf>,
+    ) -> Result<Vec<u8>, Error>
+    where
+        Proof<Conf>: CanonicalDeserialize,
+    {
+        self.randomization_message::<ConfG>(
+            time_bounds,
+            input_value_time,
+            input_value,
+            input_value_signature,
+            prf_eval_points,
+            ProofRandomnessSource::Recorded(proof_randomness),
+        )
+    }
+
+    /// Create the message of the `Randomize` step, with a proof from the given `source` of proof
+    /// randomness.
+    fn randomization_message<ConfG: ConfigGadget<Conf>>(
+        &self,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        input_value_time: [u8; TIME_BYTES],
+        input_value: BigUint,
+        input_value_signature: ClientSignatureSchemeSignature<Conf>,
+        prf_eval_points: &PrfEvalPointSet<Conf>,
+        source: ProofRandomnessSource<'_, Conf>,
+    ) -> Result<Vec<u8>, Error>
+    where
+        Proof<Conf>: CanonicalDeserialize,
+    {
+        check_time_in_bounds(&input_value_time, &time_bounds)?;
+
         // compute full seed from client and server part
         let mut seed = self
             .storage
@@ -180,68 +484,27 @@ impl<
             .zip(
                 self.storage
                     .server_seed
-                    .ok_or(ClientError::UnobtainedValue)?,
+                    .ok_or(ProtocolError::GenerateRandomnessIncomplete.local())?,
             )
             .for_each(|(client_byte, server_byte)| *client_byte ^= server_byte);
 
         // compute randomness from seeds
         let mut randomness = [0; RANDOMNESS_BYTES];
-        for (chunk, prf_eval_point) in randomness.chunks_mut(32).zip(prf_eval_points) {
+        for (chunk, prf_eval_point) in randomness
+            .chunks_mut(Conf::PRF_OUTPUT_BYTES)
+            .zip(prf_eval_points.points.iter())
+        {
             chunk.copy_from_slice(
-                &Conf::PRFScheme::evaluate(&seed, &prf_eval_point)?[0..chunk.len()],
+                &Conf::PRFScheme::evaluate(&seed, &prf_eval_point)?.as_ref()[0..chunk.len()],
             );
         }
 
         // apply LDP
-        let ldp_bit = {
-            (BigUint::from_bytes_le(&randomness[0..GAMMA_BYTES])
-                <= BigUint::from_bytes_le(&self.parameters.gamma_as_bytes()?)) as u8
-        };
-
-        let ldp_value = if ldp_bit == 0 {
-            if IS_REAL_INPUT {
-                let input_value_times_k = &input_value * K;
-                let multiplicand =
-                    &input_value_times_k / BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]);
-                let remainder = &input_value_times_k
-                    - &multiplicand * BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]);
-                let random_input_bytes =
-                    &randomness[GAMMA_BYTES + INPUT_BYTES..GAMMA_BYTES + 2 * INPUT_BYTES];
-                let random_input_bit =
-                    (BigUint::from_bytes_le(&random_input_bytes) <= remainder) as u64;
-                if multiplicand.is_zero() {
-                    random_input_bit
-                } else {
-                    multiplicand.to_u64_digits()[0] + random_input_bit
-                }
-            } else {
-                if (&input_value).is_zero() {
-                    0
-                } else {
-                    (&input_value).to_u64_digits()[0]
-                }
-            }
-        } else {
-            // ldp_bit == 1
-            let boundary_gap = if IS_REAL_INPUT {
-                BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / (K + 1)
-            } else {
-                BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) / K
-            };
-            let computed_ldp_value =
-                BigUint::from_bytes_le(&randomness[GAMMA_BYTES..GAMMA_BYTES + INPUT_BYTES])
-                    / boundary_gap;
-            let computed_ldp_value = if computed_ldp_value.is_zero() {
-                0
-            } else {
-                computed_ldp_value.to_u64_digits()[0]
-            };
-            if IS_REAL_INPUT {
-                min(computed_ldp_value, K)
-            } else {
-                min(computed_ldp_value, K - 1) + 1
-            }
-        };
+        let ldp_value = apply_ldp::<INPUT_BYTES, GAMMA_BYTES, K, IS_REAL_INPUT>(
+            &input_value,
+            &randomness,
+            &self.parameters.gamma_as_bytes()?,
+        );
 
         let mut input_value_bytes = [0; INPUT_BYTES];
         for (idx, byte) in input_value.to_bytes_le().iter().enumerate() {
@@ -249,38 +512,420 @@ impl<
         }
 
         // create proof
-        let proof = if skip_proof {
-            Proof::<Conf>::default()
-        } else {
-            CircuitShuffle::<
-                _,
-                ConfG,
-                INPUT_BYTES,
-                TIME_BYTES,
-                GAMMA_BYTES,
-                RANDOMNESS_BYTES,
-                K,
-                IS_REAL_INPUT,
-            >::prove(
-                &self.proving_key,
-                self.parameters.clone(),
+        let circuit = CircuitShuffle::<
+            _,
+            ConfG,
+            INPUT_BYTES,
+            TIME_BYTES,
+            GAMMA_BYTES,
+            RANDOMNESS_BYTES,
+            K,
+            IS_REAL_INPUT,
+        >::assigned(
+            self.parameters.clone(),
+            self.server_sig_pk.clone(),
+            prf_eval_points,
+            WitnessShuffle {
                 ldp_value,
-                self.server_sig_pk.clone(),
-                prf_eval_points,
                 time_bounds,
-                input_value_bytes,
-                input_value_time,
-                input_value_signature,
-                self.client_sig_pk.clone(),
-                self.storage.clone(),
-                zkp_rng,
-            )?
-        };
+                true_value: input_value_bytes,
+                time: input_value_time,
+                true_value_signature: input_value_signature,
+                client_sig_pk: self.client_sig_pk.clone(),
+            },
+            self.storage.clone(),
+        );
+        let proof = source.prove(&self.proving_key, self.prover_context.as_deref(), circuit)?;
 
         // return message
         let mut serialized_message = vec![];
-        VerifiableRandomizationMessageShuffle::<Conf, INPUT_BYTES> { proof, ldp_value }
-            .serialize_compressed(&mut serialized_message)?;
+        VerifiableRandomizationMessageShuffle::<Conf, INPUT_BYTES> {
+            gamma_bytes: GAMMA_BYTES as u16,
+            proof,
+            ldp_value,
+        }
+        .serialize_compressed(&mut serialized_message)?;
         Ok(serialized_message)
     }
+
+    /// Perform the `Randomize` step for several readings that share the seed of a single
+    /// `Generate Randomness` step, where reading `i` is a triple of its true input value time,
+    /// input value, and signature, and is randomized with `prf_eval_points_per_reading[i]`.
+    /// Returns one message (with its own proof) per reading, in the order of the readings.
+    ///
+    /// As the randomness of a reading only depends on the seed and its `prf_eval_points`, the
+    /// eval points of all readings must be distinct (e.g., disjoint ranges of the points derived
+    /// with `derive_prf_eval_points`), even across epochs, or the readings would share their
+    /// randomness. Reused eval points are rejected with `ClientError::ReusedPrfEvalPoints`.
+    pub fn verifiable_randomization_create_multi<ConfG: ConfigGadget<Conf>>(
+        &self,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        readings: &[(
+            [u8; TIME_BYTES],
+            BigUint,
+            ClientSignatureSchemeSignature<Conf>,
+        )],
+        prf_eval_points_per_reading: &[&PrfEvalPointSet<Conf>],
+        zkp_rng: &mut ZKPRng<Conf>,
+        skip_proof: bool,
+    ) -> Result<Vec<Vec<u8>>, Error>
+    where
+        Proof<Conf>: CanonicalDeserialize,
+    {
+        assert_eq!(readings.len(), prf_eval_points_per_reading.len());
+        let mut used_prf_eval_points = HashSet::new();
+        if !prf_eval_points_per_reading
+            .iter()
+            .flat_map(|prf_eval_points| prf_eval_points.points.iter())
+            .all(|prf_eval_point| used_prf_eval_points.insert(*prf_eval_point))
+        {
+            Err(ClientError::ReusedPrfEvalPoints)?
+        }
+
+        readings
+            .iter()
+            .zip(prf_eval_points_per_reading)
+            .map(
+                |((input_value_time, input_value, input_value_signature), prf_eval_points)| {
+                    self.verifiable_randomization_create::<ConfG>(
+                        time_bounds,
+                        *input_value_time,
+                        input_value.clone(),
+                        input_value_signature.clone(),
+                        prf_eval_points,
+                        zkp_rng,
+                        skip_proof,
+                    )
+                },
+            )
+            .collect()
+    }
+
+    /// Verify a message created by `verifiable_randomization_create` exactly as the server does
+    /// in its `Verify` step (for the same time bounds and `prf_eval_points`). This catches proving
+    /// bugs and key mismatches locally, before the message is sent.
+    /// Requires the verifying key to be set with `with_verifying_key`.
+    pub fn verify_own_proof<ConfG: ConfigGadget<Conf>>(
+        &self,
+        message: &[u8],
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        prf_eval_points: &PrfEvalPointSet<Conf>,
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<bool, Error>
+    where
+        ServerSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        Proof<Conf>: CanonicalDeserialize,
+    {
+        let verifying_key = self
+            .verifying_key
+            .as_ref()
+            .ok_or(ClientError::MissingVerifyingKey)?;
+        let message =
+            VerifiableRandomizationMessageShuffle::<Conf, INPUT_BYTES>::deserialize_compressed(
+                message,
+            )?;
+        check_gamma_bytes::<GAMMA_BYTES>(message.gamma_bytes)?;
+        CircuitShuffle::<
+            _,
+            ConfG,
+            INPUT_BYTES,
+            TIME_BYTES,
+            GAMMA_BYTES,
+            RANDOMNESS_BYTES,
+            K,
+            IS_REAL_INPUT,
+        >::verify(
+            verifying_key,
+            &message.proof,
+            message.ldp_value,
+            time_bounds,
+            &self.server_sig_pk,
+            prf_eval_points,
+            zkp_rng,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ClientShuffle;
+    use crate::circuits::CircuitShuffle;
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::messages::shuffle::PrfEvalPointSet;
+    use crate::messages::InputValueSignatureInput;
+    use crate::prelude::*;
+    use crate::server::ServerShuffle;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+    use std::sync::Arc;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+
+    #[test]
+    fn own_proof_is_verified_locally() {
+        let rng = &mut ChaChaRng::from_seed([39; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([40; 32]);
+        let parameters = ParametersShuffle::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitShuffle::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(
+                parameters.clone(),
+                zkp_rng,
+            )
+            .unwrap();
+        // keys of an independent setup of the same circuit
+        let (_, other_verifying_key) = CircuitShuffle::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(
+            parameters.clone(),
+            zkp_rng,
+        )
+        .unwrap();
+        let server = ServerShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            verifying_key.clone(),
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let new_client = |verifying_key| {
+            ClientShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+                parameters.clone(),
+                server.get_signature_public_key(),
+                client_sig_pk,
+                proving_key.clone(),
+            )
+            .unwrap()
+            .with_verifying_key(verifying_key)
+        };
+        let mut client = new_client(verifying_key);
+
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let prf_eval_points = PrfEvalPointSet::<Conf>::derive(0, [3; 32], 1).unwrap();
+        let time_bounds = ([0], [1]);
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                time_bounds,
+                [1],
+                BigUint::from(3_u8),
+                input_value_signature,
+                &prf_eval_points,
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+
+        assert!(client
+            .verify_own_proof::<ConfG>(&submission, time_bounds, &prf_eval_points, zkp_rng)
+            .unwrap());
+        assert!(!new_client(other_verifying_key)
+            .verify_own_proof::<ConfG>(&submission, time_bounds, &prf_eval_points, zkp_rng)
+            .unwrap());
+
+        // without a verifying key, the client cannot check its proof
+        let client = ClientShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key.clone(),
+        )
+        .unwrap();
+        let error = client
+            .verify_own_proof::<ConfG>(&submission, time_bounds, &prf_eval_points, zkp_rng)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Client(ClientError::MissingVerifyingKey)
+        ));
+    }
+
+    #[test]
+    fn recorded_proof_randomness_reproduces_the_proof() {
+        let rng = &mut ChaChaRng::from_seed([41; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([42; 32]);
+        let parameters = ParametersShuffle::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitShuffle::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(
+                parameters.clone(),
+                zkp_rng,
+            )
+            .unwrap();
+        let server = ServerShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            verifying_key.clone(),
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap()
+        .with_verifying_key(verifying_key);
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let prf_eval_points = PrfEvalPointSet::<Conf>::derive(0, [3; 32], 1).unwrap();
+        let time_bounds = ([0], [1]);
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+
+        let (submission, proof_randomness) = client
+            .verifiable_randomization_create_recorded::<ConfG>(
+                time_bounds,
+                [1],
+                BigUint::from(3_u8),
+                input_value_signature.clone(),
+                &prf_eval_points,
+                zkp_rng,
+            )
+            .unwrap();
+        assert!(client
+            .verify_own_proof::<ConfG>(&submission, time_bounds, &prf_eval_points, zkp_rng)
+            .unwrap());
+        let replay = |proof_randomness| {
+            client
+                .verifiable_randomization_replay::<ConfG>(
+                    time_bounds,
+                    [1],
+                    BigUint::from(3_u8),
+                    input_value_signature.clone(),
+                    &prf_eval_points,
+                    proof_randomness,
+                )
+                .unwrap()
+        };
+        assert_eq!(replay(&proof_randomness), submission);
+        let other_randomness = <Conf as Config>::ZKPScheme::sample_proof_randomness(
+            &mut ChaChaRng::from_seed([43; 32]),
+        );
+        assert_ne!(replay(&other_randomness), submission);
+
+        // a client with a prover context creates the same proof
+        let prover_context = Arc::new(
+            CircuitShuffle::<Conf, ConfG, 2, 1, 2, 4, 4, false>::prover_context(parameters.clone())
+                .unwrap(),
+        );
+        let client = client.with_prover_context(prover_context);
+        assert_eq!(
+            client
+                .verifiable_randomization_replay::<ConfG>(
+                    time_bounds,
+                    [1],
+                    BigUint::from(3_u8),
+                    input_value_signature.clone(),
+                    &prf_eval_points,
+                    &proof_randomness,
+                )
+                .unwrap(),
+            submission
+        );
+    }
+
+    #[test]
+    fn imported_storage_is_checked_against_the_commitment() {
+        let rng = &mut ChaChaRng::from_seed([64; 32]);
+        let parameters = ParametersShuffle::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        // no proofs are created, so neither key is used
+        let server = ServerShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            Default::default(),
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, _) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let new_client = || {
+            ClientShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+                parameters.clone(),
+                server.get_signature_public_key(),
+                client_sig_pk,
+                ark_groth16::ProvingKey {
+                    vk: Default::default(),
+                    beta_g1: Default::default(),
+                    delta_g1: Default::default(),
+                    a_query: vec![],
+                    b_g1_query: vec![],
+                    b_g2_query: vec![],
+                    h_query: vec![],
+                    l_query: vec![],
+                },
+            )
+            .unwrap()
+        };
+        let mut client = new_client();
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let exported = client.export_storage().unwrap();
+        let mut restarted_client = new_client();
+        restarted_client.import_storage(&exported).unwrap();
+
+        // a client seed the commitment was not made for is rejected
+        client.storage.client_seed.as_mut().unwrap()[0] ^= 1;
+        let error = restarted_client
+            .import_storage(&client.export_storage().unwrap())
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Client(ClientError::InconsistentStorage)
+        ));
+
+        // as is a server seed the server did not sign
+        client.import_storage(&exported).unwrap();
+        client.storage.server_seed.as_mut().unwrap()[0] ^= 1;
+        let error = restarted_client
+            .import_storage(&client.export_storage().unwrap())
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Client(ClientError::InconsistentStorage)
+        ));
+    }
 }
@@ -0,0 +1,339 @@
+//! Streaming driver for Expand clients, for datasets that are too large to be loaded into memory
+
+use super::ClientExpand;
+use crate::prelude::*;
+use ark_serialize::CanonicalDeserialize;
+use num_bigint::BigUint;
+use std::marker::PhantomData;
+
+/// Input of a single `Randomize` step of an Expand client, as consumed by `ClientExpandStream`.
+pub struct RandomizationRecord<Conf: Config, const TIME_BYTES: usize> {
+    /// Signature public key of the client the input value belongs to
+    pub client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
+    pub time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+    pub input_value_time: [u8; TIME_BYTES],
+    pub input_value: BigUint,
+    pub input_value_signature: ClientSignatureSchemeSignature<Conf>,
+    /// Index of the `Randomize` step (j in the paper)
+    pub index: usize,
+}
+
+/// Driver that runs the `Randomize` step of a single Expand client instance for every record of
+/// an iterator, and yields the serialized `VerifiableRandomizationMessage` of each record lazily,
+/// such that the records never have to be held in memory at once.
+///
+/// Whenever a record belongs to another client than the previous one (by its signature public
+/// key), the client is reset for it and performs the `Generate Randomness` step: the client
+/// message is passed to `generate_randomness`, which must return the server's response (e.g., by
+/// sending the message to the server). Records of the same client must hence be consecutive.
+///
+/// Note: creating the proof dominates the cost of each record, so the iterator is effectively
+/// rate-limited by `prove` (unless `skip_proof` is set).
+pub struct ClientExpandStream<
+    'a,
+    Conf: Config,
+    ConfG: ConfigGadget<Conf>,
+    I,
+    F,
+    R: Rng + CryptoRng,
+    const MT_DEPTH: usize,
+    const INPUT_BYTES: usize,
+    const TIME_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+    const K: u64,
+    const IS_REAL_INPUT: bool,
+> {
+    client: ClientExpand<
+        Conf,
+        MT_DEPTH,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >,
+    records: I,
+    generate_randomness: F,
+    prf_eval_points: Vec<PRFSchemeInput<Conf>>,
+    rng: &'a mut R,
+    zkp_rng: &'a mut ZKPRng<Conf>,
+    skip_proof: bool,
+    // client whose `Generate Randomness` step has been performed
+    session: Option<ClientSignatureSchemePublicKey<Conf>>,
+    _config_gadget: PhantomData<ConfG>,
+}
+
+impl<
+        'a,
+        Conf: Config,
+        ConfG: ConfigGadget<Conf>,
+        I: Iterator<Item = Result<RandomizationRecord<Conf, TIME_BYTES>, Error>>,
+        F: FnMut(&[u8]) -> Result<Vec<u8>, Error>,
+        R: Rng + CryptoRng,
+        const MT_DEPTH: usize,
+        const INPUT_BYTES: usize,
+        const TIME_BYTES: usize,
+        const GAMMA_BYTES: usize,
+        const RANDOMNESS_BYTES: usize,
+        const K: u64,
+        const IS_REAL_INPUT: bool,
+    >
+    ClientExpandStream<
+        'a,
+        Conf,
+        ConfG,
+        I,
+        F,
+        R,
+        MT_DEPTH,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >
+where
+    Proof<Conf>: CanonicalDeserialize,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+{
+    /// Create a driver that reuses `client` (and its proof generation key) for all `records`. The
+    /// server's response to each `Generate Randomness` client message is obtained with
+    /// `generate_randomness`.
+    pub fn new(
+        client: ClientExpand<
+            Conf,
+            MT_DEPTH,
+            INPUT_BYTES,
+            TIME_BYTES,
+            GAMMA_BYTES,
+            RANDOMNESS_BYTES,
+            K,
+            IS_REAL_INPUT,
+        >,
+        records: I,
+        generate_randomness: F,
+        prf_eval_points: Vec<PRFSchemeInput<Conf>>,
+        rng: &'a mut R,
+        zkp_rng: &'a mut ZKPRng<Conf>,
+    ) -> Self {
+        Self {
+            client,
+            records,
+            generate_randomness,
+            prf_eval_points,
+            rng,
+            zkp_rng,
+            skip_proof: false,
+            session: None,
+            _config_gadget: PhantomData,
+        }
+    }
+
+    /// Only execute the randomization of each record, without proof generation (see
+    /// `ClientExpand::verifiable_randomization_create`).
+    /// Note: in actual usage this should be set to `false`.
+    pub fn with_skip_proof(mut self, skip_proof: bool) -> Self {
+        self.skip_proof = skip_proof;
+        self
+    }
+
+    fn randomize(
+        &mut self,
+        record: RandomizationRecord<Conf, TIME_BYTES>,
+    ) -> Result<Vec<u8>, Error> {
+        if self.session.as_ref() != Some(&record.client_sig_pk) {
+            // a failed `Generate Randomness` step leaves no usable session behind
+            self.session = None;
+            self.client.reset(record.client_sig_pk.clone());
+            let client_message = self.client.generate_randomness_create(self.rng)?;
+            let server_message = (self.generate_randomness)(&client_message)?;
+            self.client.generate_randomness_verify(&server_message)?;
+            self.session = Some(record.client_sig_pk);
+        }
+        self.client.verifiable_randomization_create::<ConfG>(
+            record.time_bounds,
+            record.input_value_time,
+            record.input_value,
+            record.input_value_signature,
+            &self.prf_eval_points,
+            record.index,
+            self.zkp_rng,
+            self.skip_proof,
+        )
+    }
+}
+
+impl<
+        'a,
+        Conf: Config,
+        ConfG: ConfigGadget<Conf>,
+        I: Iterator<Item = Result<RandomizationRecord<Conf, TIME_BYTES>, Error>>,
+        F: FnMut(&[u8]) -> Result<Vec<u8>, Error>,
+        R: Rng + CryptoRng,
+        const MT_DEPTH: usize,
+        const INPUT_BYTES: usize,
+        const TIME_BYTES: usize,
+        const GAMMA_BYTES: usize,
+        const RANDOMNESS_BYTES: usize,
+        const K: u64,
+        const IS_REAL_INPUT: bool,
+    > Iterator
+    for ClientExpandStream<
+        'a,
+        Conf,
+        ConfG,
+        I,
+        F,
+        R,
+        MT_DEPTH,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >
+where
+    Proof<Conf>: CanonicalDeserialize,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+{
+    type Item = Result<Vec<u8>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = match self.records.next()? {
+            Ok(record) => record,
+            Err(error) => return Some(Err(error)),
+        };
+        Some(self.randomize(record))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ClientExpandStream, RandomizationRecord};
+    use crate::circuits::CircuitExpand;
+    use crate::client::ClientExpand;
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::messages::InputValueSignatureInput;
+    use crate::prelude::*;
+    use crate::server::ServerExpand;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+    use std::cell::Cell;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+
+    #[test]
+    fn records_are_randomized_lazily_per_client() {
+        let rng = &mut ChaChaRng::from_seed([110; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([111; 32]);
+        let parameters =
+            ParametersExpand::<Conf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitExpand::<Conf, ConfG, 3, 2, 1, 2, 4, 4, false>::keygen(
+                parameters.clone(),
+                zkp_rng,
+            )
+            .unwrap();
+        let server = ServerExpand::<Conf, 3, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            verifying_key,
+            rng,
+        )
+        .unwrap();
+        let prf_eval_points = vec![rng.gen::<PRFSchemeInput<Conf>>()];
+
+        // two clients with two consecutive records each
+        let mut records = vec![];
+        for _ in 0..2 {
+            let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+                &parameters.client_signature_scheme,
+                rng,
+            )
+            .unwrap();
+            for index in 0..2 {
+                let input_value_time = [index as u8 + 1];
+                let message = InputValueSignatureInput {
+                    input_value: [3, 0],
+                    input_value_time,
+                }
+                .to_bytes();
+                let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+                    &parameters.client_signature_scheme,
+                    &client_sig_sk,
+                    &message,
+                    rng,
+                )
+                .unwrap();
+                records.push(RandomizationRecord::<Conf, 1> {
+                    client_sig_pk: client_sig_pk,
+                    time_bounds: ([index as u8], input_value_time),
+                    input_value_time,
+                    input_value: BigUint::from(3_u8),
+                    input_value_signature,
+                    index,
+                });
+            }
+        }
+        let time_bounds = records
+            .iter()
+            .map(|record| (record.time_bounds, record.index))
+            .collect::<Vec<_>>();
+
+        let client = ClientExpand::<Conf, 3, 2, 1, 2, 4, 4, false>::new(
+            parameters,
+            server.get_signature_public_key(),
+            records[0].client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        let sessions = Cell::new(0);
+        let client_rng = &mut ChaChaRng::from_seed([112; 32]);
+        let client_zkp_rng = &mut ChaChaRng::from_seed([113; 32]);
+        let server_rng = &mut ChaChaRng::from_seed([114; 32]);
+        let mut stream = ClientExpandStream::<_, ConfG, _, _, _, 3, 2, 1, 2, 4, 4, false>::new(
+            client,
+            records.into_iter().map(Ok),
+            |client_message: &[u8]| {
+                sessions.set(sessions.get() + 1);
+                server.generate_randomness_create(client_message, server_rng)
+            },
+            prf_eval_points.clone(),
+            client_rng,
+            client_zkp_rng,
+        )
+        .with_skip_proof(true);
+
+        // nothing is randomized before the first message is requested
+        assert_eq!(sessions.get(), 0);
+        for (time_bounds, index) in time_bounds {
+            let message = stream.next().unwrap().unwrap();
+            let (valid, _) = server
+                .verifiable_randomization_verify::<ConfG>(
+                    &message,
+                    time_bounds,
+                    &prf_eval_points,
+                    index,
+                    zkp_rng,
+                    true,
+                )
+                .unwrap();
+            assert!(valid);
+        }
+        assert!(stream.next().is_none());
+        // one `Generate Randomness` step per client
+        assert_eq!(sessions.get(), 2);
+    }
+}
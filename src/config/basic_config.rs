@@ -4,7 +4,9 @@
 //! Additional implementation of other primitives may be required to support certain other
 //! configurations.
 
-use super::windows::{ClientCommitmentSchemeWindow, PedersenMerkleTreeWindow};
+use super::windows::{
+    ClientCommitmentSchemeWindow, ClientInputHashWindow, PedersenMerkleTreeWindow,
+};
 use crate::config::merkle_tree::{PedersenMerkleTreeConfig, PedersenMerkleTreeConfigGadget};
 use crate::prelude::{constraints::*, *};
 use crate::primitives::crh;
@@ -12,11 +14,19 @@ use crate::primitives::signature::schnorr::constraints::SchnorrSignatureVerifyGa
 use crate::primitives::signature::Schnorr;
 use ark_crypto_primitives::commitment::pedersen::constraints::CommGadget;
 use ark_crypto_primitives::commitment::pedersen::Commitment;
+use ark_crypto_primitives::crh::pedersen::Window;
 use ark_crypto_primitives::prf;
 use ark_ec::CurveGroup;
 use std::marker::PhantomData;
 
 /// Basic configuration struct as used for the experiments in the paper.
+///
+/// The inner curve `C` (of the commitments, signatures, and hashes) and the proof system `PS`
+/// (e.g., Groth16 over some pairing curve) are independent parameters. The only requirement is that
+/// the constraint field of `PS` (e.g., the scalar field of the pairing curve) is the base field of
+/// `C`, such that the arithmetic of `C` is native inside the circuits. For example, JubJub is
+/// combined with Groth16 over BLS12-381, and the Edwards curve over the scalar field of BLS12-377
+/// with Groth16 over BLS12-377 (see `Bls12_377Config`).
 pub struct BasicConfig<
     C: CurveGroup,
     R: ProofSystemRng,
@@ -62,9 +72,13 @@ impl<
     type ClientCommitmentScheme = Commitment<C, ClientCommitmentSchemeWindow<NUM_COMM_BYTES>>;
     type ServerSignatureScheme = Schnorr<C, crh::Blake2s256>;
     type PRFScheme = prf::Blake2s;
+    const PRF_OUTPUT_BYTES: usize = 32;
     type ClientSignatureScheme = Schnorr<C, crh::Blake2s256>;
     type ClientMerkleTreeConfig =
         PedersenMerkleTreeConfig<C, ClientCommitmentSchemeOutput<Self>, PedersenMerkleTreeWindow>;
+    type ClientInputHash = ark_crypto_primitives::crh::pedersen::CRH<C, ClientInputHashWindow>;
+    const CLIENT_INPUT_HASH_BLOCK_BYTES: usize =
+        ClientInputHashWindow::WINDOW_SIZE * ClientInputHashWindow::NUM_WINDOWS / 8;
 }
 
 /// Basic configuration struct for the R1CS part of the configuration as used for the experiments
@@ -107,4 +121,105 @@ where
         CG,
         ClientCommitmentSchemeOutputVar<BasicConfig<C, R, PS, NUM_COMM_BYTES>, Self>,
     >;
+    type ClientInputHashGadget =
+        ark_crypto_primitives::crh::pedersen::constraints::CRHGadget<C, CG, ClientInputHashWindow>;
+}
+
+#[cfg(test)]
+mod test {
+    use crate::circuits::CircuitBase;
+    use crate::client::ClientBase;
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::messages::InputValueSignatureInput;
+    use crate::prelude::*;
+    use crate::server::ServerBase;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_serialize::CanonicalDeserialize;
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    /// Run the Base scheme for a single submission with the configuration `Conf`, and return
+    /// whether the server accepted it.
+    fn submission_accepted<Conf: Config<ZKPRng = ChaChaRng>, ConfG: ConfigGadget<Conf>>(
+        seed: u8,
+    ) -> bool
+    where
+        ClientSignatureSchemePublicKey<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        ClientCommitmentSchemeOutput<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+        Proof<Conf>: CanonicalDeserialize,
+    {
+        let rng = &mut ChaChaRng::from_seed([seed; 32]);
+        let parameters =
+            ParametersBase::<Conf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitBase::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(parameters.clone(), rng)
+                .unwrap();
+        let server =
+            ServerBase::<Conf, 2, 1, 2, 4, 4, false>::new(parameters.clone(), verifying_key, rng)
+                .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientBase::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                ([0], [1]),
+                [1],
+                [BigUint::from(3_u8)],
+                input_value_signature,
+                rng,
+                false,
+            )
+            .unwrap();
+        server
+            .verifiable_randomization_verify::<ConfG>(&submission, ([0], [1]), rng, false)
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn submissions_are_accepted_for_different_curves() {
+        assert!(submission_accepted::<
+            BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>,
+            BasicConfigGadget<JubJub, JubJubVar>,
+        >(60));
+        #[cfg(feature = "bls12-377")]
+        assert!(submission_accepted::<
+            crate::config::Bls12_377Config<32>,
+            crate::config::Bls12_377ConfigGadget,
+        >(61));
+    }
 }
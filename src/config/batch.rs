@@ -0,0 +1,205 @@
+//! Verification of a batch of proofs with a configurable handling of batches that contain invalid
+//! proofs.
+
+use crate::prelude::*;
+
+/// How [`verify_batch`] proceeds if the check of the whole batch fails.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BatchFailurePolicy {
+    /// Reject every proof of the batch (a single check).
+    RejectAll,
+    /// Verify every proof on its own to identify the invalid ones (one check per proof).
+    #[default]
+    IdentifyFailures,
+    /// Split the batch in halves and check these recursively, which identifies the invalid ones
+    /// with fewer checks than `IdentifyFailures` if only few proofs are invalid.
+    Bisect,
+}
+
+/// Outcome of [`verify_batch`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchVerification {
+    /// Positions of the rejected proofs in the batch (in ascending order)
+    pub rejected: Vec<usize>,
+    /// Number of (batch) verifications that were run
+    pub checks: usize,
+}
+
+impl BatchVerification {
+    /// Whether all proofs of the batch were accepted
+    pub fn all_accepted(&self) -> bool {
+        self.rejected.is_empty()
+    }
+}
+
+/// Verify a batch of proofs for the same verifying key, given as pairs of public inputs and
+/// proof, with `ProofSystem::batch_verify`. If the batch does not verify, the proofs to reject
+/// are determined according to `policy`. A (batch) verification that fails with an error, e.g.,
+/// because public inputs are malformed, counts as not verifying, so that a single malformed
+/// instance is rejected instead of aborting the whole batch.
+pub fn verify_batch<Conf: Config>(
+    verifying_key: &VerifyingKey<Conf>,
+    instances: &[(&[ConstraintField<Conf>], &Proof<Conf>)],
+    policy: BatchFailurePolicy,
+    zkp_rng: &mut ZKPRng<Conf>,
+) -> Result<BatchVerification, Error> {
+    let mut verification = BatchVerification {
+        rejected: vec![],
+        checks: 1,
+    };
+    if Conf::ZKPScheme::batch_verify(verifying_key, instances, zkp_rng).unwrap_or(false) {
+        return Ok(verification);
+    }
+    match policy {
+        BatchFailurePolicy::RejectAll => verification.rejected = (0..instances.len()).collect(),
+        BatchFailurePolicy::IdentifyFailures => {
+            for (position, (public_inputs, proof)) in instances.iter().enumerate() {
+                verification.checks += 1;
+                if !Conf::ZKPScheme::verify(verifying_key, public_inputs, proof, zkp_rng)
+                    .unwrap_or(false)
+                {
+                    verification.rejected.push(position);
+                }
+            }
+        }
+        BatchFailurePolicy::Bisect => {
+            bisect::<Conf>(verifying_key, instances, 0, &mut verification, zkp_rng)
+        }
+    }
+    Ok(verification)
+}
+
+/// Identify the invalid proofs of `instances` (starting at position `offset` of the batch), which
+/// is known to not verify as a whole, by checking both halves recursively.
+fn bisect<Conf: Config>(
+    verifying_key: &VerifyingKey<Conf>,
+    instances: &[(&[ConstraintField<Conf>], &Proof<Conf>)],
+    offset: usize,
+    verification: &mut BatchVerification,
+    zkp_rng: &mut ZKPRng<Conf>,
+) {
+    if instances.len() == 1 {
+        verification.rejected.push(offset);
+        return;
+    }
+    let (left, right) = instances.split_at(instances.len() / 2);
+    for (half, half_offset) in [(left, offset), (right, offset + left.len())] {
+        verification.checks += 1;
+        if !Conf::ZKPScheme::batch_verify(verifying_key, half, zkp_rng).unwrap_or(false) {
+            bisect::<Conf>(verifying_key, half, half_offset, verification, zkp_rng);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{verify_batch, BatchFailurePolicy};
+    use crate::config::conformance::SquareCircuit;
+    use crate::config::BasicConfig;
+    use crate::prelude::*;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+
+    #[test]
+    fn failure_policies_reject_the_bad_proof() {
+        let zkp_rng = &mut ChaChaRng::from_seed([41; 32]);
+        let (proving_key, verifying_key) =
+            <Conf as Config>::ZKPScheme::keygen(SquareCircuit(None), zkp_rng).unwrap();
+        let roots = (1..=8_u8)
+            .map(ConstraintField::<Conf>::from)
+            .collect::<Vec<_>>();
+        let mut public_inputs = roots.iter().map(|root| [root * root]).collect::<Vec<_>>();
+        let proofs = roots
+            .iter()
+            .map(|root| {
+                <Conf as Config>::ZKPScheme::prove(
+                    &proving_key,
+                    SquareCircuit(Some(*root)),
+                    zkp_rng,
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        fn zip_instances<'a>(
+            public_inputs: &'a [[ConstraintField<Conf>; 1]],
+            proofs: &'a [Proof<Conf>],
+        ) -> Vec<(&'a [ConstraintField<Conf>], &'a Proof<Conf>)> {
+            public_inputs
+                .iter()
+                .map(|inputs| inputs.as_slice())
+                .zip(proofs.iter())
+                .collect()
+        }
+
+        // a valid batch takes a single check under every policy
+        for policy in [
+            BatchFailurePolicy::RejectAll,
+            BatchFailurePolicy::IdentifyFailures,
+            BatchFailurePolicy::Bisect,
+        ] {
+            let verification = verify_batch::<Conf>(
+                &verifying_key,
+                &zip_instances(&public_inputs, &proofs),
+                policy,
+                zkp_rng,
+            )
+            .unwrap();
+            assert!(verification.all_accepted());
+            assert_eq!(verification.checks, 1);
+        }
+
+        // the proof at position 5 does not match its public input
+        public_inputs[5][0] += ConstraintField::<Conf>::from(1_u8);
+        let instances = zip_instances(&public_inputs, &proofs);
+        let reject_all = verify_batch::<Conf>(
+            &verifying_key,
+            &instances,
+            BatchFailurePolicy::RejectAll,
+            zkp_rng,
+        )
+        .unwrap();
+        assert_eq!(reject_all.rejected, (0..8).collect::<Vec<_>>());
+        assert_eq!(reject_all.checks, 1);
+
+        let identify = verify_batch::<Conf>(
+            &verifying_key,
+            &instances,
+            BatchFailurePolicy::IdentifyFailures,
+            zkp_rng,
+        )
+        .unwrap();
+        assert_eq!(identify.rejected, vec![5]);
+        assert_eq!(identify.checks, 1 + 8);
+
+        let bisect = verify_batch::<Conf>(
+            &verifying_key,
+            &instances,
+            BatchFailurePolicy::Bisect,
+            zkp_rng,
+        )
+        .unwrap();
+        assert_eq!(bisect.rejected, vec![5]);
+        // both halves are checked on each of the 3 levels
+        assert_eq!(bisect.checks, 1 + 2 * 3);
+        assert!(bisect.checks < identify.checks);
+
+        // the public inputs at position 2 have the wrong length, which is an error for the proof
+        // system, but only rejects that instance
+        let malformed = [public_inputs[2][0], public_inputs[2][0]];
+        let mut instances = instances;
+        instances[2].0 = &malformed;
+        for policy in [
+            BatchFailurePolicy::IdentifyFailures,
+            BatchFailurePolicy::Bisect,
+        ] {
+            let verification =
+                verify_batch::<Conf>(&verifying_key, &instances, policy, zkp_rng).unwrap();
+            assert_eq!(verification.rejected, vec![2, 5]);
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Configuration over BLS12-377 (requires the `bls12-377` feature), as an alternative to the
+//! BLS12-381 configuration used for the experiments in the paper.
+//!
+//! The inner curve is the twisted Edwards curve over the scalar field of BLS12-377. As BLS12-377
+//! is the inner curve of the BW6-761 cycle, this configuration is a starting point for recursive
+//! proofs. All primitives are the same as in `BasicConfig`.
+
+use crate::config::{BasicConfig, BasicConfigGadget};
+use ark_bls12_377::Bls12_377;
+use ark_ed_on_bls12_377::constraints::EdwardsVar as EdOnBls12_377Var;
+use ark_ed_on_bls12_377::EdwardsProjective as EdOnBls12_377;
+use ark_groth16::Groth16;
+use rand_chacha::ChaChaRng;
+
+/// `BasicConfig` with the Edwards curve over the scalar field of BLS12-377 as inner curve, and
+/// Groth16 over BLS12-377.
+pub type Bls12_377Config<const NUM_COMM_BYTES: usize> =
+    BasicConfig<EdOnBls12_377, ChaChaRng, Groth16<Bls12_377>, NUM_COMM_BYTES>;
+
+/// `BasicConfigGadget` that matches `Bls12_377Config`.
+pub type Bls12_377ConfigGadget = BasicConfigGadget<EdOnBls12_377, EdOnBls12_377Var>;
+
+#[cfg(test)]
+mod test {
+    use super::{Bls12_377Config, Bls12_377ConfigGadget};
+    use crate::run_random::run_protocol_base;
+    use astro_float::BigFloat;
+
+    type Conf = Bls12_377Config<32>;
+    type ConfG = Bls12_377ConfigGadget;
+
+    #[test]
+    fn base_protocol_runs_over_bls12_377() {
+        // histogram and real input
+        run_protocol_base::<Conf, ConfG, 2, 1, 2, 4, 4, false>(BigFloat::from_f64(0.5, 64))
+            .unwrap();
+        run_protocol_base::<Conf, ConfG, 2, 1, 2, 8, 4, true>(BigFloat::from_f64(0.5, 64)).unwrap();
+    }
+}
@@ -0,0 +1,265 @@
+//! Conformance test suite that every implementation of the `ProofSystem` trait must pass.
+
+use super::{ProofSystem, ProofSystemRng, ProverPrecomputation};
+use crate::error::{GenericError, VldpError};
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+/// Circuit proving knowledge of a square root of the public input.
+#[derive(Clone)]
+pub(crate) struct SquareCircuit<F: PrimeField>(pub(crate) Option<F>);
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for SquareCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let square = FpVar::new_input(cs.clone(), || {
+            self.0
+                .map(|x| x * x)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let root = FpVar::new_witness(cs, || self.0.ok_or(SynthesisError::AssignmentMissing))?;
+        square.enforce_equal(&(&root * &root))
+    }
+}
+
+/// Run keygen, prove, and verify of the proof system `PS` over a small fixed circuit and assert
+/// that:
+/// - valid proofs verify
+/// - tampered proofs are rejected
+/// - proofs are rejected for wrong public inputs
+/// - proofs are reproduced exactly from their randomness, and proofs with a prover context are
+///   identical to those without, including an empty one (only if `prove_with_randomness` is
+///   supported)
+/// - batches verify if and only if all of their proofs verify
+/// - proofs and verifying keys round-trip through (compressed and uncompressed) serialization
+///
+/// Rejecting means that verification either returns `false` or an error. Panics if any of these
+/// does not hold.
+pub fn proof_system_conformance<PS: ProofSystem<R>, R: ProofSystemRng>()
+where
+    PS::Proof: CanonicalDeserialize,
+    PS::VerifyingKey: CanonicalSerialize + CanonicalDeserialize,
+{
+    let rng = &mut R::new();
+    let verifies = |verifying_key: &PS::VerifyingKey,
+                    public_inputs: &[PS::ConstraintField],
+                    proof: &PS::Proof,
+                    rng: &mut R| {
+        matches!(
+            PS::verify(verifying_key, public_inputs, proof, rng),
+            Ok(true)
+        )
+    };
+
+    let root = PS::ConstraintField::from(3_u8);
+    let square = root * root;
+    let (proving_key, verifying_key) =
+        PS::keygen(SquareCircuit::<PS::ConstraintField>(None), rng).expect("keygen failed");
+    let proof = PS::prove(&proving_key, SquareCircuit(Some(root)), rng).expect("proving failed");
+
+    // valid proofs verify
+    assert!(
+        verifies(&verifying_key, &[square], &proof, rng),
+        "valid proof does not verify"
+    );
+
+    // tampered proofs are rejected
+    let other_proof = PS::prove(
+        &proving_key,
+        SquareCircuit(Some(PS::ConstraintField::from(4_u8))),
+        rng,
+    )
+    .expect("proving failed");
+    assert!(
+        !verifies(&verifying_key, &[square], &other_proof, rng),
+        "proof of another statement verifies"
+    );
+    assert!(
+        !verifies(&verifying_key, &[square], &PS::Proof::default(), rng),
+        "default proof verifies"
+    );
+    let mut proof_bytes = vec![];
+    proof
+        .serialize_compressed(&mut proof_bytes)
+        .expect("proof serialization failed");
+    for index in [0, proof_bytes.len() / 2, proof_bytes.len() - 1] {
+        let mut tampered_bytes = proof_bytes.clone();
+        tampered_bytes[index] ^= 1;
+        // proofs that can no longer be deserialized are rejected as well
+        if let Ok(tampered_proof) = PS::Proof::deserialize_compressed(tampered_bytes.as_slice()) {
+            assert!(
+                !verifies(&verifying_key, &[square], &tampered_proof, rng),
+                "proof with flipped byte {} verifies",
+                index
+            );
+        }
+    }
+
+    // proofs are rejected for wrong public inputs
+    assert!(
+        !verifies(&verifying_key, &[square + root], &proof, rng),
+        "proof verifies for a wrong public input"
+    );
+    assert!(
+        !verifies(&verifying_key, &[], &proof, rng),
+        "proof verifies for missing public inputs"
+    );
+    assert!(
+        !verifies(&verifying_key, &[square, square], &proof, rng),
+        "proof verifies for additional public inputs"
+    );
+
+    // proofs are reproduced exactly from their randomness, if the proof system supports it
+    let proof_randomness = PS::sample_proof_randomness(rng);
+    let reproducible = !matches!(
+        PS::prove_with_randomness(&proving_key, SquareCircuit(Some(root)), &proof_randomness),
+        Err(VldpError::Generic(GenericError::UnsupportedByProofSystem(
+            _
+        )))
+    );
+    if reproducible {
+        let proof_bytes_for = |proof_randomness: &[PS::ConstraintField]| {
+            let proof = PS::prove_with_randomness(
+                &proving_key,
+                SquareCircuit(Some(root)),
+                proof_randomness,
+            )
+            .expect("proving failed");
+            let mut bytes = vec![];
+            proof
+                .serialize_compressed(&mut bytes)
+                .expect("proof serialization failed");
+            bytes
+        };
+        assert_eq!(
+            proof_bytes_for(&proof_randomness),
+            proof_bytes_for(&proof_randomness),
+            "proofs with the same randomness differ"
+        );
+        assert_ne!(
+            proof_bytes_for(&proof_randomness),
+            proof_bytes_for(&PS::sample_proof_randomness(rng)),
+            "proofs with different randomness are identical"
+        );
+
+        // proofs with a prover context are identical to those without
+        let context = PS::prover_context(SquareCircuit::<PS::ConstraintField>(None))
+            .expect("precomputing failed");
+        let proof_with_context = PS::prove_with_context(
+            &proving_key,
+            &context,
+            SquareCircuit(Some(root)),
+            &proof_randomness,
+        )
+        .expect("proving with context failed");
+        let mut context_bytes = vec![];
+        proof_with_context
+            .serialize_compressed(&mut context_bytes)
+            .expect("proof serialization failed");
+        assert_eq!(
+            context_bytes,
+            proof_bytes_for(&proof_randomness),
+            "proof with prover context differs"
+        );
+        assert!(
+            verifies(&verifying_key, &[square], &proof_with_context, rng),
+            "proof with prover context does not verify"
+        );
+        let proof_with_empty_context = PS::prove_with_context(
+            &proving_key,
+            &ProverPrecomputation::default(),
+            SquareCircuit(Some(root)),
+            &proof_randomness,
+        )
+        .expect("proving with empty context failed");
+        let mut empty_context_bytes = vec![];
+        proof_with_empty_context
+            .serialize_compressed(&mut empty_context_bytes)
+            .expect("proof serialization failed");
+        assert_eq!(
+            empty_context_bytes,
+            proof_bytes_for(&proof_randomness),
+            "proof with empty prover context differs"
+        );
+    }
+
+    // batches verify if and only if all of their proofs verify
+    let public_inputs: &[PS::ConstraintField] = &[square];
+    assert!(
+        matches!(
+            PS::batch_verify(
+                &verifying_key,
+                &[(public_inputs, &proof), (public_inputs, &proof)],
+                rng
+            ),
+            Ok(true)
+        ),
+        "batch of valid proofs does not verify"
+    );
+    assert!(
+        !matches!(
+            PS::batch_verify(
+                &verifying_key,
+                &[(public_inputs, &proof), (public_inputs, &other_proof)],
+                rng
+            ),
+            Ok(true)
+        ),
+        "batch with an invalid proof verifies"
+    );
+
+    // serialization round-trips
+    let deserialized_proof = PS::Proof::deserialize_compressed(proof_bytes.as_slice())
+        .expect("compressed proof deserialization failed");
+    let mut reserialized_bytes = vec![];
+    deserialized_proof
+        .serialize_compressed(&mut reserialized_bytes)
+        .expect("proof serialization failed");
+    assert_eq!(proof_bytes, reserialized_bytes, "proof does not round-trip");
+    let mut uncompressed_bytes = vec![];
+    proof
+        .serialize_uncompressed(&mut uncompressed_bytes)
+        .expect("proof serialization failed");
+    let uncompressed_proof = PS::Proof::deserialize_uncompressed(uncompressed_bytes.as_slice())
+        .expect("uncompressed proof deserialization failed");
+    let mut verifying_key_bytes = vec![];
+    verifying_key
+        .serialize_compressed(&mut verifying_key_bytes)
+        .expect("verifying key serialization failed");
+    let deserialized_verifying_key =
+        PS::VerifyingKey::deserialize_compressed(verifying_key_bytes.as_slice())
+            .expect("verifying key deserialization failed");
+    assert!(
+        verifies(
+            &deserialized_verifying_key,
+            &[square],
+            &deserialized_proof,
+            rng
+        ),
+        "deserialized proof does not verify"
+    );
+    assert!(
+        verifies(
+            &deserialized_verifying_key,
+            &[square],
+            &uncompressed_proof,
+            rng
+        ),
+        "deserialized uncompressed proof does not verify"
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::proof_system_conformance;
+    use ark_bls12_381::Bls12_381;
+    use ark_groth16::Groth16;
+    use rand_chacha::ChaChaRng;
+
+    #[test]
+    fn groth16_passes_conformance_suite() {
+        proof_system_conformance::<Groth16<Bls12_381>, ChaChaRng>();
+    }
+}
@@ -0,0 +1,346 @@
+//! Selection of the proof system at runtime (e.g., from a configuration file) instead of through
+//! the `ZKPScheme` of a `Config`.
+
+use super::{ProofSystem, ProofSystemRng};
+use crate::prelude::*;
+use ark_relations::r1cs::ConstraintSynthesizer;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
+use std::str::FromStr;
+
+/// Proof systems that can be selected at runtime. Every variant dispatches to the corresponding
+/// implementation of the `ProofSystem` trait over the pairing `E`. (Currently, only Groth16.)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DynProofSystem {
+    Groth16,
+}
+
+/// Proving key of any of the proof systems of `DynProofSystem`.
+#[derive(Clone)]
+pub enum DynProvingKey<E: Pairing> {
+    Groth16(ark_groth16::ProvingKey<E>),
+}
+
+/// Verifying key of any of the proof systems of `DynProofSystem`.
+#[derive(Clone)]
+pub enum DynVerifyingKey<E: Pairing> {
+    Groth16(ark_groth16::PreparedVerifyingKey<E>),
+}
+
+/// Proof of any of the proof systems of `DynProofSystem`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DynProof<E: Pairing> {
+    Groth16(ark_groth16::Proof<E>),
+}
+
+impl DynProofSystem {
+    /// Name of the proof system, as accepted by `from_str`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DynProofSystem::Groth16 => "groth16",
+        }
+    }
+
+    /// Byte that identifies the proof system in the serialization of proofs and keys, such that
+    /// they cannot be read as those of a different proof system.
+    pub fn tag(&self) -> u8 {
+        match self {
+            DynProofSystem::Groth16 => 0,
+        }
+    }
+
+    /// Inverse of `tag`.
+    pub fn from_tag(tag: u8) -> Result<Self, SerializationError> {
+        match tag {
+            0 => Ok(DynProofSystem::Groth16),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+
+    fn deserialize_tag<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        Self::from_tag(u8::deserialize_uncompressed(reader)?)
+    }
+
+    pub fn keygen<E, C, R>(
+        &self,
+        circuit: C,
+        rng: &mut R,
+    ) -> Result<(DynProvingKey<E>, DynVerifyingKey<E>), Error>
+    where
+        E: Pairing,
+        C: ConstraintSynthesizer<E::ScalarField> + Clone,
+        R: ProofSystemRng + CryptoRng,
+    {
+        match self {
+            DynProofSystem::Groth16 => {
+                let (pk, vk) = <ark_groth16::Groth16<E> as ProofSystem<R>>::keygen(circuit, rng)?;
+                Ok((DynProvingKey::Groth16(pk), DynVerifyingKey::Groth16(vk)))
+            }
+        }
+    }
+
+    pub fn prove<E, C, R>(
+        &self,
+        proving_key: &DynProvingKey<E>,
+        circuit: C,
+        rng: &mut R,
+    ) -> Result<DynProof<E>, Error>
+    where
+        E: Pairing,
+        C: ConstraintSynthesizer<E::ScalarField>,
+        R: ProofSystemRng + CryptoRng,
+    {
+        match (self, proving_key) {
+            (DynProofSystem::Groth16, DynProvingKey::Groth16(pk)) => Ok(DynProof::Groth16(
+                <ark_groth16::Groth16<E> as ProofSystem<R>>::prove(pk, circuit, rng)?,
+            )),
+        }
+    }
+
+    pub fn verify<E, R>(
+        &self,
+        verifying_key: &DynVerifyingKey<E>,
+        public_inputs: &[E::ScalarField],
+        proof: &DynProof<E>,
+        rng: &mut R,
+    ) -> Result<bool, Error>
+    where
+        E: Pairing,
+        R: ProofSystemRng + CryptoRng,
+    {
+        match (self, verifying_key, proof) {
+            (DynProofSystem::Groth16, DynVerifyingKey::Groth16(vk), DynProof::Groth16(proof)) => {
+                <ark_groth16::Groth16<E> as ProofSystem<R>>::verify(vk, public_inputs, proof, rng)
+            }
+        }
+    }
+}
+
+impl FromStr for DynProofSystem {
+    type Err = GenericError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "groth16" => Ok(DynProofSystem::Groth16),
+            _ => Err(GenericError::ParseError(format!(
+                "unknown proof system '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl<E: Pairing> CanonicalSerialize for DynProvingKey<E> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        match self {
+            DynProvingKey::Groth16(pk) => {
+                DynProofSystem::Groth16
+                    .tag()
+                    .serialize_uncompressed(&mut writer)?;
+                pk.serialize_with_mode(writer, compress)
+            }
+        }
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        1 + match self {
+            DynProvingKey::Groth16(pk) => pk.serialized_size(compress),
+        }
+    }
+}
+
+impl<E: Pairing> Valid for DynProvingKey<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        match self {
+            DynProvingKey::Groth16(pk) => pk.check(),
+        }
+    }
+}
+
+impl<E: Pairing> CanonicalDeserialize for DynProvingKey<E> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        match DynProofSystem::deserialize_tag(&mut reader)? {
+            DynProofSystem::Groth16 => Ok(DynProvingKey::Groth16(
+                ark_groth16::ProvingKey::deserialize_with_mode(reader, compress, validate)?,
+            )),
+        }
+    }
+}
+
+impl<E: Pairing> CanonicalSerialize for DynVerifyingKey<E> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        match self {
+            DynVerifyingKey::Groth16(vk) => {
+                DynProofSystem::Groth16
+                    .tag()
+                    .serialize_uncompressed(&mut writer)?;
+                vk.serialize_with_mode(writer, compress)
+            }
+        }
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        1 + match self {
+            DynVerifyingKey::Groth16(vk) => vk.serialized_size(compress),
+        }
+    }
+}
+
+impl<E: Pairing> Valid for DynVerifyingKey<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        match self {
+            DynVerifyingKey::Groth16(vk) => vk.check(),
+        }
+    }
+}
+
+impl<E: Pairing> CanonicalDeserialize for DynVerifyingKey<E> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        match DynProofSystem::deserialize_tag(&mut reader)? {
+            DynProofSystem::Groth16 => Ok(DynVerifyingKey::Groth16(
+                ark_groth16::PreparedVerifyingKey::deserialize_with_mode(
+                    reader, compress, validate,
+                )?,
+            )),
+        }
+    }
+}
+
+impl<E: Pairing> CanonicalSerialize for DynProof<E> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        match self {
+            DynProof::Groth16(proof) => {
+                DynProofSystem::Groth16
+                    .tag()
+                    .serialize_uncompressed(&mut writer)?;
+                proof.serialize_with_mode(writer, compress)
+            }
+        }
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        1 + match self {
+            DynProof::Groth16(proof) => proof.serialized_size(compress),
+        }
+    }
+}
+
+impl<E: Pairing> Valid for DynProof<E> {
+    fn check(&self) -> Result<(), SerializationError> {
+        match self {
+            DynProof::Groth16(proof) => proof.check(),
+        }
+    }
+}
+
+impl<E: Pairing> CanonicalDeserialize for DynProof<E> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        match DynProofSystem::deserialize_tag(&mut reader)? {
+            DynProofSystem::Groth16 => Ok(DynProof::Groth16(
+                ark_groth16::Proof::deserialize_with_mode(reader, compress, validate)?,
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DynProof, DynProofSystem, DynProvingKey, DynVerifyingKey};
+    use crate::config::conformance::SquareCircuit;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    #[test]
+    fn groth16_selected_at_runtime_proves_and_verifies() {
+        let rng = &mut ChaChaRng::from_seed([57; 32]);
+        let backend: DynProofSystem = "Groth16".parse().unwrap();
+        assert_eq!(backend, DynProofSystem::Groth16);
+        assert!("marlin".parse::<DynProofSystem>().is_err());
+
+        let root = Fr::from(3_u8);
+        let (proving_key, verifying_key) = backend
+            .keygen::<Bls12_381, _, _>(SquareCircuit(None), rng)
+            .unwrap();
+        let proof = backend
+            .prove(&proving_key, SquareCircuit(Some(root)), rng)
+            .unwrap();
+        assert!(backend
+            .verify(&verifying_key, &[root * root], &proof, rng)
+            .unwrap());
+        assert!(!backend
+            .verify(&verifying_key, &[root], &proof, rng)
+            .unwrap());
+    }
+    #[test]
+    fn serialization_is_tagged_with_the_proof_system() {
+        let rng = &mut ChaChaRng::from_seed([58; 32]);
+        let backend = DynProofSystem::Groth16;
+        let root = Fr::from(3_u8);
+        let (proving_key, verifying_key) = backend
+            .keygen::<Bls12_381, _, _>(SquareCircuit(None), rng)
+            .unwrap();
+        let proof = backend
+            .prove(&proving_key, SquareCircuit(Some(root)), rng)
+            .unwrap();
+
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+        assert_eq!(proof_bytes[0], backend.tag());
+        assert_eq!(proof_bytes.len(), proof.compressed_size());
+        assert_eq!(
+            DynProof::<Bls12_381>::deserialize_compressed(&proof_bytes[..]).unwrap(),
+            proof
+        );
+
+        let mut verifying_key_bytes = Vec::new();
+        verifying_key
+            .serialize_compressed(&mut verifying_key_bytes)
+            .unwrap();
+        let verifying_key =
+            DynVerifyingKey::<Bls12_381>::deserialize_compressed(&verifying_key_bytes[..]).unwrap();
+        let mut proving_key_bytes = Vec::new();
+        proving_key
+            .serialize_compressed(&mut proving_key_bytes)
+            .unwrap();
+        let proving_key =
+            DynProvingKey::<Bls12_381>::deserialize_compressed(&proving_key_bytes[..]).unwrap();
+        let proof = backend
+            .prove(&proving_key, SquareCircuit(Some(root)), rng)
+            .unwrap();
+        assert!(backend
+            .verify(&verifying_key, &[root * root], &proof, rng)
+            .unwrap());
+
+        // an unknown tag is rejected
+        proof_bytes[0] = u8::MAX;
+        assert!(DynProof::<Bls12_381>::deserialize_compressed(&proof_bytes[..]).is_err());
+    }
+}
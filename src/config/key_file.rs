@@ -0,0 +1,361 @@
+//! Caching of proving and verifying keys on disk. Every key file starts with a header that records
+//! the format version and the parameters of the circuit the key was generated for, such that a key
+//! is never loaded for a circuit of another shape (e.g., another `K`), which would only surface as
+//! invalid proofs.
+
+use crate::prelude::*;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use std::fs;
+use std::path::Path;
+
+/// Version of the key file format. It is increased whenever the format changes, and files of other
+/// versions are rejected.
+pub const KEY_FILE_VERSION: u16 = 1;
+
+/// Magic bytes at the start of every key file.
+const KEY_FILE_MAGIC: [u8; 8] = *b"VLDP-KEY";
+
+/// Kind of key stored in a key file, such that a verifying key is not loaded as a proving key.
+const PROVING_KEY: u8 = 0;
+const VERIFYING_KEY: u8 = 1;
+
+/// Length of the header: magic, version, key kind, scheme, seven parameters, and `IS_REAL_INPUT`.
+const HEADER_BYTES: usize = 8 + 2 + 1 + 1 + 7 * 8 + 1;
+
+/// Parameters of the circuit a key was generated for, i.e., the scheme and its const generic
+/// parameters. Parameters a scheme does not have are stored as 0 (`MT_DEPTH`) or 1 (`D`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyParameters {
+    scheme: u8,
+    mt_depth: u64,
+    input_bytes: u64,
+    time_bytes: u64,
+    gamma_bytes: u64,
+    randomness_bytes: u64,
+    k: u64,
+    d: u64,
+    is_real_input: bool,
+}
+
+impl KeyParameters {
+    /// Parameters of `CircuitBase` with the given const generic parameters.
+    pub fn base<
+        const INPUT_BYTES: usize,
+        const TIME_BYTES: usize,
+        const GAMMA_BYTES: usize,
+        const RANDOMNESS_BYTES: usize,
+        const K: u64,
+        const IS_REAL_INPUT: bool,
+        const D: usize,
+    >() -> Self {
+        Self::new::<INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>(
+            0, 0, D,
+        )
+    }
+
+    /// Parameters of `CircuitExpand` with the given const generic parameters.
+    pub fn expand<
+        const MT_DEPTH: usize,
+        const INPUT_BYTES: usize,
+        const TIME_BYTES: usize,
+        const GAMMA_BYTES: usize,
+        const RANDOMNESS_BYTES: usize,
+        const K: u64,
+        const IS_REAL_INPUT: bool,
+    >() -> Self {
+        Self::new::<INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>(
+            1, MT_DEPTH, 1,
+        )
+    }
+
+    /// Parameters of `CircuitShuffle` with the given const generic parameters.
+    pub fn shuffle<
+        const INPUT_BYTES: usize,
+        const TIME_BYTES: usize,
+        const GAMMA_BYTES: usize,
+        const RANDOMNESS_BYTES: usize,
+        const K: u64,
+        const IS_REAL_INPUT: bool,
+    >() -> Self {
+        Self::new::<INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>(
+            2, 0, 1,
+        )
+    }
+
+    fn new<
+        const INPUT_BYTES: usize,
+        const TIME_BYTES: usize,
+        const GAMMA_BYTES: usize,
+        const RANDOMNESS_BYTES: usize,
+        const K: u64,
+        const IS_REAL_INPUT: bool,
+    >(
+        scheme: u8,
+        mt_depth: usize,
+        d: usize,
+    ) -> Self {
+        Self {
+            scheme,
+            mt_depth: mt_depth as u64,
+            input_bytes: INPUT_BYTES as u64,
+            time_bytes: TIME_BYTES as u64,
+            gamma_bytes: GAMMA_BYTES as u64,
+            randomness_bytes: RANDOMNESS_BYTES as u64,
+            k: K,
+            d: d as u64,
+            is_real_input: IS_REAL_INPUT,
+        }
+    }
+
+    fn scheme_name(scheme: u8) -> String {
+        match scheme {
+            0 => "Base".to_string(),
+            1 => "Expand".to_string(),
+            2 => "Shuffle".to_string(),
+            _ => format!("unknown scheme {}", scheme),
+        }
+    }
+
+    /// Parameters as (name, value) pairs, in the order in which they are stored in the header.
+    fn named_values(&self) -> [(&'static str, u64); 7] {
+        [
+            ("MT_DEPTH", self.mt_depth),
+            ("INPUT_BYTES", self.input_bytes),
+            ("TIME_BYTES", self.time_bytes),
+            ("GAMMA_BYTES", self.gamma_bytes),
+            ("RANDOMNESS_BYTES", self.randomness_bytes),
+            ("K", self.k),
+            ("D", self.d),
+        ]
+    }
+
+    fn write_header(&self, key_kind: u8, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&KEY_FILE_MAGIC);
+        bytes.extend_from_slice(&KEY_FILE_VERSION.to_le_bytes());
+        bytes.push(key_kind);
+        bytes.push(self.scheme);
+        for (_, value) in self.named_values() {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.push(self.is_real_input as u8);
+    }
+
+    /// Check that `bytes` start with the header of a key of the given kind that was generated for
+    /// these parameters, and return the remaining bytes (i.e., the key).
+    fn check_header<'a>(&self, key_kind: u8, bytes: &'a [u8]) -> Result<&'a [u8], Error> {
+        if bytes.len() < HEADER_BYTES || bytes[0..8] != KEY_FILE_MAGIC {
+            Err(GenericError::ParseError(
+                "the file is not a VLDP key file".to_string(),
+            ))?
+        }
+        let version = u16::from_le_bytes([bytes[8], bytes[9]]);
+        if version != KEY_FILE_VERSION {
+            Err(GenericError::ParseError(format!(
+                "the key file has format version {}, but version {} is supported",
+                version, KEY_FILE_VERSION
+            )))?
+        }
+        let kind_name = |kind: u8| match kind {
+            PROVING_KEY => "proving key",
+            VERIFYING_KEY => "verifying key",
+            _ => "unknown key",
+        };
+        if bytes[10] != key_kind {
+            Err(GenericError::ParseError(format!(
+                "the key file contains a {}, but a {} is expected",
+                kind_name(bytes[10]),
+                kind_name(key_kind)
+            )))?
+        }
+        if bytes[11] != self.scheme {
+            Err(GenericError::ParseError(format!(
+                "the key was generated for the {} circuit, but the {} circuit is expected",
+                Self::scheme_name(bytes[11]),
+                Self::scheme_name(self.scheme)
+            )))?
+        }
+        for (index, (name, expected)) in self.named_values().into_iter().enumerate() {
+            let offset = 12 + 8 * index;
+            let mut value = [0; 8];
+            value.copy_from_slice(&bytes[offset..offset + 8]);
+            let value = u64::from_le_bytes(value);
+            if value != expected {
+                Err(GenericError::ParseError(format!(
+                    "the key was generated for {} = {}, but {} = {} is expected",
+                    name, value, name, expected
+                )))?
+            }
+        }
+        let is_real_input = bytes[HEADER_BYTES - 1] == 1;
+        if is_real_input != self.is_real_input {
+            Err(GenericError::ParseError(format!(
+                "the key was generated for IS_REAL_INPUT = {}, but IS_REAL_INPUT = {} is expected",
+                is_real_input, self.is_real_input
+            )))?
+        }
+        Ok(&bytes[HEADER_BYTES..])
+    }
+}
+
+fn save_key<Key: CanonicalSerialize>(
+    path: impl AsRef<Path>,
+    parameters: &KeyParameters,
+    key_kind: u8,
+    key: &Key,
+) -> Result<(), Error> {
+    let mut bytes = Vec::with_capacity(HEADER_BYTES + key.uncompressed_size());
+    parameters.write_header(key_kind, &mut bytes);
+    key.serialize_uncompressed(&mut bytes)?;
+    fs::write(path, bytes)?;
+    Ok(())
+}
+
+fn load_key<Key: CanonicalDeserialize>(
+    path: impl AsRef<Path>,
+    parameters: &KeyParameters,
+    key_kind: u8,
+) -> Result<Key, Error> {
+    let bytes = fs::read(path)?;
+    let mut reader = parameters.check_header(key_kind, &bytes)?;
+    let key = Key::deserialize_uncompressed(&mut reader)?;
+    if !reader.is_empty() {
+        Err(GenericError::ParseError(format!(
+            "the key file has {} trailing bytes",
+            reader.len()
+        )))?
+    }
+    Ok(key)
+}
+
+/// Write a proving key to the file at `path`, with a header for the circuit with the given
+/// `parameters` (see `load_proving_key`).
+pub fn save_proving_key<Conf: Config>(
+    path: impl AsRef<Path>,
+    parameters: &KeyParameters,
+    proving_key: &ProvingKey<Conf>,
+) -> Result<(), Error>
+where
+    ProvingKey<Conf>: CanonicalSerialize,
+{
+    save_key(path, parameters, PROVING_KEY, proving_key)
+}
+
+/// Read a proving key written with `save_proving_key`. Fails with `GenericError::ParseError` if
+/// the file has another format version, or if the key was generated for a circuit with other
+/// `parameters`.
+pub fn load_proving_key<Conf: Config>(
+    path: impl AsRef<Path>,
+    parameters: &KeyParameters,
+) -> Result<ProvingKey<Conf>, Error>
+where
+    ProvingKey<Conf>: CanonicalDeserialize,
+{
+    load_key(path, parameters, PROVING_KEY)
+}
+
+/// Write a verifying key to the file at `path`, with a header for the circuit with the given
+/// `parameters` (see `load_verifying_key`).
+pub fn save_verifying_key<Conf: Config>(
+    path: impl AsRef<Path>,
+    parameters: &KeyParameters,
+    verifying_key: &VerifyingKey<Conf>,
+) -> Result<(), Error>
+where
+    VerifyingKey<Conf>: CanonicalSerialize,
+{
+    save_key(path, parameters, VERIFYING_KEY, verifying_key)
+}
+
+/// Read a verifying key written with `save_verifying_key`. Fails with `GenericError::ParseError`
+/// if the file has another format version, or if the key was generated for a circuit with other
+/// `parameters`.
+pub fn load_verifying_key<Conf: Config>(
+    path: impl AsRef<Path>,
+    parameters: &KeyParameters,
+) -> Result<VerifyingKey<Conf>, Error>
+where
+    VerifyingKey<Conf>: CanonicalDeserialize,
+{
+    load_key(path, parameters, VERIFYING_KEY)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::conformance::SquareCircuit;
+    use crate::config::BasicConfig;
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+
+    fn serialized<T: CanonicalSerialize>(value: &T) -> Vec<u8> {
+        let mut bytes = vec![];
+        value.serialize_uncompressed(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn assert_parse_error(error: Error, expected: &str) {
+        assert!(
+            matches!(&error, VldpError::Generic(GenericError::ParseError(message)) if message.contains(expected)),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn keys_are_only_loaded_for_their_circuit() {
+        let rng = &mut ChaChaRng::from_seed([107; 32]);
+        let (proving_key, verifying_key) =
+            <Groth16<Bls12_381> as ProofSystem<ChaChaRng>>::keygen(SquareCircuit::<Fr>(None), rng)
+                .unwrap();
+        let directory = std::env::temp_dir();
+        let proving_key_path = directory.join(format!("vldp-{}-proving.key", std::process::id()));
+        let verifying_key_path =
+            directory.join(format!("vldp-{}-verifying.key", std::process::id()));
+        let parameters = KeyParameters::expand::<3, 2, 1, 2, 4, 4, false>();
+
+        // both keys round-trip
+        save_proving_key::<Conf>(&proving_key_path, &parameters, &proving_key).unwrap();
+        save_verifying_key::<Conf>(&verifying_key_path, &parameters, &verifying_key).unwrap();
+        let loaded_proving_key = load_proving_key::<Conf>(&proving_key_path, &parameters).unwrap();
+        let loaded_verifying_key =
+            load_verifying_key::<Conf>(&verifying_key_path, &parameters).unwrap();
+        assert_eq!(serialized(&loaded_proving_key), serialized(&proving_key));
+        assert_eq!(
+            serialized(&loaded_verifying_key),
+            serialized(&verifying_key)
+        );
+
+        // a key of a circuit with another K, another scheme, or of the other kind is rejected
+        let other_k = KeyParameters::expand::<3, 2, 1, 2, 4, 8, false>();
+        assert_parse_error(
+            load_proving_key::<Conf>(&proving_key_path, &other_k).unwrap_err(),
+            "K = 4, but K = 8",
+        );
+        let other_scheme = KeyParameters::shuffle::<2, 1, 2, 4, 4, false>();
+        assert_parse_error(
+            load_proving_key::<Conf>(&proving_key_path, &other_scheme).unwrap_err(),
+            "Expand circuit",
+        );
+        assert_parse_error(
+            load_proving_key::<Conf>(&verifying_key_path, &parameters).unwrap_err(),
+            "contains a verifying key",
+        );
+
+        // as is a file of another format version
+        let mut bytes = fs::read(&proving_key_path).unwrap();
+        bytes[8] += 1;
+        fs::write(&proving_key_path, bytes).unwrap();
+        assert_parse_error(
+            load_proving_key::<Conf>(&proving_key_path, &parameters).unwrap_err(),
+            "format version 2",
+        );
+
+        fs::remove_file(proving_key_path).unwrap();
+        fs::remove_file(verifying_key_path).unwrap();
+    }
+}
@@ -7,15 +7,29 @@
 
 use crate::prelude::{constraints::*, *};
 use crate::primitives::crh::identity::constraints::IdentityHashGadget;
-use crate::primitives::crh::IdentityHash;
+use crate::primitives::crh::poseidon::constraints::{
+    PoseidonLeafHashGadget, PoseidonTwoToOneHashGadget,
+};
+use crate::primitives::crh::sha256::constraints::Sha256LeafHashGadget;
+use crate::primitives::crh::{
+    IdentityHash, PoseidonLeafHash, PoseidonTwoToOneHash, Sha256LeafHash,
+};
 use ark_crypto_primitives::crh;
 use ark_crypto_primitives::crh::pedersen::Window;
+use ark_crypto_primitives::crh::sha256::constraints::{DigestVar, Sha256Gadget};
+use ark_crypto_primitives::crh::sha256::Sha256;
 use ark_crypto_primitives::crh::{
     CRHScheme, CRHSchemeGadget, TwoToOneCRHScheme, TwoToOneCRHSchemeGadget,
 };
 use ark_crypto_primitives::merkle_tree::constraints::BytesVarDigestConverter;
-use ark_crypto_primitives::merkle_tree::ByteDigestConverter;
+use ark_crypto_primitives::merkle_tree::{
+    ByteDigestConverter, DigestConverter, IdentityDigestConverter,
+};
+use ark_crypto_primitives::sponge::Absorb;
 use ark_ec::CurveGroup;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::ToConstraintFieldGadget;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use std::fmt::Debug;
 use std::hash::Hash;
@@ -97,3 +111,219 @@ where
     type LeafHash = IdentityHashGadget<InputG>;
     type TwoToOneHash = crh::pedersen::constraints::TwoToOneCRHGadget<C, CG, TwoToOneW>;
 }
+
+/// Configuration that uses Poseidon (over the constraint field `F`) for hashing both the leaves
+/// and two child nodes, which needs far fewer constraints than the Pedersen configuration above.
+/// The leaves (i.e., the commitments) are hashed as their field elements (`ToConstraintField`).
+#[derive(Clone)]
+pub struct PoseidonMerkleTreeConfig<F, Input> {
+    #[doc(hidden)]
+    _field: PhantomData<F>,
+
+    #[doc(hidden)]
+    _input: PhantomData<Input>,
+}
+
+impl<F: PrimeField + Absorb, Input: ToConstraintField<F>> ark_crypto_primitives::merkle_tree::Config
+    for PoseidonMerkleTreeConfig<F, Input>
+{
+    type Leaf = <Self::LeafHash as CRHScheme>::Input;
+    type LeafDigest = <Self::LeafHash as CRHScheme>::Output;
+    type LeafInnerDigestConverter = IdentityDigestConverter<Self::LeafDigest>;
+    type InnerDigest = <Self::TwoToOneHash as TwoToOneCRHScheme>::Output;
+    type LeafHash = PoseidonLeafHash<F, Input>;
+    type TwoToOneHash = PoseidonTwoToOneHash<F>;
+}
+
+/// R1CS part of the Poseidon configuration, where `InputG` is the gadget of the leaves (which
+/// must produce the same field elements as the leaves themselves).
+#[derive(Clone)]
+pub struct PoseidonMerkleTreeConfigGadget<F, InputG> {
+    #[doc(hidden)]
+    _field: PhantomData<F>,
+
+    #[doc(hidden)]
+    _input_var: PhantomData<InputG>,
+}
+
+impl<F: PrimeField + Absorb, Input: ToConstraintField<F>, InputG>
+    ark_crypto_primitives::merkle_tree::constraints::ConfigGadget<
+        PoseidonMerkleTreeConfig<F, Input>,
+        F,
+    > for PoseidonMerkleTreeConfigGadget<F, InputG>
+where
+    InputG: ToConstraintFieldGadget<F> + Debug,
+{
+    type Leaf = InputG;
+    type LeafDigest = FpVar<F>;
+    type LeafInnerConverter = IdentityDigestConverter<FpVar<F>>;
+    type InnerDigest = FpVar<F>;
+    type LeafHash = PoseidonLeafHashGadget<InputG>;
+    type TwoToOneHash = PoseidonTwoToOneHashGadget;
+}
+
+/// Converter that passes byte digests on as they are, matching `BytesVarDigestConverter` of byte
+/// digest gadgets. (`ByteDigestConverter` would serialize them with a length prefix instead.)
+pub struct RawBytesDigestConverter;
+
+impl DigestConverter<Vec<u8>, [u8]> for RawBytesDigestConverter {
+    type TargetType = Vec<u8>;
+
+    fn convert(item: Vec<u8>) -> Result<Self::TargetType, ark_crypto_primitives::Error> {
+        Ok(item)
+    }
+}
+
+/// Configuration that uses SHA-256 for hashing both the leaves and two child nodes, such that the
+/// tree matches other SHA-256 Merkle tree implementations. The leaves (i.e., the commitments) are
+/// hashed as their uncompressed serialization. SHA-256 needs far more constraints than the other
+/// configurations.
+///
+/// Note: arkworks allocates the root of such a tree as one public input per bit, whereas
+/// `CircuitExpand::public_inputs` packs the root with its `ToConstraintField` implementation, so
+/// the two do not agree for this configuration yet.
+#[derive(Clone)]
+pub struct Sha256MerkleTreeConfig<Input> {
+    #[doc(hidden)]
+    _input: PhantomData<Input>,
+}
+
+impl<Input: CanonicalSerialize> ark_crypto_primitives::merkle_tree::Config
+    for Sha256MerkleTreeConfig<Input>
+{
+    type Leaf = <Self::LeafHash as CRHScheme>::Input;
+    type LeafDigest = <Self::LeafHash as CRHScheme>::Output;
+    type LeafInnerDigestConverter = RawBytesDigestConverter;
+    type InnerDigest = <Self::TwoToOneHash as TwoToOneCRHScheme>::Output;
+    type LeafHash = Sha256LeafHash<Input>;
+    type TwoToOneHash = Sha256;
+}
+
+/// R1CS part of the SHA-256 configuration, where `InputG` is the gadget of the leaves (whose bytes
+/// must equal the uncompressed serialization of the leaves themselves).
+#[derive(Clone)]
+pub struct Sha256MerkleTreeConfigGadget<F, InputG> {
+    #[doc(hidden)]
+    _field: PhantomData<F>,
+
+    #[doc(hidden)]
+    _input_var: PhantomData<InputG>,
+}
+
+impl<F: PrimeField, Input: CanonicalSerialize, InputG>
+    ark_crypto_primitives::merkle_tree::constraints::ConfigGadget<Sha256MerkleTreeConfig<Input>, F>
+    for Sha256MerkleTreeConfigGadget<F, InputG>
+where
+    InputG: ToBytesGadget<F> + Debug,
+{
+    type Leaf = InputG;
+    type LeafDigest = DigestVar<F>;
+    type LeafInnerConverter = BytesVarDigestConverter<DigestVar<F>, F>;
+    type InnerDigest = DigestVar<F>;
+    type LeafHash = Sha256LeafHashGadget<InputG>;
+    type TwoToOneHash = Sha256Gadget<F>;
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        PoseidonMerkleTreeConfig, PoseidonMerkleTreeConfigGadget, Sha256MerkleTreeConfig,
+        Sha256MerkleTreeConfigGadget,
+    };
+    use crate::primitives::crh::poseidon_parameters;
+    use ark_crypto_primitives::crh::poseidon::constraints::CRHParametersVar;
+    use ark_crypto_primitives::crh::sha256::constraints::UnitVar;
+    use ark_crypto_primitives::merkle_tree::constraints::PathVar;
+    use ark_crypto_primitives::merkle_tree::MerkleTree;
+    use ark_ec::CurveGroup;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::{EdwardsAffine as JubJubAffine, EdwardsProjective as JubJub, Fq};
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::UniformRand;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type TreeConfig = PoseidonMerkleTreeConfig<Fq, JubJubAffine>;
+    type TreeConfigGadget = PoseidonMerkleTreeConfigGadget<Fq, JubJubVar>;
+
+    #[test]
+    fn off_circuit_root_matches_membership_gadget() {
+        let rng = &mut ChaChaRng::from_seed([77; 32]);
+        let parameters = poseidon_parameters::<Fq>();
+        let leaves: Vec<JubJubAffine> = (0..4).map(|_| JubJub::rand(rng).into_affine()).collect();
+        let tree = MerkleTree::<TreeConfig>::new(&parameters, &parameters, &leaves).unwrap();
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let root = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+        let parameters_var = CRHParametersVar::<Fq>::new_constant(cs.clone(), &parameters).unwrap();
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = tree.generate_proof(index).unwrap();
+            let path =
+                PathVar::<TreeConfig, Fq, TreeConfigGadget>::new_witness(cs.clone(), || Ok(proof))
+                    .unwrap();
+            let leaf = JubJubVar::new_witness(cs.clone(), || Ok(*leaf)).unwrap();
+            assert!(path
+                .verify_membership(&parameters_var, &parameters_var, &root, &leaf)
+                .unwrap()
+                .value()
+                .unwrap());
+        }
+        assert!(cs.is_satisfied().unwrap());
+
+        // a leaf that is not in the tree is rejected
+        let proof = tree.generate_proof(0).unwrap();
+        let path =
+            PathVar::<TreeConfig, Fq, TreeConfigGadget>::new_witness(cs.clone(), || Ok(proof))
+                .unwrap();
+        let leaf = JubJubVar::new_witness(cs.clone(), || Ok(leaves[1])).unwrap();
+        assert!(!path
+            .verify_membership(&parameters_var, &parameters_var, &root, &leaf)
+            .unwrap()
+            .value()
+            .unwrap());
+    }
+
+    #[test]
+    fn sha256_root_matches_membership_gadget() {
+        type Sha256TreeConfig = Sha256MerkleTreeConfig<JubJubAffine>;
+        type Sha256TreeConfigGadget = Sha256MerkleTreeConfigGadget<Fq, JubJubVar>;
+
+        // depth 4, i.e., 8 leaves
+        let rng = &mut ChaChaRng::from_seed([109; 32]);
+        let leaves: Vec<JubJubAffine> = (0..8).map(|_| JubJub::rand(rng).into_affine()).collect();
+        let tree = MerkleTree::<Sha256TreeConfig>::new(&(), &(), &leaves).unwrap();
+        assert_eq!(tree.height(), 4);
+
+        let cs = ConstraintSystem::<Fq>::new_ref();
+        let parameters_var = UnitVar::<Fq>::new_constant(cs.clone(), ()).unwrap();
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = tree.generate_proof(index).unwrap();
+            let path = PathVar::<Sha256TreeConfig, Fq, Sha256TreeConfigGadget>::new_witness(
+                cs.clone(),
+                || Ok(proof),
+            )
+            .unwrap();
+            let leaf = JubJubVar::new_witness(cs.clone(), || Ok(*leaf)).unwrap();
+            let root = path
+                .calculate_root(&parameters_var, &parameters_var, &leaf)
+                .unwrap();
+            assert_eq!(root.value().unwrap().to_vec(), tree.root());
+        }
+        assert!(cs.is_satisfied().unwrap());
+
+        // a leaf that is not in the tree leads to another root
+        let proof = tree.generate_proof(0).unwrap();
+        let path = PathVar::<Sha256TreeConfig, Fq, Sha256TreeConfigGadget>::new_witness(
+            cs.clone(),
+            || Ok(proof),
+        )
+        .unwrap();
+        let leaf = JubJubVar::new_witness(cs.clone(), || Ok(leaves[1])).unwrap();
+        let root = path
+            .calculate_root(&parameters_var, &parameters_var, &leaf)
+            .unwrap();
+        assert_ne!(root.value().unwrap().to_vec(), tree.root());
+    }
+}
@@ -7,7 +7,30 @@ pub use basic_config::*;
 pub mod proof_system;
 pub use proof_system::*;
 
-mod merkle_tree;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod conformance;
+#[cfg(any(test, feature = "test-utils"))]
+pub use conformance::*;
+
+pub mod batch;
+pub use batch::*;
+
+pub mod dyn_proof_system;
+pub use dyn_proof_system::*;
+
+pub mod merkle_tree;
+pub use merkle_tree::*;
+
+#[cfg(feature = "std")]
+pub mod key_file;
+#[cfg(feature = "std")]
+pub use key_file::*;
+
+#[cfg(feature = "bls12-377")]
+pub mod bls12_377;
+#[cfg(feature = "bls12-377")]
+pub use bls12_377::*;
+
 mod windows;
 
 use crate::prelude::{constraints::*, *};
@@ -18,11 +41,22 @@ pub trait Config: Clone {
     type ZKPScheme: ProofSystem<Self::ZKPRng>;
     type ClientCommitmentScheme: CommitmentScheme;
     type ServerSignatureScheme: SignatureScheme;
-    type PRFScheme: PRF<Input = [u8; 32], Seed = [u8; 32], Output = [u8; 32]>;
+    /// PRF that derives the randomness in chunks of `PRF_OUTPUT_BYTES` bytes.
+    type PRFScheme: PRF<Input = [u8; 32], Seed = [u8; 32], Output: AsRef<[u8]>>;
+    /// Length of the output of `PRFScheme` in bytes. Must be at least 32, as PRF outputs are also
+    /// used as PRF inputs (see `derive_prf_eval_points`).
+    const PRF_OUTPUT_BYTES: usize;
     type ClientSignatureScheme: SignatureScheme;
     type ClientMerkleTreeConfig: ark_crypto_primitives::merkle_tree::Config<
         Leaf = ClientCommitmentSchemeOutput<Self>,
     >;
+    /// Hash that is optionally applied to the input value message before it is signed.
+    /// Its output must serialize (uncompressed) to the same bytes as its gadget's output.
+    type ClientInputHash: CRHScheme<Input = [u8]>;
+    /// Maximum number of bytes `ClientInputHash` hashes in one evaluation. Longer messages are
+    /// hashed in chained blocks (see `InputValueSignatureInput::signed_bytes`), so this must
+    /// exceed the length of a serialized digest.
+    const CLIENT_INPUT_HASH_BLOCK_BYTES: usize;
 }
 
 /// Subtrait of the `Config` trait to additionally describe all options that are to be defined
@@ -47,4 +81,9 @@ pub trait ConfigGadget<Conf: Config>: Clone {
         ConstraintField<Conf>,
         Leaf = ClientCommitmentSchemeOutputVar<Conf, Self>,
     >;
+    type ClientInputHashGadget: CRHSchemeGadget<
+        Conf::ClientInputHash,
+        ConstraintField<Conf>,
+        InputVar = [UInt8<ConstraintField<Conf>>],
+    >;
 }
@@ -2,12 +2,22 @@
 //! adaptations to switch the Groth16 proof system we used for another one.
 
 use crate::prelude::*;
-use ark_ff::PrimeField;
-use ark_relations::r1cs::ConstraintSynthesizer;
-use ark_serialize::CanonicalSerialize;
+use crate::primitives::parameters::fingerprint::fingerprint;
+use ark_ec::AffineRepr;
+use ark_ff::{BigInteger, PrimeField};
+use ark_relations::r1cs::{
+    ConstraintMatrices, ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, SynthesisError,
+    SynthesisMode,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
 use ark_snark::{CircuitSpecificSetupSNARK, SNARK};
+use ark_std::Zero;
+use num_bigint::BigUint;
 use rand::{RngCore, SeedableRng};
 use rand_chacha::ChaChaRng;
+use std::any::Any;
+use std::marker::PhantomData;
+use std::sync::Arc;
 
 /// Some proof systems need a specifically defined RNG, this trait helps to make that generic.
 pub trait ProofSystemRng: RngCore {
@@ -20,12 +30,36 @@ impl ProofSystemRng for ChaChaRng {
     }
 }
 
+/// Domain tag that is prepended to the serialized verifying key before it is hashed into a key
+/// fingerprint.
+pub const KEY_FINGERPRINT_DOMAIN_TAG: [u8; 8] = *b"VLDP-KEY";
+
+/// Fingerprint of a key pair: the Blake2s hash of the compressed serialization of its unprepared
+/// verifying key. The proving key and the verifying key of a pair have the same fingerprint, so a
+/// client and a server can compare their fingerprints to check that their keys belong together.
+pub type KeyFingerprint = [u8; 32];
+
+/// Compute the key fingerprint of an unprepared verifying key.
+pub fn key_fingerprint<T: CanonicalSerialize>(
+    unprepared_verifying_key: &T,
+) -> Result<KeyFingerprint, Error> {
+    let mut bytes = KEY_FINGERPRINT_DOMAIN_TAG.to_vec();
+    unprepared_verifying_key.serialize_compressed(&mut bytes)?;
+    Ok(fingerprint(&bytes))
+}
+
 /// Generic trait for a ZKP scheme. This makes it easier to switch out different ZKP schemes without
 /// having to write much code. (Currently, only implemented for Groth16.)
+///
+/// Every implementation must pass the conformance test suite `config::proof_system_conformance`
+/// (available with the `test-utils` feature), which is run for Groth16 in the tests of this crate.
 pub trait ProofSystem<R: ProofSystemRng> {
     type ConstraintField: PrimeField;
     type ProvingKey: Clone;
     type VerifyingKey: Clone;
+    /// Verifying key in the form in which it is stored or sent to another process (e.g., the
+    /// unprepared Groth16 key), from which the `VerifyingKey` is prepared once on loading.
+    type UnpreparedVerifyingKey: Clone + CanonicalSerialize + CanonicalDeserialize;
     type Proof: CanonicalSerialize + Default;
 
     fn keygen<C: ConstraintSynthesizer<Self::ConstraintField> + Clone>(
@@ -39,12 +73,213 @@ pub trait ProofSystem<R: ProofSystemRng> {
         rng: &mut R,
     ) -> Result<Self::Proof, Error>;
 
+    /// Sample the randomness for a proof, exactly as `prove` does, as field elements (e.g., the
+    /// scalars r and s of Groth16). Together with the proving inputs, it determines the proof, so
+    /// it must be stored as securely as the witness.
+    ///
+    /// By default, proofs cannot be reproduced: no randomness is sampled, and
+    /// `prove_with_randomness` fails.
+    fn sample_proof_randomness(_rng: &mut R) -> Vec<Self::ConstraintField> {
+        Vec::new()
+    }
+
+    /// Create a proof like `prove`, but with the given randomness instead of fresh randomness, such
+    /// that a proof can be reproduced exactly.
+    fn prove_with_randomness<C: ConstraintSynthesizer<Self::ConstraintField>>(
+        _proving_key: &Self::ProvingKey,
+        _circuit: C,
+        _randomness: &[Self::ConstraintField],
+    ) -> Result<Self::Proof, Error> {
+        Err(GenericError::UnsupportedByProofSystem(
+            "proofs with given randomness",
+        ))?
+    }
+
+    /// Precompute the prover context for a circuit, which is given without witness (as for
+    /// `keygen`).
+    ///
+    /// By default, nothing is precomputed and the context is empty.
+    fn prover_context<C: ConstraintSynthesizer<Self::ConstraintField>>(
+        _circuit: C,
+    ) -> Result<ProverPrecomputation<Self::ConstraintField>, Error> {
+        Ok(ProverPrecomputation::default())
+    }
+
+    /// Create a proof like `prove_with_randomness`, but reuse the precomputation of `context`, such
+    /// that only the witness is synthesized for this proof. The context must have been computed
+    /// for the same circuit as the proving key.
+    ///
+    /// By default, the context is ignored and the proof is created with `prove_with_randomness`.
+    fn prove_with_context<C: ConstraintSynthesizer<Self::ConstraintField>>(
+        proving_key: &Self::ProvingKey,
+        _context: &ProverPrecomputation<Self::ConstraintField>,
+        circuit: C,
+        randomness: &[Self::ConstraintField],
+    ) -> Result<Self::Proof, Error> {
+        Self::prove_with_randomness(proving_key, circuit, randomness)
+    }
+
     fn verify(
         verifying_key: &Self::VerifyingKey,
         public_inputs: &[Self::ConstraintField],
         proof: &Self::Proof,
         rng: &mut R,
     ) -> Result<bool, Error>;
+
+    /// The unprepared form of `verifying_key`, e.g., to store it for a server process.
+    fn unprepared_verifying_key(verifying_key: &Self::VerifyingKey)
+        -> Self::UnpreparedVerifyingKey;
+
+    /// Prepare an unprepared verifying key (e.g., after deserializing it) for `verify`.
+    fn prepare_verifying_key(verifying_key: &Self::UnpreparedVerifyingKey) -> Self::VerifyingKey;
+
+    /// The unprepared verifying key of the key pair that `proving_key` belongs to.
+    fn proving_key_verifying_key(proving_key: &Self::ProvingKey) -> Self::UnpreparedVerifyingKey;
+
+    /// Key fingerprint of the key pair that `proving_key` belongs to (see `KeyFingerprint`).
+    fn proving_key_fingerprint(proving_key: &Self::ProvingKey) -> Result<KeyFingerprint, Error> {
+        key_fingerprint(&Self::proving_key_verifying_key(proving_key))
+    }
+
+    /// Key fingerprint of the key pair that `verifying_key` belongs to (see `KeyFingerprint`).
+    fn verifying_key_fingerprint(
+        verifying_key: &Self::VerifyingKey,
+    ) -> Result<KeyFingerprint, Error> {
+        key_fingerprint(&Self::unprepared_verifying_key(verifying_key))
+    }
+
+    /// Verify a proof like `verify`, but with an unprepared verifying key. The key is prepared
+    /// anew for every call, so callers that verify many proofs should prepare it once instead.
+    fn verify_unprepared(
+        verifying_key: &Self::UnpreparedVerifyingKey,
+        public_inputs: &[Self::ConstraintField],
+        proof: &Self::Proof,
+        rng: &mut R,
+    ) -> Result<bool, Error> {
+        Self::verify(
+            &Self::prepare_verifying_key(verifying_key),
+            public_inputs,
+            proof,
+            rng,
+        )
+    }
+
+    /// Verify several proofs for the same verifying key at once, given as pairs of public inputs
+    /// and proof. Returns `true` if and only if all proofs verify (except with negligible
+    /// probability for randomized checks).
+    ///
+    /// The default implementation verifies every proof on its own. Implementations may instead
+    /// check a random linear combination of the verification equations.
+    fn batch_verify(
+        verifying_key: &Self::VerifyingKey,
+        instances: &[(&[Self::ConstraintField], &Self::Proof)],
+        rng: &mut R,
+    ) -> Result<bool, Error> {
+        for (public_inputs, proof) in instances {
+            if !Self::verify(verifying_key, public_inputs, proof, rng)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Verify a proof for the configured proof system without using any of the typed structures:
+/// - `verifying_key_bytes`: the compressed serialization of the `VerifyingKey<Conf>`
+/// - `public_inputs_le`: every public input field element as little-endian bytes, in the order
+///   given by the `public_inputs` function of the corresponding circuit
+/// - `proof_bytes`: the compressed serialization of the proof
+///
+/// Public inputs that are not canonical field elements (i.e., not smaller than the modulus) are
+/// rejected with a `ConversionError`.
+pub fn verify_raw<Conf: Config>(
+    verifying_key_bytes: &[u8],
+    public_inputs_le: &[Vec<u8>],
+    proof_bytes: &[u8],
+) -> Result<bool, Error>
+where
+    VerifyingKey<Conf>: CanonicalDeserialize,
+    Proof<Conf>: CanonicalDeserialize,
+{
+    let verifying_key = VerifyingKey::<Conf>::deserialize_compressed(verifying_key_bytes)?;
+    let proof = Proof::<Conf>::deserialize_compressed(proof_bytes)?;
+    let public_inputs = public_inputs_le
+        .iter()
+        .map(|bytes| {
+            let bigint = BigUint::from_bytes_le(bytes)
+                .try_into()
+                .map_err(|_| GenericError::ConversionError)?;
+            ConstraintField::<Conf>::from_bigint(bigint).ok_or(GenericError::ConversionError)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Conf::ZKPScheme::verify(
+        &verifying_key,
+        &public_inputs,
+        &proof,
+        &mut ZKPRng::<Conf>::new(),
+    )
+}
+
+/// The scalars r and s of a Groth16 proof, as sampled by `sample_proof_randomness`.
+fn groth16_randomness<F: PrimeField>(randomness: &[F]) -> Result<(F, F), Error> {
+    match randomness {
+        [r, s] => Ok((*r, *s)),
+        _ => Err(SerializationError::InvalidData)?,
+    }
+}
+
+/// Prover context: circuit-invariant precomputation for proving over the field `F` (see
+/// `ProofSystem::prover_context`), which can be reused across all proofs for the same circuit. Its
+/// content is specific to the proof system that computed it; an empty one (the default) carries no
+/// precomputation.
+pub struct ProverPrecomputation<F> {
+    precomputation: Option<Arc<dyn Any + Send + Sync>>,
+    _field: PhantomData<F>,
+}
+
+impl<F> ProverPrecomputation<F> {
+    /// Wrap the precomputation of a proof system.
+    pub fn new<T: Any + Send + Sync>(precomputation: T) -> Self {
+        Self {
+            precomputation: Some(Arc::new(precomputation)),
+            _field: PhantomData,
+        }
+    }
+
+    /// The precomputation, if the context holds one of type `T`.
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.precomputation.as_deref()?.downcast_ref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.precomputation.is_none()
+    }
+}
+
+impl<F> Clone for ProverPrecomputation<F> {
+    fn clone(&self) -> Self {
+        Self {
+            precomputation: self.precomputation.clone(),
+            _field: PhantomData,
+        }
+    }
+}
+
+impl<F> Default for ProverPrecomputation<F> {
+    fn default() -> Self {
+        Self {
+            precomputation: None,
+            _field: PhantomData,
+        }
+    }
+}
+
+/// Prover context of Groth16: the constraint matrices of the circuit, which are otherwise rebuilt
+/// (including inlining all linear combinations) for every proof. The evaluation domain of the QAP
+/// is not part of it, as ark-groth16 derives it from the (cached) number of constraints and public
+/// inputs inside `create_proof_with_reduction_and_matrices`, which is cheap in comparison.
+pub struct Groth16ProverContext<F: PrimeField> {
+    matrices: ConstraintMatrices<F>,
 }
 
 // HERE WE IMPLEMENT THE GENERIC TRAIT FOR GROTH16 (as used in our experiments)
@@ -54,6 +289,7 @@ impl<E: Pairing, QAP: ark_groth16::r1cs_to_qap::R1CSToQAP, R: ProofSystemRng + C
     type ConstraintField = E::ScalarField;
     type ProvingKey = ark_groth16::ProvingKey<E>;
     type VerifyingKey = ark_groth16::PreparedVerifyingKey<E>;
+    type UnpreparedVerifyingKey = ark_groth16::VerifyingKey<E>;
     type Proof = ark_groth16::Proof<E>;
 
     fn keygen<C: ConstraintSynthesizer<Self::ConstraintField>>(
@@ -82,6 +318,95 @@ impl<E: Pairing, QAP: ark_groth16::r1cs_to_qap::R1CSToQAP, R: ProofSystemRng + C
         >>::prove(proving_key, circuit, rng)?)
     }
 
+    fn sample_proof_randomness(rng: &mut R) -> Vec<Self::ConstraintField> {
+        // same order as in `create_random_proof_with_reduction` of ark-groth16
+        let r = E::ScalarField::rand(rng);
+        let s = E::ScalarField::rand(rng);
+        vec![r, s]
+    }
+
+    fn prove_with_randomness<C: ConstraintSynthesizer<Self::ConstraintField>>(
+        proving_key: &Self::ProvingKey,
+        circuit: C,
+        randomness: &[Self::ConstraintField],
+    ) -> Result<Self::Proof, Error> {
+        let (r, s) = groth16_randomness(randomness)?;
+        Ok(ark_groth16::Groth16::<E, QAP>::create_proof_with_reduction(
+            circuit,
+            proving_key,
+            r,
+            s,
+        )?)
+    }
+
+    fn prover_context<C: ConstraintSynthesizer<Self::ConstraintField>>(
+        circuit: C,
+    ) -> Result<ProverPrecomputation<Self::ConstraintField>, Error> {
+        // same synthesis as in the setup of ark-groth16
+        let cs = ConstraintSystem::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Constraints);
+        cs.set_mode(SynthesisMode::Setup);
+        circuit.generate_constraints(cs.clone())?;
+        cs.finalize();
+        Ok(ProverPrecomputation::new(Groth16ProverContext {
+            matrices: cs.to_matrices().ok_or(SynthesisError::MissingCS)?,
+        }))
+    }
+
+    fn prove_with_context<C: ConstraintSynthesizer<Self::ConstraintField>>(
+        proving_key: &Self::ProvingKey,
+        context: &ProverPrecomputation<Self::ConstraintField>,
+        circuit: C,
+        randomness: &[Self::ConstraintField],
+    ) -> Result<Self::Proof, Error> {
+        let context = match context.get::<Groth16ProverContext<E::ScalarField>>() {
+            Some(context) => context,
+            // an empty context carries no matrices to reuse
+            None if context.is_empty() => {
+                return <Self as ProofSystem<R>>::prove_with_randomness(
+                    proving_key,
+                    circuit,
+                    randomness,
+                )
+            }
+            None => Err(GenericError::ProverContextMismatch)?,
+        };
+        // only compute the assignment, the matrices are taken from the context
+        let cs = ConstraintSystem::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Constraints);
+        cs.set_mode(SynthesisMode::Prove {
+            construct_matrices: false,
+        });
+        circuit.generate_constraints(cs.clone())?;
+        let matrices = &context.matrices;
+        if cs.num_instance_variables() != matrices.num_instance_variables
+            || cs.num_witness_variables() != matrices.num_witness_variables
+            || cs.num_constraints() != matrices.num_constraints
+        {
+            Err(GenericError::ProverContextMismatch)?
+        }
+        let full_assignment = {
+            let cs = cs.borrow().ok_or(SynthesisError::MissingCS)?;
+            [
+                cs.instance_assignment.as_slice(),
+                cs.witness_assignment.as_slice(),
+            ]
+            .concat()
+        };
+        let (r, s) = groth16_randomness(randomness)?;
+        Ok(
+            ark_groth16::Groth16::<E, QAP>::create_proof_with_reduction_and_matrices(
+                proving_key,
+                r,
+                s,
+                matrices,
+                matrices.num_instance_variables,
+                matrices.num_constraints,
+                &full_assignment,
+            )?,
+        )
+    }
+
     fn verify(
         verifying_key: &Self::VerifyingKey,
         public_inputs: &[Self::ConstraintField],
@@ -94,4 +419,303 @@ impl<E: Pairing, QAP: ark_groth16::r1cs_to_qap::R1CSToQAP, R: ProofSystemRng + C
             proof,
         )?)
     }
+
+    fn unprepared_verifying_key(
+        verifying_key: &Self::VerifyingKey,
+    ) -> Self::UnpreparedVerifyingKey {
+        verifying_key.vk.clone()
+    }
+
+    fn prepare_verifying_key(verifying_key: &Self::UnpreparedVerifyingKey) -> Self::VerifyingKey {
+        ark_groth16::prepare_verifying_key(verifying_key)
+    }
+
+    fn proving_key_verifying_key(proving_key: &Self::ProvingKey) -> Self::UnpreparedVerifyingKey {
+        proving_key.vk.clone()
+    }
+
+    fn batch_verify(
+        verifying_key: &Self::VerifyingKey,
+        instances: &[(&[Self::ConstraintField], &Self::Proof)],
+        rng: &mut R,
+    ) -> Result<bool, Error> {
+        if instances.is_empty() {
+            return Ok(true);
+        }
+        // random linear combination of the equations e(A, B) = e(alpha, beta) e(IC, gamma) e(C, delta)
+        // with scalars r_i, which holds for all proofs if the combined equation holds (except with
+        // negligible probability):
+        // prod e(r_i A_i, B_i) e(sum r_i IC_i, -gamma) e(sum r_i C_i, -delta) = e(alpha, beta)^(sum r_i)
+        let mut g1 = Vec::with_capacity(instances.len() + 2);
+        let mut g2 = Vec::with_capacity(instances.len() + 2);
+        let mut combined_inputs = E::G1::zero();
+        let mut combined_c = E::G1::zero();
+        let mut scalar_sum = E::ScalarField::zero();
+        for (public_inputs, proof) in instances {
+            let scalar = E::ScalarField::rand(rng);
+            let prepared_inputs =
+                ark_groth16::Groth16::<E, QAP>::prepare_inputs(verifying_key, public_inputs)?;
+            combined_inputs += prepared_inputs * scalar;
+            combined_c += proof.c * scalar;
+            scalar_sum += scalar;
+            g1.push(E::G1Prepared::from(proof.a * scalar));
+            g2.push(E::G2Prepared::from(proof.b));
+        }
+        g1.push(E::G1Prepared::from(combined_inputs));
+        g2.push(verifying_key.gamma_g2_neg_pc.clone());
+        g1.push(E::G1Prepared::from(combined_c));
+        g2.push(verifying_key.delta_g2_neg_pc.clone());
+        let combined = E::final_exponentiation(E::multi_miller_loop(g1, g2))
+            .ok_or(SynthesisError::UnexpectedIdentity)?;
+        Ok(combined.0 == verifying_key.alpha_g1_beta_g2.pow(scalar_sum.into_bigint()))
+    }
+}
+
+/// Keys that can be hashed into the key fingerprint of their key pair (see `KeyFingerprint`).
+pub trait FingerprintKey {
+    fn fingerprint(&self) -> Result<KeyFingerprint, Error>;
+}
+
+impl<E: Pairing> FingerprintKey for ark_groth16::ProvingKey<E> {
+    fn fingerprint(&self) -> Result<KeyFingerprint, Error> {
+        key_fingerprint(&self.vk)
+    }
+}
+
+impl<E: Pairing> FingerprintKey for ark_groth16::VerifyingKey<E> {
+    fn fingerprint(&self) -> Result<KeyFingerprint, Error> {
+        key_fingerprint(self)
+    }
+}
+
+impl<E: Pairing> FingerprintKey for ark_groth16::PreparedVerifyingKey<E> {
+    fn fingerprint(&self) -> Result<KeyFingerprint, Error> {
+        key_fingerprint(&self.vk)
+    }
+}
+
+/// Number of bytes of every base field element in the EVM encoding (see `OnChainVk`).
+pub const EVM_FIELD_ELEMENT_BYTES: usize = 64;
+
+/// Elements of an (unprepared) Groth16 verifying key, as consumed by on-chain verifiers (e.g., a
+/// Solidity verifier contract). The elements are encoded like the inputs of the BLS12-381
+/// precompiles of EIP-2537, independently of the serialization of the curve in arkworks:
+/// - every base field element is big-endian and left-padded with zeros to
+///   `EVM_FIELD_ELEMENT_BYTES` bytes
+/// - a G1 element is its x- and then its y-coordinate (128 bytes)
+/// - a G2 element is its x- and then its y-coordinate, each as `c0` followed by `c1` (256 bytes)
+/// - the point at infinity is all zeros
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OnChainVk {
+    pub alpha_g1: Vec<u8>,
+    pub beta_g2: Vec<u8>,
+    pub gamma_g2: Vec<u8>,
+    pub delta_g2: Vec<u8>,
+    /// One element per public input, plus one for the constant term (first element).
+    pub gamma_abc_g1: Vec<Vec<u8>>,
+}
+
+/// Export the elements of a Groth16 verifying key for use in an on-chain verifier (see
+/// `OnChainVk` for their encoding). The prepared verifying key, which is what the Groth16
+/// configurations use as `VerifyingKey`, retains the unprepared key it was derived from.
+pub fn export_solidity_verifier_inputs<E: Pairing>(
+    verifying_key: &ark_groth16::PreparedVerifyingKey<E>,
+) -> Result<OnChainVk, Error> {
+    let vk = &verifying_key.vk;
+    Ok(OnChainVk {
+        alpha_g1: evm_encoding(&vk.alpha_g1)?,
+        beta_g2: evm_encoding(&vk.beta_g2)?,
+        gamma_g2: evm_encoding(&vk.gamma_g2)?,
+        delta_g2: evm_encoding(&vk.delta_g2)?,
+        gamma_abc_g1: vk
+            .gamma_abc_g1
+            .iter()
+            .map(evm_encoding)
+            .collect::<Result<Vec<_>, _>>()?,
+    })
+}
+
+/// Encode an affine group element as described for `OnChainVk`. Fails with
+/// `GenericError::UnsupportedByProofSystem` if a base field element does not fit into
+/// `EVM_FIELD_ELEMENT_BYTES` bytes.
+fn evm_encoding<A: AffineRepr>(element: &A) -> Result<Vec<u8>, Error> {
+    let coordinates = A::BaseField::extension_degree() as usize * 2;
+    let mut bytes = vec![0; coordinates * EVM_FIELD_ELEMENT_BYTES];
+    if let Some((x, y)) = element.xy() {
+        let field_elements = x
+            .to_base_prime_field_elements()
+            .chain(y.to_base_prime_field_elements());
+        for (field_element, chunk) in
+            field_elements.zip(bytes.chunks_exact_mut(EVM_FIELD_ELEMENT_BYTES))
+        {
+            let field_element = field_element.into_bigint().to_bytes_be();
+            // strip the leading zeros of the limbs, such that only the value has to fit
+            let first_nonzero = field_element
+                .iter()
+                .position(|&byte| byte != 0)
+                .unwrap_or(field_element.len());
+            let value = &field_element[first_nonzero..];
+            if value.len() > EVM_FIELD_ELEMENT_BYTES {
+                Err(GenericError::UnsupportedByProofSystem(
+                    "EVM encodings of base field elements wider than 64 bytes",
+                ))?
+            }
+            chunk[EVM_FIELD_ELEMENT_BYTES - value.len()..].copy_from_slice(value);
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        export_solidity_verifier_inputs, FingerprintKey, ProofSystem, EVM_FIELD_ELEMENT_BYTES,
+    };
+    use crate::config::conformance::SquareCircuit;
+    use ark_bls12_381::{Bls12_381, Fq, Fq2, Fr, G1Affine, G2Affine};
+    use ark_ec::AffineRepr;
+    use ark_ff::{BigInteger, PrimeField};
+    use ark_groth16::Groth16;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    #[test]
+    fn exported_verifying_key_round_trips() {
+        let rng = &mut ChaChaRng::from_seed([0; 32]);
+        let (_, verifying_key) =
+            <Groth16<Bls12_381> as ProofSystem<ChaChaRng>>::keygen(SquareCircuit(None), rng)
+                .unwrap();
+        let exported = export_solidity_verifier_inputs(&verifying_key).unwrap();
+
+        // decode the EVM encoding independently of arkworks' serialization
+        let fq = |bytes: &[u8]| {
+            assert_eq!(bytes.len(), EVM_FIELD_ELEMENT_BYTES);
+            // BLS12-381 base field elements have 48 bytes, the rest is padding
+            assert_eq!(bytes[..16], [0; 16]);
+            Fq::from_be_bytes_mod_order(bytes)
+        };
+        let g1 = |bytes: &[u8]| {
+            assert_eq!(bytes.len(), 2 * EVM_FIELD_ELEMENT_BYTES);
+            let [x, y] = [0, 1].map(|i| fq(&bytes[i * 64..(i + 1) * 64]));
+            G1Affine::new(x, y)
+        };
+        let g2 = |bytes: &[u8]| {
+            assert_eq!(bytes.len(), 4 * EVM_FIELD_ELEMENT_BYTES);
+            let [x_c0, x_c1, y_c0, y_c1] = [0, 1, 2, 3].map(|i| fq(&bytes[i * 64..(i + 1) * 64]));
+            G2Affine::new(Fq2::new(x_c0, x_c1), Fq2::new(y_c0, y_c1))
+        };
+
+        let vk = &verifying_key.vk;
+        assert_eq!(g1(&exported.alpha_g1), vk.alpha_g1);
+        assert_eq!(g2(&exported.beta_g2), vk.beta_g2);
+        assert_eq!(g2(&exported.gamma_g2), vk.gamma_g2);
+        assert_eq!(g2(&exported.delta_g2), vk.delta_g2);
+        // one public input plus the constant term
+        assert_eq!(exported.gamma_abc_g1.len(), 2);
+        for (bytes, element) in exported.gamma_abc_g1.iter().zip(vk.gamma_abc_g1.iter()) {
+            assert_eq!(&g1(bytes), element);
+        }
+
+        // the x-coordinate of alpha is its big-endian integer, and G2 starts with c0
+        assert_eq!(
+            exported.alpha_g1[16..64],
+            vk.alpha_g1.x.into_bigint().to_bytes_be()[..]
+        );
+        assert_eq!(
+            exported.beta_g2[16..64],
+            vk.beta_g2.x.c0.into_bigint().to_bytes_be()[..]
+        );
+
+        // the point at infinity is all zeros
+        let mut zero_vk = vk.clone();
+        zero_vk.alpha_g1 = G1Affine::zero();
+        let exported = export_solidity_verifier_inputs(&zero_vk.into()).unwrap();
+        assert_eq!(exported.alpha_g1, vec![0; 128]);
+    }
+
+    #[test]
+    fn reloaded_unprepared_verifying_key_verifies_proofs() {
+        type PS = Groth16<Bls12_381>;
+        let rng = &mut ChaChaRng::from_seed([115; 32]);
+        let (proving_key, verifying_key) =
+            <PS as ProofSystem<ChaChaRng>>::keygen(SquareCircuit(None), rng).unwrap();
+        let proof = <PS as ProofSystem<ChaChaRng>>::prove(
+            &proving_key,
+            SquareCircuit(Some(Fr::from(3_u8))),
+            rng,
+        )
+        .unwrap();
+
+        // e.g., written by the setup and read by a server process on startup
+        let mut bytes = vec![];
+        <PS as ProofSystem<ChaChaRng>>::unprepared_verifying_key(&verifying_key)
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+        let reloaded =
+            ark_groth16::VerifyingKey::<Bls12_381>::deserialize_compressed(bytes.as_slice())
+                .unwrap();
+
+        assert!(<PS as ProofSystem<ChaChaRng>>::verify_unprepared(
+            &reloaded,
+            &[Fr::from(9_u8)],
+            &proof,
+            rng
+        )
+        .unwrap());
+        assert!(!<PS as ProofSystem<ChaChaRng>>::verify_unprepared(
+            &reloaded,
+            &[Fr::from(4_u8)],
+            &proof,
+            rng
+        )
+        .unwrap());
+        let prepared = <PS as ProofSystem<ChaChaRng>>::prepare_verifying_key(&reloaded);
+        assert!(
+            <PS as ProofSystem<ChaChaRng>>::verify(&prepared, &[Fr::from(9_u8)], &proof, rng)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn key_fingerprints_match_within_a_key_pair_and_survive_serialization() {
+        type PS = Groth16<Bls12_381>;
+        let rng = &mut ChaChaRng::from_seed([127; 32]);
+        let (proving_key, verifying_key) =
+            <PS as ProofSystem<ChaChaRng>>::keygen(SquareCircuit(None), rng).unwrap();
+        let fingerprint = verifying_key.fingerprint().unwrap();
+        assert_eq!(proving_key.fingerprint().unwrap(), fingerprint);
+        assert_eq!(
+            <PS as ProofSystem<ChaChaRng>>::proving_key_fingerprint(&proving_key).unwrap(),
+            fingerprint
+        );
+        assert_eq!(
+            <PS as ProofSystem<ChaChaRng>>::verifying_key_fingerprint(&verifying_key).unwrap(),
+            fingerprint
+        );
+
+        // e.g., a proving key loaded by a client and a verifying key loaded by a server
+        let mut bytes = vec![];
+        proving_key.serialize_compressed(&mut bytes).unwrap();
+        let reloaded_proving_key =
+            ark_groth16::ProvingKey::<Bls12_381>::deserialize_compressed(bytes.as_slice()).unwrap();
+        assert_eq!(reloaded_proving_key.fingerprint().unwrap(), fingerprint);
+        let mut bytes = vec![];
+        verifying_key.vk.serialize_uncompressed(&mut bytes).unwrap();
+        let reloaded_verifying_key =
+            ark_groth16::VerifyingKey::<Bls12_381>::deserialize_uncompressed(bytes.as_slice())
+                .unwrap();
+        assert_eq!(reloaded_verifying_key.fingerprint().unwrap(), fingerprint);
+        assert_eq!(
+            <PS as ProofSystem<ChaChaRng>>::prepare_verifying_key(&reloaded_verifying_key)
+                .fingerprint()
+                .unwrap(),
+            fingerprint
+        );
+
+        // a separate setup for the same circuit yields different keys
+        let (other_proving_key, _) =
+            <PS as ProofSystem<ChaChaRng>>::keygen(SquareCircuit(None), rng).unwrap();
+        assert_ne!(other_proving_key.fingerprint().unwrap(), fingerprint);
+    }
 }
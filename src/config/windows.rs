@@ -23,3 +23,15 @@ impl Window for PedersenMerkleTreeWindow {
     const WINDOW_SIZE: usize = 4;
     const NUM_WINDOWS: usize = 256;
 }
+
+/// Window sizes and number of windows for the Pedersen hash of the (optionally) hashed input value
+/// message. Window size is 4 (optimal for the Pedersen Hash). Number of windows is set to 512, so
+/// one evaluation hashes 256 bytes, which covers messages with `INPUT_BYTES + TIME_BYTES <= 244`.
+/// Longer messages are hashed in chained blocks (see `InputValueSignatureInput::signed_bytes`).
+#[derive(Clone)]
+pub struct ClientInputHashWindow;
+
+impl ark_crypto_primitives::crh::pedersen::Window for ClientInputHashWindow {
+    const WINDOW_SIZE: usize = 4;
+    const NUM_WINDOWS: usize = 512;
+}
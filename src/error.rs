@@ -1,14 +1,45 @@
 //! Error types for VLDP
 
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::SerializationError;
+use num_bigint::{BigInt, BigUint};
 use std::fmt::{Display, Formatter};
 
 /// Generic error class capturing all VLDP errors:
 /// - Conversion error from value to field elemnt
 /// - Parsing related error
+/// - Excessive precision: a byte width that would require a pathologically large float precision
+/// - Time overflow: a time window that cannot be represented with `TIME_BYTES` bytes
+/// - Float error: a floating point operation of astro-float failed (e.g., on NaN)
+/// - Prover context mismatch: a prover context was used for a circuit of a different shape than it
+///   was computed for
+/// - Invalid scheme parameters: the byte widths and `K` of a scheme instance do not fit together
+///   (see `validate_scheme_params`)
+/// - Key fingerprint mismatch: the proving key of a client and the verifying key of a server
+///   belong to different key pairs (see `KeyFingerprint`), so no proof could be accepted
+/// - Unsupported by proof system: the configured proof system does not implement an optional
+///   feature of the `ProofSystem` trait
 #[derive(Debug)]
 pub enum GenericError {
     ConversionError,
     ParseError(String),
+    ExcessivePrecision {
+        parameter: &'static str,
+        bytes: usize,
+        max_bytes: usize,
+    },
+    TimeOverflow {
+        value: u64,
+        time_bytes: usize,
+    },
+    FloatError(astro_float::Error),
+    ProverContextMismatch,
+    InvalidSchemeParameters(String),
+    KeyFingerprintMismatch {
+        expected: [u8; 32],
+        actual: [u8; 32],
+    },
+    UnsupportedByProofSystem(&'static str),
 }
 impl Display for GenericError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -18,17 +49,109 @@ impl Display for GenericError {
                 "You tried to convert a value to a field element, but this failed!"
             ),
             GenericError::ParseError(e) => write!(f, "An error occured during parsing: {}", e),
+            GenericError::ExcessivePrecision {
+                parameter,
+                bytes,
+                max_bytes,
+            } => write!(
+                f,
+                "{} = {} exceeds the maximum supported value of {} for float conversions.",
+                parameter, bytes, max_bytes
+            ),
+            GenericError::TimeOverflow { value, time_bytes } => write!(
+                f,
+                "The time window starting at {} cannot be represented with TIME_BYTES = {}.",
+                value, time_bytes
+            ),
+            GenericError::FloatError(e) => {
+                write!(f, "A floating point conversion failed: {}", e)
+            }
+            GenericError::ProverContextMismatch => write!(
+                f,
+                "The prover context was computed for a different circuit than the one to be proven."
+            ),
+            GenericError::InvalidSchemeParameters(e) => {
+                write!(f, "Invalid scheme parameters: {}", e)
+            }
+            GenericError::KeyFingerprintMismatch { expected, actual } => write!(
+                f,
+                "The key fingerprint {} does not match the expected key fingerprint {}, so the proving and verifying keys belong to different setups.",
+                hex_string(actual),
+                hex_string(expected)
+            ),
+            GenericError::UnsupportedByProofSystem(feature) => {
+                write!(f, "The proof system does not support {}.", feature)
+            }
         }
     }
 }
 
 impl std::error::Error for GenericError {}
 
+fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+impl From<astro_float::Error> for GenericError {
+    fn from(e: astro_float::Error) -> Self {
+        GenericError::FloatError(e)
+    }
+}
+
 /// Class capturing client side errors:
 /// - Unobtained value: tried to use a value from storage that has not yet been computed
+/// - Merkle path index mismatch: the Merkle path to be proven does not lead to the leaf at the
+///   claimed index
+/// - Missing verifying key: tried to verify an own proof, but the client has no verifying key
+/// - Weak server seed: the server sent a seed that consists of zeros only
+/// - Duplicate public keys: several clients were provisioned with the same signature public key
+///   (each entry lists the indices of the clients that share a key)
+/// - Time out of bounds: the time of the input value does not lie in the window of the time
+///   bounds, so no valid proof can be created
+/// - Inconsistent storage: an imported client storage contains values of a step without the values
+///   of the steps before it (or only part of the values of a step)
+/// - Unpublished parameters: the parameters do not match the fingerprint published for the
+///   deployment
+/// - Reused PRF eval points: several readings of a Shuffle client would be randomized with the
+///   same PRF eval point, and hence with the same randomness
+/// - Signed input out of range: a signed input value does not fit into `INPUT_BYTES` bytes in two's
+///   complement
+/// - Merkle tree full: tried to append a leaf to a Merkle tree whose leaves have all been appended
+/// - Time too large: a time value does not fit into `TIME_BYTES` little-endian bytes
+/// - Input too large: an input value does not fit into `INPUT_BYTES` little-endian bytes
 #[derive(Debug)]
 pub enum ClientError {
     UnobtainedValue,
+    MerklePathIndexMismatch {
+        index: usize,
+        leaf_position: usize,
+    },
+    MissingVerifyingKey,
+    WeakServerSeed,
+    DuplicatePublicKeys(Vec<Vec<usize>>),
+    TimeOutOfBounds {
+        time: BigUint,
+        lower_bound: BigUint,
+        upper_bound: BigUint,
+    },
+    InconsistentStorage,
+    UnpublishedParameters,
+    ReusedPrfEvalPoints,
+    SignedInputOutOfRange {
+        value: BigInt,
+        input_bytes: usize,
+    },
+    MerkleTreeFull {
+        leaves: usize,
+    },
+    TimeTooLarge {
+        value: u64,
+        time_bytes: usize,
+    },
+    InputTooLarge {
+        value: BigUint,
+        input_bytes: usize,
+    },
 }
 
 impl Display for ClientError {
@@ -38,8 +161,411 @@ impl Display for ClientError {
                 f,
                 "You tried to use a value from storage, but this value has not yet been obtained."
             ),
+            ClientError::MerklePathIndexMismatch {
+                index,
+                leaf_position,
+            } => write!(
+                f,
+                "The Merkle path leads to leaf {}, but the proof is for index {}.",
+                leaf_position, index
+            ),
+            ClientError::MissingVerifyingKey => write!(
+                f,
+                "You tried to verify a proof, but the client has no verifying key."
+            ),
+            ClientError::WeakServerSeed => write!(
+                f,
+                "The server seed consists of zeros only, which hints at a broken server RNG."
+            ),
+            ClientError::DuplicatePublicKeys(collisions) => write!(
+                f,
+                "Several clients share a signature public key: {:?}",
+                collisions
+            ),
+            ClientError::TimeOutOfBounds {
+                time,
+                lower_bound,
+                upper_bound,
+            } => write!(
+                f,
+                "The input value time {} does not lie in the time bounds ({}, {}].",
+                time, lower_bound, upper_bound
+            ),
+            ClientError::InconsistentStorage => write!(
+                f,
+                "The imported client storage does not form a consistent state of the client."
+            ),
+            ClientError::UnpublishedParameters => write!(
+                f,
+                "The parameters do not match the published parameters fingerprint."
+            ),
+            ClientError::ReusedPrfEvalPoints => write!(
+                f,
+                "Several readings would be randomized with the same PRF eval point."
+            ),
+            ClientError::SignedInputOutOfRange { value, input_bytes } => write!(
+                f,
+                "The signed input value {} does not fit into {} bytes.",
+                value, input_bytes
+            ),
+            ClientError::MerkleTreeFull { leaves } => write!(
+                f,
+                "All {} leaves of the Merkle tree have already been appended.",
+                leaves
+            ),
+            ClientError::TimeTooLarge { value, time_bytes } => write!(
+                f,
+                "The time {} cannot be represented with TIME_BYTES = {}.",
+                value, time_bytes
+            ),
+            ClientError::InputTooLarge { value, input_bytes } => write!(
+                f,
+                "The input value {} cannot be represented with INPUT_BYTES = {}.",
+                value, input_bytes
+            ),
         }
     }
 }
 
 impl std::error::Error for ClientError {}
+
+/// Class capturing server side errors:
+/// - Parameter mismatch: the client message was created with a different value for a parameter
+///   that client and server must agree on
+/// - Duplicate index: a submission of a client for an index that has already been aggregated
+/// - Missing blinded verifying key: a blinded submission was received, but the server has no
+///   verifying key for the blinded circuit
+/// - Missing clock: the client message echoes an expiry, but the server is built without the `std`
+///   feature and cannot read the system clock to check it
+/// - Expired server signature: the client message echoes a server signature whose signed expiry
+///   has passed
+/// - Weak seed source: every draw from the seed source yielded a weak (all-zero) server seed
+/// - Session mismatch: a compact client message references another `GenRand` session than the one
+///   it is verified with
+/// - PRF eval point count: the server was given more or fewer `prf_eval_points` than it needs to
+///   reconstruct the server randomness
+/// - Trailing bytes: the client message is longer than the encoding of the message it decodes to
+/// - LDP value out of range: the client message claims an LDP value that the mechanism cannot
+///   output, which is rejected before any signature or proof is verified
+/// - Uninformative gamma: the client message carries a public gamma of (effectively) 1, under which
+///   every LDP value is uniformly random and carries no information about the input value
+#[derive(Debug)]
+pub enum ServerError {
+    ParameterMismatch {
+        parameter: &'static str,
+        server: usize,
+        client: usize,
+    },
+    DuplicateIndex(usize),
+    MissingBlindedVerifyingKey,
+    MissingClock,
+    ExpiredServerSignature {
+        expiry: u64,
+        now: u64,
+    },
+    WeakSeedSource {
+        draws: usize,
+    },
+    SessionMismatch,
+    PrfEvalPointCount {
+        required: usize,
+        given: usize,
+    },
+    TrailingBytes {
+        expected: usize,
+        given: usize,
+    },
+    LdpValueOutOfRange {
+        ldp_value: u64,
+        min: u64,
+        max: u64,
+    },
+    UninformativeGamma,
+}
+
+impl Display for ServerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServerError::ParameterMismatch {
+                parameter,
+                server,
+                client,
+            } => write!(
+                f,
+                "Client and server disagree on {}: the server uses {}, but the client message was created with {}.",
+                parameter, server, client
+            ),
+            ServerError::DuplicateIndex(index) => write!(
+                f,
+                "A submission of this client for index {} has already been aggregated.",
+                index
+            ),
+            ServerError::MissingBlindedVerifyingKey => write!(
+                f,
+                "The server has no verifying key for blinded submissions."
+            ),
+            ServerError::MissingClock => write!(
+                f,
+                "The server signature has an expiry, but the server cannot read the system clock."
+            ),
+            ServerError::ExpiredServerSignature { expiry, now } => write!(
+                f,
+                "The server signature expired at {} (Unix time), but was presented at {}.",
+                expiry, now
+            ),
+            ServerError::WeakSeedSource { draws } => write!(
+                f,
+                "All {} server seeds drawn from the seed source consist of zeros only.",
+                draws
+            ),
+            ServerError::SessionMismatch => write!(
+                f,
+                "The client message does not belong to the given Generate Randomness session."
+            ),
+            ServerError::PrfEvalPointCount { required, given } => write!(
+                f,
+                "The server randomness is derived from exactly {} PRF eval points, but {} were given.",
+                required, given
+            ),
+            ServerError::TrailingBytes { expected, given } => write!(
+                f,
+                "The client message is encoded in {} bytes, but {} bytes were given.",
+                expected, given
+            ),
+            ServerError::LdpValueOutOfRange {
+                ldp_value,
+                min,
+                max,
+            } => write!(
+                f,
+                "The LDP value {} lies outside of the output range [{}, {}] of the mechanism.",
+                ldp_value, min, max
+            ),
+            ServerError::UninformativeGamma => write!(
+                f,
+                "The public gamma of the client message replaces every input value by a random value."
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ServerError {}
+
+/// Party that violated the protocol, from the point of view of the party that reports the error:
+/// - Local: the reporting party itself, e.g., it called the steps of the protocol out of order
+/// - Peer: the other party, e.g., it sent a message that does not verify
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Local,
+    Peer,
+}
+
+impl Display for Side {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Side::Local => write!(f, "local"),
+            Side::Peer => write!(f, "peer"),
+        }
+    }
+}
+
+/// Class capturing violations of the protocol flow between client and server (see `Side` for who
+/// is to blame):
+/// - Generate randomness incomplete: the `Randomize` step was started before the `GenRand` step
+///   was completed, i.e., before the server message was verified
+/// - Server signature invalid: the signature of the server message does not verify
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProtocolError {
+    GenerateRandomnessIncomplete,
+    ServerSignatureInvalid,
+}
+
+impl ProtocolError {
+    /// Report this violation as caused by the reporting party itself.
+    pub fn local(self) -> VldpError {
+        VldpError::Protocol(Side::Local, self)
+    }
+
+    /// Report this violation as caused by the other party.
+    pub fn peer(self) -> VldpError {
+        VldpError::Protocol(Side::Peer, self)
+    }
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProtocolError::GenerateRandomnessIncomplete => write!(
+                f,
+                "The Randomize step was started before the Generate Randomness step was completed."
+            ),
+            ProtocolError::ServerSignatureInvalid => {
+                write!(f, "The signature of the server message is invalid.")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+/// Error of all VLDP functions (see the `Error` alias), such that callers can match on the kind
+/// of failure:
+/// - Generic, client, and server errors: see `GenericError`, `ClientError`, and `ServerError`
+/// - Protocol error: a violation of the protocol flow, tagged with the side that caused it (see
+///   `ProtocolError` and `Side`)
+/// - Synthesis error: the constraints of a circuit could not be generated, or a proof could not be
+///   created or verified
+/// - Serialization error: a message, key, or proof could not be (de)serialized
+/// - Signature error: the hash function of a signature scheme failed during setup, signing, or
+///   verification (an invalid signature is not an error, but rejected)
+/// - Other: an error of a cryptographic primitive of arkworks (e.g., commitment, PRF, or Merkle
+///   tree) or of the environment (e.g., system clock or RNG). Without the `std` feature, only its
+///   message is kept.
+#[derive(Debug)]
+pub enum VldpError {
+    Generic(GenericError),
+    Client(ClientError),
+    Server(ServerError),
+    Protocol(Side, ProtocolError),
+    Synthesis(SynthesisError),
+    Serialization(SerializationError),
+    Signature(Box<dyn std::error::Error>),
+    #[cfg(feature = "std")]
+    Other(Box<dyn std::error::Error>),
+    #[cfg(not(feature = "std"))]
+    Other(String),
+}
+
+impl VldpError {
+    /// Wrap an error of a primitive or of the environment as `VldpError::Other`.
+    fn other<E: std::error::Error + 'static>(e: E) -> Self {
+        #[cfg(feature = "std")]
+        return VldpError::Other(Box::new(e));
+        #[cfg(not(feature = "std"))]
+        return VldpError::Other(e.to_string());
+    }
+}
+
+impl Display for VldpError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VldpError::Generic(e) => write!(f, "{}", e),
+            VldpError::Client(e) => write!(f, "{}", e),
+            VldpError::Server(e) => write!(f, "{}", e),
+            VldpError::Protocol(side, e) => write!(f, "Protocol violation ({}): {}", side, e),
+            VldpError::Synthesis(e) => write!(f, "A circuit could not be synthesized: {}", e),
+            VldpError::Serialization(e) => {
+                write!(f, "A value could not be (de)serialized: {}", e)
+            }
+            VldpError::Signature(e) => write!(f, "The signature scheme failed: {}", e),
+            VldpError::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for VldpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VldpError::Generic(e) => Some(e),
+            VldpError::Client(e) => Some(e),
+            VldpError::Server(e) => Some(e),
+            VldpError::Protocol(_, e) => Some(e),
+            VldpError::Synthesis(e) => Some(e),
+            VldpError::Serialization(e) => Some(e),
+            VldpError::Signature(e) => Some(e.as_ref()),
+            #[cfg(feature = "std")]
+            VldpError::Other(e) => Some(e.as_ref()),
+            #[cfg(not(feature = "std"))]
+            VldpError::Other(_) => None,
+        }
+    }
+}
+
+impl From<GenericError> for VldpError {
+    fn from(e: GenericError) -> Self {
+        VldpError::Generic(e)
+    }
+}
+
+impl From<ClientError> for VldpError {
+    fn from(e: ClientError) -> Self {
+        VldpError::Client(e)
+    }
+}
+
+impl From<ServerError> for VldpError {
+    fn from(e: ServerError) -> Self {
+        VldpError::Server(e)
+    }
+}
+
+impl From<SynthesisError> for VldpError {
+    fn from(e: SynthesisError) -> Self {
+        VldpError::Synthesis(e)
+    }
+}
+
+impl From<SerializationError> for VldpError {
+    fn from(e: SerializationError) -> Self {
+        VldpError::Serialization(e)
+    }
+}
+
+// errors of the arkworks primitives (CRH, commitment, Merkle tree)
+impl From<Box<dyn std::error::Error>> for VldpError {
+    fn from(e: Box<dyn std::error::Error>) -> Self {
+        #[cfg(feature = "std")]
+        return VldpError::Other(e);
+        #[cfg(not(feature = "std"))]
+        return VldpError::Other(e.to_string());
+    }
+}
+
+// errors of the arkworks PRFs
+impl From<ark_crypto_primitives::CryptoError> for VldpError {
+    fn from(e: ark_crypto_primitives::CryptoError) -> Self {
+        VldpError::other(e)
+    }
+}
+
+impl From<std::io::Error> for VldpError {
+    fn from(e: std::io::Error) -> Self {
+        VldpError::other(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::time::SystemTimeError> for VldpError {
+    fn from(e: std::time::SystemTimeError) -> Self {
+        VldpError::other(e)
+    }
+}
+
+impl From<rand::Error> for VldpError {
+    fn from(e: rand::Error) -> Self {
+        VldpError::other(e)
+    }
+}
+
+impl From<num_bigint::ParseBigIntError> for VldpError {
+    fn from(e: num_bigint::ParseBigIntError) -> Self {
+        VldpError::other(e)
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl From<prometheus::Error> for VldpError {
+    fn from(e: prometheus::Error) -> Self {
+        VldpError::other(e)
+    }
+}
+
+// errors that are only passed on as their message (e.g., between threads)
+impl From<String> for VldpError {
+    fn from(e: String) -> Self {
+        #[cfg(feature = "std")]
+        return VldpError::Other(e.into());
+        #[cfg(not(feature = "std"))]
+        return VldpError::Other(e);
+    }
+}
@@ -1,5 +1,6 @@
 //! All server and client functionalities for our 3 different VLDP schemes (Base, Expand, Shuffle)
 
+pub mod audit;
 pub mod circuits;
 pub mod client;
 pub mod config;
@@ -8,4 +9,8 @@ pub mod messages;
 pub mod prelude;
 mod primitives;
 pub mod run_random;
+pub mod scheme;
 pub mod server;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
+pub mod time;
@@ -2,10 +2,17 @@
 //! Base VLDP scheme.
 
 use crate::prelude::*;
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
+use std::fmt::{self, Display, Formatter};
 
 /// Message sent by client in the `GenRand` step of the paper.
-#[derive(CanonicalSerialize, CanonicalDeserialize)]
+///
+/// The message is deserialized by hand, like `VerifiableRandomizationMessageBase`, as the server
+/// deserializes it from untrusted bytes and `ark-serialize` panics on a `time` that is cut off.
+#[derive(CanonicalSerialize)]
 pub struct GenerateRandomnessMessageClientBase<Conf: Config, const TIME_BYTES: usize>
 where
     ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
@@ -16,6 +23,60 @@ where
     pub time: [u8; TIME_BYTES],
 }
 
+impl<Conf: Config, const TIME_BYTES: usize> Display
+    for GenerateRandomnessMessageClientBase<Conf, TIME_BYTES>
+where
+    ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Base gen-rand client: client_randomness_commitment=<{} bytes>, client_signature_public_key=<{} bytes>, time={:?}",
+            self.client_randomness_commitment.compressed_size(),
+            self.client_signature_public_key.compressed_size(),
+            self.time,
+        )
+    }
+}
+
+impl<Conf: Config, const TIME_BYTES: usize> Valid
+    for GenerateRandomnessMessageClientBase<Conf, TIME_BYTES>
+where
+    ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+{
+    fn check(&self) -> Result<(), SerializationError> {
+        self.client_randomness_commitment.check()?;
+        self.client_signature_public_key.check()
+    }
+}
+
+impl<Conf: Config, const TIME_BYTES: usize> CanonicalDeserialize
+    for GenerateRandomnessMessageClientBase<Conf, TIME_BYTES>
+where
+    ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+{
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let client_randomness_commitment =
+            CanonicalDeserialize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let client_signature_public_key =
+            CanonicalDeserialize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let mut time = [0; TIME_BYTES];
+        reader.read_exact(&mut time)?;
+        Ok(Self {
+            client_randomness_commitment,
+            client_signature_public_key,
+            time,
+        })
+    }
+}
+
 /// Message sent by the server in the `GenRand` step of the paper.
 #[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct GenerateRandomnessMessageServerBase<Conf: Config>
@@ -24,6 +85,27 @@ where
 {
     pub server_seed: PRFSchemeSeed<Conf>,
     pub server_signature: ServerSignatureSchemeSignature<Conf>,
+    /// Time (in seconds since the Unix epoch) from which on the server no longer accepts its
+    /// signature, if the server limits the validity of its seeds.
+    pub expiry: Option<u64>,
+    /// Nonce sampled freshly by the server for every `GenRand` exchange, which binds its signature
+    /// to this exchange.
+    pub server_nonce: [u8; 16],
+}
+
+impl<Conf: Config> Display for GenerateRandomnessMessageServerBase<Conf>
+where
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Base gen-rand server: server_seed=<{} bytes>, server_signature=<{} bytes>, expiry={:?}",
+            self.server_seed.compressed_size(),
+            self.server_signature.compressed_size(),
+            self.expiry,
+        )
+    }
 }
 
 /// Message that is to be signed by the server as part of the `GenRand` step of the paper.
@@ -36,6 +118,12 @@ where
     pub client_randomness_commitment: ClientCommitmentSchemeOutput<Conf>,
     pub client_signature_public_key: ClientSignatureSchemePublicKey<Conf>,
     pub server_seed: PRFSchemeSeed<Conf>,
+    /// Time (in seconds since the Unix epoch) from which on the server no longer accepts its
+    /// signature, if the server limits the validity of its seeds.
+    pub expiry: Option<u64>,
+    /// Nonce sampled freshly by the server for every `GenRand` exchange, which binds its signature
+    /// to this exchange.
+    pub server_nonce: [u8; 16],
 }
 
 impl<Conf: Config> GenerateRandomnessSignatureInputBase<Conf>
@@ -43,32 +131,217 @@ where
     ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
     ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
 {
-    /// Create signature input message from the received client message and the server seed.
+    /// Create signature input message from the received client message, the server seed, the
+    /// optional expiry of the server signature, and the server nonce.
     pub fn new<const TIME_BYTES: usize>(
         client_message: GenerateRandomnessMessageClientBase<Conf, TIME_BYTES>,
         server_seed: PRFSchemeSeed<Conf>,
+        expiry: Option<u64>,
+        server_nonce: [u8; 16],
     ) -> Self {
         Self {
             client_randomness_commitment: client_message.client_randomness_commitment,
             client_signature_public_key: client_message.client_signature_public_key,
             server_seed,
+            expiry,
+            server_nonce,
         }
     }
 }
 
-/// Message sent by the client as part of the `Randomize` step of the paper.
-#[derive(CanonicalSerialize, CanonicalDeserialize)]
-pub struct VerifiableRandomizationMessageBase<Conf: Config, const INPUT_BYTES: usize>
-where
+/// Flag in the encoded `gamma_bytes` of a `VerifiableRandomizationMessageBase` that marks that
+/// the client's public gamma follows. Without a public gamma, the encoding does not change.
+const PUBLIC_GAMMA_FLAG: u16 = 1 << 15;
+
+/// Message sent by the client as part of the `Randomize` step of the paper, for a submission of
+/// `D` input values. For `D = 1`, `ldp_value` is encoded exactly like a single `u64`.
+///
+/// The message is deserialized by hand, as `ark-serialize` panics on fixed-size arrays that are
+/// cut off by the end of the input, and the server deserializes this message from untrusted bytes.
+/// It is serialized by hand as well, as a public `gamma` is only encoded if present: its
+/// `gamma_bytes` bytes follow `gamma_bytes`, which is then marked with `PUBLIC_GAMMA_FLAG`.
+pub struct VerifiableRandomizationMessageBase<
+    Conf: Config,
+    const INPUT_BYTES: usize,
+    const D: usize = 1,
+> where
     Proof<Conf>: CanonicalDeserialize,
     ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
     ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
     ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
 {
+    /// `GAMMA_BYTES` the client was instantiated with, such that the server can detect a mismatch
+    /// before it verifies the proof.
+    pub gamma_bytes: u16,
+    /// Byte encoding of the client's gamma (of `gamma_bytes` bytes), if gamma is a public input
+    /// of the circuit (see `ParametersBase::with_public_gamma`).
+    pub gamma: Option<Vec<u8>>,
     pub client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
     pub client_randomness_commitment: ClientCommitmentSchemeOutput<Conf>,
     pub server_seed: PRFSchemeSeed<Conf>,
     pub server_signature: ServerSignatureSchemeSignature<Conf>,
+    /// Expiry of the server signature, as signed by the server in the `GenRand` step.
+    pub expiry: Option<u64>,
+    /// Nonce of the server signature, as signed by the server in the `GenRand` step.
+    pub server_nonce: [u8; 16],
     pub proof: Proof<Conf>,
-    pub ldp_value: u64,
+    pub ldp_value: [u64; D],
+}
+
+impl<Conf: Config, const INPUT_BYTES: usize, const D: usize> Display
+    for VerifiableRandomizationMessageBase<Conf, INPUT_BYTES, D>
+where
+    Proof<Conf>: CanonicalDeserialize,
+    ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Base randomize: ldp_value={}, proof=<{} bytes>, client_sig_pk=<{} bytes>, client_randomness_commitment=<{} bytes>, server_seed=<{} bytes>, server_signature=<{} bytes>",
+            self.ldp_value.map(|value| value.to_string()).join(","),
+            self.proof.compressed_size(),
+            self.client_sig_pk.compressed_size(),
+            self.client_randomness_commitment.compressed_size(),
+            self.server_seed.compressed_size(),
+            self.server_signature.compressed_size(),
+        )
+    }
+}
+
+impl<Conf: Config, const INPUT_BYTES: usize, const D: usize> CanonicalSerialize
+    for VerifiableRandomizationMessageBase<Conf, INPUT_BYTES, D>
+where
+    Proof<Conf>: CanonicalDeserialize,
+    ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+{
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        match &self.gamma {
+            Some(gamma) => {
+                if gamma.len() != self.gamma_bytes as usize
+                    || self.gamma_bytes & PUBLIC_GAMMA_FLAG != 0
+                {
+                    Err(SerializationError::InvalidData)?
+                }
+                (self.gamma_bytes | PUBLIC_GAMMA_FLAG)
+                    .serialize_with_mode(&mut writer, compress)?;
+                writer.write_all(gamma)?;
+            }
+            None => self
+                .gamma_bytes
+                .serialize_with_mode(&mut writer, compress)?,
+        }
+        self.client_sig_pk
+            .serialize_with_mode(&mut writer, compress)?;
+        self.client_randomness_commitment
+            .serialize_with_mode(&mut writer, compress)?;
+        self.server_seed
+            .serialize_with_mode(&mut writer, compress)?;
+        self.server_signature
+            .serialize_with_mode(&mut writer, compress)?;
+        self.expiry.serialize_with_mode(&mut writer, compress)?;
+        self.server_nonce
+            .serialize_with_mode(&mut writer, compress)?;
+        self.proof.serialize_with_mode(&mut writer, compress)?;
+        self.ldp_value.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.gamma_bytes.serialized_size(compress)
+            + self.gamma.as_ref().map_or(0, Vec::len)
+            + self.client_sig_pk.serialized_size(compress)
+            + self.client_randomness_commitment.serialized_size(compress)
+            + self.server_seed.serialized_size(compress)
+            + self.server_signature.serialized_size(compress)
+            + self.expiry.serialized_size(compress)
+            + self.server_nonce.serialized_size(compress)
+            + self.proof.serialized_size(compress)
+            + self.ldp_value.serialized_size(compress)
+    }
+}
+
+impl<Conf: Config, const INPUT_BYTES: usize, const D: usize> Valid
+    for VerifiableRandomizationMessageBase<Conf, INPUT_BYTES, D>
+where
+    Proof<Conf>: CanonicalDeserialize,
+    ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+{
+    fn check(&self) -> Result<(), SerializationError> {
+        self.gamma.check()?;
+        self.client_sig_pk.check()?;
+        self.client_randomness_commitment.check()?;
+        self.server_seed.check()?;
+        self.server_signature.check()?;
+        self.proof.check()
+    }
+}
+
+impl<Conf: Config, const INPUT_BYTES: usize, const D: usize> CanonicalDeserialize
+    for VerifiableRandomizationMessageBase<Conf, INPUT_BYTES, D>
+where
+    Proof<Conf>: CanonicalDeserialize,
+    ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+{
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let gamma_bytes = u16::deserialize_with_mode(&mut reader, compress, validate)?;
+        let (gamma_bytes, gamma) = if gamma_bytes & PUBLIC_GAMMA_FLAG != 0 {
+            let gamma_bytes = gamma_bytes & !PUBLIC_GAMMA_FLAG;
+            let mut gamma = vec![0; gamma_bytes as usize];
+            reader.read_exact(&mut gamma)?;
+            (gamma_bytes, Some(gamma))
+        } else {
+            (gamma_bytes, None)
+        };
+        let client_sig_pk =
+            CanonicalDeserialize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let client_randomness_commitment =
+            CanonicalDeserialize::deserialize_with_mode(&mut reader, compress, validate)?;
+
+        // the seed has a fixed size, so its bytes are read completely before they are decoded
+        let mut server_seed = vec![0; PRFSchemeSeed::<Conf>::default().serialized_size(compress)];
+        reader.read_exact(&mut server_seed)?;
+        let server_seed = PRFSchemeSeed::<Conf>::deserialize_with_mode(
+            server_seed.as_slice(),
+            compress,
+            validate,
+        )?;
+
+        let server_signature =
+            CanonicalDeserialize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let expiry = Option::deserialize_with_mode(&mut reader, compress, validate)?;
+        let mut server_nonce = [0; 16];
+        reader.read_exact(&mut server_nonce)?;
+        let proof = CanonicalDeserialize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let mut ldp_value = [0; D];
+        for value in &mut ldp_value {
+            *value = u64::deserialize_with_mode(&mut reader, compress, validate)?;
+        }
+        Ok(Self {
+            gamma_bytes,
+            gamma,
+            client_sig_pk,
+            client_randomness_commitment,
+            server_seed,
+            server_signature,
+            expiry,
+            server_nonce,
+            proof,
+            ldp_value,
+        })
+    }
 }
@@ -1,8 +1,14 @@
 //! Structs and basic logic for all messages that are sent between clients and server in the
 //! Expand VLDP scheme.
 
+use super::read_bytes;
 use crate::prelude::*;
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+};
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use std::fmt::{self, Display, Formatter};
 
 /// Message sent by client in the `GenRand` step of the paper.
 #[derive(CanonicalSerialize, CanonicalDeserialize)]
@@ -14,6 +20,20 @@ where
     pub client_signature_public_key: ClientSignatureSchemePublicKey<Conf>,
 }
 
+impl<Conf: Config> Display for GenerateRandomnessMessageClientExpand<Conf>
+where
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Expand gen-rand client: client_merkle_tree_root=<{} bytes>, client_signature_public_key=<{} bytes>",
+            self.client_merkle_tree_root.compressed_size(),
+            self.client_signature_public_key.compressed_size(),
+        )
+    }
+}
+
 /// Message sent by the server in the `GenRand` step of the paper.
 #[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct GenerateRandomnessMessageServerExpand<Conf: Config>
@@ -22,6 +42,27 @@ where
 {
     pub server_seed: PRFSchemeSeed<Conf>,
     pub server_signature: ServerSignatureSchemeSignature<Conf>,
+    /// Time (in seconds since the Unix epoch) from which on the server no longer accepts its
+    /// signature, if the server limits the validity of its seeds.
+    pub expiry: Option<u64>,
+    /// Nonce sampled freshly by the server for every `GenRand` exchange, which binds its signature
+    /// to this exchange.
+    pub server_nonce: [u8; 16],
+}
+
+impl<Conf: Config> Display for GenerateRandomnessMessageServerExpand<Conf>
+where
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Expand gen-rand server: server_seed=<{} bytes>, server_signature=<{} bytes>, expiry={:?}",
+            self.server_seed.compressed_size(),
+            self.server_signature.compressed_size(),
+            self.expiry,
+        )
+    }
 }
 
 /// Message that is to be signed by the server as part of the `GenRand` step of the paper.
@@ -33,21 +74,32 @@ where
     pub client_merkle_tree_root: ClientMerkleTreeRoot<Conf>,
     pub client_signature_public_key: ClientSignatureSchemePublicKey<Conf>,
     pub server_seed: PRFSchemeSeed<Conf>,
+    /// Time (in seconds since the Unix epoch) from which on the server no longer accepts its
+    /// signature, if the server limits the validity of its seeds.
+    pub expiry: Option<u64>,
+    /// Nonce sampled freshly by the server for every `GenRand` exchange, which binds its signature
+    /// to this exchange.
+    pub server_nonce: [u8; 16],
 }
 
 impl<Conf: Config> GenerateRandomnessSignatureInputExpand<Conf>
 where
     ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
 {
-    /// Create signature input message from the received client message and the server seed.
+    /// Create signature input message from the received client message, the server seed, the
+    /// optional expiry of the server signature, and the server nonce.
     pub fn new(
         client_message: GenerateRandomnessMessageClientExpand<Conf>,
         server_seed: PRFSchemeSeed<Conf>,
+        expiry: Option<u64>,
+        server_nonce: [u8; 16],
     ) -> Self {
         Self {
             client_merkle_tree_root: client_message.client_merkle_tree_root,
             client_signature_public_key: client_message.client_signature_public_key,
             server_seed,
+            expiry,
+            server_nonce,
         }
     }
 }
@@ -60,10 +112,240 @@ where
     ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
     ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
 {
+    /// `GAMMA_BYTES` the client was instantiated with, such that the server can detect a mismatch
+    /// before it verifies the proof.
+    pub gamma_bytes: u16,
+    pub client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
+    pub client_merkle_tree_root: ClientMerkleTreeRoot<Conf>,
+    pub server_seed: PRFSchemeSeed<Conf>,
+    pub server_signature: ServerSignatureSchemeSignature<Conf>,
+    /// Expiry of the server signature, as signed by the server in the `GenRand` step.
+    pub expiry: Option<u64>,
+    /// Nonce of the server signature, as signed by the server in the `GenRand` step.
+    pub server_nonce: [u8; 16],
+    pub proof: Proof<Conf>,
+    pub ldp_value: u64,
+}
+
+impl<Conf: Config, const INPUT_BYTES: usize> Display
+    for VerifiableRandomizationMessageExpand<Conf, INPUT_BYTES>
+where
+    Proof<Conf>: CanonicalDeserialize,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Expand randomize: ldp_value={}, proof=<{} bytes>, client_sig_pk=<{} bytes>, client_merkle_tree_root=<{} bytes>, server_seed=<{} bytes>, server_signature=<{} bytes>",
+            self.ldp_value,
+            self.proof.compressed_size(),
+            self.client_sig_pk.compressed_size(),
+            self.client_merkle_tree_root.compressed_size(),
+            self.server_seed.compressed_size(),
+            self.server_signature.compressed_size(),
+        )
+    }
+}
+
+impl<Conf: Config, const INPUT_BYTES: usize> VerifiableRandomizationMessageExpand<Conf, INPUT_BYTES>
+where
+    Proof<Conf>: CanonicalDeserialize,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+{
+    /// Drop all fields that the server already knows from the `GenRand` session of this message
+    /// (see `SessionExpand::expand` for the reverse).
+    pub fn compact(self) -> CompactRandomizationMessageExpand<Conf> {
+        CompactRandomizationMessageExpand {
+            gamma_bytes: self.gamma_bytes,
+            session_id: self.server_seed,
+            proof: self.proof,
+            ldp_value: self.ldp_value,
+        }
+    }
+}
+
+/// State of a `GenRand` exchange that the server keeps, such that clients can omit everything
+/// the server already knows from the `Randomize` step (see `CompactRandomizationMessageExpand`).
+/// The server seed is sampled freshly for every exchange, so it identifies the session.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SessionExpand<Conf: Config>
+where
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+{
+    pub client_merkle_tree_root: ClientMerkleTreeRoot<Conf>,
+    pub client_signature_public_key: ClientSignatureSchemePublicKey<Conf>,
+    pub server_seed: PRFSchemeSeed<Conf>,
+    pub server_signature: ServerSignatureSchemeSignature<Conf>,
+    pub expiry: Option<u64>,
+    pub server_nonce: [u8; 16],
+}
+
+impl<Conf: Config> SessionExpand<Conf>
+where
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+{
+    /// Identifier of this session, as referenced by compact client messages.
+    pub fn session_id(&self) -> PRFSchemeSeed<Conf> {
+        self.server_seed
+    }
+
+    /// Reconstruct the full client message from a compact one of this session. Fails with a
+    /// `SessionMismatch` if the compact message references another session.
+    pub fn expand<const INPUT_BYTES: usize>(
+        &self,
+        compact_message: CompactRandomizationMessageExpand<Conf>,
+    ) -> Result<VerifiableRandomizationMessageExpand<Conf, INPUT_BYTES>, Error>
+    where
+        Proof<Conf>: CanonicalDeserialize,
+    {
+        if compact_message.session_id != self.session_id() {
+            Err(ServerError::SessionMismatch)?
+        }
+        Ok(VerifiableRandomizationMessageExpand {
+            gamma_bytes: compact_message.gamma_bytes,
+            client_sig_pk: self.client_signature_public_key.clone(),
+            client_merkle_tree_root: self.client_merkle_tree_root.clone(),
+            server_seed: self.server_seed,
+            server_signature: self.server_signature.clone(),
+            expiry: self.expiry,
+            server_nonce: self.server_nonce,
+            proof: compact_message.proof,
+            ldp_value: compact_message.ldp_value,
+        })
+    }
+}
+
+/// Message sent by the client as part of the `Randomize` step to a server that keeps the state of
+/// the `GenRand` session (see `SessionExpand`). It only references the session instead of
+/// repeating the client public key, merkle tree root, server seed, and server signature.
+#[derive(CanonicalSerialize)]
+pub struct CompactRandomizationMessageExpand<Conf: Config>
+where
+    Proof<Conf>: CanonicalDeserialize,
+{
+    /// `GAMMA_BYTES` the client was instantiated with, such that the server can detect a mismatch
+    /// before it verifies the proof.
+    pub gamma_bytes: u16,
+    pub session_id: PRFSchemeSeed<Conf>,
+    pub proof: Proof<Conf>,
+    pub ldp_value: u64,
+}
+
+impl<Conf: Config> Valid for CompactRandomizationMessageExpand<Conf>
+where
+    Proof<Conf>: CanonicalDeserialize,
+{
+    fn check(&self) -> Result<(), SerializationError> {
+        self.proof.check()
+    }
+}
+
+impl<Conf: Config> CanonicalDeserialize for CompactRandomizationMessageExpand<Conf>
+where
+    Proof<Conf>: CanonicalDeserialize,
+{
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self {
+            gamma_bytes: u16::deserialize_with_mode(&mut reader, compress, validate)?,
+            session_id: read_bytes(&mut reader)?,
+            proof: Proof::<Conf>::deserialize_with_mode(&mut reader, compress, validate)?,
+            ldp_value: u64::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+/// Proof of a single submission, bundled with all public inputs it is verified against, such that
+/// a third party can verify it with only the verifying key (see `CircuitExpand::verify_bundle`).
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct VerifiableBundleExpand<
+    Conf: Config,
+    const TIME_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+> where
+    Proof<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+{
+    pub proof: Proof<Conf>,
+    pub ldp_value: u64,
+    pub time_lower_bound: [u8; TIME_BYTES],
+    pub time_upper_bound: [u8; TIME_BYTES],
+    pub client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
+    pub client_merkle_tree_root: ClientMerkleTreeRoot<Conf>,
+    pub index: usize,
+    pub server_randomness: [u8; RANDOMNESS_BYTES],
+}
+
+/// Public inputs a valid submission was verified against (see
+/// `ServerExpand::verifiable_randomization_verify_context`), such that the collector can record
+/// exactly what was proven for each submission.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct VerifiedExpandContext<Conf: Config, const TIME_BYTES: usize> {
+    pub root: ClientMerkleTreeRoot<Conf>,
+    pub index: usize,
+    pub ldp_value: u64,
+    pub time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+}
+
+/// Message sent by the client as part of the `Randomize` step in the blinded mode, which commits
+/// to the LDP value instead of revealing it. The value is only revealed later with a
+/// `LdpValueRevealMessageExpand`.
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+pub struct BlindedRandomizationMessageExpand<Conf: Config, const INPUT_BYTES: usize>
+where
+    Proof<Conf>: CanonicalDeserialize,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+{
+    /// `GAMMA_BYTES` the client was instantiated with, such that the server can detect a mismatch
+    /// before it verifies the proof.
+    pub gamma_bytes: u16,
     pub client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
     pub client_merkle_tree_root: ClientMerkleTreeRoot<Conf>,
     pub server_seed: PRFSchemeSeed<Conf>,
     pub server_signature: ServerSignatureSchemeSignature<Conf>,
+    /// Expiry of the server signature, as signed by the server in the `GenRand` step.
+    pub expiry: Option<u64>,
+    /// Nonce of the server signature, as signed by the server in the `GenRand` step.
+    pub server_nonce: [u8; 16],
     pub proof: Proof<Conf>,
+    pub ldp_value_commitment: ClientCommitmentSchemeOutput<Conf>,
+}
+
+/// Message sent by the client to reveal the LDP value of a blinded `Randomize` step, i.e., the
+/// opening of its `ldp_value_commitment`. The commitment randomness is derived from a seed, as it
+/// cannot be deserialized for every commitment scheme.
+#[derive(Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LdpValueRevealMessageExpand {
     pub ldp_value: u64,
+    pub ldp_value_commitment_seed: [u8; 32],
+}
+
+impl LdpValueRevealMessageExpand {
+    /// Commitment randomness that is derived from `ldp_value_commitment_seed`.
+    pub fn commitment_randomness<Conf: Config>(&self) -> ClientCommitmentSchemeRandomness<Conf> {
+        ClientCommitmentSchemeRandomness::<Conf>::rand(&mut ChaChaRng::from_seed(
+            self.ldp_value_commitment_seed,
+        ))
+    }
+
+    /// Commitment to the LDP value (as 8 little-endian bytes) that this message opens.
+    pub fn commitment<Conf: Config>(
+        &self,
+        parameters: &ClientCommitmentSchemeParameters<Conf>,
+    ) -> Result<ClientCommitmentSchemeOutput<Conf>, Error> {
+        Ok(Conf::ClientCommitmentScheme::commit(
+            parameters,
+            &self.ldp_value.to_le_bytes(),
+            &self.commitment_randomness::<Conf>(),
+        )?)
+    }
 }
@@ -4,3 +4,489 @@
 pub mod base;
 pub mod expand;
 pub mod shuffle;
+
+use crate::prelude::*;
+use ark_serialize::{CanonicalSerialize, Read, SerializationError};
+
+/// Domain tag that is prepended to every input value signature message.
+pub const INPUT_VALUE_SIGNATURE_DOMAIN_TAG: [u8; 8] = *b"VLDP-INP";
+
+/// Message that has to be signed (by the trusted environment) for an input value and the time it
+/// was created. This message is checked inside the ZKP circuits of all schemes.
+///
+/// The encoding is `tag || len(input_value) || input_value || len(time) || time` with lengths as
+/// 2-byte little-endian integers, so value/time pairs of different sizes can never collide.
+pub struct InputValueSignatureInput<const INPUT_BYTES: usize, const TIME_BYTES: usize> {
+    pub input_value: [u8; INPUT_BYTES],
+    pub input_value_time: [u8; TIME_BYTES],
+}
+
+impl<const INPUT_BYTES: usize, const TIME_BYTES: usize>
+    InputValueSignatureInput<INPUT_BYTES, TIME_BYTES>
+{
+    /// Encode this message into the bytes that are to be signed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        assert!(INPUT_BYTES <= u16::MAX as usize && TIME_BYTES <= u16::MAX as usize);
+        let mut bytes = INPUT_VALUE_SIGNATURE_DOMAIN_TAG.to_vec();
+        bytes.extend_from_slice(&(INPUT_BYTES as u16).to_le_bytes());
+        bytes.extend_from_slice(&self.input_value);
+        bytes.extend_from_slice(&(TIME_BYTES as u16).to_le_bytes());
+        bytes.extend_from_slice(&self.input_value_time);
+        bytes
+    }
+
+    /// Bytes that are to be signed for the given `client_input_hash_scheme` parameters (see
+    /// `with_hashed_input` of the scheme parameters): the encoded message itself if there are none,
+    /// or else its hash (serialized uncompressed).
+    ///
+    /// The hash is computed in blocks of `CLIENT_INPUT_HASH_BLOCK_BYTES` bytes: the first block of
+    /// the message is hashed directly, and every further block is hashed together with the digest
+    /// of the blocks before it (prepended). A message that fits into one block is therefore hashed
+    /// with a single evaluation, and messages of any length can be hashed.
+    pub fn signed_bytes<Conf: Config>(
+        &self,
+        client_input_hash_scheme: Option<&ClientInputHashParameters<Conf>>,
+    ) -> Result<Vec<u8>, Error> {
+        match client_input_hash_scheme {
+            None => Ok(self.to_bytes()),
+            Some(parameters) => {
+                let message = self.to_bytes();
+                let block_bytes = Conf::CLIENT_INPUT_HASH_BLOCK_BYTES;
+                let (block, mut rest) = message.split_at(message.len().min(block_bytes));
+                let mut digest = vec![];
+                Conf::ClientInputHash::evaluate(parameters, block)?
+                    .serialize_uncompressed(&mut digest)?;
+                while !rest.is_empty() {
+                    if digest.len() >= block_bytes {
+                        Err(GenericError::InvalidSchemeParameters(format!(
+                            "a digest of {} bytes leaves no room in an input hash block of {} bytes",
+                            digest.len(),
+                            block_bytes
+                        )))?
+                    }
+                    let (block, remaining) =
+                        rest.split_at(rest.len().min(block_bytes - digest.len()));
+                    let mut chained_block = std::mem::take(&mut digest);
+                    chained_block.extend_from_slice(block);
+                    Conf::ClientInputHash::evaluate(parameters, chained_block.as_slice())?
+                        .serialize_uncompressed(&mut digest)?;
+                    rest = remaining;
+                }
+                Ok(digest)
+            }
+        }
+    }
+}
+
+/// Number of `prf_eval_points` that are needed to derive `randomness_bytes` bytes of server
+/// randomness, i.e., one PRF evaluation per (possibly partial) chunk of `PRF_OUTPUT_BYTES` bytes.
+pub fn required_prf_eval_points<Conf: Config>(randomness_bytes: usize) -> usize {
+    randomness_bytes.div_ceil(Conf::PRF_OUTPUT_BYTES)
+}
+
+/// Deterministically derive `n` public `prf_eval_points` (s in the paper) from a public seed, such
+/// that client and server only have to agree on the seed (Expand and Shuffle schemes).
+/// Point `i` is the PRF evaluation under `public_seed` at the little-endian encoding of `i`, so all
+/// points are distinct except with negligible probability.
+pub fn derive_prf_eval_points<Conf: Config>(
+    public_seed: PRFSchemeSeed<Conf>,
+    n: usize,
+) -> Result<Vec<PRFSchemeInput<Conf>>, Error> {
+    (0..n)
+        .map(|index| {
+            let mut eval_point = [0; 32];
+            eval_point[0..8].copy_from_slice(&(index as u64).to_le_bytes());
+            let output = Conf::PRFScheme::evaluate(&public_seed, &eval_point)?;
+            let mut prf_eval_point = [0; 32];
+            prf_eval_point.copy_from_slice(&output.as_ref()[0..32]);
+            Ok(prf_eval_point)
+        })
+        .collect()
+}
+
+/// Whether a server seed is too weak to be used, i.e., consists of zeros only. Such a seed
+/// indicates a broken RNG on the server and leaves the randomness of the client to its own seed.
+pub fn is_weak_seed(seed: &[u8]) -> bool {
+    seed.iter().all(|byte| *byte == 0)
+}
+
+/// Read a byte array of a message with `read_exact`, such that a truncated message is rejected
+/// with an error (the `CanonicalDeserialize` implementation of arrays panics on it instead).
+pub(crate) fn read_bytes<R: Read, const N: usize>(
+    mut reader: R,
+) -> Result<[u8; N], SerializationError> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::base::*;
+    use super::expand::*;
+    use super::shuffle::*;
+    use super::{derive_prf_eval_points, InputValueSignatureInput};
+    use crate::config::BasicConfig;
+    use crate::prelude::*;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+    use astro_float::BigFloat;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+    use std::collections::HashSet;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    const INPUT_BYTES: usize = 8;
+    const GAMMA_BYTES: usize = 8;
+
+    /// Deterministically derived message components that are shared by all randomize messages.
+    struct Components {
+        client_sig_pk: ClientSignatureSchemePublicKey<Conf>,
+        client_sig_sk: ClientSignatureSchemeSecretKey<Conf>,
+        client_randomness_commitment: ClientCommitmentSchemeOutput<Conf>,
+        client_merkle_tree_root: ClientMerkleTreeRoot<Conf>,
+        server_seed: PRFSchemeSeed<Conf>,
+        server_signature: ServerSignatureSchemeSignature<Conf>,
+    }
+
+    fn components() -> Components {
+        let rng = &mut ChaChaRng::from_seed([7; 32]);
+        let parameters =
+            ParametersExpand::<Conf, GAMMA_BYTES>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let (_, server_sig_sk) = <Conf as Config>::ServerSignatureScheme::keygen(
+            &parameters.server_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let leaves = (0..2_u8)
+            .map(|byte| {
+                <Conf as Config>::ClientCommitmentScheme::commit(
+                    &parameters.client_commitment_scheme,
+                    &[byte; 16],
+                    &ClientCommitmentSchemeRandomness::<Conf>::rand(rng),
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let client_merkle_tree_root = ClientMerkleTree::<Conf>::new(
+            &parameters.client_merkle_tree_scheme.leaf_crh_params,
+            &parameters.client_merkle_tree_scheme.two_to_one_crh_params,
+            leaves.clone(),
+        )
+        .unwrap()
+        .root();
+        let server_seed = [3; 32];
+        let server_signature = <Conf as Config>::ServerSignatureScheme::sign(
+            &parameters.server_signature_scheme,
+            &server_sig_sk,
+            &server_seed,
+            rng,
+        )
+        .unwrap();
+        Components {
+            client_sig_pk,
+            client_sig_sk,
+            client_randomness_commitment: leaves[0],
+            client_merkle_tree_root,
+            server_seed,
+            server_signature,
+        }
+    }
+
+    fn base_message() -> VerifiableRandomizationMessageBase<Conf, INPUT_BYTES> {
+        let components = components();
+        VerifiableRandomizationMessageBase {
+            gamma_bytes: GAMMA_BYTES as u16,
+            gamma: None,
+            client_sig_pk: components.client_sig_pk,
+            client_randomness_commitment: components.client_randomness_commitment,
+            server_seed: components.server_seed,
+            server_signature: components.server_signature,
+            expiry: None,
+            server_nonce: [7; 16],
+            proof: Proof::<Conf>::default(),
+            ldp_value: [5],
+        }
+    }
+
+    fn expand_message() -> VerifiableRandomizationMessageExpand<Conf, INPUT_BYTES> {
+        let components = components();
+        VerifiableRandomizationMessageExpand {
+            gamma_bytes: GAMMA_BYTES as u16,
+            client_sig_pk: components.client_sig_pk,
+            client_merkle_tree_root: components.client_merkle_tree_root,
+            server_seed: components.server_seed,
+            server_signature: components.server_signature,
+            expiry: None,
+            server_nonce: [7; 16],
+            proof: Proof::<Conf>::default(),
+            ldp_value: 5,
+        }
+    }
+
+    fn shuffle_message() -> VerifiableRandomizationMessageShuffle<Conf, INPUT_BYTES> {
+        VerifiableRandomizationMessageShuffle {
+            gamma_bytes: GAMMA_BYTES as u16,
+            proof: Proof::<Conf>::default(),
+            ldp_value: 5,
+        }
+    }
+
+    fn to_hex<T: CanonicalSerialize>(message: &T) -> String {
+        let mut bytes = vec![];
+        message.serialize_compressed(&mut bytes).unwrap();
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    /// Assert that `value` deserializes to a value that serializes to the same bytes again, in
+    /// both compressed and uncompressed mode. (Most wire types do not implement `PartialEq`, so
+    /// equality is checked on the canonical encoding.)
+    fn assert_round_trip<T: CanonicalSerialize + CanonicalDeserialize>(value: &T) {
+        for compress in [Compress::Yes, Compress::No] {
+            let mut bytes = vec![];
+            value.serialize_with_mode(&mut bytes, compress).unwrap();
+            assert_eq!(bytes.len(), value.serialized_size(compress));
+            let decoded = T::deserialize_with_mode(bytes.as_slice(), compress, Validate::Yes)
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "{} does not round-trip ({}): {}",
+                        std::any::type_name::<T>(),
+                        match compress {
+                            Compress::Yes => "compressed",
+                            Compress::No => "uncompressed",
+                        },
+                        e
+                    )
+                });
+            let mut decoded_bytes = vec![];
+            decoded
+                .serialize_with_mode(&mut decoded_bytes, compress)
+                .unwrap();
+            assert_eq!(decoded_bytes, bytes, "{}", std::any::type_name::<T>());
+        }
+    }
+
+    #[test]
+    fn wire_types_round_trip() {
+        let components = components();
+
+        // keys and signatures
+        assert_round_trip(&components.client_sig_pk);
+        assert_round_trip(&components.client_sig_sk);
+        assert_round_trip(&components.server_signature);
+        assert_round_trip(&components.client_randomness_commitment);
+        assert_round_trip(&components.client_merkle_tree_root);
+        assert_round_trip(&Proof::<Conf>::default());
+
+        // Base
+        assert_round_trip(&GenerateRandomnessMessageClientBase::<Conf, 2> {
+            client_randomness_commitment: components.client_randomness_commitment,
+            client_signature_public_key: components.client_sig_pk,
+            time: [1, 2],
+        });
+        assert_round_trip(&GenerateRandomnessMessageServerBase::<Conf> {
+            server_seed: components.server_seed,
+            server_signature: components.server_signature.clone(),
+            expiry: Some(1_700_000_000),
+            server_nonce: [7; 16],
+        });
+        assert_round_trip(&GenerateRandomnessSignatureInputBase::<Conf> {
+            client_randomness_commitment: components.client_randomness_commitment,
+            client_signature_public_key: components.client_sig_pk,
+            server_seed: components.server_seed,
+            expiry: None,
+            server_nonce: [7; 16],
+        });
+        let mut message = base_message();
+        message.gamma = Some(vec![1; GAMMA_BYTES]);
+        message.expiry = Some(1_700_000_000);
+        assert_round_trip(&message);
+
+        // Expand
+        assert_round_trip(&GenerateRandomnessMessageClientExpand::<Conf> {
+            client_merkle_tree_root: components.client_merkle_tree_root,
+            client_signature_public_key: components.client_sig_pk,
+        });
+        assert_round_trip(&GenerateRandomnessMessageServerExpand::<Conf> {
+            server_seed: components.server_seed,
+            server_signature: components.server_signature.clone(),
+            expiry: Some(1_700_000_000),
+            server_nonce: [7; 16],
+        });
+        assert_round_trip(&GenerateRandomnessSignatureInputExpand::<Conf> {
+            client_merkle_tree_root: components.client_merkle_tree_root,
+            client_signature_public_key: components.client_sig_pk,
+            server_seed: components.server_seed,
+            expiry: None,
+            server_nonce: [7; 16],
+        });
+        assert_round_trip(&expand_message());
+        assert_round_trip(&VerifiableBundleExpand::<Conf, 1, 16> {
+            proof: Proof::<Conf>::default(),
+            ldp_value: 5,
+            time_lower_bound: [1],
+            time_upper_bound: [2],
+            client_sig_pk: components.client_sig_pk,
+            client_merkle_tree_root: components.client_merkle_tree_root,
+            index: 3,
+            server_randomness: [4; 16],
+        });
+        assert_round_trip(&BlindedRandomizationMessageExpand::<Conf, INPUT_BYTES> {
+            gamma_bytes: GAMMA_BYTES as u16,
+            client_sig_pk: components.client_sig_pk,
+            client_merkle_tree_root: components.client_merkle_tree_root,
+            server_seed: components.server_seed,
+            server_signature: components.server_signature.clone(),
+            expiry: None,
+            server_nonce: [7; 16],
+            proof: Proof::<Conf>::default(),
+            ldp_value_commitment: components.client_randomness_commitment,
+        });
+        assert_round_trip(&LdpValueRevealMessageExpand {
+            ldp_value: 5,
+            ldp_value_commitment_seed: [6; 32],
+        });
+
+        // Shuffle
+        assert_round_trip(&GenerateRandomnessMessageClientShuffle::<Conf> {
+            client_seed_commitment: components.client_randomness_commitment,
+            client_signature_public_key: components.client_sig_pk,
+        });
+        assert_round_trip(&GenerateRandomnessMessageServerShuffle::<Conf> {
+            server_seed: components.server_seed,
+            server_signature: components.server_signature,
+            server_nonce: [7; 16],
+        });
+        assert_round_trip(&GenerateRandomnessSignatureInputShuffle::<Conf> {
+            client_seed_commitment: components.client_randomness_commitment,
+            client_signature_public_key: components.client_sig_pk,
+            server_seed: components.server_seed,
+            server_nonce: [7; 16],
+        });
+        assert_round_trip(&shuffle_message());
+    }
+
+    #[test]
+    fn base_randomize_message_golden_bytes() {
+        assert_eq!(to_hex(&base_message()), BASE_GOLDEN);
+
+        // a public gamma is flagged in the gamma bytes and follows them
+        let mut message = base_message();
+        message.gamma = Some(vec![0xab; GAMMA_BYTES]);
+        assert_eq!(
+            to_hex(&message),
+            format!("0880{}{}", "ab".repeat(GAMMA_BYTES), &BASE_GOLDEN[4..])
+        );
+    }
+
+    #[test]
+    fn expand_randomize_message_golden_bytes() {
+        assert_eq!(to_hex(&expand_message()), EXPAND_GOLDEN);
+    }
+
+    #[test]
+    fn shuffle_randomize_message_golden_bytes() {
+        assert_eq!(to_hex(&shuffle_message()), SHUFFLE_GOLDEN);
+    }
+
+    #[test]
+    fn randomize_message_display() {
+        let proof_size = format!(
+            "proof=<{} bytes>",
+            Proof::<Conf>::default().compressed_size()
+        );
+        for summary in [
+            base_message().to_string(),
+            expand_message().to_string(),
+            shuffle_message().to_string(),
+        ] {
+            assert!(summary.contains("ldp_value=5"));
+            assert!(summary.contains(&proof_size));
+        }
+    }
+
+    #[test]
+    fn input_value_signature_input_is_unambiguous() {
+        // both pairs encode to [1, 2, 3] under raw concatenation
+        let first = InputValueSignatureInput::<2, 1> {
+            input_value: [1, 2],
+            input_value_time: [3],
+        };
+        let second = InputValueSignatureInput::<1, 2> {
+            input_value: [1],
+            input_value_time: [2, 3],
+        };
+        assert_eq!(
+            [first.input_value.as_slice(), &first.input_value_time].concat(),
+            [second.input_value.as_slice(), &second.input_value_time].concat()
+        );
+        assert_ne!(first.to_bytes(), second.to_bytes());
+    }
+
+    #[test]
+    fn derived_prf_eval_points_agree_and_are_distinct() {
+        let public_seed = [11; 32];
+        let client_points = derive_prf_eval_points::<Conf>(public_seed, 16).unwrap();
+        let server_points = derive_prf_eval_points::<Conf>(public_seed, 16).unwrap();
+        assert_eq!(client_points, server_points);
+        assert_eq!(client_points.iter().collect::<HashSet<_>>().len(), 16);
+        assert_ne!(
+            client_points,
+            derive_prf_eval_points::<Conf>([12; 32], 16).unwrap()
+        );
+    }
+
+    // compressed wire format of the messages above; only update these if a breaking change to the
+    // message format is intended
+    const BASE_GOLDEN: &str = concat!(
+        "0800",
+        "493109ffabc9dda72433668765a085f83d337b5528421ee3c82a676f5057e6a4",
+        "cc5ffc7d9e4977bf4fbed2b7c5154c995b54713fa329f614d1bc5b8ca3d86d36",
+        "0303030303030303030303030303030303030303030303030303030303030303",
+        "4019751fde031fc36634fd599776a70af63126d54d8a2df2f676965e9d266709",
+        "567d5216f16bb817e26752ec3555436fe252b8e5f3765bd4d1f250222e40cc08",
+        "00",
+        "07070707070707070707070707070707",
+        "c000000000000000000000000000000000000000000000000000000000000000",
+        "00000000000000000000000000000000c0000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "00000000000000000000000000000000c0000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "0500000000000000",
+    );
+    const EXPAND_GOLDEN: &str = concat!(
+        "0800",
+        "493109ffabc9dda72433668765a085f83d337b5528421ee3c82a676f5057e6a4",
+        "d41d3d87ed238aed9b051048ae364bbe34f94867318b92962724ca6122f3dd55",
+        "0303030303030303030303030303030303030303030303030303030303030303",
+        "4019751fde031fc36634fd599776a70af63126d54d8a2df2f676965e9d266709",
+        "567d5216f16bb817e26752ec3555436fe252b8e5f3765bd4d1f250222e40cc08",
+        "00",
+        "07070707070707070707070707070707",
+        "c000000000000000000000000000000000000000000000000000000000000000",
+        "00000000000000000000000000000000c0000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "00000000000000000000000000000000c0000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "0500000000000000",
+    );
+    const SHUFFLE_GOLDEN: &str = concat!(
+        "0800",
+        "c000000000000000000000000000000000000000000000000000000000000000",
+        "00000000000000000000000000000000c0000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "00000000000000000000000000000000c0000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "0500000000000000",
+    );
+}
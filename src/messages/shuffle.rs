@@ -1,8 +1,10 @@
 //! Structs and basic logic for all messages that are sent between clients and server in the
 //! Shuffle VLDP scheme.
 
+use super::derive_prf_eval_points;
 use crate::prelude::*;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use std::fmt::{self, Display, Formatter};
 
 /// Message sent by client in the `GenRand` step of the paper.
 #[derive(CanonicalSerialize, CanonicalDeserialize)]
@@ -15,6 +17,21 @@ where
     pub client_signature_public_key: ClientSignatureSchemePublicKey<Conf>,
 }
 
+impl<Conf: Config> Display for GenerateRandomnessMessageClientShuffle<Conf>
+where
+    ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Shuffle gen-rand client: client_seed_commitment=<{} bytes>, client_signature_public_key=<{} bytes>",
+            self.client_seed_commitment.compressed_size(),
+            self.client_signature_public_key.compressed_size(),
+        )
+    }
+}
+
 /// Message sent by the server in the `GenRand` step of the paper.
 #[derive(CanonicalSerialize, CanonicalDeserialize)]
 pub struct GenerateRandomnessMessageServerShuffle<Conf: Config>
@@ -23,6 +40,23 @@ where
 {
     pub server_seed: PRFSchemeSeed<Conf>,
     pub server_signature: ServerSignatureSchemeSignature<Conf>,
+    /// Nonce sampled freshly by the server for every `GenRand` exchange, which binds its signature
+    /// to this exchange.
+    pub server_nonce: [u8; 16],
+}
+
+impl<Conf: Config> Display for GenerateRandomnessMessageServerShuffle<Conf>
+where
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Shuffle gen-rand server: server_seed=<{} bytes>, server_signature=<{} bytes>",
+            self.server_seed.compressed_size(),
+            self.server_signature.compressed_size(),
+        )
+    }
 }
 
 /// Message that is to be signed by the server as part of the `GenRand` step of the paper.
@@ -35,6 +69,9 @@ where
     pub client_seed_commitment: ClientCommitmentSchemeOutput<Conf>,
     pub client_signature_public_key: ClientSignatureSchemePublicKey<Conf>,
     pub server_seed: PRFSchemeSeed<Conf>,
+    /// Nonce sampled freshly by the server for every `GenRand` exchange, which binds its signature
+    /// to this exchange.
+    pub server_nonce: [u8; 16],
 }
 
 impl<Conf: Config> GenerateRandomnessSignatureInputShuffle<Conf>
@@ -42,15 +79,18 @@ where
     ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
     ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
 {
-    /// Create signature input message from the received client message and the server seed.
+    /// Create signature input message from the received client message, the server seed, and the
+    /// server nonce.
     pub fn new(
         client_message: GenerateRandomnessMessageClientShuffle<Conf>,
         server_seed: PRFSchemeSeed<Conf>,
+        server_nonce: [u8; 16],
     ) -> Self {
         Self {
             client_seed_commitment: client_message.client_seed_commitment,
             client_signature_public_key: client_message.client_signature_public_key,
             server_seed,
+            server_nonce,
         }
     }
 }
@@ -61,6 +101,56 @@ pub struct VerifiableRandomizationMessageShuffle<Conf: Config, const INPUT_BYTES
 where
     Proof<Conf>: CanonicalDeserialize,
 {
+    /// `GAMMA_BYTES` the client was instantiated with, such that the server can detect a mismatch
+    /// before it verifies the proof.
+    pub gamma_bytes: u16,
     pub proof: Proof<Conf>,
     pub ldp_value: u64,
 }
+
+impl<Conf: Config, const INPUT_BYTES: usize> Display
+    for VerifiableRandomizationMessageShuffle<Conf, INPUT_BYTES>
+where
+    Proof<Conf>: CanonicalDeserialize,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Shuffle randomize: ldp_value={}, proof=<{} bytes>",
+            self.ldp_value,
+            self.proof.compressed_size(),
+        )
+    }
+}
+
+/// Public `prf_eval_points` (s in the paper) of a Shuffle epoch, versioned by an `epoch` id, such
+/// that the points can be rotated from one epoch to the next.
+///
+/// The epoch id is a public input of the Shuffle proof (next to the points themselves), so a proof
+/// created for the set of epoch N only verifies for epoch N and its points. This is required for
+/// soundness: the LDP value of a message is only meaningful for the randomness derived from the
+/// points it was created for, so verifying it for points that client and server did not agree on
+/// must fail, rather than be accepted silently. The server can keep verifying proofs of old epochs
+/// as long as it keeps their sets.
+#[derive(Clone)]
+pub struct PrfEvalPointSet<Conf: Config> {
+    pub epoch: u64,
+    pub points: Vec<PRFSchemeInput<Conf>>,
+}
+
+impl<Conf: Config> PrfEvalPointSet<Conf> {
+    /// Create the set of the given `points` for `epoch`.
+    pub fn new(epoch: u64, points: Vec<PRFSchemeInput<Conf>>) -> Self {
+        Self { epoch, points }
+    }
+
+    /// Create the set of `n` points for `epoch` that are derived from a public seed (see
+    /// `derive_prf_eval_points`). Each epoch should use its own seed, or the readings of different
+    /// epochs would share their randomness.
+    pub fn derive(epoch: u64, public_seed: PRFSchemeSeed<Conf>, n: usize) -> Result<Self, Error> {
+        Ok(Self::new(
+            epoch,
+            derive_prf_eval_points::<Conf>(public_seed, n)?,
+        ))
+    }
+}
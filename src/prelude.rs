@@ -4,10 +4,24 @@ use ark_crypto_primitives::merkle_tree;
 use ark_ec::CurveGroup;
 
 // convenient re-exports
-pub use crate::config::{Config, ConfigGadget, ProofSystem, ProofSystemRng};
+pub use crate::config::{Config, ConfigGadget, FingerprintKey, ProofSystem, ProofSystemRng};
 pub use crate::error::*;
-pub use crate::primitives::parameters::{ParametersBase, ParametersExpand, ParametersShuffle};
-pub use crate::primitives::signature::SignatureScheme;
+pub use crate::primitives::commitment::poseidon::constraints::PoseidonCommitmentGadget;
+pub use crate::primitives::commitment::PoseidonCommitment;
+pub use crate::primitives::laplace::{
+    apply_laplace, decode_laplace, laplace_alpha_as_bytes, laplace_randomness_bytes,
+};
+pub use crate::primitives::ldp::{
+    encode_signed_input, signed_input_bytes, signed_input_offset, validate_scheme_params,
+    validate_vector_scheme_params,
+};
+pub use crate::primitives::parameters::{
+    float_conversion_precision, GammaConversion, ParametersBase, ParametersExpand,
+    ParametersShuffle, SetupReport, SharedPrimitives, MAX_FLOAT_BYTES,
+};
+pub use crate::primitives::signature::ed25519::constraints::Ed25519SignatureVerifyGadget;
+pub use crate::primitives::signature::ed25519::Ed25519;
+pub use crate::primitives::signature::{SignatureScheme, SignatureVerifier};
 pub use ark_crypto_primitives::commitment::CommitmentScheme;
 pub use ark_crypto_primitives::crh::{CRHScheme, TwoToOneCRHScheme};
 pub use ark_crypto_primitives::merkle_tree::{MerkleTree, Path};
@@ -21,17 +35,21 @@ pub use rand::prelude::{CryptoRng, Rng};
 // convenient aliases
 // generic
 pub type ConstraintF<C> = <<C as CurveGroup>::BaseField as Field>::BasePrimeField;
-pub type Error = Box<dyn std::error::Error>;
+pub type Error = VldpError;
 // zkp scheme
 pub type ZKPRng<Conf> = <Conf as Config>::ZKPRng;
 pub type ConstraintField<Conf> =
     <<Conf as Config>::ZKPScheme as ProofSystem<<Conf as Config>::ZKPRng>>::ConstraintField;
 pub type Proof<Conf> =
     <<Conf as Config>::ZKPScheme as ProofSystem<<Conf as Config>::ZKPRng>>::Proof;
+pub type ProofRandomness<Conf> = Vec<ConstraintField<Conf>>;
+pub type ProverContext<Conf> = crate::config::ProverPrecomputation<ConstraintField<Conf>>;
 pub type ProvingKey<Conf> =
     <<Conf as Config>::ZKPScheme as ProofSystem<<Conf as Config>::ZKPRng>>::ProvingKey;
 pub type VerifyingKey<Conf> =
     <<Conf as Config>::ZKPScheme as ProofSystem<<Conf as Config>::ZKPRng>>::VerifyingKey;
+pub type UnpreparedVerifyingKey<Conf> =
+    <<Conf as Config>::ZKPScheme as ProofSystem<<Conf as Config>::ZKPRng>>::UnpreparedVerifyingKey;
 // client commitment scheme
 pub type ClientCommitmentSchemeParameters<Conf> =
     <<Conf as Config>::ClientCommitmentScheme as CommitmentScheme>::Parameters;
@@ -41,25 +59,25 @@ pub type ClientCommitmentSchemeRandomness<Conf> =
     <<Conf as Config>::ClientCommitmentScheme as CommitmentScheme>::Randomness;
 // server signature scheme
 pub type ServerSignatureSchemeParameters<Conf> =
-    <<Conf as Config>::ServerSignatureScheme as SignatureScheme>::Parameters;
+    <<Conf as Config>::ServerSignatureScheme as SignatureVerifier>::Parameters;
 pub type ServerSignatureSchemePublicKey<Conf> =
-    <<Conf as Config>::ServerSignatureScheme as SignatureScheme>::PublicKey;
+    <<Conf as Config>::ServerSignatureScheme as SignatureVerifier>::PublicKey;
 pub type ServerSignatureSchemeSecretKey<Conf> =
     <<Conf as Config>::ServerSignatureScheme as SignatureScheme>::SecretKey;
 pub type ServerSignatureSchemeSignature<Conf> =
-    <<Conf as Config>::ServerSignatureScheme as SignatureScheme>::Signature;
+    <<Conf as Config>::ServerSignatureScheme as SignatureVerifier>::Signature;
 // client PRF
 pub type PRFSchemeInput<Conf> = <<Conf as Config>::PRFScheme as PRF>::Input;
 pub type PRFSchemeSeed<Conf> = <<Conf as Config>::PRFScheme as PRF>::Seed;
 // client signature scheme
 pub type ClientSignatureSchemeParameters<Conf> =
-    <<Conf as Config>::ClientSignatureScheme as SignatureScheme>::Parameters;
+    <<Conf as Config>::ClientSignatureScheme as SignatureVerifier>::Parameters;
 pub type ClientSignatureSchemePublicKey<Conf> =
-    <<Conf as Config>::ClientSignatureScheme as SignatureScheme>::PublicKey;
+    <<Conf as Config>::ClientSignatureScheme as SignatureVerifier>::PublicKey;
 pub type ClientSignatureSchemeSecretKey<Conf> =
     <<Conf as Config>::ClientSignatureScheme as SignatureScheme>::SecretKey;
 pub type ClientSignatureSchemeSignature<Conf> =
-    <<Conf as Config>::ClientSignatureScheme as SignatureScheme>::Signature;
+    <<Conf as Config>::ClientSignatureScheme as SignatureVerifier>::Signature;
 // client merkle tree
 pub type ClientMerkleTreeConfig<Conf> = <Conf as Config>::ClientMerkleTreeConfig;
 pub type ClientMerkleTree<Conf> = MerkleTree<ClientMerkleTreeConfig<Conf>>;
@@ -74,6 +92,10 @@ pub type ClientMerkleTreeTwoToOneHashParameters<Conf> =
 pub type ClientMerkleTreeRoot<Conf> =
     <ClientMerkleTreeConfig<Conf> as merkle_tree::Config>::InnerDigest;
 pub type ClientMerkleTreePath<Conf> = Path<ClientMerkleTreeConfig<Conf>>;
+// client input hash
+pub type ClientInputHashParameters<Conf> =
+    <<Conf as Config>::ClientInputHash as CRHScheme>::Parameters;
+pub type ClientInputHashOutput<Conf> = <<Conf as Config>::ClientInputHash as CRHScheme>::Output;
 
 pub(crate) mod constraints {
     use super::*;
@@ -169,5 +191,11 @@ pub(crate) mod constraints {
         ConstraintField<Conf>,
         ClientMerkleTreeConfigGadget<Conf, ConfG>,
     >;
+    // client input hash
+    pub type ClientInputHashParametersVar<Conf, ConfG> =
+        <<ConfG as ConfigGadget<Conf>>::ClientInputHashGadget as CRHSchemeGadget<
+            <Conf as Config>::ClientInputHash,
+            ConstraintField<Conf>,
+        >>::ParametersVar;
 }
 // --- end CONVENIENT ALIASES ---
@@ -0,0 +1,2 @@
+pub mod poseidon;
+pub use poseidon::*;
@@ -0,0 +1,34 @@
+use crate::primitives::commitment::PoseidonCommitment;
+use ark_crypto_primitives::commitment::CommitmentGadget;
+use ark_crypto_primitives::crh::poseidon::constraints::{CRHGadget, CRHParametersVar};
+use ark_crypto_primitives::crh::CRHSchemeGadget;
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::uint8::UInt8;
+use ark_r1cs_std::ToConstraintFieldGadget;
+use ark_relations::r1cs::SynthesisError;
+
+/// Gadget of `PoseidonCommitment`, which absorbs the same field elements as the native commitment.
+#[derive(Clone)]
+pub struct PoseidonCommitmentGadget;
+
+impl<F: PrimeField + Absorb> CommitmentGadget<PoseidonCommitment<F>, F>
+    for PoseidonCommitmentGadget
+{
+    type OutputVar = FpVar<F>;
+    type ParametersVar = CRHParametersVar<F>;
+    type RandomnessVar = FpVar<F>;
+
+    fn commit(
+        parameters: &Self::ParametersVar,
+        input: &[UInt8<F>],
+        r: &Self::RandomnessVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        // the length is fixed by the circuit, so it is a constant
+        let mut elements = vec![r.clone(), FpVar::constant(F::from(input.len() as u64))];
+        elements.extend(input.to_constraint_field()?);
+        CRHGadget::<F>::evaluate(parameters, &elements)
+    }
+}
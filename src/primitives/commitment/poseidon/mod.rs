@@ -0,0 +1,139 @@
+//! Poseidon commitment, an alternative to the Pedersen commitment of `BasicConfig` for
+//! `Config::ClientCommitmentScheme` that is far cheaper to open inside the circuits.
+//!
+//! The commitment to `input` under the randomness `r` (a uniform field element) is the Poseidon
+//! hash of `r`, the length of `input`, and `input` packed into field elements (as done by its
+//! `ToConstraintField` implementation). It is hiding as `r` is uniform, and binding by the
+//! collision resistance of Poseidon; the length is hashed as well, as inputs that only differ in
+//! trailing zeros are packed into the same field elements.
+
+use crate::prelude::*;
+use crate::primitives::crh::poseidon_parameters;
+use ark_crypto_primitives::commitment::CommitmentScheme;
+use ark_crypto_primitives::crh::poseidon::CRH;
+use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
+use ark_crypto_primitives::sponge::Absorb;
+use ark_crypto_primitives::Error;
+use ark_ff::PrimeField;
+use std::marker::PhantomData;
+
+pub mod constraints;
+
+/// Commitment with Poseidon over the field `F`, which has to be the constraint field of the proof
+/// system (e.g., the base field of JubJub for `BasicConfig`).
+#[derive(Clone)]
+pub struct PoseidonCommitment<F> {
+    #[doc(hidden)]
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> CommitmentScheme for PoseidonCommitment<F> {
+    type Output = F;
+    type Parameters = PoseidonConfig<F>;
+    type Randomness = F;
+
+    fn setup<R: Rng>(_: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(poseidon_parameters())
+    }
+
+    fn commit(
+        parameters: &Self::Parameters,
+        input: &[u8],
+        r: &Self::Randomness,
+    ) -> Result<Self::Output, Error> {
+        let mut elements = vec![*r, F::from(input.len() as u64)];
+        elements.extend(
+            ToConstraintField::<F>::to_field_elements(input)
+                .ok_or(GenericError::ConversionError)?,
+        );
+        CRH::<F>::evaluate(parameters, elements.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::constraints::PoseidonCommitmentGadget;
+    use super::PoseidonCommitment;
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::prelude::{constraints::*, *};
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_relations::r1cs::ConstraintSystem;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    /// Configuration with a Pedersen commitment to 40 bytes (e.g., the client randomness).
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 40>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+    type F = ConstraintField<Conf>;
+
+    /// Number of constraints of committing to `input` with the commitment gadget `G` (excluding
+    /// the allocation of the input and randomness), after checking that the in-circuit commitment
+    /// equals the native one.
+    fn commit_constraints<C: CommitmentScheme, G: CommitmentGadget<C, F>>(
+        input: &[u8],
+        rng: &mut ChaChaRng,
+    ) -> usize {
+        let parameters = C::setup(rng).unwrap();
+        let randomness = C::Randomness::rand(rng);
+        let native = C::commit(&parameters, input, &randomness).unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let parameters_var = G::ParametersVar::new_constant(cs.clone(), &parameters).unwrap();
+        let input_var = UInt8::new_witness_vec(cs.clone(), input).unwrap();
+        let randomness_var = G::RandomnessVar::new_witness(cs.clone(), || Ok(&randomness)).unwrap();
+        let constraints_before = cs.num_constraints();
+
+        let commitment_var = G::commit(&parameters_var, &input_var, &randomness_var).unwrap();
+        let expected_var = G::OutputVar::new_input(cs.clone(), || Ok(&native)).unwrap();
+        commitment_var.enforce_equal(&expected_var).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        cs.num_constraints() - constraints_before
+    }
+
+    #[test]
+    fn poseidon_commitment_matches_gadget_and_is_cheaper_than_pedersen() {
+        let rng = &mut ChaChaRng::from_seed([129; 32]);
+        let mut input = [0; 40];
+        rng.fill(&mut input[..]);
+
+        let poseidon =
+            commit_constraints::<PoseidonCommitment<F>, PoseidonCommitmentGadget>(&input, rng);
+        let pedersen = commit_constraints::<
+            <Conf as Config>::ClientCommitmentScheme,
+            <ConfG as ConfigGadget<Conf>>::ClientCommitmentVerifyGadget,
+        >(&input, rng);
+        assert!(
+            2 * poseidon < pedersen,
+            "Poseidon {} vs. Pedersen {}",
+            poseidon,
+            pedersen
+        );
+    }
+
+    #[test]
+    fn poseidon_commitment_binds_length_and_randomness() {
+        let rng = &mut ChaChaRng::from_seed([130; 32]);
+        let parameters = PoseidonCommitment::<F>::setup(rng).unwrap();
+        let randomness = F::rand(rng);
+        let commit = |input: &[u8], randomness: &F| {
+            PoseidonCommitment::<F>::commit(&parameters, input, randomness).unwrap()
+        };
+
+        // inputs that are packed into the same field elements
+        assert_ne!(
+            commit(&[1, 2, 3], &randomness),
+            commit(&[1, 2, 3, 0], &randomness)
+        );
+        assert_ne!(
+            commit(&[1, 2, 3], &randomness),
+            commit(&[1, 2, 3], &F::rand(rng))
+        );
+        assert_eq!(
+            commit(&[1, 2, 3], &randomness),
+            commit(&[1, 2, 3], &randomness)
+        );
+    }
+}
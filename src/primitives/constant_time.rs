@@ -0,0 +1,77 @@
+//! Constant-time helpers for comparisons in the clear whose outcome has to stay secret, e.g., the
+//! LDP bit that decides whether the input value is replaced by a random value.
+
+/// Whether the little-endian integer `left` is less than or equal to `right`, as `1` or `0`.
+///
+/// Both slices must have the same (public) length. Every byte is processed with the same
+/// branch-free operations, so the running time does not depend on the compared values.
+pub fn ct_less_or_equal(left: &[u8], right: &[u8]) -> u8 {
+    assert_eq!(left.len(), right.len());
+    // the empty prefix compares as equal
+    let mut less_or_equal = 1;
+    // from the least to the most significant byte, so the last unequal byte decides
+    for (&left_byte, &right_byte) in left.iter().zip(right) {
+        // the high byte of `right - left` is 0xff iff `left > right`
+        let greater = ((u16::from(right_byte).wrapping_sub(u16::from(left_byte)) >> 8) & 1) as u8;
+        // the high byte of `(left ^ right) - 1` is 0xff iff `left == right`
+        let equal = ((u16::from(left_byte ^ right_byte).wrapping_sub(1) >> 8) & 1) as u8;
+        less_or_equal = (equal & less_or_equal) | ((1 ^ equal) & (1 ^ greater));
+    }
+    less_or_equal
+}
+
+/// `if_one` if `bit` is `1` and `if_zero` if `bit` is `0`, selected with a mask instead of a
+/// branch, so the running time does not depend on `bit`.
+pub fn ct_select(bit: u8, if_one: u64, if_zero: u64) -> u64 {
+    // all ones iff `bit` is 1
+    let mask = 0_u64.wrapping_sub(u64::from(bit & 1));
+    (if_one & mask) | (if_zero & !mask)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ct_less_or_equal, ct_select};
+    use num_bigint::BigUint;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaChaRng;
+
+    fn naive_less_or_equal(left: &[u8], right: &[u8]) -> u8 {
+        (BigUint::from_bytes_le(left) <= BigUint::from_bytes_le(right)) as u8
+    }
+
+    #[test]
+    fn agrees_with_naive_comparison() {
+        let rng = &mut ChaChaRng::from_seed([87; 32]);
+        for length in [1, 2, 3, 8, 32] {
+            for _ in 0..256 {
+                let left = (0..length).map(|_| rng.gen()).collect::<Vec<u8>>();
+                let mut right = (0..length).map(|_| rng.gen()).collect::<Vec<u8>>();
+                assert_eq!(
+                    ct_less_or_equal(&left, &right),
+                    naive_less_or_equal(&left, &right)
+                );
+
+                // equal values and values that only differ in a single byte
+                assert_eq!(ct_less_or_equal(&left, &left), 1);
+                right.copy_from_slice(&left);
+                let index = rng.gen_range(0..length);
+                right[index] = rng.gen();
+                assert_eq!(
+                    ct_less_or_equal(&left, &right),
+                    naive_less_or_equal(&left, &right)
+                );
+                assert_eq!(
+                    ct_less_or_equal(&right, &left),
+                    naive_less_or_equal(&right, &left)
+                );
+            }
+        }
+    }
+    #[test]
+    fn select_picks_by_bit() {
+        assert_eq!(ct_select(1, 7, u64::MAX), 7);
+        assert_eq!(ct_select(0, 7, u64::MAX), u64::MAX);
+        assert_eq!(ct_select(1, u64::MAX, 0), u64::MAX);
+        assert_eq!(ct_select(0, u64::MAX, 0), 0);
+    }
+}
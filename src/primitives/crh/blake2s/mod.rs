@@ -1,5 +1,6 @@
 use crate::prelude::*;
 use ark_crypto_primitives::crh::CRHScheme;
+use ark_crypto_primitives::Error;
 use blake2;
 use blake2::Digest;
 use std::borrow::Borrow;
@@ -1,5 +1,6 @@
 use crate::prelude::*;
 use ark_crypto_primitives::crh::CRHScheme;
+use ark_crypto_primitives::Error;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use std::borrow::Borrow;
 use std::fmt::Debug;
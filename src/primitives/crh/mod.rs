@@ -3,3 +3,9 @@ pub use blake2s::*;
 
 pub mod identity;
 pub use identity::*;
+
+pub mod poseidon;
+pub use poseidon::*;
+
+pub mod sha256;
+pub use sha256::*;
@@ -0,0 +1,60 @@
+use crate::prelude::*;
+use crate::primitives::crh::{PoseidonLeafHash, PoseidonTwoToOneHash};
+use ark_crypto_primitives::crh::poseidon::constraints::{
+    CRHGadget, CRHParametersVar, TwoToOneCRHGadget,
+};
+use ark_crypto_primitives::crh::{CRHSchemeGadget, TwoToOneCRHSchemeGadget};
+use ark_crypto_primitives::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::ToConstraintFieldGadget;
+use ark_relations::r1cs::SynthesisError;
+use std::marker::PhantomData;
+
+#[derive(Clone)]
+pub struct PoseidonLeafHashGadget<TG> {
+    #[doc(hidden)]
+    _type_gadget: PhantomData<TG>,
+}
+
+impl<F: PrimeField + Absorb, T: ToConstraintField<F>, TG: ToConstraintFieldGadget<F>>
+    CRHSchemeGadget<PoseidonLeafHash<F, T>, F> for PoseidonLeafHashGadget<TG>
+{
+    type InputVar = TG;
+    type OutputVar = FpVar<F>;
+    type ParametersVar = CRHParametersVar<F>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        CRHGadget::<F>::evaluate(parameters, &input.to_constraint_field()?)
+    }
+}
+
+#[derive(Clone)]
+pub struct PoseidonTwoToOneHashGadget;
+
+impl<F: PrimeField + Absorb> TwoToOneCRHSchemeGadget<PoseidonTwoToOneHash<F>, F>
+    for PoseidonTwoToOneHashGadget
+{
+    type InputVar = FpVar<F>;
+    type OutputVar = FpVar<F>;
+    type ParametersVar = CRHParametersVar<F>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::InputVar,
+        right_input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        TwoToOneCRHGadget::<F>::evaluate(parameters, left_input, right_input)
+    }
+
+    fn compress(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::OutputVar,
+        right_input: &Self::OutputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        TwoToOneCRHGadget::<F>::compress(parameters, left_input, right_input)
+    }
+}
@@ -0,0 +1,104 @@
+//! Poseidon hashing for Merkle trees, wrapping the Poseidon CRHs of arkworks.
+//!
+//! The arkworks CRHs do not implement `setup` (the parameters have to be chosen by hand), so the
+//! wrappers here set up the parameters returned by `poseidon_parameters`, which do not depend on
+//! the randomness.
+
+use crate::prelude::*;
+use ark_crypto_primitives::crh::poseidon::{TwoToOneCRH, CRH};
+use ark_crypto_primitives::crh::{CRHScheme, TwoToOneCRHScheme};
+use ark_crypto_primitives::sponge::poseidon::{find_poseidon_ark_and_mds, PoseidonConfig};
+use ark_crypto_primitives::sponge::Absorb;
+use ark_crypto_primitives::Error;
+use ark_ff::PrimeField;
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+pub mod constraints;
+
+/// Rate of the sponge (i.e., two field elements are absorbed per permutation).
+const RATE: usize = 2;
+/// Capacity of the sponge.
+const CAPACITY: usize = 1;
+/// Exponent of the S-box.
+const ALPHA: u64 = 17;
+const FULL_ROUNDS: usize = 8;
+const PARTIAL_ROUNDS: usize = 31;
+
+/// Poseidon parameters for the field `F` (the round constants and MDS matrix are derived
+/// deterministically with the Grain LFSR from the reference implementation).
+pub fn poseidon_parameters<F: PrimeField>() -> PoseidonConfig<F> {
+    let (ark, mds) = find_poseidon_ark_and_mds::<F>(
+        F::MODULUS_BIT_SIZE as u64,
+        RATE,
+        FULL_ROUNDS as u64,
+        PARTIAL_ROUNDS as u64,
+        0,
+    );
+    PoseidonConfig::new(FULL_ROUNDS, PARTIAL_ROUNDS, ALPHA, mds, ark, RATE, CAPACITY)
+}
+
+/// Hash of a single value (e.g., a commitment) into a field element, by absorbing the value as
+/// field elements into a Poseidon sponge.
+#[derive(Clone)]
+pub struct PoseidonLeafHash<F, T> {
+    #[doc(hidden)]
+    _field: PhantomData<F>,
+
+    #[doc(hidden)]
+    _type: PhantomData<T>,
+}
+
+impl<F: PrimeField + Absorb, T: ToConstraintField<F>> CRHScheme for PoseidonLeafHash<F, T> {
+    type Input = T;
+    type Output = F;
+    type Parameters = PoseidonConfig<F>;
+
+    fn setup<R: Rng>(_: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(poseidon_parameters())
+    }
+
+    fn evaluate<Input: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        input: Input,
+    ) -> Result<Self::Output, Error> {
+        let input = input
+            .borrow()
+            .to_field_elements()
+            .ok_or(GenericError::ConversionError)?;
+        CRH::<F>::evaluate(parameters, input.as_slice())
+    }
+}
+
+/// Hash of two field elements into one.
+#[derive(Clone)]
+pub struct PoseidonTwoToOneHash<F> {
+    #[doc(hidden)]
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> TwoToOneCRHScheme for PoseidonTwoToOneHash<F> {
+    type Input = F;
+    type Output = F;
+    type Parameters = PoseidonConfig<F>;
+
+    fn setup<R: Rng>(_: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(poseidon_parameters())
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        TwoToOneCRH::<F>::evaluate(parameters, left_input, right_input)
+    }
+
+    fn compress<T: Borrow<Self::Output>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        TwoToOneCRH::<F>::compress(parameters, left_input, right_input)
+    }
+}
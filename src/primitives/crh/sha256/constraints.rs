@@ -0,0 +1,31 @@
+use crate::primitives::crh::Sha256LeafHash;
+use ark_crypto_primitives::crh::sha256::constraints::{DigestVar, Sha256Gadget, UnitVar};
+use ark_crypto_primitives::crh::CRHSchemeGadget;
+use ark_ff::PrimeField;
+use ark_r1cs_std::ToBytesGadget;
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::CanonicalSerialize;
+use std::marker::PhantomData;
+
+/// Gadget of `Sha256LeafHash`, which hashes the bytes of `TG`. These must equal the uncompressed
+/// serialization of the value.
+#[derive(Clone)]
+pub struct Sha256LeafHashGadget<TG> {
+    #[doc(hidden)]
+    _type_gadget: PhantomData<TG>,
+}
+
+impl<F: PrimeField, T: CanonicalSerialize, TG: ToBytesGadget<F>>
+    CRHSchemeGadget<Sha256LeafHash<T>, F> for Sha256LeafHashGadget<TG>
+{
+    type InputVar = TG;
+    type OutputVar = DigestVar<F>;
+    type ParametersVar = UnitVar<F>;
+
+    fn evaluate(
+        _: &Self::ParametersVar,
+        input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        Sha256Gadget::<F>::digest(&input.to_bytes()?)
+    }
+}
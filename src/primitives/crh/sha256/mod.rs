@@ -0,0 +1,39 @@
+//! SHA-256 hashing of leaves for Merkle trees, based on the SHA-256 CRH of arkworks (which is used
+//! as is for hashing two child nodes).
+
+use crate::prelude::*;
+use ark_crypto_primitives::crh::sha256::digest::Digest;
+use ark_crypto_primitives::crh::sha256::Sha256;
+use ark_crypto_primitives::crh::CRHScheme;
+use ark_crypto_primitives::Error;
+use ark_serialize::CanonicalSerialize;
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+pub mod constraints;
+
+/// SHA-256 hash of a single value (e.g., a commitment), i.e., of its uncompressed serialization.
+#[derive(Clone)]
+pub struct Sha256LeafHash<T> {
+    #[doc(hidden)]
+    _type: PhantomData<T>,
+}
+
+impl<T: CanonicalSerialize> CRHScheme for Sha256LeafHash<T> {
+    type Input = T;
+    type Output = Vec<u8>;
+    type Parameters = ();
+
+    fn setup<R: Rng>(_: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(())
+    }
+
+    fn evaluate<Input: Borrow<Self::Input>>(
+        _: &Self::Parameters,
+        input: Input,
+    ) -> Result<Self::Output, Error> {
+        let mut bytes = Vec::new();
+        input.borrow().serialize_uncompressed(&mut bytes)?;
+        Ok(Sha256::digest(&bytes).to_vec())
+    }
+}
@@ -0,0 +1,191 @@
+//! Shared (non-circuit) logic of the discrete Laplace mechanism, an alternative to the randomized
+//! response of `ldp` for unbounded integer queries (see `CircuitBaseLaplace`).
+//!
+//! The noise is the difference of two geometric samples, each of which counts the leading
+//! successes of `TRIALS` Bernoulli trials with success probability alpha = exp(-epsilon). This
+//! yields the two-sided geometric (i.e., discrete Laplace) distribution
+//! `P(noise = x) = (1 - alpha) / (1 + alpha) * alpha^|x|`, truncated to `[-TRIALS, TRIALS]`. The
+//! truncation is symmetric, so the noise stays unbiased; it only removes a probability mass of
+//! about `2 * alpha^TRIALS`, which `TRIALS` should make negligible.
+
+use crate::prelude::*;
+use crate::primitives::constant_time::ct_less_or_equal;
+use crate::primitives::parameters::float_conversion_precision;
+use astro_float::{BigFloat, Consts, Radix, RoundingMode};
+use num_bigint::BigUint;
+use std::str::FromStr;
+
+/// Number of randomness bytes that `apply_laplace` consumes for a single input value:
+/// `GAMMA_BYTES` bytes for each of the `2 * TRIALS` Bernoulli trials.
+pub const fn laplace_randomness_bytes<const GAMMA_BYTES: usize, const TRIALS: usize>() -> usize {
+    2 * TRIALS * GAMMA_BYTES
+}
+
+/// Encode the success probability alpha = exp(-epsilon) of the Bernoulli trials as `GAMMA_BYTES`
+/// little-endian bytes, in the same way as gamma (see `ParametersBase::gamma_as_bytes`): a trial
+/// succeeds if its randomness is at most these bytes.
+pub fn laplace_alpha_as_bytes<const GAMMA_BYTES: usize>(
+    epsilon: &BigFloat,
+) -> Result<[u8; GAMMA_BYTES], Error> {
+    let precision = float_conversion_precision("GAMMA_BYTES", GAMMA_BYTES)?;
+    let mut consts = Consts::new().map_err(GenericError::from)?;
+    let alpha = epsilon
+        .neg()
+        .exp(precision, RoundingMode::Down, &mut consts);
+    let alpha_as_int = alpha
+        .mul_full_prec(
+            &BigFloat::from_u8(2, precision)
+                .powi(GAMMA_BYTES * 8, precision, RoundingMode::Down)
+                .sub_full_prec(&BigFloat::from_u8(1, precision)),
+        )
+        .int();
+    let alpha_as_str = alpha_as_int
+        .convert_to_radix(Radix::Dec, RoundingMode::None, &mut consts)
+        .map_err(GenericError::from)?
+        .1
+        .iter()
+        .map(|digit| digit.to_string())
+        .collect::<String>();
+    let mut bytes = [0; GAMMA_BYTES];
+    if !alpha_as_str.is_empty() {
+        let alpha_bytes = BigUint::from_str(&alpha_as_str)?.to_bytes_le();
+        bytes[0..alpha_bytes.len()].copy_from_slice(&alpha_bytes);
+    }
+    Ok(bytes)
+}
+
+/// Number of leading successes among the Bernoulli trials given by consecutive `GAMMA_BYTES`
+/// chunks of `randomness`. Every trial is compared in constant time, and all trials are
+/// evaluated, as the noise must not leak through the running time.
+fn geometric_sample<const GAMMA_BYTES: usize>(
+    randomness: &[u8],
+    alpha_bytes: &[u8; GAMMA_BYTES],
+) -> u64 {
+    let mut alive = 1;
+    let mut count = 0;
+    for trial in randomness.chunks(GAMMA_BYTES) {
+        alive &= ct_less_or_equal(trial, alpha_bytes);
+        count += u64::from(alive);
+    }
+    count
+}
+
+/// Noise of the discrete Laplace mechanism in `[-TRIALS, TRIALS]`, given the combined client and
+/// server `randomness` of a single input value.
+pub fn laplace_noise<const GAMMA_BYTES: usize, const TRIALS: usize>(
+    randomness: &[u8],
+    alpha_bytes: &[u8; GAMMA_BYTES],
+) -> i64 {
+    let trial_bytes = TRIALS * GAMMA_BYTES;
+    let positive = geometric_sample(&randomness[0..trial_bytes], alpha_bytes);
+    let negative = geometric_sample(&randomness[trial_bytes..2 * trial_bytes], alpha_bytes);
+    positive as i64 - negative as i64
+}
+
+/// Apply the discrete Laplace mechanism to `input_value`: the LDP value is `input_value + noise`,
+/// shifted by `TRIALS` such that it is never negative. Fails with `GenericError::ConversionError`
+/// if the LDP value does not fit into a `u64`.
+pub fn apply_laplace<const GAMMA_BYTES: usize, const TRIALS: usize>(
+    input_value: &BigUint,
+    randomness: &[u8],
+    alpha_bytes: &[u8; GAMMA_BYTES],
+) -> Result<u64, Error> {
+    let noise = laplace_noise::<GAMMA_BYTES, TRIALS>(randomness, alpha_bytes);
+    u64::try_from(input_value)
+        .ok()
+        .and_then(|input_value| input_value.checked_add(TRIALS as u64))
+        .and_then(|shifted| shifted.checked_add_signed(noise))
+        .ok_or_else(|| GenericError::ConversionError.into())
+}
+
+/// Estimate of the input value of an LDP value of `apply_laplace` (i.e., with the shift by
+/// `TRIALS` removed). Averages of these estimates are unbiased.
+pub fn decode_laplace<const TRIALS: usize>(ldp_value: u64) -> i128 {
+    i128::from(ldp_value) - TRIALS as i128
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaChaRng;
+
+    const GAMMA_BYTES: usize = 4;
+    const TRIALS: usize = 24;
+
+    #[test]
+    fn noise_follows_discrete_laplace_distribution() {
+        let rng = &mut ChaChaRng::from_seed([119; 32]);
+        let epsilon = 1.0;
+        let alpha_bytes =
+            laplace_alpha_as_bytes::<GAMMA_BYTES>(&BigFloat::from_f64(epsilon, 64)).unwrap();
+        let alpha = f64::from(u32::from_le_bytes(alpha_bytes)) / f64::from(u32::MAX);
+        assert!((alpha - (-epsilon).exp()).abs() < 1e-6);
+
+        const SAMPLES: usize = 50_000;
+        let mut counts = std::collections::BTreeMap::<i64, usize>::new();
+        let mut randomness = [0; laplace_randomness_bytes::<GAMMA_BYTES, TRIALS>()];
+        for _ in 0..SAMPLES {
+            rng.fill(&mut randomness[..]);
+            *counts
+                .entry(laplace_noise::<GAMMA_BYTES, TRIALS>(
+                    &randomness,
+                    &alpha_bytes,
+                ))
+                .or_default() += 1;
+        }
+        let frequency = |noise: i64| *counts.get(&noise).unwrap_or(&0) as f64 / SAMPLES as f64;
+
+        // empirical probabilities of small noise values match the target distribution
+        for noise in -4_i64..=4 {
+            let expected = (1.0 - alpha) / (1.0 + alpha) * alpha.powi(noise.abs() as i32);
+            assert!(
+                (frequency(noise) - expected).abs() < 0.01,
+                "P(noise = {}) = {}, expected {}",
+                noise,
+                frequency(noise),
+                expected
+            );
+        }
+
+        // the ratio of neighboring probabilities recovers epsilon
+        let ratio_epsilon = (frequency(0) / frequency(1)).ln();
+        assert!(
+            (ratio_epsilon - epsilon).abs() < 0.1,
+            "empirical epsilon {}",
+            ratio_epsilon
+        );
+
+        // the noise is centered
+        let mean = counts
+            .iter()
+            .map(|(noise, count)| *noise as f64 * *count as f64)
+            .sum::<f64>()
+            / SAMPLES as f64;
+        assert!(mean.abs() < 0.05, "mean noise {}", mean);
+    }
+
+    #[test]
+    fn ldp_value_is_shifted_input_plus_noise() {
+        let alpha_bytes = [0x80, 0, 0, 0x80];
+        // first trial of the first sample fails, both trials of the second sample succeed
+        let mut randomness = [0xff; laplace_randomness_bytes::<GAMMA_BYTES, 2>()];
+        randomness[2 * GAMMA_BYTES..].fill(0);
+        assert_eq!(
+            laplace_noise::<GAMMA_BYTES, 2>(&randomness, &alpha_bytes),
+            -2
+        );
+        let ldp_value =
+            apply_laplace::<GAMMA_BYTES, 2>(&BigUint::from(5_u8), &randomness, &alpha_bytes)
+                .unwrap();
+        assert_eq!(ldp_value, 5);
+        assert_eq!(decode_laplace::<2>(ldp_value), 3);
+
+        assert!(apply_laplace::<GAMMA_BYTES, 2>(
+            &BigUint::from(u64::MAX),
+            &randomness,
+            &alpha_bytes
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,349 @@
+//! Shared (non-circuit) logic of the LDP mechanism for drawing a uniformly random output value,
+//! which is identical for all VLDP schemes (Base, Expand, and Shuffle).
+
+use crate::prelude::*;
+use crate::primitives::constant_time::{ct_less_or_equal, ct_select};
+use ark_std::{One, Zero};
+use num_bigint::{BigInt, BigUint};
+use std::cmp::min;
+use std::ops::RangeInclusive;
+
+/// Width of the interval of random values that is mapped to a single output value.
+///
+/// Note that `K + 1` is computed as a `BigUint`, as it overflows a `u64` for `K == u64::MAX`.
+pub fn boundary_gap<const INPUT_BYTES: usize, const K: u64, const IS_REAL_INPUT: bool>() -> BigUint
+{
+    let max_value = BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]);
+    if IS_REAL_INPUT {
+        max_value / (BigUint::from(K) + 1_u8)
+    } else {
+        max_value / K
+    }
+}
+
+/// Map the (little-endian) `randomness` bytes to a uniformly random output value in `[0, K]`
+/// (real input) or `[1, K]` (histogram input).
+///
+/// The quotient `randomness / boundary_gap` can exceed `K` (and even `u64::MAX`, e.g., for
+/// `INPUT_BYTES > 8` and `K + 1 == 2^64`) before it is clamped to the output range. Hence, it is
+/// saturated to `u64::MAX` rather than truncated to its lowest `u64` digit.
+pub fn randomized_ldp_value<const INPUT_BYTES: usize, const K: u64, const IS_REAL_INPUT: bool>(
+    randomness: &[u8],
+) -> u64 {
+    let computed_ldp_value =
+        BigUint::from_bytes_le(randomness) / boundary_gap::<INPUT_BYTES, K, IS_REAL_INPUT>();
+    let computed_ldp_value = u64::try_from(&computed_ldp_value).unwrap_or(u64::MAX);
+    if IS_REAL_INPUT {
+        min(computed_ldp_value, K)
+    } else {
+        min(computed_ldp_value, K - 1) + 1
+    }
+}
+
+/// Exclusive upper end of the random values, i.e., one more than the largest value that fits into
+/// `INPUT_BYTES` bytes. The interval of the output value `K` extends up to it, such that every
+/// random value belongs to an interval.
+pub fn randomness_limit<const INPUT_BYTES: usize>() -> BigUint {
+    BigUint::one() << (8 * INPUT_BYTES)
+}
+
+/// Boundaries of the intervals of random values that are mapped to the output values, in
+/// increasing order and as enforced by the circuits: output value `v` is drawn for the random
+/// values in `[bins[i], bins[i + 1])` with `i = v` (real input) or `i = v - 1` (histogram input).
+/// The first boundary is 0 and the last is `randomness_limit`, so there are `K + 2` (real input)
+/// or `K + 1` (histogram input) boundaries, i.e., this is only practical for small `K`.
+pub fn bin_boundaries<const INPUT_BYTES: usize, const K: u64, const IS_REAL_INPUT: bool>(
+) -> Vec<BigUint> {
+    let boundary_gap = boundary_gap::<INPUT_BYTES, K, IS_REAL_INPUT>();
+    let last_lower_bound = if IS_REAL_INPUT { K } else { K - 1 };
+    (0..=last_lower_bound)
+        .map(|index| &boundary_gap * index)
+        .chain([randomness_limit::<INPUT_BYTES>()])
+        .collect()
+}
+
+/// Number of randomness bytes that `apply_ldp` consumes for a single input value: `GAMMA_BYTES`
+/// for the LDP bit, `INPUT_BYTES` for the random output value, and (only for real input) another
+/// `INPUT_BYTES` for the randomized rounding.
+pub const fn ldp_randomness_bytes<
+    const INPUT_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const IS_REAL_INPUT: bool,
+>() -> usize {
+    if IS_REAL_INPUT {
+        GAMMA_BYTES + 2 * INPUT_BYTES
+    } else {
+        GAMMA_BYTES + INPUT_BYTES
+    }
+}
+
+/// Check that the const generics of a scheme instance fit together, such that a misconfiguration
+/// fails when a client or server is created rather than with a wrong proof: `RANDOMNESS_BYTES`
+/// must cover `ldp_randomness_bytes`, and the output range given by `K` must not exceed the input
+/// values (i.e., `boundary_gap` must be positive, and `K >= 1` for histogram input).
+pub fn validate_scheme_params<
+    const INPUT_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+    const K: u64,
+    const IS_REAL_INPUT: bool,
+>() -> Result<(), Error> {
+    validate_vector_scheme_params::<INPUT_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT, 1>(
+    )
+}
+
+/// Check the const generics of a scheme instance like `validate_scheme_params`, for `D` input
+/// values that are randomized with consecutive chunks of the randomness (see `CircuitBase`).
+pub fn validate_vector_scheme_params<
+    const INPUT_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+    const K: u64,
+    const IS_REAL_INPUT: bool,
+    const D: usize,
+>() -> Result<(), Error> {
+    if INPUT_BYTES == 0 || GAMMA_BYTES == 0 || D == 0 {
+        Err(GenericError::InvalidSchemeParameters(format!(
+            "INPUT_BYTES = {}, GAMMA_BYTES = {}, and D = {} must be positive",
+            INPUT_BYTES, GAMMA_BYTES, D
+        )))?
+    }
+    let randomness_bytes = D * ldp_randomness_bytes::<INPUT_BYTES, GAMMA_BYTES, IS_REAL_INPUT>();
+    if randomness_bytes > RANDOMNESS_BYTES {
+        Err(GenericError::InvalidSchemeParameters(format!(
+            "RANDOMNESS_BYTES = {} is less than the {} bytes needed for {} input value(s) with \
+             INPUT_BYTES = {}, GAMMA_BYTES = {}, and IS_REAL_INPUT = {}",
+            RANDOMNESS_BYTES, randomness_bytes, D, INPUT_BYTES, GAMMA_BYTES, IS_REAL_INPUT
+        )))?
+    }
+    if !IS_REAL_INPUT && K == 0 {
+        Err(GenericError::InvalidSchemeParameters(
+            "K must be positive for histogram input".to_string(),
+        ))?
+    }
+    // the largest K for which `boundary_gap` is positive, i.e., the random values with
+    // `INPUT_BYTES` bytes still cover every output value
+    let max_k = BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]) - u8::from(IS_REAL_INPUT);
+    if BigUint::from(K) > max_k {
+        // K is positive here, as the maximum is at least 254
+        Err(GenericError::InvalidSchemeParameters(format!(
+            "K = {} (ilog2(K) = {}, i.e., {} bits) is not in the allowed range [{}, {}] for \
+             INPUT_BYTES = {} ({} bits) and IS_REAL_INPUT = {}",
+            K,
+            K.ilog2(),
+            K.ilog2() + 1,
+            u8::from(!IS_REAL_INPUT),
+            max_k,
+            INPUT_BYTES,
+            INPUT_BYTES * 8,
+            IS_REAL_INPUT
+        )))?
+    }
+    Ok(())
+}
+
+/// Apply the LDP mechanism to `input_value`, given the combined client and server `randomness`
+/// and the byte encoding of gamma: with probability (roughly) gamma the output is a uniformly
+/// random value, otherwise it is the input value (histogram input) or the input value scaled to
+/// `[0, K]` with randomized rounding (real input).
+pub fn apply_ldp<
+    const INPUT_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const K: u64,
+    const IS_REAL_INPUT: bool,
+>(
+    input_value: &BigUint,
+    randomness: &[u8],
+    gamma_bytes: &[u8; GAMMA_BYTES],
+) -> u64 {
+    // the LDP bit must not leak through the running time, so it is compared in constant time, both
+    // candidate LDP values are computed, and one of them is selected with a mask
+    let ldp_bit = ct_less_or_equal(&randomness[0..GAMMA_BYTES], gamma_bytes);
+
+    let input_ldp_value = if IS_REAL_INPUT {
+        let input_value_times_k = input_value * K;
+        let multiplicand = &input_value_times_k / BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]);
+        let remainder = &input_value_times_k
+            - &multiplicand * BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]);
+        let random_input_bytes =
+            &randomness[GAMMA_BYTES + INPUT_BYTES..GAMMA_BYTES + 2 * INPUT_BYTES];
+        let random_input_bit = (BigUint::from_bytes_le(random_input_bytes) <= remainder) as u64;
+        if multiplicand.is_zero() {
+            random_input_bit
+        } else {
+            multiplicand.to_u64_digits()[0] + random_input_bit
+        }
+    } else if input_value.is_zero() {
+        0
+    } else {
+        input_value.to_u64_digits()[0]
+    };
+    let random_ldp_value = randomized_ldp_value::<INPUT_BYTES, K, IS_REAL_INPUT>(
+        &randomness[GAMMA_BYTES..GAMMA_BYTES + INPUT_BYTES],
+    );
+    ct_select(ldp_bit, random_ldp_value, input_ldp_value)
+}
+
+/// Offset that shifts signed input values (in two's complement with `INPUT_BYTES` bytes) into the
+/// unsigned range `[0, 2^(8 * INPUT_BYTES) - 1]` of input values (see
+/// `ParametersBase::with_signed_input`).
+pub fn signed_input_offset<const INPUT_BYTES: usize>() -> BigUint {
+    BigUint::one() << (8 * INPUT_BYTES - 1)
+}
+
+/// Shift a signed input value into the range of input values, i.e., add `signed_input_offset`.
+/// This is the input value that is passed to the Randomize step for signed input.
+pub fn encode_signed_input<const INPUT_BYTES: usize>(value: &BigInt) -> Result<BigUint, Error> {
+    let encoded = value + BigInt::from(signed_input_offset::<INPUT_BYTES>());
+    match encoded.to_biguint() {
+        Some(encoded) if encoded.bits() <= 8 * INPUT_BYTES as u64 => Ok(encoded),
+        _ => Err(ClientError::SignedInputOutOfRange {
+            value: value.clone(),
+            input_bytes: INPUT_BYTES,
+        })?,
+    }
+}
+
+/// Bytes of a signed input value in two's complement, i.e., the input value bytes that are signed
+/// for signed input. These are the bytes of the encoded input value (see `encode_signed_input`)
+/// with the most significant bit flipped.
+pub fn signed_input_bytes<const INPUT_BYTES: usize>(
+    value: &BigInt,
+) -> Result<[u8; INPUT_BYTES], Error> {
+    let encoded = encode_signed_input::<INPUT_BYTES>(value)?.to_bytes_le();
+    let mut bytes = [0; INPUT_BYTES];
+    bytes[0..encoded.len()].copy_from_slice(&encoded);
+    bytes[INPUT_BYTES - 1] ^= 0x80;
+    Ok(bytes)
+}
+
+/// Range of input values that are mapped to `ldp_value` if the LDP bit is not set: for real input,
+/// all input values whose randomized rounding can yield `ldp_value`, and for histogram input,
+/// only `ldp_value` itself. For signed input, the range is shifted back by `signed_input_offset`.
+pub fn decoded_range<const INPUT_BYTES: usize, const K: u64, const IS_REAL_INPUT: bool>(
+    ldp_value: u64,
+    signed_input: bool,
+) -> RangeInclusive<BigInt> {
+    let (lower, upper) = if IS_REAL_INPUT {
+        // input_value * K / max_value lies in [ldp_value - 1, ldp_value + 1)
+        let max_value = BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]);
+        let k = BigUint::from(K);
+        let ceil_div = |dividend: BigUint| (dividend + &k - 1_u8) / &k;
+        let lower = match ldp_value {
+            0 => BigUint::zero(),
+            _ => ceil_div(&max_value * (ldp_value - 1)),
+        };
+        let upper = min(
+            ceil_div(&max_value * ldp_value + &max_value) - 1_u8,
+            max_value,
+        );
+        (BigInt::from(lower), BigInt::from(upper))
+    } else {
+        (BigInt::from(ldp_value), BigInt::from(ldp_value))
+    };
+    if signed_input {
+        let offset = BigInt::from(signed_input_offset::<INPUT_BYTES>());
+        lower - &offset..=upper - offset
+    } else {
+        lower..=upper
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_bins_partition_randomness<const K: u64, const IS_REAL_INPUT: bool>() {
+        let bins = bin_boundaries::<1, K, IS_REAL_INPUT>();
+        assert_eq!(bins.len() as u64, if IS_REAL_INPUT { K + 2 } else { K + 1 });
+        assert_eq!(bins[0], BigUint::zero());
+        assert_eq!(bins[bins.len() - 1], BigUint::from(256_u16));
+        assert!(bins.windows(2).all(|bin| bin[0] < bin[1]));
+
+        // every random value lies in exactly one bin, which is the one of its output value
+        for randomness in 0..=u8::MAX {
+            let containing = bins
+                .windows(2)
+                .enumerate()
+                .filter(|(_, bin)| {
+                    bin[0] <= BigUint::from(randomness) && BigUint::from(randomness) < bin[1]
+                })
+                .map(|(index, _)| index as u64)
+                .collect::<Vec<_>>();
+            let ldp_value = randomized_ldp_value::<1, K, IS_REAL_INPUT>(&[randomness]);
+            let expected = if IS_REAL_INPUT {
+                ldp_value
+            } else {
+                ldp_value - 1
+            };
+            assert_eq!(containing, vec![expected], "randomness {}", randomness);
+        }
+    }
+
+    #[test]
+    fn bin_boundaries_partition_randomness() {
+        assert_bins_partition_randomness::<1, true>();
+        assert_bins_partition_randomness::<1, false>();
+        assert_bins_partition_randomness::<4, true>();
+        assert_bins_partition_randomness::<4, false>();
+        // the gaps do not divide 255, so the last bin is the widest
+        assert_bins_partition_randomness::<6, true>();
+        assert_bins_partition_randomness::<7, false>();
+        assert_bins_partition_randomness::<255, false>();
+    }
+
+    #[test]
+    fn randomized_ldp_value_with_multi_digit_quotient() {
+        // boundary_gap = (2^128 - 1) / 2^64 = 2^64 - 1, so the maximal randomness yields a
+        // quotient of 2^64 + 1, whose lowest u64 digit is 1
+        const INPUT_BYTES: usize = 16;
+        const K: u64 = u64::MAX;
+        let randomness = [u8::MAX; INPUT_BYTES];
+        let quotient = BigUint::from_bytes_le(&randomness) / boundary_gap::<INPUT_BYTES, K, true>();
+        assert_eq!(quotient.to_u64_digits(), vec![1, 1]);
+
+        assert_eq!(randomized_ldp_value::<INPUT_BYTES, K, true>(&randomness), K);
+        assert_eq!(
+            randomized_ldp_value::<INPUT_BYTES, K, false>(&randomness),
+            K
+        );
+        assert_eq!(
+            randomized_ldp_value::<INPUT_BYTES, K, true>(&[0; INPUT_BYTES]),
+            0
+        );
+        assert_eq!(
+            randomized_ldp_value::<INPUT_BYTES, K, false>(&[0; INPUT_BYTES]),
+            1
+        );
+    }
+
+    #[test]
+    fn scheme_params_are_validated() {
+        // the configurations of the examples
+        assert!(validate_scheme_params::<2, 2, 4, 4, false>().is_ok());
+        assert!(validate_scheme_params::<2, 2, 6, 4, true>().is_ok());
+        // more randomness than needed is fine, e.g., for full PRF chunks
+        assert!(validate_scheme_params::<2, 2, 32, 4, false>().is_ok());
+        assert!(validate_vector_scheme_params::<2, 2, 12, 4, false, 3>().is_ok());
+
+        assert!(validate_scheme_params::<2, 2, 3, 4, false>().is_err());
+        assert!(validate_scheme_params::<2, 2, 4, 4, true>().is_err());
+        assert!(validate_vector_scheme_params::<2, 2, 8, 4, false, 3>().is_err());
+        assert!(validate_scheme_params::<1, 2, 3, 0, false>().is_err());
+        assert!(validate_scheme_params::<1, 2, 3, 255, false>().is_ok());
+        assert!(validate_scheme_params::<1, 2, 3, 256, false>().is_err());
+        assert!(validate_scheme_params::<1, 2, 4, 254, true>().is_ok());
+        assert!(validate_scheme_params::<1, 2, 4, 255, true>().is_err());
+        assert!(validate_scheme_params::<0, 2, 4, 1, true>().is_err());
+        assert!(validate_scheme_params::<8, 2, 18, { u64::MAX }, false>().is_ok());
+        assert!(validate_scheme_params::<8, 2, 18, { u64::MAX }, true>().is_err());
+
+        // the error names the violated invariant
+        let message = validate_scheme_params::<2, 2, 4, 70_000, false>()
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("ilog2(K) = 16"), "{}", message);
+        assert!(message.contains("[1, 65535]"), "{}", message);
+        assert!(message.contains("INPUT_BYTES = 2 (16 bits)"), "{}", message);
+    }
+}
@@ -1,6 +1,11 @@
 //! Cryptographic primitives for use in our schemes. Each primitive includes a regular usage
 //! implementation along with a circuit specification for use inside ZKP circuits.
 
+pub mod commitment;
+pub mod constant_time;
 pub mod crh;
+pub mod laplace;
+pub mod ldp;
 pub mod parameters;
+pub mod prf;
 pub mod signature;
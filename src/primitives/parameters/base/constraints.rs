@@ -13,6 +13,7 @@ pub struct ParametersBaseVar<Conf: Config, ConfG: ConfigGadget<Conf>> {
     #[allow(dead_code)]
     pub server_signature_scheme: ServerSignatureSchemeParametersVar<Conf, ConfG>,
     pub client_signature_scheme: ClientSignatureSchemeParametersVar<Conf, ConfG>,
+    pub client_input_hash_scheme: Option<ClientInputHashParametersVar<Conf, ConfG>>,
 }
 
 // implement variable allocation of all parameters
@@ -27,6 +28,12 @@ impl<Conf: Config, ConfG: ConfigGadget<Conf>, const GAMMA_BYTES: usize>
     ) -> Result<Self, SynthesisError> {
         let cs = cs.into();
         f().and_then(|val| {
+            // a public gamma is the first public input of the circuit
+            let gamma_mode = if val.borrow().gamma_is_public {
+                AllocationMode::Input
+            } else {
+                mode
+            };
             let gamma = GammaVar::<Conf>::new_variable(
                 cs.clone(),
                 || {
@@ -35,7 +42,7 @@ impl<Conf: Config, ConfG: ConfigGadget<Conf>, const GAMMA_BYTES: usize>
                         .gamma_as_bytes()
                         .map_err(|_| SynthesisError::AssignmentMissing)?)
                 },
-                mode,
+                gamma_mode,
             )?;
             let client_commitment_scheme =
                 ClientCommitmentSchemeParametersVar::<Conf, ConfG>::new_variable(
@@ -55,11 +62,24 @@ impl<Conf: Config, ConfG: ConfigGadget<Conf>, const GAMMA_BYTES: usize>
                     || Ok(&val.borrow().client_signature_scheme),
                     mode,
                 )?;
+            let client_input_hash_scheme = val
+                .borrow()
+                .client_input_hash_scheme
+                .as_ref()
+                .map(|parameters| {
+                    ClientInputHashParametersVar::<_, ConfG>::new_variable(
+                        cs.clone(),
+                        || Ok(parameters),
+                        mode,
+                    )
+                })
+                .transpose()?;
             return Ok(Self {
                 gamma,
                 client_commitment_scheme,
                 server_signature_scheme,
                 client_signature_scheme,
+                client_input_hash_scheme,
             });
         })
     }
@@ -1,9 +1,11 @@
 //! Convenient struct for using the parameters of the Base VLDP scheme.
 
+use super::{check_gamma, GammaConversion, SetupReport, SharedPrimitives};
 use crate::prelude::*;
-use astro_float::{BigFloat, Consts, Radix, RoundingMode};
+use crate::primitives::ldp::bin_boundaries;
+use ark_ff::PrimeField;
+use astro_float::BigFloat;
 use num_bigint::BigUint;
-use std::str::FromStr;
 
 pub mod constraints;
 pub use constraints::*;
@@ -17,46 +19,147 @@ pub struct ParametersBase<Conf: Config, const GAMMA_BYTES: usize> {
     pub client_commitment_scheme: ClientCommitmentSchemeParameters<Conf>,
     pub server_signature_scheme: ServerSignatureSchemeParameters<Conf>,
     pub client_signature_scheme: ClientSignatureSchemeParameters<Conf>,
+    /// Parameters of the hash that is applied to the input value message before signing, if any.
+    pub client_input_hash_scheme: Option<ClientInputHashParameters<Conf>>,
+    /// Whether gamma is a public input of the circuit rather than a constant, such that each
+    /// client can choose its own gamma (see `with_public_gamma`).
+    pub gamma_is_public: bool,
+    /// Whether input values are signed, i.e., signed in two's complement and shifted into the
+    /// unsigned range of input values inside the circuit (see `with_signed_input`).
+    pub signed_input: bool,
+    gamma_conversion: GammaConversion,
 }
 
 impl<Conf: Config, const GAMMA_BYTES: usize> ParametersBase<Conf, GAMMA_BYTES> {
     /// Perform the setup of the Base scheme for the given value of gamma.
     /// This simply generates parameters for all cryptographic primitives.
     pub fn setup<R: Rng + CryptoRng>(gamma: BigFloat, rng: &mut R) -> Result<Self, Error> {
-        assert!(BigFloat::from(0) < gamma && gamma <= BigFloat::from(1));
+        check_gamma(&gamma)?;
+        float_conversion_precision("GAMMA_BYTES", GAMMA_BYTES)?;
         Ok(Self {
             gamma,
             client_commitment_scheme: Conf::ClientCommitmentScheme::setup(rng)?,
             server_signature_scheme: Conf::ServerSignatureScheme::setup(rng)?,
             client_signature_scheme: Conf::ClientSignatureScheme::setup(rng)?,
+            client_input_hash_scheme: None,
+            gamma_is_public: false,
+            signed_input: false,
+            gamma_conversion: GammaConversion::default(),
         })
     }
 
+    /// Perform the setup of the Base scheme for the given value of gamma, reusing the given
+    /// parameters of the primitives that all schemes have in common.
+    pub fn from_shared(gamma: BigFloat, shared: &SharedPrimitives<Conf>) -> Result<Self, Error> {
+        check_gamma(&gamma)?;
+        Ok(Self {
+            gamma,
+            client_commitment_scheme: shared.client_commitment_scheme.clone(),
+            server_signature_scheme: shared.server_signature_scheme.clone(),
+            client_signature_scheme: shared.client_signature_scheme.clone(),
+            client_input_hash_scheme: None,
+            gamma_is_public: false,
+            signed_input: false,
+            gamma_conversion: GammaConversion::default(),
+        })
+    }
+
+    /// Perform the setup like `setup`, and additionally report the gamma that is realized by its
+    /// byte encoding (see `SetupReport`). The report flags a precision loss if the realized gamma
+    /// differs from `gamma` by more than `tolerance`.
+    pub fn setup_with_report<R: Rng + CryptoRng>(
+        gamma: BigFloat,
+        tolerance: &BigFloat,
+        rng: &mut R,
+    ) -> Result<(Self, SetupReport), Error> {
+        let parameters = Self::setup(gamma, rng)?;
+        let report = parameters.setup_report(tolerance)?;
+        Ok((parameters, report))
+    }
+
+    /// Report the gamma that is realized by the byte encoding of these parameters (see
+    /// `setup_with_report`).
+    pub fn setup_report(&self, tolerance: &BigFloat) -> Result<SetupReport, Error> {
+        SetupReport::new(&self.gamma, &self.gamma_as_bytes()?, tolerance)
+    }
+
+    /// Change the privacy level to the given value of gamma, keeping the parameters of all
+    /// cryptographic primitives, such that the signature keys of clients and server stay valid.
+    /// Unless gamma is public (see `with_public_gamma`), gamma is a constant of the circuit, so the
+    /// circuit keys have to be generated again.
+    pub fn with_gamma(mut self, gamma: BigFloat) -> Result<Self, Error> {
+        check_gamma(&gamma)?;
+        self.gamma = gamma;
+        Ok(self)
+    }
+
+    /// Let clients sign a hash of the input value message instead of the message itself (see
+    /// `InputValueSignatureInput::signed_bytes`). The in-circuit signature verification then only
+    /// hashes a fixed-size digest, which saves constraints for large `INPUT_BYTES`.
+    pub fn with_hashed_input<R: Rng + CryptoRng>(mut self, rng: &mut R) -> Result<Self, Error> {
+        self.client_input_hash_scheme = Some(Conf::ClientInputHash::setup(rng)?);
+        Ok(self)
+    }
+
+    /// Allocate gamma as a public input of the circuit instead of a constant. The keys of the
+    /// circuit then do not depend on gamma, such that clients with different gammas (set up via
+    /// `from_shared`) can prove against the same keys, and each client reveals its gamma to the
+    /// server along with its LDP value.
+    ///
+    /// The circuit compares the public gamma with the randomness by `is_cmp_unchecked`, which is
+    /// only sound for values below half the field modulus, so this fails with
+    /// `GenericError::InvalidSchemeParameters` unless `GAMMA_BYTES` bytes stay below that bound.
+    pub fn with_public_gamma(mut self) -> Result<Self, Error> {
+        // values of at most `MODULUS_BIT_SIZE - 2` bits lie below half the modulus
+        let max_bits = ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize - 2;
+        if 8 * GAMMA_BYTES > max_bits {
+            Err(GenericError::InvalidSchemeParameters(format!(
+                "a public gamma of GAMMA_BYTES = {} exceeds the {} bits that can be compared in \
+                 the circuit",
+                GAMMA_BYTES, max_bits
+            )))?
+        }
+        self.gamma_is_public = true;
+        Ok(self)
+    }
+
+    /// Accept signed input values centered at zero. Clients sign their input value in two's
+    /// complement (see `signed_input_bytes`) and pass it to the Randomize step shifted by
+    /// `signed_input_offset` (see `encode_signed_input`), which the circuit enforces. The server
+    /// shifts the decoded range of an LDP value back (see `ServerBase::decoded_range`).
+    pub fn with_signed_input(mut self) -> Self {
+        self.signed_input = true;
+        self
+    }
+
+    /// Convert gamma to bytes with the given precision and rounding mode instead of the default
+    /// ones (see `GammaConversion`), e.g., to study the bias of the rounding. Unless gamma is
+    /// public, the circuit keys have to be generated again.
+    pub fn with_gamma_conversion(mut self, gamma_conversion: GammaConversion) -> Self {
+        self.gamma_conversion = gamma_conversion;
+        self
+    }
+
+    /// Get the conversion of gamma to bytes of these parameters.
+    pub fn gamma_conversion(&self) -> GammaConversion {
+        self.gamma_conversion
+    }
+
     /// Transform a floating point value of gamma to a byte array in a deterministic way, with
-    /// as much precision as possible. This is needed for encoding inside the ZKP circuit.
+    /// the configured precision and rounding mode (see `with_gamma_conversion`). This is needed for
+    /// encoding inside the ZKP circuit.
     pub fn gamma_as_bytes(&self) -> Result<[u8; GAMMA_BYTES], Error> {
-        let precision = GAMMA_BYTES * 8 * 2;
-        let mut gamma = self.gamma.clone();
-        gamma.set_precision(precision, RoundingMode::Down)?;
-        let gamma_as_int = gamma
-            .mul_full_prec(
-                &BigFloat::from_u8(2, precision)
-                    .powi(GAMMA_BYTES * 8, precision, RoundingMode::Down)
-                    .sub_full_prec(&BigFloat::from_u8(1, precision)),
-            )
-            .int();
-        let gamma_as_str = gamma_as_int
-            .convert_to_radix(
-                Radix::Dec,
-                RoundingMode::None,
-                &mut Consts::new().expect("Constants cache initialization should not fail."),
-            )?
-            .1
-            .iter()
-            .map(|digit| digit.to_string())
-            .collect::<String>();
-        let mut bytes = [0; GAMMA_BYTES];
-        bytes.copy_from_slice(&BigUint::from_str(&gamma_as_str)?.to_bytes_le());
-        Ok(bytes)
+        self.gamma_conversion.gamma_as_bytes(&self.gamma)
+    }
+
+    /// Boundaries of the intervals of random values that `CircuitBase` maps to the LDP values,
+    /// e.g., to analyse the choice of `K`: LDP value `v` is drawn for the random values in
+    /// `[bins[i], bins[i + 1])` with `i = v` (real input) or `i = v - 1` (histogram input). The
+    /// boundaries start at 0, end at `2^(8 * INPUT_BYTES)`, and are computed with the same
+    /// `boundary_gap` as inside the circuit.
+    pub fn bin_boundaries<const INPUT_BYTES: usize, const K: u64, const IS_REAL_INPUT: bool>(
+        &self,
+    ) -> Vec<BigUint> {
+        bin_boundaries::<INPUT_BYTES, K, IS_REAL_INPUT>()
     }
 }
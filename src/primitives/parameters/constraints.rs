@@ -45,3 +45,82 @@ impl<Conf: Config, const N: usize> AllocVar<[u8; N], ConstraintField<Conf>> for
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::GammaVar;
+    use crate::config::BasicConfig;
+    use crate::prelude::{constraints::*, *};
+    use crate::primitives::constant_time::ct_less_or_equal;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_relations::r1cs::ConstraintSystem;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+
+    /// Little-endian bytes of `value`, which must fit into `N` bytes.
+    fn to_bytes<const N: usize>(value: &BigUint) -> [u8; N] {
+        let mut bytes = [0; N];
+        let value_bytes = value.to_bytes_le();
+        bytes[0..value_bytes.len()].copy_from_slice(&value_bytes);
+        bytes
+    }
+
+    /// Assert that the LDP bit of the circuit agrees with the clear computation of `apply_ldp`
+    /// for all pairs of the given values (and for random pairs).
+    fn assert_ldp_bit_agrees<const GAMMA_BYTES: usize>(rng: &mut ChaChaRng) {
+        let max = BigUint::from_bytes_le(&[u8::MAX; GAMMA_BYTES]);
+        let mut values = vec![[0; GAMMA_BYTES], [u8::MAX; GAMMA_BYTES]];
+        for _ in 0..8 {
+            let mut value = [0; GAMMA_BYTES];
+            rng.fill(&mut value[..]);
+            let value_int = BigUint::from_bytes_le(&value);
+            values.push(value);
+            // the neighbors of the boundary randomness == gamma
+            if value_int < max {
+                values.push(to_bytes(&(&value_int + 1_u8)));
+            }
+            if value_int > BigUint::from(0_u8) {
+                values.push(to_bytes(&(&value_int - 1_u8)));
+            }
+        }
+
+        for gamma_bytes in &values {
+            for randomness in &values {
+                let expected = ct_less_or_equal(randomness, gamma_bytes) == 1;
+                assert_eq!(
+                    expected,
+                    BigUint::from_bytes_le(randomness) <= BigUint::from_bytes_le(gamma_bytes)
+                );
+
+                let cs = ConstraintSystem::<ConstraintField<Conf>>::new_ref();
+                let gamma = GammaVar::<Conf>::new_witness(cs.clone(), || Ok(*gamma_bytes)).unwrap();
+                let randomness_var = UInt8::new_witness_vec(cs.clone(), randomness).unwrap();
+                let ldp_bit = gamma.compute_ldp_bit(&randomness_var).unwrap();
+                assert_eq!(
+                    ldp_bit.value().unwrap(),
+                    expected,
+                    "GAMMA_BYTES = {}, randomness = {:?}, gamma = {:?}",
+                    GAMMA_BYTES,
+                    randomness,
+                    gamma_bytes
+                );
+                assert!(cs.is_satisfied().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn ldp_bit_agrees_with_clear_computation() {
+        let rng = &mut ChaChaRng::from_seed([133; 32]);
+        assert_ldp_bit_agrees::<1>(rng);
+        assert_ldp_bit_agrees::<2>(rng);
+        assert_ldp_bit_agrees::<8>(rng);
+        // the largest width whose values stay below half the constraint field
+        assert_ldp_bit_agrees::<31>(rng);
+    }
+}
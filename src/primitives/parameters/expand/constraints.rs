@@ -13,6 +13,7 @@ pub struct ParametersExpandVar<Conf: Config, ConfG: ConfigGadget<Conf>> {
     #[allow(dead_code)]
     pub server_signature_scheme: ServerSignatureSchemeParametersVar<Conf, ConfG>,
     pub client_signature_scheme: ClientSignatureSchemeParametersVar<Conf, ConfG>,
+    pub client_input_hash_scheme: Option<ClientInputHashParametersVar<Conf, ConfG>>,
     pub client_merkle_tree_scheme: ClientMerkleTreeParametersVar<Conf, ConfG>,
 }
 
@@ -56,6 +57,18 @@ impl<Conf: Config, ConfG: ConfigGadget<Conf>, const GAMMA_BYTES: usize>
                     || Ok(&val.borrow().client_signature_scheme),
                     mode,
                 )?;
+            let client_input_hash_scheme = val
+                .borrow()
+                .client_input_hash_scheme
+                .as_ref()
+                .map(|parameters| {
+                    ClientInputHashParametersVar::<_, ConfG>::new_variable(
+                        cs.clone(),
+                        || Ok(parameters),
+                        mode,
+                    )
+                })
+                .transpose()?;
             let client_merkle_tree_scheme =
                 ClientMerkleTreeParametersVar::<_, ConfG>::new_variable(
                     cs.clone(),
@@ -67,6 +80,7 @@ impl<Conf: Config, ConfG: ConfigGadget<Conf>, const GAMMA_BYTES: usize>
                 client_commitment_scheme,
                 server_signature_scheme,
                 client_signature_scheme,
+                client_input_hash_scheme,
                 client_merkle_tree_scheme,
             });
         })
@@ -1,10 +1,9 @@
 //! Convenient struct for using the parameters of the Expand VLDP scheme.
 
+use super::{check_gamma, GammaConversion, SetupReport, SharedPrimitives};
 use crate::prelude::*;
 use ark_crypto_primitives::crh::CRHScheme;
-use astro_float::{BigFloat, Consts, Radix, RoundingMode};
-use num_bigint::BigUint;
-use std::str::FromStr;
+use astro_float::BigFloat;
 
 pub mod constraints;
 pub use constraints::*;
@@ -15,52 +14,127 @@ pub use constraints::*;
 #[derive(Clone)]
 pub struct ParametersExpand<Conf: Config, const GAMMA_BYTES: usize> {
     gamma: BigFloat,
+    // computed once during setup, as the conversion is needed for every proof
+    gamma_bytes: [u8; GAMMA_BYTES],
     pub client_commitment_scheme: ClientCommitmentSchemeParameters<Conf>,
     pub server_signature_scheme: ServerSignatureSchemeParameters<Conf>,
     pub client_signature_scheme: ClientSignatureSchemeParameters<Conf>,
+    /// Parameters of the hash that is applied to the input value message before signing, if any.
+    pub client_input_hash_scheme: Option<ClientInputHashParameters<Conf>>,
     pub client_merkle_tree_scheme: ClientMerkleTreeParameters<Conf>,
+    gamma_conversion: GammaConversion,
 }
 
 impl<Conf: Config, const GAMMA_BYTES: usize> ParametersExpand<Conf, GAMMA_BYTES> {
     /// Perform the setup of the Expand scheme for the given value of gamma.
     /// This simply generates parameters for all cryptographic primitives.
     pub fn setup<R: Rng + CryptoRng>(gamma: BigFloat, rng: &mut R) -> Result<Self, Error> {
-        assert!(BigFloat::from(0) < gamma && gamma <= BigFloat::from(1));
+        check_gamma(&gamma)?;
         Ok(Self {
+            gamma_bytes: Self::compute_gamma_as_bytes(&gamma)?,
             gamma,
             client_commitment_scheme: Conf::ClientCommitmentScheme::setup(rng)?,
             server_signature_scheme: Conf::ServerSignatureScheme::setup(rng)?,
             client_signature_scheme: Conf::ClientSignatureScheme::setup(rng)?,
+            client_input_hash_scheme: None,
             client_merkle_tree_scheme: ClientMerkleTreeParameters::setup(rng)?,
+            gamma_conversion: GammaConversion::default(),
         })
     }
 
-    /// Transform a floating point value of gamma to a byte array in a deterministic way, with
-    /// as much precision as possible. This is needed for encoding inside the ZKP circuit.
+    /// Perform the setup of the Expand scheme for the given value of gamma, reusing the given
+    /// parameters of the primitives that all schemes have in common. Only the Merkle tree
+    /// parameters are generated.
+    pub fn from_shared<R: Rng + CryptoRng>(
+        gamma: BigFloat,
+        shared: &SharedPrimitives<Conf>,
+        rng: &mut R,
+    ) -> Result<Self, Error> {
+        check_gamma(&gamma)?;
+        Ok(Self {
+            gamma_bytes: Self::compute_gamma_as_bytes(&gamma)?,
+            gamma,
+            client_commitment_scheme: shared.client_commitment_scheme.clone(),
+            server_signature_scheme: shared.server_signature_scheme.clone(),
+            client_signature_scheme: shared.client_signature_scheme.clone(),
+            client_input_hash_scheme: None,
+            client_merkle_tree_scheme: ClientMerkleTreeParameters::setup(rng)?,
+            gamma_conversion: GammaConversion::default(),
+        })
+    }
+
+    /// Perform the setup like `setup`, and additionally report the gamma that is realized by its
+    /// byte encoding (see `SetupReport`). The report flags a precision loss if the realized gamma
+    /// differs from `gamma` by more than `tolerance`.
+    pub fn setup_with_report<R: Rng + CryptoRng>(
+        gamma: BigFloat,
+        tolerance: &BigFloat,
+        rng: &mut R,
+    ) -> Result<(Self, SetupReport), Error> {
+        let parameters = Self::setup(gamma, rng)?;
+        let report = parameters.setup_report(tolerance)?;
+        Ok((parameters, report))
+    }
+
+    /// Report the gamma that is realized by the byte encoding of these parameters (see
+    /// `setup_with_report`).
+    pub fn setup_report(&self, tolerance: &BigFloat) -> Result<SetupReport, Error> {
+        SetupReport::new(&self.gamma, &self.gamma_as_bytes()?, tolerance)
+    }
+
+    /// Change the privacy level to the given value of gamma, keeping the parameters of all
+    /// cryptographic primitives (including the Merkle tree), such that the signature keys of
+    /// clients and server stay valid. As gamma is a constant of the circuit, the circuit keys have
+    /// to be generated again.
+    pub fn with_gamma(mut self, gamma: BigFloat) -> Result<Self, Error> {
+        check_gamma(&gamma)?;
+        self.gamma_bytes = self.gamma_conversion.gamma_as_bytes(&gamma)?;
+        self.gamma = gamma;
+        Ok(self)
+    }
+
+    /// Convert gamma to bytes with the given precision and rounding mode instead of the default
+    /// ones (see `GammaConversion`), e.g., to study the bias of the rounding. The stored bytes of
+    /// gamma are computed again; as gamma is a constant of the circuit, the circuit keys have to
+    /// be generated again.
+    pub fn with_gamma_conversion(
+        mut self,
+        gamma_conversion: GammaConversion,
+    ) -> Result<Self, Error> {
+        self.gamma_bytes = gamma_conversion.gamma_as_bytes(&self.gamma)?;
+        self.gamma_conversion = gamma_conversion;
+        Ok(self)
+    }
+
+    /// Get the conversion of gamma to bytes of these parameters.
+    pub fn gamma_conversion(&self) -> GammaConversion {
+        self.gamma_conversion
+    }
+
+    /// Let clients sign a hash of the input value message instead of the message itself (see
+    /// `InputValueSignatureInput::signed_bytes`). The in-circuit signature verification then only
+    /// hashes a fixed-size digest, which saves constraints for large `INPUT_BYTES`.
+    pub fn with_hashed_input<R: Rng + CryptoRng>(mut self, rng: &mut R) -> Result<Self, Error> {
+        self.client_input_hash_scheme = Some(Conf::ClientInputHash::setup(rng)?);
+        Ok(self)
+    }
+
+    /// Get the value of gamma these parameters were set up with.
+    pub fn gamma(&self) -> &BigFloat {
+        &self.gamma
+    }
+
+    /// Get gamma as a byte array, as computed by `compute_gamma_as_bytes` during setup.
+    /// This is needed for encoding inside the ZKP circuit.
     pub fn gamma_as_bytes(&self) -> Result<[u8; GAMMA_BYTES], Error> {
-        let precision = GAMMA_BYTES * 8 * 2;
-        let mut gamma = self.gamma.clone();
-        gamma.set_precision(precision, RoundingMode::Down)?;
-        let gamma_as_int = gamma
-            .mul_full_prec(
-                &BigFloat::from_u8(2, precision)
-                    .powi(GAMMA_BYTES * 8, precision, RoundingMode::Down)
-                    .sub_full_prec(&BigFloat::from_u8(1, precision)),
-            )
-            .int();
-        let gamma_as_str = gamma_as_int
-            .convert_to_radix(
-                Radix::Dec,
-                RoundingMode::None,
-                &mut Consts::new().expect("Constants cache initialization should not fail."),
-            )?
-            .1
-            .iter()
-            .map(|digit| digit.to_string())
-            .collect::<String>();
-        let mut bytes = [0; GAMMA_BYTES];
-        bytes.copy_from_slice(&BigUint::from_str(&gamma_as_str)?.to_bytes_le());
-        Ok(bytes)
+        Ok(self.gamma_bytes)
+    }
+
+    /// Transform a floating point value of gamma to a byte array in a deterministic way, with the
+    /// default precision and rounding mode (see `GammaConversion`). This is performed once during
+    /// setup; use `gamma_as_bytes` to get the stored result.
+    pub fn compute_gamma_as_bytes(gamma: &BigFloat) -> Result<[u8; GAMMA_BYTES], Error> {
+        GammaConversion::default().gamma_as_bytes(gamma)
     }
 }
 
@@ -83,3 +157,118 @@ impl<Conf: Config> ClientMerkleTreeParameters<Conf> {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::config::BasicConfig;
+    use crate::prelude::*;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use astro_float::{BigFloat, RoundingMode};
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+
+    #[test]
+    fn cached_gamma_bytes_equal_fresh_computation() {
+        let rng = &mut ChaChaRng::from_seed([15; 32]);
+        for gamma in [0.5, 0.25, 0.9, 1.0] {
+            let gamma = BigFloat::from_f64(gamma, 64);
+            let parameters = ParametersExpand::<Conf, 8>::setup(gamma.clone(), rng).unwrap();
+            assert_eq!(
+                parameters.gamma_as_bytes().unwrap(),
+                ParametersExpand::<Conf, 8>::compute_gamma_as_bytes(&gamma).unwrap()
+            );
+            // the uncached conversion of the other schemes agrees
+            assert_eq!(
+                parameters.gamma_as_bytes().unwrap(),
+                ParametersBase::<Conf, 8>::setup(gamma.clone(), rng)
+                    .unwrap()
+                    .gamma_as_bytes()
+                    .unwrap()
+            );
+            assert_eq!(parameters.gamma(), &gamma);
+
+            // changing gamma recomputes the cached bytes
+            let other_gamma = BigFloat::from_f64(0.1, 64);
+            let parameters = parameters.with_gamma(other_gamma.clone()).unwrap();
+            assert_eq!(
+                parameters.gamma_as_bytes().unwrap(),
+                ParametersExpand::<Conf, 8>::compute_gamma_as_bytes(&other_gamma).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn setup_report_flags_gamma_precision_loss() {
+        let rng = &mut ChaChaRng::from_seed([68; 32]);
+        let tolerance = BigFloat::from_f64(1e-4, 64);
+
+        // with a single byte, 0.3 is encoded as 76 / 255
+        let (_, report) = ParametersBase::<Conf, 1>::setup_with_report(
+            BigFloat::from_f64(0.3, 64),
+            &tolerance,
+            rng,
+        )
+        .unwrap();
+        assert!(report.precision_loss);
+        assert_eq!(
+            report.realized_gamma,
+            BigFloat::from_u8(76, 16).div(&BigFloat::from_u8(255, 16), 16, RoundingMode::ToEven)
+        );
+        assert_eq!(report.gamma, BigFloat::from_f64(0.3, 64));
+
+        // eight bytes encode 0.3 within the tolerance, and 1 is encoded exactly
+        for (gamma, tolerance) in [(0.3, tolerance), (1.0, BigFloat::from_u8(0, 64))] {
+            let gamma = BigFloat::from_f64(gamma, 64);
+            let (_, report) =
+                ParametersExpand::<Conf, 8>::setup_with_report(gamma.clone(), &tolerance, rng)
+                    .unwrap();
+            assert!(!report.precision_loss);
+            assert!(
+                !ParametersShuffle::<Conf, 8>::setup(gamma, rng)
+                    .unwrap()
+                    .setup_report(&tolerance)
+                    .unwrap()
+                    .precision_loss
+            );
+        }
+    }
+
+    #[test]
+    fn excessive_gamma_bytes_are_rejected() {
+        let rng = &mut ChaChaRng::from_seed([26; 32]);
+        let gamma = BigFloat::from_f64(0.5, 64);
+        let error = ParametersExpand::<Conf, 4096>::setup(gamma.clone(), rng)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            VldpError::Generic(GenericError::ExcessivePrecision {
+                parameter: "GAMMA_BYTES",
+                bytes: 4096,
+                max_bytes: MAX_FLOAT_BYTES,
+            })
+        ));
+        assert!(ParametersBase::<Conf, 4096>::setup(gamma.clone(), rng).is_err());
+        assert!(ParametersShuffle::<Conf, 4096>::setup(gamma.clone(), rng).is_err());
+        assert!(ParametersExpand::<Conf, MAX_FLOAT_BYTES>::setup(gamma, rng).is_ok());
+    }
+
+    #[test]
+    fn nan_gamma_is_reported() {
+        // setup rejects an invalid gamma, but the conversion itself must not panic on NaN either
+        let error = ParametersExpand::<Conf, 8>::compute_gamma_as_bytes(&BigFloat::nan(None))
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            VldpError::Generic(GenericError::FloatError(
+                astro_float::Error::InvalidArgument
+            ))
+        ));
+        assert!(error.to_string().contains("floating point"));
+    }
+}
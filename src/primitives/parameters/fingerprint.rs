@@ -0,0 +1,96 @@
+//! Fingerprints of scheme parameters: a deployment can publish the fingerprint of its canonical
+//! parameters, such that every participant can check that it was given the same parameter set.
+
+use crate::prelude::*;
+use crate::primitives::signature::schnorr;
+use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
+use ark_crypto_primitives::{commitment, crh};
+use ark_ec::CurveGroup;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use blake2::Digest;
+
+/// Domain tag that is prepended to the encoding of every parameter set before it is hashed.
+pub const PARAMETERS_FINGERPRINT_DOMAIN_TAG: [u8; 8] = *b"VLDP-PAR";
+
+/// Fingerprint of a parameter set: the Blake2s hash of its canonical encoding.
+pub type ParametersFingerprint = [u8; 32];
+
+/// Parameters of a cryptographic primitive that can be encoded into a parameters fingerprint.
+pub trait FingerprintParameters {
+    /// Append a canonical encoding of these parameters to `bytes`.
+    fn write_fingerprint_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), Error>;
+}
+
+// primitives without parameters (e.g., Blake2s or Ed25519)
+impl FingerprintParameters for () {
+    fn write_fingerprint_bytes(&self, _bytes: &mut Vec<u8>) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<T: FingerprintParameters> FingerprintParameters for Option<T> {
+    fn write_fingerprint_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), Error> {
+        match self {
+            None => bytes.push(0),
+            Some(parameters) => {
+                bytes.push(1);
+                parameters.write_fingerprint_bytes(bytes)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<C: CurveGroup> FingerprintParameters for commitment::pedersen::Parameters<C> {
+    fn write_fingerprint_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), Error> {
+        self.randomness_generator
+            .serialize_compressed(&mut *bytes)?;
+        self.generators.serialize_compressed(&mut *bytes)?;
+        Ok(())
+    }
+}
+
+impl<C: CurveGroup> FingerprintParameters for crh::pedersen::Parameters<C> {
+    fn write_fingerprint_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), Error> {
+        self.generators.serialize_compressed(&mut *bytes)?;
+        Ok(())
+    }
+}
+
+impl<F: PrimeField> FingerprintParameters for PoseidonConfig<F> {
+    fn write_fingerprint_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), Error> {
+        for value in [
+            self.full_rounds,
+            self.partial_rounds,
+            self.rate,
+            self.capacity,
+        ] {
+            bytes.extend_from_slice(&(value as u64).to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.alpha.to_le_bytes());
+        self.ark.serialize_compressed(&mut *bytes)?;
+        self.mds.serialize_compressed(&mut *bytes)?;
+        Ok(())
+    }
+}
+
+impl<C: CurveGroup, H: CRHScheme> FingerprintParameters for schnorr::Parameters<C, H>
+where
+    H::Parameters: FingerprintParameters + Send + Sync,
+{
+    fn write_fingerprint_bytes(&self, bytes: &mut Vec<u8>) -> Result<(), Error> {
+        self.hash_params.write_fingerprint_bytes(bytes)?;
+        self.generator.serialize_compressed(&mut *bytes)?;
+        bytes.extend_from_slice(&self.salt);
+        bytes.extend_from_slice(&self.domain);
+        Ok(())
+    }
+}
+
+/// Hash the encoding of a parameter set into its fingerprint.
+pub(crate) fn fingerprint(bytes: &[u8]) -> ParametersFingerprint {
+    let mut fingerprint = [0; 32];
+    fingerprint.copy_from_slice(&blake2::Blake2s256::digest(bytes));
+    fingerprint
+}
@@ -10,6 +10,285 @@ pub use expand::*;
 pub mod shuffle;
 pub use shuffle::*;
 
+pub mod shared;
+pub use shared::*;
+
+pub mod fingerprint;
+pub use fingerprint::*;
+
 // shared structs to prevent duplication
 pub mod constraints;
 pub use constraints::*;
+
+use crate::prelude::*;
+use astro_float::{BigFloat, Consts, Radix, RoundingMode};
+use num_bigint::BigUint;
+use std::str::FromStr;
+
+/// Maximum supported `GAMMA_BYTES` and `INPUT_BYTES`. Gamma and real input values are converted to
+/// bytes with a float precision of twice their bit width, which makes astro-float slow and
+/// memory-hungry for large widths. Note that widths beyond the size of the constraint field are
+/// not supported by the circuits anyway.
+pub const MAX_FLOAT_BYTES: usize = 64;
+
+/// Float precision (in bits) for converting a value in `[0, 1]` to `num_bytes` bytes. Returns an
+/// `ExcessivePrecision` error if `num_bytes` exceeds `MAX_FLOAT_BYTES`, where `parameter` names
+/// the offending parameter.
+pub fn float_conversion_precision(
+    parameter: &'static str,
+    num_bytes: usize,
+) -> Result<usize, Error> {
+    if num_bytes > MAX_FLOAT_BYTES {
+        Err(GenericError::ExcessivePrecision {
+            parameter,
+            bytes: num_bytes,
+            max_bytes: MAX_FLOAT_BYTES,
+        })?
+    }
+    Ok(num_bytes * 8 * 2)
+}
+
+/// Check that `gamma` lies in `(0, 1]`, as required by the parameters of every scheme. Returns an
+/// `InvalidSchemeParameters` error otherwise.
+pub fn check_gamma(gamma: &BigFloat) -> Result<(), Error> {
+    if !(BigFloat::from(0) < *gamma && *gamma <= BigFloat::from(1)) {
+        Err(GenericError::InvalidSchemeParameters(format!(
+            "gamma = {} is not in (0, 1]",
+            gamma
+        )))?
+    }
+    Ok(())
+}
+
+/// Configuration of the conversion of gamma to its byte encoding (see `gamma_as_bytes` of the
+/// parameters of each scheme): gamma is rounded to `precision` bits with `rounding_mode`, then
+/// multiplied by `2^(8 * GAMMA_BYTES) - 1` and truncated to an integer. The result only depends on
+/// gamma and this configuration, so the gamma constant of the circuits is reproducible.
+///
+/// Note that astro-float rounds precisions up to a multiple of its word size (64 bits on 64-bit
+/// targets), so the rounding mode only matters once the precision is exhausted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GammaConversion {
+    /// Float precision in bits, or `None` for `float_conversion_precision` of `GAMMA_BYTES`
+    pub precision: Option<usize>,
+    /// Rounding mode for rounding gamma to the precision
+    pub rounding_mode: RoundingMode,
+}
+
+impl Default for GammaConversion {
+    /// The conversion used for all parameters unless configured otherwise: the default precision,
+    /// rounding down.
+    fn default() -> Self {
+        Self {
+            precision: None,
+            rounding_mode: RoundingMode::Down,
+        }
+    }
+}
+
+impl GammaConversion {
+    /// Convert `gamma` to `GAMMA_BYTES` little-endian bytes.
+    pub fn gamma_as_bytes<const GAMMA_BYTES: usize>(
+        &self,
+        gamma: &BigFloat,
+    ) -> Result<[u8; GAMMA_BYTES], Error> {
+        // the scaling factor is exact at the default precision, whatever precision gamma has
+        let scale_precision = float_conversion_precision("GAMMA_BYTES", GAMMA_BYTES)?;
+        let max_precision = float_conversion_precision("MAX_FLOAT_BYTES", MAX_FLOAT_BYTES)?;
+        let precision = self.precision.unwrap_or(scale_precision);
+        if precision == 0 || precision > max_precision {
+            Err(GenericError::InvalidSchemeParameters(format!(
+                "the gamma precision of {} bits is not in [1, {}]",
+                precision, max_precision
+            )))?
+        }
+        let mut gamma = gamma.clone();
+        gamma
+            .set_precision(precision, self.rounding_mode)
+            .map_err(GenericError::from)?;
+        let gamma_as_int = gamma
+            .mul_full_prec(
+                &BigFloat::from_u8(2, scale_precision)
+                    .powi(GAMMA_BYTES * 8, scale_precision, RoundingMode::Down)
+                    .sub_full_prec(&BigFloat::from_u8(1, scale_precision)),
+            )
+            .int();
+        let gamma_as_str = gamma_as_int
+            .convert_to_radix(
+                Radix::Dec,
+                RoundingMode::None,
+                &mut Consts::new().map_err(GenericError::from)?,
+            )
+            .map_err(GenericError::from)?
+            .1
+            .iter()
+            .map(|digit| digit.to_string())
+            .collect::<String>();
+        let mut bytes = [0; GAMMA_BYTES];
+        // no digits for a gamma that is truncated to zero
+        if !gamma_as_str.is_empty() {
+            let gamma_bytes = BigUint::from_str(&gamma_as_str)?.to_bytes_le();
+            bytes[0..gamma_bytes.len()].copy_from_slice(&gamma_bytes);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Report of the setup of a scheme (see `ParametersBase::setup_with_report`), which states the
+/// gamma that the circuits actually use. `gamma_as_bytes` truncates gamma to `GAMMA_BYTES` bytes,
+/// so the realized gamma may be (slightly) smaller than the intended one.
+#[derive(Clone, Debug)]
+pub struct SetupReport {
+    /// Gamma the setup was performed for
+    pub gamma: BigFloat,
+    /// Gamma as encoded by `gamma_as_bytes`, i.e., the bytes reinterpreted as a fraction
+    pub realized_gamma: BigFloat,
+    /// Whether the realized gamma differs from the intended one by more than the tolerance
+    pub precision_loss: bool,
+}
+
+impl SetupReport {
+    /// Compare `gamma` with the gamma realized by its byte encoding `gamma_bytes`.
+    pub(crate) fn new<const GAMMA_BYTES: usize>(
+        gamma: &BigFloat,
+        gamma_bytes: &[u8; GAMMA_BYTES],
+        tolerance: &BigFloat,
+    ) -> Result<Self, Error> {
+        let realized_gamma = realized_gamma(gamma_bytes)?;
+        let precision = float_conversion_precision("GAMMA_BYTES", GAMMA_BYTES)?;
+        let difference = gamma.sub(&realized_gamma, precision, RoundingMode::None);
+        Ok(Self {
+            gamma: gamma.clone(),
+            precision_loss: difference
+                .abs_cmp(tolerance)
+                .ok_or(GenericError::FloatError(
+                    astro_float::Error::InvalidArgument,
+                ))?
+                > 0,
+            realized_gamma,
+        })
+    }
+}
+
+/// Gamma that is realized by its byte encoding `gamma_bytes` (see `ParametersBase::gamma_as_bytes`),
+/// i.e., the encoded integer divided by `2^(8 * GAMMA_BYTES) - 1`.
+pub fn realized_gamma<const GAMMA_BYTES: usize>(
+    gamma_bytes: &[u8; GAMMA_BYTES],
+) -> Result<BigFloat, Error> {
+    let precision = float_conversion_precision("GAMMA_BYTES", GAMMA_BYTES)?;
+    let gamma_as_int = BigFloat::parse(
+        &BigUint::from_bytes_le(gamma_bytes).to_string(),
+        Radix::Dec,
+        precision,
+        RoundingMode::None,
+        &mut Consts::new().map_err(GenericError::from)?,
+    );
+    Ok(gamma_as_int.div(
+        &BigFloat::from_u8(2, precision)
+            .powi(GAMMA_BYTES * 8, precision, RoundingMode::Down)
+            .sub_full_prec(&BigFloat::from_u8(1, precision)),
+        precision,
+        RoundingMode::ToEven,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::BasicConfig;
+    use crate::prelude::*;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use astro_float::{BigFloat, RoundingMode};
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+
+    #[test]
+    fn gamma_conversion_is_configurable_and_deterministic() {
+        let rng = &mut ChaChaRng::from_seed([132; 32]);
+        // 32767 / 65535 has a periodic binary expansion, so rounding it to a finite precision
+        // lands just below or above the exact value
+        let gamma = BigFloat::from_u32(32767, 256).div(
+            &BigFloat::from_u32(65535, 256),
+            256,
+            RoundingMode::ToEven,
+        );
+        let conversion = |rounding_mode| GammaConversion {
+            precision: Some(64),
+            rounding_mode,
+        };
+        let down = conversion(RoundingMode::Down)
+            .gamma_as_bytes::<2>(&gamma)
+            .unwrap();
+        let up = conversion(RoundingMode::Up)
+            .gamma_as_bytes::<2>(&gamma)
+            .unwrap();
+        assert_eq!(u16::from_le_bytes(down), 32766);
+        assert_eq!(u16::from_le_bytes(up), 32767);
+
+        // the default conversion rounds down, for all schemes alike
+        let base = ParametersBase::<Conf, 2>::setup(gamma.clone(), rng).unwrap();
+        let expand = ParametersExpand::<Conf, 2>::setup(gamma.clone(), rng).unwrap();
+        let shuffle = ParametersShuffle::<Conf, 2>::setup(gamma.clone(), rng).unwrap();
+        assert_eq!(base.gamma_as_bytes().unwrap(), down);
+        assert_eq!(expand.gamma_as_bytes().unwrap(), down);
+        assert_eq!(shuffle.gamma_as_bytes().unwrap(), down);
+
+        // the configured conversion is used by the parameters of all schemes
+        let base = base.with_gamma_conversion(conversion(RoundingMode::Up));
+        let expand = expand
+            .with_gamma_conversion(conversion(RoundingMode::Up))
+            .unwrap();
+        let shuffle = shuffle.with_gamma_conversion(conversion(RoundingMode::Up));
+        assert_eq!(base.gamma_as_bytes().unwrap(), up);
+        assert_eq!(expand.gamma_as_bytes().unwrap(), up);
+        assert_eq!(shuffle.gamma_as_bytes().unwrap(), up);
+        assert_eq!(
+            base.gamma_as_bytes().unwrap(),
+            base.gamma_as_bytes().unwrap()
+        );
+
+        assert!(GammaConversion {
+            precision: Some(0),
+            rounding_mode: RoundingMode::Down,
+        }
+        .gamma_as_bytes::<2>(&gamma)
+        .is_err());
+    }
+
+    #[test]
+    fn small_gamma_is_encoded_in_fewer_bytes_than_gamma_bytes() {
+        let rng = &mut ChaChaRng::from_seed([133; 32]);
+        // 0.003 * (2^16 - 1) = 196.6, which fits into a single byte
+        let gamma = BigFloat::from_f64(0.003, 64);
+        let base = ParametersBase::<Conf, 2>::setup(gamma.clone(), rng).unwrap();
+        let expand = ParametersExpand::<Conf, 2>::setup(gamma.clone(), rng).unwrap();
+        let shuffle = ParametersShuffle::<Conf, 2>::setup(gamma.clone(), rng).unwrap();
+        assert_eq!(base.gamma_as_bytes().unwrap(), [196, 0]);
+        assert_eq!(expand.gamma_as_bytes().unwrap(), [196, 0]);
+        assert_eq!(shuffle.gamma_as_bytes().unwrap(), [196, 0]);
+        assert!(base.setup_report(&BigFloat::from_f64(0.001, 64)).is_ok());
+    }
+
+    #[test]
+    fn gamma_outside_of_unit_interval_is_rejected() {
+        let rng = &mut ChaChaRng::from_seed([134; 32]);
+        let shared = SharedPrimitives::<Conf>::setup(rng).unwrap();
+        for gamma in [0.0, -0.5, 1.5] {
+            let gamma = BigFloat::from_f64(gamma, 64);
+            assert!(matches!(
+                ParametersBase::<Conf, 2>::setup(gamma.clone(), rng),
+                Err(VldpError::Generic(GenericError::InvalidSchemeParameters(_)))
+            ));
+            assert!(ParametersExpand::<Conf, 2>::setup(gamma.clone(), rng).is_err());
+            assert!(ParametersShuffle::<Conf, 2>::setup(gamma.clone(), rng).is_err());
+            assert!(ParametersBase::<Conf, 2>::from_shared(gamma.clone(), &shared).is_err());
+            assert!(ParametersExpand::<Conf, 2>::from_shared(gamma.clone(), &shared, rng).is_err());
+            assert!(ParametersShuffle::<Conf, 2>::from_shared(gamma.clone(), &shared).is_err());
+        }
+        let base = ParametersBase::<Conf, 2>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        assert!(base.with_gamma(BigFloat::from_f64(2.0, 64)).is_err());
+    }
+}
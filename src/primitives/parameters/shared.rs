@@ -0,0 +1,87 @@
+//! Parameters of the cryptographic primitives that are used in all VLDP schemes (Base, Expand, and
+//! Shuffle), such that the schemes can be set up with identical primitives.
+
+use crate::prelude::*;
+
+/// Parameters of the primitives that all schemes have in common. Use the `from_shared`
+/// constructors of the scheme parameters to build these on top of them.
+#[derive(Clone)]
+pub struct SharedPrimitives<Conf: Config> {
+    pub client_commitment_scheme: ClientCommitmentSchemeParameters<Conf>,
+    pub server_signature_scheme: ServerSignatureSchemeParameters<Conf>,
+    pub client_signature_scheme: ClientSignatureSchemeParameters<Conf>,
+}
+
+impl<Conf: Config> SharedPrimitives<Conf> {
+    /// Generate parameters for the commitment and signature schemes.
+    /// The PRF does not have any parameters.
+    pub fn setup<R: Rng + CryptoRng>(rng: &mut R) -> Result<Self, Error> {
+        Ok(Self {
+            client_commitment_scheme: Conf::ClientCommitmentScheme::setup(rng)?,
+            server_signature_scheme: Conf::ServerSignatureScheme::setup(rng)?,
+            client_signature_scheme: Conf::ClientSignatureScheme::setup(rng)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SharedPrimitives;
+    use crate::config::BasicConfig;
+    use crate::prelude::*;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use astro_float::BigFloat;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+
+    #[test]
+    fn schemes_share_primitive_parameters() {
+        let rng = &mut ChaChaRng::from_seed([9; 32]);
+        let gamma = BigFloat::from_f64(0.5, 64);
+        let shared = SharedPrimitives::<Conf>::setup(rng).unwrap();
+        let base = ParametersBase::<Conf, 8>::from_shared(gamma.clone(), &shared).unwrap();
+        let expand = ParametersExpand::<Conf, 8>::from_shared(gamma.clone(), &shared, rng).unwrap();
+        let shuffle = ParametersShuffle::<Conf, 8>::from_shared(gamma, &shared).unwrap();
+
+        for (commitment, server_signature, client_signature) in [
+            (
+                &base.client_commitment_scheme,
+                &base.server_signature_scheme,
+                &base.client_signature_scheme,
+            ),
+            (
+                &expand.client_commitment_scheme,
+                &expand.server_signature_scheme,
+                &expand.client_signature_scheme,
+            ),
+            (
+                &shuffle.client_commitment_scheme,
+                &shuffle.server_signature_scheme,
+                &shuffle.client_signature_scheme,
+            ),
+        ] {
+            assert_eq!(
+                commitment.generators,
+                shared.client_commitment_scheme.generators
+            );
+            assert_eq!(
+                commitment.randomness_generator,
+                shared.client_commitment_scheme.randomness_generator
+            );
+            assert_eq!(
+                server_signature.generator,
+                shared.server_signature_scheme.generator
+            );
+            assert_eq!(server_signature.salt, shared.server_signature_scheme.salt);
+            assert_eq!(
+                client_signature.generator,
+                shared.client_signature_scheme.generator
+            );
+            assert_eq!(client_signature.salt, shared.client_signature_scheme.salt);
+        }
+    }
+}
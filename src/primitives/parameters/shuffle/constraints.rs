@@ -12,6 +12,7 @@ pub struct ParametersShuffleVar<Conf: Config, ConfG: ConfigGadget<Conf>> {
     pub client_commitment_scheme: ClientCommitmentSchemeParametersVar<Conf, ConfG>,
     pub server_signature_scheme: ServerSignatureSchemeParametersVar<Conf, ConfG>,
     pub client_signature_scheme: ClientSignatureSchemeParametersVar<Conf, ConfG>,
+    pub client_input_hash_scheme: Option<ClientInputHashParametersVar<Conf, ConfG>>,
 }
 
 // implement variable allocation of all parameters
@@ -54,11 +55,24 @@ impl<Conf: Config, ConfG: ConfigGadget<Conf>, const GAMMA_BYTES: usize>
                     || Ok(&val.borrow().client_signature_scheme),
                     mode,
                 )?;
+            let client_input_hash_scheme = val
+                .borrow()
+                .client_input_hash_scheme
+                .as_ref()
+                .map(|parameters| {
+                    ClientInputHashParametersVar::<_, ConfG>::new_variable(
+                        cs.clone(),
+                        || Ok(parameters),
+                        mode,
+                    )
+                })
+                .transpose()?;
             return Ok(Self {
                 gamma,
                 client_commitment_scheme,
                 server_signature_scheme,
                 client_signature_scheme,
+                client_input_hash_scheme,
             });
         })
     }
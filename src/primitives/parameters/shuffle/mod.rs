@@ -1,9 +1,11 @@
 //! Convenient struct for using the parameters of the Shuffle VLDP scheme.
 
+use super::{
+    check_gamma, fingerprint, FingerprintParameters, GammaConversion, ParametersFingerprint,
+    SetupReport, SharedPrimitives, PARAMETERS_FINGERPRINT_DOMAIN_TAG,
+};
 use crate::prelude::*;
-use astro_float::{BigFloat, Consts, Radix, RoundingMode};
-use num_bigint::BigUint;
-use std::str::FromStr;
+use astro_float::BigFloat;
 
 // R1CS constraints for parameters inside a ZKP
 pub mod constraints;
@@ -18,46 +20,177 @@ pub struct ParametersShuffle<Conf: Config, const GAMMA_BYTES: usize> {
     pub client_commitment_scheme: ClientCommitmentSchemeParameters<Conf>,
     pub server_signature_scheme: ServerSignatureSchemeParameters<Conf>,
     pub client_signature_scheme: ClientSignatureSchemeParameters<Conf>,
+    /// Parameters of the hash that is applied to the input value message before signing, if any.
+    pub client_input_hash_scheme: Option<ClientInputHashParameters<Conf>>,
+    gamma_conversion: GammaConversion,
 }
 
 impl<Conf: Config, const GAMMA_BYTES: usize> ParametersShuffle<Conf, GAMMA_BYTES> {
     /// Perform the setup of the Shuffle scheme for the given value of gamma.
     /// This simply generates parameters for all cryptographic primitives.
     pub fn setup<R: Rng + CryptoRng>(gamma: BigFloat, rng: &mut R) -> Result<Self, Error> {
-        assert!(BigFloat::from(0) < gamma && gamma <= BigFloat::from(1));
+        check_gamma(&gamma)?;
+        float_conversion_precision("GAMMA_BYTES", GAMMA_BYTES)?;
         Ok(Self {
             gamma,
             client_commitment_scheme: Conf::ClientCommitmentScheme::setup(rng)?,
             server_signature_scheme: Conf::ServerSignatureScheme::setup(rng)?,
             client_signature_scheme: Conf::ClientSignatureScheme::setup(rng)?,
+            client_input_hash_scheme: None,
+            gamma_conversion: GammaConversion::default(),
         })
     }
 
+    /// Perform the setup of the Shuffle scheme for the given value of gamma, reusing the given
+    /// parameters of the primitives that all schemes have in common.
+    pub fn from_shared(gamma: BigFloat, shared: &SharedPrimitives<Conf>) -> Result<Self, Error> {
+        check_gamma(&gamma)?;
+        Ok(Self {
+            gamma,
+            client_commitment_scheme: shared.client_commitment_scheme.clone(),
+            server_signature_scheme: shared.server_signature_scheme.clone(),
+            client_signature_scheme: shared.client_signature_scheme.clone(),
+            client_input_hash_scheme: None,
+            gamma_conversion: GammaConversion::default(),
+        })
+    }
+
+    /// Perform the setup like `setup`, and additionally report the gamma that is realized by its
+    /// byte encoding (see `SetupReport`). The report flags a precision loss if the realized gamma
+    /// differs from `gamma` by more than `tolerance`.
+    pub fn setup_with_report<R: Rng + CryptoRng>(
+        gamma: BigFloat,
+        tolerance: &BigFloat,
+        rng: &mut R,
+    ) -> Result<(Self, SetupReport), Error> {
+        let parameters = Self::setup(gamma, rng)?;
+        let report = parameters.setup_report(tolerance)?;
+        Ok((parameters, report))
+    }
+
+    /// Report the gamma that is realized by the byte encoding of these parameters (see
+    /// `setup_with_report`).
+    pub fn setup_report(&self, tolerance: &BigFloat) -> Result<SetupReport, Error> {
+        SetupReport::new(&self.gamma, &self.gamma_as_bytes()?, tolerance)
+    }
+
+    /// Change the privacy level to the given value of gamma, keeping the parameters of all
+    /// cryptographic primitives, such that the signature keys of clients and server stay valid.
+    /// As gamma is a constant of the circuit, the circuit keys have to be generated again.
+    pub fn with_gamma(mut self, gamma: BigFloat) -> Result<Self, Error> {
+        check_gamma(&gamma)?;
+        self.gamma = gamma;
+        Ok(self)
+    }
+
+    /// Let clients sign a hash of the input value message instead of the message itself (see
+    /// `InputValueSignatureInput::signed_bytes`). The in-circuit signature verification then only
+    /// hashes a fixed-size digest, which saves constraints for large `INPUT_BYTES`.
+    pub fn with_hashed_input<R: Rng + CryptoRng>(mut self, rng: &mut R) -> Result<Self, Error> {
+        self.client_input_hash_scheme = Some(Conf::ClientInputHash::setup(rng)?);
+        Ok(self)
+    }
+
+    /// Fingerprint of these parameters, covering `GAMMA_BYTES`, the byte encoding of gamma, and the
+    /// parameters of all primitives (see `verify_against_published`).
+    pub fn parameters_fingerprint(&self) -> Result<ParametersFingerprint, Error>
+    where
+        ClientCommitmentSchemeParameters<Conf>: FingerprintParameters,
+        ServerSignatureSchemeParameters<Conf>: FingerprintParameters,
+        ClientSignatureSchemeParameters<Conf>: FingerprintParameters,
+        ClientInputHashParameters<Conf>: FingerprintParameters,
+    {
+        let mut bytes = PARAMETERS_FINGERPRINT_DOMAIN_TAG.to_vec();
+        bytes.extend_from_slice(b"shuffle");
+        bytes.extend_from_slice(&(GAMMA_BYTES as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.gamma_as_bytes()?);
+        self.client_commitment_scheme
+            .write_fingerprint_bytes(&mut bytes)?;
+        self.server_signature_scheme
+            .write_fingerprint_bytes(&mut bytes)?;
+        self.client_signature_scheme
+            .write_fingerprint_bytes(&mut bytes)?;
+        self.client_input_hash_scheme
+            .write_fingerprint_bytes(&mut bytes)?;
+        Ok(fingerprint(&bytes))
+    }
+
+    /// Check that these parameters match the fingerprint a deployment has published for its
+    /// canonical parameters, so that a server cannot hand different parameters to different
+    /// clients. Clients should do this before taking part in the scheme.
+    pub fn verify_against_published(
+        &self,
+        published_fingerprint: &ParametersFingerprint,
+    ) -> Result<(), Error>
+    where
+        ClientCommitmentSchemeParameters<Conf>: FingerprintParameters,
+        ServerSignatureSchemeParameters<Conf>: FingerprintParameters,
+        ClientSignatureSchemeParameters<Conf>: FingerprintParameters,
+        ClientInputHashParameters<Conf>: FingerprintParameters,
+    {
+        if self.parameters_fingerprint()? != *published_fingerprint {
+            Err(ClientError::UnpublishedParameters)?
+        }
+        Ok(())
+    }
+
+    /// Convert gamma to bytes with the given precision and rounding mode instead of the default
+    /// ones (see `GammaConversion`), e.g., to study the bias of the rounding. As gamma is a
+    /// constant of the circuit, the circuit keys have to be generated again.
+    pub fn with_gamma_conversion(mut self, gamma_conversion: GammaConversion) -> Self {
+        self.gamma_conversion = gamma_conversion;
+        self
+    }
+
+    /// Get the conversion of gamma to bytes of these parameters.
+    pub fn gamma_conversion(&self) -> GammaConversion {
+        self.gamma_conversion
+    }
+
     /// Transform a floating point value of gamma to a byte array in a deterministic way, with
-    /// as much precision as possible. This is needed for encoding inside the ZKP circuit.
+    /// the configured precision and rounding mode (see `with_gamma_conversion`). This is needed for
+    /// encoding inside the ZKP circuit.
     pub fn gamma_as_bytes(&self) -> Result<[u8; GAMMA_BYTES], Error> {
-        let precision = GAMMA_BYTES * 8 * 2;
-        let mut gamma = self.gamma.clone();
-        gamma.set_precision(precision, RoundingMode::Down)?;
-        let gamma_as_int = gamma
-            .mul_full_prec(
-                &BigFloat::from_u8(2, precision)
-                    .powi(GAMMA_BYTES * 8, precision, RoundingMode::Down)
-                    .sub_full_prec(&BigFloat::from_u8(1, precision)),
-            )
-            .int();
-        let gamma_as_str = gamma_as_int
-            .convert_to_radix(
-                Radix::Dec,
-                RoundingMode::None,
-                &mut Consts::new().expect("Constants cache initialization should not fail."),
-            )?
-            .1
-            .iter()
-            .map(|digit| digit.to_string())
-            .collect::<String>();
-        let mut bytes = [0; GAMMA_BYTES];
-        bytes.copy_from_slice(&BigUint::from_str(&gamma_as_str)?.to_bytes_le());
-        Ok(bytes)
+        self.gamma_conversion.gamma_as_bytes(&self.gamma)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ParametersShuffle;
+    use crate::config::BasicConfig;
+    use crate::prelude::*;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use astro_float::BigFloat;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+
+    #[test]
+    fn only_published_parameters_are_accepted() {
+        let rng = &mut ChaChaRng::from_seed([91; 32]);
+        let shared = SharedPrimitives::<Conf>::setup(rng).unwrap();
+        let parameters =
+            ParametersShuffle::<Conf, 8>::from_shared(BigFloat::from_f64(0.5, 64), &shared)
+                .unwrap();
+        let published_fingerprint = parameters.parameters_fingerprint().unwrap();
+        parameters
+            .verify_against_published(&published_fingerprint)
+            .unwrap();
+
+        // the same primitives, but another gamma
+        let tampered =
+            ParametersShuffle::<Conf, 8>::from_shared(BigFloat::from_f64(0.75, 64), &shared)
+                .unwrap();
+        let error = tampered
+            .verify_against_published(&published_fingerprint)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Client(ClientError::UnpublishedParameters)
+        ));
     }
 }
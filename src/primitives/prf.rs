@@ -0,0 +1,115 @@
+//! Expansion of a PRF seed into randomness of arbitrary length, as used in the Base scheme.
+//!
+//! With the `parallel` feature, the PRF evaluations are distributed over threads (using rayon),
+//! which pays off once `RANDOMNESS_BYTES` is in the kilobytes (e.g., for high-dimensional inputs).
+
+use crate::prelude::*;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Eval point of the `index`-th chunk: the little-endian bytes of `index`, zero-padded.
+fn eval_point(index: usize) -> [u8; 32] {
+    let mut eval_point = [0; 32];
+    for (new_byte, old_byte) in index.to_le_bytes().into_iter().zip(eval_point.iter_mut()) {
+        *old_byte = new_byte;
+    }
+    eval_point
+}
+
+/// Fill `randomness` with PRF outputs under `seed`, in chunks of `PRF_OUTPUT_BYTES` bytes: the
+/// `index`-th chunk is (a prefix of) the PRF evaluated at the eval point `index`. The chunks are
+/// computed in parallel with the `parallel` feature, and one after another otherwise; the output
+/// is the same either way.
+pub fn expand_prf_seed<Conf: Config>(
+    seed: &PRFSchemeSeed<Conf>,
+    randomness: &mut [u8],
+) -> Result<(), Error> {
+    #[cfg(feature = "parallel")]
+    {
+        expand_prf_seed_parallel::<Conf>(seed, randomness)
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        expand_prf_seed_serial::<Conf>(seed, randomness)
+    }
+}
+
+#[cfg(any(not(feature = "parallel"), test))]
+fn expand_prf_seed_serial<Conf: Config>(
+    seed: &PRFSchemeSeed<Conf>,
+    randomness: &mut [u8],
+) -> Result<(), Error> {
+    for (index, chunk) in randomness.chunks_mut(Conf::PRF_OUTPUT_BYTES).enumerate() {
+        chunk.copy_from_slice(
+            &Conf::PRFScheme::evaluate(seed, &eval_point(index))?.as_ref()[0..chunk.len()],
+        );
+    }
+    Ok(())
+}
+
+#[cfg(feature = "parallel")]
+fn expand_prf_seed_parallel<Conf: Config>(
+    seed: &PRFSchemeSeed<Conf>,
+    randomness: &mut [u8],
+) -> Result<(), Error> {
+    // every chunk is written by the task of its own index, so scheduling cannot reorder them
+    randomness
+        .par_chunks_mut(Conf::PRF_OUTPUT_BYTES)
+        .enumerate()
+        .try_for_each(|(index, chunk)| {
+            chunk.copy_from_slice(
+                &Conf::PRFScheme::evaluate(seed, &eval_point(index))?.as_ref()[0..chunk.len()],
+            );
+            Ok::<_, ark_crypto_primitives::CryptoError>(())
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::expand_prf_seed_serial;
+    use crate::config::BasicConfig;
+    use crate::prelude::*;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+
+    #[test]
+    fn chunks_are_prf_outputs_in_order() {
+        let rng = &mut ChaChaRng::from_seed([116; 32]);
+        let seed = rng.gen::<PRFSchemeSeed<Conf>>();
+        // a partial last chunk
+        let mut randomness = vec![0; 3 * Conf::PRF_OUTPUT_BYTES + 5];
+        expand_prf_seed_serial::<Conf>(&seed, &mut randomness).unwrap();
+        for (index, chunk) in randomness.chunks(Conf::PRF_OUTPUT_BYTES).enumerate() {
+            let mut eval_point = [0; 32];
+            eval_point[0] = index as u8;
+            assert_eq!(
+                chunk,
+                &<Conf as Config>::PRFScheme::evaluate(&seed, &eval_point)
+                    .unwrap()
+                    .as_ref()[0..chunk.len()]
+            );
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_and_serial_expansion_are_identical() {
+        use super::expand_prf_seed_parallel;
+
+        let rng = &mut ChaChaRng::from_seed([117; 32]);
+        for length in [1, Conf::PRF_OUTPUT_BYTES, 4096 + 17] {
+            let seed = rng.gen::<PRFSchemeSeed<Conf>>();
+            let mut serial = vec![0; length];
+            let mut parallel = vec![0; length];
+            expand_prf_seed_serial::<Conf>(&seed, &mut serial).unwrap();
+            expand_prf_seed_parallel::<Conf>(&seed, &mut parallel).unwrap();
+            assert_eq!(serial, parallel);
+        }
+    }
+}
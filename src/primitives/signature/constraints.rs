@@ -7,10 +7,10 @@ use crate::prelude::*;
 use ark_r1cs_std::prelude::*;
 use ark_relations::r1cs::SynthesisError;
 
-use crate::primitives::signature::SignatureScheme;
+use crate::primitives::signature::SignatureVerifier;
 
 /// R1CS gadget for signature verification inside ZKP circuits.
-pub trait SigVerifyGadget<S: SignatureScheme, ConstraintF: Field> {
+pub trait SigVerifyGadget<S: SignatureVerifier, ConstraintF: Field> {
     type ParametersVar: AllocVar<S::Parameters, ConstraintF> + Clone;
 
     type PublicKeyVar: ToBytesGadget<ConstraintF> + AllocVar<S::PublicKey, ConstraintF> + Clone;
@@ -27,7 +27,7 @@ pub trait SigVerifyGadget<S: SignatureScheme, ConstraintF: Field> {
 
 #[cfg(test)]
 mod test {
-    use crate::prelude::SignatureScheme;
+    use crate::prelude::{SignatureScheme, SignatureVerifier};
     use crate::primitives::crh::blake2s::constraints::Blake2s256Gadget;
     use crate::primitives::crh::Blake2s256;
     use crate::primitives::signature::schnorr::constraints::SchnorrSignatureVerifyGadget;
@@ -104,4 +104,44 @@ mod test {
             "Bad message".as_bytes(),
         );
     }
+
+    #[test]
+    fn schnorr_signature_is_bound_to_its_domain() {
+        type F = <JubJub as CurveGroup>::BaseField;
+        type S = Schnorr<JubJub, Blake2s256>;
+        type SG = SchnorrSignatureVerifyGadget<JubJub, JubJubVar, Blake2s256, Blake2s256Gadget>;
+        let rng = &mut test_rng();
+        let message = "Hi, I am a Schnorr signature!".as_bytes();
+        let parameters = S::setup(rng).unwrap();
+        let domain_a = parameters.clone().with_domain(b"VLDP server seed");
+        let domain_b = parameters.with_domain(b"VLDP input value");
+        let (pk, sk) = S::keygen(&domain_a, rng).unwrap();
+        let sig = S::sign(&domain_a, &sk, message, rng).unwrap();
+        assert!(S::verify(&domain_a, &pk, message, &sig).unwrap());
+        assert!(!S::verify(&domain_b, &pk, message, &sig).unwrap());
+
+        // the gadget prepends the same domain
+        for (parameters, valid) in [(domain_a, true), (domain_b, false)] {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let parameters_var =
+                <SG as SigVerifyGadget<S, F>>::ParametersVar::new_constant(cs.clone(), parameters)
+                    .unwrap();
+            let signature_var =
+                <SG as SigVerifyGadget<S, F>>::SignatureVar::new_witness(cs.clone(), || Ok(&sig))
+                    .unwrap();
+            let pk_var =
+                <SG as SigVerifyGadget<S, F>>::PublicKeyVar::new_witness(cs.clone(), || Ok(&pk))
+                    .unwrap();
+            let msg_var = UInt8::new_witness_vec(cs.clone(), message).unwrap();
+            let valid_sig_var = <SG as SigVerifyGadget<S, F>>::verify(
+                &parameters_var,
+                &pk_var,
+                &msg_var,
+                &signature_var,
+            )
+            .unwrap();
+            assert_eq!(valid_sig_var.value().unwrap(), valid);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
 }
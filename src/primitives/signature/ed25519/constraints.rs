@@ -0,0 +1,706 @@
+//! Definition for ZKP constraints for Ed25519 signature verification.
+//!
+//! The arithmetic of edwards25519 is emulated with non-native field variables and SHA-512 is
+//! evaluated on 64-bit words, such that the constraints can be generated over any prime field with
+//! at least 192 bits (e.g., the scalar field of BLS12-381). Note that this results in several
+//! million constraints, i.e., the gadget is meant for interoperability with existing Ed25519 keys
+//! rather than for performance.
+
+use crate::primitives::signature::ed25519::{
+    recover_x, Ed25519, Ed25519PublicKey, Ed25519Signature,
+};
+use crate::primitives::signature::SigVerifyGadget;
+use ark_ec::twisted_edwards::TECurveConfig;
+use ark_ec::AffineRepr;
+use ark_ed25519::{EdwardsAffine, EdwardsConfig, Fq, Fr};
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::bits::uint64::UInt64;
+use ark_r1cs_std::fields::nonnative::NonNativeFieldVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use ark_std::One;
+use core::{borrow::Borrow, marker::PhantomData};
+
+// coordinates of edwards25519 points (in the constraint field)
+type CoordinateVar<F> = NonNativeFieldVar<Fq, F>;
+
+/// Parameters variable for Ed25519 signature scheme (there are no parameters)
+#[derive(Clone)]
+pub struct Ed25519ParametersVar;
+
+/// Public key variable for Ed25519 signature scheme: the encoding of the public point
+#[derive(Clone)]
+pub struct Ed25519PublicKeyVar<F: PrimeField> {
+    bytes: Vec<UInt8<F>>,
+}
+
+/// Signature variable for Ed25519 signature scheme: the encoding of `R` and the bytes of `S`
+#[derive(Clone)]
+pub struct Ed25519SignatureVar<F: PrimeField> {
+    r: Vec<UInt8<F>>,
+    s: Vec<UInt8<F>>,
+}
+
+/// Gadget for generating R1CS constraints for Ed25519 signature verification
+pub struct Ed25519SignatureVerifyGadget<F: PrimeField> {
+    // required for binding the constraint field to this struct
+    #[doc(hidden)]
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField> SigVerifyGadget<Ed25519, F> for Ed25519SignatureVerifyGadget<F> {
+    type ParametersVar = Ed25519ParametersVar;
+    type PublicKeyVar = Ed25519PublicKeyVar<F>;
+    type SignatureVar = Ed25519SignatureVar<F>;
+
+    /// Set all R1CS constraints for Ed25519 signature verification for the given `message`.
+    /// As for the native verification, malformed signatures result in `FALSE` instead of an
+    /// unsatisfiable constraint system.
+    fn verify(
+        _parameters: &Self::ParametersVar,
+        public_key: &Self::PublicKeyVar,
+        message: &[UInt8<F>],
+        signature: &Self::SignatureVar,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let inputs = VerificationInputsVar::new(public_key, message, signature)?;
+        let s_times_base =
+            PointVar::constant(&EdwardsAffine::generator()).scalar_mul_le(&inputs.s_bits)?;
+        let challenge_times_public_point =
+            inputs.public_point.scalar_mul_le(&inputs.challenge_bits)?;
+        inputs.accept(&s_times_base, &challenge_times_public_point)
+    }
+}
+
+/// Everything the verification equation `[S]B = R + [k]A` is evaluated on: the decoded public
+/// point `A` and `R`, the bits of `S`, and the bits of the challenge `k = H(R || A || M) mod L`.
+struct VerificationInputsVar<F: PrimeField> {
+    public_point: PointVar<F>,
+    r_point: PointVar<F>,
+    s_bits: Vec<Boolean<F>>,
+    challenge_bits: Vec<Boolean<F>>,
+    is_well_formed: Boolean<F>,
+}
+
+impl<F: PrimeField> VerificationInputsVar<F> {
+    fn new(
+        public_key: &Ed25519PublicKeyVar<F>,
+        message: &[UInt8<F>],
+        signature: &Ed25519SignatureVar<F>,
+    ) -> Result<Self, SynthesisError> {
+        let (public_point, public_point_is_valid) = PointVar::decode(&public_key.bytes)?;
+        let (r_point, r_point_is_valid) = PointVar::decode(&signature.r)?;
+        let s_bits = signature.s.to_bits_le()?;
+        let s_is_canonical = is_less_than_le(&s_bits, &Fr::MODULUS.to_bits_le())?;
+
+        // k = H(R || A || M) mod L
+        let mut hash_input = signature.r.clone();
+        hash_input.extend_from_slice(&public_key.bytes);
+        hash_input.extend_from_slice(message);
+        let digest_bits = sha512(&hash_input)?.to_bits_le()?;
+        let mut challenge_bits = from_bits_le::<Fr, F>(&digest_bits).to_bits_le()?;
+        challenge_bits.truncate(Fr::MODULUS_BIT_SIZE as usize);
+
+        Ok(Self {
+            public_point,
+            r_point,
+            s_bits,
+            challenge_bits,
+            is_well_formed: Boolean::kary_and(&[
+                public_point_is_valid,
+                r_point_is_valid,
+                s_is_canonical,
+            ])?,
+        })
+    }
+
+    /// Whether the signature is well-formed and `[S]B = R + [k]A` for the given `[S]B` and `[k]A`.
+    fn accept(
+        &self,
+        s_times_base: &PointVar<F>,
+        challenge_times_public_point: &PointVar<F>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        self.is_well_formed
+            .and(&s_times_base.is_eq(&self.r_point.add(challenge_times_public_point)?)?)
+    }
+}
+
+/// Affine point of edwards25519 with non-native coordinates.
+#[derive(Clone)]
+struct PointVar<F: PrimeField> {
+    x: CoordinateVar<F>,
+    y: CoordinateVar<F>,
+}
+
+impl<F: PrimeField> PointVar<F> {
+    fn constant(point: &EdwardsAffine) -> Self {
+        Self {
+            x: CoordinateVar::constant(point.x),
+            y: CoordinateVar::constant(point.y),
+        }
+    }
+
+    fn identity() -> Self {
+        Self {
+            x: CoordinateVar::zero(),
+            y: CoordinateVar::one(),
+        }
+    }
+
+    /// Decode a point as `decode_point` does natively. Returns the decoded point (or the identity
+    /// if decoding fails, to keep the subsequent arithmetic well-defined) and whether decoding
+    /// succeeded.
+    fn decode(bytes: &[UInt8<F>]) -> Result<(Self, Boolean<F>), SynthesisError> {
+        let bits = bytes.to_bits_le()?;
+        let (y_bits, sign) = (&bits[..255], &bits[255]);
+        let y_is_canonical = is_less_than_le(y_bits, &Fq::MODULUS.to_bits_le()[..255])?;
+        let y = from_bits_le::<Fq, F>(y_bits);
+
+        // x is recovered outside of the circuit and then checked against the sign and the curve
+        // equation, which have a unique solution for valid encodings and none otherwise
+        let x = CoordinateVar::new_witness(bytes.cs(), || {
+            Ok(recover_x(y.value()?, sign.value()?).unwrap_or_default())
+        })?;
+        let sign_matches = x.to_bits_le()?[0].is_eq(sign)?;
+        let x_squared = x.square()?;
+        let y_squared = y.square()?;
+        let is_on_curve = (&y_squared - &x_squared)
+            .is_eq(&(&x_squared * &y_squared * EdwardsConfig::COEFF_D + Fq::one()))?;
+
+        let is_valid = Boolean::kary_and(&[y_is_canonical, sign_matches, is_on_curve])?;
+        let point = Self::select(&is_valid, &Self { x, y }, &Self::identity())?;
+        Ok((point, is_valid))
+    }
+
+    /// Addition on twisted Edwards curves with `a = -1`, which is complete for edwards25519 (the
+    /// denominators are never zero as `d` is not a square).
+    fn add(&self, other: &Self) -> Result<Self, SynthesisError> {
+        let x1y2 = &self.x * &other.y;
+        let y1x2 = &self.y * &other.x;
+        let x1x2 = &self.x * &other.x;
+        let y1y2 = &self.y * &other.y;
+        let dx1x2y1y2 = &x1x2 * &y1y2 * EdwardsConfig::COEFF_D;
+
+        let x_denominator = dx1x2y1y2.clone() + Fq::one();
+        let y_denominator = CoordinateVar::one() - dx1x2y1y2;
+        Ok(Self {
+            x: (x1y2 + y1x2).mul_by_inverse_unchecked(&x_denominator)?,
+            y: (y1y2 + x1x2).mul_by_inverse_unchecked(&y_denominator)?,
+        })
+    }
+
+    /// Multiply the point by the scalar with the given little-endian bits (double-and-add).
+    fn scalar_mul_le(&self, bits: &[Boolean<F>]) -> Result<Self, SynthesisError> {
+        let mut result = Self::identity();
+        let mut multiple = self.clone();
+        for (i, bit) in bits.iter().enumerate() {
+            result = Self::select(bit, &result.add(&multiple)?, &result)?;
+            if i + 1 < bits.len() {
+                multiple = multiple.add(&multiple)?;
+            }
+        }
+        Ok(result)
+    }
+
+    fn select(
+        condition: &Boolean<F>,
+        true_value: &Self,
+        false_value: &Self,
+    ) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            x: CoordinateVar::conditionally_select(condition, &true_value.x, &false_value.x)?,
+            y: CoordinateVar::conditionally_select(condition, &true_value.y, &false_value.y)?,
+        })
+    }
+
+    fn is_eq(&self, other: &Self) -> Result<Boolean<F>, SynthesisError> {
+        self.x.is_eq(&other.x)?.and(&self.y.is_eq(&other.y)?)
+    }
+}
+
+/// Non-native field element with the given little-endian bits (reduced modulo the field size).
+fn from_bits_le<T: PrimeField, F: PrimeField>(bits: &[Boolean<F>]) -> NonNativeFieldVar<T, F> {
+    let mut result = NonNativeFieldVar::zero();
+    let mut power = T::one();
+    for bit in bits {
+        result += NonNativeFieldVar::from(bit.clone()) * power;
+        power.double_in_place();
+    }
+    result
+}
+
+/// Whether the number with little-endian `bits` is smaller than the constant with little-endian
+/// `constant_bits` (of the same length).
+fn is_less_than_le<F: PrimeField>(
+    bits: &[Boolean<F>],
+    constant_bits: &[bool],
+) -> Result<Boolean<F>, SynthesisError> {
+    assert_eq!(bits.len(), constant_bits.len());
+    let mut is_less = Boolean::FALSE;
+    let mut is_equal = Boolean::TRUE;
+    for (bit, &constant_bit) in bits.iter().zip(constant_bits).rev() {
+        if constant_bit {
+            is_less = is_less.or(&is_equal.and(&bit.not())?)?;
+            is_equal = is_equal.and(bit)?;
+        } else {
+            is_equal = is_equal.and(&bit.not())?;
+        }
+    }
+    Ok(is_less)
+}
+
+const SHA512_INITIAL_STATE: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const SHA512_ROUND_CONSTANTS: [u64; 80] = [
+    0x428a2f98d728ae22,
+    0x7137449123ef65cd,
+    0xb5c0fbcfec4d3b2f,
+    0xe9b5dba58189dbbc,
+    0x3956c25bf348b538,
+    0x59f111f1b605d019,
+    0x923f82a4af194f9b,
+    0xab1c5ed5da6d8118,
+    0xd807aa98a3030242,
+    0x12835b0145706fbe,
+    0x243185be4ee4b28c,
+    0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f,
+    0x80deb1fe3b1696b1,
+    0x9bdc06a725c71235,
+    0xc19bf174cf692694,
+    0xe49b69c19ef14ad2,
+    0xefbe4786384f25e3,
+    0x0fc19dc68b8cd5b5,
+    0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275,
+    0x4a7484aa6ea6e483,
+    0x5cb0a9dcbd41fbd4,
+    0x76f988da831153b5,
+    0x983e5152ee66dfab,
+    0xa831c66d2db43210,
+    0xb00327c898fb213f,
+    0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2,
+    0xd5a79147930aa725,
+    0x06ca6351e003826f,
+    0x142929670a0e6e70,
+    0x27b70a8546d22ffc,
+    0x2e1b21385c26c926,
+    0x4d2c6dfc5ac42aed,
+    0x53380d139d95b3df,
+    0x650a73548baf63de,
+    0x766a0abb3c77b2a8,
+    0x81c2c92e47edaee6,
+    0x92722c851482353b,
+    0xa2bfe8a14cf10364,
+    0xa81a664bbc423001,
+    0xc24b8b70d0f89791,
+    0xc76c51a30654be30,
+    0xd192e819d6ef5218,
+    0xd69906245565a910,
+    0xf40e35855771202a,
+    0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8,
+    0x1e376c085141ab53,
+    0x2748774cdf8eeb99,
+    0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63,
+    0x4ed8aa4ae3418acb,
+    0x5b9cca4f7763e373,
+    0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc,
+    0x78a5636f43172f60,
+    0x84c87814a1f0ab72,
+    0x8cc702081a6439ec,
+    0x90befffa23631e28,
+    0xa4506cebde82bde9,
+    0xbef9a3f7b2c67915,
+    0xc67178f2e372532b,
+    0xca273eceea26619c,
+    0xd186b8c721c0c207,
+    0xeada7dd6cde0eb1e,
+    0xf57d4f7fee6ed178,
+    0x06f067aa72176fba,
+    0x0a637dc5a2c898a6,
+    0x113f9804bef90dae,
+    0x1b710b35131c471b,
+    0x28db77f523047d84,
+    0x32caab7b40c72493,
+    0x3c9ebe0a15c9bebc,
+    0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6,
+    0x597f299cfc657e2a,
+    0x5fcb6fab3ad6faec,
+    0x6c44198c4a475817,
+];
+
+/// SHA-512 (FIPS 180-4) of the given bytes.
+fn sha512<F: PrimeField>(message: &[UInt8<F>]) -> Result<Vec<UInt8<F>>, SynthesisError> {
+    // padding: 0x80, zeros, and the message length in bits (128-bit big-endian)
+    let mut padded = message.to_vec();
+    padded.push(UInt8::constant(0x80));
+    while padded.len() % 128 != 112 {
+        padded.push(UInt8::constant(0));
+    }
+    padded.extend(UInt8::constant_vec(
+        &(message.len() as u128 * 8).to_be_bytes(),
+    ));
+
+    let mut state = SHA512_INITIAL_STATE.map(UInt64::constant);
+    for block in padded.chunks(128) {
+        // message schedule (at most three 64-bit words can be added at once in a 255-bit field)
+        let mut schedule = block
+            .chunks(8)
+            .map(word_from_be_bytes)
+            .collect::<Result<Vec<_>, _>>()?;
+        for t in 16..80 {
+            let w15 = &schedule[t - 15];
+            let w2 = &schedule[t - 2];
+            let s0 = w15.rotr(1).xor(&w15.rotr(8))?.xor(&shr(w15, 7))?;
+            let s1 = w2.rotr(19).xor(&w2.rotr(61))?.xor(&shr(w2, 6))?;
+            let sum = UInt64::addmany(&[s1, schedule[t - 7].clone(), s0])?;
+            schedule.push(UInt64::addmany(&[sum, schedule[t - 16].clone()])?);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state.clone();
+        for t in 0..80 {
+            let big_s1 = e.rotr(14).xor(&e.rotr(18))?.xor(&e.rotr(41))?;
+            let sum = UInt64::addmany(&[h, big_s1, choose(&e, &f, &g)?])?;
+            let t1 = UInt64::addmany(&[
+                sum,
+                UInt64::constant(SHA512_ROUND_CONSTANTS[t]),
+                schedule[t].clone(),
+            ])?;
+            let big_s0 = a.rotr(28).xor(&a.rotr(34))?.xor(&a.rotr(39))?;
+            let t2 = UInt64::addmany(&[big_s0, majority(&a, &b, &c)?])?;
+
+            h = g;
+            g = f;
+            f = e;
+            e = UInt64::addmany(&[d, t1.clone()])?;
+            d = c;
+            c = b;
+            b = a;
+            a = UInt64::addmany(&[t1, t2])?;
+        }
+        for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *word = UInt64::addmany(&[word.clone(), value])?;
+        }
+    }
+    Ok(state.iter().flat_map(word_to_be_bytes).collect())
+}
+
+fn word_from_be_bytes<F: PrimeField>(bytes: &[UInt8<F>]) -> Result<UInt64<F>, SynthesisError> {
+    let bytes: Vec<UInt8<F>> = bytes.iter().rev().cloned().collect();
+    Ok(UInt64::from_bits_le(&bytes.to_bits_le()?))
+}
+
+fn word_to_be_bytes<F: PrimeField>(word: &UInt64<F>) -> Vec<UInt8<F>> {
+    word.to_bits_le()
+        .chunks(8)
+        .rev()
+        .map(UInt8::from_bits_le)
+        .collect()
+}
+
+fn shr<F: PrimeField>(word: &UInt64<F>, by: usize) -> UInt64<F> {
+    let bits: Vec<Boolean<F>> = word.to_bits_le()[by..]
+        .iter()
+        .cloned()
+        .chain(std::iter::repeat_n(Boolean::FALSE, by))
+        .collect();
+    UInt64::from_bits_le(&bits)
+}
+
+/// `(e AND f) XOR (NOT e AND g)`, computed bitwise as `g XOR (e AND (f XOR g))`.
+fn choose<F: PrimeField>(
+    e: &UInt64<F>,
+    f: &UInt64<F>,
+    g: &UInt64<F>,
+) -> Result<UInt64<F>, SynthesisError> {
+    let bits = e
+        .to_bits_le()
+        .iter()
+        .zip(f.to_bits_le())
+        .zip(g.to_bits_le())
+        .map(|((e, f), g)| g.xor(&e.and(&f.xor(&g)?)?))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(UInt64::from_bits_le(&bits))
+}
+
+/// `(a AND b) XOR (a AND c) XOR (b AND c)`, computed bitwise as `(a AND b) XOR (c AND (a XOR b))`.
+fn majority<F: PrimeField>(
+    a: &UInt64<F>,
+    b: &UInt64<F>,
+    c: &UInt64<F>,
+) -> Result<UInt64<F>, SynthesisError> {
+    let bits = a
+        .to_bits_le()
+        .iter()
+        .zip(b.to_bits_le())
+        .zip(c.to_bits_le())
+        .map(|((a, b), c)| a.and(&b)?.xor(&c.and(&a.xor(&b)?)?))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(UInt64::from_bits_le(&bits))
+}
+
+// R1CS variable allocation for Ed25519 parameters
+impl<F: PrimeField> AllocVar<(), F> for Ed25519ParametersVar {
+    fn new_variable<T: Borrow<()>>(
+        _cs: impl Into<Namespace<F>>,
+        _f: impl FnOnce() -> Result<T, SynthesisError>,
+        _mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        Ok(Ed25519ParametersVar)
+    }
+}
+
+/// Allocate the given bytes in the given mode.
+fn new_bytes<F: PrimeField>(
+    cs: impl Into<Namespace<F>>,
+    bytes: &[u8],
+    mode: AllocationMode,
+) -> Result<Vec<UInt8<F>>, SynthesisError> {
+    match mode {
+        AllocationMode::Constant => Ok(UInt8::constant_vec(bytes)),
+        AllocationMode::Input => UInt8::new_input_vec(cs, bytes),
+        AllocationMode::Witness => UInt8::new_witness_vec(cs, bytes),
+    }
+}
+
+// R1CS variable allocation for Ed25519 public key
+impl<F: PrimeField> AllocVar<Ed25519PublicKey, F> for Ed25519PublicKeyVar<F> {
+    fn new_variable<T: Borrow<Ed25519PublicKey>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let cs = cs.into().cs();
+        // the public key is not known during the setup (the bytes are packed into field elements
+        // as by `ToConstraintField`, which requires concrete values)
+        let public_key = match f() {
+            Ok(val) => *val.borrow(),
+            Err(_) if cs.is_in_setup_mode() => Ed25519PublicKey::default(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            bytes: new_bytes(cs, &public_key.bytes, mode)?,
+        })
+    }
+}
+
+// R1CS variable allocation for Ed25519 signature
+impl<F: PrimeField> AllocVar<Ed25519Signature, F> for Ed25519SignatureVar<F> {
+    fn new_variable<T: Borrow<Ed25519Signature>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        f().and_then(|val| {
+            let cs = cs.into().cs();
+            Ok(Self {
+                r: new_bytes(cs.clone(), &val.borrow().r, mode)?,
+                s: new_bytes(cs, &val.borrow().s, mode)?,
+            })
+        })
+    }
+}
+
+// implement signature to bytes conversion for Ed25519 signature (needed for proof serialization)
+impl<F: PrimeField> ToBytesGadget<F> for Ed25519SignatureVar<F> {
+    fn to_bytes(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        let mut bytes = self.r.clone();
+        bytes.extend_from_slice(&self.s);
+        Ok(bytes)
+    }
+}
+
+// implement public key to bytes conversion for Ed25519 public key (needed for proof serialization)
+impl<F: PrimeField> ToBytesGadget<F> for Ed25519PublicKeyVar<F> {
+    fn to_bytes(&self) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        Ok(self.bytes.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        sha512, CoordinateVar, Ed25519SignatureVerifyGadget, PointVar, VerificationInputsVar,
+    };
+    use crate::prelude::SignatureScheme;
+    use crate::primitives::signature::ed25519::{decode_point, encode_point, hash_to_scalar};
+    use crate::primitives::signature::{Ed25519, SigVerifyGadget};
+    use ark_bls12_381::Fr;
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_ed25519::EdwardsAffine;
+    use ark_ff::PrimeField;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::{ConstraintSystem, ConstraintSystemRef};
+    use ark_std::test_rng;
+    use sha2::{Digest, Sha512};
+
+    type Gadget = Ed25519SignatureVerifyGadget<Fr>;
+
+    fn new_point_witness(cs: ConstraintSystemRef<Fr>, point: &EdwardsAffine) -> PointVar<Fr> {
+        PointVar {
+            x: CoordinateVar::new_witness(cs.clone(), || Ok(point.x)).unwrap(),
+            y: CoordinateVar::new_witness(cs, || Ok(point.y)).unwrap(),
+        }
+    }
+
+    #[test]
+    fn sha512_gadget_matches_native_hash() {
+        // lengths around the padding boundaries (one and two blocks)
+        for length in [0, 3, 111, 112, 200] {
+            let message: Vec<u8> = (0..length).map(|i| i as u8).collect();
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let message_var = UInt8::new_witness_vec(cs.clone(), &message).unwrap();
+            let digest_var = sha512(&message_var).unwrap();
+            assert_eq!(
+                digest_var.value().unwrap(),
+                Sha512::digest(&message).to_vec()
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn point_decoding_and_addition_match_native_arithmetic() {
+        let rng = &mut test_rng();
+        let (public_key, _) = Ed25519::keygen(&(), rng).unwrap();
+        let point = decode_point(&public_key.bytes).unwrap();
+        let expected = encode_point(&(point + EdwardsAffine::generator()).into_affine());
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let bytes_var = UInt8::new_witness_vec(cs.clone(), &public_key.bytes).unwrap();
+        let (point_var, is_valid) = PointVar::decode(&bytes_var).unwrap();
+        let sum_var = point_var
+            .add(&PointVar::constant(&EdwardsAffine::generator()))
+            .unwrap();
+        assert!(is_valid.value().unwrap());
+        assert!(sum_var
+            .is_eq(&PointVar::constant(&decode_point(&expected).unwrap()))
+            .unwrap()
+            .value()
+            .unwrap());
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn scalar_multiplication_matches_native_arithmetic() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let scalar = 0b1011_u8;
+        let bits = Vec::<Boolean<Fr>>::new_witness(cs.clone(), || {
+            Ok((0..4).map(|i| scalar >> i & 1 == 1).collect::<Vec<_>>())
+        })
+        .unwrap();
+        let product = PointVar::constant(&EdwardsAffine::generator())
+            .scalar_mul_le(&bits)
+            .unwrap();
+        let expected = (EdwardsAffine::generator() * ark_ed25519::Fr::from(scalar)).into_affine();
+        assert!(product
+            .is_eq(&PointVar::constant(&expected))
+            .unwrap()
+            .value()
+            .unwrap());
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    /// Verify a signature over a short message with the gadget, except for the two scalar
+    /// multiplications (see `scalar_multiplication_matches_native_arithmetic`), whose products are
+    /// computed natively. The full gadget is checked by `gadget_agrees_with_native_verification`,
+    /// which is too large to run by default.
+    #[test]
+    fn signature_over_short_message_satisfies_constraints() {
+        let rng = &mut test_rng();
+        let (public_key, secret_key) = Ed25519::keygen(&(), rng).unwrap();
+        let message = b"seed";
+        let signature = Ed25519::sign(&(), &secret_key, message, rng).unwrap();
+        let mut tampered = signature;
+        tampered.s[0] ^= 1;
+
+        for (signature, is_valid) in [(signature, true), (tampered, false)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let public_key_var = <Gadget as SigVerifyGadget<Ed25519, Fr>>::PublicKeyVar::new_input(
+                cs.clone(),
+                || Ok(public_key),
+            )
+            .unwrap();
+            let signature_var =
+                <Gadget as SigVerifyGadget<Ed25519, Fr>>::SignatureVar::new_witness(
+                    cs.clone(),
+                    || Ok(signature),
+                )
+                .unwrap();
+            let message_var = UInt8::new_witness_vec(cs.clone(), &message[..]).unwrap();
+            let inputs =
+                VerificationInputsVar::new(&public_key_var, &message_var, &signature_var).unwrap();
+
+            let s = ark_ed25519::Fr::from_le_bytes_mod_order(&signature.s);
+            let challenge = hash_to_scalar(&[&signature.r[..], &public_key.bytes[..], message]);
+            let s_times_base = (EdwardsAffine::generator() * s).into_affine();
+            let challenge_times_public_point =
+                (decode_point(&public_key.bytes).unwrap() * challenge).into_affine();
+            inputs
+                .accept(
+                    &new_point_witness(cs.clone(), &s_times_base),
+                    &new_point_witness(cs.clone(), &challenge_times_public_point),
+                )
+                .unwrap()
+                .enforce_equal(&Boolean::TRUE)
+                .unwrap();
+            assert_eq!(cs.is_satisfied().unwrap(), is_valid);
+        }
+    }
+
+    #[test]
+    #[ignore = "synthesizes several million constraints and needs more than 6 GB of memory"]
+    fn gadget_agrees_with_native_verification() {
+        let rng = &mut test_rng();
+        let (public_key, secret_key) = Ed25519::keygen(&(), rng).unwrap();
+        let message = b"server seed";
+        let signature = Ed25519::sign(&(), &secret_key, message, rng).unwrap();
+        let mut malformed = signature;
+        malformed.s = [0xff; 32];
+
+        // the tampered signature results in FALSE, not in an unsatisfiable constraint system
+        for (signature, expected) in [(signature, true), (malformed, false)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let parameters_var =
+                <Gadget as SigVerifyGadget<Ed25519, Fr>>::ParametersVar::new_constant(
+                    cs.clone(),
+                    (),
+                )
+                .unwrap();
+            let public_key_var = <Gadget as SigVerifyGadget<Ed25519, Fr>>::PublicKeyVar::new_input(
+                cs.clone(),
+                || Ok(public_key),
+            )
+            .unwrap();
+            let signature_var =
+                <Gadget as SigVerifyGadget<Ed25519, Fr>>::SignatureVar::new_witness(
+                    cs.clone(),
+                    || Ok(signature),
+                )
+                .unwrap();
+            let message_var = UInt8::new_witness_vec(cs.clone(), &message[..]).unwrap();
+            let is_valid = Gadget::verify(
+                &parameters_var,
+                &public_key_var,
+                &message_var,
+                &signature_var,
+            )
+            .unwrap();
+            assert_eq!(is_valid.value().unwrap(), expected);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! Implementation of the Ed25519 signature scheme as specified in RFC 8032, such that signatures
+//! produced by existing EdDSA tooling verify (and vice versa).
+//!
+//! Verification checks the cofactorless equation `[S]B = R + [k]A`, which is also what the
+//! constraints in `constraints.rs` enforce.
+
+use crate::messages::read_bytes;
+use crate::prelude::*;
+use ark_ec::twisted_edwards::TECurveConfig;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ed25519::{EdwardsAffine, EdwardsConfig, EdwardsProjective, Fq, Fr};
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+};
+use ark_std::{One, Zero};
+use num_bigint::BigUint;
+use sha2::{Digest, Sha512};
+
+pub mod constraints;
+
+/// Ed25519 Signature Scheme (RFC 8032)
+pub struct Ed25519;
+
+/// Public key for Ed25519 signatures: the encoding of the public point `A` (32 bytes).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, CanonicalSerialize)]
+pub struct Ed25519PublicKey {
+    pub bytes: [u8; 32],
+}
+
+/// Secret key for Ed25519 signatures: the 32 byte seed from which the secret scalar and the nonce
+/// prefix are derived.
+#[derive(Clone, Default, CanonicalSerialize)]
+pub struct Ed25519SecretKey {
+    pub seed: [u8; 32],
+}
+
+/// Ed25519 signature: the encoding of the point `R` and the scalar `S` (as 32 little-endian bytes).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, CanonicalSerialize)]
+pub struct Ed25519Signature {
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+}
+
+// the byte arrays are read with `read_exact`, such that truncated keys and signatures are rejected
+// with an error
+impl Valid for Ed25519PublicKey {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for Ed25519PublicKey {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        _compress: Compress,
+        _validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self {
+            bytes: read_bytes(reader)?,
+        })
+    }
+}
+
+impl Valid for Ed25519SecretKey {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for Ed25519SecretKey {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        _compress: Compress,
+        _validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self {
+            seed: read_bytes(reader)?,
+        })
+    }
+}
+
+impl Valid for Ed25519Signature {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for Ed25519Signature {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        _compress: Compress,
+        _validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self {
+            r: read_bytes(&mut reader)?,
+            s: read_bytes(&mut reader)?,
+        })
+    }
+}
+
+impl Ed25519SecretKey {
+    /// Secret scalar `s` (clamped lower half of the hashed seed) and the nonce prefix (upper half).
+    fn expand(&self) -> (Fr, [u8; 32]) {
+        let hash = Sha512::digest(self.seed);
+        let mut scalar_bytes = [0; 32];
+        scalar_bytes.copy_from_slice(&hash[..32]);
+        scalar_bytes[0] &= 248;
+        scalar_bytes[31] &= 127;
+        scalar_bytes[31] |= 64;
+        let mut prefix = [0; 32];
+        prefix.copy_from_slice(&hash[32..]);
+        (Fr::from_le_bytes_mod_order(&scalar_bytes), prefix)
+    }
+
+    /// Public key that belongs to this secret key.
+    pub fn public_key(&self) -> Ed25519PublicKey {
+        let (scalar, _) = self.expand();
+        Ed25519PublicKey {
+            bytes: encode_point(&(EdwardsAffine::generator() * scalar).into_affine()),
+        }
+    }
+}
+
+/// Encode a point as its y-coordinate (little-endian) with the sign of x in the most significant
+/// bit.
+pub(crate) fn encode_point(point: &EdwardsAffine) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    bytes.copy_from_slice(&point.y.into_bigint().to_bytes_le());
+    if point.x.into_bigint().is_odd() {
+        bytes[31] |= 0x80;
+    }
+    bytes
+}
+
+/// Recover the x-coordinate of the point with the given y-coordinate and sign of x, i.e.,
+/// `x^2 = (y^2 - 1) / (d y^2 + 1)`. Returns `None` if there is no such point.
+pub(crate) fn recover_x(y: Fq, sign: bool) -> Option<Fq> {
+    let y2 = y.square();
+    let x2 = (y2 - Fq::one()) * (EdwardsConfig::COEFF_D * y2 + Fq::one()).inverse()?;
+    let x = x2.sqrt()?;
+    if x.is_zero() && sign {
+        return None;
+    }
+    Some(if x.into_bigint().is_odd() == sign {
+        x
+    } else {
+        -x
+    })
+}
+
+/// Decode a point as specified in RFC 8032. Returns `None` for non-canonical y-coordinates and
+/// encodings of points that are not on the curve.
+pub(crate) fn decode_point(bytes: &[u8; 32]) -> Option<EdwardsAffine> {
+    let sign = bytes[31] >> 7 == 1;
+    let mut y_bytes = *bytes;
+    y_bytes[31] &= 0x7f;
+    if BigUint::from_bytes_le(&y_bytes) >= BigUint::from(Fq::MODULUS) {
+        return None;
+    }
+    let y = Fq::from_le_bytes_mod_order(&y_bytes);
+    Some(EdwardsAffine::new_unchecked(recover_x(y, sign)?, y))
+}
+
+/// Hash the given byte strings with SHA-512 and reduce the digest modulo the group order.
+fn hash_to_scalar(inputs: &[&[u8]]) -> Fr {
+    let mut hasher = Sha512::new();
+    for input in inputs {
+        hasher.update(input);
+    }
+    Fr::from_le_bytes_mod_order(&hasher.finalize())
+}
+
+impl SignatureVerifier for Ed25519 {
+    type Parameters = ();
+    type PublicKey = Ed25519PublicKey;
+    type Signature = Ed25519Signature;
+
+    fn verify(
+        _parameters: &Self::Parameters,
+        pk: &Self::PublicKey,
+        message: &[u8],
+        signature: &Self::Signature,
+    ) -> Result<bool, Error> {
+        // malformed signatures are rejected rather than reported as an error
+        let (Some(public_point), Some(r_point)) =
+            (decode_point(&pk.bytes), decode_point(&signature.r))
+        else {
+            return Ok(false);
+        };
+        if BigUint::from_bytes_le(&signature.s) >= BigUint::from(Fr::MODULUS) {
+            return Ok(false);
+        }
+        let s = Fr::from_le_bytes_mod_order(&signature.s);
+
+        // [S]B = R + [H(R || A || M)]A
+        let challenge = hash_to_scalar(&[&signature.r[..], &pk.bytes[..], message]);
+        let lhs: EdwardsProjective = EdwardsAffine::generator() * s;
+        Ok(lhs == public_point * challenge + r_point)
+    }
+}
+
+impl SignatureScheme for Ed25519 {
+    type SecretKey = Ed25519SecretKey;
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(())
+    }
+
+    fn keygen<R: Rng>(
+        _parameters: &Self::Parameters,
+        rng: &mut R,
+    ) -> Result<(Self::PublicKey, Self::SecretKey), Error> {
+        let mut seed = [0; 32];
+        rng.fill_bytes(&mut seed);
+        let secret_key = Ed25519SecretKey { seed };
+        Ok((secret_key.public_key(), secret_key))
+    }
+
+    /// Sign deterministically, i.e., the nonce is derived from the secret key and the message as
+    /// specified in RFC 8032 (`rng` is not used).
+    fn sign<R: Rng>(
+        _parameters: &Self::Parameters,
+        sk: &Self::SecretKey,
+        message: &[u8],
+        _rng: &mut R,
+    ) -> Result<Self::Signature, Error> {
+        let (scalar, prefix) = sk.expand();
+        let public_key = sk.public_key();
+
+        // r = H(prefix || M), R = [r]B
+        let nonce = hash_to_scalar(&[&prefix[..], message]);
+        let r = encode_point(&(EdwardsAffine::generator() * nonce).into_affine());
+
+        // S = r + H(R || A || M) * s
+        let challenge = hash_to_scalar(&[&r[..], &public_key.bytes[..], message]);
+        let mut s = [0; 32];
+        s.copy_from_slice(&(nonce + challenge * scalar).into_bigint().to_bytes_le());
+        Ok(Ed25519Signature { r, s })
+    }
+}
+
+impl<F: PrimeField> ToConstraintField<F> for Ed25519PublicKey {
+    #[inline]
+    fn to_field_elements(&self) -> Option<Vec<F>> {
+        self.bytes.as_slice().to_field_elements()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Ed25519, Ed25519PublicKey, Ed25519SecretKey, Ed25519Signature};
+    use crate::prelude::{SignatureScheme, SignatureVerifier};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use ark_std::test_rng;
+
+    fn from_hex<const N: usize>(hex: &str) -> [u8; N] {
+        let mut bytes = [0; N];
+        for (byte, chunk) in bytes.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            *byte = u8::from_str_radix(std::str::from_utf8(chunk).unwrap(), 16).unwrap();
+        }
+        bytes
+    }
+
+    #[test]
+    fn rfc8032_test_vectors() {
+        // test vectors 1 and 2 of RFC 8032, Sec. 7.1
+        let vectors: [(&str, &str, &[u8], &str); 2] = [
+            (
+                "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+                &[],
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+            ),
+            (
+                "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+                "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+                &[0x72],
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+            ),
+        ];
+        let rng = &mut test_rng();
+        for (seed, public_key, message, signature) in vectors {
+            let secret_key = Ed25519SecretKey {
+                seed: from_hex(seed),
+            };
+            let public_key = Ed25519PublicKey {
+                bytes: from_hex(public_key),
+            };
+            let signature_bytes: [u8; 64] = from_hex(signature);
+            let mut expected_signature = Ed25519Signature::default();
+            expected_signature.r.copy_from_slice(&signature_bytes[..32]);
+            expected_signature.s.copy_from_slice(&signature_bytes[32..]);
+
+            assert_eq!(secret_key.public_key(), public_key);
+            let signature = Ed25519::sign(&(), &secret_key, message, rng).unwrap();
+            assert_eq!(signature, expected_signature);
+            assert!(Ed25519::verify(&(), &public_key, message, &expected_signature).unwrap());
+        }
+    }
+
+    #[test]
+    fn malformed_signatures_are_rejected_without_error() {
+        let rng = &mut test_rng();
+        let (public_key, secret_key) = Ed25519::keygen(&(), rng).unwrap();
+        let message = b"server seed";
+        let signature = Ed25519::sign(&(), &secret_key, message, rng).unwrap();
+
+        // S not reduced modulo the group order
+        let mut malformed = signature;
+        malformed.s = [0xff; 32];
+        assert!(!Ed25519::verify(&(), &public_key, message, &malformed).unwrap());
+
+        // R with a non-canonical y-coordinate (y = p)
+        let mut malformed = signature;
+        malformed.r = [0xff; 32];
+        malformed.r[0] = 0xed;
+        malformed.r[31] = 0x7f;
+        assert!(!Ed25519::verify(&(), &public_key, message, &malformed).unwrap());
+
+        // public key that does not encode a point on the curve (y = 2)
+        let mut malformed_public_key = Ed25519PublicKey::default();
+        malformed_public_key.bytes[0] = 2;
+        assert!(!Ed25519::verify(&(), &malformed_public_key, message, &signature).unwrap());
+    }
+    #[test]
+    fn truncated_encodings_are_rejected() {
+        let rng = &mut test_rng();
+        let (public_key, secret_key) = Ed25519::keygen(&(), rng).unwrap();
+        let signature = Ed25519::sign(&(), &secret_key, b"server seed", rng).unwrap();
+
+        let mut bytes = Vec::new();
+        signature.serialize_compressed(&mut bytes).unwrap();
+        assert_eq!(
+            Ed25519Signature::deserialize_compressed(bytes.as_slice()).unwrap(),
+            signature
+        );
+        assert!(Ed25519Signature::deserialize_compressed(&bytes[..40]).is_err());
+
+        let mut bytes = Vec::new();
+        public_key.serialize_compressed(&mut bytes).unwrap();
+        assert_eq!(
+            Ed25519PublicKey::deserialize_compressed(bytes.as_slice()).unwrap(),
+            public_key
+        );
+        assert!(Ed25519PublicKey::deserialize_compressed(&bytes[..31]).is_err());
+    }
+}
@@ -16,13 +16,29 @@ pub use constraints::*;
 pub mod schnorr;
 pub use schnorr::*;
 
-/// Generic definition of a Signature Scheme
-pub trait SignatureScheme {
+// Ed25519 scheme
+pub mod ed25519;
+
+/// Verification half of a signature scheme, such that components that only verify signatures
+/// (e.g., a client checking the server signature) do not depend on the signing half.
+pub trait SignatureVerifier {
     type Parameters: Clone + Send + Sync;
     type PublicKey: CanonicalSerialize + Hash + Eq + Clone + Default + Send + Sync;
-    type SecretKey: CanonicalSerialize + Clone + Default;
     type Signature: CanonicalSerialize + Clone + Default + Send + Sync;
 
+    fn verify(
+        pp: &Self::Parameters,
+        pk: &Self::PublicKey,
+        message: &[u8],
+        signature: &Self::Signature,
+    ) -> Result<bool, Error>;
+}
+
+/// Generic definition of a Signature Scheme (the signing half, see `SignatureVerifier` for the
+/// verification half)
+pub trait SignatureScheme: SignatureVerifier {
+    type SecretKey: CanonicalSerialize + Clone + Default;
+
     fn setup<R: Rng>(rng: &mut R) -> Result<Self::Parameters, Error>;
 
     fn keygen<R: Rng>(
@@ -36,20 +52,13 @@ pub trait SignatureScheme {
         message: &[u8],
         rng: &mut R,
     ) -> Result<Self::Signature, Error>;
-
-    fn verify(
-        pp: &Self::Parameters,
-        pk: &Self::PublicKey,
-        message: &[u8],
-        signature: &Self::Signature,
-    ) -> Result<bool, Error>;
 }
 
 #[cfg(test)]
 mod test {
-    use crate::prelude::SignatureScheme;
+    use crate::prelude::{SignatureScheme, SignatureVerifier};
     use crate::primitives::crh::Blake2s256;
-    use crate::primitives::signature::Schnorr;
+    use crate::primitives::signature::{Ed25519, Schnorr};
     use ark_crypto_primitives::crh::sha256::Sha256;
     use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
     use ark_std::test_rng;
@@ -85,4 +94,38 @@ mod test {
             "Bad message".as_bytes(),
         );
     }
+
+    /// Verifier-only node, which does not depend on the signing half of the scheme.
+    fn verify_only<V: SignatureVerifier>(
+        parameters: &V::Parameters,
+        pk: &V::PublicKey,
+        message: &[u8],
+        signature: &V::Signature,
+    ) -> bool {
+        V::verify(parameters, pk, message, signature).unwrap()
+    }
+
+    #[test]
+    fn schnorr_verifies_with_verifier_half_only() {
+        type S = Schnorr<JubJub, Blake2s256>;
+        let rng = &mut test_rng();
+        let message = "Hi, I am a Schnorr signature!".as_bytes();
+        let parameters = S::setup(rng).unwrap();
+        let (pk, sk) = S::keygen(&parameters, rng).unwrap();
+        let sig = S::sign(&parameters, &sk, message, rng).unwrap();
+        assert!(verify_only::<S>(&parameters, &pk, message, &sig));
+        assert!(!verify_only::<S>(
+            &parameters,
+            &pk,
+            "Bad message".as_bytes(),
+            &sig
+        ));
+    }
+
+    #[test]
+    fn ed25519_signature_test() {
+        let message = "Hi, I am an Ed25519 signature!";
+        sign_and_verify::<Ed25519>(message.as_bytes());
+        failed_verification::<Ed25519>(message.as_bytes(), "Bad message".as_bytes());
+    }
 }
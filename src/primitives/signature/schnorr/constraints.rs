@@ -22,6 +22,7 @@ type ConstraintF<C> = <<C as CurveGroup>::BaseField as Field>::BasePrimeField;
 /// - Parameters for the message hash function
 /// - Group generator
 /// - Salt
+/// - Domain separator
 #[derive(Clone)]
 pub struct ParametersVar<
     C: CurveGroup,
@@ -34,6 +35,7 @@ pub struct ParametersVar<
     hash_params: HG::ParametersVar,
     generator: CG,
     salt: Vec<UInt8<ConstraintF<C>>>,
+    domain: Vec<UInt8<ConstraintF<C>>>,
 }
 
 /// Public key variable for Schnorr Signature scheme
@@ -124,6 +126,7 @@ where
         claimed_prover_commitment += &public_key_times_verifier_challenge;
 
         let mut hash_input = Vec::new();
+        hash_input.extend_from_slice(parameters.domain.as_ref());
         hash_input.extend_from_slice(parameters.salt.as_ref());
         hash_input.extend_from_slice(&claimed_prover_commitment.to_bytes()?);
         hash_input.extend_from_slice(message);
@@ -166,10 +169,18 @@ where
                     UInt8::new_witness_vec(cs.clone(), &val.borrow().salt.map(|x| Some(x)))?
                 }
             };
+            let domain = match mode {
+                AllocationMode::Constant => UInt8::constant_vec(&val.borrow().domain),
+                AllocationMode::Input => UInt8::new_input_vec(cs.clone(), &val.borrow().domain)?,
+                AllocationMode::Witness => {
+                    UInt8::new_witness_vec(cs.clone(), &val.borrow().domain)?
+                }
+            };
             return Ok(Self {
                 hash_params,
                 generator,
                 salt,
+                domain,
             });
         })
     }
@@ -0,0 +1,87 @@
+//! Known-answer tests for the Schnorr signature scheme: signatures from a fixed RNG seed are
+//! compared against checked-in encodings, such that changing the hash (or the way its digest is
+//! turned into a challenge) cannot silently break compatibility with existing signatures.
+//!
+//! Only digests that deserialize to a canonical scalar are accepted as challenge, so the retry
+//! loop in `sign` consumes a different number of nonces for each hash. With the seed below,
+//! Blake2s256 only yields a valid challenge for the 15th nonce, while SHA-256 accepts the first.
+
+use super::{Schnorr, Signature};
+use crate::prelude::*;
+use crate::primitives::crh::Blake2s256;
+use ark_crypto_primitives::crh::sha256::Sha256;
+use ark_ed_on_bls12_381::{EdwardsProjective as JubJub, Fr};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::One;
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+
+type S<H> = Schnorr<JubJub, H>;
+
+const MESSAGE: &[u8] = b"VLDP Schnorr known-answer test";
+
+/// Compressed public key for seed 97 (the same for both hashes, as they need no randomness).
+const PUBLIC_KEY: &str = "832ffbbef37a458ed67a7801025384e427dec0c0645bfb08a0c04a85bb992f2f";
+
+/// Compressed signature (prover response, then verifier challenge) with Blake2s256.
+const SIGNATURE_BLAKE2S256: &str = "7a5399c89a4bf6759d29ac7551bd79860204f02c4172ec08c6e969a5f8588a0b818121a4528859dd480ab39c71eb230c23df5d01f4f469dbf5a979ab17f2b600";
+
+/// Compressed signature (prover response, then verifier challenge) with SHA-256.
+const SIGNATURE_SHA256: &str = "8466c1d3dcb0e690955428348d970c2d64123e35c5e306f4c23ff87f9c4f730aedc180c96b6f4192816260a1e72eb8d1a26eaa89820c21974087140f197aae01";
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Run setup, key generation, and signing from the fixed seed, compare the public key and the
+/// signature against the expected encodings, and check that the signature verifies while
+/// tampered signatures do not.
+fn known_answer_test<H>(expected_signature: &str)
+where
+    H: CRHScheme<Input = [u8], Output = Vec<u8>> + Send + Sync,
+    H::Parameters: Send + Sync,
+{
+    let rng = &mut ChaChaRng::from_seed([97; 32]);
+    let parameters = S::<H>::setup(rng).unwrap();
+    let (pk, sk) = S::<H>::keygen(&parameters, rng).unwrap();
+    let signature = S::<H>::sign(&parameters, &sk, MESSAGE, rng).unwrap();
+
+    let mut pk_bytes = vec![];
+    pk.serialize_compressed(&mut pk_bytes).unwrap();
+    assert_eq!(to_hex(&pk_bytes), PUBLIC_KEY);
+    let mut signature_bytes = vec![];
+    signature
+        .serialize_compressed(&mut signature_bytes)
+        .unwrap();
+    assert_eq!(to_hex(&signature_bytes), expected_signature);
+
+    // the checked-in encoding decodes to a valid signature
+    let signature =
+        Signature::<JubJub>::deserialize_compressed(signature_bytes.as_slice()).unwrap();
+    assert!(S::<H>::verify(&parameters, &pk, MESSAGE, &signature).unwrap());
+
+    // tampering with either half of the signature or with the message is detected
+    let mut tampered = signature.clone();
+    tampered.prover_response += Fr::one();
+    assert!(!S::<H>::verify(&parameters, &pk, MESSAGE, &tampered).unwrap());
+    let mut tampered = signature.clone();
+    tampered.verifier_challenge += Fr::one();
+    assert!(!S::<H>::verify(&parameters, &pk, MESSAGE, &tampered).unwrap());
+    assert!(!S::<H>::verify(
+        &parameters,
+        &pk,
+        b"VLDP Schnorr known-answer tesT",
+        &signature
+    )
+    .unwrap());
+}
+
+#[test]
+fn blake2s256_known_answer() {
+    known_answer_test::<Blake2s256>(SIGNATURE_BLAKE2S256);
+}
+
+#[test]
+fn sha256_known_answer() {
+    known_answer_test::<Sha256>(SIGNATURE_SHA256);
+}
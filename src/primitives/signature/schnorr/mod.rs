@@ -13,6 +13,8 @@ use std::ops::Mul;
 
 use derivative::Derivative;
 pub mod constraints;
+#[cfg(test)]
+mod kat;
 
 /// Schnorr Signature Scheme
 pub struct Schnorr<C: CurveGroup, H: CRHScheme> {
@@ -26,6 +28,7 @@ pub struct Schnorr<C: CurveGroup, H: CRHScheme> {
 /// - Parameters for the message hash function
 /// - Group generator
 /// - Salt
+/// - Domain separator (see `with_domain`)
 #[derive(Derivative)]
 #[derivative(Clone(
     bound = "C: CurveGroup, H: CRHScheme, <H as CRHScheme>::Parameters: Send + Sync"
@@ -37,13 +40,30 @@ where
     pub hash_params: H::Parameters,
     pub generator: C::Affine,
     pub salt: [u8; 32],
+    /// Label that is prepended to every hash input, such that signatures cannot be used across
+    /// contexts that share these parameters otherwise. Empty after `setup`.
+    pub domain: Vec<u8>,
+}
+
+impl<C: CurveGroup, H: CRHScheme> Parameters<C, H>
+where
+    <H as CRHScheme>::Parameters: Send + Sync,
+{
+    /// Sign and verify under the domain separator `domain`: the hash input becomes
+    /// `domain || salt || kG || msg`, so a signature made under one domain does not verify under
+    /// another. The domains of a deployment should be prefix-free (e.g., of equal length), as the
+    /// domain is not length-prefixed; the empty domain leaves the hash input unchanged.
+    pub fn with_domain(mut self, domain: &[u8]) -> Self {
+        self.domain = domain.to_vec();
+        self
+    }
 }
 
 // Public key is simply an alias to an affine group element (struct is overkill)
 pub type PublicKey<C> = <C as CurveGroup>::Affine;
 
 /// Secret Key for Schnorr Signature (contains only a scalar)
-#[derive(Clone, Default, Debug, CanonicalSerialize)]
+#[derive(Clone, Default, Debug, CanonicalSerialize, CanonicalDeserialize)]
 pub struct SecretKey<C: CurveGroup>(pub C::ScalarField);
 
 /// Convenient trait for conversing a message hash digest to a scalar. This is mostly defined for
@@ -68,7 +88,7 @@ pub struct Signature<C: CurveGroup> {
     pub verifier_challenge: C::ScalarField,
 }
 
-impl<C: CurveGroup + Hash, H: CRHScheme<Input = [u8]> + Send + Sync> SignatureScheme
+impl<C: CurveGroup + Hash, H: CRHScheme<Input = [u8]> + Send + Sync> SignatureVerifier
     for Schnorr<C, H>
 where
     C::ScalarField: PrimeField,
@@ -77,13 +97,65 @@ where
 {
     type Parameters = Parameters<C, H>;
     type PublicKey = PublicKey<C>;
-    type SecretKey = SecretKey<C>;
     type Signature = Signature<C>;
 
+    fn verify(
+        parameters: &Self::Parameters,
+        pk: &Self::PublicKey,
+        message: &[u8],
+        signature: &Self::Signature,
+    ) -> Result<bool, Error> {
+        let verify_time = start_timer!(|| "SchnorrSig::Verify");
+
+        let Signature {
+            prover_response,
+            verifier_challenge,
+        } = signature;
+        // sG = kG - eY
+        // kG = sG + eY
+        // so we first solve for kG.
+        let mut claimed_prover_commitment = parameters.generator.mul(*prover_response);
+        let public_key_times_verifier_challenge = pk.mul(verifier_challenge);
+        claimed_prover_commitment += &public_key_times_verifier_challenge;
+        let claimed_prover_commitment = claimed_prover_commitment.into_affine();
+
+        // e = H(domain, salt, kG, msg)
+        let mut hash_input = Vec::new();
+        hash_input.extend_from_slice(&parameters.domain);
+        hash_input.extend_from_slice(&parameters.salt);
+        claimed_prover_commitment.serialize_uncompressed(&mut hash_input)?;
+        hash_input.extend_from_slice(&message);
+
+        // cast the hash output to get e
+        let hash_digest = H::evaluate(&parameters.hash_params, hash_input.as_slice())
+            .map_err(VldpError::Signature)?;
+
+        let obtained_verifier_challenge = hash_digest.digest_to_scalar_field();
+        end_timer!(verify_time);
+        // The signature is valid iff the computed verifier challenge is the same as the one
+        // provided in the signature
+        match obtained_verifier_challenge {
+            Ok(obtained_verifier_challenge) => {
+                Ok(*verifier_challenge == obtained_verifier_challenge)
+            }
+            Err(_) => Ok(false),
+        }
+    }
+}
+
+impl<C: CurveGroup + Hash, H: CRHScheme<Input = [u8]> + Send + Sync> SignatureScheme
+    for Schnorr<C, H>
+where
+    C::ScalarField: PrimeField,
+    <H as CRHScheme>::Parameters: Send + Sync,
+    <H as CRHScheme>::Output: DigestToScalarField<C>,
+{
+    type SecretKey = SecretKey<C>;
+
     fn setup<R: Rng>(rng: &mut R) -> Result<Self::Parameters, Error> {
         let setup_time = start_timer!(|| "SchnorrSig::Setup");
 
-        let hash_params = H::setup(rng)?;
+        let hash_params = H::setup(rng).map_err(VldpError::Signature)?;
         let mut salt = [0u8; 32];
         rng.fill_bytes(&mut salt);
         let generator = C::rand(rng).into();
@@ -93,6 +165,7 @@ where
             hash_params,
             generator,
             salt,
+            domain: Vec::new(),
         })
     }
 
@@ -127,13 +200,15 @@ where
             let prover_commitment = parameters.generator.mul(random_scalar).into_affine();
 
             // Hash everything to get verifier challenge.
-            // e := H(salt || r || msg);
+            // e := H(domain || salt || r || msg);
             let mut hash_input = Vec::new();
+            hash_input.extend_from_slice(&parameters.domain);
             hash_input.extend_from_slice(&parameters.salt);
             prover_commitment.serialize_uncompressed(&mut hash_input)?;
             hash_input.extend_from_slice(&message);
 
-            let hash_digest = H::evaluate(&parameters.hash_params, hash_input.as_slice())?;
+            let hash_digest = H::evaluate(&parameters.hash_params, hash_input.as_slice())
+                .map_err(VldpError::Signature)?;
 
             if let Ok(verifier_challenge) = hash_digest.digest_to_scalar_field() {
                 break (random_scalar, verifier_challenge);
@@ -149,47 +224,6 @@ where
         end_timer!(sign_time);
         Ok(signature)
     }
-
-    fn verify(
-        parameters: &Self::Parameters,
-        pk: &Self::PublicKey,
-        message: &[u8],
-        signature: &Self::Signature,
-    ) -> Result<bool, Error> {
-        let verify_time = start_timer!(|| "SchnorrSig::Verify");
-
-        let Signature {
-            prover_response,
-            verifier_challenge,
-        } = signature;
-        // sG = kG - eY
-        // kG = sG + eY
-        // so we first solve for kG.
-        let mut claimed_prover_commitment = parameters.generator.mul(*prover_response);
-        let public_key_times_verifier_challenge = pk.mul(verifier_challenge);
-        claimed_prover_commitment += &public_key_times_verifier_challenge;
-        let claimed_prover_commitment = claimed_prover_commitment.into_affine();
-
-        // e = H(salt, kG, msg)
-        let mut hash_input = Vec::new();
-        hash_input.extend_from_slice(&parameters.salt);
-        claimed_prover_commitment.serialize_uncompressed(&mut hash_input)?;
-        hash_input.extend_from_slice(&message);
-
-        // cast the hash output to get e
-        let hash_digest = H::evaluate(&parameters.hash_params, hash_input.as_slice())?;
-
-        let obtained_verifier_challenge = hash_digest.digest_to_scalar_field();
-        end_timer!(verify_time);
-        // The signature is valid iff the computed verifier challenge is the same as the one
-        // provided in the signature
-        match obtained_verifier_challenge {
-            Ok(obtained_verifier_challenge) => {
-                Ok(*verifier_challenge == obtained_verifier_challenge)
-            }
-            Err(_) => Ok(false),
-        }
-    }
 }
 
 impl<ConstraintF: Field, C: CurveGroup + ToConstraintField<ConstraintF>, H: CRHScheme>
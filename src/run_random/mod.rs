@@ -9,3 +9,6 @@ pub use run_protocol_expand::*;
 
 mod run_protocol_shuffle;
 pub use run_protocol_shuffle::*;
+
+mod sweep_gamma;
+pub use sweep_gamma::*;
@@ -3,6 +3,7 @@
 
 use crate::circuits::CircuitBase;
 use crate::client::*;
+use crate::messages::InputValueSignatureInput;
 use crate::prelude::*;
 use crate::server::*;
 use ark_ff::{PrimeField, Zero};
@@ -13,6 +14,26 @@ use num_bigint::BigUint;
 use rand::SeedableRng;
 use rand_chacha::ChaChaRng;
 use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Durations of the phases of a protocol run (see `run_protocol_base_timed`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProtocolTimings {
+    /// Generating the proving and verifying key of the circuit
+    pub keygen: Duration,
+    /// First part of the `Generate Randomness` step on the client
+    pub generate_randomness_client: Duration,
+    /// `Generate Randomness` step on the server
+    pub generate_randomness_server: Duration,
+    /// Second part of the `Generate Randomness` step on the client
+    pub generate_randomness_verify: Duration,
+    /// Signing the input value in the (emulated) trusted environment
+    pub trusted_environment: Duration,
+    /// `Randomize` step on the client, i.e., creating the proof
+    pub prove: Duration,
+    /// `Verify` step on the server
+    pub verify: Duration,
+}
 
 /// Run the Base protocol for a given gamma on random inputs (trusted environment and
 /// communication are emulated).
@@ -36,17 +57,51 @@ where
     ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
     Proof<Conf>: CanonicalDeserialize,
 {
-    assert!(IS_REAL_INPUT || INPUT_BYTES + GAMMA_BYTES <= RANDOMNESS_BYTES);
-    assert!(!IS_REAL_INPUT || 2 * INPUT_BYTES + GAMMA_BYTES <= RANDOMNESS_BYTES);
-    assert!(K >= 2 && (K.ilog2() + 1) as usize <= INPUT_BYTES * 8);
+    run_protocol_base_timed::<
+        Conf,
+        ConfG,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >(gamma)?;
+    Ok(())
+}
+
+/// Run the Base protocol like `run_protocol_base`, and return how long each of its phases took.
+pub fn run_protocol_base_timed<
+    Conf: Config,
+    ConfG: ConfigGadget<Conf>,
+    const INPUT_BYTES: usize,
+    const TIME_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+    const K: u64,
+    const IS_REAL_INPUT: bool,
+>(
+    gamma: BigFloat,
+) -> Result<ProtocolTimings, Error>
+where
+    ClientSignatureSchemePublicKey<Conf>:
+        ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+    ClientCommitmentSchemeOutput<Conf>:
+        ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    Proof<Conf>: CanonicalDeserialize,
+{
+    validate_scheme_params::<INPUT_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>()?;
     assert!(INPUT_BYTES * 8 <= ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize);
     assert!(GAMMA_BYTES * 8 <= ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize);
     assert!(TIME_BYTES * 8 <= ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize);
     let mut rng = ChaChaRng::from_entropy();
     let mut zkp_rng = Conf::ZKPRng::new();
+    let mut timings = ProtocolTimings::default();
 
     // setup
     let parameters = ParametersBase::setup(gamma, &mut rng)?;
+    let start = Instant::now();
     let (proving_key, verifying_key) = CircuitBase::<
         _,
         ConfG,
@@ -57,6 +112,7 @@ where
         K,
         IS_REAL_INPUT,
     >::keygen(parameters.clone(), &mut zkp_rng)?;
+    timings.keygen = start.elapsed();
     let (client_sig_pk, client_sig_sk) =
         Conf::ClientSignatureScheme::keygen(&parameters.client_signature_scheme, &mut rng)?;
 
@@ -93,30 +149,31 @@ where
 
     // 1. generate randomness
     let timer_gen_rand_client = start_timer!(|| "Client generation");
+    let start = Instant::now();
     let gen_rand_client_message = client.generate_randomness_create([0; TIME_BYTES], &mut rng)?;
+    timings.generate_randomness_client = start.elapsed();
     end_timer!(timer_gen_rand_client);
 
     let timer_gen_rand_server = start_timer!(|| "Server generation");
+    let start = Instant::now();
     let gen_rand_server_message =
         server.generate_randomness_create(&gen_rand_client_message, &mut rng)?;
+    timings.generate_randomness_server = start.elapsed();
     end_timer!(timer_gen_rand_server);
 
     let timer_verify_rand_client = start_timer!(|| "Client verification");
-    let result = client.generate_randomness_verify(&gen_rand_server_message)?;
+    let start = Instant::now();
+    client.generate_randomness_verify(&gen_rand_server_message)?;
+    timings.generate_randomness_verify = start.elapsed();
     end_timer!(timer_verify_rand_client);
 
     end_timer!(timer_gen_rand);
 
-    assert!(
-        result,
-        "Verification of generate randomness protocol failed."
-    );
-
     // 2. verifiable randomization
     // -- START TRUSTED ENVIRONMENT --
 
     let input_value = if IS_REAL_INPUT {
-        let precision = INPUT_BYTES * 8 * 2;
+        let precision = float_conversion_precision("INPUT_BYTES", INPUT_BYTES)?;
         let input_f64 = rng.gen_range(0.0..=1.0);
         let input_bigfloat = BigFloat::from_f64(input_f64, precision);
         if input_bigfloat.is_zero() {
@@ -132,8 +189,9 @@ where
                 .convert_to_radix(
                     Radix::Dec,
                     RoundingMode::None,
-                    &mut Consts::new().expect("Constants cache initialization should not fail."),
-                )?
+                    &mut Consts::new().map_err(GenericError::from)?,
+                )
+                .map_err(GenericError::from)?
                 .1
                 .iter()
                 .map(|digit| digit.to_string())
@@ -161,39 +219,48 @@ where
         input_value_bytes[idx] = *byte;
     }
 
-    let mut message_bytes = input_value_bytes.to_vec();
-    message_bytes.extend_from_slice(&input_value_time);
+    let message_bytes = InputValueSignatureInput {
+        input_value: input_value_bytes,
+        input_value_time,
+    }
+    .to_bytes();
 
     let timer_te = start_timer!(|| "Trusted environment computation");
+    let start = Instant::now();
     let input_value_signature = Conf::ClientSignatureScheme::sign(
         &parameters.client_signature_scheme,
         &client_sig_sk,
         &message_bytes,
         &mut rng,
     )?;
+    timings.trusted_environment = start.elapsed();
     end_timer!(timer_te);
 
     // -- END TRUSTED ENVIRONMENT --
     let timer_ver_rand = start_timer!(|| "Verifiable randomization");
 
     let timer_ver_rand_client = start_timer!(|| "Client generation");
+    let start = Instant::now();
     let ver_rand_client_message = client.verifiable_randomization_create::<ConfG>(
         time_bounds,
         input_value_time,
-        input_value,
+        [input_value],
         input_value_signature,
         &mut zkp_rng,
         false,
     )?;
+    timings.prove = start.elapsed();
     end_timer!(timer_ver_rand_client);
 
     let timer_ver_rand_server = start_timer!(|| "Server verification");
+    let start = Instant::now();
     let result = server.verifiable_randomization_verify::<ConfG>(
         &ver_rand_client_message,
         time_bounds,
         &mut zkp_rng,
         false,
     )?;
+    timings.verify = start.elapsed();
     end_timer!(timer_ver_rand_server);
 
     end_timer!(timer_ver_rand);
@@ -223,5 +290,5 @@ where
         )
     }
 
-    Ok(())
+    Ok(timings)
 }
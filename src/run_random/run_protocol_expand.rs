@@ -3,6 +3,7 @@
 
 use crate::circuits::CircuitExpand;
 use crate::client::*;
+use crate::messages::{derive_prf_eval_points, required_prf_eval_points, InputValueSignatureInput};
 use crate::prelude::*;
 use crate::server::*;
 use ark_ff::PrimeField;
@@ -13,6 +14,7 @@ use num_bigint::BigUint;
 use rand::SeedableRng;
 use rand_chacha::ChaChaRng;
 use std::str::FromStr;
+use std::sync::Arc;
 
 /// Run the Expand protocol for a given gamma on random inputs (trusted environment and
 /// communication are emulated).
@@ -36,9 +38,7 @@ where
     ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
     Proof<Conf>: CanonicalDeserialize,
 {
-    assert!(IS_REAL_INPUT || INPUT_BYTES + GAMMA_BYTES <= RANDOMNESS_BYTES);
-    assert!(!IS_REAL_INPUT || 2 * INPUT_BYTES + GAMMA_BYTES <= RANDOMNESS_BYTES);
-    assert!(K >= 2 && (K.ilog2() + 1) as usize <= INPUT_BYTES * 8);
+    validate_scheme_params::<INPUT_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>()?;
     assert!(INPUT_BYTES * 8 <= ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize);
     assert!(TIME_BYTES * 8 <= ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize);
     let mut rng = ChaChaRng::from_entropy();
@@ -59,9 +59,10 @@ where
     >::keygen(parameters.clone(), &mut zkp_rng)?;
     let (client_sig_pk, client_sig_sk) =
         Conf::ClientSignatureScheme::keygen(&parameters.client_signature_scheme, &mut rng)?;
-    let prf_eval_points = (0..((RANDOMNESS_BYTES - 1) / 32) + 1)
-        .map(|_| rng.gen::<PRFSchemeInput<Conf>>())
-        .collect::<Vec<_>>();
+    let prf_eval_points = derive_prf_eval_points::<Conf>(
+        rng.gen(),
+        required_prf_eval_points::<Conf>(RANDOMNESS_BYTES),
+    )?;
     let index = 0;
 
     // create server
@@ -108,21 +109,16 @@ where
     end_timer!(timer_gen_rand_server);
 
     let timer_verify_rand_client = start_timer!(|| "Client verification");
-    let result = client.generate_randomness_verify(&gen_rand_server_message)?;
+    client.generate_randomness_verify(&gen_rand_server_message)?;
     end_timer!(timer_verify_rand_client);
 
     end_timer!(timer_gen_rand);
 
-    assert!(
-        result,
-        "Verification of generate randomness protocol failed."
-    );
-
     // 2. verifiable randomization
     // -- START TRUSTED ENVIRONMENT --
 
     let input_value = if IS_REAL_INPUT {
-        let precision = INPUT_BYTES * 8 * 2;
+        let precision = float_conversion_precision("INPUT_BYTES", INPUT_BYTES)?;
         let input_f64 = rng.gen_range(0.0..=1.0);
         let input_bigfloat = BigFloat::from_f64(input_f64, precision);
         if input_bigfloat.is_zero() {
@@ -138,8 +134,9 @@ where
                 .convert_to_radix(
                     Radix::Dec,
                     RoundingMode::None,
-                    &mut Consts::new().expect("Constants cache initialization should not fail."),
-                )?
+                    &mut Consts::new().map_err(GenericError::from)?,
+                )
+                .map_err(GenericError::from)?
                 .1
                 .iter()
                 .map(|digit| digit.to_string())
@@ -162,13 +159,11 @@ where
     let upper_bound_time = input_value_time.clone();
     let time_bounds = (lower_bound_time, upper_bound_time);
 
-    let mut input_value_bytes = [0; INPUT_BYTES];
-    for (idx, byte) in input_value.to_bytes_le().iter().enumerate() {
-        input_value_bytes[idx] = *byte;
+    let message_bytes = InputValueSignatureInput {
+        input_value: encode_input_value::<INPUT_BYTES>(&input_value)?,
+        input_value_time,
     }
-
-    let mut message_bytes = input_value_bytes.to_vec();
-    message_bytes.extend_from_slice(&input_value_time);
+    .signed_bytes::<Conf>(parameters.client_input_hash_scheme.as_ref())?;
 
     let timer_te = start_timer!(|| "Trusted environment computation");
     let input_value_signature = Conf::ClientSignatureScheme::sign(
@@ -235,3 +230,345 @@ where
 
     Ok(())
 }
+
+/// A single reading for `run_single_expand`: the input value and its time, the time bounds of the
+/// `Randomize` step, the public `prf_eval_points`, and the `index` of the reading.
+pub struct ReadingExpand<'a, Conf: Config, const TIME_BYTES: usize> {
+    pub input_value: BigUint,
+    pub input_value_time: [u8; TIME_BYTES],
+    pub time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+    pub prf_eval_points: &'a [PRFSchemeInput<Conf>],
+    pub index: usize,
+}
+
+/// Run the Expand protocol for a single reading with explicit inputs: a fresh client with the
+/// given signature keys performs `Generate Randomness` with `server`, signs the input value of
+/// `reading` (as the trusted environment) and performs `Randomize` for it, which `server` then
+/// verifies. Returns whether the server accepted along with the LDP value, or `(false, 0)` if the
+/// client already rejected the `Generate Randomness` message of the server.
+pub fn run_single_expand<
+    Conf: Config,
+    ConfG: ConfigGadget<Conf>,
+    R: Rng + CryptoRng,
+    const MT_DEPTH: usize,
+    const INPUT_BYTES: usize,
+    const TIME_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+    const K: u64,
+    const IS_REAL_INPUT: bool,
+>(
+    parameters: &ParametersExpand<Conf, GAMMA_BYTES>,
+    proving_key: Arc<ProvingKey<Conf>>,
+    server: &ServerExpand<
+        Conf,
+        MT_DEPTH,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >,
+    client_keys: &(
+        ClientSignatureSchemePublicKey<Conf>,
+        ClientSignatureSchemeSecretKey<Conf>,
+    ),
+    reading: ReadingExpand<Conf, TIME_BYTES>,
+    rng: &mut R,
+    zkp_rng: &mut ZKPRng<Conf>,
+) -> Result<(bool, u64), Error>
+where
+    ClientSignatureSchemePublicKey<Conf>:
+        ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+    ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>>,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    Proof<Conf>: CanonicalDeserialize,
+{
+    let (client_sig_pk, client_sig_sk) = client_keys;
+    let ReadingExpand {
+        input_value,
+        input_value_time,
+        time_bounds,
+        prf_eval_points,
+        index,
+    } = reading;
+    let mut client = ClientExpand::<
+        Conf,
+        MT_DEPTH,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >::new_with_shared_proving_key(
+        parameters.clone(),
+        server.get_signature_public_key(),
+        client_sig_pk.clone(),
+        proving_key,
+    )?;
+
+    // 1. generate randomness
+    let gen_rand_client_message = client.generate_randomness_create(rng)?;
+    let gen_rand_server_message =
+        server.generate_randomness_create(&gen_rand_client_message, rng)?;
+    client.generate_randomness_verify(&gen_rand_server_message)?;
+
+    // 2. verifiable randomization (the input value is signed by the emulated trusted environment)
+    let input_value_signature = Conf::ClientSignatureScheme::sign(
+        &parameters.client_signature_scheme,
+        client_sig_sk,
+        &InputValueSignatureInput {
+            input_value: encode_input_value::<INPUT_BYTES>(&input_value)?,
+            input_value_time,
+        }
+        .signed_bytes::<Conf>(parameters.client_input_hash_scheme.as_ref())?,
+        rng,
+    )?;
+    let ver_rand_client_message = client.verifiable_randomization_create::<ConfG>(
+        time_bounds,
+        input_value_time,
+        input_value,
+        input_value_signature,
+        prf_eval_points,
+        index,
+        zkp_rng,
+        false,
+    )?;
+    server.verifiable_randomization_verify::<ConfG>(
+        &ver_rand_client_message,
+        time_bounds,
+        prf_eval_points,
+        index,
+        zkp_rng,
+        false,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{run_single_expand, ReadingExpand};
+    use crate::circuits::CircuitExpand;
+    use crate::client::ClientExpand;
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::messages::expand::VerifiableRandomizationMessageExpand;
+    use crate::messages::{derive_prf_eval_points, InputValueSignatureInput};
+    use crate::prelude::*;
+    use crate::server::{ServerExpand, VerificationStepExpand};
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+    use std::sync::Arc;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+
+    #[test]
+    fn single_reading_yields_its_input_for_negligible_gamma() {
+        let rng = &mut ChaChaRng::from_seed([49; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([50; 32]);
+        // for a negligible gamma, the LDP value is the input value (unless the gamma bytes of the
+        // randomness are (close to) zero, which does not happen for these seeds)
+        let parameters = ParametersExpand::setup(BigFloat::from_f64(1e-6, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitExpand::<Conf, ConfG, 2, 2, 1, 2, 4, 4, false>::keygen(
+                parameters.clone(),
+                zkp_rng,
+            )
+            .unwrap();
+        let server = ServerExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            verifying_key,
+            rng,
+        )
+        .unwrap();
+        let client_keys = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let prf_eval_points = derive_prf_eval_points::<Conf>([5; 32], 1).unwrap();
+        let proving_key = Arc::new(proving_key);
+
+        let (accepted, ldp_value) = run_single_expand::<Conf, ConfG, _, 2, 2, 1, 2, 4, 4, false>(
+            &parameters,
+            proving_key.clone(),
+            &server,
+            &client_keys,
+            ReadingExpand {
+                input_value: BigUint::from(3_u8),
+                input_value_time: [1],
+                time_bounds: ([0], [1]),
+                prf_eval_points: &prf_eval_points,
+                index: 0,
+            },
+            rng,
+            zkp_rng,
+        )
+        .unwrap();
+        assert!(accepted);
+        assert_eq!(ldp_value, 3);
+
+        // an input value wider than INPUT_BYTES is an error rather than a panic
+        let error = run_single_expand::<Conf, ConfG, _, 2, 2, 1, 2, 4, 4, false>(
+            &parameters,
+            proving_key,
+            &server,
+            &client_keys,
+            ReadingExpand {
+                input_value: BigUint::from(1_u32 << 16),
+                input_value_time: [1],
+                time_bounds: ([0], [1]),
+                prf_eval_points: &prf_eval_points,
+                index: 0,
+            },
+            rng,
+            zkp_rng,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Client(ClientError::InputTooLarge { input_bytes: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn compact_message_verifies_like_full_message() {
+        let rng = &mut ChaChaRng::from_seed([58; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([59; 32]);
+        let parameters = ParametersExpand::setup(BigFloat::from_f64(1.0, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitExpand::<Conf, ConfG, 2, 2, 1, 2, 4, 4, false>::keygen(
+                parameters.clone(),
+                zkp_rng,
+            )
+            .unwrap();
+        let server = ServerExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            verifying_key,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientExpand::<Conf, 2, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        let prf_eval_points = derive_prf_eval_points::<Conf>([6; 32], 1).unwrap();
+
+        // the server keeps the session of the gen-rand exchange
+        let gen_rand_client_message = client.generate_randomness_create(rng).unwrap();
+        let (gen_rand_server_message, session) = server
+            .generate_randomness_create_session(&gen_rand_client_message, rng)
+            .unwrap();
+        client
+            .generate_randomness_verify(&gen_rand_server_message)
+            .unwrap();
+
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let full_message = client
+            .verifiable_randomization_create::<ConfG>(
+                ([0], [1]),
+                [1],
+                BigUint::from(3_u8),
+                input_value_signature,
+                &prf_eval_points,
+                0,
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+        let mut compact_message = vec![];
+        VerifiableRandomizationMessageExpand::<Conf, 2>::deserialize_compressed(
+            full_message.as_slice(),
+        )
+        .unwrap()
+        .compact()
+        .serialize_compressed(&mut compact_message)
+        .unwrap();
+        assert!(compact_message.len() < full_message.len());
+
+        let full_result = server
+            .verifiable_randomization_verify::<ConfG>(
+                &full_message,
+                ([0], [1]),
+                &prf_eval_points,
+                0,
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+        let compact_result = server
+            .compact_randomization_verify::<ConfG>(
+                &compact_message,
+                &session,
+                VerificationStepExpand {
+                    time_bounds: ([0], [1]),
+                    prf_eval_points: &prf_eval_points,
+                    index: 0,
+                },
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+        assert!(full_result.0);
+        assert_eq!(compact_result, full_result);
+
+        // the compact message cannot be verified with the state of another session
+        let (_, other_session) = server
+            .generate_randomness_create_session(&gen_rand_client_message, rng)
+            .unwrap();
+        assert!(server
+            .compact_randomization_verify::<ConfG>(
+                &compact_message,
+                &other_session,
+                VerificationStepExpand {
+                    time_bounds: ([0], [1]),
+                    prf_eval_points: &prf_eval_points,
+                    index: 0,
+                },
+                zkp_rng,
+                false,
+            )
+            .is_err());
+
+        // a truncated compact message is rejected instead of panicking
+        assert!(server
+            .compact_randomization_verify::<ConfG>(
+                &compact_message[..4],
+                &session,
+                VerificationStepExpand {
+                    time_bounds: ([0], [1]),
+                    prf_eval_points: &prf_eval_points,
+                    index: 0,
+                },
+                zkp_rng,
+                false,
+            )
+            .is_err());
+    }
+}
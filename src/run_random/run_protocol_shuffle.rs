@@ -3,6 +3,8 @@
 
 use crate::circuits::CircuitShuffle;
 use crate::client::*;
+use crate::messages::shuffle::PrfEvalPointSet;
+use crate::messages::{required_prf_eval_points, InputValueSignatureInput};
 use crate::prelude::*;
 use crate::server::*;
 use ark_ff::PrimeField;
@@ -35,9 +37,7 @@ where
     ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
     Proof<Conf>: CanonicalDeserialize,
 {
-    assert!(IS_REAL_INPUT || INPUT_BYTES + GAMMA_BYTES <= RANDOMNESS_BYTES);
-    assert!(!IS_REAL_INPUT || 2 * INPUT_BYTES + GAMMA_BYTES <= RANDOMNESS_BYTES);
-    assert!(K >= 2 && (K.ilog2() + 1) as usize <= INPUT_BYTES * 8);
+    validate_scheme_params::<INPUT_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>()?;
     assert!(INPUT_BYTES * 8 <= ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize);
     assert!(TIME_BYTES * 8 <= ConstraintField::<Conf>::MODULUS_BIT_SIZE as usize);
     let mut rng = ChaChaRng::from_entropy();
@@ -57,9 +57,11 @@ where
     >::keygen(parameters.clone(), &mut zkp_rng)?;
     let (client_sig_pk, client_sig_sk) =
         Conf::ClientSignatureScheme::keygen(&parameters.client_signature_scheme, &mut rng)?;
-    let prf_eval_points = (0..((RANDOMNESS_BYTES - 1) / 32) + 1)
-        .map(|_| rng.gen::<PRFSchemeInput<Conf>>())
-        .collect::<Vec<_>>();
+    let prf_eval_points = PrfEvalPointSet::<Conf>::derive(
+        0,
+        rng.gen(),
+        required_prf_eval_points::<Conf>(RANDOMNESS_BYTES),
+    )?;
 
     // create server
     let server = ServerShuffle::<
@@ -103,21 +105,16 @@ where
     end_timer!(timer_gen_rand_server);
 
     let timer_verify_rand_client = start_timer!(|| "Client verification");
-    let result = client.generate_randomness_verify(&gen_rand_server_message)?;
+    client.generate_randomness_verify(&gen_rand_server_message)?;
     end_timer!(timer_verify_rand_client);
 
     end_timer!(timer_gen_rand);
 
-    assert!(
-        result,
-        "Verification of generate randomness protocol failed."
-    );
-
     // 2. verifiable randomization
     // -- START TRUSTED ENVIRONMENT --
 
     let input_value = if IS_REAL_INPUT {
-        let precision = INPUT_BYTES * 8 * 2;
+        let precision = float_conversion_precision("INPUT_BYTES", INPUT_BYTES)?;
         let input_f64 = rng.gen_range(0.0..=1.0);
         let input_bigfloat = BigFloat::from_f64(input_f64, precision);
         if input_bigfloat.is_zero() {
@@ -133,8 +130,9 @@ where
                 .convert_to_radix(
                     Radix::Dec,
                     RoundingMode::None,
-                    &mut Consts::new().expect("Constants cache initialization should not fail."),
-                )?
+                    &mut Consts::new().map_err(GenericError::from)?,
+                )
+                .map_err(GenericError::from)?
                 .1
                 .iter()
                 .map(|digit| digit.to_string())
@@ -162,8 +160,11 @@ where
         input_value_bytes[idx] = *byte;
     }
 
-    let mut message_bytes = input_value_bytes.to_vec();
-    message_bytes.extend_from_slice(&input_value_time);
+    let message_bytes = InputValueSignatureInput {
+        input_value: input_value_bytes,
+        input_value_time,
+    }
+    .to_bytes();
 
     let timer_te = start_timer!(|| "Trusted environment computation");
     let input_value_signature = Conf::ClientSignatureScheme::sign(
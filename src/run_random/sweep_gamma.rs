@@ -0,0 +1,346 @@
+//! Sweep over several values of gamma on a given dataset, to obtain the tradeoff between privacy
+//! and utility of the LDP mechanism (which is identical for all VLDP schemes), and sample its
+//! output distribution for calibration.
+
+use crate::prelude::*;
+use crate::primitives::ldp::apply_ldp;
+use astro_float::BigFloat;
+use num_bigint::BigUint;
+
+/// Randomize every input value of `dataset` for each of the given `gammas`, exactly as a client
+/// does in its `Randomize` step (but without creating a proof), and return the error of the
+/// resulting estimate against the ground truth per gamma.
+///
+/// The input values are encoded as for `verifiable_randomization_create`. For real input, the
+/// error is the absolute error of the estimated mean (as a fraction of the maximal input value).
+/// For histogram input, it is the maximal absolute error of the estimated relative frequencies of
+/// all `K` values.
+pub fn sweep_gamma<
+    Conf: Config,
+    const INPUT_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const K: u64,
+    const IS_REAL_INPUT: bool,
+    R: Rng + CryptoRng,
+>(
+    dataset: &[BigUint],
+    gammas: &[BigFloat],
+    rng: &mut R,
+) -> Result<Vec<(BigFloat, f64)>, Error> {
+    // the combined randomness of client and server is uniformly random
+    let mut randomness = vec![0; GAMMA_BYTES + 2 * INPUT_BYTES];
+    let shared = SharedPrimitives::<Conf>::setup(rng)?;
+    let mut results = vec![];
+    for gamma in gammas {
+        let gamma_bytes = ParametersBase::<Conf, GAMMA_BYTES>::from_shared(gamma.clone(), &shared)?
+            .gamma_as_bytes()?;
+        let ldp_values = dataset
+            .iter()
+            .map(|input_value| {
+                rng.fill_bytes(&mut randomness);
+                apply_ldp::<INPUT_BYTES, GAMMA_BYTES, K, IS_REAL_INPUT>(
+                    input_value,
+                    &randomness,
+                    &gamma_bytes,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let effective_gamma = effective_gamma(&gamma_bytes);
+        let estimate_error = if IS_REAL_INPUT {
+            let max_value = BigUint::from_bytes_le(&[u8::MAX; INPUT_BYTES]);
+            let true_mean = dataset
+                .iter()
+                .map(|input_value| ratio(input_value, &max_value))
+                .sum::<f64>()
+                / dataset.len() as f64;
+            (estimate_mean::<K>(&ldp_values, effective_gamma) - true_mean).abs()
+        } else {
+            let mut true_counts = vec![0; K as usize];
+            for input_value in dataset {
+                let value =
+                    u64::try_from(input_value).map_err(|_| GenericError::ConversionError)?;
+                if !(1..=K).contains(&value) {
+                    Err(GenericError::ConversionError)?
+                }
+                true_counts[value as usize - 1] += 1;
+            }
+            estimate_frequencies::<K>(&ldp_values, effective_gamma)
+                .iter()
+                .zip(true_counts)
+                .map(|(estimate, count)| (estimate - count as f64 / dataset.len() as f64).abs())
+                .fold(0.0, f64::max)
+        };
+        results.push((gamma.clone(), estimate_error));
+    }
+    Ok(results)
+}
+
+/// Apply the LDP mechanism `n_samples` times to the same `input_value` (encoded as for
+/// `verifiable_randomization_create`), each time with fresh uniformly random randomness, and return
+/// the resulting LDP values. This allows to compare the output distribution of the mechanism for a
+/// fixed input against the theoretical randomized response distribution.
+pub fn sample_output_distribution<
+    Conf: Config,
+    const INPUT_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const K: u64,
+    const IS_REAL_INPUT: bool,
+    R: Rng + CryptoRng,
+>(
+    input_value: &BigUint,
+    gamma: &BigFloat,
+    n_samples: usize,
+    rng: &mut R,
+) -> Result<Vec<u64>, Error> {
+    let gamma_bytes = ParametersExpand::<Conf, GAMMA_BYTES>::compute_gamma_as_bytes(gamma)?;
+    let mut randomness = vec![0; GAMMA_BYTES + 2 * INPUT_BYTES];
+    Ok((0..n_samples)
+        .map(|_| {
+            rng.fill_bytes(&mut randomness);
+            apply_ldp::<INPUT_BYTES, GAMMA_BYTES, K, IS_REAL_INPUT>(
+                input_value,
+                &randomness,
+                &gamma_bytes,
+            )
+        })
+        .collect())
+}
+
+/// Estimate the mean of real input values (as a fraction of the maximal input value) from their
+/// LDP values in `[0, K]`, which were each replaced by a uniformly random value with probability
+/// `gamma`.
+pub fn estimate_mean<const K: u64>(ldp_values: &[u64], gamma: f64) -> f64 {
+    let sample_mean = ldp_values.iter().sum::<u64>() as f64 / ldp_values.len() as f64;
+    (sample_mean / K as f64 - gamma / 2.0) / (1.0 - gamma)
+}
+
+/// Estimate the relative frequencies of the histogram input values `1..=K` from their LDP values,
+/// which were each replaced by a uniformly random value with probability `gamma`.
+pub fn estimate_frequencies<const K: u64>(ldp_values: &[u64], gamma: f64) -> Vec<f64> {
+    let mut counts = vec![0; K as usize];
+    for ldp_value in ldp_values {
+        counts[*ldp_value as usize - 1] += 1;
+    }
+    counts
+        .into_iter()
+        .map(|count| (count as f64 / ldp_values.len() as f64 - gamma / K as f64) / (1.0 - gamma))
+        .collect()
+}
+
+/// Estimate the mean like `estimate_mean`, but for clients that each chose their own gamma (see
+/// `ParametersBase::with_public_gamma`), given as `(ldp_value, gamma)` pairs. Every LDP value is
+/// debiased with the gamma of its client. Clients with `gamma >= 1` are skipped, since their LDP
+/// values carry no information about their input values.
+pub fn estimate_mean_per_client<const K: u64>(ldp_values: &[(u64, f64)]) -> f64 {
+    let informative = informative_clients(ldp_values);
+    informative
+        .iter()
+        .map(|&(ldp_value, gamma)| (ldp_value as f64 / K as f64 - gamma / 2.0) / (1.0 - gamma))
+        .sum::<f64>()
+        / informative.len() as f64
+}
+
+/// Estimate the relative frequencies like `estimate_frequencies`, but for clients that each chose
+/// their own gamma (see `ParametersBase::with_public_gamma`), given as `(ldp_value, gamma)` pairs.
+/// Every LDP value is debiased with the gamma of its client. Clients with `gamma >= 1` are skipped
+/// like in `estimate_mean_per_client`.
+pub fn estimate_frequencies_per_client<const K: u64>(ldp_values: &[(u64, f64)]) -> Vec<f64> {
+    let informative = informative_clients(ldp_values);
+    let mut frequencies = vec![0.0; K as usize];
+    for &(ldp_value, gamma) in &informative {
+        for (value, frequency) in (1..=K).zip(frequencies.iter_mut()) {
+            let indicator = if value == ldp_value { 1.0 } else { 0.0 };
+            *frequency += (indicator - gamma / K as f64) / (1.0 - gamma);
+        }
+    }
+    frequencies
+        .into_iter()
+        .map(|frequency| frequency / informative.len() as f64)
+        .collect()
+}
+
+/// The `(ldp_value, gamma)` pairs of the clients with `gamma < 1`, whose LDP values can be debiased.
+fn informative_clients(ldp_values: &[(u64, f64)]) -> Vec<(u64, f64)> {
+    ldp_values
+        .iter()
+        .copied()
+        .filter(|&(_, gamma)| gamma < 1.0)
+        .collect()
+}
+
+/// Probability with which a client replaces its input value, given the byte encoding of gamma
+/// (the LDP bit is set if the random bytes are at most `gamma_bytes`).
+pub fn effective_gamma(gamma_bytes: &[u8]) -> f64 {
+    let max_value = BigUint::from_bytes_le(&vec![u8::MAX; gamma_bytes.len()]);
+    ratio(
+        &(BigUint::from_bytes_le(gamma_bytes) + 1_u8),
+        &(max_value + 1_u8),
+    )
+}
+
+/// `numerator / denominator` as a floating point value.
+fn ratio(numerator: &BigUint, denominator: &BigUint) -> f64 {
+    // keep 52 bits of precision, such that both fit into an `f64`
+    let shift = denominator.bits().saturating_sub(52);
+    let numerator = u64::try_from(numerator >> shift).unwrap_or(u64::MAX);
+    let denominator = u64::try_from(denominator >> shift).unwrap_or(u64::MAX);
+    numerator as f64 / denominator as f64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::BasicConfig;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use rand::{RngCore, SeedableRng};
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+
+    #[test]
+    fn estimate_error_decreases_with_gamma() {
+        let rng = &mut ChaChaRng::from_seed([31; 32]);
+        // skewed histogram, such that the noise visibly flattens the frequencies
+        let dataset = (0..2000)
+            .map(|_| {
+                BigUint::from(if rng.gen_bool(0.7) {
+                    1_u8
+                } else {
+                    rng.gen_range(2..=4)
+                })
+            })
+            .collect::<Vec<_>>();
+        let gammas = [0.9, 0.6, 0.3, 0.1]
+            .map(|gamma| BigFloat::from_f64(gamma, 64))
+            .to_vec();
+
+        let results = sweep_gamma::<Conf, 1, 8, 4, false, _>(&dataset, &gammas, rng).unwrap();
+        assert_eq!(results.len(), gammas.len());
+        assert!(results
+            .iter()
+            .zip(&gammas)
+            .all(|((gamma, _), expected)| gamma == expected));
+        // allow for sampling noise of roughly one standard deviation of the least noisy estimate
+        for window in results.windows(2) {
+            assert!(
+                window[1].1 <= window[0].1 + 0.03,
+                "{} -> {}",
+                window[0].1,
+                window[1].1
+            );
+        }
+        assert!(results.last().unwrap().1 < results[0].1);
+    }
+
+    #[test]
+    fn per_client_gamma_is_debiased() {
+        let rng = &mut ChaChaRng::from_seed([34; 32]);
+        let shared = SharedPrimitives::<Conf>::setup(rng).unwrap();
+        // the clients with little noise all hold 1, those with much noise all hold 4
+        let groups = [(0.2, 1_u8), (0.8, 4_u8)].map(|(gamma, input_value)| {
+            let gamma_bytes =
+                ParametersBase::<Conf, 8>::from_shared(BigFloat::from_f64(gamma, 64), &shared)
+                    .unwrap()
+                    .gamma_as_bytes()
+                    .unwrap();
+            (gamma_bytes, BigUint::from(input_value))
+        });
+        let mut randomness = [0; 8 + 2];
+        let ldp_values = groups
+            .iter()
+            .flat_map(|group| std::iter::repeat_n(group, 2000))
+            .map(|(gamma_bytes, input_value)| {
+                rng.fill_bytes(&mut randomness);
+                (
+                    apply_ldp::<1, 8, 4, false>(input_value, &randomness, gamma_bytes),
+                    effective_gamma(gamma_bytes),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let max_error = |estimate: Vec<f64>| {
+            estimate
+                .iter()
+                .zip([0.5, 0.0, 0.0, 0.5])
+                .map(|(estimate, truth)| (estimate - truth).abs())
+                .fold(0.0, f64::max)
+        };
+        assert!(max_error(estimate_frequencies_per_client::<4>(&ldp_values)) < 0.06);
+        // debiasing with the average gamma overestimates the value of the less noisy clients
+        let average_gamma =
+            ldp_values.iter().map(|(_, gamma)| gamma).sum::<f64>() / ldp_values.len() as f64;
+        let ldp_values = ldp_values
+            .iter()
+            .map(|(ldp_value, _)| *ldp_value)
+            .collect::<Vec<_>>();
+        assert!(max_error(estimate_frequencies::<4>(&ldp_values, average_gamma)) > 0.1);
+    }
+
+    #[test]
+    fn per_client_estimates_skip_uninformative_clients() {
+        // a single client that replaces its input value by a random value with certainty
+        let ldp_values = [(1, 0.0), (3, 0.0), (4, effective_gamma(&[u8::MAX; 8]))];
+        assert_eq!(effective_gamma(&[u8::MAX; 8]), 1.0);
+        let mean = estimate_mean_per_client::<4>(&ldp_values);
+        assert!((mean - 0.5).abs() < 1e-9, "{}", mean);
+        let frequencies = estimate_frequencies_per_client::<4>(&ldp_values);
+        assert!(frequencies.iter().all(|frequency| frequency.is_finite()));
+        assert_eq!(frequencies, vec![0.5, 0.0, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn output_distribution_follows_gamma() {
+        let rng = &mut ChaChaRng::from_seed([54; 32]);
+        let input_value = BigUint::from(2_u8);
+        let bucket_frequencies = |ldp_values: Vec<u64>| {
+            let mut counts = [0; 4];
+            for ldp_value in &ldp_values {
+                counts[*ldp_value as usize - 1] += 1;
+            }
+            counts.map(|count| count as f64 / ldp_values.len() as f64)
+        };
+
+        // almost no noise: every output is the input value
+        let ldp_values = sample_output_distribution::<Conf, 1, 8, 4, false, _>(
+            &input_value,
+            &BigFloat::from_f64(1e-9, 64),
+            2000,
+            rng,
+        )
+        .unwrap();
+        assert_eq!(ldp_values.len(), 2000);
+        assert!(ldp_values.iter().all(|ldp_value| *ldp_value == 2));
+
+        // almost only noise: the outputs spread uniformly over all buckets
+        let frequencies = bucket_frequencies(
+            sample_output_distribution::<Conf, 1, 8, 4, false, _>(
+                &input_value,
+                &BigFloat::from_f64(0.99, 64),
+                4000,
+                rng,
+            )
+            .unwrap(),
+        );
+        for frequency in frequencies {
+            assert!((frequency - 0.25).abs() < 0.04, "{:?}", frequencies);
+        }
+
+        // in between: randomized response keeps the input value with probability 1 - 3/4 gamma
+        let frequencies = bucket_frequencies(
+            sample_output_distribution::<Conf, 1, 8, 4, false, _>(
+                &input_value,
+                &BigFloat::from_f64(0.4, 64),
+                4000,
+                rng,
+            )
+            .unwrap(),
+        );
+        for (value, frequency) in (1..=4).zip(frequencies) {
+            let expected = if value == 2 { 1.0 - 0.75 * 0.4 } else { 0.1 };
+            assert!((frequency - expected).abs() < 0.04, "{:?}", frequencies);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! All types of the Base scheme
+
+pub use super::Scheme;
+pub use crate::circuits::CircuitBase;
+pub use crate::client::{ClientBase, ClientBaseStorage};
+pub use crate::messages::base::*;
+pub use crate::messages::InputValueSignatureInput;
+pub use crate::prelude::ParametersBase;
+pub use crate::server::ServerBase;
+
+use crate::prelude::*;
+use std::marker::PhantomData;
+
+/// Bundle of the matched Base scheme types for the given configuration, byte widths, and number
+/// `D` of input values per submission
+pub struct BaseScheme<
+    Conf: Config,
+    ConfG: ConfigGadget<Conf>,
+    const INPUT_BYTES: usize,
+    const TIME_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+    const K: u64,
+    const IS_REAL_INPUT: bool,
+    const D: usize = 1,
+>(PhantomData<(Conf, ConfG)>);
+
+impl<
+        Conf: Config,
+        ConfG: ConfigGadget<Conf>,
+        const INPUT_BYTES: usize,
+        const TIME_BYTES: usize,
+        const GAMMA_BYTES: usize,
+        const RANDOMNESS_BYTES: usize,
+        const K: u64,
+        const IS_REAL_INPUT: bool,
+        const D: usize,
+    > Scheme
+    for BaseScheme<
+        Conf,
+        ConfG,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+        D,
+    >
+{
+    type Parameters = ParametersBase<Conf, GAMMA_BYTES>;
+    type Circuit = CircuitBase<
+        Conf,
+        ConfG,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+        D,
+    >;
+    type Client = ClientBase<
+        Conf,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+        D,
+    >;
+    type Server = ServerBase<
+        Conf,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+        D,
+    >;
+}
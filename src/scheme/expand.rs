@@ -0,0 +1,83 @@
+//! All types of the Expand scheme
+
+pub use super::Scheme;
+pub use crate::circuits::CircuitExpand;
+pub use crate::client::{ClientExpand, ClientExpandStorage};
+pub use crate::messages::expand::*;
+pub use crate::messages::{derive_prf_eval_points, InputValueSignatureInput};
+pub use crate::prelude::ParametersExpand;
+pub use crate::server::ServerExpand;
+
+use crate::prelude::*;
+use std::marker::PhantomData;
+
+/// Bundle of the matched Expand scheme types for the given configuration, Merkle tree depth, and
+/// byte widths
+pub struct ExpandScheme<
+    Conf: Config,
+    ConfG: ConfigGadget<Conf>,
+    const MT_DEPTH: usize,
+    const INPUT_BYTES: usize,
+    const TIME_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+    const K: u64,
+    const IS_REAL_INPUT: bool,
+>(PhantomData<(Conf, ConfG)>);
+
+impl<
+        Conf: Config,
+        ConfG: ConfigGadget<Conf>,
+        const MT_DEPTH: usize,
+        const INPUT_BYTES: usize,
+        const TIME_BYTES: usize,
+        const GAMMA_BYTES: usize,
+        const RANDOMNESS_BYTES: usize,
+        const K: u64,
+        const IS_REAL_INPUT: bool,
+    > Scheme
+    for ExpandScheme<
+        Conf,
+        ConfG,
+        MT_DEPTH,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >
+{
+    type Parameters = ParametersExpand<Conf, GAMMA_BYTES>;
+    type Circuit = CircuitExpand<
+        Conf,
+        ConfG,
+        MT_DEPTH,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >;
+    type Client = ClientExpand<
+        Conf,
+        MT_DEPTH,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >;
+    type Server = ServerExpand<
+        Conf,
+        MT_DEPTH,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >;
+}
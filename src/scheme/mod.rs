@@ -0,0 +1,16 @@
+//! Matched sets of the client, server, circuit, message, and parameter types of each VLDP scheme
+//! (Base, Expand, and Shuffle), such that e.g. `use vldp::scheme::shuffle::*;` brings everything
+//! needed to run one scheme into scope.
+
+pub mod base;
+pub mod expand;
+pub mod shuffle;
+
+/// The matched types of one VLDP scheme for a fixed configuration and fixed byte widths (see
+/// `BaseScheme`, `ExpandScheme`, and `ShuffleScheme`).
+pub trait Scheme {
+    type Parameters;
+    type Circuit;
+    type Client;
+    type Server;
+}
@@ -0,0 +1,165 @@
+//! All types of the Shuffle scheme
+
+pub use super::Scheme;
+pub use crate::circuits::CircuitShuffle;
+pub use crate::client::{ClientShuffle, ClientShuffleStorage};
+pub use crate::messages::shuffle::*;
+pub use crate::messages::{derive_prf_eval_points, InputValueSignatureInput};
+pub use crate::prelude::ParametersShuffle;
+pub use crate::server::ServerShuffle;
+
+use crate::prelude::*;
+use std::marker::PhantomData;
+
+/// Bundle of the matched Shuffle scheme types for the given configuration and byte widths
+pub struct ShuffleScheme<
+    Conf: Config,
+    ConfG: ConfigGadget<Conf>,
+    const INPUT_BYTES: usize,
+    const TIME_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+    const K: u64,
+    const IS_REAL_INPUT: bool,
+>(PhantomData<(Conf, ConfG)>);
+
+impl<
+        Conf: Config,
+        ConfG: ConfigGadget<Conf>,
+        const INPUT_BYTES: usize,
+        const TIME_BYTES: usize,
+        const GAMMA_BYTES: usize,
+        const RANDOMNESS_BYTES: usize,
+        const K: u64,
+        const IS_REAL_INPUT: bool,
+    > Scheme
+    for ShuffleScheme<
+        Conf,
+        ConfG,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >
+{
+    type Parameters = ParametersShuffle<Conf, GAMMA_BYTES>;
+    type Circuit = CircuitShuffle<
+        Conf,
+        ConfG,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >;
+    type Client = ClientShuffle<
+        Conf,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >;
+    type Server = ServerShuffle<
+        Conf,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >;
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::{BasicConfig, BasicConfigGadget, Config};
+    use crate::prelude::SignatureScheme;
+    use crate::scheme::shuffle::*;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_serialize::CanonicalDeserialize;
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+    type Shuffle = ShuffleScheme<Conf, ConfG, 2, 1, 2, 4, 4, false>;
+    type Parameters = <Shuffle as Scheme>::Parameters;
+    type Circuit = <Shuffle as Scheme>::Circuit;
+    type Client = <Shuffle as Scheme>::Client;
+    type Server = <Shuffle as Scheme>::Server;
+
+    #[test]
+    fn reexports_suffice_for_a_shuffle_round() {
+        let rng = &mut ChaChaRng::from_seed([45; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([46; 32]);
+        let parameters = Parameters::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) = Circuit::keygen(parameters.clone(), zkp_rng).unwrap();
+        let server = Server::new(parameters.clone(), verifying_key, rng).unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = Client::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let prf_eval_points = PrfEvalPointSet::<Conf>::derive(0, [3; 32], 1).unwrap();
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                ([0], [1]),
+                [1],
+                BigUint::from(3_u8),
+                input_value_signature,
+                &prf_eval_points,
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+        let message = VerifiableRandomizationMessageShuffle::<Conf, 2>::deserialize_compressed(
+            submission.as_slice(),
+        )
+        .unwrap();
+        let (accepted, ldp_value) = server
+            .verifiable_randomization_verify_message::<ConfG>(
+                &message,
+                ([0], [1]),
+                &prf_eval_points,
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+        assert!(accepted);
+        assert_eq!(ldp_value, message.ldp_value);
+    }
+}
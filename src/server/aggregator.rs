@@ -0,0 +1,307 @@
+//! Aggregation of the LDP values of accepted submissions
+
+use crate::prelude::*;
+use crate::run_random::{effective_gamma, estimate_frequencies, estimate_mean};
+use ark_serialize::CanonicalSerialize;
+use astro_float::BigFloat;
+use std::collections::BTreeMap;
+
+/// Precision (in bits) of the per-window estimates, which are computed as `f64`
+const ESTIMATE_PRECISION: usize = 64;
+
+/// How an index-keyed [`Aggregator`] handles a submission for an index it has already aggregated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Return a `ServerError::DuplicateIndex`.
+    #[default]
+    Reject,
+    /// Count the duplicate separately and ignore its value.
+    Count,
+}
+
+/// Aggregator of the LDP values of accepted submissions.
+///
+/// By default, every added value is aggregated. Keyed by index (see `with_index_keying`), values
+/// of the Expand scheme are stored by their client and `index`, such that submissions that arrive
+/// out of order are aggregated correctly and a replayed index of a client is detected. As every
+/// Expand client uses the same indices, a client is identified by a key such as the root of its
+/// Merkle tree (see `add_indexed`).
+///
+/// Values added with their time (see `add_timed`) are kept in a separate accumulator per time
+/// window, such that an estimate can be computed for every window.
+#[derive(Clone, Debug, Default)]
+pub struct Aggregator {
+    values: Vec<u64>,
+    /// Values keyed by the serialized client key and the index
+    values_by_index: Option<BTreeMap<(Vec<u8>, usize), u64>>,
+    duplicate_policy: DuplicatePolicy,
+    duplicate_indices: Vec<(Vec<u8>, usize)>,
+    /// Values per time window, keyed by the big-endian time bytes such that the windows are
+    /// ordered chronologically
+    values_by_window: BTreeMap<Vec<u8>, Vec<u64>>,
+}
+
+impl Aggregator {
+    /// Construct an empty aggregator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Key the aggregated values by index, handling duplicate indices according to `policy`.
+    pub fn with_index_keying(mut self, policy: DuplicatePolicy) -> Self {
+        self.values_by_index = Some(BTreeMap::new());
+        self.duplicate_policy = policy;
+        self
+    }
+
+    /// Add the LDP value of an accepted submission without an index.
+    pub fn add(&mut self, ldp_value: u64) {
+        self.values.push(ldp_value);
+    }
+
+    /// Add the LDP value of an accepted submission of the given client for the given `index`,
+    /// where `client` identifies the client across its submissions, e.g., the
+    /// `client_merkle_tree_root` of its messages (which also separates its `Generate Randomness`
+    /// steps). Returns whether the value was aggregated, which is only not the case for a counted
+    /// duplicate.
+    ///
+    /// Without index keying, this is identical to `add`.
+    pub fn add_indexed<T: CanonicalSerialize>(
+        &mut self,
+        client: &T,
+        index: usize,
+        ldp_value: u64,
+    ) -> Result<bool, Error> {
+        let Some(values_by_index) = self.values_by_index.as_mut() else {
+            self.add(ldp_value);
+            return Ok(true);
+        };
+        let key = (client_key(client)?, index);
+        if values_by_index.contains_key(&key) {
+            return match self.duplicate_policy {
+                DuplicatePolicy::Count => {
+                    self.duplicate_indices.push(key);
+                    Ok(false)
+                }
+                DuplicatePolicy::Reject => Err(ServerError::DuplicateIndex(index))?,
+            };
+        }
+        values_by_index.insert(key, ldp_value);
+        Ok(true)
+    }
+
+    /// All aggregated values: first those added without an index (in order of addition), then
+    /// those added with an index (ordered by client key and index).
+    pub fn values(&self) -> impl Iterator<Item = u64> + '_ {
+        self.values
+            .iter()
+            .chain(
+                self.values_by_index
+                    .iter()
+                    .flat_map(|values| values.values()),
+            )
+            .copied()
+    }
+
+    /// Aggregated value of the given client (see `add_indexed`) and `index`, if any
+    pub fn value_at<T: CanonicalSerialize>(&self, client: &T, index: usize) -> Option<u64> {
+        let key = (client_key(client).ok()?, index);
+        self.values_by_index.as_ref()?.get(&key).copied()
+    }
+
+    /// Number of aggregated values
+    pub fn count(&self) -> usize {
+        self.values.len() + self.values_by_index.as_ref().map_or(0, BTreeMap::len)
+    }
+
+    /// Sum of all aggregated values
+    pub fn sum(&self) -> u128 {
+        self.values().map(u128::from).sum()
+    }
+
+    /// Mean of all aggregated values, if there is any
+    pub fn mean(&self) -> Option<f64> {
+        (self.count() > 0).then(|| self.sum() as f64 / self.count() as f64)
+    }
+
+    /// Serialized client key and index of all duplicates that were counted (in order of arrival)
+    pub fn duplicate_indices(&self) -> &[(Vec<u8>, usize)] {
+        &self.duplicate_indices
+    }
+
+    /// Add the LDP value of an accepted submission to the accumulator of its time window, given
+    /// as the little-endian time bytes (e.g., the lower time bound of the `Randomize` step).
+    ///
+    /// Timed values are aggregated separately from the values added with `add` or `add_indexed`.
+    pub fn add_timed(&mut self, time: &[u8], ldp_value: u64) {
+        self.values_by_window
+            .entry(time.iter().rev().copied().collect())
+            .or_default()
+            .push(ldp_value);
+    }
+
+    /// All time windows for which a value has been added (as little-endian time bytes, in
+    /// chronological order)
+    pub fn windows(&self) -> Vec<Vec<u8>> {
+        self.values_by_window
+            .keys()
+            .map(|time| time.iter().rev().copied().collect())
+            .collect()
+    }
+
+    /// Estimate the mean of the real input values (as a fraction of the maximal input value)
+    /// of the given time window from its LDP values in `[0, K]` (see `estimate_mean`), given the
+    /// byte encoding of gamma as used by the clients (see `gamma_as_bytes` of the parameters).
+    /// Returns `None` for a window without values.
+    pub fn estimate_for_window<const K: u64>(
+        &self,
+        time: &[u8],
+        gamma_bytes: &[u8],
+    ) -> Option<BigFloat> {
+        let values = self.window_values(time)?;
+        let estimate = estimate_mean::<K>(values, effective_gamma(gamma_bytes));
+        Some(BigFloat::from_f64(estimate, ESTIMATE_PRECISION))
+    }
+
+    /// Estimate the relative frequencies of the histogram input values `1..=K` of the given time
+    /// window (see `estimate_frequencies`), given the byte encoding of gamma as for
+    /// `estimate_for_window`. Returns `None` for a window without values.
+    pub fn frequencies_for_window<const K: u64>(
+        &self,
+        time: &[u8],
+        gamma_bytes: &[u8],
+    ) -> Option<Vec<f64>> {
+        let values = self.window_values(time)?;
+        Some(estimate_frequencies::<K>(
+            values,
+            effective_gamma(gamma_bytes),
+        ))
+    }
+
+    fn window_values(&self, time: &[u8]) -> Option<&[u64]> {
+        let key = time.iter().rev().copied().collect::<Vec<_>>();
+        self.values_by_window.get(&key).map(Vec::as_slice)
+    }
+}
+
+/// Key of a client in an index-keyed aggregator: the compressed serialization of `client`
+fn client_key<T: CanonicalSerialize>(client: &T) -> Result<Vec<u8>, Error> {
+    let mut key = Vec::new();
+    client.serialize_compressed(&mut key)?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn out_of_order_and_duplicate_indices() {
+        // index 2 is submitted twice, the replay carrying a different value
+        let submissions = [(3, 7), (0, 1), (2, 5), (1, 2), (2, 9)];
+
+        let client = [1_u8; 32];
+
+        let mut aggregator = Aggregator::new().with_index_keying(DuplicatePolicy::Count);
+        let aggregated = submissions
+            .iter()
+            .map(|&(index, ldp_value)| aggregator.add_indexed(&client, index, ldp_value).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(aggregated, vec![true, true, true, true, false]);
+        assert_eq!(aggregator.values().collect::<Vec<_>>(), vec![1, 2, 5, 7]);
+        assert_eq!(aggregator.value_at(&client, 2), Some(5));
+        assert_eq!(aggregator.count(), 4);
+        assert_eq!(aggregator.sum(), 15);
+        assert_eq!(aggregator.mean(), Some(3.75));
+        assert_eq!(aggregator.duplicate_indices(), &[(client.to_vec(), 2)]);
+
+        let mut aggregator = Aggregator::new().with_index_keying(DuplicatePolicy::Reject);
+        for &(index, ldp_value) in &submissions[..4] {
+            assert!(aggregator.add_indexed(&client, index, ldp_value).unwrap());
+        }
+        let error = aggregator.add_indexed(&client, 2, 9).unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Server(ServerError::DuplicateIndex(2))
+        ));
+        assert_eq!(aggregator.sum(), 15);
+        assert!(aggregator.duplicate_indices().is_empty());
+
+        // without index keying, every value is aggregated
+        let mut aggregator = Aggregator::new();
+        for &(index, ldp_value) in &submissions {
+            assert!(aggregator.add_indexed(&client, index, ldp_value).unwrap());
+        }
+        assert_eq!(aggregator.count(), 5);
+        assert_eq!(aggregator.sum(), 24);
+    }
+
+    #[test]
+    fn clients_with_the_same_indices_are_aggregated() {
+        // both clients submit indices 0 and 1, in interleaved order
+        let clients = [[1_u8; 32], [2_u8; 32]];
+        let submissions = [(0, 0, 3), (1, 1, 4), (1, 0, 2), (0, 1, 1)];
+
+        let mut aggregator = Aggregator::new().with_index_keying(DuplicatePolicy::Reject);
+        for (client, index, ldp_value) in submissions {
+            assert!(aggregator
+                .add_indexed(&clients[client], index, ldp_value)
+                .unwrap());
+        }
+        assert_eq!(aggregator.count(), 4);
+        assert_eq!(aggregator.sum(), 10);
+        assert_eq!(aggregator.value_at(&clients[0], 1), Some(1));
+        assert_eq!(aggregator.value_at(&clients[1], 1), Some(4));
+
+        // only a replay of the same client is a duplicate
+        assert!(matches!(
+            aggregator.add_indexed(&clients[1], 0, 3).unwrap_err(),
+            VldpError::Server(ServerError::DuplicateIndex(0))
+        ));
+        assert!(aggregator.value_at(&[3_u8; 32], 0).is_none());
+    }
+
+    #[test]
+    fn independent_estimates_per_window() {
+        // encodes an effective gamma of exactly 1/2
+        let gamma_bytes = [u8::MAX, 0x7f];
+        let mut aggregator = Aggregator::new();
+        // windows 1 and 256 (little-endian), interleaved
+        for (time, ldp_value) in [
+            ([1, 0], 4),
+            ([0, 1], 0),
+            ([1, 0], 2),
+            ([0, 1], 1),
+            ([1, 0], 3),
+        ] {
+            aggregator.add_timed(&time, ldp_value);
+        }
+        assert_eq!(aggregator.windows(), vec![vec![1, 0], vec![0, 1]]);
+        assert_eq!(aggregator.count(), 0);
+
+        // sample means 3/4 and 1/8 with K = 4 are debiased to (x - 1/4) / (1/2)
+        assert_eq!(
+            aggregator.estimate_for_window::<4>(&[1, 0], &gamma_bytes),
+            Some(BigFloat::from_f64(1.0, 64))
+        );
+        assert_eq!(
+            aggregator.estimate_for_window::<4>(&[0, 1], &gamma_bytes),
+            Some(BigFloat::from_f64(-0.25, 64))
+        );
+        assert!(aggregator
+            .estimate_for_window::<4>(&[2, 0], &gamma_bytes)
+            .is_none());
+
+        // histogram input: relative frequencies 1/2, 1/4, 0, 1/4 are debiased to (x - 1/8) / (1/2)
+        for ldp_value in [1, 2, 1, 4] {
+            aggregator.add_timed(&[2, 0], ldp_value);
+        }
+        assert_eq!(
+            aggregator.frequencies_for_window::<4>(&[2, 0], &gamma_bytes),
+            Some(vec![0.75, 0.25, -0.25, 0.25])
+        );
+        assert!(aggregator
+            .frequencies_for_window::<4>(&[3, 0], &gamma_bytes)
+            .is_none());
+    }
+}
@@ -1,11 +1,39 @@
 //! All functionalities for a server in the Base scheme
 
 use crate::circuits::CircuitBase;
+use crate::config::KeyFingerprint;
 use crate::messages::base::*;
 use crate::prelude::*;
+use crate::primitives::ldp::decoded_range;
+use crate::primitives::prf::expand_prf_seed;
+#[cfg(feature = "metrics")]
+use crate::server::ServerMetrics;
+use crate::server::{
+    check_gamma_bytes, check_ldp_value_range, check_public_gamma, check_seed_expiry,
+    deserialize_client_message, sample_server_seed, seed_expiry,
+};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use num_bigint::BigInt;
+use std::ops::RangeInclusive;
+use std::time::Duration;
 
-/// Base scheme server
+/// Outcome of the `Verify` step of the Base server together with the server randomness that the
+/// proof was verified against, for the audit log of the server.
+///
+/// Note: the trace must not be shared with clients, as the server randomness is half of the
+/// randomness that decides whether a submission carries the true input value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifyTraceBase<const RANDOMNESS_BYTES: usize, const D: usize = 1> {
+    /// Whether the submission is accepted
+    pub valid: bool,
+    /// The `D` LDP values of the submission (`u64::MAX` if the server signature is invalid)
+    pub ldp_value: [u64; D],
+    /// Server randomness reconstructed from the server seed (all zeros if the server signature is
+    /// invalid, as the seed is then not used)
+    pub server_randomness: [u8; RANDOMNESS_BYTES],
+}
+
+/// Base scheme server, for submissions of `D` input values (a single one by default)
 pub struct ServerBase<
     Conf: Config,
     const INPUT_BYTES: usize,
@@ -14,11 +42,15 @@ pub struct ServerBase<
     const RANDOMNESS_BYTES: usize,
     const K: u64,
     const IS_REAL_INPUT: bool,
+    const D: usize = 1,
 > {
     parameters: ParametersBase<Conf, GAMMA_BYTES>,
     sig_pk: ServerSignatureSchemePublicKey<Conf>,
     sig_sk: ServerSignatureSchemeSecretKey<Conf>,
     verifying_key: VerifyingKey<Conf>,
+    seed_validity: Option<Duration>,
+    #[cfg(feature = "metrics")]
+    metrics: ServerMetrics,
 }
 
 impl<
@@ -29,7 +61,9 @@ impl<
         const RANDOMNESS_BYTES: usize,
         const K: u64,
         const IS_REAL_INPUT: bool,
-    > ServerBase<Conf, INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>
+        const D: usize,
+    >
+    ServerBase<Conf, INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT, D>
 {
     /// Create a new server with the given system parameters and proof verification key.
     pub fn new<R: Rng + CryptoRng>(
@@ -37,6 +71,14 @@ impl<
         verifying_key: VerifyingKey<Conf>,
         rng: &mut R,
     ) -> Result<Self, Error> {
+        validate_vector_scheme_params::<
+            INPUT_BYTES,
+            GAMMA_BYTES,
+            RANDOMNESS_BYTES,
+            K,
+            IS_REAL_INPUT,
+            D,
+        >()?;
         let (sig_pk, sig_sk) =
             Conf::ServerSignatureScheme::keygen(&parameters.server_signature_scheme, rng)?;
         Ok(Self {
@@ -44,15 +86,61 @@ impl<
             sig_pk,
             sig_sk,
             verifying_key,
+            seed_validity: None,
+            #[cfg(feature = "metrics")]
+            metrics: ServerMetrics::new(K)?,
         })
     }
 
+    /// Get the registry with the Prometheus metrics of this server, which is to be scraped by the
+    /// operator.
+    #[cfg(feature = "metrics")]
+    pub fn metrics_registry(&self) -> &prometheus::Registry {
+        self.metrics.registry()
+    }
+
+    /// Let the server signatures issued in the `Generate Randomness` step expire after
+    /// `seed_validity`, such that later client messages that echo them are rejected.
+    #[cfg(feature = "std")]
+    pub fn with_seed_validity(mut self, seed_validity: Duration) -> Self {
+        self.seed_validity = Some(seed_validity);
+        self
+    }
+
+    /// Key fingerprint of the verifying key, which a client can check with
+    /// `ClientBase::with_verifying_key_fingerprint`.
+    pub fn verifying_key_fingerprint(&self) -> Result<KeyFingerprint, Error> {
+        Conf::ZKPScheme::verifying_key_fingerprint(&self.verifying_key)
+    }
+
+    /// Check that the verifying key belongs to the same key pair as the proving key of the clients
+    /// with the given key fingerprint (see `ClientBase::proving_key_fingerprint`). Fails with
+    /// `GenericError::KeyFingerprintMismatch` otherwise, as every proof of these clients would be
+    /// rejected.
+    pub fn with_proving_key_fingerprint(
+        self,
+        proving_key_fingerprint: KeyFingerprint,
+    ) -> Result<Self, Error> {
+        let verifying_key_fingerprint = self.verifying_key_fingerprint()?;
+        if verifying_key_fingerprint != proving_key_fingerprint {
+            Err(GenericError::KeyFingerprintMismatch {
+                expected: proving_key_fingerprint,
+                actual: verifying_key_fingerprint,
+            })?
+        }
+        Ok(self)
+    }
+
     /// Get server's signature public key
     pub fn get_signature_public_key(&self) -> ServerSignatureSchemePublicKey<Conf> {
         self.sig_pk.clone()
     }
 
     /// Given a client message perform the `Generate Randomness` step for the server.
+    ///
+    /// The signature covers a nonce that is sampled freshly for every call, so a recorded server
+    /// message cannot be passed off as the answer to another exchange. Rejecting a submission that
+    /// is replayed as a whole still requires the caller to track the nonces it has accepted.
     pub fn generate_randomness_create<R: Rng + CryptoRng>(
         &self,
         client_message: &[u8],
@@ -63,19 +151,23 @@ impl<
         ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
         ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
     {
-        // deserialize client message
-        let client_message =
-            GenerateRandomnessMessageClientBase::<Conf, TIME_BYTES>::deserialize_compressed(
-                client_message,
-            )?;
+        // deserialize client message, rejecting trailing bytes
+        let client_message = deserialize_client_message::<
+            GenerateRandomnessMessageClientBase<Conf, TIME_BYTES>,
+        >(client_message)?;
 
         // compute server seed
-        let mut server_seed = PRFSchemeSeed::<Conf>::default();
-        rng.fill_bytes(&mut server_seed);
+        let server_seed = sample_server_seed::<Conf, _>(rng)?;
 
-        // create signature input
-        let signature_input =
-            GenerateRandomnessSignatureInputBase::new(client_message, server_seed.clone());
+        // create signature input, bound to this exchange by a fresh nonce
+        let expiry = seed_expiry(self.seed_validity)?;
+        let server_nonce: [u8; 16] = rng.gen();
+        let signature_input = GenerateRandomnessSignatureInputBase::new(
+            client_message,
+            server_seed.clone(),
+            expiry,
+            server_nonce,
+        );
         let mut signature_input_bytes = Vec::new();
         signature_input.serialize_uncompressed(&mut signature_input_bytes)?;
 
@@ -92,19 +184,82 @@ impl<
         GenerateRandomnessMessageServerBase::<Conf> {
             server_seed,
             server_signature,
+            expiry,
+            server_nonce,
         }
         .serialize_compressed(&mut serialized_message)?;
         Ok(serialized_message)
     }
 
     /// Given a client message perform the `Verify` step for the server.
+    ///
+    /// If gamma is public (see `ParametersBase::with_public_gamma`), the proof is verified for the
+    /// gamma carried by the client message, which the estimation then has to account for.
+    ///
+    /// Returns whether the submission is accepted and its `D` LDP values.
     pub fn verifiable_randomization_verify<ConfG: ConfigGadget<Conf>>(
         &self,
         client_message: &[u8],
         time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
         zkp_rng: &mut ZKPRng<Conf>,
         skip_proof: bool,
-    ) -> Result<(bool, u64), Error>
+    ) -> Result<(bool, [u64; D]), Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        ClientCommitmentSchemeOutput<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result = deserialize_client_message::<
+            VerifiableRandomizationMessageBase<Conf, INPUT_BYTES, D>,
+        >(client_message)
+        .and_then(|client_message| {
+            self.verify_submission::<ConfG>(&client_message, time_bounds, zkp_rng, skip_proof)
+        });
+        #[cfg(feature = "metrics")]
+        self.metrics.observe_all(&result, start.elapsed());
+        result
+    }
+
+    /// Perform the `Verify` step like `verifiable_randomization_verify`, but for a client message
+    /// that has already been deserialized, such that it does not have to be parsed again.
+    pub fn verifiable_randomization_verify_message<ConfG: ConfigGadget<Conf>>(
+        &self,
+        client_message: &VerifiableRandomizationMessageBase<Conf, INPUT_BYTES, D>,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        zkp_rng: &mut ZKPRng<Conf>,
+        skip_proof: bool,
+    ) -> Result<(bool, [u64; D]), Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        ClientCommitmentSchemeOutput<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result =
+            self.verify_submission::<ConfG>(client_message, time_bounds, zkp_rng, skip_proof);
+        #[cfg(feature = "metrics")]
+        self.metrics.observe_all(&result, start.elapsed());
+        result
+    }
+
+    /// Perform the `Verify` step like `verifiable_randomization_verify`, but also return the
+    /// reconstructed server randomness (see `VerifyTraceBase`), also if `skip_proof` is set.
+    pub fn verifiable_randomization_verify_with_trace<ConfG: ConfigGadget<Conf>>(
+        &self,
+        client_message: &[u8],
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        zkp_rng: &mut ZKPRng<Conf>,
+        skip_proof: bool,
+    ) -> Result<VerifyTraceBase<RANDOMNESS_BYTES, D>, Error>
     where
         ClientSignatureSchemePublicKey<Conf>:
             ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
@@ -113,17 +268,83 @@ impl<
         Proof<Conf>: CanonicalDeserialize,
         ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
     {
-        // deserialize client message
-        let client_message =
-            VerifiableRandomizationMessageBase::<Conf, INPUT_BYTES>::deserialize_compressed(
-                client_message,
-            )?;
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result = deserialize_client_message::<
+            VerifiableRandomizationMessageBase<Conf, INPUT_BYTES, D>,
+        >(client_message)
+        .and_then(|client_message| {
+            self.verify_submission_with_trace::<ConfG>(
+                &client_message,
+                time_bounds,
+                zkp_rng,
+                skip_proof,
+            )
+        });
+        #[cfg(feature = "metrics")]
+        self.metrics.observe_trace(&result, start.elapsed());
+        result
+    }
+
+    /// Range of input values that an accepted LDP value stands for if it was not randomized (see
+    /// `decoded_range`), shifted back to signed values for signed input (see
+    /// `ParametersBase::with_signed_input`).
+    pub fn decoded_range(&self, ldp_value: u64) -> RangeInclusive<BigInt> {
+        decoded_range::<INPUT_BYTES, K, IS_REAL_INPUT>(ldp_value, self.parameters.signed_input)
+    }
+
+    fn verify_submission<ConfG: ConfigGadget<Conf>>(
+        &self,
+        client_message: &VerifiableRandomizationMessageBase<Conf, INPUT_BYTES, D>,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        zkp_rng: &mut ZKPRng<Conf>,
+        skip_proof: bool,
+    ) -> Result<(bool, [u64; D]), Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        ClientCommitmentSchemeOutput<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
+        self.verify_submission_with_trace::<ConfG>(client_message, time_bounds, zkp_rng, skip_proof)
+            .map(|trace| (trace.valid, trace.ldp_value))
+    }
+
+    fn verify_submission_with_trace<ConfG: ConfigGadget<Conf>>(
+        &self,
+        client_message: &VerifiableRandomizationMessageBase<Conf, INPUT_BYTES, D>,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        zkp_rng: &mut ZKPRng<Conf>,
+        skip_proof: bool,
+    ) -> Result<VerifyTraceBase<RANDOMNESS_BYTES, D>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        ClientCommitmentSchemeOutput<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
+        // reject messages of clients that use a different gamma encoding
+        check_gamma_bytes::<GAMMA_BYTES>(client_message.gamma_bytes)?;
+        let gamma = check_public_gamma::<GAMMA_BYTES>(
+            self.parameters.gamma_is_public,
+            client_message.gamma.as_deref(),
+        )?;
+        // reject LDP values that no proof can be valid for
+        for ldp_value in client_message.ldp_value {
+            check_ldp_value_range::<K, IS_REAL_INPUT>(ldp_value)?;
+        }
 
         // reconstruct signature input
         let signature_input = GenerateRandomnessSignatureInputBase::<Conf> {
             client_randomness_commitment: client_message.client_randomness_commitment.clone(),
             client_signature_public_key: client_message.client_sig_pk.clone(),
             server_seed: client_message.server_seed,
+            expiry: client_message.expiry,
+            server_nonce: client_message.server_nonce,
         };
         let mut signature_input_bytes = Vec::new();
         signature_input.serialize_uncompressed(&mut signature_input_bytes)?;
@@ -135,25 +356,15 @@ impl<
             &signature_input_bytes,
             &client_message.server_signature,
         )? {
+            // reject signatures that are no longer valid
+            check_seed_expiry(client_message.expiry)?;
+
             // reconstruct server randomness
             let mut server_randomness = [0; RANDOMNESS_BYTES];
-            for (index, chunk) in server_randomness.chunks_mut(32).enumerate() {
-                let mut eval_point = [0; 32];
-                for (new_byte, old_byte) in
-                    index.to_le_bytes().into_iter().zip(eval_point.iter_mut())
-                {
-                    *old_byte = new_byte;
-                }
-                chunk.copy_from_slice(
-                    &Conf::PRFScheme::evaluate(&client_message.server_seed, &eval_point)?
-                        [0..chunk.len()],
-                );
-            }
+            expand_prf_seed::<Conf>(&client_message.server_seed, &mut server_randomness)?;
             // then verify proof
-            if skip_proof {
-                Ok((true, client_message.ldp_value))
-            } else {
-                CircuitBase::<
+            let valid = skip_proof
+                || CircuitBase::<
                     _,
                     ConfG,
                     INPUT_BYTES,
@@ -162,20 +373,29 @@ impl<
                     RANDOMNESS_BYTES,
                     K,
                     IS_REAL_INPUT,
+                    D,
                 >::verify(
                     &self.verifying_key,
                     &client_message.proof,
+                    gamma.as_ref(),
                     client_message.ldp_value,
                     time_bounds,
                     &client_message.client_sig_pk,
-                    client_message.client_randomness_commitment,
+                    client_message.client_randomness_commitment.clone(),
                     server_randomness,
                     zkp_rng,
-                )
-                .map(|x| (x, client_message.ldp_value))
-            }
+                )?;
+            Ok(VerifyTraceBase {
+                valid,
+                ldp_value: client_message.ldp_value,
+                server_randomness,
+            })
         } else {
-            Ok((false, u64::MAX))
+            Ok(VerifyTraceBase {
+                valid: false,
+                ldp_value: [u64::MAX; D],
+                server_randomness: [0; RANDOMNESS_BYTES],
+            })
         }
     }
 }
@@ -1,9 +1,16 @@
 //! All functionalities for a server in the Expand scheme
 
-use crate::circuits::CircuitExpand;
+use crate::circuits::{CircuitExpand, PublicInputsExpand};
 use crate::messages::expand::*;
 use crate::prelude::*;
+use crate::primitives::parameters::ClientMerkleTreeParameters;
+#[cfg(feature = "metrics")]
+use crate::server::ServerMetrics;
+use crate::server::{
+    check_gamma_bytes, check_prf_eval_points, check_seed_expiry, sample_server_seed, seed_expiry,
+};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use std::time::Duration;
 
 /// Expand scheme server
 pub struct ServerExpand<
@@ -20,6 +27,10 @@ pub struct ServerExpand<
     sig_pk: ServerSignatureSchemePublicKey<Conf>,
     sig_sk: ServerSignatureSchemeSecretKey<Conf>,
     verifying_key: VerifyingKey<Conf>,
+    blinded_verifying_key: Option<VerifyingKey<Conf>>,
+    seed_validity: Option<Duration>,
+    #[cfg(feature = "metrics")]
+    metrics: ServerMetrics,
 }
 
 impl<
@@ -49,6 +60,7 @@ impl<
         verifying_key: VerifyingKey<Conf>,
         rng: &mut R,
     ) -> Result<Self, Error> {
+        validate_scheme_params::<INPUT_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>()?;
         let (sig_pk, sig_sk) =
             Conf::ServerSignatureScheme::keygen(&parameters.server_signature_scheme, rng)?;
         Ok(Self {
@@ -56,9 +68,35 @@ impl<
             sig_pk,
             sig_sk,
             verifying_key,
+            seed_validity: None,
+            blinded_verifying_key: None,
+            #[cfg(feature = "metrics")]
+            metrics: ServerMetrics::new(K)?,
         })
     }
 
+    /// Also accept blinded submissions (see `blinded_randomization_verify`), whose proofs are
+    /// verified with the given key from `CircuitExpand::keygen_blinded`.
+    pub fn with_blinded_verifying_key(mut self, blinded_verifying_key: VerifyingKey<Conf>) -> Self {
+        self.blinded_verifying_key = Some(blinded_verifying_key);
+        self
+    }
+
+    /// Get the registry with the Prometheus metrics of this server, which is to be scraped by the
+    /// operator.
+    #[cfg(feature = "metrics")]
+    pub fn metrics_registry(&self) -> &prometheus::Registry {
+        self.metrics.registry()
+    }
+
+    /// Let the server signatures issued in the `Generate Randomness` step expire after
+    /// `seed_validity`, such that later client messages that echo them are rejected.
+    #[cfg(feature = "std")]
+    pub fn with_seed_validity(mut self, seed_validity: Duration) -> Self {
+        self.seed_validity = Some(seed_validity);
+        self
+    }
+
     /// Get server's signature public key
     pub fn get_signature_public_key(&self) -> ServerSignatureSchemePublicKey<Conf> {
         self.sig_pk.clone()
@@ -70,6 +108,23 @@ impl<
         client_message: &[u8],
         rng: &mut R,
     ) -> Result<Vec<u8>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
+        Ok(self
+            .generate_randomness_create_session(client_message, rng)?
+            .0)
+    }
+
+    /// Perform the `Generate Randomness` step like `generate_randomness_create`, but also return
+    /// the state of this session, which is to be kept by the caller (under its `session_id`) to
+    /// verify compact client messages with `compact_randomization_verify`.
+    pub fn generate_randomness_create_session<R: Rng + CryptoRng>(
+        &self,
+        client_message: &[u8],
+        rng: &mut R,
+    ) -> Result<(Vec<u8>, SessionExpand<Conf>), Error>
     where
         ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
         ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
@@ -79,12 +134,17 @@ impl<
             GenerateRandomnessMessageClientExpand::<Conf>::deserialize_compressed(client_message)?;
 
         // compute server seed
-        let mut server_seed = PRFSchemeSeed::<Conf>::default();
-        rng.fill_bytes(&mut server_seed);
+        let server_seed = sample_server_seed::<Conf, _>(rng)?;
 
-        // create signature input
-        let signature_input =
-            GenerateRandomnessSignatureInputExpand::new(client_message, server_seed.clone());
+        // create signature input, bound to this exchange by a fresh nonce
+        let expiry = seed_expiry(self.seed_validity)?;
+        let server_nonce: [u8; 16] = rng.gen();
+        let signature_input = GenerateRandomnessSignatureInputExpand::new(
+            client_message,
+            server_seed.clone(),
+            expiry,
+            server_nonce,
+        );
         let mut signature_input_bytes = Vec::new();
         signature_input.serialize_uncompressed(&mut signature_input_bytes)?;
 
@@ -100,10 +160,20 @@ impl<
         let mut serialized_message = vec![];
         GenerateRandomnessMessageServerExpand::<Conf> {
             server_seed,
-            server_signature,
+            server_signature: server_signature.clone(),
+            expiry,
+            server_nonce,
         }
         .serialize_compressed(&mut serialized_message)?;
-        Ok(serialized_message)
+        let session = SessionExpand {
+            client_merkle_tree_root: signature_input.client_merkle_tree_root,
+            client_signature_public_key: signature_input.client_signature_public_key,
+            server_seed,
+            server_signature,
+            expiry,
+            server_nonce,
+        };
+        Ok((serialized_message, session))
     }
 
     /// Given a client message, current time (step) bounds, list of `prf_eval_points` (s in the
@@ -128,17 +198,381 @@ impl<
         Proof<Conf>: CanonicalDeserialize,
         ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
     {
-        // deserialize client message
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result =
+            VerifiableRandomizationMessageExpand::<Conf, INPUT_BYTES>::deserialize_compressed(
+                client_message,
+            )
+            .map_err(Error::from)
+            .and_then(|client_message| {
+                self.verify_submission::<ConfG>(
+                    &client_message,
+                    time_bounds,
+                    prf_eval_points,
+                    index,
+                    zkp_rng,
+                    skip_proof,
+                )
+            });
+        #[cfg(feature = "metrics")]
+        self.metrics.observe(&result, start.elapsed());
+        result
+    }
+
+    /// Perform the `Verify` step like `verifiable_randomization_verify`, but for a client message
+    /// that has already been deserialized, such that it does not have to be parsed again.
+    pub fn verifiable_randomization_verify_message<ConfG: ConfigGadget<Conf>>(
+        &self,
+        client_message: &VerifiableRandomizationMessageExpand<Conf, INPUT_BYTES>,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        prf_eval_points: &[PRFSchemeInput<Conf>],
+        index: usize,
+        zkp_rng: &mut ZKPRng<Conf>,
+        skip_proof: bool,
+    ) -> Result<(bool, u64), Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result = self.verify_submission::<ConfG>(
+            client_message,
+            time_bounds,
+            prf_eval_points,
+            index,
+            zkp_rng,
+            skip_proof,
+        );
+        #[cfg(feature = "metrics")]
+        self.metrics.observe(&result, start.elapsed());
+        result
+    }
+
+    /// Perform the `Verify` step like `verifiable_randomization_verify`, but return the merkle tree
+    /// root, index, LDP value, and time bounds the proof was verified against if the submission is
+    /// valid (and `None` otherwise), such that they can be recorded for auditing.
+    pub fn verifiable_randomization_verify_context<ConfG: ConfigGadget<Conf>>(
+        &self,
+        client_message: &[u8],
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        prf_eval_points: &[PRFSchemeInput<Conf>],
+        index: usize,
+        zkp_rng: &mut ZKPRng<Conf>,
+        skip_proof: bool,
+    ) -> Result<Option<VerifiedExpandContext<Conf, TIME_BYTES>>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
         let client_message =
             VerifiableRandomizationMessageExpand::<Conf, INPUT_BYTES>::deserialize_compressed(
                 client_message,
             )?;
+        let (is_valid, ldp_value) = self.verifiable_randomization_verify_message::<ConfG>(
+            &client_message,
+            time_bounds,
+            prf_eval_points,
+            index,
+            zkp_rng,
+            skip_proof,
+        )?;
+        Ok(is_valid.then(|| VerifiedExpandContext {
+            root: client_message.client_merkle_tree_root,
+            index,
+            ldp_value,
+            time_bounds,
+        }))
+    }
+
+    /// Perform the `Verify` step like `verifiable_randomization_verify`, but for a compact client
+    /// message (see `ClientExpand::compact_randomization_create`), whose omitted fields are taken
+    /// from the given `session` of its `Generate Randomness` step. Fails with a `SessionMismatch`
+    /// if the message belongs to another session.
+    pub fn compact_randomization_verify<ConfG: ConfigGadget<Conf>>(
+        &self,
+        client_message: &[u8],
+        session: &SessionExpand<Conf>,
+        step: VerificationStepExpand<Conf, TIME_BYTES>,
+        zkp_rng: &mut ZKPRng<Conf>,
+        skip_proof: bool,
+    ) -> Result<(bool, u64), Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result =
+            CompactRandomizationMessageExpand::<Conf>::deserialize_compressed(client_message)
+                .map_err(Error::from)
+                .and_then(|client_message| session.expand::<INPUT_BYTES>(client_message))
+                .and_then(|client_message| {
+                    self.verify_submission::<ConfG>(
+                        &client_message,
+                        step.time_bounds,
+                        step.prf_eval_points,
+                        step.index,
+                        zkp_rng,
+                        skip_proof,
+                    )
+                });
+        #[cfg(feature = "metrics")]
+        self.metrics.observe(&result, start.elapsed());
+        result
+    }
+
+    fn verify_submission<ConfG: ConfigGadget<Conf>>(
+        &self,
+        client_message: &VerifiableRandomizationMessageExpand<Conf, INPUT_BYTES>,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        prf_eval_points: &[PRFSchemeInput<Conf>],
+        index: usize,
+        zkp_rng: &mut ZKPRng<Conf>,
+        skip_proof: bool,
+    ) -> Result<(bool, u64), Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
+        let server_randomness =
+            match self.open_submission(client_message.into(), prf_eval_points)? {
+                Some(server_randomness) => server_randomness,
+                None => return Ok((false, u64::MAX)),
+            };
+
+        // then verify proof
+        if skip_proof {
+            Ok((true, client_message.ldp_value))
+        } else {
+            CircuitExpand::<
+                _,
+                ConfG,
+                MT_DEPTH,
+                INPUT_BYTES,
+                TIME_BYTES,
+                GAMMA_BYTES,
+                RANDOMNESS_BYTES,
+                K,
+                IS_REAL_INPUT,
+            >::verify_with_inputs(
+                &self.verifying_key,
+                &client_message.proof,
+                client_message.ldp_value,
+                &PublicInputsExpand {
+                    time_bounds,
+                    client_sig_pk: &client_message.client_sig_pk,
+                    client_merkle_tree_root: &client_message.client_merkle_tree_root,
+                    index,
+                    server_randomness,
+                },
+                zkp_rng,
+            )
+            .map(|x| (x, client_message.ldp_value))
+        }
+    }
+
+    /// Given a client message, current time (step) bounds, list of `prf_eval_points` (s in the
+    /// paper) and current `index` (j in the paper), bundle the proof with all of its public inputs,
+    /// such that it can be verified by a third party that only has the verifying key (see
+    /// `CircuitExpand::verify_bundle`). Returns `None` if the server signature in the message is
+    /// invalid.
+    pub fn verifiable_bundle(
+        &self,
+        client_message: &[u8],
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        prf_eval_points: &[PRFSchemeInput<Conf>],
+        index: usize,
+    ) -> Result<Option<VerifiableBundleExpand<Conf, TIME_BYTES, RANDOMNESS_BYTES>>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
+        let client_message =
+            VerifiableRandomizationMessageExpand::<Conf, INPUT_BYTES>::deserialize_compressed(
+                client_message,
+            )?;
+        Ok(self
+            .open_submission((&client_message).into(), prf_eval_points)?
+            .map(|server_randomness| VerifiableBundleExpand {
+                proof: client_message.proof,
+                ldp_value: client_message.ldp_value,
+                time_lower_bound: time_bounds.0,
+                time_upper_bound: time_bounds.1,
+                client_sig_pk: client_message.client_sig_pk,
+                client_merkle_tree_root: client_message.client_merkle_tree_root,
+                index,
+                server_randomness,
+            }))
+    }
+
+    /// Given a client message in the blinded mode (see `ClientExpand::blinded_randomization_create`),
+    /// current time (step) bounds, list of `prf_eval_points` (s in the paper) and current `index`
+    /// (j in the paper), perform the `Verify` step for the server. If the message is valid, return
+    /// the commitment to its LDP value, which is opened later with `ldp_value_reveal_verify`.
+    ///
+    /// The `skip_proof` flag can be set to `true` to do a faster test run of this function that
+    /// skips proof verification.
+    /// Note: in actual usage this should be set to `false`.
+    pub fn blinded_randomization_verify<ConfG: ConfigGadget<Conf>>(
+        &self,
+        client_message: &[u8],
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        prf_eval_points: &[PRFSchemeInput<Conf>],
+        index: usize,
+        zkp_rng: &mut ZKPRng<Conf>,
+        skip_proof: bool,
+    ) -> Result<Option<ClientCommitmentSchemeOutput<Conf>>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        ClientCommitmentSchemeOutput<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result = self.verify_blinded_submission::<ConfG>(
+            client_message,
+            time_bounds,
+            prf_eval_points,
+            index,
+            zkp_rng,
+            skip_proof,
+        );
+        #[cfg(feature = "metrics")]
+        self.metrics.observe_blinded(&result, start.elapsed());
+        result
+    }
+
+    fn verify_blinded_submission<ConfG: ConfigGadget<Conf>>(
+        &self,
+        client_message: &[u8],
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        prf_eval_points: &[PRFSchemeInput<Conf>],
+        index: usize,
+        zkp_rng: &mut ZKPRng<Conf>,
+        skip_proof: bool,
+    ) -> Result<Option<ClientCommitmentSchemeOutput<Conf>>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        ClientMerkleTreeRoot<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        ClientCommitmentSchemeOutput<Conf>:
+            ToConstraintField<ConstraintField<Conf>> + CanonicalDeserialize,
+        Proof<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
+        // deserialize client message
+        let client_message =
+            BlindedRandomizationMessageExpand::<Conf, INPUT_BYTES>::deserialize_compressed(
+                client_message,
+            )?;
+
+        let server_randomness =
+            match self.open_submission((&client_message).into(), prf_eval_points)? {
+                Some(server_randomness) => server_randomness,
+                None => return Ok(None),
+            };
+
+        // then verify proof
+        if !skip_proof
+            && !CircuitExpand::<
+                _,
+                ConfG,
+                MT_DEPTH,
+                INPUT_BYTES,
+                TIME_BYTES,
+                GAMMA_BYTES,
+                RANDOMNESS_BYTES,
+                K,
+                IS_REAL_INPUT,
+            >::verify_blinded(
+                self.blinded_verifying_key
+                    .as_ref()
+                    .ok_or(ServerError::MissingBlindedVerifyingKey)?,
+                &client_message.proof,
+                &client_message.ldp_value_commitment,
+                &PublicInputsExpand {
+                    time_bounds,
+                    client_sig_pk: &client_message.client_sig_pk,
+                    client_merkle_tree_root: &client_message.client_merkle_tree_root,
+                    index,
+                    server_randomness,
+                },
+                zkp_rng,
+            )?
+        {
+            return Ok(None);
+        }
+        Ok(Some(client_message.ldp_value_commitment))
+    }
+
+    /// Given the commitment returned by `blinded_randomization_verify` and the client message that
+    /// reveals the LDP value, check that the revealed value opens the commitment (the proof already
+    /// guarantees that the committed value is the correct LDP value). Returns whether this is the
+    /// case, along with the revealed LDP value (`u64::MAX` if it does not open the commitment).
+    pub fn ldp_value_reveal_verify(
+        &self,
+        ldp_value_commitment: &ClientCommitmentSchemeOutput<Conf>,
+        reveal_message: &[u8],
+    ) -> Result<(bool, u64), Error> {
+        let result = LdpValueRevealMessageExpand::deserialize_compressed(reveal_message)
+            .map_err(Error::from)
+            .and_then(|reveal_message| {
+                let opens_commitment = reveal_message
+                    .commitment::<Conf>(&self.parameters.client_commitment_scheme)?
+                    == *ldp_value_commitment;
+                // do not report a value the client could not open the commitment to
+                Ok(if opens_commitment {
+                    (true, reveal_message.ldp_value)
+                } else {
+                    (false, u64::MAX)
+                })
+            });
+        #[cfg(feature = "metrics")]
+        self.metrics.observe_revealed(&result);
+        result
+    }
+
+    /// Verify the server signature over the fields of a client message. If the signature is valid
+    /// (and has not expired), return the reconstructed server randomness.
+    fn open_submission(
+        &self,
+        opening: SubmissionOpening<Conf>,
+        prf_eval_points: &[PRFSchemeInput<Conf>],
+    ) -> Result<Option<[u8; RANDOMNESS_BYTES]>, Error>
+    where
+        ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+        ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    {
+        // reject messages of clients that use a different gamma encoding
+        check_gamma_bytes::<GAMMA_BYTES>(opening.gamma_bytes)?;
+        check_prf_eval_points::<Conf, RANDOMNESS_BYTES>(prf_eval_points)?;
 
         // reconstruct signature input
         let signature_input = GenerateRandomnessSignatureInputExpand::<Conf> {
-            client_merkle_tree_root: client_message.client_merkle_tree_root.clone(),
-            client_signature_public_key: client_message.client_sig_pk.clone(),
-            server_seed: client_message.server_seed,
+            client_merkle_tree_root: opening.client_merkle_tree_root.clone(),
+            client_signature_public_key: opening.client_sig_pk.clone(),
+            server_seed: opening.server_seed,
+            expiry: opening.expiry,
+            server_nonce: opening.server_nonce,
         };
         let mut signature_input_bytes = Vec::new();
         signature_input.serialize_uncompressed(&mut signature_input_bytes)?;
@@ -148,45 +582,112 @@ impl<
             &self.parameters.server_signature_scheme,
             &self.sig_pk,
             &signature_input_bytes,
-            &client_message.server_signature,
+            opening.server_signature,
         )? {
+            // reject signatures that are no longer valid
+            check_seed_expiry(opening.expiry)?;
+
             // reconstruct server randomness
             let mut server_randomness = [0; RANDOMNESS_BYTES];
-            for (chunk, prf_eval_point) in server_randomness.chunks_mut(32).zip(prf_eval_points) {
+            for (chunk, prf_eval_point) in server_randomness
+                .chunks_mut(Conf::PRF_OUTPUT_BYTES)
+                .zip(prf_eval_points)
+            {
                 chunk.copy_from_slice(
-                    &Conf::PRFScheme::evaluate(&client_message.server_seed, &prf_eval_point)?
+                    &Conf::PRFScheme::evaluate(&opening.server_seed, &prf_eval_point)?.as_ref()
                         [0..chunk.len()],
                 );
             }
-            // then verify proof
-            if skip_proof {
-                Ok((true, client_message.ldp_value))
-            } else {
-                CircuitExpand::<
-                    _,
-                    ConfG,
-                    MT_DEPTH,
-                    INPUT_BYTES,
-                    TIME_BYTES,
-                    GAMMA_BYTES,
-                    RANDOMNESS_BYTES,
-                    K,
-                    IS_REAL_INPUT,
-                >::verify(
-                    &self.verifying_key,
-                    &client_message.proof,
-                    client_message.ldp_value,
-                    time_bounds,
-                    &client_message.client_sig_pk,
-                    &client_message.client_merkle_tree_root,
-                    index,
-                    server_randomness,
-                    zkp_rng,
-                )
-                .map(|x| (x, client_message.ldp_value))
-            }
+            Ok(Some(server_randomness))
         } else {
-            Ok((false, u64::MAX))
+            Ok(None)
+        }
+    }
+}
+
+/// Public values of the `Randomize` step that a client message is verified against: the current
+/// time (step) bounds, the list of `prf_eval_points` (s in the paper), and the current `index` (j
+/// in the paper).
+pub struct VerificationStepExpand<'a, Conf: Config, const TIME_BYTES: usize> {
+    pub time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+    pub prf_eval_points: &'a [PRFSchemeInput<Conf>],
+    pub index: usize,
+}
+
+/// Fields of a client message from which `open_submission` reconstructs the server randomness.
+struct SubmissionOpening<'a, Conf: Config> {
+    gamma_bytes: u16,
+    client_merkle_tree_root: &'a ClientMerkleTreeRoot<Conf>,
+    client_sig_pk: &'a ClientSignatureSchemePublicKey<Conf>,
+    server_seed: PRFSchemeSeed<Conf>,
+    server_signature: &'a ServerSignatureSchemeSignature<Conf>,
+    expiry: Option<u64>,
+    server_nonce: [u8; 16],
+}
+
+impl<'a, Conf: Config, const INPUT_BYTES: usize>
+    From<&'a VerifiableRandomizationMessageExpand<Conf, INPUT_BYTES>>
+    for SubmissionOpening<'a, Conf>
+where
+    Proof<Conf>: CanonicalDeserialize,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+{
+    fn from(message: &'a VerifiableRandomizationMessageExpand<Conf, INPUT_BYTES>) -> Self {
+        Self {
+            gamma_bytes: message.gamma_bytes,
+            client_merkle_tree_root: &message.client_merkle_tree_root,
+            client_sig_pk: &message.client_sig_pk,
+            server_seed: message.server_seed,
+            server_signature: &message.server_signature,
+            expiry: message.expiry,
+            server_nonce: message.server_nonce,
+        }
+    }
+}
+
+impl<'a, Conf: Config, const INPUT_BYTES: usize>
+    From<&'a BlindedRandomizationMessageExpand<Conf, INPUT_BYTES>> for SubmissionOpening<'a, Conf>
+where
+    Proof<Conf>: CanonicalDeserialize,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+{
+    fn from(message: &'a BlindedRandomizationMessageExpand<Conf, INPUT_BYTES>) -> Self {
+        Self {
+            gamma_bytes: message.gamma_bytes,
+            client_merkle_tree_root: &message.client_merkle_tree_root,
+            client_sig_pk: &message.client_sig_pk,
+            server_seed: message.server_seed,
+            server_signature: &message.server_signature,
+            expiry: message.expiry,
+            server_nonce: message.server_nonce,
         }
     }
 }
+
+/// Reconstruct the Merkle tree of a client from its leaves (i.e., the commitments to the client
+/// randomness of all `Randomize` steps), such that a server can audit the root the client claimed
+/// by comparing it to the `root()` of the reconstructed tree. The tree must have `depth` levels
+/// (`MT_DEPTH`, at least 2), i.e., there must be `2^(depth - 1)` leaves, otherwise the
+/// reconstruction fails with `ServerError::ParameterMismatch`.
+pub fn reconstruct_merkle_tree<Conf: Config>(
+    parameters: &ClientMerkleTreeParameters<Conf>,
+    leaves: &[ClientCommitmentSchemeOutput<Conf>],
+    depth: usize,
+) -> Result<ClientMerkleTree<Conf>, Error> {
+    let num_leaves = 1_usize << depth.saturating_sub(1);
+    if depth < 2 || leaves.len() != num_leaves {
+        Err(ServerError::ParameterMismatch {
+            parameter: "number of Merkle tree leaves",
+            server: num_leaves,
+            client: leaves.len(),
+        })?
+    }
+    Ok(ClientMerkleTree::<Conf>::new(
+        &parameters.leaf_crh_params,
+        &parameters.two_to_one_crh_params,
+        leaves,
+    )?)
+}
@@ -0,0 +1,252 @@
+//! Prometheus metrics that are collected by the servers of all VLDP schemes (only available with
+//! the `metrics` feature).
+
+use crate::prelude::*;
+use crate::server::VerifyTraceBase;
+use prometheus::{Histogram, HistogramOpts, IntCounterVec, Opts, Registry};
+use std::time::Duration;
+
+/// Metrics about the submissions a server has verified:
+/// - `vldp_submissions_total{outcome}`: number of submissions, by outcome (`accepted`, `rejected`,
+///   or `error` for submissions that could not be processed at all)
+/// - `vldp_verify_duration_seconds`: time spent verifying a submission
+/// - `vldp_ldp_value`: distribution of the LDP values of accepted submissions
+#[derive(Clone)]
+pub struct ServerMetrics {
+    registry: Registry,
+    submissions_total: IntCounterVec,
+    verify_duration_seconds: Histogram,
+    ldp_value: Histogram,
+}
+
+impl ServerMetrics {
+    /// Create and register all metrics for a server with output values in `[0, k]`.
+    pub fn new(k: u64) -> Result<Self, Error> {
+        let registry = Registry::new();
+        let submissions_total = IntCounterVec::new(
+            Opts::new("vldp_submissions_total", "Number of verified submissions."),
+            &["outcome"],
+        )?;
+        let verify_duration_seconds = Histogram::with_opts(HistogramOpts::new(
+            "vldp_verify_duration_seconds",
+            "Time spent verifying a submission.",
+        ))?;
+        // at most 32 buckets, so large values of `k` do not blow up the exposition
+        let num_buckets = k.saturating_add(1).min(32);
+        let bucket_width = (k as f64 / num_buckets as f64).max(1.0);
+        let ldp_value = Histogram::with_opts(
+            HistogramOpts::new("vldp_ldp_value", "LDP values of accepted submissions.").buckets(
+                prometheus::linear_buckets(0.0, bucket_width, num_buckets as usize)?,
+            ),
+        )?;
+        registry.register(Box::new(submissions_total.clone()))?;
+        registry.register(Box::new(verify_duration_seconds.clone()))?;
+        registry.register(Box::new(ldp_value.clone()))?;
+        Ok(Self {
+            registry,
+            submissions_total,
+            verify_duration_seconds,
+            ldp_value,
+        })
+    }
+
+    /// Registry containing all metrics, which is to be scraped by the operator.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Record the result of verifying a single submission.
+    pub(crate) fn observe(&self, result: &Result<(bool, u64), Error>, duration: Duration) {
+        self.record(
+            result
+                .as_ref()
+                .map(|(accepted, ldp_value)| (*accepted, std::slice::from_ref(ldp_value))),
+            duration,
+        );
+    }
+
+    /// Record the result of verifying a single submission of several LDP values (Base scheme),
+    /// each of which is observed in `vldp_ldp_value`.
+    pub(crate) fn observe_all<const D: usize>(
+        &self,
+        result: &Result<(bool, [u64; D]), Error>,
+        duration: Duration,
+    ) {
+        self.record(
+            result
+                .as_ref()
+                .map(|(accepted, ldp_values)| (*accepted, ldp_values.as_slice())),
+            duration,
+        );
+    }
+
+    /// Record the result of verifying a single submission with a trace (Base scheme).
+    pub(crate) fn observe_trace<const RANDOMNESS_BYTES: usize, const D: usize>(
+        &self,
+        result: &Result<VerifyTraceBase<RANDOMNESS_BYTES, D>, Error>,
+        duration: Duration,
+    ) {
+        self.record(
+            result
+                .as_ref()
+                .map(|trace| (trace.valid, trace.ldp_value.as_slice())),
+            duration,
+        );
+    }
+
+    /// Record the result of verifying a single blinded submission (Expand scheme), whose LDP value
+    /// is only observed once it is revealed (see `observe_revealed`).
+    pub(crate) fn observe_blinded<T>(&self, result: &Result<Option<T>, Error>, duration: Duration) {
+        self.record(
+            result
+                .as_ref()
+                .map(|commitment| (commitment.is_some(), &[][..])),
+            duration,
+        );
+    }
+
+    /// Record the LDP value revealed for a blinded submission, if it opens its commitment.
+    pub(crate) fn observe_revealed(&self, result: &Result<(bool, u64), Error>) {
+        if let Ok((true, ldp_value)) = result {
+            self.ldp_value.observe(*ldp_value as f64);
+        }
+    }
+
+    fn record(&self, result: Result<(bool, &[u64]), &Error>, duration: Duration) {
+        let outcome = match result {
+            Ok((true, ldp_values)) => {
+                for ldp_value in ldp_values {
+                    self.ldp_value.observe(*ldp_value as f64);
+                }
+                "accepted"
+            }
+            Ok((false, _)) => "rejected",
+            Err(_) => "error",
+        };
+        self.submissions_total.with_label_values(&[outcome]).inc();
+        self.verify_duration_seconds.observe(duration.as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::client::ClientBase;
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::messages::base::VerifiableRandomizationMessageBase;
+    use crate::prelude::*;
+    use crate::server::ServerBase;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+
+    #[test]
+    fn counters_reflect_verification_outcomes() {
+        let rng = &mut ChaChaRng::from_seed([5; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([6; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (client_sig_pk, _) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        // proofs are skipped, so neither key is used
+        let server = ServerBase::<Conf, 8, 1, 8, 16, 8, false>::new(
+            parameters.clone(),
+            Default::default(),
+            rng,
+        )
+        .unwrap();
+        let proving_key = ark_groth16::ProvingKey {
+            vk: Default::default(),
+            beta_g1: Default::default(),
+            delta_g1: Default::default(),
+            a_query: vec![],
+            b_g1_query: vec![],
+            b_g2_query: vec![],
+            h_query: vec![],
+            l_query: vec![],
+        };
+
+        let mut submissions = vec![];
+        for _ in 0..3 {
+            let mut client = ClientBase::<Conf, 8, 1, 8, 16, 8, false>::new(
+                parameters.clone(),
+                server.get_signature_public_key(),
+                client_sig_pk,
+                proving_key.clone(),
+            )
+            .unwrap();
+            let client_message = client.generate_randomness_create([1], rng).unwrap();
+            let server_message = server
+                .generate_randomness_create(&client_message, rng)
+                .unwrap();
+            client.generate_randomness_verify(&server_message).unwrap();
+            submissions.push(
+                client
+                    .verifiable_randomization_create::<ConfG>(
+                        ([0], [1]),
+                        [1],
+                        [BigUint::from(3_u8)],
+                        Default::default(),
+                        zkp_rng,
+                        true,
+                    )
+                    .unwrap(),
+            );
+        }
+        // tamper with the server seed of the last submission, so its server signature is invalid
+        let mut message = VerifiableRandomizationMessageBase::<Conf, 8>::deserialize_compressed(
+            submissions[2].as_slice(),
+        )
+        .unwrap();
+        message.server_seed[0] ^= 1;
+        submissions[2].clear();
+        message.serialize_compressed(&mut submissions[2]).unwrap();
+        // add a submission that cannot be deserialized
+        submissions.push(vec![0; 4]);
+
+        for submission in submissions.iter() {
+            let _ = server.verifiable_randomization_verify::<ConfG>(
+                submission,
+                ([0], [1]),
+                zkp_rng,
+                true,
+            );
+        }
+
+        let metrics = server.metrics_registry().gather();
+        let submissions_total = metrics
+            .iter()
+            .find(|family| family.get_name() == "vldp_submissions_total")
+            .unwrap();
+        let count = |outcome: &str| {
+            submissions_total
+                .get_metric()
+                .iter()
+                .find(|metric| metric.get_label()[0].get_value() == outcome)
+                .map(|metric| metric.get_counter().get_value())
+        };
+        assert_eq!(count("accepted"), Some(2.0));
+        assert_eq!(count("rejected"), Some(1.0));
+        assert_eq!(count("error"), Some(1.0));
+        let verify_duration = metrics
+            .iter()
+            .find(|family| family.get_name() == "vldp_verify_duration_seconds")
+            .unwrap();
+        assert_eq!(
+            verify_duration.get_metric()[0]
+                .get_histogram()
+                .get_sample_count(),
+            4
+        );
+    }
+}
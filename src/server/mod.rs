@@ -8,3 +8,1032 @@ pub use expand::*;
 
 pub mod shuffle;
 pub use shuffle::*;
+
+pub mod aggregator;
+pub use aggregator::*;
+
+use crate::messages::{is_weak_seed, required_prf_eval_points};
+use crate::prelude::*;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use blake2::Digest;
+use std::time::Duration;
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Check that the client message was created for the same `GAMMA_BYTES` as the server uses.
+/// Otherwise, client and server disagree on how the LDP bit is computed, which would only surface
+/// as an (opaque) invalid proof.
+pub(crate) fn check_gamma_bytes<const GAMMA_BYTES: usize>(gamma_bytes: u16) -> Result<(), Error> {
+    if gamma_bytes as usize == GAMMA_BYTES {
+        Ok(())
+    } else {
+        Err(ServerError::ParameterMismatch {
+            parameter: "GAMMA_BYTES",
+            server: GAMMA_BYTES,
+            client: gamma_bytes as usize,
+        })?
+    }
+}
+
+/// Check that exactly as many `prf_eval_points` are given as are needed to reconstruct the server
+/// randomness. Additional points would be ignored and missing points would leave the randomness
+/// partially zero, so a mismatch with the points of the client would only surface as an invalid
+/// proof.
+pub(crate) fn check_prf_eval_points<Conf: Config, const RANDOMNESS_BYTES: usize>(
+    prf_eval_points: &[PRFSchemeInput<Conf>],
+) -> Result<(), Error> {
+    let required = required_prf_eval_points::<Conf>(RANDOMNESS_BYTES);
+    if prf_eval_points.len() == required {
+        Ok(())
+    } else {
+        Err(ServerError::PrfEvalPointCount {
+            required,
+            given: prf_eval_points.len(),
+        })?
+    }
+}
+
+/// Check that the client message carries its gamma if and only if gamma is a public input of the
+/// server's circuit, and that it has `GAMMA_BYTES` bytes. A public gamma whose bytes are all
+/// `u8::MAX` is rejected, since it sets the LDP bit for all randomness (see `effective_gamma`), such
+/// that the LDP value carries no information and debiasing it would divide by zero. Returns the
+/// client's gamma, if public.
+pub(crate) fn check_public_gamma<const GAMMA_BYTES: usize>(
+    gamma_is_public: bool,
+    gamma: Option<&[u8]>,
+) -> Result<Option<[u8; GAMMA_BYTES]>, Error> {
+    let server = if gamma_is_public { GAMMA_BYTES } else { 0 };
+    let client = gamma.map_or(0, <[u8]>::len);
+    if server != client {
+        Err(ServerError::ParameterMismatch {
+            parameter: "public gamma bytes",
+            server,
+            client,
+        })?
+    }
+    if gamma_is_public && gamma.is_some_and(|gamma| gamma.iter().all(|&byte| byte == u8::MAX)) {
+        Err(ServerError::UninformativeGamma)?
+    }
+    Ok(gamma
+        .filter(|_| gamma_is_public)
+        .map(|gamma| gamma.try_into().expect("the length has been checked")))
+}
+
+/// Maximum number of draws from a `SeedSource` until `sample_server_seed` considers it broken.
+pub const MAX_SEED_DRAWS: usize = 16;
+
+/// Source of the server seeds. Every cryptographically secure RNG is a seed source; implementing
+/// it by hand allows to supply the seeds from elsewhere (e.g., to test a broken source).
+pub trait SeedSource {
+    /// Fill `seed` with fresh random bytes.
+    fn fill_seed(&mut self, seed: &mut [u8]);
+}
+
+impl<R: Rng + CryptoRng + ?Sized> SeedSource for R {
+    fn fill_seed(&mut self, seed: &mut [u8]) {
+        self.fill_bytes(seed)
+    }
+}
+
+/// Draw a server seed from `source`, re-rolling weak seeds (see `is_weak_seed`), which would make
+/// the randomness of the client predictable. Fails with `ServerError::WeakSeedSource` if
+/// `MAX_SEED_DRAWS` draws in a row are weak, since the source is broken then.
+pub fn sample_server_seed<Conf: Config, S: SeedSource + ?Sized>(
+    source: &mut S,
+) -> Result<PRFSchemeSeed<Conf>, Error> {
+    let mut server_seed = PRFSchemeSeed::<Conf>::default();
+    for _ in 0..MAX_SEED_DRAWS {
+        source.fill_seed(&mut server_seed);
+        if !is_weak_seed(&server_seed) {
+            return Ok(server_seed);
+        }
+    }
+    Err(ServerError::WeakSeedSource {
+        draws: MAX_SEED_DRAWS,
+    })?
+}
+
+/// Identifier of a submission, see `submission_id`.
+pub type SubmissionId = [u8; 32];
+
+/// Identifier of a submission: the Blake2s hash of the serialized client message, i.e., of the
+/// bytes passed to the verification function of the server. A submission that is replayed byte
+/// for byte has the same identifier, so duplicates can be detected (e.g., with a set of the
+/// identifiers) before or after the (much more expensive) verification.
+pub fn submission_id(client_message: &[u8]) -> SubmissionId {
+    let mut id = [0; 32];
+    id.copy_from_slice(&blake2::Blake2s256::digest(client_message));
+    id
+}
+
+/// Deserialize an (untrusted) client message, and reject it if the buffer has bytes left over
+/// after the message, such that every accepted message has exactly one encoding. Truncated or
+/// otherwise malformed buffers fail to deserialize.
+pub(crate) fn deserialize_client_message<T: CanonicalSerialize + CanonicalDeserialize>(
+    client_message: &[u8],
+) -> Result<T, Error> {
+    let message = T::deserialize_compressed(client_message)?;
+    let expected = message.compressed_size();
+    if expected == client_message.len() {
+        Ok(message)
+    } else {
+        Err(ServerError::TrailingBytes {
+            expected,
+            given: client_message.len(),
+        })?
+    }
+}
+
+/// Check that `ldp_value` lies in the output range of the LDP mechanism, i.e., `[0, K]` for real
+/// input and `[1, K]` for histogram input. Values outside of it cannot have a valid proof, so
+/// they are rejected before any cryptographic work.
+pub(crate) fn check_ldp_value_range<const K: u64, const IS_REAL_INPUT: bool>(
+    ldp_value: u64,
+) -> Result<(), Error> {
+    let min = if IS_REAL_INPUT { 0 } else { 1 };
+    if (min..=K).contains(&ldp_value) {
+        Ok(())
+    } else {
+        Err(ServerError::LdpValueOutOfRange {
+            ldp_value,
+            min,
+            max: K,
+        })?
+    }
+}
+
+/// Current time in seconds since the Unix epoch.
+#[cfg(feature = "std")]
+fn unix_time() -> Result<u64, Error> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+/// Without the `std` feature there is no system clock, so an expiry can neither be issued (see
+/// `with_seed_validity` of the servers) nor checked.
+#[cfg(not(feature = "std"))]
+fn unix_time() -> Result<u64, Error> {
+    Err(ServerError::MissingClock)?
+}
+
+/// Expiry of a server signature that is issued now and valid for `seed_validity` (if any).
+pub(crate) fn seed_expiry(seed_validity: Option<Duration>) -> Result<Option<u64>, Error> {
+    seed_validity
+        .map(|seed_validity| Ok(unix_time()?.saturating_add(seed_validity.as_secs())))
+        .transpose()
+}
+
+/// Check that the (signed) `expiry` of a server signature has not passed yet.
+pub(crate) fn check_seed_expiry(expiry: Option<u64>) -> Result<(), Error> {
+    match expiry {
+        Some(expiry) => {
+            let now = unix_time()?;
+            if now < expiry {
+                Ok(())
+            } else {
+                Err(ServerError::ExpiredServerSignature { expiry, now })?
+            }
+        }
+        None => Ok(()),
+    }
+}
+
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "metrics")]
+pub use metrics::*;
+
+#[cfg(feature = "async")]
+pub mod transport;
+#[cfg(feature = "async")]
+pub use transport::*;
+
+#[cfg(test)]
+mod test {
+    use crate::circuits::CircuitBase;
+    use crate::client::ClientBase;
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::messages::base::*;
+    use crate::messages::expand::VerifiableRandomizationMessageExpand;
+    use crate::messages::shuffle::{PrfEvalPointSet, VerifiableRandomizationMessageShuffle};
+    use crate::messages::{
+        derive_prf_eval_points, required_prf_eval_points, InputValueSignatureInput,
+    };
+    use crate::prelude::*;
+    use crate::server::{
+        sample_server_seed, submission_id, SeedSource, ServerBase, ServerExpand, ServerShuffle,
+        MAX_SEED_DRAWS,
+    };
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    /// Commits to up to 40 bytes of client randomness.
+    type WideConf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 40>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+
+    #[test]
+    fn gamma_bytes_mismatch_is_reported() {
+        let rng = &mut ChaChaRng::from_seed([13; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([14; 32]);
+        let gamma = BigFloat::from_f64(0.5, 64);
+        let shared = SharedPrimitives::<Conf>::setup(rng).unwrap();
+        let (client_sig_pk, _) =
+            <Conf as Config>::ClientSignatureScheme::keygen(&shared.client_signature_scheme, rng)
+                .unwrap();
+        // the server uses 4 gamma bytes, the client 8; proofs are skipped, so no keys are needed
+        let server = ServerBase::<Conf, 8, 1, 4, 16, 8, false>::new(
+            ParametersBase::from_shared(gamma.clone(), &shared).unwrap(),
+            Default::default(),
+            rng,
+        )
+        .unwrap();
+        let proving_key = ark_groth16::ProvingKey {
+            vk: Default::default(),
+            beta_g1: Default::default(),
+            delta_g1: Default::default(),
+            a_query: vec![],
+            b_g1_query: vec![],
+            b_g2_query: vec![],
+            h_query: vec![],
+            l_query: vec![],
+        };
+        let mut client = ClientBase::<Conf, 8, 1, 8, 16, 8, false>::new(
+            ParametersBase::from_shared(gamma, &shared).unwrap(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                ([0], [1]),
+                [1],
+                [BigUint::from(3_u8)],
+                Default::default(),
+                zkp_rng,
+                true,
+            )
+            .unwrap();
+
+        let error = server
+            .verifiable_randomization_verify::<ConfG>(&submission, ([0], [1]), zkp_rng, true)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Server(ServerError::ParameterMismatch {
+                parameter: "GAMMA_BYTES",
+                server: 4,
+                client: 8,
+            })
+        ));
+        assert!(error.to_string().contains("GAMMA_BYTES"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn expired_server_signature_is_rejected() {
+        use std::time::Duration;
+
+        let rng = &mut ChaChaRng::from_seed([32; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([33; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (client_sig_pk, _) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let proving_key = ark_groth16::ProvingKey {
+            vk: Default::default(),
+            beta_g1: Default::default(),
+            delta_g1: Default::default(),
+            a_query: vec![],
+            b_g1_query: vec![],
+            b_g2_query: vec![],
+            h_query: vec![],
+            l_query: vec![],
+        };
+        // proofs are skipped, so neither key is used
+        let mut submit = |seed_validity: Duration, zkp_rng: &mut ChaChaRng| {
+            let server = ServerBase::<Conf, 8, 1, 8, 16, 8, false>::new(
+                parameters.clone(),
+                Default::default(),
+                rng,
+            )
+            .unwrap()
+            .with_seed_validity(seed_validity);
+            let mut client = ClientBase::<Conf, 8, 1, 8, 16, 8, false>::new(
+                parameters.clone(),
+                server.get_signature_public_key(),
+                client_sig_pk,
+                proving_key.clone(),
+            )
+            .unwrap();
+            let client_message = client.generate_randomness_create([1], rng).unwrap();
+            let server_message = server
+                .generate_randomness_create(&client_message, rng)
+                .unwrap();
+            client.generate_randomness_verify(&server_message).unwrap();
+            let submission = client
+                .verifiable_randomization_create::<ConfG>(
+                    ([0], [1]),
+                    [1],
+                    [BigUint::from(3_u8)],
+                    Default::default(),
+                    zkp_rng,
+                    true,
+                )
+                .unwrap();
+            (server, submission)
+        };
+
+        let (server, submission) = submit(Duration::from_secs(3600), zkp_rng);
+        assert!(
+            server
+                .verifiable_randomization_verify::<ConfG>(&submission, ([0], [1]), zkp_rng, true)
+                .unwrap()
+                .0
+        );
+
+        // the expiry is signed, so it cannot be extended by the client
+        let mut message = VerifiableRandomizationMessageBase::<Conf, 8>::deserialize_compressed(
+            submission.as_slice(),
+        )
+        .unwrap();
+        message.expiry = message.expiry.map(|expiry| expiry + 1);
+        let mut tampered = vec![];
+        message.serialize_compressed(&mut tampered).unwrap();
+        assert!(
+            !server
+                .verifiable_randomization_verify::<ConfG>(&tampered, ([0], [1]), zkp_rng, true)
+                .unwrap()
+                .0
+        );
+
+        // so is the server nonce, which cannot be replaced by the nonce of another exchange
+        message.expiry = message.expiry.map(|expiry| expiry - 1);
+        message.server_nonce[0] ^= 1;
+        let mut tampered = vec![];
+        message.serialize_compressed(&mut tampered).unwrap();
+        assert!(
+            !server
+                .verifiable_randomization_verify::<ConfG>(&tampered, ([0], [1]), zkp_rng, true)
+                .unwrap()
+                .0
+        );
+
+        let (server, submission) = submit(Duration::ZERO, zkp_rng);
+        let error = server
+            .verifiable_randomization_verify::<ConfG>(&submission, ([0], [1]), zkp_rng, true)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Server(ServerError::ExpiredServerSignature { .. })
+        ));
+        assert!(error.to_string().contains("expired"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn expiry_cannot_be_checked_without_clock() {
+        assert!(super::check_seed_expiry(None).is_ok());
+        assert!(matches!(
+            super::check_seed_expiry(Some(u64::MAX)).unwrap_err(),
+            VldpError::Server(ServerError::MissingClock)
+        ));
+        assert!(super::seed_expiry(None).unwrap().is_none());
+    }
+
+    #[test]
+    fn clients_with_different_public_gammas() {
+        let rng = &mut ChaChaRng::from_seed([35; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([36; 32]);
+        let shared = SharedPrimitives::<Conf>::setup(rng).unwrap();
+        let parameters_for = |gamma: f64| {
+            ParametersBase::<Conf, 2>::from_shared(BigFloat::from_f64(gamma, 64), &shared)
+                .unwrap()
+                .with_public_gamma()
+                .unwrap()
+        };
+        // a public gamma must stay below half the modulus of the (255 bit) constraint field
+        assert!(
+            ParametersBase::<Conf, 32>::from_shared(BigFloat::from_f64(0.5, 64), &shared)
+                .unwrap()
+                .with_public_gamma()
+                .is_err()
+        );
+        // with a public gamma, the keys do not depend on the gamma they are generated for
+        let (proving_key, verifying_key) =
+            CircuitBase::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(parameters_for(0.5), zkp_rng)
+                .unwrap();
+        let server =
+            ServerBase::<Conf, 2, 1, 2, 4, 4, false>::new(parameters_for(0.5), verifying_key, rng)
+                .unwrap();
+
+        let mut messages = vec![];
+        for gamma in [0.25, 0.75] {
+            let parameters = parameters_for(gamma);
+            let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+                &parameters.client_signature_scheme,
+                rng,
+            )
+            .unwrap();
+            let mut client = ClientBase::<Conf, 2, 1, 2, 4, 4, false>::new(
+                parameters.clone(),
+                server.get_signature_public_key(),
+                client_sig_pk,
+                proving_key.clone(),
+            )
+            .unwrap();
+            let client_message = client.generate_randomness_create([1], rng).unwrap();
+            let server_message = server
+                .generate_randomness_create(&client_message, rng)
+                .unwrap();
+            client.generate_randomness_verify(&server_message).unwrap();
+            let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+                &parameters.client_signature_scheme,
+                &client_sig_sk,
+                &InputValueSignatureInput {
+                    input_value: [3, 0],
+                    input_value_time: [1],
+                }
+                .to_bytes(),
+                rng,
+            )
+            .unwrap();
+            let submission = client
+                .verifiable_randomization_create::<ConfG>(
+                    ([0], [1]),
+                    [1],
+                    [BigUint::from(3_u8)],
+                    input_value_signature,
+                    zkp_rng,
+                    false,
+                )
+                .unwrap();
+
+            let message = VerifiableRandomizationMessageBase::<Conf, 2>::deserialize_compressed(
+                submission.as_slice(),
+            )
+            .unwrap();
+            assert_eq!(
+                message.gamma.as_deref(),
+                Some(parameters.gamma_as_bytes().unwrap().as_slice())
+            );
+            assert!(
+                server
+                    .verifiable_randomization_verify_message::<ConfG>(
+                        &message,
+                        ([0], [1]),
+                        zkp_rng,
+                        false
+                    )
+                    .unwrap()
+                    .0
+            );
+            messages.push(message);
+        }
+
+        // a public gamma of 1 is rejected before the proof is verified
+        messages[1].gamma = Some(vec![u8::MAX; 2]);
+        assert!(matches!(
+            server.verifiable_randomization_verify_message::<ConfG>(
+                &messages[1],
+                ([0], [1]),
+                zkp_rng,
+                false
+            ),
+            Err(VldpError::Server(ServerError::UninformativeGamma))
+        ));
+
+        // the proof is bound to the gamma of the client
+        messages[1].gamma = messages[0].gamma.clone();
+        assert!(
+            !server
+                .verifiable_randomization_verify_message::<ConfG>(
+                    &messages[1],
+                    ([0], [1]),
+                    zkp_rng,
+                    false
+                )
+                .unwrap()
+                .0
+        );
+
+        // a server with a constant gamma rejects messages that carry a gamma
+        let server = ServerBase::<Conf, 2, 1, 2, 4, 4, false>::new(
+            ParametersBase::from_shared(BigFloat::from_f64(0.25, 64), &shared).unwrap(),
+            Default::default(),
+            rng,
+        )
+        .unwrap();
+        let error = server
+            .verifiable_randomization_verify_message::<ConfG>(
+                &messages[0],
+                ([0], [1]),
+                zkp_rng,
+                true,
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Server(ServerError::ParameterMismatch {
+                parameter: "public gamma bytes",
+                server: 0,
+                client: 2,
+            })
+        ));
+    }
+
+    /// Seed source that yields all-zero seeds for the first `zero_draws` draws, like a broken RNG
+    struct ZeroSeedSource {
+        zero_draws: usize,
+        rng: ChaChaRng,
+    }
+
+    impl SeedSource for ZeroSeedSource {
+        fn fill_seed(&mut self, seed: &mut [u8]) {
+            if self.zero_draws > 0 {
+                self.zero_draws -= 1;
+                seed.fill(0);
+            } else {
+                self.rng.fill(seed);
+            }
+        }
+    }
+
+    #[test]
+    fn all_zero_server_seeds_are_rejected() {
+        let rng = &mut ChaChaRng::from_seed([47; 32]);
+
+        // the server re-rolls zero seeds, but gives up on a source that only yields these
+        let mut source = ZeroSeedSource {
+            zero_draws: 3,
+            rng: ChaChaRng::from_seed([48; 32]),
+        };
+        let server_seed = sample_server_seed::<Conf, _>(&mut source).unwrap();
+        assert_ne!(server_seed, [0; 32]);
+        let mut source = ZeroSeedSource {
+            zero_draws: MAX_SEED_DRAWS,
+            rng: ChaChaRng::from_seed([48; 32]),
+        };
+        let error = sample_server_seed::<Conf, _>(&mut source).unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Server(ServerError::WeakSeedSource {
+                draws: MAX_SEED_DRAWS
+            })
+        ));
+
+        // a client rejects a zero seed, even if it is validly signed by the server
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (server_sig_pk, server_sig_sk) = <Conf as Config>::ServerSignatureScheme::keygen(
+            &parameters.server_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, _) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        // the proof is never created, so the proving key is not used
+        let proving_key = ark_groth16::ProvingKey {
+            vk: Default::default(),
+            beta_g1: Default::default(),
+            delta_g1: Default::default(),
+            a_query: vec![],
+            b_g1_query: vec![],
+            b_g2_query: vec![],
+            h_query: vec![],
+            l_query: vec![],
+        };
+        let mut client = ClientBase::<Conf, 8, 1, 8, 16, 8, false>::new(
+            parameters.clone(),
+            server_sig_pk,
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        let client_message =
+            GenerateRandomnessMessageClientBase::<Conf, 1>::deserialize_compressed(
+                client
+                    .generate_randomness_create([1], rng)
+                    .unwrap()
+                    .as_slice(),
+            )
+            .unwrap();
+        let mut source = ZeroSeedSource {
+            zero_draws: 1,
+            rng: ChaChaRng::from_seed([48; 32]),
+        };
+        let mut server_seed = [1; 32];
+        source.fill_seed(&mut server_seed);
+        let mut signature_input_bytes = vec![];
+        GenerateRandomnessSignatureInputBase::new(client_message, server_seed, None, [0; 16])
+            .serialize_uncompressed(&mut signature_input_bytes)
+            .unwrap();
+        let server_signature = <Conf as Config>::ServerSignatureScheme::sign(
+            &parameters.server_signature_scheme,
+            &server_sig_sk,
+            &signature_input_bytes,
+            rng,
+        )
+        .unwrap();
+        let mut server_message = vec![];
+        GenerateRandomnessMessageServerBase::<Conf> {
+            server_seed,
+            server_signature,
+            expiry: None,
+            server_nonce: [0; 16],
+        }
+        .serialize_compressed(&mut server_message)
+        .unwrap();
+
+        let error = client
+            .generate_randomness_verify(&server_message)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Client(ClientError::WeakServerSeed)
+        ));
+    }
+
+    #[test]
+    fn verify_trace_contains_the_server_randomness() {
+        let rng = &mut ChaChaRng::from_seed([89; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([90; 32]);
+        let parameters =
+            ParametersBase::<WideConf, 8>::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (client_sig_pk, _) = <WideConf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        // proofs are skipped, so no keys are needed
+        let server = ServerBase::<WideConf, 8, 1, 8, 40, 8, false>::new(
+            parameters.clone(),
+            Default::default(),
+            rng,
+        )
+        .unwrap();
+        let proving_key = ark_groth16::ProvingKey {
+            vk: Default::default(),
+            beta_g1: Default::default(),
+            delta_g1: Default::default(),
+            a_query: vec![],
+            b_g1_query: vec![],
+            b_g2_query: vec![],
+            h_query: vec![],
+            l_query: vec![],
+        };
+        let mut client = ClientBase::<WideConf, 8, 1, 8, 40, 8, false>::new(
+            parameters,
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                ([0], [1]),
+                [1],
+                [BigUint::from(3_u8)],
+                Default::default(),
+                zkp_rng,
+                true,
+            )
+            .unwrap();
+
+        // the trace is populated although the proof is skipped
+        let trace = server
+            .verifiable_randomization_verify_with_trace::<ConfG>(
+                &submission,
+                ([0], [1]),
+                zkp_rng,
+                true,
+            )
+            .unwrap();
+        let (valid, ldp_value) = server
+            .verifiable_randomization_verify::<ConfG>(&submission, ([0], [1]), zkp_rng, true)
+            .unwrap();
+        assert_eq!((trace.valid, trace.ldp_value), (valid, ldp_value));
+        assert!(trace.valid);
+
+        // two PRF evaluations at the chunk indices, the second one truncated to 8 bytes
+        let mut message =
+            VerifiableRandomizationMessageBase::<WideConf, 8>::deserialize_compressed(
+                submission.as_slice(),
+            )
+            .unwrap();
+        let mut expected_randomness = [0; 40];
+        for (index, chunk) in expected_randomness
+            .chunks_mut(<WideConf as Config>::PRF_OUTPUT_BYTES)
+            .enumerate()
+        {
+            let mut eval_point = [0; 32];
+            eval_point[0] = index as u8;
+            chunk.copy_from_slice(
+                &<WideConf as Config>::PRFScheme::evaluate(&message.server_seed, &eval_point)
+                    .unwrap()
+                    .as_ref()[0..chunk.len()],
+            );
+        }
+        assert_eq!(trace.server_randomness, expected_randomness);
+
+        // the seed of a message with an invalid server signature is not used
+        message.server_seed[0] ^= 1;
+        let mut tampered = vec![];
+        message.serialize_compressed(&mut tampered).unwrap();
+        let trace = server
+            .verifiable_randomization_verify_with_trace::<ConfG>(
+                &tampered,
+                ([0], [1]),
+                zkp_rng,
+                true,
+            )
+            .unwrap();
+        assert!(!trace.valid);
+        assert_eq!(trace.server_randomness, [0; 40]);
+    }
+
+    #[test]
+    fn replayed_submissions_have_the_same_id() {
+        let rng = &mut ChaChaRng::from_seed([80; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([81; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (client_sig_pk, _) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        // proofs are skipped, so neither key is used
+        let server = ServerBase::<Conf, 8, 1, 8, 16, 8, false>::new(
+            parameters.clone(),
+            Default::default(),
+            rng,
+        )
+        .unwrap();
+        let proving_key = ark_groth16::ProvingKey {
+            vk: Default::default(),
+            beta_g1: Default::default(),
+            delta_g1: Default::default(),
+            a_query: vec![],
+            b_g1_query: vec![],
+            b_g2_query: vec![],
+            h_query: vec![],
+            l_query: vec![],
+        };
+        let mut client = ClientBase::<Conf, 8, 1, 8, 16, 8, false>::new(
+            parameters,
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                ([0], [1]),
+                [1],
+                [BigUint::from(3_u8)],
+                Default::default(),
+                zkp_rng,
+                true,
+            )
+            .unwrap();
+
+        let replayed = submission.clone();
+        assert_eq!(submission_id(&submission), submission_id(&replayed));
+
+        let mut message = VerifiableRandomizationMessageBase::<Conf, 8>::deserialize_compressed(
+            submission.as_slice(),
+        )
+        .unwrap();
+        message.ldp_value[0] += 1;
+        let mut tampered = vec![];
+        message.serialize_compressed(&mut tampered).unwrap();
+        assert_ne!(submission_id(&submission), submission_id(&tampered));
+    }
+
+    #[test]
+    fn prf_eval_point_count_mismatch_is_reported() {
+        let rng = &mut ChaChaRng::from_seed([102; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([103; 32]);
+        let gamma = BigFloat::from_f64(0.5, 64);
+        // 40 bytes of server randomness take two PRF evaluations; proofs are skipped, so no keys
+        // are needed
+        let shuffle_server = ServerShuffle::<Conf, 2, 1, 2, 40, 4, false>::new(
+            ParametersShuffle::setup(gamma.clone(), rng).unwrap(),
+            Default::default(),
+            rng,
+        )
+        .unwrap();
+        let expand_server = ServerExpand::<Conf, 2, 2, 1, 2, 40, 4, false>::new(
+            ParametersExpand::setup(gamma, rng).unwrap(),
+            Default::default(),
+            rng,
+        )
+        .unwrap();
+        let shuffle_message = VerifiableRandomizationMessageShuffle::<Conf, 2> {
+            gamma_bytes: 2,
+            proof: Default::default(),
+            ldp_value: 1,
+        };
+        let expand_message = VerifiableRandomizationMessageExpand::<Conf, 2> {
+            gamma_bytes: 2,
+            client_sig_pk: Default::default(),
+            client_merkle_tree_root: Default::default(),
+            server_seed: [0; 32],
+            server_signature: Default::default(),
+            expiry: None,
+            server_nonce: [0; 16],
+            proof: Default::default(),
+            ldp_value: 1,
+        };
+        let prf_eval_points = derive_prf_eval_points::<Conf>([104; 32], 3).unwrap();
+        assert_eq!(required_prf_eval_points::<Conf>(40), 2);
+
+        // too few and too many points are both reported, before anything else is checked
+        for given in [1, 3] {
+            let errors = [
+                shuffle_server
+                    .verifiable_randomization_verify_message::<ConfG>(
+                        &shuffle_message,
+                        ([0], [1]),
+                        &PrfEvalPointSet::new(0, prf_eval_points[0..given].to_vec()),
+                        zkp_rng,
+                        true,
+                    )
+                    .unwrap_err(),
+                expand_server
+                    .verifiable_randomization_verify_message::<ConfG>(
+                        &expand_message,
+                        ([0], [1]),
+                        &prf_eval_points[0..given],
+                        0,
+                        zkp_rng,
+                        true,
+                    )
+                    .unwrap_err(),
+            ];
+            for error in errors {
+                assert!(
+                    matches!(
+                        error,
+                        VldpError::Server(ServerError::PrfEvalPointCount { required: 2, given: g })
+                            if g == given
+                    ),
+                    "{}",
+                    error
+                );
+            }
+        }
+
+        // the exact number of points passes the check
+        assert!(
+            shuffle_server
+                .verifiable_randomization_verify_message::<ConfG>(
+                    &shuffle_message,
+                    ([0], [1]),
+                    &PrfEvalPointSet::new(0, prf_eval_points[0..2].to_vec()),
+                    zkp_rng,
+                    true,
+                )
+                .unwrap()
+                .0
+        );
+    }
+
+    #[test]
+    fn malformed_client_messages_are_rejected_cleanly() {
+        let rng = &mut ChaChaRng::from_seed([121; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([122; 32]);
+        let parameters = ParametersBase::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (client_sig_pk, _) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        // proofs are skipped, so neither key is used
+        let server = ServerBase::<Conf, 8, 1, 8, 16, 8, false>::new(
+            parameters.clone(),
+            Default::default(),
+            rng,
+        )
+        .unwrap();
+        let proving_key = ark_groth16::ProvingKey {
+            vk: Default::default(),
+            beta_g1: Default::default(),
+            delta_g1: Default::default(),
+            a_query: vec![],
+            b_g1_query: vec![],
+            b_g2_query: vec![],
+            h_query: vec![],
+            l_query: vec![],
+        };
+        let mut client = ClientBase::<Conf, 8, 1, 8, 16, 8, false>::new(
+            parameters,
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        let client_message = client.generate_randomness_create([1], rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+
+        // truncated and oversized GenRand messages
+        for length in 0..client_message.len() {
+            assert!(server
+                .generate_randomness_create(&client_message[0..length], rng)
+                .is_err());
+        }
+        let mut oversized = client_message.clone();
+        oversized.push(0);
+        assert!(matches!(
+            server.generate_randomness_create(&oversized, rng).unwrap_err(),
+            VldpError::Server(ServerError::TrailingBytes { .. })
+        ));
+
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                ([0], [1]),
+                [1],
+                [BigUint::from(3_u8)],
+                Default::default(),
+                zkp_rng,
+                true,
+            )
+            .unwrap();
+        let mut verify = |client_message: &[u8]| {
+            server.verifiable_randomization_verify::<ConfG>(
+                client_message,
+                ([0], [1]),
+                zkp_rng,
+                true,
+            )
+        };
+        assert!(verify(&submission).unwrap().0);
+
+        // random buffers of all sizes around the size of a submission
+        for length in (0..2 * submission.len()).step_by(7) {
+            let mut buffer = vec![0; length];
+            rng.fill(&mut buffer[..]);
+            assert!(verify(&buffer).is_err());
+        }
+
+        // every truncation of a valid submission
+        for length in 0..submission.len() {
+            assert!(verify(&submission[0..length]).is_err());
+        }
+
+        // trailing bytes after a valid submission
+        let mut oversized = submission.clone();
+        oversized.push(0);
+        assert!(matches!(
+            verify(&oversized).unwrap_err(),
+            VldpError::Server(ServerError::TrailingBytes { expected, given })
+                if expected == submission.len() && given == submission.len() + 1
+        ));
+
+        // LDP values outside of [1, K] are rejected before the server signature is verified
+        for ldp_value in [0, 9, u64::MAX] {
+            let mut message =
+                VerifiableRandomizationMessageBase::<Conf, 8>::deserialize_compressed(
+                    submission.as_slice(),
+                )
+                .unwrap();
+            message.ldp_value = [ldp_value];
+            let mut tampered = vec![];
+            message.serialize_compressed(&mut tampered).unwrap();
+            assert!(matches!(
+                verify(&tampered).unwrap_err(),
+                VldpError::Server(ServerError::LdpValueOutOfRange { min: 1, max: 8, .. })
+            ));
+        }
+    }
+}
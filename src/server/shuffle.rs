@@ -1,8 +1,12 @@
 //! All functionalities for a server in the Expand scheme
 
 use crate::circuits::CircuitShuffle;
+use crate::config::{verify_batch, BatchFailurePolicy};
 use crate::messages::shuffle::*;
 use crate::prelude::*;
+#[cfg(feature = "metrics")]
+use crate::server::ServerMetrics;
+use crate::server::{check_gamma_bytes, check_prf_eval_points, sample_server_seed};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 
 /// Expand scheme server
@@ -19,6 +23,8 @@ pub struct ServerShuffle<
     sig_pk: ServerSignatureSchemePublicKey<Conf>,
     sig_sk: ServerSignatureSchemeSecretKey<Conf>,
     verifying_key: VerifyingKey<Conf>,
+    #[cfg(feature = "metrics")]
+    metrics: ServerMetrics,
 }
 
 impl<
@@ -38,6 +44,7 @@ impl<
         verifying_key: VerifyingKey<Conf>,
         rng: &mut R,
     ) -> Result<Self, Error> {
+        validate_scheme_params::<INPUT_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>()?;
         let (sig_pk, sig_sk) =
             Conf::ServerSignatureScheme::keygen(&parameters.server_signature_scheme, rng)?;
         Ok(Self {
@@ -45,9 +52,18 @@ impl<
             sig_pk,
             sig_sk,
             verifying_key,
+            #[cfg(feature = "metrics")]
+            metrics: ServerMetrics::new(K)?,
         })
     }
 
+    /// Get the registry with the Prometheus metrics of this server, which is to be scraped by the
+    /// operator.
+    #[cfg(feature = "metrics")]
+    pub fn metrics_registry(&self) -> &prometheus::Registry {
+        self.metrics.registry()
+    }
+
     /// Get server's signature public key
     pub fn get_signature_public_key(&self) -> ServerSignatureSchemePublicKey<Conf> {
         self.sig_pk.clone()
@@ -69,12 +85,15 @@ impl<
             GenerateRandomnessMessageClientShuffle::<Conf>::deserialize_compressed(client_message)?;
 
         // compute server seed
-        let mut server_seed = PRFSchemeSeed::<Conf>::default();
-        rng.fill_bytes(&mut server_seed);
+        let server_seed = sample_server_seed::<Conf, _>(rng)?;
 
-        // create signature input
-        let signature_input =
-            GenerateRandomnessSignatureInputShuffle::new(client_message, server_seed.clone());
+        // create signature input, bound to this exchange by a fresh nonce
+        let server_nonce: [u8; 16] = rng.gen();
+        let signature_input = GenerateRandomnessSignatureInputShuffle::new(
+            client_message,
+            server_seed.clone(),
+            server_nonce,
+        );
         let mut signature_input_bytes = Vec::new();
         signature_input.serialize_uncompressed(&mut signature_input_bytes)?;
 
@@ -91,13 +110,16 @@ impl<
         GenerateRandomnessMessageServerShuffle::<Conf> {
             server_seed,
             server_signature,
+            server_nonce,
         }
         .serialize_compressed(&mut serialized_message)?;
         Ok(serialized_message)
     }
 
-    /// Given a client message, current time (step) bounds, list of `prf_eval_points` (s in the
-    /// paper) and current `index` (j in the paper) perform the `Verify` step for the server.
+    /// Given a client message, current time (step) bounds, and the public `prf_eval_points` (s in
+    /// the paper) of the epoch the message was created for, perform the `Verify` step for the
+    /// server. A message that was created for another epoch, or for other points, is rejected (see
+    /// `PrfEvalPointSet`).
     ///
     /// The `skip_proof` flag can be set to `true` to do a faster test run of this function that
     /// skips proof verification.
@@ -106,7 +128,7 @@ impl<
         &self,
         client_message: &[u8],
         time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
-        prf_eval_point: &[PRFSchemeInput<Conf>],
+        prf_eval_point: &PrfEvalPointSet<Conf>,
         zkp_rng: &mut ZKPRng<Conf>,
         skip_proof: bool,
     ) -> Result<(bool, u64), Error>
@@ -114,11 +136,217 @@ impl<
         ServerSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
         Proof<Conf>: CanonicalDeserialize,
     {
-        // deserialize client message
-        let client_message =
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result =
             VerifiableRandomizationMessageShuffle::<Conf, INPUT_BYTES>::deserialize_compressed(
                 client_message,
-            )?;
+            )
+            .map_err(Error::from)
+            .and_then(|client_message| {
+                self.verify_submission::<ConfG>(
+                    &client_message,
+                    time_bounds,
+                    prf_eval_point,
+                    zkp_rng,
+                    skip_proof,
+                )
+            });
+        #[cfg(feature = "metrics")]
+        self.metrics.observe(&result, start.elapsed());
+        result
+    }
+
+    /// Perform the `Verify` step like `verifiable_randomization_verify`, but for a client message
+    /// that has already been deserialized, such that it does not have to be parsed again.
+    pub fn verifiable_randomization_verify_message<ConfG: ConfigGadget<Conf>>(
+        &self,
+        client_message: &VerifiableRandomizationMessageShuffle<Conf, INPUT_BYTES>,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        prf_eval_point: &PrfEvalPointSet<Conf>,
+        zkp_rng: &mut ZKPRng<Conf>,
+        skip_proof: bool,
+    ) -> Result<(bool, u64), Error>
+    where
+        ServerSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        Proof<Conf>: CanonicalDeserialize,
+    {
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result = self.verify_submission::<ConfG>(
+            client_message,
+            time_bounds,
+            prf_eval_point,
+            zkp_rng,
+            skip_proof,
+        );
+        #[cfg(feature = "metrics")]
+        self.metrics.observe(&result, start.elapsed());
+        result
+    }
+
+    /// Perform the `Verify` step like `verifiable_randomization_verify` for the messages of many
+    /// clients at once, checking all proofs with a single batch verification (see `verify_batch`).
+    /// The outcomes are returned in the order of the messages. A message that cannot be
+    /// deserialized, or that was created for another `GAMMA_BYTES`, is rejected with
+    /// `(false, u64::MAX)` instead of failing the whole batch.
+    pub fn verifiable_randomization_verify_batch<ConfG: ConfigGadget<Conf>>(
+        &self,
+        client_messages: &[&[u8]],
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        prf_eval_point: &PrfEvalPointSet<Conf>,
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<Vec<(bool, u64)>, Error>
+    where
+        ServerSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        Proof<Conf>: CanonicalDeserialize,
+    {
+        self.verify_batch_submissions::<ConfG>(
+            client_messages,
+            time_bounds,
+            &vec![prf_eval_point; client_messages.len()],
+            zkp_rng,
+        )
+    }
+
+    /// Perform the `Verify` step for several readings of a single client that share one
+    /// `Generate Randomness` step (see `ClientShuffle::verifiable_randomization_create_multi`),
+    /// where the message of reading `i` is verified for `prf_eval_points_per_reading[i]`. All
+    /// proofs are checked with a single batch verification, and malformed messages are rejected
+    /// as in `verifiable_randomization_verify_batch`.
+    pub fn verifiable_randomization_verify_multi<ConfG: ConfigGadget<Conf>>(
+        &self,
+        client_messages: &[&[u8]],
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        prf_eval_points_per_reading: &[&PrfEvalPointSet<Conf>],
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<Vec<(bool, u64)>, Error>
+    where
+        ServerSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        Proof<Conf>: CanonicalDeserialize,
+    {
+        if prf_eval_points_per_reading.len() != client_messages.len() {
+            Err(ServerError::ParameterMismatch {
+                parameter: "number of readings",
+                server: prf_eval_points_per_reading.len(),
+                client: client_messages.len(),
+            })?
+        }
+        self.verify_batch_submissions::<ConfG>(
+            client_messages,
+            time_bounds,
+            prf_eval_points_per_reading,
+            zkp_rng,
+        )
+    }
+
+    fn verify_batch_submissions<ConfG: ConfigGadget<Conf>>(
+        &self,
+        client_messages: &[&[u8]],
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        prf_eval_points: &[&PrfEvalPointSet<Conf>],
+        zkp_rng: &mut ZKPRng<Conf>,
+    ) -> Result<Vec<(bool, u64)>, Error>
+    where
+        ServerSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        Proof<Conf>: CanonicalDeserialize,
+    {
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        for prf_eval_points in prf_eval_points {
+            check_prf_eval_points::<Conf, RANDOMNESS_BYTES>(&prf_eval_points.points)?;
+        }
+
+        // deserialize all messages first
+        let client_messages = client_messages
+            .iter()
+            .map(|client_message| {
+                VerifiableRandomizationMessageShuffle::<Conf, INPUT_BYTES>::deserialize_compressed(
+                    *client_message,
+                )
+                .ok()
+                .filter(|client_message| {
+                    check_gamma_bytes::<GAMMA_BYTES>(client_message.gamma_bytes).is_ok()
+                })
+            })
+            .collect::<Vec<_>>();
+        let public_inputs = client_messages
+            .iter()
+            .zip(prf_eval_points)
+            .filter_map(|(client_message, prf_eval_points)| {
+                Some((client_message.as_ref()?, *prf_eval_points))
+            })
+            .map(|(client_message, prf_eval_points)| {
+                CircuitShuffle::<
+                    _,
+                    ConfG,
+                    INPUT_BYTES,
+                    TIME_BYTES,
+                    GAMMA_BYTES,
+                    RANDOMNESS_BYTES,
+                    K,
+                    IS_REAL_INPUT,
+                >::public_inputs(
+                    client_message.ldp_value,
+                    time_bounds,
+                    &self.sig_pk,
+                    prf_eval_points,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // then verify the proofs of all well-formed messages as one batch
+        let instances = client_messages
+            .iter()
+            .flatten()
+            .zip(public_inputs.iter())
+            .map(|(client_message, public_inputs)| {
+                (public_inputs.as_slice(), &client_message.proof)
+            })
+            .collect::<Vec<_>>();
+        let verification = verify_batch::<Conf>(
+            &self.verifying_key,
+            &instances,
+            BatchFailurePolicy::IdentifyFailures,
+            zkp_rng,
+        )?;
+
+        // positions in the batch only count the well-formed messages
+        let mut position = 0;
+        let outcomes = client_messages
+            .iter()
+            .map(|client_message| match client_message {
+                Some(client_message) => {
+                    let accepted = !verification.rejected.contains(&position);
+                    position += 1;
+                    (accepted, client_message.ldp_value)
+                }
+                None => (false, u64::MAX),
+            })
+            .collect::<Vec<_>>();
+        #[cfg(feature = "metrics")]
+        for outcome in outcomes.iter() {
+            self.metrics
+                .observe(&Ok(*outcome), start.elapsed() / outcomes.len() as u32);
+        }
+        Ok(outcomes)
+    }
+
+    fn verify_submission<ConfG: ConfigGadget<Conf>>(
+        &self,
+        client_message: &VerifiableRandomizationMessageShuffle<Conf, INPUT_BYTES>,
+        time_bounds: ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        prf_eval_point: &PrfEvalPointSet<Conf>,
+        zkp_rng: &mut ZKPRng<Conf>,
+        skip_proof: bool,
+    ) -> Result<(bool, u64), Error>
+    where
+        ServerSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+        Proof<Conf>: CanonicalDeserialize,
+    {
+        // reject messages of clients that use a different gamma encoding
+        check_gamma_bytes::<GAMMA_BYTES>(client_message.gamma_bytes)?;
+        check_prf_eval_points::<Conf, RANDOMNESS_BYTES>(&prf_eval_point.points)?;
 
         if skip_proof {
             Ok((true, client_message.ldp_value))
@@ -145,3 +373,445 @@ impl<
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::ServerShuffle;
+    use crate::circuits::CircuitShuffle;
+    use crate::client::ClientShuffle;
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::messages::shuffle::{PrfEvalPointSet, VerifiableRandomizationMessageShuffle};
+    use crate::messages::{derive_prf_eval_points, InputValueSignatureInput};
+    use crate::prelude::*;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+
+    #[test]
+    fn verifying_message_agrees_with_verifying_bytes() {
+        let rng = &mut ChaChaRng::from_seed([27; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([28; 32]);
+        let parameters = ParametersShuffle::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitShuffle::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(
+                parameters.clone(),
+                zkp_rng,
+            )
+            .unwrap();
+        let server = ServerShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            verifying_key,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let prf_eval_points = PrfEvalPointSet::<Conf>::derive(0, [3; 32], 1).unwrap();
+        let time_bounds = ([0], [1]);
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                time_bounds,
+                [1],
+                BigUint::from(3_u8),
+                input_value_signature,
+                &prf_eval_points,
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+
+        // the original message, one with a different LDP value, and one with a different gamma
+        // encoding
+        let message = |tamper: fn(&mut VerifiableRandomizationMessageShuffle<Conf, 2>)| {
+            let mut message =
+                VerifiableRandomizationMessageShuffle::<Conf, 2>::deserialize_compressed(
+                    submission.as_slice(),
+                )
+                .unwrap();
+            tamper(&mut message);
+            message
+        };
+        let messages = [
+            message(|_| {}),
+            message(|message| message.ldp_value = message.ldp_value % 4 + 1),
+            message(|message| message.gamma_bytes += 1),
+        ];
+        let mut outcomes = vec![];
+        for message in messages.iter() {
+            let mut bytes = vec![];
+            message.serialize_compressed(&mut bytes).unwrap();
+            let from_bytes = server
+                .verifiable_randomization_verify::<ConfG>(
+                    &bytes,
+                    time_bounds,
+                    &prf_eval_points,
+                    zkp_rng,
+                    false,
+                )
+                .ok();
+            let from_message = server
+                .verifiable_randomization_verify_message::<ConfG>(
+                    message,
+                    time_bounds,
+                    &prf_eval_points,
+                    zkp_rng,
+                    false,
+                )
+                .ok();
+            assert_eq!(from_bytes, from_message);
+            outcomes.push(from_message.map(|(valid, _)| valid));
+        }
+        assert_eq!(outcomes, vec![Some(true), Some(false), None]);
+    }
+
+    #[test]
+    fn batch_verification_preserves_order_and_rejects_malformed_messages() {
+        let rng = &mut ChaChaRng::from_seed([66; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([67; 32]);
+        let parameters = ParametersShuffle::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitShuffle::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(
+                parameters.clone(),
+                zkp_rng,
+            )
+            .unwrap();
+        let server = ServerShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            verifying_key,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let prf_eval_points = PrfEvalPointSet::<Conf>::derive(0, [5; 32], 1).unwrap();
+        let time_bounds = ([0], [1]);
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                time_bounds,
+                [1],
+                BigUint::from(3_u8),
+                input_value_signature,
+                &prf_eval_points,
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+        let mut tampered =
+            VerifiableRandomizationMessageShuffle::<Conf, 2>::deserialize_compressed(
+                submission.as_slice(),
+            )
+            .unwrap();
+        let ldp_value = tampered.ldp_value;
+        tampered.ldp_value = ldp_value % 4 + 1;
+        let mut tampered_bytes = vec![];
+        tampered.serialize_compressed(&mut tampered_bytes).unwrap();
+
+        // the invalid proof and the malformed message are rejected without failing the batch
+        let outcomes = server
+            .verifiable_randomization_verify_batch::<ConfG>(
+                &[
+                    submission.as_slice(),
+                    tampered_bytes.as_slice(),
+                    &[0_u8; 3][..],
+                    submission.as_slice(),
+                ],
+                time_bounds,
+                &prf_eval_points,
+                zkp_rng,
+            )
+            .unwrap();
+        assert_eq!(
+            outcomes,
+            vec![
+                (true, ldp_value),
+                (false, ldp_value % 4 + 1),
+                (false, u64::MAX),
+                (true, ldp_value)
+            ]
+        );
+        assert!(server
+            .verifiable_randomization_verify_batch::<ConfG>(
+                &[],
+                time_bounds,
+                &prf_eval_points,
+                zkp_rng
+            )
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn readings_share_one_generate_randomness_step() {
+        let rng = &mut ChaChaRng::from_seed([92; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([93; 32]);
+        let parameters = ParametersShuffle::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitShuffle::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(
+                parameters.clone(),
+                zkp_rng,
+            )
+            .unwrap();
+        let server = ServerShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            verifying_key,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+
+        // a single handshake for all readings
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+
+        // RANDOMNESS_BYTES = 4, so every reading needs a single eval point
+        let prf_eval_points = derive_prf_eval_points::<Conf>([7; 32], 3).unwrap();
+        let prf_eval_point_sets = prf_eval_points
+            .chunks(1)
+            .map(|points| PrfEvalPointSet::new(0, points.to_vec()))
+            .collect::<Vec<_>>();
+        let prf_eval_points_per_reading = prf_eval_point_sets.iter().collect::<Vec<_>>();
+        let time_bounds = ([0], [1]);
+        let readings = [1_u8, 2, 3]
+            .into_iter()
+            .map(|input_value| {
+                let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+                    &parameters.client_signature_scheme,
+                    &client_sig_sk,
+                    &InputValueSignatureInput {
+                        input_value: [input_value, 0],
+                        input_value_time: [1],
+                    }
+                    .to_bytes(),
+                    rng,
+                )
+                .unwrap();
+                ([1_u8], BigUint::from(input_value), input_value_signature)
+            })
+            .collect::<Vec<_>>();
+        let submissions = client
+            .verifiable_randomization_create_multi::<ConfG>(
+                time_bounds,
+                &readings,
+                &prf_eval_points_per_reading,
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+        let submissions = submissions.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+        let outcomes = server
+            .verifiable_randomization_verify_multi::<ConfG>(
+                &submissions,
+                time_bounds,
+                &prf_eval_points_per_reading,
+                zkp_rng,
+            )
+            .unwrap();
+        assert!(outcomes.iter().all(|(accepted, _)| *accepted));
+
+        // every proof only holds for the eval points of its own reading
+        let swapped = [
+            prf_eval_points_per_reading[1],
+            prf_eval_points_per_reading[0],
+            prf_eval_points_per_reading[2],
+        ];
+        let outcomes = server
+            .verifiable_randomization_verify_multi::<ConfG>(
+                &submissions,
+                time_bounds,
+                &swapped,
+                zkp_rng,
+            )
+            .unwrap();
+        assert_eq!(
+            outcomes
+                .iter()
+                .map(|(accepted, _)| *accepted)
+                .collect::<Vec<_>>(),
+            vec![false, false, true]
+        );
+
+        // readings must not share their randomness
+        let error = client
+            .verifiable_randomization_create_multi::<ConfG>(
+                time_bounds,
+                &readings[0..2],
+                &[
+                    prf_eval_points_per_reading[0],
+                    prf_eval_points_per_reading[0],
+                ],
+                zkp_rng,
+                true,
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Client(ClientError::ReusedPrfEvalPoints)
+        ));
+    }
+
+    #[test]
+    fn proofs_are_bound_to_the_epoch_of_their_eval_points() {
+        let rng = &mut ChaChaRng::from_seed([123; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([124; 32]);
+        let parameters = ParametersShuffle::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitShuffle::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(
+                parameters.clone(),
+                zkp_rng,
+            )
+            .unwrap();
+        let server = ServerShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            verifying_key,
+            rng,
+        )
+        .unwrap();
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server.get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+
+        let client_message = client.generate_randomness_create(rng).unwrap();
+        let server_message = server
+            .generate_randomness_create(&client_message, rng)
+            .unwrap();
+        client.generate_randomness_verify(&server_message).unwrap();
+        let old_epoch = PrfEvalPointSet::<Conf>::derive(1, [8; 32], 1).unwrap();
+        let new_epoch = PrfEvalPointSet::<Conf>::derive(2, [9; 32], 1).unwrap();
+        let time_bounds = ([0], [1]);
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+        let submission = client
+            .verifiable_randomization_create::<ConfG>(
+                time_bounds,
+                [1],
+                BigUint::from(3_u8),
+                input_value_signature,
+                &old_epoch,
+                zkp_rng,
+                false,
+            )
+            .unwrap();
+        let verify = |prf_eval_points: &PrfEvalPointSet<Conf>, zkp_rng: &mut ChaChaRng| {
+            server
+                .verifiable_randomization_verify::<ConfG>(
+                    &submission,
+                    time_bounds,
+                    prf_eval_points,
+                    zkp_rng,
+                    false,
+                )
+                .unwrap()
+                .0
+        };
+
+        // the proof of the old epoch stays valid after the points were rotated
+        assert!(verify(&old_epoch, zkp_rng));
+        // but it neither verifies for the points of the new epoch
+        assert!(!verify(&new_epoch, zkp_rng));
+        // nor for its points under the new epoch id, or for its epoch id with the new points
+        assert!(!verify(
+            &PrfEvalPointSet::new(new_epoch.epoch, old_epoch.points.clone()),
+            zkp_rng
+        ));
+        assert!(!verify(
+            &PrfEvalPointSet::new(old_epoch.epoch, new_epoch.points.clone()),
+            zkp_rng
+        ));
+    }
+}
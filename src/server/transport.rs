@@ -0,0 +1,289 @@
+//! Async adapter of the Shuffle server for networked deployments (requires the `async` feature).
+//!
+//! The synchronous server remains the source of truth: `AsyncServerShuffle` only forwards the
+//! serialized messages to the methods of `ServerShuffle`, and moves the CPU-heavy proof
+//! verification off the async executor.
+
+use crate::messages::shuffle::PrfEvalPointSet;
+use crate::prelude::*;
+use crate::server::ServerShuffle;
+use ark_serialize::CanonicalDeserialize;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Server of a VLDP scheme that handles serialized client messages asynchronously, e.g., in the
+/// request handlers of a network service. The caller remains responsible for the I/O.
+pub trait AsyncVldpServer {
+    /// Public values besides the client message that the `Verify` step depends on.
+    type VerifyContext;
+
+    /// Perform the `Generate Randomness` step for a client message, and return the serialized
+    /// server message.
+    fn handle_generate_randomness(
+        &self,
+        msg: &[u8],
+    ) -> impl Future<Output = Result<Vec<u8>, Error>> + Send;
+
+    /// Perform the `Verify` step (including proof verification) for a client message, and return
+    /// whether it is accepted along with its LDP value.
+    fn handle_verify(
+        &self,
+        msg: &[u8],
+        context: Self::VerifyContext,
+    ) -> impl Future<Output = Result<(bool, u64), Error>> + Send;
+}
+
+/// `AsyncVldpServer` that wraps a `ServerShuffle`.
+///
+/// Proofs are verified with `tokio::task::block_in_place` rather than on a separate blocking task,
+/// as `Error` is not `Send` (the errors of the arkworks primitives are not) and the server is only
+/// borrowed. Hence, the adapter must be used within the multi-threaded Tokio runtime.
+pub struct AsyncServerShuffle<
+    Conf: Config,
+    ConfG: ConfigGadget<Conf>,
+    const INPUT_BYTES: usize,
+    const TIME_BYTES: usize,
+    const GAMMA_BYTES: usize,
+    const RANDOMNESS_BYTES: usize,
+    const K: u64,
+    const IS_REAL_INPUT: bool,
+> {
+    server: ServerShuffle<
+        Conf,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >,
+    // the gadgets are only needed for their types, so they need not be `Sync`
+    _config_gadget: PhantomData<fn() -> ConfG>,
+}
+
+impl<
+        Conf: Config,
+        ConfG: ConfigGadget<Conf>,
+        const INPUT_BYTES: usize,
+        const TIME_BYTES: usize,
+        const GAMMA_BYTES: usize,
+        const RANDOMNESS_BYTES: usize,
+        const K: u64,
+        const IS_REAL_INPUT: bool,
+    >
+    AsyncServerShuffle<
+        Conf,
+        ConfG,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >
+{
+    /// Handle the messages of `server` asynchronously.
+    pub fn new(
+        server: ServerShuffle<
+            Conf,
+            INPUT_BYTES,
+            TIME_BYTES,
+            GAMMA_BYTES,
+            RANDOMNESS_BYTES,
+            K,
+            IS_REAL_INPUT,
+        >,
+    ) -> Self {
+        Self {
+            server,
+            _config_gadget: PhantomData,
+        }
+    }
+
+    /// Get the wrapped server, e.g., for its signature public key.
+    pub fn server(
+        &self,
+    ) -> &ServerShuffle<
+        Conf,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    > {
+        &self.server
+    }
+}
+
+impl<
+        Conf: Config,
+        ConfG: ConfigGadget<Conf>,
+        const INPUT_BYTES: usize,
+        const TIME_BYTES: usize,
+        const GAMMA_BYTES: usize,
+        const RANDOMNESS_BYTES: usize,
+        const K: u64,
+        const IS_REAL_INPUT: bool,
+    > AsyncVldpServer
+    for AsyncServerShuffle<
+        Conf,
+        ConfG,
+        INPUT_BYTES,
+        TIME_BYTES,
+        GAMMA_BYTES,
+        RANDOMNESS_BYTES,
+        K,
+        IS_REAL_INPUT,
+    >
+where
+    ServerShuffle<Conf, INPUT_BYTES, TIME_BYTES, GAMMA_BYTES, RANDOMNESS_BYTES, K, IS_REAL_INPUT>:
+        Sync,
+    ClientCommitmentSchemeOutput<Conf>: CanonicalDeserialize,
+    ClientSignatureSchemePublicKey<Conf>: CanonicalDeserialize,
+    ServerSignatureSchemeSignature<Conf>: CanonicalDeserialize,
+    ServerSignatureSchemePublicKey<Conf>: ToConstraintField<ConstraintField<Conf>>,
+    Proof<Conf>: CanonicalDeserialize,
+{
+    /// Time (step) bounds and the eval points of the epoch the message was created for, which are
+    /// typically shared by all requests of an epoch.
+    type VerifyContext = (
+        ([u8; TIME_BYTES], [u8; TIME_BYTES]),
+        Arc<PrfEvalPointSet<Conf>>,
+    );
+
+    async fn handle_generate_randomness(&self, msg: &[u8]) -> Result<Vec<u8>, Error> {
+        // only a signature, which is cheap enough to be created on the executor
+        self.server
+            .generate_randomness_create(msg, &mut rand::thread_rng())
+    }
+
+    async fn handle_verify(
+        &self,
+        msg: &[u8],
+        (time_bounds, prf_eval_points): Self::VerifyContext,
+    ) -> Result<(bool, u64), Error> {
+        tokio::task::block_in_place(|| {
+            self.server.verifiable_randomization_verify::<ConfG>(
+                msg,
+                time_bounds,
+                &prf_eval_points,
+                &mut ZKPRng::<Conf>::new(),
+                false,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AsyncServerShuffle, AsyncVldpServer};
+    use crate::circuits::CircuitShuffle;
+    use crate::client::ClientShuffle;
+    use crate::config::{BasicConfig, BasicConfigGadget};
+    use crate::messages::shuffle::PrfEvalPointSet;
+    use crate::messages::InputValueSignatureInput;
+    use crate::prelude::*;
+    use crate::server::ServerShuffle;
+    use ark_bls12_381::Bls12_381;
+    use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+    use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+    use ark_groth16::Groth16;
+    use astro_float::BigFloat;
+    use num_bigint::BigUint;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+    use std::sync::Arc;
+
+    type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+    type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+
+    #[test]
+    fn async_server_agrees_with_sync_server() {
+        let rng = &mut ChaChaRng::from_seed([125; 32]);
+        let zkp_rng = &mut ChaChaRng::from_seed([126; 32]);
+        let parameters = ParametersShuffle::setup(BigFloat::from_f64(0.5, 64), rng).unwrap();
+        let (proving_key, verifying_key) =
+            CircuitShuffle::<Conf, ConfG, 2, 1, 2, 4, 4, false>::keygen(
+                parameters.clone(),
+                zkp_rng,
+            )
+            .unwrap();
+        let server = AsyncServerShuffle::<Conf, ConfG, 2, 1, 2, 4, 4, false>::new(
+            ServerShuffle::new(parameters.clone(), verifying_key, rng).unwrap(),
+        );
+        let (client_sig_pk, client_sig_sk) = <Conf as Config>::ClientSignatureScheme::keygen(
+            &parameters.client_signature_scheme,
+            rng,
+        )
+        .unwrap();
+        let mut client = ClientShuffle::<Conf, 2, 1, 2, 4, 4, false>::new(
+            parameters.clone(),
+            server.server().get_signature_public_key(),
+            client_sig_pk,
+            proving_key,
+        )
+        .unwrap();
+        let prf_eval_points = Arc::new(PrfEvalPointSet::<Conf>::derive(0, [3; 32], 1).unwrap());
+        let time_bounds = ([0], [1]);
+        let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+            &parameters.client_signature_scheme,
+            &client_sig_sk,
+            &InputValueSignatureInput {
+                input_value: [3, 0],
+                input_value_time: [1],
+            }
+            .to_bytes(),
+            rng,
+        )
+        .unwrap();
+
+        let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+        runtime.block_on(async {
+            let client_message = client.generate_randomness_create(rng).unwrap();
+            let server_message = server
+                .handle_generate_randomness(&client_message)
+                .await
+                .unwrap();
+            client.generate_randomness_verify(&server_message).unwrap();
+            let submission = client
+                .verifiable_randomization_create::<ConfG>(
+                    time_bounds,
+                    [1],
+                    BigUint::from(3_u8),
+                    input_value_signature,
+                    &prf_eval_points,
+                    zkp_rng,
+                    false,
+                )
+                .unwrap();
+
+            let outcome = server
+                .handle_verify(&submission, (time_bounds, prf_eval_points.clone()))
+                .await
+                .unwrap();
+            assert_eq!(
+                outcome,
+                server
+                    .server()
+                    .verifiable_randomization_verify::<ConfG>(
+                        &submission,
+                        time_bounds,
+                        &prf_eval_points,
+                        zkp_rng,
+                        false,
+                    )
+                    .unwrap()
+            );
+            assert!(outcome.0);
+
+            // malformed messages are reported as by the synchronous server
+            assert!(server
+                .handle_verify(&submission[1..], (time_bounds, prf_eval_points.clone()))
+                .await
+                .is_err());
+            assert!(server.handle_generate_randomness(&[0; 3]).await.is_err());
+        });
+    }
+}
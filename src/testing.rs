@@ -0,0 +1,64 @@
+//! Fixtures shared by the tests and benchmarks of this crate (available with the `test-utils`
+//! feature).
+
+use crate::client::ClientBase;
+use crate::config::{BasicConfig, BasicConfigGadget};
+use crate::messages::InputValueSignatureInput;
+use crate::prelude::*;
+use crate::server::ServerBase;
+use ark_bls12_381::Bls12_381;
+use ark_ed_on_bls12_381::constraints::EdwardsVar as JubJubVar;
+use ark_ed_on_bls12_381::EdwardsProjective as JubJub;
+use ark_groth16::Groth16;
+use num_bigint::BigUint;
+use rand_chacha::ChaChaRng;
+
+/// Configuration over JubJub and BLS12-381 with Groth16 and 32 bytes of client randomness.
+pub type Conf = BasicConfig<JubJub, ChaChaRng, Groth16<Bls12_381>, 32>;
+pub type ConfG = BasicConfigGadget<JubJub, JubJubVar>;
+
+/// Base client with 2 input bytes, 1 time byte, 2 gamma bytes, 4 randomness bytes and `K = 4`.
+pub type SmallClientBase = ClientBase<Conf, 2, 1, 2, 4, 4, false>;
+/// Base server matching `SmallClientBase`.
+pub type SmallServerBase = ServerBase<Conf, 2, 1, 2, 4, 4, false>;
+
+/// Run the protocol for a single submission of the input value 3 at time 1 by the given client
+/// and return whether the server accepted it.
+pub fn submit_base(
+    client: &mut SmallClientBase,
+    client_sig_sk: &ClientSignatureSchemeSecretKey<Conf>,
+    server: &SmallServerBase,
+    parameters: &ParametersBase<Conf, 2>,
+    rng: &mut ChaChaRng,
+) -> bool {
+    let client_message = client.generate_randomness_create([1], rng).unwrap();
+    let server_message = server
+        .generate_randomness_create(&client_message, rng)
+        .unwrap();
+    client.generate_randomness_verify(&server_message).unwrap();
+    let input_value_signature = <Conf as Config>::ClientSignatureScheme::sign(
+        &parameters.client_signature_scheme,
+        client_sig_sk,
+        &InputValueSignatureInput {
+            input_value: [3, 0],
+            input_value_time: [1],
+        }
+        .to_bytes(),
+        rng,
+    )
+    .unwrap();
+    let submission = client
+        .verifiable_randomization_create::<ConfG>(
+            ([0], [1]),
+            [1],
+            [BigUint::from(3_u8)],
+            input_value_signature,
+            rng,
+            false,
+        )
+        .unwrap();
+    server
+        .verifiable_randomization_verify::<ConfG>(&submission, ([0], [1]), rng, false)
+        .unwrap()
+        .0
+}
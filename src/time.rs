@@ -0,0 +1,99 @@
+//! Helpers for constructing the `time_bounds` that clients prove their input time to lie in
+
+use crate::client::encode_time;
+use crate::prelude::*;
+use num_bigint::BigUint;
+
+/// Time window `[value, value + 1]` of a monotonic counter (e.g., the day of the measurement),
+/// encoded as `TIME_BYTES` little-endian bytes each, as expected for the `time_bounds` of the
+/// `Randomize` step.
+pub struct TimeWindow;
+
+impl TimeWindow {
+    /// Encode `(value, value + 1)` as `time_bounds`. Fails if `value + 1` cannot be represented
+    /// with `TIME_BYTES` bytes.
+    pub fn from_counter<const TIME_BYTES: usize>(
+        value: u64,
+    ) -> Result<([u8; TIME_BYTES], [u8; TIME_BYTES]), Error> {
+        let overflow = || GenericError::TimeOverflow {
+            value,
+            time_bytes: TIME_BYTES,
+        };
+        let upper_bound = value.checked_add(1).ok_or_else(overflow)?;
+        Ok((
+            encode_time(value).map_err(|_| overflow())?,
+            encode_time(upper_bound).map_err(|_| overflow())?,
+        ))
+    }
+}
+
+/// Check that `time` lies in the window given by `time_bounds`, i.e., `time_bounds.0 < time <=
+/// time_bounds.1` (as little-endian integers), as the circuits of all schemes enforce. Otherwise,
+/// fails with `ClientError::TimeOutOfBounds`, before an unverifiable proof is created.
+pub(crate) fn check_time_in_bounds<const TIME_BYTES: usize>(
+    time: &[u8; TIME_BYTES],
+    time_bounds: &([u8; TIME_BYTES], [u8; TIME_BYTES]),
+) -> Result<(), Error> {
+    let time = BigUint::from_bytes_le(time);
+    let lower_bound = BigUint::from_bytes_le(&time_bounds.0);
+    let upper_bound = BigUint::from_bytes_le(&time_bounds.1);
+    if lower_bound < time && time <= upper_bound {
+        Ok(())
+    } else {
+        Err(ClientError::TimeOutOfBounds {
+            time,
+            lower_bound,
+            upper_bound,
+        })?
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TimeWindow;
+    use crate::prelude::*;
+
+    #[test]
+    fn mid_range_counter() {
+        assert_eq!(TimeWindow::from_counter::<1>(42).unwrap(), ([42], [43]));
+        assert_eq!(
+            TimeWindow::from_counter::<2>(255).unwrap(),
+            ([255, 0], [0, 1])
+        );
+    }
+
+    #[test]
+    fn max_counter_is_rejected() {
+        assert_eq!(TimeWindow::from_counter::<1>(254).unwrap(), ([254], [255]));
+        let error = TimeWindow::from_counter::<1>(255).unwrap_err();
+        assert!(matches!(
+            error,
+            VldpError::Generic(GenericError::TimeOverflow {
+                value: 255,
+                time_bytes: 1,
+            })
+        ));
+        assert!(TimeWindow::from_counter::<8>(u64::MAX).is_err());
+    }
+
+    #[test]
+    fn multi_byte_time() {
+        assert_eq!(
+            TimeWindow::from_counter::<4>(0x0001_ffff).unwrap(),
+            ([0xff, 0xff, 0x01, 0x00], [0x00, 0x00, 0x02, 0x00])
+        );
+        assert_eq!(
+            TimeWindow::from_counter::<4>(1000).unwrap(),
+            ([0xe8, 0x03, 0, 0], [0xe9, 0x03, 0, 0])
+        );
+        assert!(TimeWindow::from_counter::<4>(0xffff_ffff).is_err());
+        // more bytes than a counter can fill are zero-padded
+        assert_eq!(
+            TimeWindow::from_counter::<10>(u64::MAX - 1).unwrap(),
+            (
+                [0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0, 0],
+                [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0, 0]
+            )
+        );
+    }
+}